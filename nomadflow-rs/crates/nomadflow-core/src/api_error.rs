@@ -0,0 +1,223 @@
+//! Errors of the HTTP API. Each carries a stable `code` that clients branch
+//! on, and a `detail` meant for people, which may change:
+//!
+//! ```json
+//! { "code": "feature_not_found", "detail": "Feature 'login' not found" }
+//! ```
+//!
+//! Some add fields of their own, such as the `suggestions` of an invalid
+//! branch name.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::NomadError;
+
+/// What went wrong, for clients to act on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// A field of the request is missing or invalid.
+    InvalidRequest,
+    InvalidBranchName,
+    /// The main worktree of a repo cannot be deleted.
+    MainWorktree,
+    NoUpstream,
+    NothingToCommit,
+    /// git could not create or attach a feature's worktree.
+    WorktreeFailed,
+    /// No forge token, or no forge, for the repo.
+    ForgeNotConfigured,
+    OtpRequired,
+    InvalidOtp,
+    /// No credential, or a wrong one.
+    Unauthorized,
+    Forbidden,
+    /// The client failed to authenticate too often.
+    Banned,
+    RepoNotFound,
+    FeatureNotFound,
+    NotFound,
+    AlreadyExists,
+    /// The branch is checked out or created already.
+    BranchExists,
+    BranchProtected,
+    BranchDiverged,
+    /// Uncommitted changes are in the way.
+    WorktreeDirty,
+    RepoHasWorktrees,
+    /// Something else still uses what the request would change.
+    InUse,
+    /// A program runs in the feature's window.
+    WindowBusy,
+    /// The same operation already runs on the target.
+    JobRunning,
+    PushRejected,
+    TooLarge,
+    /// The repo's `.nomadflow.toml` cannot be parsed.
+    InvalidRepoConfig,
+    CommitRulesBroken,
+    HookFailed,
+    TooManyRequests,
+    Internal,
+    /// A git or shell command failed.
+    CommandFailed,
+    TmuxUnavailable,
+    /// ttyd, which serves share links, is not installed.
+    TtydUnavailable,
+    /// The forge refused or failed a request.
+    ForgeFailed,
+    Timeout,
+}
+
+impl ErrorCode {
+    /// HTTP status of the errors with this code, unless told otherwise.
+    pub fn status(self) -> u16 {
+        match self {
+            Self::InvalidRequest
+            | Self::InvalidBranchName
+            | Self::MainWorktree
+            | Self::NoUpstream
+            | Self::NothingToCommit
+            | Self::WorktreeFailed
+            | Self::ForgeNotConfigured => 400,
+            Self::OtpRequired | Self::InvalidOtp | Self::Unauthorized => 401,
+            Self::Forbidden | Self::Banned => 403,
+            Self::RepoNotFound | Self::FeatureNotFound | Self::NotFound => 404,
+            Self::AlreadyExists
+            | Self::BranchExists
+            | Self::BranchProtected
+            | Self::BranchDiverged
+            | Self::WorktreeDirty
+            | Self::RepoHasWorktrees
+            | Self::InUse
+            | Self::WindowBusy
+            | Self::JobRunning
+            | Self::PushRejected => 409,
+            Self::TooLarge => 413,
+            Self::InvalidRepoConfig | Self::CommitRulesBroken | Self::HookFailed => 422,
+            Self::TooManyRequests => 429,
+            Self::Internal
+            | Self::CommandFailed
+            | Self::TmuxUnavailable
+            | Self::TtydUnavailable => 500,
+            Self::ForgeFailed => 502,
+            Self::Timeout => 504,
+        }
+    }
+}
+
+/// An error answered by the API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub status: u16,
+    pub code: ErrorCode,
+    pub detail: String,
+    /// More fields of the body.
+    pub extra: Map<String, Value>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, detail: impl Into<String>) -> Self {
+        Self {
+            status: code.status(),
+            code,
+            detail: detail.into(),
+            extra: Map::new(),
+        }
+    }
+
+    pub fn internal(detail: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Internal, detail.to_string())
+    }
+
+    /// The same error, answered with `status` instead of its code's.
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// The same error, with `key` added to its body.
+    pub fn with(mut self, key: &str, value: impl Serialize) -> Self {
+        self.extra.insert(
+            key.to_string(),
+            serde_json::to_value(value).unwrap_or(Value::Null),
+        );
+        self
+    }
+
+    /// The JSON body of the response.
+    pub fn body(&self) -> Value {
+        let mut body = self.extra.clone();
+        body.insert("code".to_string(), serde_json::to_value(self.code).unwrap());
+        body.insert("detail".to_string(), Value::String(self.detail.clone()));
+        Value::Object(body)
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.detail)
+    }
+}
+
+impl From<NomadError> for ApiError {
+    fn from(e: NomadError) -> Self {
+        let code = match &e {
+            NomadError::AlreadyExists(_) => ErrorCode::AlreadyExists,
+            NomadError::NotFound(_) => ErrorCode::NotFound,
+            NomadError::InUse(_) => ErrorCode::InUse,
+            NomadError::CommandFailed(_) => ErrorCode::CommandFailed,
+            NomadError::Timeout(_) => ErrorCode::Timeout,
+            NomadError::InvalidName(_) => ErrorCode::InvalidBranchName,
+            NomadError::Config(_) => ErrorCode::InvalidRepoConfig,
+            NomadError::Secrets(_) | NomadError::Io(_) | NomadError::Other(_) => {
+                ErrorCode::Internal
+            }
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+#[cfg(feature = "axum")]
+impl axum_core::response::IntoResponse for ApiError {
+    fn into_response(self) -> axum_core::response::Response {
+        let status = http::StatusCode::from_u16(self.status)
+            .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+        let body = self.body().to_string();
+        (
+            status,
+            [(http::header::CONTENT_TYPE, "application/json")],
+            body,
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body() {
+        let error = ApiError::new(ErrorCode::InvalidBranchName, "Invalid branch name 'a b'")
+            .with("suggestions", ["a-b"]);
+        assert_eq!(error.status, 400);
+        assert_eq!(
+            error.body(),
+            serde_json::json!({
+                "code": "invalid_branch_name",
+                "detail": "Invalid branch name 'a b'",
+                "suggestions": ["a-b"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_nomad_error() {
+        let error = ApiError::from(NomadError::NotFound("Feature 'x'".to_string()));
+        assert_eq!((error.status, error.code), (404, ErrorCode::NotFound));
+        let error = ApiError::from(NomadError::Timeout(5.0));
+        assert_eq!((error.status, error.code), (504, ErrorCode::Timeout));
+    }
+}
@@ -0,0 +1,291 @@
+//! Validation of the branch names given to new features.
+//!
+//! Names follow git's ref rules, restricted to characters that are safe in
+//! shell commands and tmux targets. The last segment also names the worktree
+//! and the tmux window, where a `.` would be read as a pane index.
+
+use std::fmt;
+
+use crate::i18n::{t, tf};
+
+/// Longest accepted branch name, in characters.
+const MAX_LEN: usize = 100;
+/// Prefixes offered for names given without one.
+const PREFIXES: &[&str] = &["feature/", "fix/"];
+/// Words kept from a description to name its branch.
+const DESCRIPTION_WORDS: usize = 6;
+/// Words left out of names derived from descriptions.
+const FILLER_WORDS: &[&str] = &[
+    "a", "an", "the", "to", "of", "for", "and", "in", "on", "at", "with", "from",
+];
+/// First words of a description that put its branch under `fix/`.
+const FIX_WORDS: &[&str] = &["fix", "fixes", "bug", "bugfix", "hotfix", "repair"];
+
+/// Why a branch name is refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameProblem {
+    Empty,
+    TooLong,
+    InvalidChar(char),
+    LeadingDash,
+    DoubleDot,
+    /// Starts or ends with `/`, or has an empty segment.
+    EmptySegment,
+    /// A segment starts with `.` or ends with `.lock`.
+    HiddenSegment,
+    /// The last segment, used as the tmux window name, contains a `.`.
+    DotInWindowName,
+}
+
+impl fmt::Display for NameProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameProblem::Empty => f.write_str(t("branch.empty")),
+            NameProblem::TooLong => f.write_str(&tf("branch.too_long", &[("max", &MAX_LEN)])),
+            NameProblem::InvalidChar(c) => {
+                f.write_str(&tf("branch.invalid_char", &[("char", &format!("{c:?}"))]))
+            }
+            NameProblem::LeadingDash => f.write_str(t("branch.leading_dash")),
+            NameProblem::DoubleDot => f.write_str(t("branch.double_dot")),
+            NameProblem::EmptySegment => f.write_str(t("branch.empty_segment")),
+            NameProblem::HiddenSegment => f.write_str(t("branch.hidden_segment")),
+            NameProblem::DotInWindowName => f.write_str(t("branch.window_dot")),
+        }
+    }
+}
+
+fn is_allowed(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | '+')
+}
+
+/// Check `name` as the branch of a new feature.
+pub fn check_branch_name(name: &str) -> Result<(), NameProblem> {
+    if name.is_empty() {
+        return Err(NameProblem::Empty);
+    }
+    if name.chars().count() > MAX_LEN {
+        return Err(NameProblem::TooLong);
+    }
+    if let Some(c) = name.chars().find(|&c| !is_allowed(c)) {
+        return Err(NameProblem::InvalidChar(c));
+    }
+    if name.starts_with('-') {
+        return Err(NameProblem::LeadingDash);
+    }
+    if name.contains("..") {
+        return Err(NameProblem::DoubleDot);
+    }
+    let segments: Vec<&str> = name.split('/').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(NameProblem::EmptySegment);
+    }
+    if segments
+        .iter()
+        .any(|s| s.starts_with('.') || s.ends_with(".lock"))
+    {
+        return Err(NameProblem::HiddenSegment);
+    }
+    if segments.last().is_some_and(|s| s.contains('.')) {
+        return Err(NameProblem::DotInWindowName);
+    }
+    Ok(())
+}
+
+/// `input` turned into a valid branch name: lowercased, with runs of other
+/// characters replaced by `-`. Empty when nothing usable is left.
+pub fn slugify_branch_name(input: &str) -> String {
+    let segments: Vec<String> = input
+        .trim()
+        .to_lowercase()
+        .split('/')
+        .map(|segment| {
+            let mut slug = String::new();
+            for c in segment.chars() {
+                if c.is_alphanumeric() || c == '_' || c == '+' {
+                    slug.push(c);
+                } else if !slug.is_empty() && !slug.ends_with('-') {
+                    slug.push('-');
+                }
+            }
+            slug.trim_end_matches('-').to_string()
+        })
+        .filter(|s| !s.is_empty())
+        .collect();
+    let slug = segments.join("/");
+    slug.chars()
+        .take(MAX_LEN)
+        .collect::<String>()
+        .trim_end_matches(['-', '/'])
+        .to_string()
+}
+
+/// Valid names to offer instead of, or on top of, `input`: its slug, then
+/// the slug under the usual prefixes when it has none. Never `input` itself.
+pub fn suggest_branch_names(input: &str) -> Vec<String> {
+    let slug = slugify_branch_name(input);
+    if slug.is_empty() {
+        return Vec::new();
+    }
+    let mut suggestions = vec![slug.clone()];
+    if !slug.contains('/') {
+        suggestions.extend(PREFIXES.iter().map(|p| format!("{p}{slug}")));
+    }
+    suggestions.retain(|s| s != input && check_branch_name(s).is_ok());
+    suggestions.dedup();
+    suggestions
+}
+
+/// A branch name for a feature described by a sentence, not among `taken`:
+/// its first meaningful words under `prefix`, or under `fix/` or `feature/`
+/// depending on the first word when `prefix` is `None`. A number is appended
+/// to names already taken. `None` when nothing usable is left.
+pub fn name_from_description(
+    description: &str,
+    prefix: Option<&str>,
+    taken: &[String],
+) -> Option<String> {
+    let slug = slugify_branch_name(&description.replace('/', " "));
+    let mut words: Vec<&str> = slug
+        .split('-')
+        .filter(|w| !w.is_empty() && !FILLER_WORDS.contains(w))
+        .collect();
+    let prefix = match prefix {
+        Some(prefix) => {
+            let prefix = prefix.trim().trim_end_matches('/');
+            if prefix.is_empty() {
+                String::new()
+            } else {
+                format!("{prefix}/")
+            }
+        }
+        None if words.len() > 1 && FIX_WORDS.contains(&words[0]) => {
+            words.remove(0);
+            "fix/".to_string()
+        }
+        None => "feature/".to_string(),
+    };
+    words.truncate(DESCRIPTION_WORDS);
+    if words.is_empty() {
+        return None;
+    }
+
+    let base: String = format!("{prefix}{}", words.join("-"))
+        .chars()
+        // Room for a number
+        .take(MAX_LEN - 4)
+        .collect();
+    let base = base.trim_end_matches('-');
+    let name = (1..)
+        .map(|n| match n {
+            1 => base.to_string(),
+            n => format!("{base}-{n}"),
+        })
+        .find(|name| !taken.contains(name))?;
+    check_branch_name(&name).ok().map(|()| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_branch_name() {
+        assert_eq!(check_branch_name("feature/add-login"), Ok(()));
+        assert_eq!(check_branch_name("fix/issue_42+b"), Ok(()));
+        assert_eq!(check_branch_name("v1.2/hotfix"), Ok(()));
+        assert_eq!(check_branch_name(""), Err(NameProblem::Empty));
+        assert_eq!(
+            check_branch_name("add login"),
+            Err(NameProblem::InvalidChar(' '))
+        );
+        assert_eq!(
+            check_branch_name("x\"; rm -rf ~"),
+            Err(NameProblem::InvalidChar('"'))
+        );
+        assert_eq!(check_branch_name("a:b"), Err(NameProblem::InvalidChar(':')));
+        assert_eq!(check_branch_name("-x"), Err(NameProblem::LeadingDash));
+        assert_eq!(check_branch_name("a..b"), Err(NameProblem::DoubleDot));
+        assert_eq!(
+            check_branch_name("feature/"),
+            Err(NameProblem::EmptySegment)
+        );
+        assert_eq!(check_branch_name("a//b"), Err(NameProblem::EmptySegment));
+        assert_eq!(check_branch_name(".a/b"), Err(NameProblem::HiddenSegment));
+        assert_eq!(
+            check_branch_name("a.lock/b"),
+            Err(NameProblem::HiddenSegment)
+        );
+        assert_eq!(
+            check_branch_name("release/v1.2"),
+            Err(NameProblem::DotInWindowName)
+        );
+        assert_eq!(
+            check_branch_name(&"a".repeat(MAX_LEN + 1)),
+            Err(NameProblem::TooLong)
+        );
+    }
+
+    #[test]
+    fn test_slugify_branch_name() {
+        assert_eq!(slugify_branch_name("Add Login Page!"), "add-login-page");
+        assert_eq!(
+            slugify_branch_name("Feature//Fix the .bug"),
+            "feature/fix-the-bug"
+        );
+        assert_eq!(slugify_branch_name("release/v1.2"), "release/v1-2");
+        assert_eq!(slugify_branch_name(" --- "), "");
+    }
+
+    #[test]
+    fn test_name_from_description() {
+        assert_eq!(
+            name_from_description("Add a login page for the admin area", None, &[]).as_deref(),
+            Some("feature/add-login-page-admin-area")
+        );
+        assert_eq!(
+            name_from_description("Fix crash on startup", None, &[]).as_deref(),
+            Some("fix/crash-startup")
+        );
+        // A word on its own is the name, not the prefix
+        assert_eq!(
+            name_from_description("hotfix", None, &[]).as_deref(),
+            Some("feature/hotfix")
+        );
+        assert_eq!(
+            name_from_description("Dark mode", Some("ui/"), &[]).as_deref(),
+            Some("ui/dark-mode")
+        );
+        assert_eq!(
+            name_from_description("Dark mode", Some(""), &[]).as_deref(),
+            Some("dark-mode")
+        );
+        let taken = ["feature/dark-mode".to_string(), "feature/dark-mode-2".to_string()];
+        assert_eq!(
+            name_from_description("Dark mode", None, &taken).as_deref(),
+            Some("feature/dark-mode-3")
+        );
+        assert_eq!(
+            name_from_description("one two three four five six seven", None, &[]).as_deref(),
+            Some("feature/one-two-three-four-five-six")
+        );
+        assert_eq!(name_from_description("the ?!", None, &[]), None);
+        assert_eq!(name_from_description("Dark mode", Some(".bad"), &[]), None);
+    }
+
+    #[test]
+    fn test_suggest_branch_names() {
+        assert_eq!(
+            suggest_branch_names("Add login"),
+            vec!["add-login", "feature/add-login", "fix/add-login"]
+        );
+        assert_eq!(
+            suggest_branch_names("add-login"),
+            vec!["feature/add-login", "fix/add-login"]
+        );
+        assert_eq!(suggest_branch_names("feature/x"), Vec::<String>::new());
+        assert!(suggest_branch_names("!!").is_empty());
+        for s in suggest_branch_names("Ça va? ~^:") {
+            assert_eq!(check_branch_name(&s), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Commit messages the way a repo wants them, from the `[commit]` table of
+//! its `.nomadflow.toml`, so that commits made from a phone pass the same
+//! commit-lint hooks as those made at a desk:
+//!
+//! ```toml
+//! [commit]
+//! template = "{message}\n\nRefs: {branch}"
+//! conventional = true
+//! types = ["feat", "fix", "docs"]
+//! scopes = ["api", "tui"]
+//! max_header_length = 72
+//! ```
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::{NomadError, Result};
+use crate::models::{CommitRule, CommitViolation};
+use crate::tasks::REPO_CONFIG;
+
+/// Types allowed when the repo does not list its own.
+pub const DEFAULT_TYPES: &[&str] = &[
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CommitRules {
+    /// Laid around each message: `{message}` is replaced by it, `{branch}`
+    /// by the feature's branch. Empty = the message as is.
+    pub template: String,
+    /// Require `type(scope)!: subject` headers.
+    pub conventional: bool,
+    /// Allowed types. Empty = [`DEFAULT_TYPES`].
+    pub types: Vec<String>,
+    /// Allowed scopes. Empty = any.
+    pub scopes: Vec<String>,
+    /// Longest header, in characters. 0 = no limit.
+    pub max_header_length: usize,
+}
+
+/// What a commit message is made of.
+#[derive(Debug, Clone, Default)]
+pub struct CommitParts<'a> {
+    pub message: &'a str,
+    pub commit_type: Option<&'a str>,
+    pub scope: Option<&'a str>,
+    pub breaking: bool,
+    pub branch: &'a str,
+}
+
+/// Type, scope and subject of a `type(scope)!: subject` header.
+fn parse_header(header: &str) -> Option<(&str, Option<&str>, &str)> {
+    let (prefix, subject) = header.split_once(':')?;
+    let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+    let (commit_type, scope) = match prefix.split_once('(') {
+        Some((commit_type, rest)) => (commit_type, Some(rest.strip_suffix(')')?)),
+        None => (prefix, None),
+    };
+    let is_word = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '-');
+    (is_word(commit_type) && scope.is_none_or(|s| !s.is_empty()))
+        .then(|| (commit_type, scope, subject.trim()))
+}
+
+impl CommitRules {
+    fn allows_type(&self, commit_type: &str) -> bool {
+        if self.types.is_empty() {
+            DEFAULT_TYPES.contains(&commit_type)
+        } else {
+            self.types.iter().any(|t| t == commit_type)
+        }
+    }
+
+    /// The message to commit: `parts.message`, after its conventional type,
+    /// scope and `!` when a type is given, laid into the template.
+    pub fn compose(&self, parts: &CommitParts<'_>) -> String {
+        let message = parts.message.trim();
+        let message = match parts.commit_type.map(str::trim).filter(|t| !t.is_empty()) {
+            Some(commit_type) => {
+                let scope = parts
+                    .scope
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| format!("({s})"))
+                    .unwrap_or_default();
+                let bang = if parts.breaking { "!" } else { "" };
+                format!("{commit_type}{scope}{bang}: {message}")
+            }
+            None => message.to_string(),
+        };
+        if self.template.trim().is_empty() {
+            return message;
+        }
+        // The branch first, so that a message mentioning `{branch}` is kept
+        let template = self.template.replace("{branch}", parts.branch);
+        if template.contains("{message}") {
+            template.replace("{message}", &message)
+        } else {
+            // A template without the placeholder holds trailers
+            format!("{message}\n\n{}", template.trim())
+        }
+    }
+
+    /// The rules `message` breaks. `conventional` checks its header even
+    /// when the repo does not require it, as when a type was given.
+    pub fn check(&self, message: &str, conventional: bool) -> Vec<CommitViolation> {
+        let mut violations = Vec::new();
+        let mut violate =
+            |rule, message: String| violations.push(CommitViolation { rule, message });
+        let header = message.lines().next().unwrap_or("").trim_end();
+
+        let length = header.chars().count();
+        if self.max_header_length > 0 && length > self.max_header_length {
+            violate(
+                CommitRule::HeaderTooLong,
+                format!(
+                    "The header is {length} characters long, the limit is {}",
+                    self.max_header_length
+                ),
+            );
+        }
+
+        if !(conventional || self.conventional) {
+            if header.trim().is_empty() {
+                violate(CommitRule::EmptySubject, "The header is empty".to_string());
+            }
+            return violations;
+        }
+        let Some((commit_type, scope, subject)) = parse_header(header) else {
+            violate(
+                CommitRule::NotConventional,
+                "The header must read `type(scope): subject`".to_string(),
+            );
+            return violations;
+        };
+        if !self.allows_type(commit_type) {
+            let allowed = if self.types.is_empty() {
+                DEFAULT_TYPES.join(", ")
+            } else {
+                self.types.join(", ")
+            };
+            violate(
+                CommitRule::UnknownType,
+                format!("Unknown type '{commit_type}', use one of: {allowed}"),
+            );
+        }
+        if let Some(scope) = scope {
+            if !self.scopes.is_empty() && !self.scopes.iter().any(|s| s == scope) {
+                violate(
+                    CommitRule::UnknownScope,
+                    format!(
+                        "Unknown scope '{scope}', use one of: {}",
+                        self.scopes.join(", ")
+                    ),
+                );
+            }
+        }
+        if subject.is_empty() {
+            violate(CommitRule::EmptySubject, "The subject is empty".to_string());
+        }
+        violations
+    }
+}
+
+#[derive(Deserialize)]
+struct RepoConfig {
+    #[serde(default)]
+    commit: CommitRules,
+}
+
+/// Commit rules of the `.nomadflow.toml` of `worktree`, none without one.
+pub fn load_commit_rules(worktree: &Path) -> Result<CommitRules> {
+    let path = worktree.join(REPO_CONFIG);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(CommitRules::default()),
+        Err(e) => return Err(e.into()),
+    };
+    toml::from_str::<RepoConfig>(&content)
+        .map(|config| config.commit)
+        .map_err(|e| NomadError::Config(format!("Failed to parse {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(content: &str) -> CommitRules {
+        toml::from_str::<RepoConfig>(content).unwrap().commit
+    }
+
+    #[test]
+    fn test_compose() {
+        let parts = CommitParts {
+            message: " add the login form\n\nWith OAuth. ",
+            commit_type: Some("feat"),
+            scope: Some("auth"),
+            breaking: true,
+            branch: "feature/login",
+        };
+        assert_eq!(
+            CommitRules::default().compose(&parts),
+            "feat(auth)!: add the login form\n\nWith OAuth."
+        );
+
+        let plain = CommitParts {
+            message: "Fix {branch} typo",
+            branch: "fix/typo",
+            ..Default::default()
+        };
+        let template = rules("[commit]\ntemplate = \"{message}\\n\\nRefs: {branch}\"\n");
+        assert_eq!(
+            template.compose(&plain),
+            "Fix {branch} typo\n\nRefs: fix/typo"
+        );
+        let trailers = rules("[commit]\ntemplate = \"Signed-off-by: Me\"\n");
+        assert_eq!(
+            trailers.compose(&plain),
+            "Fix {branch} typo\n\nSigned-off-by: Me"
+        );
+    }
+
+    #[test]
+    fn test_check_conventional() {
+        let rules =
+            rules("[commit]\nconventional = true\nscopes = [\"api\"]\nmax_header_length = 20\n");
+        let broken = |message| -> Vec<CommitRule> {
+            rules
+                .check(message, false)
+                .into_iter()
+                .map(|v| v.rule)
+                .collect()
+        };
+        assert!(broken("fix(api)!: a bug").is_empty());
+        assert!(broken("docs: readme\n\nLonger explanation").is_empty());
+        assert_eq!(broken("Fix a bug"), [CommitRule::NotConventional]);
+        assert_eq!(broken("fix(): a bug"), [CommitRule::NotConventional]);
+        assert_eq!(broken("wip: a bug"), [CommitRule::UnknownType]);
+        assert_eq!(broken("fix(tui): a bug"), [CommitRule::UnknownScope]);
+        assert_eq!(broken("fix:"), [CommitRule::EmptySubject]);
+        assert_eq!(
+            broken("wip(api): a rather long subject"),
+            [CommitRule::HeaderTooLong, CommitRule::UnknownType]
+        );
+    }
+
+    #[test]
+    fn test_check_free_form() {
+        let rules = CommitRules::default();
+        assert!(rules.check("Fix a bug", false).is_empty());
+        assert_eq!(
+            rules.check("Fix a bug", true)[0].rule,
+            CommitRule::NotConventional
+        );
+        assert_eq!(
+            rules.check("\nbody", false)[0].rule,
+            CommitRule::EmptySubject
+        );
+    }
+}
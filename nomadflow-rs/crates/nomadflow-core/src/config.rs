@@ -1,8 +1,14 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 
 use crate::error::{NomadError, Result};
+use crate::fsutil;
+use crate::models::ForgeKind;
+use crate::secrets::{KeySource, SecretKey, TokenStore, PASSPHRASE_ENV};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -22,25 +28,159 @@ impl Default for PathsConfig {
 #[serde(default)]
 pub struct TmuxConfig {
     pub session: String,
+    /// Close the idle windows of features whose worktree is gone.
+    pub close_orphan_windows: bool,
+    /// Reopen a window for every feature worktree when the server starts.
+    pub restore_windows: bool,
+    /// Install tmux hooks reporting pane deaths, bells and attaches to the server.
+    pub hooks: bool,
+    /// How the repo part of window names is chosen.
+    pub window_prefix: WindowPrefix,
+    /// Report a program that produced no output for this long, as waiting
+    /// for input or stuck. 0 = never.
+    pub idle_alert_seconds: u32,
+}
+
+/// Repo part of the tmux window names, `<prefix>:<feature>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowPrefix {
+    /// The repo's directory name, with a hash of its path only when another
+    /// repo already uses that name.
+    #[default]
+    Name,
+    /// The repo's directory name and a hash of its path, always.
+    Hash,
 }
 
 impl Default for TmuxConfig {
     fn default() -> Self {
         Self {
             session: "nomadflow".to_string(),
+            close_orphan_windows: true,
+            restore_windows: true,
+            hooks: true,
+            window_prefix: WindowPrefix::default(),
+            idle_alert_seconds: 120,
         }
     }
 }
 
+/// Suspension of the jobs running in windows nobody looked at for a while.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SuspendConfig {
+    /// Minutes without output or a client viewing the window before its
+    /// foreground job is suspended. `0` disables suspension.
+    pub idle_minutes: u32,
+    /// Command suspending the job, `{pgid}` being its process group.
+    /// Empty = `SIGSTOP`.
+    pub command: String,
+    /// Command resuming the job. Empty = `SIGCONT`.
+    pub resume_command: String,
+}
+
+/// Experimental terminal transport over a WebRTC data channel, negotiated
+/// through `/api/webrtc/offer`. Clients fall back to the WebSocket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebRtcConfig {
+    pub enabled: bool,
+    /// STUN servers used to find a direct path, as `stun:host:port` URLs.
+    pub stun_servers: Vec<String>,
+}
+
+impl Default for WebRtcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stun_servers: vec!["stun:stun.l.google.com:19302".to_string()],
+        }
+    }
+}
+
+/// Limits on the processes of a feature window. `0` leaves a limit off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// CPU cap, in percent of one core (`200` = two cores).
+    pub cpu_percent: u32,
+    /// Memory cap, in MiB.
+    pub memory_mb: u64,
+    /// Scheduling priority, as for `nice` (`1` to `19` lower it).
+    pub nice: i32,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    pub cpu_percent: u32,
+    pub memory_mb: u64,
+    pub nice: i32,
+    /// Limits replacing the ones above for some windows, keyed by window
+    /// name (`repo:feature`).
+    pub features: BTreeMap<String, ResourceLimits>,
+}
+
+impl LimitsConfig {
+    /// Limits applying to the window `name`.
+    pub fn for_window(&self, name: &str) -> ResourceLimits {
+        self.features
+            .get(name)
+            .copied()
+            .unwrap_or(ResourceLimits {
+                cpu_percent: self.cpu_percent,
+                memory_mb: self.memory_mb,
+                nice: self.nice,
+            })
+    }
+
+    /// Whether no window is limited.
+    pub fn is_empty(&self) -> bool {
+        self.for_window("").is_empty() && self.features.values().all(ResourceLimits::is_empty)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct TtydConfig {
+    /// What runs the terminal of `/terminal/ws`.
+    pub backend: TerminalBackend,
+    /// Port of the ttyd started with the `ttyd` backend.
     pub port: u16,
+    /// Terminal output is sent to clients at most once per interval. 0 = as
+    /// soon as the client took the previous batch.
+    pub output_interval_ms: u64,
+    /// Output waiting for a slow client beyond which redraws preceding a
+    /// full-screen clear are dropped. 0 = never drop.
+    pub output_backlog_kb: usize,
+}
+
+/// Terminal backend of the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TerminalBackend {
+    /// tmux attached in a PTY by the server itself.
+    #[default]
+    Native,
+    /// An external ttyd the server proxies to.
+    Ttyd,
 }
 
 impl Default for TtydConfig {
     fn default() -> Self {
-        Self { port: 7681 }
+        Self {
+            backend: TerminalBackend::default(),
+            port: 7681,
+            output_interval_ms: 0,
+            output_backlog_kb: 256,
+        }
     }
 }
 
@@ -49,6 +189,8 @@ impl Default for TtydConfig {
 pub struct ApiConfig {
     pub port: u16,
     pub host: String,
+    /// Gzip/deflate API and proxied terminal responses when the client accepts it.
+    pub compression: bool,
 }
 
 impl Default for ApiConfig {
@@ -56,14 +198,82 @@ impl Default for ApiConfig {
         Self {
             port: 8080,
             host: "0.0.0.0".to_string(),
+            compression: true,
         }
     }
 }
 
+/// HTTPS on the API port, for LAN use without a reverse proxy. Ignored with
+/// `--public`, where the relay serves HTTPS.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
+pub struct TlsConfig {
+    /// PEM certificate chain. Empty = no certificate of one's own.
+    pub cert: String,
+    /// PEM private key. Empty = read it from `cert`.
+    pub key: String,
+    /// Without `cert`, serve a self-signed certificate generated once under
+    /// `<base_dir>/tls`.
+    pub self_signed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AuthConfig {
     pub secret: String,
+    /// Base32 TOTP secret required as a second factor. Empty = disabled.
+    pub totp_secret: String,
+    /// How long a device stays trusted after "remember this device".
+    pub totp_remember_days: u32,
+    /// Failed authentications from one IP within 10 minutes before it is banned. 0 = never ban.
+    pub ban_max_failures: u32,
+    /// How long a ban lasts.
+    pub ban_minutes: u32,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            totp_secret: String::new(),
+            totp_remember_days: 30,
+            ban_max_failures: 10,
+            ban_minutes: 60,
+        }
+    }
+}
+
+/// Single sign-on: bearer JWTs issued by an OpenID Connect provider, accepted
+/// besides the secret and API tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OidcConfig {
+    /// URL of the provider, which tokens must name as their `iss`. Empty = off.
+    pub issuer: String,
+    /// Value tokens must have in their `aud` claim.
+    pub audience: String,
+    /// Where the provider publishes its signing keys. Empty = the `jwks_uri`
+    /// of its discovery document.
+    pub jwks_url: String,
+    /// Prefix of the scopes granting access, such as `nomadflow:terminal`.
+    pub scope_prefix: String,
+}
+
+impl Default for OidcConfig {
+    fn default() -> Self {
+        Self {
+            issuer: String::new(),
+            audience: "nomadflow".to_string(),
+            jwks_url: String::new(),
+            scope_prefix: "nomadflow:".to_string(),
+        }
+    }
+}
+
+impl OidcConfig {
+    pub fn enabled(&self) -> bool {
+        !self.issuer.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +284,39 @@ pub struct TunnelConfig {
     pub relay_secret: String,
     /// Preferred subdomain for stable public URL. Empty = random (default).
     pub subdomain: String,
+    /// Personal relay API key, with its own quotas and reserved subdomains.
+    /// Empty = register with the shared `relay_secret`.
+    pub api_key: String,
+    /// Own hostname fronting the tunnel, set up as a CNAME to the tunnel's
+    /// hostname. Empty = use the tunnel hostname only.
+    pub custom_domain: String,
+    /// PEM client certificate issued by the relay admin, presented when
+    /// registering (mutual TLS). Empty = register with a secret or API key.
+    pub client_cert: String,
+    /// PEM private key of `client_cert`. Empty = read from `client_cert`.
+    pub client_key: String,
+    /// Relays to use instead of `relay_host`, in order of preference. The
+    /// tunnel moves to the next one when the current one announces it is
+    /// shutting down or keeps failing. They take the same secret or key.
+    pub relay_hosts: Vec<String>,
+}
+
+impl TunnelConfig {
+    /// The relays to try in order: `relay_hosts` without blanks and
+    /// duplicates, or `relay_host` alone.
+    pub fn relays(&self) -> Vec<String> {
+        let mut hosts: Vec<String> = Vec::new();
+        for host in &self.relay_hosts {
+            let host = host.trim();
+            if !host.is_empty() && !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        }
+        if hosts.is_empty() {
+            hosts.push(self.relay_host.clone());
+        }
+        hosts
+    }
 }
 
 impl Default for TunnelConfig {
@@ -85,19 +328,170 @@ impl Default for TunnelConfig {
             // This prevents casual abuse from non-nomadflow traffic but is not a real secret.
             relay_secret: "2990b3a121ae2a13492e71b4e41b33f7d0a7c5beea722974".to_string(),
             subdomain: String::new(),
+            api_key: String::new(),
+            custom_domain: String::new(),
+            client_cert: String::new(),
+            client_key: String::new(),
+            relay_hosts: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Display language (`en`, `fr`). Empty = detect from `LANG`.
+    pub locale: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TuiConfig {
+    /// ASCII status glyphs, for fonts without the Unicode symbols.
+    pub ascii: bool,
+    /// Give up on switching to a feature after this long. 0 = wait forever.
+    pub attach_timeout_seconds: u32,
+}
+
+impl Default for TuiConfig {
+    fn default() -> Self {
+        Self {
+            ascii: false,
+            attach_timeout_seconds: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Desktop notifications while the TUI runs.
+    pub enabled: bool,
+    /// A program in a window went quiet (see `tmux.idle_alert_seconds`).
+    pub pane_idle: bool,
+    /// A background task or the program of a window ended.
+    pub job_finished: bool,
+    /// The tunnel lost its connection to the relay.
+    pub tunnel_drop: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pane_idle: true,
+            job_finished: true,
+            tunnel_drop: true,
         }
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorConfig {
+    /// SSH host editors reach this machine through (`devbox`, `me@host`).
+    /// Empty = worktrees are opened as local folders.
+    pub ssh_host: String,
+    /// Tool `git mergetool` resolves conflicts with (`vimdiff`, `meld`).
+    /// Empty = conflicts are listed with `git status`.
+    pub mergetool: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrashConfig {
+    /// Write a report file to `<base_dir>/crashes` when NomadFlow panics.
+    pub reports: bool,
+    /// Also POST reports to this URL. Empty = keep them local.
+    pub upload_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GcConfig {
+    /// Days crash reports and task runs are kept by `nomadflow gc`.
+    /// `0` keeps them forever.
+    pub retention_days: u32,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForgeConfig {
+    /// Token opening pull requests on GitHub and GitHub Enterprise hosts.
+    pub github_token: String,
+    /// Token opening merge requests on GitLab hosts.
+    pub gitlab_token: String,
+}
+
+impl ForgeConfig {
+    /// Token for `kind`, if one is set.
+    pub fn token(&self, kind: ForgeKind) -> Option<&str> {
+        let token = match kind {
+            ForgeKind::GitHub => &self.github_token,
+            ForgeKind::GitLab => &self.gitlab_token,
+        };
+        Some(token.as_str()).filter(|t| !t.is_empty())
+    }
+}
+
+/// Commands run around a feature's life. With `repo_hooks`, a repo's own
+/// `[hooks]` in its `.nomadflow.toml` replace these, event by event.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Typed into a feature's window once its worktree is created.
+    pub post_create: Vec<String>,
+    /// Typed into a feature's window when it is switched to, if idle.
+    pub post_switch: Vec<String>,
+    /// Run in a feature's worktree before it is deleted.
+    pub pre_delete: Vec<String>,
+    /// Honour the hooks of the repos' `.nomadflow.toml`. Off by default:
+    /// they would run the commands of any repo cloned or switched to.
+    pub repo_hooks: bool,
+    /// Let `/api/commit` skip the repos' git hooks when asked to.
+    pub allow_skip_git_hooks: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecretsConfig {
+    /// Where the key encrypting the secrets of this file comes from.
+    pub key_source: KeySource,
+    /// Base64 salt for the passphrase key.
+    pub salt: String,
+    /// Where the TUI keeps the auth tokens of saved servers.
+    pub server_tokens: TokenStore,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Settings {
     pub paths: PathsConfig,
     pub tmux: TmuxConfig,
+    pub limits: LimitsConfig,
+    pub suspend: SuspendConfig,
     pub ttyd: TtydConfig,
     pub api: ApiConfig,
+    pub tls: TlsConfig,
+    pub webrtc: WebRtcConfig,
     pub auth: AuthConfig,
+    pub oidc: OidcConfig,
     pub tunnel: TunnelConfig,
+    pub ui: UiConfig,
+    pub tui: TuiConfig,
+    pub notifications: NotificationsConfig,
+    pub editor: EditorConfig,
+    pub crash: CrashConfig,
+    pub gc: GcConfig,
+    pub forge: ForgeConfig,
+    pub hooks: HooksConfig,
+    pub secrets: SecretsConfig,
 }
 
 impl Settings {
@@ -116,6 +510,38 @@ impl Settings {
         Self::expand_home(&self.paths.base_dir)
     }
 
+    /// Paths of the client certificate and key presented to the relay, if set.
+    pub fn relay_client_identity(&self) -> Option<(PathBuf, PathBuf)> {
+        let tunnel = &self.tunnel;
+        if tunnel.client_cert.is_empty() {
+            return None;
+        }
+        let key = if tunnel.client_key.is_empty() {
+            &tunnel.client_cert
+        } else {
+            &tunnel.client_key
+        };
+        Some((
+            Self::expand_home(&tunnel.client_cert),
+            Self::expand_home(key),
+        ))
+    }
+
+    /// Paths of the certificate and key the API is served with, if TLS is on.
+    /// With `tls.self_signed`, they may not exist yet.
+    pub fn tls_identity(&self) -> Option<(PathBuf, PathBuf)> {
+        let tls = &self.tls;
+        if !tls.cert.is_empty() {
+            let key = if tls.key.is_empty() { &tls.cert } else { &tls.key };
+            return Some((Self::expand_home(&tls.cert), Self::expand_home(key)));
+        }
+        if tls.self_signed {
+            let dir = self.base_dir().join("tls");
+            return Some((dir.join("cert.pem"), dir.join("key.pem")));
+        }
+        None
+    }
+
     /// Repos directory.
     pub fn repos_dir(&self) -> PathBuf {
         self.base_dir().join("repos")
@@ -141,22 +567,32 @@ impl Settings {
         self.base_dir().join("config.toml")
     }
 
-    /// Load settings from the TOML config file.
+    /// Load settings from the system config, the user config (`config_path` or
+    /// the default location) and the environment, later layers winning.
     pub fn load(config_path: Option<&PathBuf>) -> Result<Self> {
-        let path = match config_path {
+        Self::load_layered(config_path).map(|layered| layered.settings)
+    }
+
+    /// Like [`load`](Self::load), keeping track of where each value comes from.
+    pub fn load_layered(config_path: Option<&PathBuf>) -> Result<Layered> {
+        let mut layered = Self::load_sealed(config_path)?;
+        layered.settings.open_secrets()?;
+        Ok(layered)
+    }
+
+    /// Like [`load`](Self::load), leaving the secrets as stored on disk, for
+    /// commands that never use them and must not ask for a passphrase or the
+    /// keychain, such as the shell prompt's.
+    pub fn load_plain(config_path: Option<&PathBuf>) -> Result<Self> {
+        Self::load_sealed(config_path).map(|layered| layered.settings)
+    }
+
+    fn load_sealed(config_path: Option<&PathBuf>) -> Result<Layered> {
+        let user = match config_path {
             Some(p) => p.clone(),
             None => Self::config_path(),
         };
-
-        if path.exists() {
-            let content = std::fs::read_to_string(&path)
-                .map_err(|e| NomadError::Config(format!("Failed to read config: {e}")))?;
-            let settings: Settings = toml::from_str(&content)
-                .map_err(|e| NomadError::Config(format!("Failed to parse config: {e}")))?;
-            Ok(settings)
-        } else {
-            Ok(Settings::default())
-        }
+        load_layers(Path::new(SYSTEM_CONFIG), &user, std::env::vars())
     }
 
     /// Save the current settings to the TOML config file.
@@ -166,13 +602,152 @@ impl Settings {
             std::fs::create_dir_all(parent)
                 .map_err(|e| NomadError::Config(format!("Failed to create config dir: {e}")))?;
         }
-        let content = toml::to_string_pretty(self)
+        let user = read_table(&path)?.unwrap_or_default();
+        let system = read_table(Path::new(SYSTEM_CONFIG))?.unwrap_or_default();
+        let table = self.user_table(&user, &system, std::env::vars())?;
+        let content = toml::to_string_pretty(&table)
             .map_err(|e| NomadError::Config(format!("Failed to serialize config: {e}")))?;
-        std::fs::write(&path, content)
+        fsutil::write_atomic(&path, content)
             .map_err(|e| NomadError::Config(format!("Failed to write config: {e}")))?;
         Ok(())
     }
 
+    /// What [`save`](Self::save) writes over the `user` config: values set
+    /// through the environment keep their value from the files, and values
+    /// inherited from the `system` config are left to it.
+    fn user_table(
+        &self,
+        user: &toml::Table,
+        system: &toml::Table,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<toml::Table> {
+        let mut stored = self.clone();
+        if let Some(key) = self.secret_key(true)? {
+            for value in stored.secret_fields() {
+                *value = key.seal(value)?;
+            }
+        }
+        let mut table = to_table(&stored)?;
+
+        let defaults = to_table(&Settings::default())?;
+        let current = to_table(self)?;
+        for (section, key, value, _) in env_layer(vars, &defaults)? {
+            if lookup(&current, &section, &key) != Some(&value) {
+                // Changed since loaded: the new value is meant to be saved
+                continue;
+            }
+            let below = [user, system, &defaults]
+                .into_iter()
+                .find_map(|layer| lookup(layer, &section, &key));
+            if let (Some(below), Some(toml::Value::Table(values))) =
+                (below, table.get_mut(&section))
+            {
+                values.insert(key, below.clone());
+            }
+        }
+
+        for (section, values) in table.iter_mut() {
+            if let Some(values) = values.as_table_mut() {
+                values.retain(|key, value| {
+                    lookup(user, section, key).is_some()
+                        || lookup(system, section, key) != Some(value)
+                });
+            }
+        }
+        Ok(table)
+    }
+
+    /// Secrets encrypted on disk when a key source is configured.
+    fn secret_fields(&mut self) -> [&mut String; 6] {
+        [
+            &mut self.auth.secret,
+            &mut self.auth.totp_secret,
+            &mut self.tunnel.relay_secret,
+            &mut self.tunnel.api_key,
+            &mut self.forge.github_token,
+            &mut self.forge.gitlab_token,
+        ]
+    }
+
+    /// Key encrypting the secrets, or `None` when they are stored in plain text.
+    /// With `create`, a missing keychain key or passphrase salt is generated.
+    pub fn secret_key(&self, create: bool) -> Result<Option<SecretKey>> {
+        match self.secrets.key_source {
+            KeySource::None => Ok(None),
+            KeySource::Keychain => SecretKey::from_keychain(create).map(Some),
+            KeySource::Passphrase => {
+                let passphrase = std::env::var(PASSPHRASE_ENV).map_err(|_| {
+                    NomadError::Secrets(format!("Secrets are encrypted: set {PASSPHRASE_ENV}"))
+                })?;
+                let salt = base64::engine::general_purpose::STANDARD
+                    .decode(&self.secrets.salt)
+                    .ok()
+                    .filter(|salt| !salt.is_empty())
+                    .ok_or_else(|| {
+                        NomadError::Secrets("Missing or invalid secrets.salt".to_string())
+                    })?;
+                SecretKey::from_passphrase(&passphrase, &salt).map(Some)
+            }
+        }
+    }
+
+    /// Switch to `source`, generating a passphrase salt if needed.
+    /// Secrets are encrypted with the new key on the next [`save`](Self::save).
+    pub fn set_key_source(&mut self, source: KeySource) -> Result<()> {
+        self.secrets.key_source = source;
+        if source == KeySource::Passphrase {
+            self.secrets.salt = base64::engine::general_purpose::STANDARD.encode(random_bytes(16)?);
+        }
+        Ok(())
+    }
+
+    /// Decrypt the secrets loaded from disk.
+    fn open_secrets(&mut self) -> Result<()> {
+        if !self.secret_fields().iter().any(|v| crate::secrets::is_encrypted(v)) {
+            return Ok(());
+        }
+        let key = self.secret_key(false)?.ok_or_else(|| {
+            NomadError::Secrets("Encrypted secrets but no secrets.key_source".to_string())
+        })?;
+        for value in self.secret_fields() {
+            *value = key.open(value)?;
+        }
+        Ok(())
+    }
+
+    /// Device token that lets local clients (the TUI) skip the TOTP prompt.
+    /// Created on first use in the base directory, readable only by the owner.
+    pub fn local_device_token(&self) -> Result<String> {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let path = self.base_dir().join("local-device.token");
+        if let Ok(token) = std::fs::read_to_string(&path) {
+            if !token.trim().is_empty() {
+                return Ok(token.trim().to_string());
+            }
+        }
+
+        std::fs::create_dir_all(self.base_dir())?;
+        let token = random_hex(32)?;
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                file.write_all(token.as_bytes())?;
+                Ok(token)
+            }
+            // Another process created it first
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                Ok(std::fs::read_to_string(&path)?.trim().to_string())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Create necessary directories if they don't exist.
     pub fn ensure_directories(&self) -> Result<()> {
         std::fs::create_dir_all(self.base_dir())?;
@@ -182,6 +757,198 @@ impl Settings {
     }
 }
 
+/// `len` random bytes from the OS.
+/// System-wide config, set by an administrator and overridden by the user's.
+pub const SYSTEM_CONFIG: &str = "/etc/nomadflow/config.toml";
+
+/// Prefix of the environment variables overriding settings, named
+/// `NOMADFLOW_<SECTION>_<KEY>` (e.g. `NOMADFLOW_API_PORT`).
+pub const ENV_PREFIX: &str = "NOMADFLOW_";
+
+/// Layer a setting value comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Env(var) => write!(f, "env {var}"),
+        }
+    }
+}
+
+/// Merged settings with the origin of each value, keyed `section.key`.
+#[derive(Debug, Clone)]
+pub struct Layered {
+    pub settings: Settings,
+    pub origins: BTreeMap<String, Origin>,
+}
+
+impl Layered {
+    /// The merged settings as TOML, secrets masked, each value followed by
+    /// its origin as a comment when `with_origin` is set.
+    pub fn render(&self, with_origin: bool) -> Result<String> {
+        let mut masked = self.settings.clone();
+        for value in masked.secret_fields() {
+            if !value.is_empty() {
+                *value = "********".to_string();
+            }
+        }
+        let mut out = String::new();
+        for (section, values) in to_table(&masked)? {
+            let toml::Value::Table(values) = values else {
+                continue;
+            };
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("[{section}]\n"));
+            for (key, value) in values {
+                out.push_str(&format!("{key} = {value}"));
+                if let Some(origin) = self
+                    .origins
+                    .get(&format!("{section}.{key}"))
+                    .filter(|_| with_origin)
+                {
+                    out.push_str(&format!("  # {origin}"));
+                }
+                out.push('\n');
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn to_table<T: Serialize>(value: &T) -> Result<toml::Table> {
+    toml::Table::try_from(value)
+        .map_err(|e| NomadError::Config(format!("Failed to serialize config: {e}")))
+}
+
+fn lookup<'a>(table: &'a toml::Table, section: &str, key: &str) -> Option<&'a toml::Value> {
+    table.get(section)?.as_table()?.get(key)
+}
+
+/// Read a config file as a TOML table; `None` when it does not exist. An empty
+/// file was truncated by a crash mid-save, so its backup is used instead.
+/// Syntax errors are reported: they come from hand edits.
+fn read_table(path: &Path) -> Result<Option<toml::Table>> {
+    let parse = |content: &str| {
+        (!content.trim().is_empty()).then(|| {
+            toml::from_str::<toml::Table>(content)
+                .map_err(|e| NomadError::Config(format!("Failed to parse {}: {e}", path.display())))
+        })
+    };
+    fsutil::read_with_backup(path, parse)
+        .map(|(table, _)| table)
+        .transpose()
+}
+
+/// Settings overridden by `NOMADFLOW_<SECTION>_<KEY>` variables, as
+/// `(section, key, value, variable)`, typed after the defaults. Variables
+/// naming no setting are ignored.
+fn env_layer(
+    vars: impl IntoIterator<Item = (String, String)>,
+    defaults: &toml::Table,
+) -> Result<Vec<(String, String, toml::Value, String)>> {
+    let mut overrides = Vec::new();
+    for (var, raw) in vars {
+        let Some(name) = var.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let name = name.to_lowercase();
+        let found = defaults.iter().find_map(|(section, values)| {
+            let key = name.strip_prefix(section.as_str())?.strip_prefix('_')?;
+            Some((section, key, values.as_table()?.get(key)?))
+        });
+        let Some((section, key, default)) = found else {
+            continue;
+        };
+        let invalid = || NomadError::Config(format!("Invalid value for {var}: {raw}"));
+        let value = match default {
+            toml::Value::Integer(_) => toml::Value::Integer(raw.parse().map_err(|_| invalid())?),
+            toml::Value::Boolean(_) => toml::Value::Boolean(raw.parse().map_err(|_| invalid())?),
+            // Comma-separated
+            toml::Value::Array(_) => toml::Value::Array(
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| toml::Value::String(s.to_string()))
+                    .collect(),
+            ),
+            _ => toml::Value::String(raw.clone()),
+        };
+        overrides.push((section.clone(), key.to_string(), value, var));
+    }
+    Ok(overrides)
+}
+
+/// Merge the defaults, the `system` and `user` config files and the
+/// environment `vars`, in that order of precedence. The secrets are left
+/// sealed.
+fn load_layers(
+    system: &Path,
+    user: &Path,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Layered> {
+    let defaults = to_table(&Settings::default())?;
+    let mut merged = defaults.clone();
+    let mut origins = BTreeMap::new();
+    let mut set = |section: &str, key: &str, value: toml::Value, origin: Origin| {
+        // Unknown sections are ignored, as serde would
+        if let Some(toml::Value::Table(values)) = merged.get_mut(section) {
+            values.insert(key.to_string(), value);
+            origins.insert(format!("{section}.{key}"), origin);
+        }
+    };
+
+    for (section, values) in &defaults {
+        for (key, value) in values.as_table().into_iter().flatten() {
+            set(section, key, value.clone(), Origin::Default);
+        }
+    }
+    for path in [system, user] {
+        let Some(layer) = read_table(path)? else {
+            continue;
+        };
+        for (section, values) in &layer {
+            for (key, value) in values.as_table().into_iter().flatten() {
+                set(
+                    section,
+                    key,
+                    value.clone(),
+                    Origin::File(path.to_path_buf()),
+                );
+            }
+        }
+    }
+    for (section, key, value, var) in env_layer(vars, &defaults)? {
+        set(&section, &key, value, Origin::Env(var));
+    }
+
+    let settings: Settings = toml::Value::Table(merged)
+        .try_into()
+        .map_err(|e| NomadError::Config(format!("Failed to parse config: {e}")))?;
+    Ok(Layered { settings, origins })
+}
+
+pub(crate) fn random_bytes(len: usize) -> Result<Vec<u8>> {
+    use std::io::Read;
+    let mut bytes = vec![0u8; len];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// `len` random bytes from the OS, hex-encoded.
+fn random_hex(len: usize) -> Result<String> {
+    Ok(random_bytes(len)?.iter().map(|b| format!("{b:02x}")).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,6 +964,7 @@ base_dir = "/tmp/nomadtest"
 session = "mytest"
 
 [ttyd]
+backend = "ttyd"
 port = 9999
 
 [api]
@@ -205,14 +973,20 @@ host = "127.0.0.1"
 
 [auth]
 secret = "s3cret"
+
+[ui]
+locale = "fr"
 "#;
         let settings: Settings = toml::from_str(toml_str).unwrap();
         assert_eq!(settings.paths.base_dir, "/tmp/nomadtest");
         assert_eq!(settings.tmux.session, "mytest");
+        assert_eq!(settings.ttyd.backend, TerminalBackend::Ttyd);
         assert_eq!(settings.ttyd.port, 9999);
         assert_eq!(settings.api.port, 3000);
         assert_eq!(settings.api.host, "127.0.0.1");
+        assert!(settings.api.compression);
         assert_eq!(settings.auth.secret, "s3cret");
+        assert_eq!(settings.ui.locale, "fr");
     }
 
     #[test]
@@ -221,9 +995,46 @@ secret = "s3cret"
         let settings: Settings = toml::from_str(toml_str).unwrap();
         assert_eq!(settings.paths.base_dir, "~/.nomadflowcode");
         assert_eq!(settings.tmux.session, "nomadflow");
+        assert_eq!(settings.ttyd.backend, TerminalBackend::Native);
         assert_eq!(settings.ttyd.port, 7681);
         assert_eq!(settings.api.port, 8080);
         assert_eq!(settings.auth.secret, "");
+        assert_eq!(settings.auth.ban_max_failures, 10);
+        assert!(!settings.oidc.enabled());
+        assert_eq!(settings.oidc.audience, "nomadflow");
+    }
+
+    #[test]
+    fn test_window_limits() {
+        let toml_str = r#"
+[limits]
+cpu_percent = 100
+nice = 10
+
+[limits.features."app:build"]
+memory_mb = 4096
+"#;
+        let settings: Settings = toml::from_str(toml_str).unwrap();
+        let limits = &settings.limits;
+        assert_eq!(
+            limits.for_window("app:login"),
+            ResourceLimits {
+                cpu_percent: 100,
+                memory_mb: 0,
+                nice: 10
+            }
+        );
+        // A feature entry replaces the defaults rather than adding to them
+        assert_eq!(
+            limits.for_window("app:build"),
+            ResourceLimits {
+                cpu_percent: 0,
+                memory_mb: 4096,
+                nice: 0
+            }
+        );
+        assert!(!limits.is_empty());
+        assert!(Settings::default().limits.is_empty());
     }
 
     #[test]
@@ -250,6 +1061,7 @@ secret = "s3cret"
             },
             auth: super::AuthConfig {
                 secret: "my-password".to_string(),
+                ..Default::default()
             },
             tunnel: super::TunnelConfig {
                 subdomain: "my-laptop".to_string(),
@@ -270,6 +1082,130 @@ secret = "s3cret"
         assert_eq!(loaded.api.port, 8080); // default preserved
     }
 
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_layers_precedence() {
+        let tmp = TempDir::new().unwrap();
+        let system = tmp.path().join("system.toml");
+        let user = tmp.path().join("user.toml");
+        std::fs::write(&system, "[api]\nport = 9000\n\n[tmux]\nsession = \"fleet\"\n").unwrap();
+        std::fs::write(&user, "[tmux]\nsession = \"mine\"\n").unwrap();
+
+        let layered = load_layers(
+            &system,
+            &user,
+            vars(&[
+                ("NOMADFLOW_API_HOST", "127.0.0.1"),
+                ("NOMADFLOW_TMUX_RESTORE_WINDOWS", "false"),
+                ("NOMADFLOW_PASSPHRASE", "not a setting"),
+                ("HOME", "/root"),
+            ]),
+        )
+        .unwrap();
+        let settings = &layered.settings;
+        assert_eq!(settings.api.port, 9000);
+        assert_eq!(settings.tmux.session, "mine");
+        assert_eq!(settings.api.host, "127.0.0.1");
+        assert!(!settings.tmux.restore_windows);
+        assert_eq!(settings.ttyd.port, 7681);
+
+        let origin = |key: &str| layered.origins[key].clone();
+        assert_eq!(origin("api.port"), Origin::File(system.clone()));
+        assert_eq!(origin("tmux.session"), Origin::File(user.clone()));
+        assert_eq!(
+            origin("api.host"),
+            Origin::Env("NOMADFLOW_API_HOST".to_string())
+        );
+        assert_eq!(origin("ttyd.port"), Origin::Default);
+
+        let shown = layered.render(true).unwrap();
+        assert!(shown.contains(&format!("port = 9000  # {}\n", system.display())));
+        assert!(shown.contains("host = \"127.0.0.1\"  # env NOMADFLOW_API_HOST\n"));
+        // The public relay secret is masked like any other
+        assert!(shown.contains("relay_secret = \"********\"  # default\n"));
+        assert!(!layered.render(false).unwrap().contains('#'));
+    }
+
+    #[test]
+    fn test_relay_hosts_from_env_list() {
+        let missing = std::path::PathBuf::from("/nonexistent/config.toml");
+        let layered = load_layers(&missing, &missing, vars(&[])).unwrap();
+        assert_eq!(layered.settings.tunnel.relays(), ["relay.nomadflowcode.dev"]);
+
+        let env = vars(&[(
+            "NOMADFLOW_TUNNEL_RELAY_HOSTS",
+            "relay2.example.com, relay3.example.com,,relay2.example.com",
+        )]);
+        let layered = load_layers(&missing, &missing, env).unwrap();
+        assert_eq!(
+            layered.settings.tunnel.relays(),
+            ["relay2.example.com", "relay3.example.com"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_env_override() {
+        let tmp = TempDir::new().unwrap();
+        let missing = tmp.path().join("missing.toml");
+        let result = load_layers(&missing, &missing, vars(&[("NOMADFLOW_API_PORT", "http")]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_save_leaves_system_and_env_values_out() {
+        let system: toml::Table = toml::from_str("[api]\nport = 9000\n").unwrap();
+        let env = vars(&[("NOMADFLOW_API_HOST", "127.0.0.1")]);
+        let mut settings = Settings::default();
+        settings.api.port = 9000;
+        settings.api.host = "127.0.0.1".to_string();
+        settings.tmux.session = "work".to_string();
+
+        let table = settings
+            .user_table(&toml::Table::new(), &system, env.clone())
+            .unwrap();
+        assert_eq!(lookup(&table, "api", "port"), None);
+        assert_eq!(lookup(&table, "api", "host"), Some(&"0.0.0.0".into()));
+        assert_eq!(lookup(&table, "tmux", "session"), Some(&"work".into()));
+
+        // Values the user set explicitly stay in their file
+        let user: toml::Table = toml::from_str("[api]\nport = 9000\nhost = \"::\"\n").unwrap();
+        let table = settings.user_table(&user, &system, env).unwrap();
+        assert_eq!(lookup(&table, "api", "port"), Some(&9000.into()));
+        assert_eq!(lookup(&table, "api", "host"), Some(&"::".into()));
+    }
+
+    #[test]
+    fn test_tls_identity() {
+        let mut settings = Settings::default();
+        settings.paths.base_dir = "/srv/nomad".to_string();
+        assert_eq!(settings.tls_identity(), None);
+
+        settings.tls.self_signed = true;
+        assert_eq!(
+            settings.tls_identity(),
+            Some((
+                PathBuf::from("/srv/nomad/tls/cert.pem"),
+                PathBuf::from("/srv/nomad/tls/key.pem"),
+            ))
+        );
+
+        // A certificate of one's own wins, with its key in the same file
+        settings.tls.cert = "/etc/nomad/both.pem".to_string();
+        assert_eq!(
+            settings.tls_identity(),
+            Some((
+                PathBuf::from("/etc/nomad/both.pem"),
+                PathBuf::from("/etc/nomad/both.pem"),
+            ))
+        );
+    }
+
     #[test]
     fn test_ensure_directories() {
         let tmp = TempDir::new().unwrap();
@@ -285,4 +1221,60 @@ secret = "s3cret"
         assert!(base.join("repos").exists());
         assert!(base.join("worktrees").exists());
     }
+
+    #[test]
+    fn test_secrets_encrypted_with_passphrase() {
+        let tmp = TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        settings.auth.secret = "s3cret".to_string();
+        settings.set_key_source(KeySource::Passphrase).unwrap();
+
+        std::env::remove_var(PASSPHRASE_ENV);
+        assert!(matches!(settings.save(), Err(NomadError::Secrets(_))));
+
+        std::env::set_var(PASSPHRASE_ENV, "correct horse");
+        settings.save().unwrap();
+        let raw = std::fs::read_to_string(settings.config_file()).unwrap();
+        assert!(!raw.contains("s3cret"));
+        assert!(raw.contains(crate::secrets::PREFIX));
+
+        let loaded = Settings::load(Some(&settings.config_file())).unwrap();
+        assert_eq!(loaded.auth.secret, "s3cret");
+        // Empty secrets stay empty
+        assert_eq!(loaded.auth.totp_secret, "");
+
+        std::env::set_var(PASSPHRASE_ENV, "wrong");
+        assert!(matches!(
+            Settings::load(Some(&settings.config_file())),
+            Err(NomadError::Secrets(_))
+        ));
+        std::env::remove_var(PASSPHRASE_ENV);
+
+        // Without the passphrase, the rest of the settings still load
+        let plain = Settings::load_plain(Some(&settings.config_file())).unwrap();
+        assert!(plain.auth.secret.starts_with(crate::secrets::PREFIX));
+        assert_eq!(plain.base_dir(), settings.base_dir());
+    }
+
+    #[test]
+    fn test_local_device_token_is_stable() {
+        let tmp = TempDir::new().unwrap();
+        let settings = Settings {
+            paths: PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        let token = settings.local_device_token().unwrap();
+        assert_eq!(token.len(), 64);
+        assert_eq!(settings.local_device_token().unwrap(), token);
+
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(tmp.path().join("local-device.token"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
 }
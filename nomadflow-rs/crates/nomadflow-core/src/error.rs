@@ -8,15 +8,24 @@ pub enum NomadError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Still in use: {0}")]
+    InUse(String),
+
     #[error("Command failed: {0}")]
     CommandFailed(String),
 
     #[error("Command timed out after {0}s")]
     Timeout(f64),
 
+    #[error("Invalid name: {0}")]
+    InvalidName(String),
+
     #[error("Configuration error: {0}")]
     Config(String),
 
+    #[error("Secrets error: {0}")]
+    Secrets(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
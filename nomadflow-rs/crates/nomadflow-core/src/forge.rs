@@ -0,0 +1,338 @@
+//! Pull requests and protected branches on the forge hosting a repository:
+//! GitHub (and GitHub Enterprise) or GitLab, told apart by the host of the remote.
+
+use std::time::Duration;
+
+use reqwest::RequestBuilder;
+use serde_json::{json, Value};
+
+use crate::error::{NomadError, Result};
+use crate::models::ForgeKind;
+
+const USER_AGENT: &str = concat!("nomadflow/", env!("CARGO_PKG_VERSION"));
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A repository on a forge, as named by a remote URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeRepo {
+    pub kind: ForgeKind,
+    pub host: String,
+    /// `owner/repo`, or `group/subgroup/project` on GitLab.
+    pub path: String,
+}
+
+/// What to open a pull request with.
+#[derive(Debug, Clone)]
+pub struct PullRequest<'a> {
+    pub head: &'a str,
+    pub base: &'a str,
+    pub title: &'a str,
+    pub body: &'a str,
+    pub draft: bool,
+}
+
+impl ForgeRepo {
+    /// The repository `url` points at, from its HTTPS, SSH or scp-like form.
+    /// `None` when the host is not a known forge.
+    pub fn from_remote_url(url: &str) -> Option<Self> {
+        let (host, path) = split_remote(url.trim())?;
+        let kind = if host.contains("github") {
+            ForgeKind::GitHub
+        } else if host.contains("gitlab") {
+            ForgeKind::GitLab
+        } else {
+            return None;
+        };
+        Some(Self { kind, host, path })
+    }
+
+    fn api_base(&self) -> String {
+        match self.kind {
+            ForgeKind::GitHub if self.host == "github.com" => "https://api.github.com".to_string(),
+            ForgeKind::GitHub => format!("https://{}/api/v3", self.host),
+            ForgeKind::GitLab => format!("https://{}/api/v4", self.host),
+        }
+    }
+
+    /// URL of the repository's API, under which `rest` is appended.
+    fn api_url(&self, rest: &str) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("{}/repos/{}/{rest}", self.api_base(), self.path),
+            ForgeKind::GitLab => format!(
+                "{}/projects/{}/{rest}",
+                self.api_base(),
+                self.path.replace('/', "%2F")
+            ),
+        }
+    }
+
+    /// `request` with the headers the forge expects, `token` included.
+    fn authorize(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        let request = match self.kind {
+            ForgeKind::GitHub => request
+                .bearer_auth(token)
+                .header("Accept", "application/vnd.github+json"),
+            ForgeKind::GitLab => request.header("PRIVATE-TOKEN", token),
+        };
+        request
+            .header("User-Agent", USER_AGENT)
+            .timeout(REQUEST_TIMEOUT)
+    }
+
+    /// Send `request`, returning the status and JSON body of the response.
+    async fn send(&self, request: RequestBuilder) -> Result<(u16, Value)> {
+        let response = request.send().await.map_err(|e| {
+            NomadError::CommandFailed(format!("Failed to reach {}: {e}", self.host))
+        })?;
+        let status = response.status().as_u16();
+        Ok((status, response.json().await.unwrap_or(Value::Null)))
+    }
+
+    /// Names of the protected branches, or the patterns protecting them on
+    /// GitLab (`release/*`). Only the first hundred are listed.
+    pub async fn protected_branches(&self, token: &str) -> Result<Vec<String>> {
+        let rest = match self.kind {
+            ForgeKind::GitHub => "branches?protected=true&per_page=100",
+            ForgeKind::GitLab => "protected_branches?per_page=100",
+        };
+        let request = reqwest::Client::new().get(self.api_url(rest));
+        let (status, body) = self.send(self.authorize(request, token)).await?;
+        if !(200..300).contains(&status) {
+            let message = error_message(&body).unwrap_or_else(|| format!("HTTP {status}"));
+            return Err(NomadError::CommandFailed(format!(
+                "{} refused to list protected branches: {message}",
+                self.host
+            )));
+        }
+        Ok(body
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|b| b["name"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    /// Open a pull request with `token`, and return its web page.
+    /// `AlreadyExists` when one is open for the branch already, `Config`
+    /// when the forge refuses the token.
+    pub async fn create_pull_request(&self, token: &str, pr: &PullRequest<'_>) -> Result<String> {
+        let (rest, url_field) = match self.kind {
+            ForgeKind::GitHub => ("pulls", "html_url"),
+            ForgeKind::GitLab => ("merge_requests", "web_url"),
+        };
+        let request = reqwest::Client::new()
+            .post(self.api_url(rest))
+            .json(&request_body(self.kind, pr));
+        let (status, body) = self.send(self.authorize(request, token)).await?;
+        if (200..300).contains(&status) {
+            return body[url_field].as_str().map(str::to_string).ok_or_else(|| {
+                NomadError::CommandFailed(format!("{} returned no pull request URL", self.host))
+            });
+        }
+        let message = error_message(&body).unwrap_or_else(|| format!("HTTP {status}"));
+        Err(match status {
+            401 | 403 => NomadError::Config(format!("{} refused the token: {message}", self.host)),
+            _ if message.contains("already exists") => NomadError::AlreadyExists(message),
+            _ => NomadError::CommandFailed(format!(
+                "{} refused the pull request: {message}",
+                self.host
+            )),
+        })
+    }
+}
+
+/// Host and repository path of a remote URL, credentials, port and `.git`
+/// left out.
+fn split_remote(url: &str) -> Option<(String, String)> {
+    let (authority, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        // scp-like: [user@]host:path
+        None => url.split_once(':')?,
+    };
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_lowercase();
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    (!host.is_empty() && path.contains('/')).then(|| (host, path.to_string()))
+}
+
+fn request_body(kind: ForgeKind, pr: &PullRequest<'_>) -> Value {
+    match kind {
+        ForgeKind::GitHub => json!({
+            "head": pr.head,
+            "base": pr.base,
+            "title": pr.title,
+            "body": pr.body,
+            "draft": pr.draft,
+        }),
+        // GitLab drafts are merge requests whose title says so
+        ForgeKind::GitLab => json!({
+            "source_branch": pr.head,
+            "target_branch": pr.base,
+            "title": if pr.draft {
+                format!("Draft: {}", pr.title)
+            } else {
+                pr.title.to_string()
+            },
+            "description": pr.body,
+        }),
+    }
+}
+
+/// What a forge said went wrong: the details GitHub lists under `errors`,
+/// or GitLab's `message`, a string or a list.
+fn error_message(body: &Value) -> Option<String> {
+    let details: Vec<&str> = body["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e["message"].as_str())
+        .collect();
+    if !details.is_empty() {
+        return Some(details.join("; "));
+    }
+    match &body["message"] {
+        Value::String(message) => Some(message.clone()),
+        Value::Array(messages) => Some(
+            messages
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join("; "),
+        ),
+        _ => None,
+    }
+}
+
+/// Whether `branch` is among `protected`, where `*` matches any run of
+/// characters.
+pub fn is_protected(protected: &[String], branch: &str) -> bool {
+    protected.iter().any(|pattern| glob_match(pattern, branch))
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == name;
+    };
+    let Some(name) = name.strip_prefix(prefix) else {
+        return false;
+    };
+    if rest.is_empty() {
+        return true;
+    }
+    // Try every position the star could stop at
+    name.char_indices()
+        .map(|(i, _)| i)
+        .chain([name.len()])
+        .any(|i| glob_match(rest, &name[i..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_remote_url() {
+        let repo = |url| ForgeRepo::from_remote_url(url).map(|r| (r.kind, r.host, r.path));
+        let github = |path: &str| {
+            Some((
+                ForgeKind::GitHub,
+                "github.com".to_string(),
+                path.to_string(),
+            ))
+        };
+
+        assert_eq!(
+            repo("git@github.com:fab/nomadflow.git"),
+            github("fab/nomadflow")
+        );
+        assert_eq!(
+            repo("https://github.com/fab/nomadflow"),
+            github("fab/nomadflow")
+        );
+        assert_eq!(
+            repo("https://x-access-token:t@GitHub.com/fab/nomadflow.git/"),
+            github("fab/nomadflow")
+        );
+        assert_eq!(
+            repo("ssh://git@github.com:22/fab/nomadflow.git"),
+            github("fab/nomadflow")
+        );
+        assert_eq!(
+            repo("git@gitlab.example.com:team/tools/nomadflow.git"),
+            Some((
+                ForgeKind::GitLab,
+                "gitlab.example.com".to_string(),
+                "team/tools/nomadflow".to_string()
+            ))
+        );
+        assert_eq!(repo("git@git.example.com:fab/nomadflow.git"), None);
+        assert_eq!(repo("/srv/git/nomadflow.git"), None);
+        assert_eq!(repo("https://github.com/nomadflow"), None);
+    }
+
+    #[test]
+    fn test_api_base() {
+        let base = |url| ForgeRepo::from_remote_url(url).unwrap().api_base();
+        assert_eq!(base("git@github.com:a/b"), "https://api.github.com");
+        assert_eq!(
+            base("git@github.corp.com:a/b"),
+            "https://github.corp.com/api/v3"
+        );
+        assert_eq!(base("git@gitlab.com:a/b"), "https://gitlab.com/api/v4");
+    }
+
+    #[test]
+    fn test_gitlab_drafts_are_marked_in_the_title() {
+        let pr = PullRequest {
+            head: "feature/login",
+            base: "main",
+            title: "Add login",
+            body: "",
+            draft: true,
+        };
+        assert_eq!(
+            request_body(ForgeKind::GitLab, &pr)["title"],
+            "Draft: Add login"
+        );
+        assert_eq!(request_body(ForgeKind::GitHub, &pr)["title"], "Add login");
+        assert_eq!(request_body(ForgeKind::GitHub, &pr)["draft"], true);
+    }
+
+    #[test]
+    fn test_is_protected() {
+        let protected = vec![
+            "main".to_string(),
+            "release/*".to_string(),
+            "*-stable".to_string(),
+        ];
+        assert!(is_protected(&protected, "main"));
+        assert!(is_protected(&protected, "release/1.2"));
+        assert!(is_protected(&protected, "v2-stable"));
+        assert!(!is_protected(&protected, "maintenance"));
+        assert!(!is_protected(&protected, "feature/release/1.2"));
+        assert!(!is_protected(&[], "main"));
+    }
+
+    #[test]
+    fn test_error_message() {
+        let github = json!({
+            "message": "Validation Failed",
+            "errors": [{ "message": "A pull request already exists for fab:login." }]
+        });
+        assert_eq!(
+            error_message(&github).unwrap(),
+            "A pull request already exists for fab:login."
+        );
+        let gitlab = json!({ "message": ["Another open merge request already exists"] });
+        assert_eq!(
+            error_message(&gitlab).unwrap(),
+            "Another open merge request already exists"
+        );
+        assert_eq!(
+            error_message(&json!({ "message": "Bad credentials" })).unwrap(),
+            "Bad credentials"
+        );
+        assert_eq!(error_message(&Value::Null), None);
+    }
+}
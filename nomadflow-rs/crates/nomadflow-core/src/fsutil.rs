@@ -0,0 +1,251 @@
+//! Crash-safe persistence of config and state files: writes go to a temp file
+//! that is synced and renamed over the target, the previous version is kept
+//! as `<file>.bak`, and readers fall back to it when the file is corrupt.
+//! Processes sharing a file serialize their read-modify-write cycles with
+//! [`lock`]. Server-side stores keep their state in a [`JsonStore`].
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Where a value returned by [`read_with_backup`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    File,
+    Backup,
+}
+
+/// `<path>.bak`, the previous version of a file written by [`write_atomic`].
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Replace `path` with `contents` so that a crash leaves either the old or the
+/// new file, never a truncated one. The old file is kept as `<path>.bak` and
+/// its permissions carried over to the new one.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mode = std::fs::metadata(path)
+        .map(|m| m.permissions().mode() & 0o7777)
+        .unwrap_or(0o644);
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".tmp{}", std::process::id()));
+    let tmp = dir.join(tmp_name);
+
+    let written = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(mode)
+            .open(&tmp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        if path.exists() {
+            std::fs::copy(path, backup_path(path))?;
+        }
+        std::fs::rename(&tmp, path)
+    })();
+    if written.is_err() {
+        std::fs::remove_file(&tmp).ok();
+    }
+    written?;
+
+    // Make the rename itself durable
+    File::open(dir)?.sync_all()
+}
+
+/// Exclusive advisory lock on a file, released when dropped.
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+/// Wait for the lock of `path`, held on a `<path>.lock` file next to it:
+/// [`write_atomic`] replaces `path` itself, so locking it would not exclude
+/// a process that opened the previous version.
+pub fn lock(path: &Path) -> io::Result<FileLock> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path.with_file_name(name))?;
+    file.lock()?;
+    Ok(FileLock { _file: file })
+}
+
+/// Read and `parse` the file at `path`, falling back to its `.bak` when it is
+/// unreadable or `parse` rejects it. `None` when the file does not exist, so
+/// that deleting a file still resets it.
+pub fn read_with_backup<T>(
+    path: &Path,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Option<(T, Source)> {
+    if !path.exists() {
+        return None;
+    }
+    if let Some(value) = std::fs::read_to_string(path).ok().and_then(|s| parse(&s)) {
+        return Some((value, Source::File));
+    }
+    let content = std::fs::read_to_string(backup_path(path)).ok()?;
+    parse(&content).map(|value| (value, Source::Backup))
+}
+
+/// Save `value` as JSON at `path` with [`write_atomic`]. Failures are only
+/// logged: the caller keeps its state in memory either way.
+pub fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            if let Err(e) = write_atomic(path, json) {
+                tracing::warn!("Failed to save {}: {e}", path.display());
+            }
+        }
+        Err(e) => tracing::warn!("Failed to serialize {}: {e}", path.display()),
+    }
+}
+
+/// A value kept in memory and saved as JSON to a file after each change.
+pub struct JsonStore<T> {
+    path: Option<PathBuf>,
+    value: Mutex<T>,
+}
+
+impl<T: Default + Serialize + DeserializeOwned> JsonStore<T> {
+    /// Load the store from `path`, or start empty if it is missing or unreadable.
+    pub fn load(path: PathBuf) -> Self {
+        let value = match read_with_backup(&path, |s| serde_json::from_str(s).ok()) {
+            Some((value, Source::Backup)) => {
+                tracing::warn!("{} is corrupt, restored from its backup", path.display());
+                value
+            }
+            Some((value, Source::File)) => value,
+            None if path.exists() => {
+                tracing::warn!("Ignoring invalid {}", path.display());
+                T::default()
+            }
+            None => T::default(),
+        };
+        Self {
+            path: Some(path),
+            value: Mutex::new(value),
+        }
+    }
+
+    /// A store that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            value: Mutex::new(T::default()),
+        }
+    }
+
+    /// The value, to read or change. Changes are kept by [`Self::persist`].
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.value.lock().unwrap()
+    }
+
+    /// Save `value`, the one behind the guard of [`Self::lock`].
+    pub fn persist(&self, value: &T) {
+        if let Some(path) = &self.path {
+            save_json(path, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn parse_number(s: &str) -> Option<u32> {
+        s.trim().parse().ok()
+    }
+
+    #[test]
+    fn test_write_atomic_keeps_backup_and_mode() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("state.json");
+
+        write_atomic(&path, "1").unwrap();
+        assert!(!backup_path(&path).exists());
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomic(&path, "2").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2");
+        assert_eq!(std::fs::read_to_string(backup_path(&path)).unwrap(), "1");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // No temp file left behind
+        let names: Vec<_> = std::fs::read_dir(tmp.path()).unwrap().flatten().collect();
+        assert_eq!(names.len(), 2);
+    }
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("state.json");
+        let held = lock(&path).unwrap();
+
+        let other = File::open(tmp.path().join("state.json.lock")).unwrap();
+        assert!(other.try_lock().is_err());
+        drop(held);
+        assert!(other.try_lock().is_ok());
+    }
+
+    #[test]
+    fn test_read_with_backup_recovers_corrupt_file() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("state.json");
+        assert_eq!(read_with_backup(&path, parse_number), None);
+
+        write_atomic(&path, "1").unwrap();
+        write_atomic(&path, "2").unwrap();
+        assert_eq!(read_with_backup(&path, parse_number), Some((2, Source::File)));
+
+        // Truncated by a crash of an older, non-atomic writer
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(
+            read_with_backup(&path, parse_number),
+            Some((1, Source::Backup))
+        );
+
+        // A deleted file is not resurrected from its backup
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read_with_backup(&path, parse_number), None);
+    }
+
+    #[test]
+    fn test_json_store() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("names.json");
+        let store = JsonStore::<Vec<String>>::load(path.clone());
+        assert!(store.lock().is_empty());
+
+        let mut names = store.lock();
+        names.push("login".to_string());
+        store.persist(&names);
+        drop(names);
+        assert_eq!(
+            *JsonStore::<Vec<String>>::load(path.clone()).lock(),
+            ["login"]
+        );
+
+        // Unreadable without a backup: start empty
+        std::fs::write(&path, "[").unwrap();
+        std::fs::remove_file(backup_path(&path)).ok();
+        assert!(JsonStore::<Vec<String>>::load(path).lock().is_empty());
+    }
+}
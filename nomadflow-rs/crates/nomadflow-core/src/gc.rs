@@ -0,0 +1,273 @@
+//! Disk cleanup behind `nomadflow gc` and `POST /api/gc`: directories of the
+//! worktrees directory that git no longer knows, and the crash reports, task
+//! runs and interrupted writes older than `gc.retention_days`.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::config::Settings;
+use crate::models::{GcEntry, GcKind, GcResponse};
+use crate::services::git::GitService;
+
+/// Age past which a temporary file is the leftover of an interrupted write
+/// rather than one in progress.
+const TEMP_FILE_AGE: Duration = Duration::from_secs(60 * 60);
+
+impl GcKind {
+    /// i18n key of the kind's label.
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::OrphanedWorktree => "gc.orphaned_worktree",
+            Self::CrashReport => "gc.crash_report",
+            Self::TaskRun => "gc.task_run",
+            Self::TempFile => "gc.temp_file",
+        }
+    }
+}
+
+pub fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Directories of `dir` that are none of the `registered` worktrees.
+pub fn unregistered_dirs(dir: &Path, registered: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() && !registered.contains(&canonical(p)))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Files of `dir` named like `matches` and last modified before `cutoff`.
+fn old_files(dir: &Path, cutoff: SystemTime, matches: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| matches(&e.file_name().to_string_lossy()))
+        .filter_map(|e| e.metadata().ok().map(|m| (e.path(), m)))
+        .filter(|(_, m)| m.is_file() && m.modified().is_ok_and(|t| t < cutoff))
+        .map(|(path, _)| path)
+        .collect();
+    files.sort();
+    files
+}
+
+/// Whether `name` is the temporary file of [`crate::fsutil::write_atomic`].
+fn is_temp_file(name: &str) -> bool {
+    name.rsplit_once(".tmp")
+        .is_some_and(|(_, pid)| !pid.is_empty() && pid.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Bytes taken by `path` and, for a directory, by what it holds. Symlinks
+/// are not followed.
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| disk_usage(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+/// `bytes` for people: `512 B`, `3.2 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// What there is to clean up.
+async fn garbage(settings: &Settings, git: &GitService) -> Vec<(GcKind, PathBuf)> {
+    let mut found = Vec::new();
+    let worktrees_dir = settings.worktrees_dir();
+    for repo in git.list_repos().await.unwrap_or_default() {
+        // A repo git cannot read may still own its worktrees
+        let Ok(registered) = git.registered_worktrees(&repo.path).await else {
+            continue;
+        };
+        let known: HashSet<PathBuf> = registered
+            .iter()
+            .map(|(path, _)| canonical(Path::new(path)))
+            .collect();
+        for dir in unregistered_dirs(&worktrees_dir.join(&repo.name), &known) {
+            found.push((GcKind::OrphanedWorktree, dir));
+        }
+    }
+
+    let base_dir = settings.base_dir();
+    let now = SystemTime::now();
+    let retention = Duration::from_secs(u64::from(settings.gc.retention_days) * 24 * 60 * 60);
+    if let Some(cutoff) = now.checked_sub(retention).filter(|_| !retention.is_zero()) {
+        for file in old_files(&base_dir.join("crashes"), cutoff, |_| true) {
+            found.push((GcKind::CrashReport, file));
+        }
+        let runs = old_files(&base_dir.join("task-runs"), cutoff, |n| {
+            n.ends_with(".json")
+        });
+        for file in runs {
+            found.push((GcKind::TaskRun, file));
+        }
+    }
+    let cutoff = now - TEMP_FILE_AGE;
+    for dir in [base_dir.clone(), base_dir.join("task-runs")] {
+        for file in old_files(&dir, cutoff, is_temp_file) {
+            found.push((GcKind::TempFile, file));
+        }
+    }
+    found
+}
+
+/// Remove what is no longer needed on disk, or with `dry_run` only list it,
+/// with the space it takes.
+pub async fn collect(settings: &Settings, git: &GitService, dry_run: bool) -> GcResponse {
+    let mut entries = Vec::new();
+    let mut reclaimed_bytes = 0;
+    for (kind, path) in garbage(settings, git).await {
+        let bytes = disk_usage(&path);
+        let error = if dry_run {
+            None
+        } else if kind == GcKind::OrphanedWorktree {
+            std::fs::remove_dir_all(&path).err()
+        } else {
+            std::fs::remove_file(&path).err()
+        };
+        if error.is_none() {
+            reclaimed_bytes += bytes;
+        }
+        entries.push(GcEntry {
+            kind,
+            path: path.to_string_lossy().to_string(),
+            bytes,
+            error: error.map(|e| e.to_string()),
+        });
+    }
+    GcResponse {
+        dry_run,
+        entries,
+        reclaimed_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::run;
+
+    #[test]
+    fn test_unregistered_dirs() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("known")).unwrap();
+        std::fs::create_dir(tmp.path().join("stray")).unwrap();
+        std::fs::write(tmp.path().join("notes.txt"), "").unwrap();
+
+        let registered = HashSet::from([canonical(&tmp.path().join("known"))]);
+        assert_eq!(
+            unregistered_dirs(tmp.path(), &registered),
+            vec![tmp.path().join("stray")]
+        );
+        assert!(unregistered_dirs(&tmp.path().join("missing"), &registered).is_empty());
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 + 200 * 1024), "3.2 MiB");
+    }
+
+    #[test]
+    fn test_is_temp_file() {
+        assert!(is_temp_file("tokens.json.tmp4242"));
+        assert!(!is_temp_file("tokens.json"));
+        assert!(!is_temp_file("notes.tmp"));
+        assert!(!is_temp_file("x.tmpfile"));
+    }
+
+    #[tokio::test]
+    async fn test_collect() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        settings.ensure_directories().unwrap();
+        let repo = settings.repos_dir().join("app");
+        std::fs::create_dir_all(&repo).unwrap();
+        let repo = repo.to_string_lossy().to_string();
+        let git = |cmd: &str| format!("git -c user.name=t -c user.email=t@t {cmd}");
+        assert!(run(&git("init -b main"), Some(&repo)).await.success());
+        assert!(run(&git("commit -q --allow-empty -m init"), Some(&repo))
+            .await
+            .success());
+
+        let worktrees = settings.worktrees_dir().join("app");
+        let live = worktrees.join("live");
+        let command = git(&format!("worktree add -q -b live {}", live.display()));
+        assert!(run(&command, Some(&repo)).await.success());
+        std::fs::create_dir_all(worktrees.join("stray/src")).unwrap();
+        std::fs::write(worktrees.join("stray/src/main.rs"), "fn main() {}").unwrap();
+
+        // Only files older than the retention go
+        let old = SystemTime::now() - Duration::from_secs(40 * 24 * 60 * 60);
+        let crashes = tmp.path().join("crashes");
+        std::fs::create_dir_all(&crashes).unwrap();
+        for name in ["old.txt", "new.txt"] {
+            std::fs::write(crashes.join(name), "panic").unwrap();
+        }
+        let file = std::fs::File::options()
+            .write(true)
+            .open(crashes.join("old.txt"))
+            .unwrap();
+        file.set_modified(old).unwrap();
+        let temp = tmp.path().join("tokens.json.tmp123");
+        std::fs::write(&temp, "{}").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&temp)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let svc = GitService::new(&settings);
+        let report = collect(&settings, &svc, true).await;
+        let found: Vec<(GcKind, &str)> = report
+            .entries
+            .iter()
+            .map(|e| (e.kind, e.path.rsplit('/').next().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (GcKind::OrphanedWorktree, "stray"),
+                (GcKind::CrashReport, "old.txt"),
+                (GcKind::TempFile, "tokens.json.tmp123"),
+            ]
+        );
+        assert_eq!(report.reclaimed_bytes, 12 + 5 + 2);
+        assert!(worktrees.join("stray").exists());
+
+        let report = collect(&settings, &svc, false).await;
+        assert!(report.entries.iter().all(|e| e.error.is_none()));
+        assert!(!worktrees.join("stray").exists() && live.exists());
+        assert!(!crashes.join("old.txt").exists() && crashes.join("new.txt").exists());
+        assert!(!temp.exists());
+        assert!(collect(&settings, &svc, false).await.entries.is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+//! Commands run around a feature's life (`npm install`, `direnv allow`…),
+//! defined per repo under `[hooks]` in `.nomadflow.toml`, read from the
+//! feature's worktree, or in the settings.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::config::HooksConfig;
+use crate::error::{NomadError, Result};
+use crate::tasks::REPO_CONFIG;
+
+/// When a hook runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// The feature's worktree was just created.
+    PostCreate,
+    /// The feature was switched to.
+    PostSwitch,
+    /// The feature is about to be deleted.
+    PreDelete,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RepoHooks {
+    post_create: Vec<String>,
+    post_switch: Vec<String>,
+    pre_delete: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RepoConfig {
+    #[serde(default)]
+    hooks: RepoHooks,
+}
+
+fn commands<'a>(
+    event: HookEvent,
+    post_create: &'a [String],
+    post_switch: &'a [String],
+    pre_delete: &'a [String],
+) -> &'a [String] {
+    match event {
+        HookEvent::PostCreate => post_create,
+        HookEvent::PostSwitch => post_switch,
+        HookEvent::PreDelete => pre_delete,
+    }
+}
+
+fn load_repo_hooks(worktree: &Path) -> Result<RepoHooks> {
+    let path = worktree.join(REPO_CONFIG);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(RepoHooks::default()),
+        Err(e) => return Err(e.into()),
+    };
+    toml::from_str::<RepoConfig>(&content)
+        .map(|config| config.hooks)
+        .map_err(|e| NomadError::Config(format!("Failed to parse {}: {e}", path.display())))
+}
+
+/// The commands of `event` for the feature checked out in `worktree`, joined
+/// to stop at the first failure. The repo's hooks win over the settings'
+/// when it defines any for `event`. `None` when there is nothing to run.
+pub fn hook_command(
+    settings: &HooksConfig,
+    worktree: &Path,
+    event: HookEvent,
+) -> Result<Option<String>> {
+    let repo = if settings.repo_hooks {
+        load_repo_hooks(worktree)?
+    } else {
+        RepoHooks::default()
+    };
+    let repo = commands(
+        event,
+        &repo.post_create,
+        &repo.post_switch,
+        &repo.pre_delete,
+    );
+    let list = if repo.is_empty() {
+        commands(
+            event,
+            &settings.post_create,
+            &settings.post_switch,
+            &settings.pre_delete,
+        )
+    } else {
+        repo
+    };
+    let list: Vec<&str> = list
+        .iter()
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+        .collect();
+    Ok((!list.is_empty()).then(|| list.join(" && ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_hooks_win_event_by_event() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            tmp.path().join(REPO_CONFIG),
+            "[hooks]\npost_create = [\"npm install\", \"direnv allow\"]\n",
+        )
+        .unwrap();
+        let mut settings = HooksConfig {
+            post_create: vec!["make setup".to_string()],
+            post_switch: vec!["git fetch".to_string()],
+            repo_hooks: true,
+            ..Default::default()
+        };
+
+        let command =
+            |settings: &HooksConfig, event| hook_command(settings, tmp.path(), event).unwrap();
+        assert_eq!(
+            command(&settings, HookEvent::PostCreate).as_deref(),
+            Some("npm install && direnv allow")
+        );
+        assert_eq!(
+            command(&settings, HookEvent::PostSwitch).as_deref(),
+            Some("git fetch")
+        );
+        assert_eq!(command(&settings, HookEvent::PreDelete), None);
+
+        settings.repo_hooks = false;
+        assert_eq!(
+            command(&settings, HookEvent::PostCreate).as_deref(),
+            Some("make setup")
+        );
+    }
+
+    #[test]
+    fn test_missing_and_invalid_repo_config() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = HooksConfig {
+            repo_hooks: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            hook_command(&settings, tmp.path(), HookEvent::PostCreate).unwrap(),
+            None
+        );
+
+        std::fs::write(tmp.path().join(REPO_CONFIG), "[hooks]\npost_create = 1\n").unwrap();
+        assert!(hook_command(&settings, tmp.path(), HookEvent::PostCreate).is_err());
+        // Not even read unless enabled
+        settings.repo_hooks = false;
+        assert_eq!(
+            hook_command(&settings, tmp.path(), HookEvent::PostCreate).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,1057 @@
+//! Minimal message catalogs for user-facing CLI/TUI/server strings.
+//!
+//! Messages are looked up by key in the active locale, falling back to English.
+//! Placeholders use `{name}` and are filled by [`tf`].
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    Fr,
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+impl Locale {
+    /// Parse a locale tag such as `fr`, `fr_FR.UTF-8` or `en-US`.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let lang = tag
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match lang.as_str() {
+            "en" => Some(Locale::En),
+            "fr" => Some(Locale::Fr),
+            _ => None,
+        }
+    }
+
+    /// Resolve the locale from the configured value, or from the environment
+    /// (`LC_ALL`, `LC_MESSAGES`, `LANG`) when it is empty or unknown.
+    pub fn detect(configured: &str) -> Self {
+        if let Some(locale) = Self::from_tag(configured) {
+            return locale;
+        }
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty())
+            .and_then(|v| Self::from_tag(&v))
+            .unwrap_or(Locale::En)
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => EN,
+            Locale::Fr => FR,
+        }
+    }
+}
+
+/// Set the locale used by [`t`] and [`tf`] for the rest of the process.
+pub fn set_locale(locale: Locale) {
+    CURRENT.store(locale as u8, Ordering::Relaxed);
+}
+
+pub fn locale() -> Locale {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Locale::Fr,
+        _ => Locale::En,
+    }
+}
+
+fn lookup(locale: Locale, key: &'static str) -> &'static str {
+    let find = |catalog: &'static [(&'static str, &'static str)]| {
+        catalog.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    };
+    find(locale.catalog()).or_else(|| find(EN)).unwrap_or(key)
+}
+
+/// Translate `key` in the active locale.
+pub fn t(key: &'static str) -> &'static str {
+    lookup(locale(), key)
+}
+
+/// Translate `key` and replace its `{name}` placeholders.
+pub fn tf(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = t(key).to_string();
+    for (name, value) in args {
+        out = out.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    out
+}
+
+const EN: &[(&str, &str)] = &[
+    // Common
+    ("common.error", "Error: {err}"),
+    ("common.press_esc", "Press Escape to go back"),
+    ("common.press_q", "Press q to quit"),
+    ("common.yes", "yes"),
+    ("common.no", "no"),
+    // Footer hints
+    ("footer.back", "Escape: back"),
+    ("footer.back_quit", "Escape: back  q: quit"),
+    ("footer.cancel", "Escape: cancel"),
+    ("footer.batch_confirm", "y: confirm  n/Escape: cancel"),
+    (
+        "footer.marked",
+        "Space: mark  d: delete  s: sync  p: prune  Escape: clear marks",
+    ),
+    (
+        "footer.features",
+        "Space: mark  t: tasks  n: notes  Escape: back  q: quit",
+    ),
+    ("footer.repos", "/: search  Escape: back  q: quit"),
+    (
+        "footer.repos_tips",
+        "/: search  x: dismiss tips  Escape: back  q: quit",
+    ),
+    (
+        "footer.search",
+        "Enter: search, then open  Up/Down: navigate  Escape: back",
+    ),
+    (
+        "footer.tasks",
+        "Enter: run  b: run in background  Escape: back",
+    ),
+    (
+        "footer.picker",
+        "Up/Down: navigate  Enter: select  Esc: cancel",
+    ),
+    // Attaching
+    ("attaching.preparing", "Preparing tmux session..."),
+    ("spinner.elapsed", "{secs}s"),
+    ("attaching.timeout", "The server did not finish switching within {secs}s"),
+    // Server picker / add
+    ("servers.checking", "Checking servers..."),
+    ("servers.title", "Select a server:"),
+    ("servers.no_url", "no url"),
+    ("servers.add", "+ Add server"),
+    ("servers.save_failed", "Could not save the server list: {err}"),
+    (
+        "servers.keychain_failed",
+        "Could not read the token of {server} from the keychain: {err}",
+    ),
+    ("state.save_failed", "Could not save the last session: {err}"),
+    (
+        "pin.changed",
+        "The TLS certificate of {server} changed (now {fingerprint}). Check it was renewed before trusting this connection.",
+    ),
+    // Latency
+    ("latency.ms", "{ms} ms"),
+    ("latency.unreachable", "unreachable"),
+    ("header.inside", "in {window}"),
+    (
+        "latency.warning",
+        "High latency: the terminal will lag, consider mosh or SSH",
+    ),
+    // Reconciliation
+    ("reconcile.banner", "Fix issues ({count}) — press f"),
+    // Tips
+    (
+        "tips.no_terminal",
+        "ttyd is not installed — the terminal won't open from a phone",
+    ),
+    (
+        "tips.no_secret",
+        "No secret set — anyone reaching the server can use it",
+    ),
+    (
+        "tips.merged_features",
+        "{count} feature(s) with a merged pull request — prune them?",
+    ),
+    ("tips.save_failed", "Could not remember the dismissed tips: {err}"),
+    // Offline mode
+    ("offline.badge", "server down — local mode"),
+    (
+        "offline.notice",
+        "Server unreachable: running operations locally",
+    ),
+    ("server_add.title", "Add a new server:"),
+    ("server_add.name", "Server name: "),
+    ("server_add.url", "API URL: "),
+    ("server_add.token", "Auth token: "),
+    ("server_add.token_hint", "(optional, Enter to skip)"),
+    ("server_add.confirm", "Add server {name} at {url}? (y/n)"),
+    // Repo picker
+    ("repos.loading", "Loading repos..."),
+    ("repos.empty", "No repositories found."),
+    (
+        "repos.empty_hint",
+        "Clone a repo via the mobile app or server first.",
+    ),
+    ("repos.title", "Select a repo ({server}):"),
+    ("repos.last_used", "  (last used)"),
+    // Feature picker
+    ("features.loading", "Loading features..."),
+    ("features.title", "Select a feature ({repo}):"),
+    (
+        "features.title_marked",
+        "Select a feature ({repo}) — {count} marked:",
+    ),
+    ("features.idle", "idle"),
+    ("features.running", "{cmd} running"),
+    ("features.new_output", "new output"),
+    ("features.dirty", "uncommitted"),
+    ("features.needs_resolution", "needs resolution"),
+    ("features.source", "[source]"),
+    ("features.create", "+ Create a feature"),
+    ("features.notes", "Notes"),
+    ("features.note_loading", "Loading note..."),
+    ("features.no_note", "No note for this feature."),
+    // Task menu
+    ("tasks.loading", "Loading tasks..."),
+    ("tasks.title", "Run a task in {feature}:"),
+    ("tasks.empty", "No tasks defined."),
+    ("tasks.empty_hint", "Add a [tasks] table to the repo's .nomadflow.toml."),
+    ("tasks.background", "[background]"),
+    ("tasks.started_window", "{task} started in {window}"),
+    ("tasks.started_job", "{task} running in the background (run {id})"),
+    // Search
+    ("search.title", "Search all repos ({server}):"),
+    ("search.label", "Find: "),
+    ("search.placeholder", "text in any feature"),
+    ("search.searching", "Searching..."),
+    ("search.empty", "No matches."),
+    ("search.truncated", "Only the first {n} matches are shown."),
+    // Desktop notifications
+    ("notify.pane_idle", "{window} is quiet"),
+    ("notify.pane_idle_body", "No output for a while: it may be waiting for input."),
+    ("notify.pane_died", "{window} exited"),
+    ("notify.task_ok", "Task {task} succeeded"),
+    ("notify.task_failed", "Task {task} failed"),
+    ("notify.task_body", "{feature}: {status}"),
+    ("notify.exited", "exited with {code}"),
+    ("notify.killed", "killed"),
+    ("notify.tunnel_drop", "Tunnel down"),
+    ("notify.tunnel_drop_body", "{server} lost its connection to the relay."),
+    ("notify.tunnel_back", "Tunnel back up"),
+    ("batch.delete_title", "Delete {count} feature(s)?"),
+    (
+        "batch.delete_impact",
+        "Removes {count} worktree(s) and tmux window(s), deletes branches:",
+    ),
+    ("batch.prune_title", "Prune {count} feature(s)?"),
+    (
+        "batch.prune_impact",
+        "Removes {count} worktree(s) and tmux window(s), keeps branches:",
+    ),
+    ("batch.sync_title", "Sync {count} feature(s)?"),
+    (
+        "batch.sync_impact",
+        "Fast-forwards each worktree from its upstream branch:",
+    ),
+    ("batch.busy", "Running processes will be killed in: {names}"),
+    ("batch.continue", "Continue? (y/n)"),
+    ("batch.result", "{ok}/{total} succeeded"),
+    // Feature create
+    ("create.title", "Create a new feature ({repo}):"),
+    ("create.label", "Feature name: "),
+    ("create.confirm", "Create feature {name} in {repo}? (y/n)"),
+    ("create.suggest", "Tab: use {name}"),
+    ("create.from_description", "Enter: create {name}  Tab: edit the name"),
+    ("create.try", "Try: {names}"),
+    ("create.protected", "{name} is protected on the forge: work on a feature branch instead"),
+    // Branch names
+    ("branch.empty", "the name is empty"),
+    ("branch.too_long", "the name is longer than {max} characters"),
+    ("branch.invalid_char", "{char} is not allowed (letters, digits, - _ . / + only)"),
+    ("branch.leading_dash", "the name cannot start with -"),
+    ("branch.double_dot", "the name cannot contain .."),
+    ("branch.empty_segment", "the name cannot start or end with /, or contain //"),
+    ("branch.hidden_segment", "a part of the name cannot start with . or end with .lock"),
+    ("branch.window_dot", "the part after the last / names the tmux window and cannot contain ."),
+    // Resume
+    ("resume.title", "Resume previous session?"),
+    ("resume.last", "Last session: {label} on {server}"),
+    ("resume.jump", "Jump to where you left off: {label} ({state})"),
+    ("resume.yes", "Yes, attach tmux session"),
+    ("resume.no", "No, choose another session"),
+    // History bar
+    ("history.last", "Last: "),
+    ("history.undo", "u: undo"),
+    ("history.created", "created {name}"),
+    ("history.deleted", "deleted {name}"),
+    ("history.pruned", "pruned {name}"),
+    ("history.switched", "switched to {name}"),
+    ("history.attached", "attached {name}"),
+    ("history.undid", "undid change to {name}"),
+    ("history.banned", "banned {name}"),
+    ("history.unbanned", "unbanned {name}"),
+    ("history.ran_task", "ran a task in {name}"),
+    ("history.task_failed", "a task failed in {name}"),
+    ("history.stopped_process", "stopped a process in {name}"),
+    ("history.just_now", "just now"),
+    ("history.minutes", "{n}m ago"),
+    ("history.hours", "{n}h ago"),
+    ("history.days", "{n}d ago"),
+    // Setup wizard
+    ("setup.welcome", "Welcome to NomadFlow!"),
+    (
+        "setup.password_prompt",
+        "Set a password to secure your server:",
+    ),
+    ("setup.generate", "Generate a password (recommended)"),
+    ("setup.custom", "Enter my own password"),
+    ("setup.enter_password", "Enter your password:"),
+    ("setup.password_label", "Password: "),
+    (
+        "setup.public_title",
+        "Will you use public tunnel mode? (y/n)",
+    ),
+    (
+        "setup.public_hint",
+        "This exposes your server over the internet via a tunnel URL.",
+    ),
+    (
+        "setup.subdomain_title",
+        "Use a fixed subdomain for a stable public URL? (y/n)",
+    ),
+    ("setup.subdomain_label", "  Your subdomain: "),
+    (
+        "setup.subdomain_hint",
+        "y: use this fixed subdomain  n: random URL each time",
+    ),
+    ("setup.summary", "Configuration summary:"),
+    ("setup.summary_password", "  Password: "),
+    ("setup.summary_public", "  Public mode: "),
+    ("setup.summary_subdomain", "  Subdomain: "),
+    ("setup.random", "(random)"),
+    ("setup.summary_config", "  Config: "),
+    ("setup.save", "Save and continue? (y/n)"),
+    ("setup.save_failed", "Failed to save config: {err}"),
+    // Status
+    ("status.session", "Session: {session}"),
+    ("status.no_session", "No active session"),
+    ("status.windows", "{count} window(s)"),
+    // CLI
+    (
+        "cli.daemon_already_running",
+        "NomadFlow daemon already running (PID {pid})",
+    ),
+    ("cli.daemon_started", "NomadFlow daemon started (PID {pid})"),
+    ("cli.logs", "Logs: {path}"),
+    ("cli.no_pid_file", "No PID file found — daemon not running"),
+    (
+        "cli.stale_pid",
+        "Process {pid} not running, removing stale PID file",
+    ),
+    ("cli.stopping", "Stopping NomadFlow daemon (PID {pid})…"),
+    ("cli.sigkill", "Process did not exit, sending SIGKILL…"),
+    ("cli.stopped", "NomadFlow daemon stopped"),
+    (
+        "cli.status_running",
+        "NomadFlow daemon: running (PID {pid})",
+    ),
+    (
+        "cli.status_stale",
+        "NomadFlow daemon: not running (stale PID file)",
+    ),
+    ("cli.status_stopped", "NomadFlow daemon: not running"),
+    ("cli.status_api_down", "The daemon's API does not answer"),
+    ("cli.linked", "Linked {name} -> {path}"),
+    ("cli.no_links", "No linked repositories found."),
+    ("cli.unlink_title", "Unlink a repository:"),
+    (
+        "cli.remove_worktrees",
+        "Remove {count} worktree(s)? ({names})",
+    ),
+    ("cli.removed_worktree", "Removed worktree {name}"),
+    (
+        "cli.removed_worktree_dir",
+        "Removed worktree directory {name}",
+    ),
+    ("cli.unlinked", "Unlinked {name}"),
+    ("cli.attach_title", "Attach to window:"),
+    ("cli.gc_nothing", "Nothing to clean up"),
+    ("cli.gc_would_reclaim", "Would reclaim {size} (dry run)"),
+    ("cli.gc_reclaimed", "Reclaimed {size}"),
+    ("gc.orphaned_worktree", "orphaned worktree"),
+    ("gc.crash_report", "crash report"),
+    ("gc.task_run", "task run"),
+    ("gc.temp_file", "temporary file"),
+    (
+        "migrate.done",
+        "Updated the NomadFlow files to layout version {version} (backup in {backup})",
+    ),
+    (
+        "remote.unknown_server",
+        "Unknown server '{server}'. Add it from the TUI first.",
+    ),
+    (
+        "remote.bad_target",
+        "Invalid target '{target}': expected repo:feature",
+    ),
+    ("remote.unknown_repo", "No repository named '{repo}' on this server"),
+    (
+        "remote.connected",
+        "Connected to {server} — press Ctrl-] to detach",
+    ),
+    ("remote.detached", "Detached."),
+    (
+        "new.not_in_repo",
+        "Not inside a repository known to NomadFlow. Link it first with `nomadflow link <path>`.",
+    ),
+    ("new.creating", "Creating {feature} in {repo}…"),
+    ("open.title", "Features matching '{query}':"),
+    ("cd.no_match", "No feature matches '{query}'"),
+    (
+        "cd.hint",
+        "A program cannot change your shell's directory. To make `nomadflow cd` do it, add this to your shell's rc file: eval \"$(nomadflow shell-init bash)\" (or zsh, or fish: nomadflow shell-init fish | source)",
+    ),
+    (
+        "open.create",
+        "No feature matches '{feature}'. Create it?",
+    ),
+    // Init
+    (
+        "init.invalid_subdomain",
+        "Invalid subdomain '{subdomain}': use 3-32 letters, digits or hyphens",
+    ),
+    ("init.empty_secret", "The secret must not be empty"),
+    ("init.saved", "Config written to {path}"),
+    (
+        "init.exists",
+        "A config already exists at {path}. Overwrite it?",
+    ),
+    (
+        "init.exists_abort",
+        "Config already exists at {path} (use --force to overwrite)",
+    ),
+    // TOTP
+    (
+        "totp.scan",
+        "Scan this QR code with your authenticator app:",
+    ),
+    ("totp.manual", "or enter the secret manually: {secret}"),
+    ("totp.code_prompt", "Code shown by the app: "),
+    ("totp.bad_code", "Invalid code, try again."),
+    ("totp.too_many", "Too many invalid codes, TOTP was not enabled"),
+    (
+        "totp.already_enabled",
+        "TOTP is already enabled (use --force to replace the secret)",
+    ),
+    (
+        "totp.needs_secret",
+        "TOTP requires a server password: set auth.secret first (nomadflow init)",
+    ),
+    ("totp.enabled", "TOTP enabled."),
+    ("totp.disabled", "TOTP disabled and remembered devices forgotten."),
+    (
+        "totp.restart",
+        "Restart the server for the change to take effect.",
+    ),
+    (
+        "totp.status_enabled",
+        "TOTP: enabled ({count} remembered device(s))",
+    ),
+    ("totp.status_disabled", "TOTP: disabled"),
+    // Secrets encryption
+    (
+        "secrets.encrypted_keychain",
+        "Secrets encrypted with a key stored in the OS keychain.",
+    ),
+    (
+        "secrets.encrypted_passphrase",
+        "Secrets encrypted with a key derived from {env}. Set it every time NomadFlow starts.",
+    ),
+    ("secrets.decrypted", "Secrets are now stored in plain text."),
+    ("secrets.status_none", "Secrets: stored in plain text"),
+    ("secrets.status_keychain", "Secrets: encrypted, key in the OS keychain"),
+    ("secrets.status_passphrase", "Secrets: encrypted, key derived from {env}"),
+    // Connectivity self-test
+    ("nettest.running", "Testing connectivity…"),
+    ("nettest.local_api", "Local API"),
+    ("nettest.ttyd", "ttyd WebSocket"),
+    ("nettest.relay_api", "Relay API"),
+    ("nettest.bore", "Bore control port"),
+    ("nettest.public_url", "Public URL"),
+    ("nettest.skip_no_server", "skipped: the local server is not running"),
+    ("nettest.skip_not_public", "skipped: the server was not started with --public"),
+    ("nettest.skip_native_terminal", "skipped: the server attaches tmux itself"),
+    ("nettest.all_passed", "All checks passed."),
+    ("nettest.some_failed", "{count} check(s) failed."),
+    ("check.running", "Checking the API of {server}…"),
+    ("check.health", "Health"),
+    ("check.auth", "Auth required"),
+    ("check.list_repos", "List repos"),
+    ("check.feature", "Feature create/delete"),
+    ("check.terminal", "Terminal WebSocket"),
+    ("check.skip_no_token", "skipped: no token configured for this server"),
+    ("check.skip_no_repo", "skipped: pass --repo to create and delete a scratch feature"),
+    ("check.skip_no_repos", "skipped: the repos could not be listed"),
+    // Plain mode
+    ("plain.choice", "Enter a number (empty to cancel): "),
+    ("plain.invalid", "Invalid choice, enter a number between 1 and {max}."),
+    ("plain.yes_no", "[y/N] "),
+    // Connection info (server)
+    ("connect.ready", "NomadFlow Server Ready"),
+    ("connect.scan", "Scan this QR code from the app"),
+    ("connect.manual", "or enter manually:"),
+    ("connect.open_app", "Open in the NomadFlow app"),
+    ("connect.qr_failed", "[QR code generation failed]"),
+    ("connect.qr_image", "QR image : {path}"),
+    (
+        "connect.qr_image_failed",
+        "QR image : failed to write {path} ({err})",
+    ),
+    (
+        "connect.tunnel_beta",
+        "Public tunnel provided by fab_uleuh — free during beta.",
+    ),
+    (
+        "connect.tunnel_paid",
+        "This may become a paid option in the future.",
+    ),
+    (
+        "connect.tunnel_selfhost",
+        "You can always self-host via VPN or your own relay.",
+    ),
+];
+
+const FR: &[(&str, &str)] = &[
+    // Common
+    ("common.error", "Erreur : {err}"),
+    ("common.press_esc", "Appuyez sur Échap pour revenir"),
+    ("common.press_q", "Appuyez sur q pour quitter"),
+    ("common.yes", "oui"),
+    ("common.no", "non"),
+    // Footer hints
+    ("footer.back", "Échap : retour"),
+    ("footer.back_quit", "Échap : retour  q : quitter"),
+    ("footer.cancel", "Échap : annuler"),
+    ("footer.batch_confirm", "y : confirmer  n/Échap : annuler"),
+    (
+        "footer.marked",
+        "Espace : marquer  d : supprimer  s : synchroniser  p : élaguer  Échap : démarquer",
+    ),
+    (
+        "footer.features",
+        "Espace : marquer  t : tâches  n : notes  Échap : retour  q : quitter",
+    ),
+    ("footer.repos", "/ : rechercher  Échap : retour  q : quitter"),
+    (
+        "footer.repos_tips",
+        "/ : rechercher  x : masquer les conseils  Échap : retour  q : quitter",
+    ),
+    (
+        "footer.search",
+        "Entrée : rechercher, puis ouvrir  Haut/Bas : naviguer  Échap : retour",
+    ),
+    (
+        "footer.tasks",
+        "Entrée : lancer  b : lancer en arrière-plan  Échap : retour",
+    ),
+    (
+        "footer.picker",
+        "Haut/Bas : naviguer  Entrée : choisir  Échap : annuler",
+    ),
+    // Attaching
+    ("attaching.preparing", "Préparation de la session tmux..."),
+    ("spinner.elapsed", "{secs} s"),
+    ("attaching.timeout", "Le serveur n'a pas terminé le changement en {secs} s"),
+    // Server picker / add
+    ("servers.checking", "Vérification des serveurs..."),
+    ("servers.title", "Choisissez un serveur :"),
+    ("servers.no_url", "pas d'URL"),
+    ("servers.add", "+ Ajouter un serveur"),
+    ("servers.save_failed", "Impossible d'enregistrer la liste des serveurs : {err}"),
+    (
+        "servers.keychain_failed",
+        "Impossible de lire le jeton de {server} dans le trousseau : {err}",
+    ),
+    ("state.save_failed", "Impossible d'enregistrer la dernière session : {err}"),
+    (
+        "pin.changed",
+        "Le certificat TLS de {server} a changé (désormais {fingerprint}). Vérifiez qu'il a été renouvelé avant de faire confiance à cette connexion.",
+    ),
+    // Latence
+    ("latency.ms", "{ms} ms"),
+    ("latency.unreachable", "injoignable"),
+    ("header.inside", "dans {window}"),
+    (
+        "latency.warning",
+        "Latence élevée : le terminal va ramer, essayez mosh ou SSH",
+    ),
+    // Réconciliation
+    ("reconcile.banner", "Corriger les problèmes ({count}) — appuyez sur f"),
+    // Tips
+    (
+        "tips.no_terminal",
+        "ttyd n'est pas installé — le terminal ne s'ouvrira pas depuis un téléphone",
+    ),
+    (
+        "tips.no_secret",
+        "Aucun secret défini — quiconque atteint le serveur peut l'utiliser",
+    ),
+    (
+        "tips.merged_features",
+        "{count} feature(s) avec une pull request fusionnée — les élaguer ?",
+    ),
+    (
+        "tips.save_failed",
+        "Impossible de retenir les conseils masqués : {err}",
+    ),
+    // Mode hors ligne
+    ("offline.badge", "serveur arrêté — mode local"),
+    (
+        "offline.notice",
+        "Serveur injoignable : opérations exécutées localement",
+    ),
+    ("server_add.title", "Ajouter un serveur :"),
+    ("server_add.name", "Nom du serveur : "),
+    ("server_add.url", "URL de l'API : "),
+    ("server_add.token", "Jeton d'accès : "),
+    ("server_add.token_hint", "(facultatif, Entrée pour passer)"),
+    (
+        "server_add.confirm",
+        "Ajouter le serveur {name} à l'adresse {url} ? (y/n)",
+    ),
+    // Repo picker
+    ("repos.loading", "Chargement des dépôts..."),
+    ("repos.empty", "Aucun dépôt trouvé."),
+    (
+        "repos.empty_hint",
+        "Clonez d'abord un dépôt depuis l'app mobile ou le serveur.",
+    ),
+    ("repos.title", "Choisissez un dépôt ({server}) :"),
+    ("repos.last_used", "  (dernier utilisé)"),
+    // Feature picker
+    ("features.loading", "Chargement des features..."),
+    ("features.title", "Choisissez une feature ({repo}) :"),
+    (
+        "features.title_marked",
+        "Choisissez une feature ({repo}) — {count} marquée(s) :",
+    ),
+    ("features.idle", "inactif"),
+    ("features.running", "{cmd} en cours"),
+    ("features.new_output", "nouvelle sortie"),
+    ("features.dirty", "non commité"),
+    ("features.needs_resolution", "conflits à résoudre"),
+    ("features.source", "[source]"),
+    ("features.create", "+ Créer une feature"),
+    ("features.notes", "Notes"),
+    ("features.note_loading", "Chargement de la note..."),
+    ("features.no_note", "Aucune note pour cette feature."),
+    // Task menu
+    ("tasks.loading", "Chargement des tâches..."),
+    ("tasks.title", "Lancer une tâche dans {feature} :"),
+    ("tasks.empty", "Aucune tâche définie."),
+    ("tasks.empty_hint", "Ajoutez une table [tasks] au .nomadflow.toml du dépôt."),
+    ("tasks.background", "[arrière-plan]"),
+    ("tasks.started_window", "{task} lancée dans {window}"),
+    ("tasks.started_job", "{task} lancée en arrière-plan (exécution {id})"),
+    // Search
+    ("search.title", "Rechercher dans tous les dépôts ({server}) :"),
+    ("search.label", "Chercher : "),
+    ("search.placeholder", "du texte dans une feature"),
+    ("search.searching", "Recherche..."),
+    ("search.empty", "Aucun résultat."),
+    ("search.truncated", "Seuls les {n} premiers résultats sont affichés."),
+    // Desktop notifications
+    ("notify.pane_idle", "{window} est silencieuse"),
+    (
+        "notify.pane_idle_body",
+        "Aucune sortie depuis un moment : elle attend peut-être une saisie.",
+    ),
+    ("notify.pane_died", "{window} s'est terminée"),
+    ("notify.task_ok", "Tâche {task} réussie"),
+    ("notify.task_failed", "Tâche {task} en échec"),
+    ("notify.task_body", "{feature} : {status}"),
+    ("notify.exited", "code de sortie {code}"),
+    ("notify.killed", "interrompue"),
+    ("notify.tunnel_drop", "Tunnel coupé"),
+    ("notify.tunnel_drop_body", "{server} a perdu sa connexion au relais."),
+    ("notify.tunnel_back", "Tunnel rétabli"),
+    ("batch.delete_title", "Supprimer {count} feature(s) ?"),
+    (
+        "batch.delete_impact",
+        "Supprime {count} worktree(s) et fenêtre(s) tmux, ainsi que les branches :",
+    ),
+    ("batch.prune_title", "Élaguer {count} feature(s) ?"),
+    (
+        "batch.prune_impact",
+        "Supprime {count} worktree(s) et fenêtre(s) tmux, conserve les branches :",
+    ),
+    ("batch.sync_title", "Synchroniser {count} feature(s) ?"),
+    (
+        "batch.sync_impact",
+        "Avance chaque worktree depuis sa branche amont (fast-forward) :",
+    ),
+    ("batch.busy", "Les processus en cours seront arrêtés dans : {names}"),
+    ("batch.continue", "Continuer ? (y/n)"),
+    ("batch.result", "{ok}/{total} réussie(s)"),
+    // Feature create
+    ("create.title", "Créer une nouvelle feature ({repo}) :"),
+    ("create.label", "Nom de la feature : "),
+    (
+        "create.confirm",
+        "Créer la feature {name} dans {repo} ? (y/n)",
+    ),
+    ("create.suggest", "Tab : utiliser {name}"),
+    (
+        "create.from_description",
+        "Entrée : créer {name}  Tab : modifier le nom",
+    ),
+    ("create.try", "Essayez : {names}"),
+    (
+        "create.protected",
+        "{name} est protégée sur la forge : travaillez plutôt sur une branche de feature",
+    ),
+    // Branch names
+    ("branch.empty", "le nom est vide"),
+    ("branch.too_long", "le nom dépasse {max} caractères"),
+    (
+        "branch.invalid_char",
+        "{char} n'est pas autorisé (lettres, chiffres, - _ . / + uniquement)",
+    ),
+    ("branch.leading_dash", "le nom ne peut pas commencer par -"),
+    ("branch.double_dot", "le nom ne peut pas contenir .."),
+    (
+        "branch.empty_segment",
+        "le nom ne peut pas commencer ou finir par /, ni contenir //",
+    ),
+    (
+        "branch.hidden_segment",
+        "une partie du nom ne peut pas commencer par . ni finir par .lock",
+    ),
+    (
+        "branch.window_dot",
+        "la partie après le dernier / nomme la fenêtre tmux et ne peut pas contenir .",
+    ),
+    // Resume
+    ("resume.title", "Reprendre la session précédente ?"),
+    ("resume.last", "Dernière session : {label} sur {server}"),
+    (
+        "resume.jump",
+        "Reprendre là où vous en étiez : {label} ({state})",
+    ),
+    ("resume.yes", "Oui, rattacher la session tmux"),
+    ("resume.no", "Non, choisir une autre session"),
+    // History bar
+    ("history.last", "Dernière action : "),
+    ("history.undo", "u : annuler"),
+    ("history.created", "{name} créée"),
+    ("history.deleted", "{name} supprimée"),
+    ("history.pruned", "{name} élaguée"),
+    ("history.switched", "passage à {name}"),
+    ("history.attached", "{name} rattachée"),
+    ("history.undid", "modification de {name} annulée"),
+    ("history.banned", "{name} bannie"),
+    ("history.unbanned", "{name} débannie"),
+    ("history.ran_task", "tâche lancée dans {name}"),
+    ("history.task_failed", "échec d'une tâche dans {name}"),
+    ("history.stopped_process", "processus arrêté dans {name}"),
+    ("history.just_now", "à l'instant"),
+    ("history.minutes", "il y a {n} min"),
+    ("history.hours", "il y a {n} h"),
+    ("history.days", "il y a {n} j"),
+    // Setup wizard
+    ("setup.welcome", "Bienvenue dans NomadFlow !"),
+    (
+        "setup.password_prompt",
+        "Définissez un mot de passe pour sécuriser votre serveur :",
+    ),
+    ("setup.generate", "Générer un mot de passe (recommandé)"),
+    ("setup.custom", "Saisir mon propre mot de passe"),
+    ("setup.enter_password", "Saisissez votre mot de passe :"),
+    ("setup.password_label", "Mot de passe : "),
+    (
+        "setup.public_title",
+        "Utiliserez-vous le mode tunnel public ? (y/n)",
+    ),
+    (
+        "setup.public_hint",
+        "Votre serveur sera exposé sur Internet via une URL de tunnel.",
+    ),
+    (
+        "setup.subdomain_title",
+        "Utiliser un sous-domaine fixe pour une URL publique stable ? (y/n)",
+    ),
+    ("setup.subdomain_label", "  Votre sous-domaine : "),
+    (
+        "setup.subdomain_hint",
+        "y : utiliser ce sous-domaine  n : URL aléatoire à chaque fois",
+    ),
+    ("setup.summary", "Récapitulatif de la configuration :"),
+    ("setup.summary_password", "  Mot de passe : "),
+    ("setup.summary_public", "  Mode public : "),
+    ("setup.summary_subdomain", "  Sous-domaine : "),
+    ("setup.random", "(aléatoire)"),
+    ("setup.summary_config", "  Configuration : "),
+    ("setup.save", "Enregistrer et continuer ? (y/n)"),
+    (
+        "setup.save_failed",
+        "Échec de l'enregistrement de la configuration : {err}",
+    ),
+    // Status
+    ("status.session", "Session : {session}"),
+    ("status.no_session", "Aucune session active"),
+    ("status.windows", "{count} fenêtre(s)"),
+    // CLI
+    (
+        "cli.daemon_already_running",
+        "Le démon NomadFlow tourne déjà (PID {pid})",
+    ),
+    ("cli.daemon_started", "Démon NomadFlow démarré (PID {pid})"),
+    ("cli.logs", "Journaux : {path}"),
+    (
+        "cli.no_pid_file",
+        "Aucun fichier PID — le démon ne tourne pas",
+    ),
+    (
+        "cli.stale_pid",
+        "Le processus {pid} ne tourne pas, suppression du fichier PID obsolète",
+    ),
+    ("cli.stopping", "Arrêt du démon NomadFlow (PID {pid})…"),
+    (
+        "cli.sigkill",
+        "Le processus ne s'est pas arrêté, envoi de SIGKILL…",
+    ),
+    ("cli.stopped", "Démon NomadFlow arrêté"),
+    (
+        "cli.status_running",
+        "Démon NomadFlow : en cours (PID {pid})",
+    ),
+    (
+        "cli.status_stale",
+        "Démon NomadFlow : arrêté (fichier PID obsolète)",
+    ),
+    ("cli.status_stopped", "Démon NomadFlow : arrêté"),
+    ("cli.status_api_down", "L'API du démon ne répond pas"),
+    ("cli.linked", "{name} lié -> {path}"),
+    ("cli.no_links", "Aucun dépôt lié trouvé."),
+    ("cli.unlink_title", "Délier un dépôt :"),
+    (
+        "cli.remove_worktrees",
+        "Supprimer {count} worktree(s) ? ({names})",
+    ),
+    ("cli.removed_worktree", "Worktree {name} supprimé"),
+    (
+        "cli.removed_worktree_dir",
+        "Dossier du worktree {name} supprimé",
+    ),
+    ("cli.unlinked", "{name} délié"),
+    ("cli.attach_title", "Se rattacher à la fenêtre :"),
+    ("cli.gc_nothing", "Rien à nettoyer"),
+    ("cli.gc_would_reclaim", "{size} seraient libérés (simulation)"),
+    ("cli.gc_reclaimed", "{size} libérés"),
+    ("gc.orphaned_worktree", "worktree orphelin"),
+    ("gc.crash_report", "rapport de plantage"),
+    ("gc.task_run", "exécution de tâche"),
+    ("gc.temp_file", "fichier temporaire"),
+    (
+        "migrate.done",
+        "Fichiers NomadFlow mis à jour vers la version {version} (sauvegarde dans {backup})",
+    ),
+    (
+        "remote.unknown_server",
+        "Serveur '{server}' inconnu. Ajoutez-le d'abord depuis la TUI.",
+    ),
+    (
+        "remote.bad_target",
+        "Cible '{target}' invalide : format attendu dépôt:feature",
+    ),
+    ("remote.unknown_repo", "Aucun dépôt nommé '{repo}' sur ce serveur"),
+    (
+        "remote.connected",
+        "Connecté à {server} — Ctrl-] pour se détacher",
+    ),
+    ("remote.detached", "Détaché."),
+    (
+        "new.not_in_repo",
+        "Pas dans un dépôt connu de NomadFlow. Liez-le d'abord avec `nomadflow link <chemin>`.",
+    ),
+    ("new.creating", "Création de {feature} dans {repo}…"),
+    ("open.title", "Features correspondant à '{query}' :"),
+    ("cd.no_match", "Aucune feature ne correspond à '{query}'"),
+    (
+        "cd.hint",
+        "Un programme ne peut pas changer le répertoire de votre shell. Pour que `nomadflow cd` le fasse, ajoutez ceci au fichier rc de votre shell : eval \"$(nomadflow shell-init bash)\" (ou zsh, ou fish : nomadflow shell-init fish | source)",
+    ),
+    (
+        "open.create",
+        "Aucune feature ne correspond à '{feature}'. La créer ?",
+    ),
+    // Init
+    (
+        "init.invalid_subdomain",
+        "Sous-domaine '{subdomain}' invalide : utilisez 3 à 32 lettres, chiffres ou tirets",
+    ),
+    ("init.empty_secret", "Le secret ne doit pas être vide"),
+    ("init.saved", "Configuration écrite dans {path}"),
+    (
+        "init.exists",
+        "Une configuration existe déjà dans {path}. L'écraser ?",
+    ),
+    (
+        "init.exists_abort",
+        "Une configuration existe déjà dans {path} (utilisez --force pour l'écraser)",
+    ),
+    // TOTP
+    (
+        "totp.scan",
+        "Scannez ce QR code avec votre application d'authentification :",
+    ),
+    ("totp.manual", "ou saisissez le secret manuellement : {secret}"),
+    ("totp.code_prompt", "Code affiché par l'application : "),
+    ("totp.bad_code", "Code invalide, réessayez."),
+    (
+        "totp.too_many",
+        "Trop de codes invalides, TOTP n'a pas été activé",
+    ),
+    (
+        "totp.already_enabled",
+        "TOTP est déjà activé (utilisez --force pour remplacer le secret)",
+    ),
+    (
+        "totp.needs_secret",
+        "TOTP nécessite un mot de passe serveur : définissez d'abord auth.secret (nomadflow init)",
+    ),
+    ("totp.enabled", "TOTP activé."),
+    (
+        "totp.disabled",
+        "TOTP désactivé et appareils mémorisés oubliés.",
+    ),
+    (
+        "totp.restart",
+        "Redémarrez le serveur pour appliquer le changement.",
+    ),
+    (
+        "totp.status_enabled",
+        "TOTP : activé ({count} appareil(s) mémorisé(s))",
+    ),
+    ("totp.status_disabled", "TOTP : désactivé"),
+    // Secrets encryption
+    (
+        "secrets.encrypted_keychain",
+        "Secrets chiffrés avec une clé conservée dans le trousseau du système.",
+    ),
+    (
+        "secrets.encrypted_passphrase",
+        "Secrets chiffrés avec une clé dérivée de {env}. Définissez-la à chaque démarrage de NomadFlow.",
+    ),
+    ("secrets.decrypted", "Les secrets sont désormais stockés en clair."),
+    ("secrets.status_none", "Secrets : stockés en clair"),
+    ("secrets.status_keychain", "Secrets : chiffrés, clé dans le trousseau du système"),
+    ("secrets.status_passphrase", "Secrets : chiffrés, clé dérivée de {env}"),
+    // Connectivity self-test
+    ("nettest.running", "Test de la connectivité…"),
+    ("nettest.local_api", "API locale"),
+    ("nettest.ttyd", "WebSocket ttyd"),
+    ("nettest.relay_api", "API du relais"),
+    ("nettest.bore", "Port de contrôle bore"),
+    ("nettest.public_url", "URL publique"),
+    ("nettest.skip_no_server", "ignoré : le serveur local ne tourne pas"),
+    (
+        "nettest.skip_not_public",
+        "ignoré : le serveur n'a pas été lancé avec --public",
+    ),
+    (
+        "nettest.skip_native_terminal",
+        "ignoré : le serveur attache tmux lui-même",
+    ),
+    ("nettest.all_passed", "Tous les tests sont passés."),
+    ("nettest.some_failed", "{count} test(s) en échec."),
+    ("check.running", "Vérification de l'API de {server}…"),
+    ("check.health", "Santé"),
+    ("check.auth", "Authentification exigée"),
+    ("check.list_repos", "Liste des dépôts"),
+    ("check.feature", "Création/suppression de feature"),
+    ("check.terminal", "WebSocket du terminal"),
+    ("check.skip_no_token", "ignoré : aucun jeton configuré pour ce serveur"),
+    ("check.skip_no_repo", "ignoré : passez --repo pour créer et supprimer une feature de test"),
+    ("check.skip_no_repos", "ignoré : les dépôts n'ont pas pu être listés"),
+    // Plain mode
+    ("plain.choice", "Saisissez un numéro (vide pour annuler) : "),
+    (
+        "plain.invalid",
+        "Choix invalide, saisissez un numéro entre 1 et {max}.",
+    ),
+    ("plain.yes_no", "[o/N] "),
+    // Connection info (server)
+    ("connect.ready", "Serveur NomadFlow prêt"),
+    ("connect.scan", "Scannez ce QR code depuis l'app"),
+    ("connect.manual", "ou saisissez manuellement :"),
+    ("connect.open_app", "Ouvrir dans l'app NomadFlow"),
+    ("connect.qr_failed", "[Échec de la génération du QR code]"),
+    ("connect.qr_image", "Image QR : {path}"),
+    (
+        "connect.qr_image_failed",
+        "Image QR : impossible d'écrire {path} ({err})",
+    ),
+    (
+        "connect.tunnel_beta",
+        "Tunnel public fourni par fab_uleuh — gratuit pendant la bêta.",
+    ),
+    (
+        "connect.tunnel_paid",
+        "Il pourrait devenir payant à l'avenir.",
+    ),
+    (
+        "connect.tunnel_selfhost",
+        "Vous pouvez toujours l'héberger vous-même via VPN ou votre propre relais.",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(s: &str) -> Vec<&str> {
+        let mut out: Vec<&str> = s
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect();
+        out.sort_unstable();
+        out
+    }
+
+    #[test]
+    fn test_catalogs_have_same_keys_and_placeholders() {
+        for (key, en) in EN {
+            let fr = FR
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| *v)
+                .unwrap_or_else(|| panic!("missing French translation for {key}"));
+            assert_eq!(
+                placeholders(en),
+                placeholders(fr),
+                "placeholders differ for {key}"
+            );
+        }
+        for (key, _) in FR {
+            assert!(
+                EN.iter().any(|(k, _)| k == key),
+                "unknown key {key} in French catalog"
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(Locale::from_tag("fr"), Some(Locale::Fr));
+        assert_eq!(Locale::from_tag("fr_FR.UTF-8"), Some(Locale::Fr));
+        assert_eq!(Locale::from_tag("en-US"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("C"), None);
+        assert_eq!(Locale::from_tag(""), None);
+        assert_eq!(Locale::detect("fr"), Locale::Fr);
+    }
+
+    #[test]
+    fn test_lookup_and_placeholders() {
+        assert_eq!(lookup(Locale::Fr, "common.yes"), "oui");
+        assert_eq!(lookup(Locale::En, "common.yes"), "yes");
+        assert_eq!(lookup(Locale::Fr, "no.such.key"), "no.such.key");
+        // The process-wide locale defaults to English
+        assert_eq!(
+            tf("batch.result", &[("ok", &2), ("total", &3)]),
+            "2/3 succeeded"
+        );
+    }
+}
@@ -1,5 +1,22 @@
+pub mod api_error;
+pub mod branch;
+pub mod commits;
 pub mod config;
 pub mod error;
+pub mod forge;
+pub mod fsutil;
+pub mod gc;
+pub mod hooks;
+pub mod i18n;
+pub mod limits;
+pub mod migrations;
 pub mod models;
+pub mod search;
+pub mod secrets;
 pub mod shell;
+pub mod statusline;
+pub mod tasks;
+pub mod window_names;
+pub mod workspace;
+pub mod worktree_files;
 pub mod services;
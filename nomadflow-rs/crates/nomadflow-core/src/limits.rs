@@ -0,0 +1,177 @@
+//! Resource limits of feature windows: the window's shell is started in a
+//! transient systemd scope (cgroups v2) capping CPU and memory, or under
+//! `nice`/`ionice` when no user systemd instance is available. Also finds
+//! the processes of a window, for usage and idle suspension.
+
+use std::fmt;
+
+use crate::config::ResourceLimits;
+use crate::shell::{command_exists, run};
+
+/// Window option holding the usage of a limited window, shown by its
+/// window-status.
+pub const USAGE_OPTION: &str = "@nomadflow_usage";
+
+/// How limits can be enforced on this machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Enforcement {
+    /// `systemd-run --user --scope`: CPU and memory caps plus priority.
+    Scope,
+    /// `nice`, and `ionice` when `io` is set: priority only.
+    Nice { io: bool },
+}
+
+impl Enforcement {
+    /// Probe for a user systemd instance able to create scopes.
+    pub async fn detect() -> Self {
+        if command_exists("systemd-run").await
+            && run("systemd-run --user --scope --quiet true", None)
+                .await
+                .success()
+        {
+            return Self::Scope;
+        }
+        Self::Nice {
+            io: command_exists("ionice").await,
+        }
+    }
+}
+
+/// Command starting the user's login shell under `limits`, for `tmux
+/// new-window`. `None` when there is nothing to enforce.
+pub fn shell_command(limits: &ResourceLimits, enforcement: Enforcement) -> Option<String> {
+    const SHELL: &str = r#"sh -c 'exec "${SHELL:-/bin/sh}" -l'"#;
+    match enforcement {
+        Enforcement::Scope if !limits.is_empty() => {
+            let mut cmd = "systemd-run --user --scope --quiet --collect".to_string();
+            if limits.cpu_percent > 0 {
+                cmd.push_str(&format!(" -p CPUQuota={}%", limits.cpu_percent));
+            }
+            if limits.memory_mb > 0 {
+                cmd.push_str(&format!(" -p MemoryMax={}M", limits.memory_mb));
+            }
+            if limits.nice != 0 {
+                cmd.push_str(&format!(" --nice={}", limits.nice));
+            }
+            Some(format!("{cmd} -- {SHELL}"))
+        }
+        Enforcement::Nice { io } if limits.nice != 0 => {
+            let ionice = if io { "ionice -c 2 -n 7 " } else { "" };
+            Some(format!("nice -n {} {ionice}{SHELL}", limits.nice))
+        }
+        _ => None,
+    }
+}
+
+/// CPU and memory used by the processes of a pane.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    /// Sum of the processes' CPU usage, in percent of one core.
+    pub cpu_percent: f32,
+    pub memory_mb: u64,
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.0}% {}M", self.cpu_percent, self.memory_mb)
+    }
+}
+
+/// Parse `ps -o rss=,pcpu=` lines.
+fn parse_ps(output: &str) -> Usage {
+    let (rss_kb, cpu) = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let rss: u64 = fields.next()?.parse().ok()?;
+            let cpu: f32 = fields.next()?.parse().ok()?;
+            Some((rss, cpu))
+        })
+        .fold((0, 0.0), |(rss, cpu), (r, c)| (rss + r, cpu + c));
+    Usage {
+        cpu_percent: cpu,
+        memory_mb: rss_kb / 1024,
+    }
+}
+
+/// Usage of the pane whose shell is `pid`: the shell leads the session of
+/// every process started from it, scoped or not.
+pub async fn pane_usage(pid: u32) -> Option<Usage> {
+    let result = run(&format!("ps -o rss=,pcpu= --sid {pid}"), None).await;
+    result.success().then(|| parse_ps(&result.stdout))
+}
+
+/// Process group of the job running in the foreground of the shell `pid`,
+/// `None` when the shell itself is waiting for input. The group is checked
+/// to be one of the pane's, so that signalling it never reaches init, the
+/// server or another pane.
+pub async fn foreground_group(pid: u32) -> Option<u32> {
+    let result = run(&format!("ps -o tpgid= -p {pid}"), None).await;
+    let tpgid: i64 = result.stdout.trim().parse().ok()?;
+    let own = run(&format!("ps -o pgid= -p {}", std::process::id()), None).await;
+    let pane = run(&format!("ps -o pgid= --sid {pid}"), None).await;
+    pane_group(tpgid, pid, own.stdout.trim().parse().ok(), &pane.stdout)
+}
+
+/// `tpgid` when it is a job of the shell `pid`: not the shell, init or
+/// `own_group`, and among the `pane_groups` of the shell's session.
+fn pane_group(tpgid: i64, pid: u32, own_group: Option<u32>, pane_groups: &str) -> Option<u32> {
+    let pgid = u32::try_from(tpgid).ok().filter(|&g| g > 1 && g != pid)?;
+    if own_group.is_some_and(|own| own == pgid) {
+        return None;
+    }
+    pane_groups
+        .split_whitespace()
+        .any(|g| g.parse() == Ok(pgid))
+        .then_some(pgid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_command() {
+        let limits = ResourceLimits {
+            cpu_percent: 150,
+            memory_mb: 2048,
+            nice: 10,
+        };
+        assert_eq!(
+            shell_command(&limits, Enforcement::Scope).unwrap(),
+            r#"systemd-run --user --scope --quiet --collect -p CPUQuota=150% -p MemoryMax=2048M --nice=10 -- sh -c 'exec "${SHELL:-/bin/sh}" -l'"#
+        );
+        assert_eq!(
+            shell_command(&limits, Enforcement::Nice { io: true }).unwrap(),
+            r#"nice -n 10 ionice -c 2 -n 7 sh -c 'exec "${SHELL:-/bin/sh}" -l'"#
+        );
+
+        // Caps without cgroups cannot be enforced
+        let caps_only = ResourceLimits { nice: 0, ..limits };
+        assert_eq!(shell_command(&caps_only, Enforcement::Nice { io: true }), None);
+        assert_eq!(
+            shell_command(&ResourceLimits::default(), Enforcement::Scope),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pane_group() {
+        let pane = " 4000\n 4100\n 4100\n";
+        assert_eq!(pane_group(4100, 4000, Some(900), pane), Some(4100));
+        // The shell waits for input
+        assert_eq!(pane_group(4000, 4000, Some(900), pane), None);
+        // No terminal, init, the server or a group of another pane
+        assert_eq!(pane_group(-1, 4000, Some(900), pane), None);
+        assert_eq!(pane_group(1, 4000, Some(900), " 1\n"), None);
+        assert_eq!(pane_group(900, 4000, Some(900), " 900\n"), None);
+        assert_eq!(pane_group(5100, 4000, Some(900), pane), None);
+    }
+
+    #[test]
+    fn test_usage() {
+        let usage = parse_ps(" 3320  2.0\n 204800 97.0\nbogus\n");
+        assert_eq!(usage.memory_mb, 203);
+        assert_eq!(usage.to_string(), "99% 203M");
+    }
+}
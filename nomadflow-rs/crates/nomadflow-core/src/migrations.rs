@@ -0,0 +1,216 @@
+//! Versioned migrations of the base directory (config schema, state files,
+//! path moves), run on startup. The files are backed up before the first
+//! pending migration and restored if one fails.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{NomadError, Result};
+use crate::fsutil;
+
+/// File of the base directory holding its layout version.
+pub const VERSION_FILE: &str = "layout-version";
+/// Directory of the base directory holding the pre-migration backups.
+pub const BACKUPS_DIR: &str = "backups";
+
+/// One change of the on-disk layout, bringing it to `version`.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    /// Applied to the base directory.
+    pub apply: fn(&Path) -> Result<()>,
+}
+
+/// Every migration, in version order. Append new ones; never edit shipped ones.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "Start tracking the layout version",
+    apply: |_| Ok(()),
+}];
+
+/// Outcome of [`run`] when migrations were applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migrated {
+    pub from: u32,
+    pub to: u32,
+    /// Copy of the files as they were before, if there were any.
+    pub backup: Option<PathBuf>,
+}
+
+/// Layout version of `base_dir`; 0 before the first migration.
+pub fn current_version(base_dir: &Path) -> u32 {
+    std::fs::read_to_string(base_dir.join(VERSION_FILE))
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn set_version(base_dir: &Path, version: u32) -> Result<()> {
+    fsutil::write_atomic(&base_dir.join(VERSION_FILE), format!("{version}\n"))?;
+    Ok(())
+}
+
+/// Bring `base_dir` to the latest layout version.
+pub fn run(base_dir: &Path) -> Result<Option<Migrated>> {
+    run_migrations(base_dir, MIGRATIONS)
+}
+
+fn run_migrations(base_dir: &Path, migrations: &[Migration]) -> Result<Option<Migrated>> {
+    let from = current_version(base_dir);
+    let latest = migrations.last().map_or(0, |m| m.version);
+    if from > latest {
+        return Err(NomadError::Config(format!(
+            "{} uses layout version {from}, newer than this nomadflow supports ({latest}). Upgrade nomadflow.",
+            base_dir.display()
+        )));
+    }
+    let pending: Vec<&Migration> = migrations.iter().filter(|m| m.version > from).collect();
+    if pending.is_empty() {
+        return Ok(None);
+    }
+
+    std::fs::create_dir_all(base_dir)?;
+    let backup = backup(base_dir, from)?;
+    for migration in pending {
+        let applied = (migration.apply)(base_dir).and_then(|()| set_version(base_dir, migration.version));
+        if let Err(e) = applied {
+            if let Some(ref backup) = backup {
+                restore(backup, base_dir)?;
+            }
+            return Err(NomadError::Other(format!(
+                "Migration to layout version {} ({}) failed: {e}",
+                migration.version, migration.description
+            )));
+        }
+    }
+    Ok(Some(Migrated {
+        from,
+        to: latest,
+        backup,
+    }))
+}
+
+/// Copy the top-level files of `base_dir` (config and state, not the repos or
+/// worktrees) to a new backup directory. `None` when there is nothing to save.
+fn backup(base_dir: &Path, version: u32) -> Result<Option<PathBuf>> {
+    let files: Vec<PathBuf> = std::fs::read_dir(base_dir)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let dir = base_dir
+        .join(BACKUPS_DIR)
+        .join(format!("layout-v{version}-{secs}"));
+    std::fs::create_dir_all(&dir)?;
+    for file in files {
+        if let Some(name) = file.file_name() {
+            std::fs::copy(&file, dir.join(name))?;
+        }
+    }
+    Ok(Some(dir))
+}
+
+/// Put the backed up files back, dropping files the migrations created.
+fn restore(backup: &Path, base_dir: &Path) -> Result<()> {
+    for entry in std::fs::read_dir(base_dir)?.flatten() {
+        let path = entry.path();
+        if path.is_file() && !backup.join(entry.file_name()).exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    for entry in std::fs::read_dir(backup)?.flatten() {
+        std::fs::copy(entry.path(), base_dir.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn rename_state(base_dir: &Path) -> Result<()> {
+        std::fs::rename(base_dir.join("state.json"), base_dir.join("cli-state.json"))?;
+        Ok(())
+    }
+
+    fn fail(_: &Path) -> Result<()> {
+        Err(NomadError::Other("boom".to_string()))
+    }
+
+    const MOVE: &[Migration] = &[
+        Migration {
+            version: 1,
+            description: "baseline",
+            apply: |_| Ok(()),
+        },
+        Migration {
+            version: 2,
+            description: "rename state file",
+            apply: rename_state,
+        },
+    ];
+
+    #[test]
+    fn test_fresh_dir_is_stamped_without_backup() {
+        let tmp = TempDir::new().unwrap();
+        let migrated = run(tmp.path()).unwrap().unwrap();
+        assert_eq!(migrated.from, 0);
+        assert_eq!(migrated.backup, None);
+        assert_eq!(current_version(tmp.path()), MIGRATIONS.last().unwrap().version);
+
+        // Nothing left to do
+        assert_eq!(run(tmp.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_pending_migrations_run_after_backup() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("state.json"), "{}").unwrap();
+        set_version(tmp.path(), 1).unwrap();
+
+        let migrated = run_migrations(tmp.path(), MOVE).unwrap().unwrap();
+        assert_eq!((migrated.from, migrated.to), (1, 2));
+        assert!(tmp.path().join("cli-state.json").exists());
+        assert!(migrated.backup.unwrap().join("state.json").exists());
+        assert_eq!(current_version(tmp.path()), 2);
+    }
+
+    #[test]
+    fn test_failed_migration_restores_backup() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("state.json"), "{}").unwrap();
+        let failing = [
+            Migration {
+                version: 1,
+                description: "rename state file",
+                apply: rename_state,
+            },
+            Migration {
+                version: 2,
+                description: "broken",
+                apply: fail,
+            },
+        ];
+
+        assert!(run_migrations(tmp.path(), &failing).is_err());
+        assert!(tmp.path().join("state.json").exists());
+        assert!(!tmp.path().join("cli-state.json").exists());
+        assert_eq!(current_version(tmp.path()), 0);
+    }
+
+    #[test]
+    fn test_newer_layout_is_refused() {
+        let tmp = TempDir::new().unwrap();
+        set_version(tmp.path(), 99).unwrap();
+        assert!(run(tmp.path()).is_err());
+    }
+}
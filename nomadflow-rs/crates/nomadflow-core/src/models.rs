@@ -1,173 +1,1612 @@
 use serde::{Deserialize, Serialize};
 
+// ---- Pagination ----
+
+/// Pagination and filtering accepted by list endpoints.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageParams {
+    /// Maximum number of items to return (all when absent).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Number of matching items to skip.
+    #[serde(default)]
+    pub offset: usize,
+    /// Case-insensitive substring an item's name must contain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+impl PageParams {
+    /// Filter `items` by `name`, then slice the requested page.
+    /// Returns the page and the number of items that matched the filter.
+    pub fn apply<T>(&self, items: Vec<T>, name: impl Fn(&T) -> &str) -> (Vec<T>, usize) {
+        let needle = self
+            .filter
+            .as_deref()
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(str::to_lowercase);
+        let matching: Vec<T> = items
+            .into_iter()
+            .filter(|item| match &needle {
+                Some(n) => name(item).to_lowercase().contains(n),
+                None => true,
+            })
+            .collect();
+        let total = matching.len();
+        let page = matching
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        (page, total)
+    }
+}
+
 // ---- Response models ----
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Repository {
+    pub name: String,
+    pub path: String,
+    pub branch: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Feature {
+    pub name: String,
+    pub worktree_path: String,
+    pub branch: String,
+    #[serde(default)]
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_main: bool,
+    /// The feature's tmux window produced output since it was last attached.
+    #[serde(default)]
+    pub has_unread: bool,
+    /// The job running in the feature's window was suspended for being idle.
+    #[serde(default)]
+    pub suspended: bool,
+    /// Upstream of the branch, e.g. `origin/feature/login`.
+    #[serde(default)]
+    pub upstream: Option<String>,
+    /// Commit checked out in the worktree.
+    #[serde(default)]
+    pub last_commit_sha: Option<String>,
+    /// Unix timestamp (seconds) of that commit.
+    #[serde(default)]
+    pub last_commit_at: Option<u64>,
+    /// Subject line of that commit.
+    #[serde(default)]
+    pub last_commit_subject: Option<String>,
+    /// Unix timestamp (seconds) at which the worktree was created.
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    /// The worktree has uncommitted changes, untracked files included.
+    #[serde(default)]
+    pub dirty: bool,
+    /// Commits of the branch not in its upstream. `None` without an upstream,
+    /// or when it is gone.
+    #[serde(default)]
+    pub ahead: Option<u32>,
+    /// Commits of the upstream not in the branch.
+    #[serde(default)]
+    pub behind: Option<u32>,
+    /// A rebase or merge stopped on conflicts in the worktree and is not
+    /// finished yet.
+    #[serde(default)]
+    pub needs_resolution: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListReposResponse {
+    pub repos: Vec<Repository>,
+    /// Number of repos matching the filter, before pagination.
+    #[serde(default)]
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListFeaturesResponse {
+    pub features: Vec<Feature>,
+    /// Number of features matching the filter, before pagination.
+    #[serde(default)]
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateFeatureResponse {
+    pub worktree_path: String,
+    pub branch: String,
+    pub tmux_window: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteFeatureResponse {
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteRepoResponse {
+    pub deleted: bool,
+    /// The repo was a symlink: only the link was removed, not what it
+    /// points to.
+    pub unlinked: bool,
+    /// Worktrees removed along with the repo.
+    pub removed_features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchFeatureResponse {
+    pub switched: bool,
+    pub worktree_path: String,
+    pub tmux_window: String,
+    #[serde(default)]
+    pub has_running_process: bool,
+}
+
+/// A switch done for a launcher, with the ways to get to the window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickAttachResponse {
+    pub repo_path: String,
+    pub feature_name: String,
+    pub worktree_path: String,
+    pub tmux_window: String,
+    #[serde(default)]
+    pub has_running_process: bool,
+    /// Opens the feature's terminal in the mobile app.
+    pub deep_link: String,
+    /// Shell command attaching a terminal to the window, over SSH when
+    /// `editor.ssh_host` is set.
+    pub attach_command: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloneRepoResponse {
+    pub name: String,
+    pub path: String,
+    pub branch: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+    pub status: String,
+    pub tmux_session: String,
+    pub api_port: u16,
+    /// Public key signing this response, see the `X-NomadFlow-Signature` header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_key: Option<String>,
+    /// Whether `/terminal/ws` works. Missing from older servers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<TerminalStatus>,
+    /// Whether the API asks for a credential, that is `auth.secret` is
+    /// set. Missing from older servers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_required: Option<bool>,
+}
+
+/// State of the terminal backend, as reported by `/health`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TerminalStatus {
+    Ok,
+    /// ttyd stopped responding and is being restarted.
+    Restarting,
+    /// ttyd cannot be started, e.g. because it is not installed.
+    Unavailable,
+}
+
+// ---- Branch models ----
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchInfo {
+    pub name: String,
+    pub is_remote: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListBranchesRequest {
+    pub repo_path: String,
+    #[serde(flatten)]
+    pub page: PageParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListBranchesResponse {
+    pub branches: Vec<BranchInfo>,
+    pub default_branch: String,
+    /// Number of branches matching the filter, before pagination.
+    #[serde(default)]
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachBranchRequest {
+    pub repo_path: String,
+    pub branch_name: String,
+    /// Work on the branch even though the forge protects it.
+    #[serde(default)]
+    pub allow_protected: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachBranchResponse {
+    pub worktree_path: String,
+    pub branch: String,
+    pub tmux_window: String,
+}
+
+// ---- Request models ----
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListReposRequest {
+    #[serde(flatten)]
+    pub page: PageParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListFeaturesRequest {
+    pub repo_path: String,
+    #[serde(flatten)]
+    pub page: PageParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateFeatureRequest {
+    pub repo_path: String,
+    /// Full branch name (e.g. "feature/add-login", "bugfix/crash", "my-branch").
+    /// Derived from `description` when empty.
+    #[serde(alias = "featureName", default)]
+    pub branch_name: String,
+    #[serde(default = "default_base_branch")]
+    pub base_branch: String,
+    /// What the feature is for, kept as its note.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Work on the branch even though the forge protects it.
+    #[serde(default)]
+    pub allow_protected: bool,
+}
+
+fn default_base_branch() -> String {
+    "main".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestBranchNameRequest {
+    pub repo_path: String,
+    /// A sentence saying what the feature is for.
+    pub description: String,
+    /// Prefix of the name, e.g. `ui/`. Guessed from the description when
+    /// omitted, empty for none.
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestBranchNameResponse {
+    /// A valid name no branch of the repo has, `None` when the description
+    /// has no usable word.
+    pub branch_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateBranchRequest {
+    #[serde(alias = "featureName")]
+    pub branch_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateBranchResponse {
+    pub valid: bool,
+    /// Why the name is refused, when it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub problem: Option<String>,
+    /// Valid names close to the one given, best first.
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteFeatureRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchFeatureRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+}
+
+/// Query of `GET /api/quick-attach`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickAttachParams {
+    /// Name or path of the repo.
+    pub repo: String,
+    pub feature: String,
+}
+
+/// Action applied to every feature of a bulk request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BulkFeatureAction {
+    /// Remove the worktree, its tmux window, and delete the branch.
+    Delete,
+    /// Remove the worktree and its tmux window but keep the branch.
+    Prune,
+    /// Fast-forward the worktree from its upstream.
+    Sync,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkFeaturesRequest {
+    pub repo_path: String,
+    pub feature_names: Vec<String>,
+    pub action: BulkFeatureAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkFeatureResult {
+    pub feature_name: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkFeaturesResponse {
+    pub results: Vec<BulkFeatureResult>,
+}
+
+/// How `/api/pull-feature` brings in the upstream commits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PullStrategy {
+    /// Only move the branch forward; refuse when it has diverged.
+    #[default]
+    FastForward,
+    /// Replay local commits on top of the upstream.
+    Rebase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullFeatureRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+    #[serde(default)]
+    pub strategy: PullStrategy,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullFeatureResponse {
+    /// Whether the branch moved.
+    pub updated: bool,
+    /// Upstream commits the branch did not have.
+    pub commits_pulled: u32,
+    /// Files a rebase stopped on. The rebase is aborted, the branch is left
+    /// as it was.
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+    pub message: String,
+    /// Paths to commit, relative to the worktree. All changes when absent.
+    #[serde(default)]
+    pub paths: Option<Vec<String>>,
+    /// Conventional-commit type (`feat`, `fix`…) prefixed to the message.
+    #[serde(default, rename = "type")]
+    pub commit_type: Option<String>,
+    /// Conventional-commit scope, used with `type`.
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// Mark the change as breaking with a `!` after the type.
+    #[serde(default)]
+    pub breaking: bool,
+    /// Commit without running the repo's git hooks, when the server allows it.
+    #[serde(default)]
+    pub skip_hooks: bool,
+}
+
+/// A git hook that refused a commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookFailure {
+    /// `pre-commit` or `commit-msg`.
+    pub hook: String,
+    pub exit_code: i32,
+    /// The end of what the hook printed.
+    pub output: String,
+}
+
+/// A rule of the repo's `[commit]` table that a commit message breaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommitRule {
+    /// The header is not `type(scope)!: subject`.
+    NotConventional,
+    UnknownType,
+    UnknownScope,
+    EmptySubject,
+    HeaderTooLong,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitViolation {
+    pub rule: CommitRule,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitResponse {
+    /// Abbreviated sha of the new commit.
+    pub commit: String,
+    pub files_changed: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PushRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PushResponse {
+    pub remote: String,
+    pub branch: String,
+    /// Whether the branch had no upstream and now tracks the pushed one.
+    pub set_upstream: bool,
+}
+
+/// Why a remote refused a push, so that clients can offer the way out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PushRejection {
+    /// The remote has commits the branch lacks: pull first.
+    NonFastForward,
+    /// The remote refused the branch, e.g. a protected branch or a hook.
+    RemoteRejected,
+    /// The credentials were missing or refused.
+    Unauthorized,
+}
+
+/// Open a feature's window and start resolving its conflicts there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveConflictsRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveConflictsResponse {
+    pub tmux_window: String,
+    /// What was typed at the window's prompt.
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtectedBranchesRequest {
+    pub repo_path: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtectedBranchesResponse {
+    /// Branch names, or patterns where `*` matches anything (`release/*`).
+    pub branches: Vec<String>,
+}
+
+/// Forge hosting a repository's remote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForgeKind {
+    #[serde(rename = "github")]
+    GitHub,
+    #[serde(rename = "gitlab")]
+    GitLab,
+}
+
+/// Open a pull request (a merge request on GitLab) for a feature's branch.
+/// The branch must have been pushed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePrRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+    /// Branch to merge into, instead of the repo's default branch.
+    #[serde(default)]
+    pub base: Option<String>,
+    /// Defaults to the subject of the branch's last commit.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Defaults to the feature's note.
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePrResponse {
+    /// Web page of the new pull request.
+    pub url: String,
+    pub forge: ForgeKind,
+    pub head: String,
+    pub base: String,
+    pub draft: bool,
+}
+
+/// Query of the diff endpoints.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffParams {
+    /// Branch to compare with, instead of the repo's default branch.
+    #[serde(default)]
+    pub base: Option<String>,
+}
+
+/// Changes of a feature's worktree, as unified diffs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureDiff {
+    pub base: String,
+    /// Commit the branch forked from `base` at, which the diff starts from.
+    pub merge_base: String,
+    /// Commits and uncommitted changes of the feature, from the merge base
+    /// to the worktree.
+    pub diff: String,
+    /// Changes in the index, not committed yet.
+    pub staged: String,
+    /// Changes in the worktree, not staged yet.
+    pub unstaged: String,
+    /// A diff was cut at the size limit.
+    pub truncated: bool,
+}
+
+/// Lines changed in a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiffStat {
+    pub path: String,
+    /// `None` for binary files.
+    pub additions: Option<u32>,
+    pub deletions: Option<u32>,
+}
+
+/// What [`FeatureDiff::diff`] changes, file by file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSummary {
+    pub base: String,
+    pub merge_base: String,
+    pub files: Vec<FileDiffStat>,
+    pub additions: u32,
+    pub deletions: u32,
+}
+
+/// Query of the file browser endpoints.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesParams {
+    pub repo_path: String,
+    pub feature: String,
+    /// Path relative to the feature's worktree, its root when empty.
+    #[serde(default)]
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// An entry of a worktree directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileEntry {
+    pub name: String,
+    /// Path relative to the worktree, to list or read it next.
+    pub path: String,
+    pub kind: FileKind,
+    /// Size in bytes, 0 for directories.
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListFilesResponse {
+    pub path: String,
+    /// Directories first, then files, each by name.
+    pub entries: Vec<FileEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContentResponse {
+    pub path: String,
+    pub size: u64,
+    /// The file is not UTF-8 text; `content` is then left out.
+    pub binary: bool,
+    pub content: Option<String>,
+}
+
+/// Get the note of a feature, or replace it when `note` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureNotesRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+    /// New text of the note. Blank removes it.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Freeform note left on a feature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureNote {
+    pub text: String,
+    /// Last edit, in seconds since the epoch.
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureNotesResponse {
+    /// `None` when the feature has no note.
+    pub note: Option<FeatureNote>,
+}
+
+// ---- Activity log ----
+
+/// Kind of operation recorded in the server activity log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivityAction {
+    CreateFeature,
+    DeleteFeature,
+    PruneFeature,
+    SwitchFeature,
+    AttachBranch,
+    /// A previous entry was reverted.
+    Undo,
+    /// An IP was banned after repeated authentication failures (`featureName` holds the IP).
+    BanIp,
+    /// A ban was lifted through the API (`featureName` holds the IP).
+    UnbanIp,
+    /// A task of the repo was started for the feature.
+    RunTask,
+    /// A background task of the feature ended with an error.
+    TaskFailed,
+    /// The program running in the feature's window was interrupted or killed.
+    StopProcess,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityEntry {
+    pub id: u64,
+    /// Unix timestamp (seconds).
+    pub timestamp: u64,
+    pub action: ActivityAction,
+    pub repo_path: String,
+    pub feature_name: String,
+    /// Whether `/api/undo` can still revert this entry.
+    pub undoable: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListActivityRequest {
+    #[serde(flatten)]
+    pub page: PageParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListActivityResponse {
+    /// Most recent first.
+    pub entries: Vec<ActivityEntry>,
+    /// Number of entries matching the filter, before pagination.
+    #[serde(default)]
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoRequest {
+    pub id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoResponse {
+    pub undone: bool,
+    pub detail: String,
+}
+
+// ---- Guest share links ----
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateShareRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+    /// Lifetime of the link in seconds (server default when omitted).
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+    /// Guests can watch but not type.
+    #[serde(default = "default_read_only")]
+    pub read_only: bool,
+}
+
+fn default_read_only() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareInfo {
+    pub id: String,
+    pub repo_path: String,
+    pub feature_name: String,
+    pub read_only: bool,
+    /// Unix timestamp (seconds).
+    pub created_at: u64,
+    /// Unix timestamp (seconds) after which the link stops working.
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateShareResponse {
+    pub share: ShareInfo,
+    /// Secret part of the link; only returned once.
+    pub token: String,
+    /// Server-relative URL of the guest terminal page.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadUrlRequest {
+    /// Path of the download, e.g. `/api/task-runs/3/log`.
+    pub path: String,
+    /// Lifetime of the URL, 15 minutes by default.
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadUrlResponse {
+    /// Server-relative URL reaching the download without credentials.
+    pub url: String,
+    /// Unix timestamp (seconds).
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSharesRequest {
+    #[serde(flatten)]
+    pub page: PageParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSharesResponse {
+    pub shares: Vec<ShareInfo>,
+    #[serde(default)]
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeShareRequest {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeShareResponse {
+    pub revoked: bool,
+}
+
+// ---- TOTP second factor ----
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyOtpRequest {
+    pub code: String,
+    /// Keep the returned device token valid for `auth.totp_remember_days`
+    /// instead of a single day.
+    #[serde(default)]
+    pub remember: bool,
+    #[serde(default)]
+    pub device_name: Option<String>,
+}
+
+/// A device that passed the TOTP check and can skip it until `expires_at`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceInfo {
+    pub id: String,
+    pub name: String,
+    /// Unix timestamp (seconds).
+    pub created_at: u64,
+    /// Unix timestamp (seconds).
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyOtpResponse {
+    /// Send as `X-NomadFlow-Device` on later requests.
+    pub device_token: String,
+    pub device: DeviceInfo,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDevicesRequest {
+    #[serde(flatten)]
+    pub page: PageParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDevicesResponse {
+    pub devices: Vec<DeviceInfo>,
+    #[serde(default)]
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgetDeviceRequest {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgetDeviceResponse {
+    pub forgotten: bool,
+}
+
+// ---- WebRTC ----
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebRtcOfferRequest {
+    /// SDP offer of the client, with its ICE candidates gathered.
+    pub sdp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebRtcAnswerResponse {
+    /// SDP answer of the server, with its ICE candidates gathered.
+    pub sdp: String,
+}
+
+// ---- Terminal sessions ----
+
+/// How a terminal session reaches ttyd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionTransport {
+    /// The `/terminal/ws` proxy.
+    WebSocket,
+    /// A WebRTC data channel.
+    WebRtc,
+    /// A guest share link.
+    Share,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalSessionInfo {
+    pub id: u64,
+    pub transport: SessionTransport,
+    /// Trusted device that opened the session, when TOTP is enabled.
+    pub device_id: Option<String>,
+    /// Unix timestamp (seconds).
+    pub started_at: u64,
+    /// Bytes sent to the client.
+    pub bytes_sent: u64,
+    /// Bytes received from the client.
+    pub bytes_received: u64,
+}
+
+/// Traffic of a device since the server started, ended sessions included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceTrafficInfo {
+    /// `None` for sessions opened without a device token.
+    pub device_id: Option<String>,
+    pub sessions: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSessionsResponse {
+    /// Open sessions, oldest first.
+    pub sessions: Vec<TerminalSessionInfo>,
+    pub devices: Vec<DeviceTrafficInfo>,
+}
+
+/// A window of the managed tmux session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub window: String,
+    /// Repo and feature of the window, when it is a feature's.
+    pub repo_path: Option<String>,
+    pub feature_name: Option<String>,
+    /// Program in the window's pane.
+    pub command: Option<String>,
+    /// The pane is back at the shell prompt.
+    pub idle: bool,
+    /// When the window last produced output, in Unix seconds.
+    pub last_activity: u64,
+    /// Output was produced since the window was last viewed.
+    pub has_unread: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowStatusResponse {
+    /// Busy windows first, then the most recently active.
+    pub windows: Vec<WindowState>,
+}
+
+// ---- API tokens ----
+
+/// What a named API token may do, on top of reading (which every scope grants).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenScope {
+    /// List and read repos, features, tasks, jobs and events.
+    ReadOnly,
+    /// Create, delete and switch features, attach branches, undo.
+    Features,
+    /// Clone and delete repositories.
+    Repos,
+    /// Terminal WebSocket and pages, share links, tasks.
+    Terminal,
+}
+
+/// A token issued to a teammate instead of the server secret.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Repository {
+pub struct ApiTokenInfo {
+    pub id: String,
     pub name: String,
-    pub path: String,
-    pub branch: String,
+    pub scopes: Vec<TokenScope>,
+    /// Unix timestamp (seconds).
+    pub created_at: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Feature {
+pub struct CreateTokenRequest {
     pub name: String,
-    pub worktree_path: String,
-    pub branch: String,
+    pub scopes: Vec<TokenScope>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTokenResponse {
+    /// Shown only once; the server keeps a hash of it.
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiTokenInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTokensResponse {
+    pub tokens: Vec<ApiTokenInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeTokenResponse {
+    pub revoked: bool,
+}
+
+// ---- IP bans ----
+
+/// An IP refused by the server after too many failed authentications.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BanInfo {
+    pub ip: String,
+    /// Failures counted when the ban was issued.
+    pub failures: u32,
+    /// Unix timestamp (seconds).
+    pub banned_at: u64,
+    /// Unix timestamp (seconds).
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListBansResponse {
+    pub bans: Vec<BanInfo>,
     #[serde(default)]
-    pub is_active: bool,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteBansResponse {
+    /// Number of bans lifted.
+    pub removed: usize,
+}
+
+/// State of the public tunnel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelState {
+    /// The server was not started with `--public`.
+    #[default]
+    Disabled,
+    Connected,
+    /// The connection dropped or the machine resumed from sleep; re-registering.
+    Reconnecting,
+    /// The tunnel could not be opened.
+    Failed,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelStatus {
+    pub state: TunnelState,
+    pub public_url: Option<String>,
+    pub subdomain: Option<String>,
+    /// Relay of the current registration, one of `tunnel.relay_hosts`.
+    pub relay_host: Option<String>,
+    /// Port assigned by the relay's bore server.
+    pub remote_port: Option<u16>,
+    /// Unix timestamp (seconds) of the current registration.
+    pub registered_at: Option<u64>,
+    /// Registrations since the server started, re-registrations included.
     #[serde(default)]
-    pub is_main: bool,
+    pub registrations: u32,
+    /// Whether the current registration kept the public URL of the previous
+    /// one, from this run or cached by the last run.
+    #[serde(default)]
+    pub continuous: bool,
+    /// Public URL before the last registration, when it changed.
+    pub previous_url: Option<String>,
+    pub last_error: Option<String>,
+}
+
+// ---- Reconciliation ----
+
+/// Kind of inconsistency between git, the worktrees directory and tmux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReconcileIssueKind {
+    /// A directory of the worktrees directory that git does not know about.
+    OrphanedDir,
+    /// A worktree registered in git whose directory is gone.
+    PrunableWorktree,
+    /// A feature worktree without a tmux window.
+    MissingWindow,
+    /// A `repo:feature` tmux window whose feature no longer exists.
+    OrphanedWindow,
+    /// A feature whose upstream branch was deleted from the remote, as
+    /// forges do once its pull request is merged. Left for the user to
+    /// prune.
+    GoneUpstream,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ListReposResponse {
-    pub repos: Vec<Repository>,
+pub struct ReconcileIssue {
+    pub kind: ReconcileIssueKind,
+    pub repo_path: String,
+    /// Directory of the worktree, or name of the window.
+    pub target: String,
+    /// Whether the issue was fixed by this request.
+    pub fixed: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconcileRequest {
+    /// Fix the issues found instead of only reporting them.
+    #[serde(default)]
+    pub fix: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ListFeaturesResponse {
-    pub features: Vec<Feature>,
+pub struct ReconcileResponse {
+    pub issues: Vec<ReconcileIssue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcRequest {
+    /// Only report what would be removed.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What a cleanup removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GcKind {
+    /// Directory of the worktrees directory its repo's git does not list.
+    OrphanedWorktree,
+    CrashReport,
+    TaskRun,
+    /// Leftover of a write interrupted by a crash.
+    TempFile,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CreateFeatureResponse {
-    pub worktree_path: String,
-    pub branch: String,
-    pub tmux_window: String,
+pub struct GcEntry {
+    pub kind: GcKind,
+    pub path: String,
+    /// Space taken on disk.
+    pub bytes: u64,
+    /// Why it could not be removed.
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct DeleteFeatureResponse {
-    pub deleted: bool,
+pub struct GcResponse {
+    pub dry_run: bool,
+    pub entries: Vec<GcEntry>,
+    /// Space freed, or that would be with `dryRun`.
+    pub reclaimed_bytes: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct SwitchFeatureResponse {
-    pub switched: bool,
-    pub worktree_path: String,
-    pub tmux_window: String,
+pub struct CloneRepoRequest {
+    pub url: String,
+    pub token: Option<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteRepoRequest {
+    pub repo_path: String,
+    /// Also remove the repo's worktrees and their windows, uncommitted work
+    /// included. Without it, a repo with worktrees is not deleted.
     #[serde(default)]
-    pub has_running_process: bool,
+    pub force: bool,
+}
+
+/// How `/api/duplicate-repo` copies a repository.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateMode {
+    /// A clone with objects of its own.
+    #[default]
+    Clone,
+    /// A clone borrowing the objects of the original (`git clone --shared`):
+    /// cheap, but broken if the original is deleted or garbage collected.
+    Shared,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CloneRepoResponse {
+pub struct DuplicateRepoRequest {
+    pub repo_path: String,
+    /// Name of the copy in the repos directory.
     pub name: String,
-    pub path: String,
-    pub branch: String,
+    #[serde(default)]
+    pub mode: DuplicateMode,
+    /// Where the copy's `origin` points. Defaults to the original's `origin`.
+    #[serde(default)]
+    pub remote_url: Option<String>,
 }
 
+// ---- Server model (for TUI config) ----
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct HealthResponse {
-    pub status: String,
-    pub tmux_session: String,
-    pub api_port: u16,
+pub struct Server {
+    pub id: String,
+    pub name: String,
+    pub api_url: Option<String>,
+    pub auth_token: Option<String>,
+    pub last_connected: Option<u64>,
 }
 
-// ---- Branch models ----
+// ---- tmux events ----
+
+/// Something that happened in the tmux session, reported by a tmux hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TmuxEventKind {
+    /// The program of a pane kept with `remain-on-exit` exited.
+    PaneDied,
+    /// A window rang the bell.
+    Bell,
+    /// A client attached to the session.
+    ClientAttached,
+    /// A program running in a window produced no output for
+    /// `tmux.idle_alert_seconds`.
+    PaneIdle,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct BranchInfo {
+pub struct TmuxEvent {
+    pub id: u64,
+    /// Unix timestamp (seconds).
+    pub timestamp: u64,
+    pub kind: TmuxEventKind,
+    /// Window the event happened in (`repo:feature` for feature windows).
+    pub window: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTmuxEventsRequest {
+    #[serde(flatten)]
+    pub page: PageParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTmuxEventsResponse {
+    /// Most recent first.
+    pub events: Vec<TmuxEvent>,
+    /// Number of events matching the filter, before pagination.
+    pub total: usize,
+}
+
+// ---- Tasks ----
+
+/// A named task of a repo, from its `.nomadflow.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
     pub name: String,
-    pub is_remote: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub remote_name: Option<String>,
+    /// Shell command, run from the feature's worktree.
+    pub command: String,
+    #[serde(default)]
+    pub description: String,
+    /// Run as a background job with captured output rather than in the
+    /// feature's window, unless the request says otherwise.
+    #[serde(default)]
+    pub background: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ListBranchesRequest {
+pub struct ListTasksRequest {
     pub repo_path: String,
+    pub feature_name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ListBranchesResponse {
-    pub branches: Vec<BranchInfo>,
-    pub default_branch: String,
+pub struct ListTasksResponse {
+    pub tasks: Vec<TaskInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct AttachBranchRequest {
+pub struct FeatureProcessRequest {
     pub repo_path: String,
-    pub branch_name: String,
+    pub feature_name: String,
 }
 
+/// What runs in the foreground of a feature's window.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct AttachBranchResponse {
-    pub worktree_path: String,
-    pub branch: String,
-    pub tmux_window: String,
+pub struct FeatureProcessResponse {
+    pub window: String,
+    /// A program runs instead of the shell waiting for input.
+    pub running: bool,
+    /// Name of the program, or of the shell when none runs.
+    pub command: Option<String>,
+    /// Process group of the program.
+    pub pgid: Option<u32>,
+    /// The program was stopped for being left idle.
+    pub suspended: bool,
 }
 
-// ---- Request models ----
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureOutputRequest {
+    pub repo_path: String,
+    pub feature_name: String,
+    /// How many lines to return, the visible ones included. Defaults to 200.
+    #[serde(default)]
+    pub lines: Option<u32>,
+}
 
+/// The end of what a feature's window shows, scrollback included.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ListFeaturesRequest {
+pub struct FeatureOutputResponse {
+    pub window: String,
+    /// Plain text, without colors, one line per line of the pane.
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunTaskRequest {
     pub repo_path: String,
+    pub feature_name: String,
+    pub task: String,
+    /// Overrides the task's `background` setting.
+    #[serde(default)]
+    pub background: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CreateFeatureRequest {
+pub struct RunTaskResponse {
+    /// Window the task was typed into, when run in the foreground.
+    pub window: Option<String>,
+    /// Background run, to poll with `GET /api/task-runs/{id}`.
+    pub run_id: Option<u64>,
+}
+
+/// A task run as a background job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+    pub id: u64,
+    pub task: String,
     pub repo_path: String,
-    /// Full branch name (e.g. "feature/add-login", "bugfix/crash", "my-branch")
-    #[serde(alias = "featureName")]
-    pub branch_name: String,
-    #[serde(default = "default_base_branch")]
-    pub base_branch: String,
+    pub feature_name: String,
+    pub command: String,
+    /// Unix timestamps (seconds).
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    /// `None` while running, or when the job was killed by a signal.
+    pub exit_code: Option<i32>,
+    /// Interleaved stdout and stderr, keeping the end when too long.
+    pub output: String,
 }
 
-fn default_base_branch() -> String {
-    "main".to_string()
+impl TaskRun {
+    /// The run has ended without exiting successfully.
+    pub fn failed(&self) -> bool {
+        self.finished_at.is_some() && self.exit_code != Some(0)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct DeleteFeatureRequest {
+pub struct ListTaskRunsResponse {
+    /// Most recent first.
+    pub runs: Vec<TaskRun>,
+    /// Number of runs matching the filter, before paging.
+    pub total: usize,
+}
+
+// ---- Jobs ----
+
+/// Answer of `clone-repo`: the clone goes on in the background as a job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloneRepoStarted {
+    pub job_id: u64,
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    CloneRepo,
+    /// A commit, while the repo's git hooks run.
+    Commit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Step a job is at, as reported by the command doing it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobProgress {
+    /// e.g. "Receiving objects".
+    pub phase: String,
+    pub percent: u8,
+}
+
+/// Long operation started by a request and followed with `GET /api/jobs/{id}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: u64,
+    pub kind: JobKind,
+    /// What the job works on, e.g. the path of the repo being cloned.
+    pub target: String,
+    pub status: JobStatus,
+    pub progress: Option<JobProgress>,
+    /// Unix timestamps (seconds).
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    /// The cloned repo, once a clone succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<CloneRepoResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// What the job printed so far, such as the output of git hooks.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListJobsResponse {
+    /// Most recent first.
+    pub jobs: Vec<Job>,
+}
+
+// ---- Search ----
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchRequest {
+    /// Text to look for, matched literally.
+    pub query: String,
+    /// Repos whose features are searched; all repos when empty.
+    #[serde(default)]
+    pub repo_paths: Vec<String>,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Maximum number of matches to return.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
     pub repo_path: String,
     pub feature_name: String,
+    /// Path of the file, relative to the feature's worktree.
+    pub path: String,
+    /// 1-based line number.
+    pub line: u64,
+    /// The matching line, shortened when too long.
+    pub text: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct SwitchFeatureRequest {
+pub struct SearchResponse {
+    pub matches: Vec<SearchMatch>,
+    /// More matches were found than returned.
+    pub truncated: bool,
+}
+
+// ---- Workspace descriptor ----
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDescriptorRequest {
     pub repo_path: String,
     pub feature_name: String,
 }
 
+/// Editor settings of a repo, from the `[editor]` table of its `.nomadflow.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorHints {
+    /// Extensions to recommend, e.g. `rust-lang.rust-analyzer`.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Files to open, relative to the worktree.
+    #[serde(default)]
+    pub open_files: Vec<String>,
+}
+
+/// What an editor needs to open a feature's worktree.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CloneRepoRequest {
-    pub url: String,
-    pub token: Option<String>,
-    pub name: Option<String>,
+pub struct WorkspaceDescriptor {
+    /// `repo/feature`, to title the editor window.
+    pub name: String,
+    pub repo_path: String,
+    pub feature_name: String,
+    pub branch: String,
+    /// Path of the worktree on the server.
+    pub path: String,
+    /// SSH host to open the worktree through, when configured.
+    pub remote_host: Option<String>,
+    /// Folder URI: `file://` locally, `vscode-remote://ssh-remote+host` otherwise.
+    pub uri: String,
+    pub hints: EditorHints,
 }
 
-// ---- Server model (for TUI config) ----
+// ---- Status line ----
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A feature as shown by `nomadflow statusline`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Server {
-    pub id: String,
+pub struct FeatureSnapshot {
+    pub repo: String,
     pub name: String,
-    pub api_url: Option<String>,
-    pub auth_token: Option<String>,
-    pub last_connected: Option<u64>,
+    pub worktree_path: String,
+    /// The worktree has uncommitted changes.
+    pub dirty: bool,
+}
+
+/// What the server last saw, written to the base dir so that
+/// `nomadflow statusline` can answer without asking git or tmux.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusSnapshot {
+    /// Unix timestamp (seconds).
+    pub updated_at: u64,
+    /// Windows of the session running something other than a shell.
+    pub busy_windows: usize,
+    pub tunnel: TunnelState,
+    pub features: Vec<FeatureSnapshot>,
 }
 
 #[cfg(test)]
@@ -182,14 +1621,35 @@ mod tests {
             branch: "feature/my-feature".to_string(),
             is_active: true,
             is_main: false,
+            has_unread: false,
+            suspended: false,
+            upstream: Some("origin/feature/my-feature".to_string()),
+            last_commit_sha: Some("abc123".to_string()),
+            last_commit_at: Some(1_700_000_000),
+            created_at: Some(1_690_000_000),
+            ..Default::default()
         };
         let json = serde_json::to_string(&feature).unwrap();
         assert!(json.contains("\"worktreePath\""));
         assert!(json.contains("\"isActive\""));
         assert!(json.contains("\"isMain\""));
+        assert!(json.contains("\"hasUnread\""));
+        assert!(json.contains("\"lastCommitSha\""));
+        assert!(json.contains("\"lastCommitAt\""));
+        assert!(json.contains("\"createdAt\""));
         assert!(!json.contains("\"worktree_path\""));
     }
 
+    #[test]
+    fn test_feature_from_older_server() {
+        let json = r#"{"name": "x", "worktreePath": "/wt", "branch": "x", "isActive": true}"#;
+        let feature: Feature = serde_json::from_str(json).unwrap();
+        assert!(feature.is_active);
+        assert_eq!(feature.upstream, None);
+        assert_eq!(feature.last_commit_at, None);
+        assert_eq!(feature.created_at, None);
+    }
+
     #[test]
     fn test_switch_response_serialization() {
         let resp = SwitchFeatureResponse {
@@ -225,6 +1685,72 @@ mod tests {
         assert_eq!(req2.branch_name, "x");
     }
 
+    #[test]
+    fn test_bulk_features_request_deserialization() {
+        let json = r#"{"repoPath": "/r", "featureNames": ["a", "b"], "action": "prune"}"#;
+        let req: BulkFeaturesRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.feature_names, vec!["a", "b"]);
+        assert_eq!(req.action, BulkFeatureAction::Prune);
+    }
+
+    #[test]
+    fn test_activity_entry_serialization() {
+        let entry = ActivityEntry {
+            id: 3,
+            timestamp: 1_700_000_000,
+            action: ActivityAction::DeleteFeature,
+            repo_path: "/tmp/repo".to_string(),
+            feature_name: "old".to_string(),
+            undoable: true,
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains("\"action\":\"deleteFeature\""));
+        assert!(json.contains("\"featureName\""));
+
+        let req: ListActivityRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(req.page, PageParams::default());
+    }
+
+    #[test]
+    fn test_create_share_request_defaults_to_read_only() {
+        let json = r#"{"repoPath": "/r", "featureName": "demo"}"#;
+        let req: CreateShareRequest = serde_json::from_str(json).unwrap();
+        assert!(req.read_only);
+        assert_eq!(req.ttl_secs, None);
+
+        let json = r#"{"repoPath": "/r", "featureName": "demo", "readOnly": false, "ttlSecs": 60}"#;
+        let req: CreateShareRequest = serde_json::from_str(json).unwrap();
+        assert!(!req.read_only);
+        assert_eq!(req.ttl_secs, Some(60));
+    }
+
+    #[test]
+    fn test_page_params_flattened_into_request() {
+        let json = r#"{"repoPath": "/r", "limit": 2, "offset": 1, "filter": "LOG"}"#;
+        let req: ListFeaturesRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.repo_path, "/r");
+        assert_eq!(req.page.limit, Some(2));
+        assert_eq!(req.page.offset, 1);
+        assert_eq!(req.page.filter.as_deref(), Some("LOG"));
+    }
+
+    #[test]
+    fn test_page_params_apply() {
+        let items = vec!["login", "logout", "signup", "blog", "catalog"];
+        let page = PageParams {
+            limit: Some(2),
+            offset: 1,
+            filter: Some("LOG".to_string()),
+        };
+        let (slice, total) = page.apply(items.clone(), |s| s);
+        assert_eq!(total, 4);
+        assert_eq!(slice, vec!["logout", "blog"]);
+
+        let (all, total) = PageParams::default().apply(items, |s| s);
+        assert_eq!(total, 5);
+        assert_eq!(all.len(), 5);
+    }
+
     #[test]
     fn test_round_trip_feature() {
         let original = Feature {
@@ -233,11 +1759,51 @@ mod tests {
             branch: "feature/test".to_string(),
             is_active: false,
             is_main: true,
+            has_unread: true,
+            suspended: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&original).unwrap();
         let deserialized: Feature = serde_json::from_str(&json).unwrap();
         assert_eq!(original.name, deserialized.name);
         assert_eq!(original.worktree_path, deserialized.worktree_path);
         assert_eq!(original.is_main, deserialized.is_main);
+        assert_eq!(original.has_unread, deserialized.has_unread);
     }
 }
+
+// ---- Events ----
+
+/// Change of server state, streamed by `GET /api/events`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ServerEvent {
+    FeatureCreated {
+        repo_path: String,
+        feature_name: String,
+    },
+    /// The feature's worktree was removed, its branch deleted or kept.
+    FeatureDeleted {
+        repo_path: String,
+        feature_name: String,
+    },
+    FeatureSwitched {
+        repo_path: String,
+        feature_name: String,
+        tmux_window: String,
+    },
+    RepoCloned {
+        name: String,
+        path: String,
+    },
+    RepoDeleted {
+        name: String,
+        path: String,
+    },
+    /// Something happened in a tmux window, as reported by the hooks.
+    WindowChanged(TmuxEvent),
+    /// The tunnel registered with the relay, on start or after a reconnection.
+    TunnelConnected {
+        public_url: String,
+    },
+}
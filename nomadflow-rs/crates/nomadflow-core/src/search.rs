@@ -0,0 +1,293 @@
+//! Text search across feature worktrees, with ripgrep when it is installed
+//! and a plain directory walk otherwise.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::process::Command;
+
+use crate::models::SearchMatch;
+use crate::shell::command_exists;
+
+/// Longest line excerpt returned for a match, in characters.
+const MAX_TEXT: usize = 300;
+/// Files larger than this are not searched.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+/// Time allowed to search one worktree.
+const ROOT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Directories the fallback walk never enters, on top of hidden ones.
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target"];
+
+/// A worktree to search, and the feature it belongs to.
+#[derive(Debug, Clone)]
+pub struct SearchRoot {
+    pub repo_path: String,
+    pub feature_name: String,
+    pub worktree: PathBuf,
+}
+
+/// A line matching the query, relative to the searched worktree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LineMatch {
+    path: String,
+    line: u64,
+    text: String,
+}
+
+fn excerpt(line: &str) -> String {
+    line.trim_end_matches(['\r', '\n'])
+        .chars()
+        .take(MAX_TEXT)
+        .collect()
+}
+
+/// Parse a line of `rg --null --line-number` output: `./path\0line:text`.
+fn parse_rg_line(line: &str) -> Option<LineMatch> {
+    let (path, rest) = line.split_once('\0')?;
+    let (number, text) = rest.split_once(':')?;
+    Some(LineMatch {
+        path: path.strip_prefix("./").unwrap_or(path).to_string(),
+        line: number.parse().ok()?,
+        text: excerpt(text),
+    })
+}
+
+async fn search_with_rg(
+    root: &Path,
+    query: &str,
+    case_sensitive: bool,
+    limit: usize,
+) -> Vec<LineMatch> {
+    let output = Command::new("rg")
+        .args([
+            "--null",
+            "--line-number",
+            "--no-heading",
+            "--color",
+            "never",
+        ])
+        .args(["--fixed-strings", "--max-filesize", "1M"])
+        .arg(if case_sensitive {
+            "--case-sensitive"
+        } else {
+            "--ignore-case"
+        })
+        .arg("--")
+        .arg(query)
+        .arg(".")
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let Ok(Ok(output)) = tokio::time::timeout(ROOT_TIMEOUT, output).await else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_rg_line)
+        .take(limit)
+        .collect()
+}
+
+/// Search the files under `root` without following symlinks, skipping hidden
+/// directories, dependencies, build output, large and binary files.
+fn walk(
+    root: &Path,
+    dir: &Path,
+    needle: &str,
+    case_sensitive: bool,
+    limit: usize,
+    found: &mut Vec<LineMatch>,
+) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        if found.len() >= limit {
+            return;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let path = entry.path();
+        if file_type.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                walk(root, &path, needle, case_sensitive, limit, found);
+            }
+            continue;
+        }
+        if !file_type.is_file() || entry.metadata().map_or(true, |m| m.len() > MAX_FILE_SIZE) {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(&path) else {
+            continue;
+        };
+        if bytes.contains(&0) {
+            continue;
+        }
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        for (i, line) in String::from_utf8_lossy(&bytes).lines().enumerate() {
+            let matches = if case_sensitive {
+                line.contains(needle)
+            } else {
+                line.to_lowercase().contains(needle)
+            };
+            if matches {
+                found.push(LineMatch {
+                    path: relative.clone(),
+                    line: i as u64 + 1,
+                    text: excerpt(line),
+                });
+                if found.len() >= limit {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+async fn search_with_walk(
+    root: &Path,
+    query: &str,
+    case_sensitive: bool,
+    limit: usize,
+) -> Vec<LineMatch> {
+    let root = root.to_path_buf();
+    let needle = if case_sensitive {
+        query.to_string()
+    } else {
+        query.to_lowercase()
+    };
+    let task = tokio::task::spawn_blocking(move || {
+        let mut found = Vec::new();
+        walk(&root, &root, &needle, case_sensitive, limit, &mut found);
+        found
+    });
+    match tokio::time::timeout(ROOT_TIMEOUT, task).await {
+        Ok(Ok(found)) => found,
+        _ => Vec::new(),
+    }
+}
+
+/// Lines containing `query` in the worktrees of `roots`, searched in order,
+/// up to `limit` matches. Also returns whether matches were left out.
+pub async fn search(
+    roots: &[SearchRoot],
+    query: &str,
+    case_sensitive: bool,
+    limit: usize,
+) -> (Vec<SearchMatch>, bool) {
+    let use_rg = command_exists("rg").await;
+    let mut matches = Vec::new();
+    for root in roots {
+        // One more than needed tells whether the results were cut
+        let wanted = limit + 1 - matches.len();
+        let found = if use_rg {
+            search_with_rg(&root.worktree, query, case_sensitive, wanted).await
+        } else {
+            search_with_walk(&root.worktree, query, case_sensitive, wanted).await
+        };
+        matches.extend(found.into_iter().map(|m| SearchMatch {
+            repo_path: root.repo_path.clone(),
+            feature_name: root.feature_name.clone(),
+            path: m.path,
+            line: m.line,
+            text: m.text,
+        }));
+        if matches.len() > limit {
+            break;
+        }
+    }
+    let truncated = matches.len() > limit;
+    matches.truncate(limit);
+    (matches, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rg_line() {
+        assert_eq!(
+            parse_rg_line("./src/a:b.rs\u{0}12:    let x = 1; // a:b"),
+            Some(LineMatch {
+                path: "src/a:b.rs".to_string(),
+                line: 12,
+                text: "    let x = 1; // a:b".to_string(),
+            })
+        );
+        assert_eq!(parse_rg_line("garbage"), None);
+    }
+
+    #[tokio::test]
+    async fn test_walk_finds_lines() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules/x")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n// TODO: Login\n").unwrap();
+        std::fs::write(root.join(".git/config"), "todo: login").unwrap();
+        std::fs::write(root.join("node_modules/x/i.js"), "todo: login").unwrap();
+        std::fs::write(root.join("blob.bin"), b"todo: login\0").unwrap();
+
+        let found = search_with_walk(root, "todo: login", false, 10).await;
+        assert_eq!(
+            found,
+            vec![LineMatch {
+                path: "src/main.rs".to_string(),
+                line: 2,
+                text: "// TODO: Login".to_string(),
+            }]
+        );
+        assert!(search_with_walk(root, "todo: login", true, 10)
+            .await
+            .is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_walk_stays_in_the_worktree() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let outside = tmp.path().join("outside");
+        let root = tmp.path().join("root");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(outside.join("secret.txt"), "needle").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), root.join("file")).unwrap();
+
+        assert!(search_with_walk(&root, "needle", false, 10)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_reports_truncation() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("a.txt"), "x\nx\nx\n").unwrap();
+        let roots = vec![SearchRoot {
+            repo_path: "/r".to_string(),
+            feature_name: "f".to_string(),
+            worktree: tmp.path().to_path_buf(),
+        }];
+        let (matches, truncated) = search(&roots, "x", false, 2).await;
+        assert_eq!(matches.len(), 2);
+        assert!(truncated);
+        assert_eq!(matches[0].feature_name, "f");
+        let (matches, truncated) = search(&roots, "x", false, 3).await;
+        assert_eq!(matches.len(), 3);
+        assert!(!truncated);
+    }
+}
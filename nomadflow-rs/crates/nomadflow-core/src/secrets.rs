@@ -0,0 +1,199 @@
+//! Encryption at rest for the secrets kept in config files.
+//!
+//! Values are sealed with ChaCha20-Poly1305 and stored as `enc:v1:<base64>`.
+//! The key lives in the OS keychain or, on headless machines, is derived with
+//! Argon2id from the passphrase in `NOMADFLOW_PASSPHRASE`. The auth tokens of
+//! the servers saved by the TUI go to the OS keychain themselves when it is
+//! available.
+
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use serde::{Deserialize, Serialize};
+
+use crate::config::random_bytes;
+use crate::error::{NomadError, Result};
+
+/// Marks an encrypted value.
+pub const PREFIX: &str = "enc:v1:";
+/// Environment variable holding the passphrase for [`KeySource::Passphrase`].
+pub const PASSPHRASE_ENV: &str = "NOMADFLOW_PASSPHRASE";
+/// Stands in a config file for a server auth token kept in the OS keychain.
+pub const KEYCHAIN_REF: &str = "keychain:";
+
+const KEYCHAIN_SERVICE: &str = "nomadflow";
+const KEYCHAIN_ACCOUNT: &str = "config-key";
+const NONCE_LEN: usize = 12;
+
+/// Where the encryption key comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySource {
+    /// Secrets are stored in plain text.
+    #[default]
+    None,
+    Keychain,
+    Passphrase,
+}
+
+/// Where the auth tokens of the servers saved by the TUI are kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenStore {
+    /// The OS keychain, or the file when it is unavailable.
+    #[default]
+    Keychain,
+    /// The servers file, sealed like the other secrets.
+    File,
+}
+
+/// 256-bit key sealing config secrets.
+#[derive(Clone)]
+pub struct SecretKey([u8; 32]);
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+fn keychain_error(e: keyring::Error) -> NomadError {
+    NomadError::Secrets(format!("OS keychain unavailable: {e}"))
+}
+
+impl SecretKey {
+    /// Derive the key from a passphrase with Argon2id.
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| NomadError::Secrets(format!("Failed to derive key: {e}")))?;
+        Ok(Self(key))
+    }
+
+    /// Read the key from the OS keychain, generating and storing one when
+    /// missing if `create` is set.
+    pub fn from_keychain(create: bool) -> Result<Self> {
+        let entry =
+            keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT).map_err(keychain_error)?;
+        match entry.get_password() {
+            Ok(encoded) => {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(encoded.trim())
+                    .ok()
+                    .and_then(|b| <[u8; 32]>::try_from(b).ok())
+                    .ok_or_else(|| {
+                        NomadError::Secrets("Invalid key in the OS keychain".to_string())
+                    })?;
+                Ok(Self(bytes))
+            }
+            Err(keyring::Error::NoEntry) if create => {
+                let mut key = [0u8; 32];
+                key.copy_from_slice(&random_bytes(32)?);
+                entry
+                    .set_password(&base64::engine::general_purpose::STANDARD.encode(key))
+                    .map_err(keychain_error)?;
+                Ok(Self(key))
+            }
+            Err(keyring::Error::NoEntry) => Err(NomadError::Secrets(
+                "No encryption key in the OS keychain".to_string(),
+            )),
+            Err(e) => Err(keychain_error(e)),
+        }
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(&self.0.into())
+    }
+
+    /// Encrypt `value`. Empty and already encrypted values are returned as is.
+    pub fn seal(&self, value: &str) -> Result<String> {
+        if value.is_empty() || is_encrypted(value) {
+            return Ok(value.to_string());
+        }
+        let nonce = random_bytes(NONCE_LEN)?;
+        let ciphertext = self
+            .cipher()
+            .encrypt(Nonce::from_slice(&nonce), value.as_bytes())
+            .map_err(|_| NomadError::Secrets("Encryption failed".to_string()))?;
+        let mut payload = nonce;
+        payload.extend_from_slice(&ciphertext);
+        Ok(format!(
+            "{PREFIX}{}",
+            base64::engine::general_purpose::STANDARD.encode(payload)
+        ))
+    }
+
+    /// Decrypt `value`. Plain-text values are returned as is.
+    pub fn open(&self, value: &str) -> Result<String> {
+        let Some(encoded) = value.strip_prefix(PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let wrong_key =
+            || NomadError::Secrets("Cannot decrypt a secret: wrong key or passphrase".to_string());
+        let payload = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|_| wrong_key())?;
+        if payload.len() < NONCE_LEN {
+            return Err(wrong_key());
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| wrong_key())?;
+        String::from_utf8(plaintext).map_err(|_| wrong_key())
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+fn server_token_entry(server_id: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, &format!("server-token:{server_id}"))
+        .map_err(keychain_error)
+}
+
+/// Keep the auth token of the server `server_id` in the OS keychain.
+pub fn store_server_token(server_id: &str, token: &str) -> Result<()> {
+    server_token_entry(server_id)?
+        .set_password(token)
+        .map_err(keychain_error)
+}
+
+/// Auth token of the server `server_id` kept by [`store_server_token`].
+pub fn load_server_token(server_id: &str) -> Result<String> {
+    server_token_entry(server_id)?
+        .get_password()
+        .map_err(keychain_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = SecretKey::from_passphrase("correct horse", b"0123456789abcdef").unwrap();
+        let sealed = key.seal("s3cret").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("s3cret"));
+        // A fresh nonce every time
+        assert_ne!(sealed, key.seal("s3cret").unwrap());
+        assert_eq!(key.open(&sealed).unwrap(), "s3cret");
+
+        assert_eq!(key.seal("").unwrap(), "");
+        assert_eq!(key.seal(&sealed).unwrap(), sealed);
+        assert_eq!(key.open("plain").unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_wrong_key_fails() {
+        let key = SecretKey::from_passphrase("correct horse", b"0123456789abcdef").unwrap();
+        let other = SecretKey::from_passphrase("battery staple", b"0123456789abcdef").unwrap();
+        let sealed = key.seal("s3cret").unwrap();
+        assert!(matches!(other.open(&sealed), Err(NomadError::Secrets(_))));
+        assert!(key.open("enc:v1:not-base64!").is_err());
+    }
+}
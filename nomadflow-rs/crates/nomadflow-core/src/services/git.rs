@@ -1,13 +1,120 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+
+use crate::branch::check_branch_name;
 use crate::config::Settings;
 use crate::error::{NomadError, Result};
-use crate::models::{BranchInfo, Feature, Repository};
-use crate::shell::{run, run_command};
+use crate::models::{
+    BranchInfo, CommitResponse, DeleteRepoResponse, DiffSummary, DuplicateMode, Feature,
+    FeatureDiff, FileDiffStat, HookFailure, JobProgress, PullFeatureResponse, PullStrategy,
+    PushRejection, PushResponse, Repository,
+};
+use crate::shell::{observe, run, run_command, shell_quote};
+
+/// Longest a clone may take.
+const CLONE_TIMEOUT: Duration = Duration::from_secs(600);
+/// Longest a fetch or pull may take, in seconds.
+const PULL_TIMEOUT_SECS: f64 = 120.0;
+/// Longest `git archive` may take.
+const ARCHIVE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Longest a git hook may run.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(600);
+/// Hook output kept to report a failure. Past it, the beginning is dropped.
+const MAX_HOOK_OUTPUT: usize = 64 * 1024;
+/// Largest diff returned, in bytes. Longer ones are cut.
+const MAX_DIFF_BYTES: usize = 2 * 1024 * 1024;
+
+/// Result of [`GitService::push_feature`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushOutcome {
+    Pushed(PushResponse),
+    /// The remote refused the push, with what git said about it.
+    Rejected {
+        reason: PushRejection,
+        detail: String,
+    },
+}
+
+/// Result of [`GitService::commit_feature`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum CommitOutcome {
+    Committed(CommitResponse),
+    NothingToCommit,
+    /// A hook of the repo refused the commit.
+    HookFailed(HookFailure),
+}
+
+/// Run the `hook` of the repo checked out at `worktree_path`, if it has one,
+/// passing each line it prints to `on_output`. Its failure, with the end of
+/// its output, when it exits non-zero.
+async fn run_git_hook(
+    worktree_path: &str,
+    hook: &str,
+    arg: Option<&str>,
+    on_output: &mut impl FnMut(&str),
+) -> Result<Option<HookFailure>> {
+    let arg = arg
+        .map(|a| format!(" -- {}", shell_quote(a)))
+        .unwrap_or_default();
+    let started = Instant::now();
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "exec git hook run --ignore-missing {hook}{arg} 2>&1"
+        ))
+        .current_dir(worktree_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdout = child.stdout.take();
+    let hook_run = async {
+        let mut output = String::new();
+        if let Some(stdout) = stdout {
+            let mut lines = BufReader::new(stdout).split(b'\n');
+            while let Ok(Some(line)) = lines.next_segment().await {
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim_end_matches('\r');
+                on_output(line);
+                output.push_str(line);
+                output.push('\n');
+                if output.len() > MAX_HOOK_OUTPUT {
+                    let mut cut = output.len() - MAX_HOOK_OUTPUT;
+                    while !output.is_char_boundary(cut) {
+                        cut += 1;
+                    }
+                    output.drain(..cut);
+                }
+            }
+        }
+        (child.wait().await, output)
+    };
+    let finished = tokio::time::timeout(HOOK_TIMEOUT, hook_run).await;
+    observe(&format!("git hook run {hook}"), started.elapsed());
+    let Ok((status, output)) = finished else {
+        return Err(NomadError::Timeout(HOOK_TIMEOUT.as_secs_f64()));
+    };
+    let status = status?;
+    Ok((!status.success()).then(|| HookFailure {
+        hook: hook.to_string(),
+        exit_code: status.code().unwrap_or(-1),
+        output,
+    }))
+}
 
 pub struct GitService {
     repos_dir: PathBuf,
     worktrees_dir: PathBuf,
+    /// Creation times of worktrees, by path.
+    created_at: Mutex<HashMap<String, u64>>,
+    /// Commit times and subjects, by sha.
+    commits: Mutex<HashMap<String, (u64, String)>>,
 }
 
 impl GitService {
@@ -15,6 +122,8 @@ impl GitService {
         Self {
             repos_dir: settings.repos_dir(),
             worktrees_dir: settings.worktrees_dir(),
+            created_at: Mutex::new(HashMap::new()),
+            commits: Mutex::new(HashMap::new()),
         }
     }
 
@@ -39,16 +148,14 @@ impl GitService {
             }
         }
 
+        // read_dir order is unspecified; keep listings stable for pagination
+        repos.sort_by(|a, b| a.name.cmp(&b.name));
         Ok(repos)
     }
 
-    /// Clone a git repository into the repos directory.
-    pub async fn clone_repo(
-        &self,
-        url: &str,
-        token: Option<&str>,
-        name: Option<&str>,
-    ) -> Result<(String, String, String)> {
+    /// Name and destination of a clone of `url` into the repos directory,
+    /// which is created if needed.
+    pub async fn prepare_clone(&self, url: &str, name: Option<&str>) -> Result<(String, PathBuf)> {
         // Extract repo name from URL if not provided
         let repo_name = match name {
             Some(n) if !n.is_empty() => n.to_string(),
@@ -79,7 +186,18 @@ impl GitService {
 
         // Ensure repos directory exists
         tokio::fs::create_dir_all(&self.repos_dir).await?;
+        Ok((repo_name, dest))
+    }
 
+    /// Clone `url` into `dest`, passing each step git reports to
+    /// `on_progress`. Returns the checked out branch.
+    pub async fn clone_into(
+        &self,
+        url: &str,
+        token: Option<&str>,
+        dest: &Path,
+        mut on_progress: impl FnMut(JobProgress),
+    ) -> Result<String> {
         // Build clone URL with token if provided
         let clone_url = if let Some(tok) = token {
             inject_token(url, tok)
@@ -87,22 +205,72 @@ impl GitService {
             url.to_string()
         };
 
-        let dest_str = dest.to_string_lossy();
-        let result = run_command(
-            &format!("git clone {clone_url} {dest_str}"),
-            None,
-            600.0,
-        )
-        .await;
+        let started = Instant::now();
+        let mut child = Command::new("git")
+            .arg("clone")
+            .arg("--progress")
+            .arg(&clone_url)
+            .arg(dest)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stderr = child.stderr.take();
+        let clone = async {
+            // Lines other than progress, shown if the clone fails
+            let mut log = String::new();
+            if let Some(mut stderr) = stderr {
+                let mut buf = vec![0u8; 4096];
+                let mut line = Vec::new();
+                loop {
+                    match stderr.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => {
+                            // Progress lines are redrawn with \r
+                            for &b in &buf[..n] {
+                                if b != b'\r' && b != b'\n' {
+                                    line.push(b);
+                                    continue;
+                                }
+                                let text = String::from_utf8_lossy(&line).into_owned();
+                                line.clear();
+                                match parse_git_progress(&text) {
+                                    Some(progress) => on_progress(progress),
+                                    None if !text.trim().is_empty() => {
+                                        log.push_str(text.trim());
+                                        log.push('\n');
+                                    }
+                                    None => {}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            (child.wait().await, log)
+        };
 
-        if !result.success() {
+        let cloned = tokio::time::timeout(CLONE_TIMEOUT, clone).await;
+        observe("git clone", started.elapsed());
+        let Ok((status, log)) = cloned else {
+            drop(child);
+            let _ = tokio::fs::remove_dir_all(dest).await;
+            return Err(NomadError::Timeout(CLONE_TIMEOUT.as_secs_f64()));
+        };
+        if !status?.success() {
+            let log = match token {
+                Some(tok) => log.replace(tok, "***"),
+                None => log,
+            };
             return Err(NomadError::CommandFailed(format!(
                 "git clone failed: {}",
-                result.stderr
+                log.trim_end()
             )));
         }
 
         // Security: remove token from remote URL
+        let dest_str = dest.to_string_lossy();
         if token.is_some() {
             run(
                 &format!("git remote set-url origin {url}"),
@@ -111,8 +279,7 @@ impl GitService {
             .await;
         }
 
-        let branch = self.get_current_branch(&dest).await;
-        Ok((repo_name, dest.to_string_lossy().to_string(), branch))
+        Ok(self.get_current_branch(dest).await)
     }
 
     /// List all worktrees (features) for a repository.
@@ -137,6 +304,7 @@ impl GitService {
         // Parse worktree list output
         let mut current_worktree: Option<String> = None;
         let mut current_branch: Option<String> = None;
+        let mut heads: HashMap<String, String> = HashMap::new();
 
         for line in result.stdout.lines() {
             let line = line.trim();
@@ -171,6 +339,9 @@ impl GitService {
                         branch,
                         is_active: false,
                         is_main,
+                        has_unread: false,
+                        suspended: false,
+                        ..Default::default()
                     });
                 }
                 current_branch = None;
@@ -178,6 +349,10 @@ impl GitService {
                 current_worktree = Some(rest.to_string());
             } else if let Some(rest) = line.strip_prefix("branch ") {
                 current_branch = Some(rest.to_string());
+            } else if let Some(rest) = line.strip_prefix("HEAD ") {
+                if let Some(wt_path) = &current_worktree {
+                    heads.insert(wt_path.clone(), rest.to_string());
+                }
             }
         }
 
@@ -206,6 +381,9 @@ impl GitService {
                 branch,
                 is_active: false,
                 is_main,
+                has_unread: false,
+                suspended: false,
+                ..Default::default()
             });
         }
 
@@ -226,14 +404,107 @@ impl GitService {
                         branch,
                         is_active: false,
                         is_main: false,
+                        has_unread: false,
+                        suspended: false,
+                        ..Default::default()
                     });
                 }
             }
         }
 
+        self.add_history(repo_path, &heads, &mut features).await;
         Ok(features)
     }
 
+    /// Fill in the upstream, last commit, creation time and status of
+    /// `features`. `heads` gives the commit of worktrees not on a local branch.
+    async fn add_history(
+        &self,
+        repo_path: &str,
+        heads: &HashMap<String, String>,
+        features: &mut [Feature],
+    ) {
+        let result = run(
+            "git for-each-ref --format=\"%(refname:short)%09%(upstream:short)%09%(objectname)\
+             %09%(committerdate:unix)%09%(upstream:track,nobracket)%09%(contents:subject)\" \
+             refs/heads",
+            Some(repo_path),
+        )
+        .await;
+        let refs = if result.success() {
+            parse_branch_refs(&result.stdout)
+        } else {
+            HashMap::new()
+        };
+
+        // One `git status` per worktree, run side by side
+        let mut statuses = tokio::task::JoinSet::new();
+        for (i, feature) in features.iter().enumerate() {
+            let path = feature.worktree_path.clone();
+            statuses.spawn(async move { (i, is_dirty(&path).await) });
+        }
+
+        for feature in features.iter_mut() {
+            if let Some(branch) = refs.get(&feature.branch) {
+                feature.upstream = branch.upstream.clone();
+                feature.last_commit_sha = Some(branch.sha.clone());
+                feature.last_commit_at = branch.committed_at;
+                feature.last_commit_subject = Some(branch.subject.clone());
+                if let Some((ahead, behind)) = branch.ahead_behind {
+                    feature.ahead = Some(ahead);
+                    feature.behind = Some(behind);
+                }
+            } else if let Some(sha) = heads.get(&feature.worktree_path) {
+                feature.last_commit_sha = Some(sha.clone());
+                if let Some((time, subject)) = self.commit(repo_path, sha).await {
+                    feature.last_commit_at = Some(time);
+                    feature.last_commit_subject = Some(subject);
+                }
+            }
+            feature.created_at = self.worktree_created_at(&feature.worktree_path);
+            feature.needs_resolution = needs_resolution(&feature.worktree_path);
+        }
+
+        while let Some(status) = statuses.join_next().await {
+            if let Ok((i, dirty)) = status {
+                features[i].dirty = dirty;
+            }
+        }
+    }
+
+    /// Time and subject of commit `sha`, which never change, so they are
+    /// looked up once.
+    async fn commit(&self, repo_path: &str, sha: &str) -> Option<(u64, String)> {
+        if let Some(commit) = self.commits.lock().unwrap().get(sha) {
+            return Some(commit.clone());
+        }
+        if !sha.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let result = run(&format!("git show -s --format=%ct%x09%s {sha}"), Some(repo_path)).await;
+        if !result.success() {
+            return None;
+        }
+        let (time, subject) = result.stdout.trim_end().split_once('\t')?;
+        let commit = (time.parse().ok()?, subject.to_string());
+        self.commits.lock().unwrap().insert(sha.to_string(), commit.clone());
+        Some(commit)
+    }
+
+    /// Creation time of a worktree: that of its `.git` entry, written by
+    /// `git worktree add` (or the clone for the main worktree).
+    fn worktree_created_at(&self, worktree_path: &str) -> Option<u64> {
+        let mut cache = self.created_at.lock().unwrap();
+        if let Some(time) = cache.get(worktree_path) {
+            return Some(*time);
+        }
+        let meta = std::fs::symlink_metadata(Path::new(worktree_path).join(".git")).ok()?;
+        let time = meta.created().or_else(|_| meta.modified()).ok()?;
+        let time = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        cache.insert(worktree_path.to_string(), time);
+        Some(time)
+    }
+
     /// List all branches (local and remote) for a repository, excluding those already in a worktree.
     pub async fn list_branches(&self, repo_path: &str) -> Result<(Vec<BranchInfo>, String)> {
         // Fetch latest (ignore errors if offline)
@@ -366,6 +637,8 @@ impl GitService {
         branch_name: &str,
         base_branch: Option<&str>,
     ) -> Result<(String, String)> {
+        check_branch_name(branch_name)
+            .map_err(|e| NomadError::InvalidName(format!("'{branch_name}': {e}")))?;
         let repo_path_obj = PathBuf::from(repo_path);
         let repo_name = repo_path_obj
             .file_name()
@@ -445,6 +718,84 @@ impl GitService {
         repo_path: &str,
         feature_name: &str,
     ) -> Result<bool> {
+        self.remove_worktree(repo_path, feature_name).await?;
+
+        // Delete the branch
+        let branch_name = format!("feature/{feature_name}");
+        run(
+            &format!("git branch -D \"{branch_name}\""),
+            Some(repo_path),
+        )
+        .await;
+
+        Ok(true)
+    }
+
+    /// Names of the local and remote-tracking branches of a repo, the latter
+    /// without their remote, as last fetched.
+    pub async fn branch_names(&self, repo_path: &str) -> Vec<String> {
+        let result = run(
+            "git for-each-ref --format='%(refname)' refs/heads refs/remotes",
+            Some(repo_path),
+        )
+        .await;
+        let mut names: Vec<String> = result
+            .stdout
+            .lines()
+            .filter_map(|r| {
+                r.strip_prefix("refs/heads/").or_else(|| {
+                    let remote = r.strip_prefix("refs/remotes/")?;
+                    remote.split_once('/').map(|(_, branch)| branch)
+                })
+            })
+            .filter(|b| *b != "HEAD")
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Remove a feature worktree but keep its branch, so it can be re-attached later.
+    /// Return the commit a local branch points at, if the branch exists.
+    pub async fn branch_tip(&self, repo_path: &str, branch: &str) -> Option<String> {
+        let result = run(
+            &format!("git rev-parse --verify \"refs/heads/{branch}\""),
+            Some(repo_path),
+        )
+        .await;
+        if result.success() {
+            Some(result.stdout.trim().to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Recreate a deleted branch at `sha` (if it is missing) and attach a worktree to it.
+    pub async fn restore_branch(
+        &self,
+        repo_path: &str,
+        branch: &str,
+        sha: &str,
+    ) -> Result<(String, String)> {
+        if self.branch_tip(repo_path, branch).await.is_none() {
+            let result = run(
+                &format!("git branch \"{branch}\" \"{sha}\""),
+                Some(repo_path),
+            )
+            .await;
+            if !result.success() {
+                return Err(NomadError::CommandFailed(format!(
+                    "Failed to restore branch '{}': {}",
+                    branch, result.stderr
+                )));
+            }
+        }
+
+        self.attach_branch(repo_path, branch).await
+    }
+
+    pub async fn remove_worktree(&self, repo_path: &str, feature_name: &str) -> Result<()> {
         let repo_path_obj = PathBuf::from(repo_path);
         let repo_name = repo_path_obj
             .file_name()
@@ -454,6 +805,8 @@ impl GitService {
 
         let worktree_path = self.worktrees_dir.join(&repo_name).join(feature_name);
         let wt = worktree_path.to_string_lossy();
+        // A worktree created again at the same path gets a new creation time
+        self.created_at.lock().unwrap().remove(wt.as_ref());
 
         let result = run(
             &format!("git worktree remove \"{wt}\" --force"),
@@ -469,15 +822,527 @@ impl GitService {
             }
         }
 
-        // Delete the branch
-        let branch_name = format!("feature/{feature_name}");
+        Ok(())
+    }
+
+    /// Remove a repo of the repos directory: delete a clone, or only unlink a
+    /// symlinked repo. With `force`, its worktrees under the worktrees
+    /// directory go first; without it, a repo with worktrees is left alone.
+    pub async fn delete_repo(&self, repo_path: &str, force: bool) -> Result<DeleteRepoResponse> {
+        let path = Path::new(repo_path);
+        let repo_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if path.parent() != Some(self.repos_dir.as_path()) || !path.join(".git").exists() {
+            return Err(NomadError::NotFound(format!("Repository '{repo_path}'")));
+        }
+        let unlinked = tokio::fs::symlink_metadata(path).await?.is_symlink();
+
+        let worktrees: Vec<Feature> = self
+            .list_features(repo_path)
+            .await?
+            .into_iter()
+            .filter(|f| !f.is_main)
+            .collect();
+        if !worktrees.is_empty() && !force {
+            let names: Vec<&str> = worktrees.iter().map(|f| f.name.as_str()).collect();
+            return Err(NomadError::InUse(format!(
+                "'{repo_name}' has worktrees: {}",
+                names.join(", ")
+            )));
+        }
+
+        // Only the worktrees NomadFlow made: others belong to the user
+        let managed_dir = self.worktrees_dir.join(&repo_name);
+        let mut removed_features = Vec::new();
+        for feature in &worktrees {
+            if Path::new(&feature.worktree_path).starts_with(&managed_dir) {
+                self.remove_worktree(repo_path, &feature.name).await?;
+                removed_features.push(feature.name.clone());
+            }
+        }
+        if unlinked {
+            // The repo lives on: forget the worktrees just removed
+            run("git worktree prune", Some(repo_path)).await;
+            tokio::fs::remove_file(path).await?;
+        } else {
+            tokio::fs::remove_dir_all(path).await?;
+        }
+        if managed_dir.exists() {
+            tokio::fs::remove_dir_all(&managed_dir).await?;
+        }
+
+        Ok(DeleteRepoResponse {
+            deleted: true,
+            unlinked,
+            removed_features,
+        })
+    }
+
+    /// Worktrees registered in git, each with whether its directory is gone.
+    pub async fn registered_worktrees(&self, repo_path: &str) -> Result<Vec<(String, bool)>> {
+        let result = run("git worktree list --porcelain", Some(repo_path)).await;
+        if !result.success() {
+            return Err(NomadError::CommandFailed(format!(
+                "git worktree list failed: {}",
+                result.stderr.trim()
+            )));
+        }
+        Ok(parse_worktree_list(&result.stdout))
+    }
+
+    /// Forget the worktrees whose directory is gone.
+    pub async fn prune_worktrees(&self, repo_path: &str) -> bool {
+        run("git worktree prune", Some(repo_path)).await.success()
+    }
+
+    /// Reconnect a moved or unregistered worktree directory to its repository.
+    pub async fn repair_worktree(&self, repo_path: &str, worktree_path: &str) -> bool {
         run(
-            &format!("git branch -D \"{branch_name}\""),
+            &format!("git worktree repair {}", shell_quote(worktree_path)),
             Some(repo_path),
         )
+        .await
+        .success()
+    }
+
+    /// Fast-forward a worktree from its upstream branch.
+    /// Returns the short summary printed by git (e.g. "Already up to date.").
+    pub async fn sync_feature(&self, worktree_path: &str) -> Result<String> {
+        let result = run_command("git pull --ff-only", Some(worktree_path), 120.0).await;
+        if !result.success() {
+            return Err(NomadError::CommandFailed(format!(
+                "git pull --ff-only failed: {}",
+                result.stderr.trim()
+            )));
+        }
+        Ok(result.stdout.lines().last().unwrap_or_default().trim().to_string())
+    }
+
+    /// Copy the repo at `repo_path` to `dest`, from
+    /// [`prepare_clone`](Self::prepare_clone), as an independent repository.
+    /// Its `origin` points at `remote_url`, or at the original's `origin`, so
+    /// that nothing done in the copy reaches the original. Returns the
+    /// checked out branch.
+    pub async fn duplicate_repo(
+        &self,
+        repo_path: &str,
+        dest: &Path,
+        mode: DuplicateMode,
+        remote_url: Option<&str>,
+    ) -> Result<String> {
+        let flag = match mode {
+            DuplicateMode::Clone => "--no-hardlinks",
+            DuplicateMode::Shared => "--shared",
+        };
+        let started = Instant::now();
+        let result = run_command(
+            &format!(
+                "git clone --quiet {flag} {} {}",
+                shell_quote(repo_path),
+                shell_quote(&dest.to_string_lossy())
+            ),
+            None,
+            CLONE_TIMEOUT.as_secs_f64(),
+        )
         .await;
+        observe("git clone", started.elapsed());
+        if !result.success() {
+            let _ = tokio::fs::remove_dir_all(dest).await;
+            return Err(NomadError::CommandFailed(format!(
+                "git clone failed: {}",
+                result.stderr.trim()
+            )));
+        }
 
-        Ok(true)
+        let original_remote = run("git remote get-url origin", Some(repo_path)).await;
+        let remote = match remote_url {
+            Some(url) => Some(url.to_string()),
+            None if original_remote.success() => Some(original_remote.stdout.trim().to_string()),
+            None => None,
+        };
+        let dest_str = dest.to_string_lossy();
+        let repointed = match remote {
+            Some(url) => {
+                run(
+                    &format!("git remote set-url origin {}", shell_quote(&url)),
+                    Some(&dest_str),
+                )
+                .await
+            }
+            None => run("git remote remove origin", Some(&dest_str)).await,
+        };
+        if !repointed.success() {
+            let _ = tokio::fs::remove_dir_all(dest).await;
+            return Err(NomadError::CommandFailed(format!(
+                "Failed to set the remote of the copy: {}",
+                repointed.stderr.trim()
+            )));
+        }
+
+        Ok(self.get_current_branch(dest).await)
+    }
+
+    /// Fetch the upstream of a worktree's branch and bring its commits in,
+    /// with `strategy`. A rebase that conflicts is aborted and its conflicting
+    /// files are reported; a fast-forward of a diverged branch is refused.
+    pub async fn pull_feature(
+        &self,
+        worktree_path: &str,
+        strategy: PullStrategy,
+    ) -> Result<PullFeatureResponse> {
+        let upstream = run(
+            "git rev-parse --abbrev-ref --symbolic-full-name @{u}",
+            Some(worktree_path),
+        )
+        .await;
+        if !upstream.success() {
+            return Err(NomadError::NotFound(format!(
+                "Upstream of the branch in '{worktree_path}'"
+            )));
+        }
+
+        let fetch = run_command("git fetch --quiet", Some(worktree_path), PULL_TIMEOUT_SECS).await;
+        if !fetch.success() {
+            return Err(NomadError::CommandFailed(format!(
+                "git fetch failed: {}",
+                fetch.stderr.trim()
+            )));
+        }
+
+        let behind = run("git rev-list --count HEAD..@{u}", Some(worktree_path)).await;
+        let commits_pulled = behind.stdout.trim().parse().unwrap_or(0);
+        if commits_pulled == 0 {
+            return Ok(PullFeatureResponse::default());
+        }
+
+        let command = match strategy {
+            PullStrategy::FastForward => "git merge --ff-only @{u}",
+            PullStrategy::Rebase => "git rebase --autostash @{u}",
+        };
+        let result = run_command(command, Some(worktree_path), PULL_TIMEOUT_SECS).await;
+        if result.success() {
+            return Ok(PullFeatureResponse {
+                updated: true,
+                commits_pulled,
+                conflicts: Vec::new(),
+            });
+        }
+
+        match strategy {
+            PullStrategy::FastForward => Err(NomadError::InUse(format!(
+                "the branch has diverged from its upstream, pull with a rebase: {}",
+                result.stderr.trim()
+            ))),
+            PullStrategy::Rebase => {
+                let unmerged =
+                    run("git diff --name-only --diff-filter=U", Some(worktree_path)).await;
+                run("git rebase --abort", Some(worktree_path)).await;
+                let conflicts: Vec<String> = unmerged.stdout.lines().map(str::to_string).collect();
+                if conflicts.is_empty() {
+                    return Err(NomadError::CommandFailed(format!(
+                        "git rebase failed: {}",
+                        result.stderr.trim()
+                    )));
+                }
+                Ok(PullFeatureResponse {
+                    updated: false,
+                    commits_pulled: 0,
+                    conflicts,
+                })
+            }
+        }
+    }
+
+    /// Commit the changes of a worktree to `paths`, or all of them, untracked
+    /// files included. The repo's `pre-commit` and `commit-msg` hooks run
+    /// first unless `skip_hooks`, each line they print passed to `on_output`.
+    pub async fn commit_feature(
+        &self,
+        worktree_path: &str,
+        message: &str,
+        paths: Option<&[String]>,
+        skip_hooks: bool,
+        mut on_output: impl FnMut(&str),
+    ) -> Result<CommitOutcome> {
+        let pathspec = paths
+            .map(|paths| {
+                let quoted: Vec<String> = paths.iter().map(|p| shell_quote(p)).collect();
+                format!(" -- {}", quoted.join(" "))
+            })
+            .unwrap_or_default();
+        let staged = run(&format!("git add --all{pathspec}"), Some(worktree_path)).await;
+        if !staged.success() {
+            return Err(NomadError::CommandFailed(format!(
+                "git add failed: {}",
+                staged.stderr.trim()
+            )));
+        }
+        let unchanged = run(
+            &format!("git diff --cached --quiet{pathspec}"),
+            Some(worktree_path),
+        )
+        .await;
+        if unchanged.success() {
+            return Ok(CommitOutcome::NothingToCommit);
+        }
+
+        // The message goes through a file that the commit-msg hook may edit
+        let message_file = run(
+            "git rev-parse --git-path NOMADFLOW_COMMIT_MSG",
+            Some(worktree_path),
+        )
+        .await
+        .stdout
+        .trim()
+        .to_string();
+        let message_file = Path::new(worktree_path).join(message_file);
+        tokio::fs::write(&message_file, message).await?;
+        let outcome = self
+            .commit_with_hooks(
+                worktree_path,
+                &message_file,
+                &pathspec,
+                skip_hooks,
+                &mut on_output,
+            )
+            .await;
+        let _ = tokio::fs::remove_file(&message_file).await;
+        outcome
+    }
+
+    async fn commit_with_hooks(
+        &self,
+        worktree_path: &str,
+        message_file: &Path,
+        pathspec: &str,
+        skip_hooks: bool,
+        on_output: &mut impl FnMut(&str),
+    ) -> Result<CommitOutcome> {
+        let message_arg = message_file.to_string_lossy();
+        if !skip_hooks {
+            let hooks = [
+                ("pre-commit", None),
+                ("commit-msg", Some(message_arg.as_ref())),
+            ];
+            for (hook, arg) in hooks {
+                if let Some(failure) = run_git_hook(worktree_path, hook, arg, on_output).await? {
+                    return Ok(CommitOutcome::HookFailed(failure));
+                }
+            }
+        }
+
+        // Only the given paths, even if others were staged before. The hooks
+        // git would run again have already run.
+        let commit = run(
+            &format!(
+                "git commit --quiet --no-verify -F {}{pathspec}",
+                shell_quote(&message_arg)
+            ),
+            Some(worktree_path),
+        )
+        .await;
+        if !commit.success() {
+            let output = if commit.stderr.trim().is_empty() {
+                &commit.stdout
+            } else {
+                &commit.stderr
+            };
+            return Err(NomadError::CommandFailed(format!(
+                "git commit failed: {}",
+                output.trim()
+            )));
+        }
+
+        let sha = run("git rev-parse --short HEAD", Some(worktree_path)).await;
+        let files = run("git show --name-only --format= HEAD", Some(worktree_path)).await;
+        Ok(CommitOutcome::Committed(CommitResponse {
+            commit: sha.stdout.trim().to_string(),
+            files_changed: files.stdout.lines().filter(|l| !l.is_empty()).count() as u32,
+        }))
+    }
+
+    /// Push `branch`, checked out in a worktree, to its upstream, or to a
+    /// branch of the same name on `origin` which becomes its upstream.
+    pub async fn push_feature(&self, worktree_path: &str, branch: &str) -> Result<PushOutcome> {
+        let config = |key: String| async move {
+            let result = run(&format!("git config {key}"), Some(worktree_path)).await;
+            Some(result.stdout.trim().to_string()).filter(|v| result.success() && !v.is_empty())
+        };
+        let quoted = shell_quote(branch);
+        let remote = config(format!("branch.{quoted}.remote")).await;
+        let merge = config(format!("branch.{quoted}.merge")).await;
+
+        let (command, remote, set_upstream) = match (remote, merge) {
+            (Some(remote), Some(merge)) => (
+                format!("git push {} HEAD:{}", shell_quote(&remote), shell_quote(&merge)),
+                remote,
+                false,
+            ),
+            _ => (
+                format!("git push --set-upstream origin {quoted}"),
+                "origin".to_string(),
+                true,
+            ),
+        };
+        let result = run_command(&command, Some(worktree_path), PULL_TIMEOUT_SECS).await;
+        if result.success() {
+            return Ok(PushOutcome::Pushed(PushResponse {
+                remote,
+                branch: branch.to_string(),
+                set_upstream,
+            }));
+        }
+        match parse_push_rejection(&result.stderr) {
+            Some(reason) => Ok(PushOutcome::Rejected {
+                reason,
+                detail: result.stderr.trim().to_string(),
+            }),
+            None => Err(NomadError::CommandFailed(format!(
+                "git push failed: {}",
+                result.stderr.trim()
+            ))),
+        }
+    }
+
+    /// URL of the remote `branch` is pushed to: its upstream's, or `origin`.
+    pub async fn push_remote_url(&self, worktree_path: &str, branch: &str) -> Option<String> {
+        let configured = run(
+            &format!("git config branch.{}.remote", shell_quote(branch)),
+            Some(worktree_path),
+        )
+        .await;
+        let remote = Some(configured.stdout.trim())
+            .filter(|r| configured.success() && !r.is_empty())
+            .unwrap_or("origin");
+        self.remote_url(worktree_path, remote).await
+    }
+
+    /// URL of `remote` in the repo or worktree at `path`.
+    pub async fn remote_url(&self, path: &str, remote: &str) -> Option<String> {
+        let result = run(
+            &format!("git remote get-url {}", shell_quote(remote)),
+            Some(path),
+        )
+        .await;
+        Some(result.stdout.trim().to_string()).filter(|u| result.success() && !u.is_empty())
+    }
+
+    /// Subject line of the last commit of a worktree.
+    pub async fn last_commit_subject(&self, worktree_path: &str) -> Option<String> {
+        let result = run("git log -1 --format=%s", Some(worktree_path)).await;
+        Some(result.stdout.trim().to_string()).filter(|s| result.success() && !s.is_empty())
+    }
+
+    /// The files of a worktree's last commit, as a gzipped tarball under
+    /// `prefix/`. Uncommitted changes are left out.
+    pub async fn archive(&self, worktree_path: &str, prefix: &str) -> Result<Vec<u8>> {
+        let started = Instant::now();
+        let archive = Command::new("git")
+            .args(["archive", "--format=tar.gz"])
+            .arg(format!("--prefix={prefix}/"))
+            .arg("HEAD")
+            .current_dir(worktree_path)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(ARCHIVE_TIMEOUT, archive).await;
+        observe("git archive", started.elapsed());
+        let output = output.map_err(|_| NomadError::Timeout(ARCHIVE_TIMEOUT.as_secs_f64()))??;
+        if !output.status.success() {
+            return Err(NomadError::CommandFailed(format!(
+                "git archive failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+
+    /// Commit a worktree's branch forked from `base` at. `NotFound` when
+    /// `base` is no branch or commit.
+    async fn merge_base(&self, worktree_path: &str, base: &str) -> Result<String> {
+        let unknown = || NomadError::NotFound(format!("Unknown base branch '{base}'"));
+        // Quoting does not stop git from taking it as an option
+        if base.is_empty() || base.starts_with('-') {
+            return Err(unknown());
+        }
+        let commit = run(
+            &format!(
+                "git rev-parse --verify --quiet {}",
+                shell_quote(&format!("{base}^{{commit}}"))
+            ),
+            Some(worktree_path),
+        )
+        .await;
+        if !commit.success() {
+            return Err(unknown());
+        }
+        let result = run(
+            &format!("git merge-base {} HEAD", commit.stdout.trim()),
+            Some(worktree_path),
+        )
+        .await;
+        if !result.success() {
+            return Err(unknown());
+        }
+        Ok(result.stdout.trim().to_string())
+    }
+
+    /// Changes of a worktree since it forked from `base`, and its staged and
+    /// unstaged changes. Untracked files are left out.
+    pub async fn feature_diff(&self, worktree_path: &str, base: &str) -> Result<FeatureDiff> {
+        let merge_base = self.merge_base(worktree_path, base).await?;
+        let git_diff = |args: String| async move {
+            let result = run(
+                &format!("git diff --no-color --no-ext-diff {args}"),
+                Some(worktree_path),
+            )
+            .await;
+            if !result.success() {
+                return Err(NomadError::CommandFailed(format!(
+                    "git diff failed: {}",
+                    result.stderr.trim()
+                )));
+            }
+            Ok(truncate_diff(result.stdout))
+        };
+        let (diff, cut) = git_diff(merge_base.clone()).await?;
+        let (staged, staged_cut) = git_diff("--cached".to_string()).await?;
+        let (unstaged, unstaged_cut) = git_diff(String::new()).await?;
+        Ok(FeatureDiff {
+            base: base.to_string(),
+            merge_base,
+            diff,
+            staged,
+            unstaged,
+            truncated: cut || staged_cut || unstaged_cut,
+        })
+    }
+
+    /// Lines added and deleted per file by [`feature_diff`](Self::feature_diff).
+    pub async fn diff_summary(&self, worktree_path: &str, base: &str) -> Result<DiffSummary> {
+        let merge_base = self.merge_base(worktree_path, base).await?;
+        let result = run(
+            &format!("git diff --numstat --no-renames {merge_base}"),
+            Some(worktree_path),
+        )
+        .await;
+        if !result.success() {
+            return Err(NomadError::CommandFailed(format!(
+                "git diff failed: {}",
+                result.stderr.trim()
+            )));
+        }
+        let files: Vec<FileDiffStat> = result.stdout.lines().filter_map(parse_numstat).collect();
+        Ok(DiffSummary {
+            base: base.to_string(),
+            merge_base,
+            additions: files.iter().filter_map(|f| f.additions).sum(),
+            deletions: files.iter().filter_map(|f| f.deletions).sum(),
+            files,
+        })
     }
 
     /// Get the current branch of a repository.
@@ -576,6 +1441,170 @@ pub fn derive_worktree_name(branch_name: &str, worktrees_dir: &Path) -> String {
     base // unreachable in practice
 }
 
+/// Paths and prunable flags of `git worktree list --porcelain` output.
+fn parse_worktree_list(output: &str) -> Vec<(String, bool)> {
+    let mut worktrees: Vec<(String, bool)> = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktrees.push((path.to_string(), false));
+        } else if line == "prunable" || line.starts_with("prunable ") {
+            if let Some(last) = worktrees.last_mut() {
+                last.1 = true;
+            }
+        }
+    }
+    worktrees
+}
+
+/// A local branch, from `git for-each-ref`.
+struct BranchRef {
+    upstream: Option<String>,
+    sha: String,
+    committed_at: Option<u64>,
+    /// Commits ahead of and behind the upstream.
+    ahead_behind: Option<(u32, u32)>,
+    subject: String,
+}
+
+/// Parse `git for-each-ref` lines of tab-separated `name upstream sha time
+/// track subject` by branch name. The upstream and track may be empty.
+fn parse_branch_refs(output: &str) -> HashMap<String, BranchRef> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let name = fields.next().map(str::trim).filter(|n| !n.is_empty())?;
+            let upstream = fields.next()?;
+            let sha = fields.next()?;
+            let committed_at = fields.next().and_then(|t| t.parse().ok());
+            let track = fields.next().unwrap_or_default();
+            let upstream = Some(upstream.to_string()).filter(|u| !u.is_empty());
+            let branch = BranchRef {
+                ahead_behind: upstream.as_ref().and_then(|_| parse_track(track)),
+                upstream,
+                sha: sha.to_string(),
+                committed_at,
+                subject: fields.next().unwrap_or_default().trim_end().to_string(),
+            };
+            Some((name.to_string(), branch))
+        })
+        .collect()
+}
+
+/// Ahead and behind counts of `%(upstream:track,nobracket)`, such as
+/// `ahead 2, behind 1`, or empty when in sync. `None` if the upstream is gone.
+/// `diff` cut to [`MAX_DIFF_BYTES`] at a line end, and whether it was.
+fn truncate_diff(mut diff: String) -> (String, bool) {
+    if diff.len() <= MAX_DIFF_BYTES {
+        return (diff, false);
+    }
+    let end = diff[..MAX_DIFF_BYTES].rfind('\n').map_or(0, |i| i + 1);
+    diff.truncate(end);
+    (diff, true)
+}
+
+/// A line of `git diff --numstat`: additions, deletions and path, with `-`
+/// counts for binary files.
+fn parse_numstat(line: &str) -> Option<FileDiffStat> {
+    let mut fields = line.splitn(3, '\t');
+    let additions = fields.next()?;
+    let deletions = fields.next()?;
+    let path = fields.next()?;
+    Some(FileDiffStat {
+        path: path.to_string(),
+        additions: additions.parse().ok(),
+        deletions: deletions.parse().ok(),
+    })
+}
+
+/// Why git says a push was refused, if it was refused rather than failed.
+fn parse_push_rejection(stderr: &str) -> Option<PushRejection> {
+    if stderr.contains("[remote rejected]") {
+        Some(PushRejection::RemoteRejected)
+    } else if stderr.contains("[rejected]") {
+        Some(PushRejection::NonFastForward)
+    } else if stderr.contains("Authentication failed")
+        || stderr.contains("could not read Username")
+        || stderr.contains("Permission denied")
+    {
+        Some(PushRejection::Unauthorized)
+    } else {
+        None
+    }
+}
+
+fn parse_track(track: &str) -> Option<(u32, u32)> {
+    let (mut ahead, mut behind) = (0, 0);
+    for part in track.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.split_once(' ')? {
+            ("ahead", n) => ahead = n.parse().ok()?,
+            ("behind", n) => behind = n.parse().ok()?,
+            _ => return None,
+        }
+    }
+    Some((ahead, behind))
+}
+
+/// Whether the worktree at `path` has uncommitted changes, untracked files
+/// included. Takes no lock on the index, so that it never gets in the way of
+/// the user's own git commands.
+async fn is_dirty(path: &str) -> bool {
+    let result = run("git --no-optional-locks status --porcelain", Some(path)).await;
+    result.success() && !result.stdout.trim().is_empty()
+}
+
+/// Whether a rebase, merge, cherry-pick or revert stopped in the worktree at
+/// `path` and waits for its conflicts to be resolved.
+fn needs_resolution(path: &str) -> bool {
+    let dot_git = Path::new(path).join(".git");
+    let git_dir = match std::fs::read_to_string(&dot_git) {
+        // A linked worktree's `.git` is a file naming its git dir
+        Ok(content) => match content.trim().strip_prefix("gitdir: ") {
+            Some(dir) => Path::new(path).join(dir),
+            None => return false,
+        },
+        Err(_) => dot_git,
+    };
+    [
+        "rebase-merge",
+        "rebase-apply",
+        "MERGE_HEAD",
+        "CHERRY_PICK_HEAD",
+        "REVERT_HEAD",
+    ]
+    .iter()
+    .any(|f| git_dir.join(f).exists())
+}
+
+/// Shell command typed in a feature's window to resolve its conflicts: the
+/// rebase onto the upstream unless one is already `in_progress`, then
+/// `git mergetool` with `mergetool`, or `git status` when it is empty.
+pub fn resolve_conflicts_command(mergetool: &str, in_progress: bool) -> String {
+    let tool = if mergetool.is_empty() {
+        "git status".to_string()
+    } else {
+        format!("git mergetool --tool={}", shell_quote(mergetool))
+    };
+    if in_progress {
+        tool
+    } else {
+        format!("git rebase --autostash @{{u}} || {tool}")
+    }
+}
+
+/// Step of a `git clone --progress` line such as
+/// `Receiving objects:  42% (420/1000), 1.20 MiB | 2.00 MiB/s`.
+fn parse_git_progress(line: &str) -> Option<JobProgress> {
+    let line = line.trim();
+    let line = line.strip_prefix("remote: ").unwrap_or(line);
+    let (phase, rest) = line.split_once(':')?;
+    let (percent, _) = rest.split_once('%')?;
+    Some(JobProgress {
+        phase: phase.trim().to_string(),
+        percent: percent.trim().parse().ok()?,
+    })
+}
+
 /// Inject a token into a git HTTPS URL.
 fn inject_token(url: &str, token: &str) -> String {
     if let Some(rest) = url.strip_prefix("https://") {
@@ -618,6 +1647,20 @@ mod tests {
         assert_eq!(sanitize_name("with.dots_and-dashes"), "with.dots_and-dashes");
     }
 
+    #[test]
+    fn test_parse_worktree_list() {
+        let output = "worktree /repos/app\nHEAD abc\nbranch refs/heads/main\n\n\
+                      worktree /worktrees/app/gone\nHEAD def\nbranch refs/heads/feature/gone\n\
+                      prunable gitdir file points to non-existent location\n";
+        assert_eq!(
+            parse_worktree_list(output),
+            vec![
+                ("/repos/app".to_string(), false),
+                ("/worktrees/app/gone".to_string(), true)
+            ]
+        );
+    }
+
     #[test]
     fn test_inject_token() {
         assert_eq!(
@@ -626,6 +1669,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_git_progress() {
+        assert_eq!(
+            parse_git_progress("Receiving objects:  42% (420/1000), 1.20 MiB | 2.00 MiB/s"),
+            Some(JobProgress {
+                phase: "Receiving objects".to_string(),
+                percent: 42,
+            })
+        );
+        assert_eq!(
+            parse_git_progress("remote: Counting objects: 100% (5/5), done."),
+            Some(JobProgress {
+                phase: "Counting objects".to_string(),
+                percent: 100,
+            })
+        );
+        assert_eq!(parse_git_progress("Cloning into 'repo'..."), None);
+        assert_eq!(parse_git_progress("fatal: repository not found"), None);
+    }
+
     #[tokio::test]
     async fn test_list_repos_empty_dir() {
         let tmp = TempDir::new().unwrap();
@@ -664,6 +1727,39 @@ mod tests {
         assert_eq!(repos[0].name, "test-repo");
     }
 
+    #[tokio::test]
+    async fn test_clone_into() {
+        let tmp = TempDir::new().unwrap();
+        let settings = Settings {
+            paths: crate::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        let origin = tmp.path().join("origin");
+        std::fs::create_dir_all(&origin).unwrap();
+        let origin_str = origin.to_string_lossy().to_string();
+        run("git init", Some(&origin_str)).await;
+        run("git commit --allow-empty -m init", Some(&origin_str)).await;
+
+        let svc = GitService::new(&settings);
+        let (name, dest) = svc.prepare_clone(&origin_str, Some("copy")).await.unwrap();
+        assert_eq!(name, "copy");
+        let branch = svc.clone_into(&origin_str, None, &dest, |_| {}).await.unwrap();
+        assert_ne!(branch, "unknown");
+        assert!(matches!(
+            svc.prepare_clone(&origin_str, Some("copy")).await,
+            Err(NomadError::AlreadyExists(_))
+        ));
+
+        let missing = tmp.path().join("missing").to_string_lossy().to_string();
+        let (_, dest) = svc.prepare_clone(&missing, None).await.unwrap();
+        assert!(matches!(
+            svc.clone_into(&missing, None, &dest, |_| {}).await,
+            Err(NomadError::CommandFailed(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_get_current_branch() {
         let tmp = TempDir::new().unwrap();
@@ -720,6 +1816,14 @@ mod tests {
         assert!(wt_path.contains("test-feat"));
         assert_eq!(branch, "feature/test-feat");
 
+        // Names git or tmux would choke on are refused before running anything
+        let err = svc
+            .create_feature(&repo_path, "feature/x\"; touch pwned", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, NomadError::InvalidName(_)));
+        assert!(!repo_dir.join("pwned").exists());
+
         // List features
         let features = svc.list_features(&repo_path).await.unwrap();
         let feat = features.iter().find(|f| f.name == "test-feat");
@@ -729,6 +1833,367 @@ mod tests {
         // Main should be present too
         let main = features.iter().find(|f| f.is_main);
         assert!(main.is_some());
+
+        // Both are at the initial commit; neither has an upstream
+        let (feat, main) = (feat.unwrap(), main.unwrap());
+        assert!(feat.last_commit_sha.is_some());
+        assert_eq!(feat.last_commit_sha, main.last_commit_sha);
+        assert!(feat.last_commit_at.is_some());
+        assert!(feat.created_at.is_some());
+        assert_eq!(feat.upstream, None);
+        assert_eq!(feat.ahead, None);
+        assert_eq!(main.last_commit_subject.as_deref(), Some("init"));
+        assert!(!feat.dirty);
+
+        // An untracked file is uncommitted work
+        std::fs::write(Path::new(&feat.worktree_path).join("notes.txt"), "wip").unwrap();
+
+        // A detached worktree still gets its commit
+        let detached = settings.worktrees_dir().join("test-repo").join("detached");
+        run(
+            &format!("git worktree add --detach \"{}\"", detached.display()),
+            Some(&repo_path),
+        )
+        .await;
+        let features = svc.list_features(&repo_path).await.unwrap();
+        let detached = features.iter().find(|f| f.name == "detached").unwrap();
+        assert_eq!(detached.last_commit_sha, main.last_commit_sha);
+        assert_eq!(detached.last_commit_at, main.last_commit_at);
+        assert_eq!(detached.last_commit_subject.as_deref(), Some("init"));
+        assert!(!detached.dirty);
+        assert!(features.iter().any(|f| f.name == "test-feat" && f.dirty));
+    }
+
+    #[test]
+    fn test_parse_branch_refs() {
+        let refs = parse_branch_refs(
+            "main\torigin/main\tabc123\t1700000000\tahead 2, behind 1\tFix login\n\
+             feature/x\t\tdef456\t1700000100\t\tAdd x, y and z\n\
+             old\torigin/old\t0123ab\t1700000200\tgone\tWip\n",
+        );
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs["main"].upstream.as_deref(), Some("origin/main"));
+        assert_eq!(refs["main"].ahead_behind, Some((2, 1)));
+        assert_eq!(refs["main"].subject, "Fix login");
+        assert_eq!(refs["feature/x"].upstream, None);
+        assert_eq!(refs["feature/x"].ahead_behind, None);
+        assert_eq!(refs["feature/x"].sha, "def456");
+        assert_eq!(refs["feature/x"].committed_at, Some(1_700_000_100));
+        assert_eq!(refs["feature/x"].subject, "Add x, y and z");
+        assert_eq!(refs["old"].ahead_behind, None);
+
+        assert_eq!(parse_track(""), Some((0, 0)));
+        assert_eq!(parse_track("behind 3"), Some((0, 3)));
+        assert_eq!(parse_track("gone"), None);
+    }
+
+    #[test]
+    fn test_parse_numstat() {
+        assert_eq!(
+            parse_numstat("3\t1\tsrc/main.rs"),
+            Some(FileDiffStat {
+                path: "src/main.rs".to_string(),
+                additions: Some(3),
+                deletions: Some(1),
+            })
+        );
+        assert_eq!(
+            parse_numstat("-\t-\tlogo.png"),
+            Some(FileDiffStat {
+                path: "logo.png".to_string(),
+                additions: None,
+                deletions: None,
+            })
+        );
+        assert_eq!(parse_numstat(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_feature_diff() {
+        let tmp = TempDir::new().unwrap();
+        let repo = tmp.path().to_string_lossy().to_string();
+        let git = |cmd: &'static str| {
+            let repo = repo.clone();
+            async move {
+                let full = format!("git -c user.name=t -c user.email=t@t {cmd}");
+                assert!(run(&full, Some(&repo)).await.success(), "{cmd}");
+            }
+        };
+        git("init -b main").await;
+        std::fs::write(tmp.path().join("a"), "one\n").unwrap();
+        git("add a").await;
+        git("commit -m one").await;
+        git("checkout -q -b feature/x").await;
+        std::fs::write(tmp.path().join("a"), "one\ntwo\n").unwrap();
+        git("commit -qam two").await;
+        std::fs::write(tmp.path().join("b"), "staged\n").unwrap();
+        git("add b").await;
+        std::fs::write(tmp.path().join("a"), "two\n").unwrap();
+
+        let svc = GitService::new(&Settings::default());
+        let diff = svc.feature_diff(&repo, "main").await.unwrap();
+        assert!(diff.diff.contains("+staged"));
+        assert!(diff.diff.contains("-one"));
+        assert!(diff.staged.contains("+staged") && !diff.staged.contains("-one"));
+        assert!(diff.unstaged.contains("-one") && !diff.unstaged.contains("+staged"));
+        assert!(!diff.truncated);
+
+        let summary = svc.diff_summary(&repo, "main").await.unwrap();
+        assert_eq!(summary.files.len(), 2);
+        assert_eq!((summary.additions, summary.deletions), (2, 1));
+
+        assert!(matches!(
+            svc.feature_diff(&repo, "nope").await,
+            Err(NomadError::NotFound(_))
+        ));
+        let out = tmp.path().join("out");
+        let option = format!("--output={}", out.display());
+        assert!(matches!(
+            svc.feature_diff(&repo, &option).await,
+            Err(NomadError::NotFound(_))
+        ));
+        assert!(!out.exists());
+    }
+
+    #[tokio::test]
+    async fn test_archive() {
+        let tmp = TempDir::new().unwrap();
+        let repo = tmp.path().to_string_lossy().to_string();
+        let git = |cmd: &str| format!("git -c user.name=t -c user.email=t@t {cmd}");
+        assert!(run(&git("init -b main"), Some(&repo)).await.success());
+        std::fs::write(tmp.path().join("a"), "one\n").unwrap();
+        assert!(run(&git("add a"), Some(&repo)).await.success());
+        assert!(run(&git("commit -qm one"), Some(&repo)).await.success());
+        std::fs::write(tmp.path().join("b"), "untracked\n").unwrap();
+
+        let svc = GitService::new(&Settings::default());
+        let archive = svc.archive(&repo, "app-x").await.unwrap();
+        // gzip magic
+        assert_eq!(archive[..2], [0x1f, 0x8b]);
+        let out = tmp.path().join("out.tar.gz");
+        std::fs::write(&out, &archive).unwrap();
+        let listing = run(&format!("tar -tzf {}", out.display()), None).await;
+        assert_eq!(
+            listing.stdout.lines().collect::<Vec<_>>(),
+            ["app-x/", "app-x/a"]
+        );
+
+        let empty = TempDir::new().unwrap();
+        let empty_repo = empty.path().to_string_lossy().to_string();
+        assert!(run("git init -q", Some(&empty_repo)).await.success());
+        assert!(matches!(
+            svc.archive(&empty_repo, "x").await,
+            Err(NomadError::CommandFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_push_rejection() {
+        let stale = " ! [rejected]        main -> main (fetch first)\n\
+                     error: failed to push some refs to 'origin'";
+        assert_eq!(parse_push_rejection(stale), Some(PushRejection::NonFastForward));
+        let hook = " ! [remote rejected] main -> main (protected branch hook declined)";
+        assert_eq!(parse_push_rejection(hook), Some(PushRejection::RemoteRejected));
+        let auth = "fatal: Authentication failed for 'https://example.com/app.git/'";
+        assert_eq!(parse_push_rejection(auth), Some(PushRejection::Unauthorized));
+        assert_eq!(parse_push_rejection("fatal: unable to access"), None);
+    }
+
+    #[tokio::test]
+    async fn test_commit_and_push_feature() {
+        let tmp = TempDir::new().unwrap();
+        let dir = |name: &str| tmp.path().join(name).to_string_lossy().to_string();
+        std::fs::create_dir_all(tmp.path().join("remote")).unwrap();
+        run("git init --bare -b main", Some(&dir("remote"))).await;
+        let tmp_dir = tmp.path().to_string_lossy().to_string();
+        run("git clone -q remote work", Some(&tmp_dir)).await;
+        run("git checkout -q -b feature/x", Some(&dir("work"))).await;
+        run("git config user.name t", Some(&dir("work"))).await;
+        run("git config user.email t@t", Some(&dir("work"))).await;
+
+        let svc = GitService::new(&Settings::default());
+        assert_eq!(
+            svc.commit_feature(&dir("work"), "nothing", None, false, |_| {}).await.unwrap(),
+            CommitOutcome::NothingToCommit
+        );
+
+        std::fs::write(tmp.path().join("work/a"), "a").unwrap();
+        std::fs::write(tmp.path().join("work/b"), "b").unwrap();
+        let only_a = ["a".to_string()];
+        let CommitOutcome::Committed(committed) = svc
+            .commit_feature(&dir("work"), "Add a", Some(&only_a), false, |_| {})
+            .await
+            .unwrap()
+        else {
+            panic!("nothing was committed");
+        };
+        assert_eq!(committed.files_changed, 1);
+        assert!(is_dirty(&dir("work")).await, "b must stay uncommitted");
+
+        let pushed = svc.push_feature(&dir("work"), "feature/x").await.unwrap();
+        assert_eq!(
+            pushed,
+            PushOutcome::Pushed(PushResponse {
+                remote: "origin".to_string(),
+                branch: "feature/x".to_string(),
+                set_upstream: true,
+            })
+        );
+        let pushed = svc.push_feature(&dir("work"), "feature/x").await.unwrap();
+        assert!(matches!(pushed, PushOutcome::Pushed(PushResponse { set_upstream: false, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_commit_hooks() {
+        let tmp = TempDir::new().unwrap();
+        let work = tmp.path().to_string_lossy().to_string();
+        run("git init -q -b main", Some(&work)).await;
+        run("git config user.name t", Some(&work)).await;
+        run("git config user.email t@t", Some(&work)).await;
+        let hook = tmp.path().join(".git/hooks/commit-msg");
+        std::fs::write(
+            &hook,
+            "#!/bin/sh\necho checking\ngrep -q '^feat' \"$1\" || { echo 'not conventional'; exit 3; }\n",
+        )
+        .unwrap();
+        run(&format!("chmod +x {}", hook.display()), None).await;
+        std::fs::write(tmp.path().join("a"), "a").unwrap();
+
+        let svc = GitService::new(&Settings::default());
+        let mut lines = Vec::new();
+        let outcome = svc
+            .commit_feature(&work, "Add a", None, false, |l| lines.push(l.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(lines, ["checking", "not conventional"]);
+        assert_eq!(
+            outcome,
+            CommitOutcome::HookFailed(HookFailure {
+                hook: "commit-msg".to_string(),
+                exit_code: 3,
+                output: "checking\nnot conventional\n".to_string(),
+            })
+        );
+
+        let skipped = svc.commit_feature(&work, "Add a", None, true, |_| {}).await.unwrap();
+        assert!(matches!(skipped, CommitOutcome::Committed(_)));
+        std::fs::write(tmp.path().join("b"), "b").unwrap();
+        let conventional = svc.commit_feature(&work, "feat: b", None, false, |_| {}).await.unwrap();
+        assert!(matches!(conventional, CommitOutcome::Committed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_repo() {
+        let tmp = TempDir::new().unwrap();
+        let settings = Settings {
+            paths: crate::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        let original = settings.repos_dir().join("app");
+        std::fs::create_dir_all(&original).unwrap();
+        let original_str = original.to_string_lossy().to_string();
+        run("git init", Some(&original_str)).await;
+        run("git commit --allow-empty -m init", Some(&original_str)).await;
+        run(
+            "git remote add origin https://example.com/app.git",
+            Some(&original_str),
+        )
+        .await;
+
+        let svc = GitService::new(&settings);
+        let remote = |path: &Path| {
+            let path = path.to_string_lossy().to_string();
+            async move { run("git remote get-url origin", Some(&path)).await.stdout }
+        };
+
+        let (_, dest) = svc.prepare_clone(&original_str, Some("app-copy")).await.unwrap();
+        let branch = svc
+            .duplicate_repo(&original_str, &dest, DuplicateMode::Clone, None)
+            .await
+            .unwrap();
+        assert_eq!(branch, svc.get_current_branch(&original).await);
+        assert_eq!(remote(&dest).await.trim(), "https://example.com/app.git");
+
+        let (_, dest) = svc.prepare_clone(&original_str, Some("app-fork")).await.unwrap();
+        svc.duplicate_repo(
+            &original_str,
+            &dest,
+            DuplicateMode::Shared,
+            Some("https://example.com/fork.git"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(remote(&dest).await.trim(), "https://example.com/fork.git");
+        assert!(dest.join(".git/objects/info/alternates").exists());
+        assert_eq!(svc.list_repos().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_pull_feature() {
+        let tmp = TempDir::new().unwrap();
+        let dir = |name: &str| tmp.path().join(name).to_string_lossy().to_string();
+        let git = |cmd: &'static str, cwd: String| async move {
+            let full = format!("git -c user.name=t -c user.email=t@t {cmd}");
+            assert!(run(&full, Some(&cwd)).await.success(), "{cmd}");
+        };
+        std::fs::create_dir_all(tmp.path().join("origin")).unwrap();
+        git("init -b main", dir("origin")).await;
+        std::fs::write(tmp.path().join("origin/file"), "one\n").unwrap();
+        git("add file", dir("origin")).await;
+        git("commit -m one", dir("origin")).await;
+        let tmp_dir = tmp.path().to_string_lossy().to_string();
+        run("git clone -q origin clone", Some(&tmp_dir)).await;
+
+        let svc = GitService::new(&Settings::default());
+        let pulled = svc
+            .pull_feature(&dir("clone"), PullStrategy::FastForward)
+            .await
+            .unwrap();
+        assert_eq!(pulled, PullFeatureResponse::default());
+
+        git("commit --allow-empty -m two", dir("origin")).await;
+        git("commit --allow-empty -m three", dir("origin")).await;
+        let pulled = svc
+            .pull_feature(&dir("clone"), PullStrategy::FastForward)
+            .await
+            .unwrap();
+        assert!(pulled.updated);
+        assert_eq!(pulled.commits_pulled, 2);
+
+        // Both sides change the same line
+        std::fs::write(tmp.path().join("origin/file"), "upstream\n").unwrap();
+        git("commit -am upstream", dir("origin")).await;
+        std::fs::write(tmp.path().join("clone/file"), "local\n").unwrap();
+        git("commit -am local", dir("clone")).await;
+        assert!(matches!(
+            svc.pull_feature(&dir("clone"), PullStrategy::FastForward).await,
+            Err(NomadError::InUse(_))
+        ));
+        let pulled = svc.pull_feature(&dir("clone"), PullStrategy::Rebase).await.unwrap();
+        assert!(!pulled.updated);
+        assert_eq!(pulled.conflicts, vec!["file".to_string()]);
+        let status = run("git status --porcelain", Some(&dir("clone"))).await;
+        assert!(status.stdout.trim().is_empty(), "rebase was not aborted");
+
+        assert!(!needs_resolution(&dir("clone")));
+        run("git -c user.name=t -c user.email=t@t rebase @{u}", Some(&dir("clone"))).await;
+        assert!(needs_resolution(&dir("clone")));
+        run("git rebase --abort", Some(&dir("clone"))).await;
+        assert!(!needs_resolution(&dir("clone")));
+    }
+
+    #[test]
+    fn test_resolve_conflicts_command() {
+        assert_eq!(
+            resolve_conflicts_command("", false),
+            "git rebase --autostash @{u} || git status"
+        );
+        assert_eq!(
+            resolve_conflicts_command("meld", true),
+            "git mergetool --tool='meld'"
+        );
     }
 
     #[tokio::test]
@@ -761,4 +2226,120 @@ mod tests {
         let deleted = svc.delete_feature(&repo_path, "to-delete").await.unwrap();
         assert!(deleted);
     }
+
+    #[tokio::test]
+    async fn test_repair_worktree_quotes_path() {
+        let tmp = TempDir::new().unwrap();
+        let repo = tmp.path().join("repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        let repo_path = repo.to_string_lossy().to_string();
+        run("git init", Some(&repo_path)).await;
+        run("git commit --allow-empty -m init", Some(&repo_path)).await;
+
+        let worktree = tmp.path().join("wt-\"$(touch pwned)\"");
+        let worktree_path = worktree.to_string_lossy().to_string();
+        let add = format!("git worktree add -q -b wt {}", shell_quote(&worktree_path));
+        assert!(run(&add, Some(&repo_path)).await.success());
+
+        let svc = GitService::new(&Settings::default());
+        assert!(svc.repair_worktree(&repo_path, &worktree_path).await);
+        assert!(!repo.join("pwned").exists());
+    }
+
+    #[tokio::test]
+    async fn test_delete_repo() {
+        let tmp = TempDir::new().unwrap();
+        let settings = Settings {
+            paths: crate::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        settings.ensure_directories().unwrap();
+        let svc = GitService::new(&settings);
+
+        let repo_dir = settings.repos_dir().join("test-repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        let repo_path = repo_dir.to_string_lossy().to_string();
+        run("git init", Some(&repo_path)).await;
+        run("git commit --allow-empty -m init", Some(&repo_path)).await;
+        let (wt_path, _) = svc
+            .create_feature(&repo_path, "feature/wip", None)
+            .await
+            .unwrap();
+
+        // Worktrees hold the repo back unless forced
+        let err = svc.delete_repo(&repo_path, false).await.unwrap_err();
+        assert!(matches!(err, NomadError::InUse(_)));
+        assert!(repo_dir.exists());
+
+        let deleted = svc.delete_repo(&repo_path, true).await.unwrap();
+        assert!(!deleted.unlinked);
+        assert_eq!(deleted.removed_features, vec!["wip".to_string()]);
+        assert!(!repo_dir.exists());
+        assert!(!Path::new(&wt_path).exists());
+
+        // A symlinked repo is only unlinked
+        let outside = tmp.path().join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        run("git init", Some(&outside.to_string_lossy())).await;
+        let link = settings.repos_dir().join("linked");
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+        let deleted = svc
+            .delete_repo(&link.to_string_lossy(), false)
+            .await
+            .unwrap();
+        assert!(deleted.unlinked);
+        assert!(!link.exists());
+        assert!(outside.join(".git").exists());
+
+        // Nothing outside the repos directory can be deleted
+        let err = svc
+            .delete_repo(&outside.to_string_lossy(), true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, NomadError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_restore_deleted_branch() {
+        let tmp = TempDir::new().unwrap();
+        let settings = Settings {
+            paths: crate::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        settings.ensure_directories().unwrap();
+
+        let repo_dir = settings.repos_dir().join("test-repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        run("git init", Some(&repo_dir.to_string_lossy())).await;
+        run(
+            "git commit --allow-empty -m init",
+            Some(&repo_dir.to_string_lossy()),
+        )
+        .await;
+
+        let svc = GitService::new(&settings);
+        let repo_path = repo_dir.to_string_lossy().to_string();
+
+        svc.create_feature(&repo_path, "feature/undo-me", None)
+            .await
+            .unwrap();
+        let sha = svc.branch_tip(&repo_path, "feature/undo-me").await.unwrap();
+        svc.delete_feature(&repo_path, "undo-me").await.unwrap();
+        assert!(svc.branch_tip(&repo_path, "feature/undo-me").await.is_none());
+
+        let (wt_path, branch) = svc
+            .restore_branch(&repo_path, "feature/undo-me", &sha)
+            .await
+            .unwrap();
+        assert_eq!(branch, "feature/undo-me");
+        assert!(Path::new(&wt_path).exists());
+        assert_eq!(
+            svc.branch_tip(&repo_path, "feature/undo-me").await.as_deref(),
+            Some(sha.as_str())
+        );
+    }
 }
@@ -1,17 +1,41 @@
+use crate::config::LimitsConfig;
 use crate::error::{NomadError, Result};
-use crate::shell::{command_exists, run};
+use crate::limits::{shell_command, Enforcement, USAGE_OPTION};
+use crate::shell::{command_exists, run, shell_quote};
 
 pub struct TmuxService {
     session_name: String,
+    limits: LimitsConfig,
+    /// `monitor-silence` of new windows, in seconds. 0 = off.
+    silence_alert: u32,
 }
 
 impl TmuxService {
     pub fn new(session_name: &str) -> Self {
         Self {
             session_name: session_name.to_string(),
+            limits: LimitsConfig::default(),
+            silence_alert: 0,
         }
     }
 
+    /// Have tmux raise a silence alert on new windows without output for
+    /// `seconds`.
+    pub fn with_silence_alert(mut self, seconds: u32) -> Self {
+        self.silence_alert = seconds;
+        self
+    }
+
+    /// Start the shell of new windows under `limits`.
+    pub fn with_limits(mut self, limits: LimitsConfig) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn limits(&self) -> &LimitsConfig {
+        &self.limits
+    }
+
     /// Ensure the tmux session exists, create if not.
     pub async fn ensure_session(&self) -> Result<bool> {
         if !command_exists("tmux").await {
@@ -43,31 +67,112 @@ impl TmuxService {
         Ok(true)
     }
 
+    /// Append `command` to a global hook, run for every session: `window`
+    /// for window and pane hooks such as `pane-died`.
+    pub async fn add_global_hook(&self, hook: &str, command: &str, window: bool) -> bool {
+        let flags = if window { "-gwa" } else { "-ga" };
+        run(
+            &format!("tmux set-hook {flags} {hook} {}", shell_quote(command)),
+            None,
+        )
+        .await
+        .success()
+    }
+
+    /// Global hooks as `(name[index], command, window)`, session hooks then
+    /// window ones.
+    pub async fn global_hooks(&self) -> Vec<(String, String, bool)> {
+        let mut hooks = Vec::new();
+        for (flags, window) in [("-g", false), ("-gw", true)] {
+            let result = run(&format!("tmux show-hooks {flags}"), None).await;
+            if !result.success() {
+                continue;
+            }
+            for line in result.stdout.lines() {
+                if let Some((name, command)) = line.split_once(' ') {
+                    hooks.push((name.to_string(), command.to_string(), window));
+                }
+            }
+        }
+        hooks
+    }
+
+    /// Remove one entry (`name[index]`) of a global hook.
+    pub async fn remove_global_hook(&self, entry: &str, window: bool) -> bool {
+        let flags = if window { "-gwu" } else { "-gu" };
+        run(
+            &format!("tmux set-hook {flags} {}", shell_quote(entry)),
+            None,
+        )
+        .await
+        .success()
+    }
+
     /// List all windows in the session.
     pub async fn list_windows(&self) -> Vec<TmuxWindow> {
         let result = run(
             &format!(
-                "tmux list-windows -t \"{}\" -F \"#{{window_index}}:#{{window_name}}\"",
+                "tmux list-windows -t \"{}\" -F \"#{{window_index}}:#{{window_activity_flag}}:#{{window_name}}\"",
                 self.session_name
             ),
             None,
         )
         .await;
 
-        let mut windows = Vec::new();
-        if result.success() {
-            for line in result.stdout.trim().lines() {
-                if let Some((index_str, name)) = line.split_once(':') {
-                    if let Ok(index) = index_str.parse::<u32>() {
-                        windows.push(TmuxWindow {
-                            index,
-                            name: name.to_string(),
-                        });
-                    }
-                }
+        if !result.success() {
+            return Vec::new();
+        }
+        result
+            .stdout
+            .trim()
+            .lines()
+            .filter_map(parse_window_line)
+            .collect()
+    }
+
+    /// Name of the window `window_id` (`@3`) if it belongs to this session,
+    /// `session_id` (`$1`) being the session it was reported in.
+    pub async fn window_name_by_id(&self, session_id: &str, window_id: &str) -> Option<String> {
+        let result = run(
+            &format!(
+                "tmux list-windows -t \"{}\" -F \"#{{session_id}}:#{{window_id}}:#{{window_name}}\"",
+                self.session_name
+            ),
+            None,
+        )
+        .await;
+        if !result.success() {
+            return None;
+        }
+        result.stdout.lines().find_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            if parts.next()? == session_id && parts.next()? == window_id {
+                parts.next().map(str::to_string)
+            } else {
+                None
             }
+        })
+    }
+
+    /// When each window last produced output (Unix seconds) and whether a
+    /// client is looking at it.
+    pub async fn window_activity(&self) -> Vec<(String, u64, bool)> {
+        let result = run(
+            &format!(
+                "tmux list-windows -t \"{}\" -F \"#{{window_activity}}:#{{window_active}}:#{{session_attached}}:#{{window_name}}\"",
+                self.session_name
+            ),
+            None,
+        )
+        .await;
+        if !result.success() {
+            return Vec::new();
         }
-        windows
+        result
+            .stdout
+            .lines()
+            .filter_map(parse_activity_line)
+            .collect()
     }
 
     /// Create a new window in the session.
@@ -79,6 +184,15 @@ impl TmuxService {
         if let Some(dir) = working_dir {
             cmd.push_str(&format!(" -c \"{dir}\""));
         }
+        let limits = self.limits.for_window(name);
+        let shell = if limits.is_empty() {
+            None
+        } else {
+            shell_command(&limits, Enforcement::detect().await)
+        };
+        if let Some(ref shell) = shell {
+            cmd.push_str(&format!(" {}", shell_quote(shell)));
+        }
 
         let result = run(&cmd, None).await;
         if !result.success() {
@@ -87,9 +201,109 @@ impl TmuxService {
                 result.stderr
             )));
         }
+        self.monitor_activity(name).await;
+        if self.silence_alert > 0 {
+            run(
+                &format!(
+                    "tmux set-option -w -t \"{}:{}\" monitor-silence {}",
+                    self.session_name, name, self.silence_alert
+                ),
+                None,
+            )
+            .await;
+        }
+        if shell.is_some() {
+            self.show_usage(name).await;
+        }
         Ok(())
     }
 
+    /// Append the window's usage, kept in [`USAGE_OPTION`], to its entry of
+    /// the status line.
+    async fn show_usage(&self, name: &str) {
+        let usage = format!("#{{?{USAGE_OPTION}, [#{{{USAGE_OPTION}}}],}}");
+        for option in ["window-status-format", "window-status-current-format"] {
+            let global = run(&format!("tmux show-options -gwv {option}"), None).await;
+            if !global.success() {
+                continue;
+            }
+            let format = format!("{}{usage}", global.stdout.trim_end_matches('\n'));
+            run(
+                &format!(
+                    "tmux set-option -w -t \"{}:{}\" {option} {}",
+                    self.session_name,
+                    name,
+                    shell_quote(&format)
+                ),
+                None,
+            )
+            .await;
+        }
+    }
+
+    /// Set the window option [`USAGE_OPTION`] shown by limited windows.
+    pub async fn set_usage(&self, name: &str, usage: &str) -> bool {
+        run(
+            &format!(
+                "tmux set-option -w -t \"{}:{}\" {USAGE_OPTION} {}",
+                self.session_name,
+                name,
+                shell_quote(usage)
+            ),
+            None,
+        )
+        .await
+        .success()
+    }
+
+    /// PID of the process started in the window's active pane, its shell.
+    pub async fn pane_pid(&self, window: &str) -> Option<u32> {
+        let result = run(
+            &format!(
+                "tmux display-message -p -t \"{}:{}\" \"#{{pane_pid}}\"",
+                self.session_name, window
+            ),
+            None,
+        )
+        .await;
+        if !result.success() {
+            return None;
+        }
+        result.stdout.trim().parse().ok()
+    }
+
+    /// The last `lines` lines of the window's active pane, scrollback
+    /// included, as plain text. Lines tmux wrapped are joined back.
+    pub async fn capture_pane(&self, window: &str, lines: u32) -> Option<String> {
+        let result = run(
+            &format!(
+                "tmux capture-pane -p -J -S -{} -t \"{}:{}\"",
+                lines, self.session_name, window
+            ),
+            None,
+        )
+        .await;
+        if !result.success() {
+            return None;
+        }
+        Some(last_lines(&result.stdout, lines as usize))
+    }
+
+    /// Enable `monitor-activity` on a window so tmux flags output produced
+    /// while the window is not being viewed. The flag is cleared by tmux
+    /// when the window is selected again.
+    pub async fn monitor_activity(&self, name: &str) -> bool {
+        run(
+            &format!(
+                "tmux set-option -w -t \"{}:{}\" monitor-activity on",
+                self.session_name, name
+            ),
+            None,
+        )
+        .await
+        .success()
+    }
+
     /// Select/focus a window by name.
     pub async fn select_window(&self, name: &str) -> bool {
         run(
@@ -124,6 +338,46 @@ impl TmuxService {
         .success()
     }
 
+    /// Type `command` literally at the window's prompt and run it.
+    pub async fn type_command(&self, window: &str, command: &str) -> bool {
+        let target = format!("\"{}:{}\"", self.session_name, window);
+        run(
+            &format!("tmux send-keys -t {target} -l {}", shell_quote(command)),
+            None,
+        )
+        .await
+        .success()
+            && run(&format!("tmux send-keys -t {target} Enter"), None)
+                .await
+                .success()
+    }
+
+    /// Show `message` in the status line of every client attached to the session.
+    pub async fn notify(&self, message: &str) {
+        let clients = run(
+            &format!(
+                "tmux list-clients -t \"{}\" -F '#{{client_name}}'",
+                self.session_name
+            ),
+            None,
+        )
+        .await;
+        if !clients.success() {
+            return;
+        }
+        for client in clients.stdout.lines().filter(|c| !c.is_empty()) {
+            run(
+                &format!(
+                    "tmux display-message -c {} {}",
+                    shell_quote(client),
+                    shell_quote(message)
+                ),
+                None,
+            )
+            .await;
+        }
+    }
+
     /// Check if a window exists.
     pub async fn window_exists(&self, name: &str) -> bool {
         self.list_windows().await.iter().any(|w| w.name == name)
@@ -150,14 +404,7 @@ impl TmuxService {
 
     /// Check if the window has an idle shell.
     pub async fn is_shell_idle(&self, window: &str) -> bool {
-        match self.get_pane_command(window).await {
-            None => true,
-            Some(cmd) => {
-                const IDLE_SHELLS: &[&str] =
-                    &["bash", "zsh", "sh", "fish", "dash", "ksh", "tcsh", "csh"];
-                IDLE_SHELLS.contains(&cmd.to_lowercase().as_str())
-            }
-        }
+        is_idle_command(self.get_pane_command(window).await.as_deref())
     }
 
     /// Ensure a window exists, create if not.
@@ -167,6 +414,9 @@ impl TmuxService {
             if let Some(dir) = working_dir {
                 self.send_keys(name, &format!("cd \"{dir}\""), true).await;
             }
+        } else {
+            // Windows created by older versions don't have activity monitoring yet
+            self.monitor_activity(name).await;
         }
         Ok(())
     }
@@ -203,29 +453,117 @@ impl TmuxService {
         Ok((true, has_running_process))
     }
 
+    /// Create the detached session `name` holding only `window` of this session.
+    /// The window is linked, not copied, so a client attached to the new
+    /// session sees it live but cannot switch to the other windows.
+    pub async fn create_linked_session(&self, name: &str, window: &str) -> Result<()> {
+        let result = run(
+            &format!("tmux new-session -d -s \"{name}\" -P -F \"#{{window_id}}\""),
+            None,
+        )
+        .await;
+        if !result.success() {
+            return Err(NomadError::CommandFailed(format!(
+                "Failed to create tmux session: {}",
+                result.stderr
+            )));
+        }
+        let placeholder = result.stdout.trim().to_string();
+
+        let result = run(
+            &format!(
+                "tmux link-window -s \"{}:{}\" -t \"{name}:\"",
+                self.session_name, window
+            ),
+            None,
+        )
+        .await;
+        if !result.success() {
+            TmuxService::new(name).kill_session().await;
+            return Err(NomadError::CommandFailed(format!(
+                "Failed to link tmux window: {}",
+                result.stderr
+            )));
+        }
+
+        // Drop the shell window new-session always starts with
+        run(&format!("tmux kill-window -t \"{placeholder}\""), None).await;
+        Ok(())
+    }
+
+    /// Kill this session and all windows not linked elsewhere.
+    pub async fn kill_session(&self) -> bool {
+        run(
+            &format!("tmux kill-session -t \"{}\"", self.session_name),
+            None,
+        )
+        .await
+        .success()
+    }
+
     pub fn session_name(&self) -> &str {
         &self.session_name
     }
+
+    /// Shell command attaching a terminal to `window` of this session.
+    pub fn attach_command(&self, window: &str) -> String {
+        format!(
+            "tmux attach-session -t {}",
+            shell_quote(&format!("{}:{window}", self.session_name))
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct TmuxWindow {
     pub index: u32,
     pub name: String,
+    /// Output was produced since the window was last viewed.
+    pub activity: bool,
 }
 
-/// Build a tmux window name from repo path and feature name.
-pub fn window_name(repo_path: &str, feature_name: &str) -> String {
-    let repo_name = std::path::Path::new(repo_path)
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy();
-    format!("{repo_name}:{feature_name}")
+/// Whether a pane running `command` sits at a shell prompt.
+pub fn is_idle_command(command: Option<&str>) -> bool {
+    const IDLE_SHELLS: &[&str] = &["bash", "zsh", "sh", "fish", "dash", "ksh", "tcsh", "csh"];
+    command.is_none_or(|cmd| IDLE_SHELLS.contains(&cmd.to_lowercase().as_str()))
+}
+
+/// The last `n` lines of a `capture-pane` output. The blank lines below the
+/// prompt of a pane that is not full do not count.
+fn last_lines(output: &str, n: usize) -> String {
+    let lines: Vec<&str> = output.trim_end().lines().collect();
+    lines[lines.len().saturating_sub(n)..].join("\n")
+}
+
+/// Parse a `list-windows` line formatted as `index:activity_flag:name`.
+/// The name comes last because window names contain `:` (`repo:feature`).
+fn parse_window_line(line: &str) -> Option<TmuxWindow> {
+    let mut parts = line.splitn(3, ':');
+    let index = parts.next()?.parse::<u32>().ok()?;
+    let activity = parts.next()? == "1";
+    let name = parts.next()?.to_string();
+    Some(TmuxWindow {
+        index,
+        name,
+        activity,
+    })
+}
+
+/// Parse a `window_activity` line formatted as
+/// `activity:window_active:session_attached:name`.
+fn parse_activity_line(line: &str) -> Option<(String, u64, bool)> {
+    let mut parts = line.splitn(4, ':');
+    let activity = parts.next()?.parse::<u64>().ok()?;
+    let active = parts.next()? == "1";
+    let attached = parts.next()?.parse::<u32>().ok()? > 0;
+    Some((parts.next()?.to_string(), activity, active && attached))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::WindowPrefix;
+    use crate::window_names::WindowNames;
 
     fn tmux_available() -> bool {
         std::process::Command::new("which")
@@ -237,7 +575,55 @@ mod tests {
 
     #[test]
     fn test_window_name() {
-        assert_eq!(window_name("/home/user/repos/my-project", "add-login"), "my-project:add-login");
+        let names = WindowNames::in_memory(WindowPrefix::Name);
+        assert_eq!(
+            names.window_name("/home/user/repos/my-project", "add-login"),
+            "my-project:add-login"
+        );
+    }
+
+    #[test]
+    fn test_attach_command() {
+        let tmux = TmuxService::new("nomadflow");
+        assert_eq!(
+            tmux.attach_command("api:it's"),
+            r"tmux attach-session -t 'nomadflow:api:it'\''s'"
+        );
+    }
+
+    #[test]
+    fn test_parse_window_line() {
+        let w = parse_window_line("3:1:my-project:add-login").unwrap();
+        assert_eq!(w.index, 3);
+        assert!(w.activity);
+        assert_eq!(w.name, "my-project:add-login");
+
+        let w = parse_window_line("0:0:zsh").unwrap();
+        assert!(!w.activity);
+        assert_eq!(w.name, "zsh");
+
+        assert!(parse_window_line("garbage").is_none());
+    }
+
+    #[test]
+    fn test_parse_activity_line() {
+        assert_eq!(
+            parse_activity_line("1760000000:1:2:my-project:add-login"),
+            Some(("my-project:add-login".to_string(), 1760000000, true))
+        );
+        // The active window of a detached session is not being looked at
+        assert_eq!(
+            parse_activity_line("1760000000:1:0:zsh"),
+            Some(("zsh".to_string(), 1760000000, false))
+        );
+        assert!(parse_activity_line("garbage").is_none());
+    }
+
+    #[test]
+    fn test_last_lines() {
+        assert_eq!(last_lines("a\nb\nc\n$ \n\n\n", 2), "c\n$");
+        assert_eq!(last_lines("a\nb\n", 10), "a\nb");
+        assert_eq!(last_lines("\n\n", 5), "");
     }
 
     #[tokio::test]
@@ -277,7 +663,68 @@ mod tests {
         let idle = svc.is_shell_idle(win).await;
         assert!(idle);
 
+        // The shell may still be starting: wait for the echo, not the typing
+        svc.type_command(win, "echo nf-capture-$((1 + 1))").await;
+        let mut captured = false;
+        for _ in 0..50 {
+            let output = svc.capture_pane(win, 50).await.unwrap();
+            if output.lines().any(|l| l == "nf-capture-2") {
+                captured = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(captured);
+        assert!(svc.capture_pane("test-missing-win", 50).await.is_none());
+
+        // Hooks report ids, resolved back to names whatever they contain
+        let quoted = "it's";
+        svc.create_window(quoted, None).await.unwrap();
+        let ids = run(
+            &format!("tmux display-message -p -t \"{session}:{quoted}\" \"#{{session_id}} #{{window_id}}\""),
+            None,
+        )
+        .await;
+        let (session_id, window_id) = ids.stdout.trim().split_once(' ').unwrap();
+        assert_eq!(
+            svc.window_name_by_id(session_id, window_id)
+                .await
+                .as_deref(),
+            Some(quoted)
+        );
+        assert!(svc.window_name_by_id("$999999", window_id).await.is_none());
+        assert!(svc.window_name_by_id(session_id, "@999999").await.is_none());
+
         // Cleanup: kill the entire test session (more reliable than kill_window)
         run(&format!("tmux kill-session -t \"{session}\""), None).await;
     }
+
+    #[tokio::test]
+    async fn test_create_linked_session() {
+        if !tmux_available() {
+            eprintln!("Skipping tmux test: tmux not available");
+            return;
+        }
+
+        let session = &format!("nf-test-link-{}", std::process::id());
+        let linked = &format!("{session}-share");
+        run(&format!("tmux kill-session -t \"{session}\" 2>/dev/null"), None).await;
+
+        let svc = TmuxService::new(session);
+        svc.ensure_session().await.unwrap();
+        svc.create_window("repo:shared", None).await.unwrap();
+        svc.create_window("repo:private", None).await.unwrap();
+
+        svc.create_linked_session(linked, "repo:shared").await.unwrap();
+        let share = TmuxService::new(linked);
+        let names: Vec<String> = share.list_windows().await.into_iter().map(|w| w.name).collect();
+        assert_eq!(names, vec!["repo:shared".to_string()]);
+
+        // Killing the linked session leaves the original window alone
+        assert!(share.kill_session().await);
+        assert!(svc.window_exists("repo:shared").await);
+        assert!(svc.create_linked_session(linked, "repo:missing").await.is_err());
+
+        svc.kill_session().await;
+    }
 }
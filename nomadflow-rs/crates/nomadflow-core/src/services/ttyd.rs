@@ -1,15 +1,58 @@
+use std::collections::HashMap;
 use std::net::TcpListener;
+use std::time::{Duration, Instant};
 
 use tokio::process::{Child, Command};
+use tokio::sync::{oneshot, watch, Mutex};
+use tokio::task::JoinHandle;
 
-use crate::config::Settings;
+use crate::config::{Settings, TerminalBackend};
 use crate::error::{NomadError, Result};
+use crate::models::TerminalStatus;
+use crate::services::tmux::TmuxService;
 use crate::shell::command_exists;
 
+/// How often a supervised ttyd is checked while healthy.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Wait before the first restart attempt, doubled after each failed one.
+const RESTART_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(60);
+/// How long a ttyd may take to accept a connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Ask the OS for a free local port for a ttyd.
+pub fn free_port() -> std::io::Result<u16> {
+    let listener = TcpListener::bind(("127.0.0.1", 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// What a terminal client is bridged to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalTarget {
+    /// A ttyd listening on `port`, protected by `secret`.
+    Ttyd { port: u16, secret: String },
+    /// A tmux session the server attaches in a PTY itself.
+    Tmux(String),
+}
+
+impl TerminalTarget {
+    /// The whole tmux session, through the configured backend.
+    pub fn for_session(settings: &Settings) -> Self {
+        match settings.ttyd.backend {
+            TerminalBackend::Native => Self::Tmux(settings.tmux.session.clone()),
+            TerminalBackend::Ttyd => Self::Ttyd {
+                port: settings.ttyd.port,
+                secret: settings.auth.secret.clone(),
+            },
+        }
+    }
+}
+
 pub struct TtydService {
     port: u16,
     session_name: String,
     secret: String,
+    writable: bool,
     process: Option<Child>,
 }
 
@@ -19,6 +62,19 @@ impl TtydService {
             port: settings.ttyd.port,
             session_name: settings.tmux.session.clone(),
             secret: settings.auth.secret.clone(),
+            writable: true,
+            process: None,
+        }
+    }
+
+    /// A ttyd attached to `session_name` on `port`, protected by `secret`.
+    /// When `writable` is false, keyboard input from clients is ignored.
+    pub fn for_session(port: u16, session_name: &str, secret: &str, writable: bool) -> Self {
+        Self {
+            port,
+            session_name: session_name.to_string(),
+            secret: secret.to_string(),
+            writable,
             process: None,
         }
     }
@@ -38,19 +94,21 @@ impl TtydService {
         }
 
         let mut cmd = Command::new("ttyd");
-        cmd.arg("-p")
-            .arg(self.port.to_string())
-            .arg("-W");
+        cmd.arg("-p").arg(self.port.to_string());
+        if self.writable {
+            cmd.arg("-W");
+        }
 
         if !self.secret.is_empty() {
             cmd.arg("-c")
                 .arg(format!("nomadflow:{}", self.secret));
         }
 
-        cmd.arg("tmux")
-            .arg("attach-session")
-            .arg("-t")
-            .arg(&self.session_name);
+        cmd.arg("tmux").arg("attach-session");
+        if !self.writable {
+            cmd.arg("-r");
+        }
+        cmd.arg("-t").arg(&self.session_name);
 
         cmd.stdout(std::process::Stdio::null());
         cmd.stderr(std::process::Stdio::null());
@@ -83,4 +141,281 @@ impl TtydService {
     pub fn port(&self) -> u16 {
         self.port
     }
+
+    /// Whether our ttyd is still running, or another one holds the port, and
+    /// accepts connections.
+    async fn is_healthy(&mut self) -> bool {
+        if let Some(child) = &mut self.process {
+            if !matches!(child.try_wait(), Ok(None)) {
+                return false;
+            }
+        }
+        let connect = tokio::net::TcpStream::connect(("127.0.0.1", self.port));
+        matches!(tokio::time::timeout(CONNECT_TIMEOUT, connect).await, Ok(Ok(_)))
+    }
+
+    /// Keep ttyd running in the background, restarting it with exponential
+    /// backoff whenever it exits or stops accepting connections.
+    pub async fn supervise(mut self) -> TtydSupervisor {
+        let initial = if self.is_healthy().await {
+            TerminalStatus::Ok
+        } else {
+            TerminalStatus::Restarting
+        };
+        let (status_tx, status) = watch::channel(initial);
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut backoff = RESTART_BACKOFF_MIN;
+            loop {
+                let wait = if self.is_healthy().await {
+                    status_tx.send_replace(TerminalStatus::Ok);
+                    backoff = RESTART_BACKOFF_MIN;
+                    CHECK_INTERVAL
+                } else {
+                    self.stop().await;
+                    let status = match self.start().await {
+                        Ok(()) if self.is_healthy().await => TerminalStatus::Ok,
+                        Err(NomadError::NotFound(_)) => TerminalStatus::Unavailable,
+                        _ => TerminalStatus::Restarting,
+                    };
+                    status_tx.send_replace(status);
+                    if status == TerminalStatus::Ok {
+                        backoff = RESTART_BACKOFF_MIN;
+                        CHECK_INTERVAL
+                    } else {
+                        let wait = backoff;
+                        backoff = next_backoff(backoff);
+                        wait
+                    }
+                };
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = tokio::time::sleep(wait) => {}
+                }
+            }
+            self.stop().await;
+        });
+        TtydSupervisor {
+            status,
+            stop: Some(stop),
+            task: Some(task),
+        }
+    }
+}
+
+fn next_backoff(backoff: Duration) -> Duration {
+    (backoff * 2).min(RESTART_BACKOFF_MAX)
+}
+
+/// A ttyd kept running by [`TtydService::supervise`].
+pub struct TtydSupervisor {
+    status: watch::Receiver<TerminalStatus>,
+    stop: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl TtydSupervisor {
+    /// Follows the status of ttyd as the supervisor sees it.
+    pub fn status(&self) -> watch::Receiver<TerminalStatus> {
+        self.status.clone()
+    }
+
+    /// Stop supervising and stop ttyd.
+    pub async fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+/// A terminal attached to a session holding a single window.
+struct WindowTtyd {
+    /// `None` with the native backend, which attaches the session itself.
+    ttyd: Option<TtydService>,
+    /// Session the window is linked into, so that the client switching
+    /// windows does not move the others.
+    tmux_session: String,
+    clients: usize,
+    idle_since: Option<Instant>,
+}
+
+impl WindowTtyd {
+    async fn stop(self) {
+        if let Some(mut ttyd) = self.ttyd {
+            ttyd.stop().await;
+        }
+        TmuxService::new(&self.tmux_session).kill_session().await;
+    }
+}
+
+#[derive(Default)]
+struct Instances {
+    next_id: u64,
+    by_window: HashMap<String, WindowTtyd>,
+}
+
+impl WindowTtyd {
+    fn target(&self) -> TerminalTarget {
+        match &self.ttyd {
+            Some(ttyd) => TerminalTarget::Ttyd {
+                port: ttyd.port(),
+                secret: ttyd.secret.clone(),
+            },
+            None => TerminalTarget::Tmux(self.tmux_session.clone()),
+        }
+    }
+}
+
+/// Terminals bound to single windows of the session, started when a client
+/// asks for a window and stopped once unused for a while.
+pub struct WindowTtyds {
+    session_name: String,
+    secret: String,
+    backend: TerminalBackend,
+    instances: Mutex<Instances>,
+}
+
+impl WindowTtyds {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            session_name: settings.tmux.session.clone(),
+            secret: settings.auth.secret.clone(),
+            backend: settings.ttyd.backend,
+            instances: Mutex::new(Instances::default()),
+        }
+    }
+
+    /// The terminal attached to `window`, started if needed. Each call must
+    /// be matched by a [`release`](Self::release).
+    pub async fn acquire(&self, window: &str) -> Result<TerminalTarget> {
+        let mut instances = self.instances.lock().await;
+        if let Some(instance) = instances.by_window.get_mut(window) {
+            instance.clients += 1;
+            instance.idle_since = None;
+            return Ok(instance.target());
+        }
+
+        instances.next_id += 1;
+        let tmux_session = format!("{}-win-{}", self.session_name, instances.next_id);
+        TmuxService::new(&self.session_name)
+            .create_linked_session(&tmux_session, window)
+            .await?;
+        let started = match self.backend {
+            TerminalBackend::Native => Ok(None),
+            TerminalBackend::Ttyd => match free_port() {
+                Ok(port) => {
+                    let mut ttyd =
+                        TtydService::for_session(port, &tmux_session, &self.secret, true);
+                    ttyd.start().await.map(|()| Some(ttyd))
+                }
+                Err(e) => Err(NomadError::CommandFailed(format!("No free port for ttyd: {e}"))),
+            },
+        };
+        let ttyd = match started {
+            Ok(ttyd) => ttyd,
+            Err(e) => {
+                TmuxService::new(&tmux_session).kill_session().await;
+                return Err(e);
+            }
+        };
+
+        let instance = WindowTtyd {
+            ttyd,
+            tmux_session,
+            clients: 1,
+            idle_since: None,
+        };
+        let target = instance.target();
+        instances.by_window.insert(window.to_string(), instance);
+        Ok(target)
+    }
+
+    /// A client of `window` disconnected.
+    pub async fn release(&self, window: &str) {
+        let mut instances = self.instances.lock().await;
+        if let Some(instance) = instances.by_window.get_mut(window) {
+            instance.clients = instance.clients.saturating_sub(1);
+            if instance.clients == 0 {
+                instance.idle_since = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Stop the instances nobody used for `idle`. Returns how many were stopped.
+    pub async fn stop_idle(&self, idle: Duration) -> usize {
+        let stale: Vec<WindowTtyd> = {
+            let mut instances = self.instances.lock().await;
+            let windows: Vec<String> = instances
+                .by_window
+                .iter()
+                .filter(|(_, i)| i.idle_since.is_some_and(|since| since.elapsed() >= idle))
+                .map(|(window, _)| window.clone())
+                .collect();
+            windows
+                .iter()
+                .filter_map(|w| instances.by_window.remove(w))
+                .collect()
+        };
+        let count = stale.len();
+        for instance in stale {
+            instance.stop().await;
+        }
+        count
+    }
+
+    /// Stop every instance, e.g. on shutdown.
+    pub async fn stop_all(&self) {
+        let all: Vec<WindowTtyd> = {
+            let mut instances = self.instances.lock().await;
+            instances.by_window.drain().map(|(_, i)| i).collect()
+        };
+        for instance in all {
+            instance.stop().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_backoff_doubles_up_to_the_cap() {
+        assert_eq!(next_backoff(RESTART_BACKOFF_MIN), Duration::from_secs(2));
+        assert_eq!(next_backoff(Duration::from_secs(40)), RESTART_BACKOFF_MAX);
+        assert_eq!(next_backoff(RESTART_BACKOFF_MAX), RESTART_BACKOFF_MAX);
+    }
+
+    #[tokio::test]
+    async fn test_health_follows_the_port() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut ttyd = TtydService::for_session(port, "nomadflow", "", true);
+        assert!(ttyd.is_healthy().await);
+
+        drop(listener);
+        assert!(!ttyd.is_healthy().await);
+    }
+
+    #[test]
+    fn test_session_target_follows_the_backend() {
+        let mut settings = Settings::default();
+        settings.auth.secret = "s3cret".to_string();
+        assert_eq!(
+            TerminalTarget::for_session(&settings),
+            TerminalTarget::Tmux("nomadflow".to_string())
+        );
+
+        settings.ttyd.backend = TerminalBackend::Ttyd;
+        assert_eq!(
+            TerminalTarget::for_session(&settings),
+            TerminalTarget::Ttyd {
+                port: 7681,
+                secret: "s3cret".to_string()
+            }
+        );
+    }
 }
@@ -1,7 +1,26 @@
-use std::time::Duration;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
 use tokio::process::Command;
 
+type Observer = Box<dyn Fn(&str, Duration) + Send + Sync>;
+
+/// Called with each command run and how long it took, e.g. for metrics.
+static OBSERVER: RwLock<Option<Observer>> = RwLock::new(None);
+
+/// Report every command run from now on to `observer`, replacing the
+/// previous one.
+pub fn set_observer(observer: impl Fn(&str, Duration) + Send + Sync + 'static) {
+    *OBSERVER.write().unwrap() = Some(Box::new(observer));
+}
+
+/// Report `command` to the observer, if any.
+pub(crate) fn observe(command: &str, elapsed: Duration) {
+    if let Some(observer) = &*OBSERVER.read().unwrap() {
+        observer(command, elapsed);
+    }
+}
+
 #[derive(Debug)]
 pub struct CommandResult {
     pub stdout: String,
@@ -31,6 +50,7 @@ pub async fn run_command(
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
 
+    let started = Instant::now();
     let result = tokio::time::timeout(
         Duration::from_secs_f64(timeout_secs),
         async {
@@ -42,6 +62,7 @@ pub async fn run_command(
         },
     )
     .await;
+    observe(command, started.elapsed());
 
     match result {
         Ok(Ok(output)) => CommandResult {
@@ -67,6 +88,11 @@ pub async fn run(command: &str, cwd: Option<&str>) -> CommandResult {
     run_command(command, cwd, 30.0).await
 }
 
+/// Quote `s` as a single shell word.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
 /// Check if a command exists in PATH.
 pub async fn command_exists(name: &str) -> bool {
     run(&format!("which {name}"), None).await.success()
@@ -96,6 +122,15 @@ mod tests {
         assert!(result.stderr.contains("timed out"));
     }
 
+    #[tokio::test]
+    async fn test_observer_sees_commands() {
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        set_observer(move |command, _| sink.lock().unwrap().push(command.to_string()));
+        run("echo observed", None).await;
+        assert!(seen.lock().unwrap().iter().any(|c| c == "echo observed"));
+    }
+
     #[tokio::test]
     async fn test_command_exists_git() {
         assert!(command_exists("git").await);
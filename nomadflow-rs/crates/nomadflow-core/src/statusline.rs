@@ -0,0 +1,118 @@
+//! `nomadflow statusline`: one compact line for tmux `status-right` or a
+//! starship custom module, rendered from the snapshot the server keeps in the
+//! base dir so that it never waits on git or tmux.
+
+use std::path::Path;
+
+use crate::fsutil;
+use crate::models::{FeatureSnapshot, StatusSnapshot, TunnelState};
+
+/// File of the base dir holding the server's [`StatusSnapshot`].
+pub const SNAPSHOT_FILE: &str = "statusline.json";
+
+/// Age after which a snapshot is ignored: the server is gone.
+pub const STALE_SECS: u64 = 60;
+
+/// The snapshot last written by the server, if any.
+pub fn load(base_dir: &Path) -> Option<StatusSnapshot> {
+    fsutil::read_with_backup(&base_dir.join(SNAPSHOT_FILE), |s| {
+        serde_json::from_str(s).ok()
+    })
+    .map(|(snapshot, _)| snapshot)
+}
+
+/// The feature whose worktree contains `dir`, the innermost one when nested.
+fn feature_at<'a>(snapshot: &'a StatusSnapshot, dir: &Path) -> Option<&'a FeatureSnapshot> {
+    snapshot
+        .features
+        .iter()
+        .filter(|f| dir.starts_with(&f.worktree_path))
+        .max_by_key(|f| f.worktree_path.len())
+}
+
+/// `repo:feature*  2 busy  tunnel connected` for `dir`: the feature it is in
+/// (`*` when dirty), the busy windows and the tunnel, each left out when
+/// there is nothing to say. `nomadflow off` without a fresh snapshot.
+pub fn render(snapshot: Option<&StatusSnapshot>, dir: &Path, now: u64) -> String {
+    let Some(snapshot) = snapshot.filter(|s| now.saturating_sub(s.updated_at) <= STALE_SECS) else {
+        return "nomadflow off".to_string();
+    };
+
+    let mut parts = Vec::new();
+    if let Some(feature) = feature_at(snapshot, dir) {
+        let dirty = if feature.dirty { "*" } else { "" };
+        parts.push(format!("{}:{}{dirty}", feature.repo, feature.name));
+    }
+    if snapshot.busy_windows > 0 {
+        parts.push(format!("{} busy", snapshot.busy_windows));
+    }
+    let tunnel = match snapshot.tunnel {
+        TunnelState::Disabled => None,
+        TunnelState::Connected => Some("connected"),
+        TunnelState::Reconnecting => Some("reconnecting"),
+        TunnelState::Failed => Some("failed"),
+    };
+    if let Some(tunnel) = tunnel {
+        parts.push(format!("tunnel {tunnel}"));
+    }
+    parts.join("  ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> StatusSnapshot {
+        let feature = |name: &str, path: &str, dirty| FeatureSnapshot {
+            repo: "app".to_string(),
+            name: name.to_string(),
+            worktree_path: path.to_string(),
+            dirty,
+        };
+        StatusSnapshot {
+            updated_at: 1000,
+            busy_windows: 2,
+            tunnel: TunnelState::Connected,
+            features: vec![
+                feature("main", "/repos/app", false),
+                feature("login", "/wt/app/login", true),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let snapshot = snapshot();
+        assert_eq!(
+            render(Some(&snapshot), Path::new("/wt/app/login/src"), 1010),
+            "app:login*  2 busy  tunnel connected"
+        );
+        assert_eq!(
+            render(Some(&snapshot), Path::new("/repos/app"), 1010),
+            "app:main  2 busy  tunnel connected"
+        );
+
+        let quiet = StatusSnapshot {
+            busy_windows: 0,
+            tunnel: TunnelState::Disabled,
+            ..snapshot.clone()
+        };
+        assert_eq!(render(Some(&quiet), Path::new("/home"), 1010), "");
+
+        // A snapshot the server stopped refreshing is not trusted
+        assert_eq!(
+            render(Some(&snapshot), Path::new("/repos/app"), 2000),
+            "nomadflow off"
+        );
+        assert_eq!(render(None, Path::new("/repos/app"), 1010), "nomadflow off");
+    }
+
+    #[test]
+    fn test_load() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert_eq!(load(tmp.path()), None);
+        let json = serde_json::to_string(&snapshot()).unwrap();
+        fsutil::write_atomic(&tmp.path().join(SNAPSHOT_FILE), json).unwrap();
+        assert_eq!(load(tmp.path()), Some(snapshot()));
+    }
+}
@@ -0,0 +1,111 @@
+//! Named tasks (test, lint, deploy…) defined per repo in `.nomadflow.toml`,
+//! read from each feature's worktree so that branches can change them.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::{NomadError, Result};
+use crate::models::TaskInfo;
+
+/// File of a repo defining its tasks.
+pub const REPO_CONFIG: &str = ".nomadflow.toml";
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TaskEntry {
+    /// `test = "cargo test"`
+    Command(String),
+    /// `[tasks.deploy]` with `command`, `description` and `background`.
+    Full {
+        command: String,
+        #[serde(default)]
+        description: String,
+        #[serde(default)]
+        background: bool,
+    },
+}
+
+#[derive(Deserialize)]
+struct RepoConfig {
+    #[serde(default)]
+    tasks: BTreeMap<String, TaskEntry>,
+}
+
+fn parse_tasks(content: &str) -> std::result::Result<Vec<TaskInfo>, toml::de::Error> {
+    let config: RepoConfig = toml::from_str(content)?;
+    Ok(config
+        .tasks
+        .into_iter()
+        .map(|(name, entry)| match entry {
+            TaskEntry::Command(command) => TaskInfo {
+                name,
+                command,
+                description: String::new(),
+                background: false,
+            },
+            TaskEntry::Full {
+                command,
+                description,
+                background,
+            } => TaskInfo {
+                name,
+                command,
+                description,
+                background,
+            },
+        })
+        .collect())
+}
+
+/// Tasks defined in the `.nomadflow.toml` of `worktree`, by name. None when
+/// the file does not exist.
+pub fn load_tasks(worktree: &Path) -> Result<Vec<TaskInfo>> {
+    let path = worktree.join(REPO_CONFIG);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    parse_tasks(&content)
+        .map_err(|e| NomadError::Config(format!("Failed to parse {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tasks() {
+        let tasks = parse_tasks(
+            r#"
+[tasks]
+test = "cargo test"
+
+[tasks.deploy]
+command = "./deploy.sh staging"
+description = "Deploy to staging"
+background = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "deploy");
+        assert!(tasks[0].background);
+        assert_eq!(tasks[0].description, "Deploy to staging");
+        assert_eq!(tasks[1].name, "test");
+        assert_eq!(tasks[1].command, "cargo test");
+        assert!(!tasks[1].background);
+
+        assert!(parse_tasks("[tasks]\ntest = 3\n").is_err());
+    }
+
+    #[test]
+    fn test_load_tasks_without_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert!(load_tasks(tmp.path()).unwrap().is_empty());
+        std::fs::write(tmp.path().join(REPO_CONFIG), "[tasks\n").unwrap();
+        assert!(load_tasks(tmp.path()).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Names of the tmux windows of features, `<prefix>:<feature>`.
+//!
+//! The prefix is the repo's directory name. Two repos with the same directory
+//! name (two forks both named `api`) would share their windows, so a short
+//! hash of the repo path is added to the second one, or to every repo with
+//! `tmux.window_prefix = "hash"`. Prefixes are kept in `windows.json` so that
+//! windows keep their name across restarts and map back to their repo.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config::{Settings, WindowPrefix};
+use crate::fsutil;
+
+/// File of the base directory mapping repo paths to window prefixes.
+pub const WINDOWS_FILE: &str = "windows.json";
+
+/// Short, stable hash of `repo_path` (FNV-1a, 6 hex digits).
+pub fn repo_hash(repo_path: &str) -> String {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in repo_path.bytes() {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    format!("{:06x}", hash & 0xff_ffff)
+}
+
+/// `name` with the characters tmux gives a meaning to in targets (`:`, `.`)
+/// and any other unusual one replaced by `-`.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn normalize(repo_path: &str) -> &str {
+    match repo_path.trim_end_matches('/') {
+        "" => repo_path,
+        path => path,
+    }
+}
+
+/// Window prefixes of repos, by repo path.
+pub struct WindowNames {
+    path: Option<PathBuf>,
+    style: WindowPrefix,
+    prefixes: Mutex<BTreeMap<String, String>>,
+}
+
+impl WindowNames {
+    /// The table of `settings`' base directory.
+    pub fn load(settings: &Settings) -> Self {
+        let path = settings.base_dir().join(WINDOWS_FILE);
+        let prefixes = read(&path);
+        Self {
+            path: Some(path),
+            style: settings.tmux.window_prefix,
+            prefixes: Mutex::new(prefixes),
+        }
+    }
+
+    /// A table that is never written to disk.
+    pub fn in_memory(style: WindowPrefix) -> Self {
+        Self {
+            path: None,
+            style,
+            prefixes: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// The window prefix of the repo at `repo_path`, assigned on first use.
+    pub fn prefix(&self, repo_path: &str) -> String {
+        let repo_path = normalize(repo_path);
+        let mut prefixes = self.prefixes.lock().unwrap();
+        if let Some(prefix) = prefixes.get(repo_path) {
+            return prefix.clone();
+        }
+        // Another process (server, TUI in local mode) may have assigned it
+        if let Some(path) = &self.path {
+            for (repo, prefix) in read(path) {
+                prefixes.entry(repo).or_insert(prefix);
+            }
+            if let Some(prefix) = prefixes.get(repo_path) {
+                return prefix.clone();
+            }
+        }
+
+        let name = std::path::Path::new(repo_path)
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let name = sanitize(&name);
+        let hashed = format!("{name}-{}", repo_hash(repo_path));
+        let prefix = match self.style {
+            WindowPrefix::Hash => hashed,
+            WindowPrefix::Name if prefixes.values().any(|p| *p == name) => hashed,
+            WindowPrefix::Name => name,
+        };
+        prefixes.insert(repo_path.to_string(), prefix.clone());
+        if let (Some(path), Ok(json)) = (&self.path, serde_json::to_string_pretty(&*prefixes)) {
+            // Failing to save only risks another name for the repo's windows
+            // after a restart
+            let _ = fsutil::write_atomic(path, json);
+        }
+        prefix
+    }
+
+    /// The tmux window of a feature.
+    pub fn window_name(&self, repo_path: &str, feature_name: &str) -> String {
+        format!("{}:{feature_name}", self.prefix(repo_path))
+    }
+
+    /// The repo whose windows use `prefix`, if one was assigned it.
+    pub fn repo_path(&self, prefix: &str) -> Option<String> {
+        let prefixes = self.prefixes.lock().unwrap();
+        prefixes
+            .iter()
+            .find(|(_, p)| *p == prefix)
+            .map(|(repo, _)| repo.clone())
+    }
+}
+
+fn read(path: &std::path::Path) -> BTreeMap<String, String> {
+    fsutil::read_with_backup(path, |s| serde_json::from_str(s).ok())
+        .map(|(prefixes, _)| prefixes)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_hash_is_stable() {
+        assert_eq!(
+            repo_hash("/home/user/repos/api"),
+            repo_hash("/home/user/repos/api")
+        );
+        assert_ne!(repo_hash("/home/a/api"), repo_hash("/home/b/api"));
+        assert_eq!(repo_hash("").len(), 6);
+    }
+
+    #[test]
+    fn test_same_repo_names_get_distinct_windows() {
+        let names = WindowNames::in_memory(WindowPrefix::Name);
+        assert_eq!(names.window_name("/srv/a/api", "login"), "api:login");
+        let other = names.window_name("/srv/b/api/", "login");
+        assert_eq!(other, format!("api-{}:login", repo_hash("/srv/b/api")));
+        // Assignments are kept
+        assert_eq!(names.window_name("/srv/a/api/", "x"), "api:x");
+        assert_eq!(names.repo_path("api").as_deref(), Some("/srv/a/api"));
+        assert_eq!(names.prefix("/srv/c/my.app"), "my-app");
+    }
+
+    #[test]
+    fn test_hash_style() {
+        let names = WindowNames::in_memory(WindowPrefix::Hash);
+        assert_eq!(
+            names.prefix("/srv/a/api"),
+            format!("api-{}", repo_hash("/srv/a/api"))
+        );
+    }
+
+    #[test]
+    fn test_prefixes_are_shared_through_the_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+
+        let server = WindowNames::load(&settings);
+        let tui = WindowNames::load(&settings);
+        assert_eq!(server.prefix("/srv/a/api"), "api");
+        // Loaded before the server assigned "api", the TUI still agrees
+        assert_eq!(
+            tui.prefix("/srv/b/api"),
+            format!("api-{}", repo_hash("/srv/b/api"))
+        );
+        assert_eq!(WindowNames::load(&settings).prefix("/srv/a/api"), "api");
+    }
+}
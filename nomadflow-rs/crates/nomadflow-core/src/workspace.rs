@@ -0,0 +1,160 @@
+//! Descriptors telling editors how to open a feature's worktree, as a local
+//! folder or over SSH, with the hints of the repo's `.nomadflow.toml`:
+//!
+//! ```toml
+//! [editor]
+//! extensions = ["rust-lang.rust-analyzer"]
+//! open_files = ["README.md"]
+//! ```
+
+use std::path::{Component, Path};
+
+use serde::Deserialize;
+
+use crate::error::{NomadError, Result};
+use crate::models::{EditorHints, Feature, WorkspaceDescriptor};
+use crate::tasks::REPO_CONFIG;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct EditorTable {
+    extensions: Vec<String>,
+    open_files: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RepoConfig {
+    #[serde(default)]
+    editor: EditorTable,
+}
+
+/// Whether `path` stays inside the worktree it is relative to.
+fn is_inner_path(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+fn parse_hints(content: &str) -> std::result::Result<EditorHints, toml::de::Error> {
+    let config: RepoConfig = toml::from_str(content)?;
+    Ok(EditorHints {
+        extensions: config.editor.extensions,
+        // An editor should not be sent outside the worktree
+        open_files: config
+            .editor
+            .open_files
+            .into_iter()
+            .filter(|f| is_inner_path(f))
+            .collect(),
+    })
+}
+
+/// Editor hints of the `.nomadflow.toml` of `worktree`, empty without one.
+pub fn load_editor_hints(worktree: &Path) -> Result<EditorHints> {
+    let path = worktree.join(REPO_CONFIG);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(EditorHints::default()),
+        Err(e) => return Err(e.into()),
+    };
+    parse_hints(&content)
+        .map_err(|e| NomadError::Config(format!("Failed to parse {}: {e}", path.display())))
+}
+
+/// `path` with the bytes that are not allowed in a URI path percent-encoded.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Folder URI of `path`: `file://` when `ssh_host` is empty, a VS Code
+/// remote SSH URI otherwise.
+pub fn folder_uri(path: &str, ssh_host: &str) -> String {
+    if ssh_host.is_empty() {
+        format!("file://{}", encode_path(path))
+    } else {
+        format!(
+            "vscode-remote://ssh-remote+{}{}",
+            encode_path(ssh_host),
+            encode_path(path)
+        )
+    }
+}
+
+/// Descriptor of `feature` of the repo at `repo_path`.
+pub fn describe(repo_path: &str, feature: &Feature, ssh_host: &str) -> Result<WorkspaceDescriptor> {
+    let repo_name = Path::new(repo_path)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    Ok(WorkspaceDescriptor {
+        name: format!("{repo_name}/{}", feature.name),
+        repo_path: repo_path.to_string(),
+        feature_name: feature.name.clone(),
+        branch: feature.branch.clone(),
+        path: feature.worktree_path.clone(),
+        remote_host: Some(ssh_host.to_string()).filter(|h| !h.is_empty()),
+        uri: folder_uri(&feature.worktree_path, ssh_host),
+        hints: load_editor_hints(Path::new(&feature.worktree_path))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folder_uri() {
+        assert_eq!(folder_uri("/srv/wt/api", ""), "file:///srv/wt/api");
+        assert_eq!(
+            folder_uri("/srv/my wt/api", "me@devbox"),
+            "vscode-remote://ssh-remote+me%40devbox/srv/my%20wt/api"
+        );
+    }
+
+    #[test]
+    fn test_parse_hints() {
+        let hints = parse_hints(
+            r#"
+[tasks]
+test = "cargo test"
+
+[editor]
+extensions = ["rust-lang.rust-analyzer"]
+open_files = ["README.md", "../secret", "/etc/passwd", "src/main.rs"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(hints.extensions, vec!["rust-lang.rust-analyzer"]);
+        assert_eq!(hints.open_files, vec!["README.md", "src/main.rs"]);
+        assert_eq!(parse_hints("").unwrap(), EditorHints::default());
+        assert!(parse_hints("[editor]\nextensions = 1\n").is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let feature = Feature {
+            name: "login".to_string(),
+            worktree_path: tmp.path().to_string_lossy().to_string(),
+            branch: "feature/login".to_string(),
+            ..Default::default()
+        };
+        let local = describe("/srv/repos/api", &feature, "").unwrap();
+        assert_eq!(local.name, "api/login");
+        assert_eq!(local.remote_host, None);
+        assert!(local.uri.starts_with("file:///"));
+        assert!(local.hints.extensions.is_empty());
+
+        std::fs::write(tmp.path().join(REPO_CONFIG), "[editor\n").unwrap();
+        assert!(describe("/srv/repos/api", &feature, "devbox").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! Gitignored files a project needs to run (`.env`, local configs), copied
+//! from the repo's main worktree into each new one, as listed in the
+//! `[worktree]` table of its `.nomadflow.toml`:
+//!
+//! ```toml
+//! [worktree]
+//! copy = [".env*", "config/local.*"]
+//! ```
+//!
+//! `*` and `?` match within a path component; directories are not copied
+//! as a whole.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::{NomadError, Result};
+use crate::tasks::REPO_CONFIG;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct WorktreeTable {
+    copy: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RepoConfig {
+    #[serde(default)]
+    worktree: WorktreeTable,
+}
+
+/// Patterns of the files to copy into new worktrees, from the
+/// `.nomadflow.toml` of `worktree`. Empty without one.
+pub fn load_copy_patterns(worktree: &Path) -> Result<Vec<String>> {
+    let path = worktree.join(REPO_CONFIG);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    toml::from_str::<RepoConfig>(&content)
+        .map(|config| config.worktree.copy)
+        .map_err(|e| NomadError::Config(format!("Failed to parse {}: {e}", path.display())))
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any run of
+/// characters and `?` any one.
+fn matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches(rest, &name[1..]),
+    }
+}
+
+/// Files under `root`, relative to it, whose path matches `components`.
+fn find(root: &Path, dir: &Path, components: &[&str], found: &mut Vec<PathBuf>) {
+    let Some((first, rest)) = components.split_first() else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(root.join(dir)) else {
+        return;
+    };
+    let pattern: Vec<char> = first.chars().collect();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" || !matches(&pattern, &name.chars().collect::<Vec<_>>()) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let relative = dir.join(&name);
+        if rest.is_empty() {
+            if file_type.is_file() || (file_type.is_symlink() && entry.path().is_file()) {
+                found.push(relative);
+            }
+        } else if file_type.is_dir() {
+            find(root, &relative, rest, found);
+        }
+    }
+}
+
+/// Copy the files of `from` matching `patterns` into `to`, at the same
+/// relative paths, leaving the files `to` already has alone. Returns the
+/// paths copied.
+pub fn copy_matching(from: &Path, to: &Path, patterns: &[String]) -> Result<Vec<String>> {
+    let mut found = Vec::new();
+    for pattern in patterns {
+        let components: Vec<&str> = pattern
+            .split('/')
+            .filter(|c| !c.is_empty() && *c != ".")
+            .collect();
+        // Patterns must stay inside the worktree
+        if components.contains(&"..") {
+            continue;
+        }
+        find(from, Path::new(""), &components, &mut found);
+    }
+    found.sort();
+    found.dedup();
+
+    let mut copied = Vec::new();
+    for relative in found {
+        let target = to.join(&relative);
+        if target.symlink_metadata().is_ok() {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(from.join(&relative), &target)?;
+        copied.push(relative.to_string_lossy().to_string());
+    }
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let m = |p: &str, n: &str| {
+            matches(
+                &p.chars().collect::<Vec<_>>(),
+                &n.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert!(m(".env*", ".env"));
+        assert!(m(".env*", ".env.local"));
+        assert!(m("local.*", "local.yml"));
+        assert!(m("?.txt", "a.txt"));
+        assert!(!m("?.txt", "ab.txt"));
+        assert!(!m(".env*", "env"));
+    }
+
+    #[test]
+    fn test_copy_matching() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let (main, new) = (tmp.path().join("main"), tmp.path().join("new"));
+        std::fs::create_dir_all(main.join("config")).unwrap();
+        std::fs::create_dir_all(main.join(".git")).unwrap();
+        std::fs::create_dir_all(&new).unwrap();
+        for file in [
+            ".env",
+            ".env.local",
+            "config/local.yml",
+            "config/prod.yml",
+            ".git/HEAD",
+        ] {
+            std::fs::write(main.join(file), file).unwrap();
+        }
+        std::fs::write(new.join(".env.local"), "kept").unwrap();
+
+        let patterns = vec![
+            ".env*".to_string(),
+            "config/local.*".to_string(),
+            "../*".to_string(),
+            ".git/*".to_string(),
+        ];
+        let copied = copy_matching(&main, &new, &patterns).unwrap();
+        assert_eq!(copied, [".env", "config/local.yml"]);
+        assert_eq!(std::fs::read_to_string(new.join(".env")).unwrap(), ".env");
+        assert_eq!(
+            std::fs::read_to_string(new.join(".env.local")).unwrap(),
+            "kept"
+        );
+        assert!(!new.join("config/prod.yml").exists());
+    }
+}
@@ -0,0 +1,170 @@
+//! Opt-in crash reports, shared by the `nomadflow` binary and the relay.
+//!
+//! Once installed, a panic writes a report (version, platform, message,
+//! location, backtrace) to a file and prints one line pointing to it
+//! instead of a raw backtrace. When an upload URL is set, the report is
+//! also POSTed there with `curl`, in the background.
+
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where and how the panics of one program are reported.
+#[derive(Debug, Clone)]
+pub struct Reporter {
+    app: &'static str,
+    version: &'static str,
+    dir: PathBuf,
+    upload_url: Option<String>,
+}
+
+impl Reporter {
+    /// Reports of `app` written to `dir`.
+    pub fn new(app: &'static str, version: &'static str, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            app,
+            version,
+            dir: dir.into(),
+            upload_url: None,
+        }
+    }
+
+    /// Also send reports to `url`. Empty = keep them local.
+    pub fn with_upload(mut self, url: &str) -> Self {
+        self.upload_url = Some(url.to_string()).filter(|u| !u.is_empty());
+        self
+    }
+
+    /// Report every panic from now on, replacing the current panic hook.
+    pub fn install(self) {
+        std::panic::set_hook(Box::new(move |info| {
+            let report = self.render(&message(info), location(info), Backtrace::force_capture());
+            match self.save(&report) {
+                Ok(path) => {
+                    eprintln!(
+                        "\n{} {} crashed. A report was saved to {}",
+                        self.app,
+                        self.version,
+                        path.display()
+                    );
+                    if let Some(url) = &self.upload_url {
+                        upload(url, &path);
+                        eprintln!("It is being sent to {url}.");
+                    } else {
+                        eprintln!("Attaching it to a bug report helps fix the problem.");
+                    }
+                }
+                // Better a raw report than none
+                Err(_) => eprintln!("{report}"),
+            }
+        }));
+    }
+
+    fn render(&self, message: &str, location: Option<String>, backtrace: Backtrace) -> String {
+        let mut report = String::new();
+        let _ = writeln!(report, "app: {} {}", self.app, self.version);
+        let _ = writeln!(
+            report,
+            "platform: {} {}",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        let _ = writeln!(report, "time: {}", unix_now());
+        let thread = std::thread::current();
+        let _ = writeln!(report, "thread: {}", thread.name().unwrap_or("unnamed"));
+        if let Some(location) = location {
+            let _ = writeln!(report, "location: {location}");
+        }
+        let _ = writeln!(report, "message: {message}");
+        let _ = write!(report, "\nbacktrace:\n{backtrace}");
+        report
+    }
+
+    /// Write `report` to a new file of the report directory.
+    fn save(&self, report: &str) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!(
+            "{}-{}-{}.txt",
+            self.app,
+            unix_now(),
+            std::process::id()
+        ));
+        std::fs::write(&path, report)?;
+        Ok(path)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn location(info: &PanicHookInfo<'_>) -> Option<String> {
+    info.location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+}
+
+/// POST the report at `path` to `url` without waiting: the process is about
+/// to die, `curl` outlives it.
+fn upload(url: &str, path: &Path) {
+    let _ = Command::new("curl")
+        .args([
+            "-sf",
+            "-m",
+            "10",
+            "-H",
+            "Content-Type: text/plain",
+            "--data-binary",
+        ])
+        .arg(format!("@{}", path.display()))
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_is_saved() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let reporter =
+            Reporter::new("nomadflow", "1.2.3", tmp.path().join("crashes")).with_upload("");
+        assert!(reporter.upload_url.is_none());
+
+        let report = reporter.render(
+            "index out of bounds",
+            Some("src/app.rs:10:5".to_string()),
+            Backtrace::disabled(),
+        );
+        assert!(report.starts_with("app: nomadflow 1.2.3\n"));
+        assert!(report.contains("location: src/app.rs:10:5\n"));
+        assert!(report.contains("message: index out of bounds\n"));
+
+        let path = reporter.save(&report).unwrap();
+        assert!(path.starts_with(tmp.path().join("crashes")));
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("nomadflow-"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), report);
+    }
+}
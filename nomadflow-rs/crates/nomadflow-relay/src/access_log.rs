@@ -0,0 +1,293 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tracing::warn;
+
+/// Default size at which the log file is rotated.
+const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+/// Default number of rotated files kept (`access.log.1` … `access.log.N`).
+const DEFAULT_KEEP: usize = 5;
+
+/// One proxied request.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessEntry {
+    /// Unix timestamp (seconds) when the request arrived.
+    pub time: u64,
+    pub client_ip: IpAddr,
+    pub subdomain: String,
+    pub method: String,
+    pub path: String,
+    pub protocol: String,
+    pub status: u16,
+    /// Response body size, when known up front.
+    pub bytes: Option<u64>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Apache `vhost_common`: the common log format prefixed with the subdomain.
+    Clf,
+    /// One JSON object per line, with every field.
+    Json,
+}
+
+enum Sink {
+    Stdout,
+    File {
+        path: PathBuf,
+        file: File,
+        written: u64,
+        max_bytes: u64,
+        keep: usize,
+    },
+}
+
+/// Access log of the proxied traffic, written to stdout or a size-rotated file.
+pub struct AccessLog {
+    format: Format,
+    sink: Mutex<Sink>,
+}
+
+/// `[10/Oct/2000:13:55:36 +0000]` for a Unix timestamp, in UTC.
+fn clf_time(secs: u64) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "[{day:02}/{}/{year}:{:02}:{:02}:{:02} +0000]",
+        MONTHS[(month - 1) as usize],
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+impl AccessEntry {
+    pub fn new(
+        client_ip: IpAddr,
+        subdomain: &str,
+        method: &str,
+        path: &str,
+        protocol: &str,
+    ) -> Self {
+        Self {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            client_ip,
+            subdomain: subdomain.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            protocol: protocol.to_string(),
+            status: 0,
+            bytes: None,
+            duration_ms: 0,
+        }
+    }
+
+    /// Fill in the response side of the entry.
+    pub fn finish(&mut self, status: u16, bytes: Option<u64>, duration: Duration) {
+        self.status = status;
+        self.bytes = bytes;
+        self.duration_ms = duration.as_millis() as u64;
+    }
+
+    pub fn format(&self, format: Format) -> String {
+        match format {
+            Format::Clf => format!(
+                "{} {} - - {} \"{} {} {}\" {} {}",
+                if self.subdomain.is_empty() {
+                    "-"
+                } else {
+                    &self.subdomain
+                },
+                self.client_ip,
+                clf_time(self.time),
+                self.method,
+                self.path.replace('"', "%22"),
+                self.protocol,
+                self.status,
+                self.bytes.map_or("-".to_string(), |b| b.to_string()),
+            ),
+            Format::Json => serde_json::to_string(self).unwrap_or_default(),
+        }
+    }
+}
+
+impl AccessLog {
+    /// Configure from `ACCESS_LOG` (`stdout` or a file path; unset = disabled),
+    /// `ACCESS_LOG_FORMAT` (`clf` or `json`), `ACCESS_LOG_MAX_BYTES` and `ACCESS_LOG_KEEP`.
+    pub fn from_env() -> std::io::Result<Option<Self>> {
+        let target = std::env::var("ACCESS_LOG").unwrap_or_default();
+        let format = match std::env::var("ACCESS_LOG_FORMAT").as_deref() {
+            Ok("json") => Format::Json,
+            _ => Format::Clf,
+        };
+        match target.as_str() {
+            "" | "off" => Ok(None),
+            "stdout" => Ok(Some(Self::stdout(format))),
+            path => {
+                let max_bytes = std::env::var("ACCESS_LOG_MAX_BYTES")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_MAX_BYTES);
+                let keep = std::env::var("ACCESS_LOG_KEEP")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_KEEP);
+                Self::file(PathBuf::from(path), format, max_bytes, keep).map(Some)
+            }
+        }
+    }
+
+    pub fn stdout(format: Format) -> Self {
+        Self {
+            format,
+            sink: Mutex::new(Sink::Stdout),
+        }
+    }
+
+    /// Append to `path`, rotating it once it grows past `max_bytes`.
+    pub fn file(
+        path: PathBuf,
+        format: Format,
+        max_bytes: u64,
+        keep: usize,
+    ) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            format,
+            sink: Mutex::new(Sink::File {
+                path,
+                file,
+                written,
+                max_bytes,
+                keep,
+            }),
+        })
+    }
+
+    pub fn write(&self, entry: &AccessEntry) {
+        let line = entry.format(self.format);
+        let mut sink = self.sink.lock().unwrap();
+        match &mut *sink {
+            Sink::Stdout => println!("{line}"),
+            Sink::File {
+                path,
+                file,
+                written,
+                max_bytes,
+                keep,
+            } => {
+                if let Err(e) = writeln!(file, "{line}") {
+                    warn!("Failed to write access log: {e}");
+                    return;
+                }
+                *written += line.len() as u64 + 1;
+                if *written >= *max_bytes {
+                    match rotate(path, *keep) {
+                        Ok(new_file) => {
+                            *file = new_file;
+                            *written = 0;
+                        }
+                        Err(e) => warn!("Failed to rotate access log: {e}"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Shift `log.N-1` → `log.N` … `log` → `log.1` and reopen an empty `log`.
+fn rotate(path: &std::path::Path, keep: usize) -> std::io::Result<File> {
+    let rotated = |n: usize| PathBuf::from(format!("{}.{n}", path.display()));
+    if keep == 0 {
+        std::fs::remove_file(path)?;
+    } else {
+        for n in (1..keep).rev() {
+            if rotated(n).exists() {
+                std::fs::rename(rotated(n), rotated(n + 1))?;
+            }
+        }
+        std::fs::rename(path, rotated(1))?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> AccessEntry {
+        AccessEntry {
+            time: 971_186_136,
+            client_ip: "203.0.113.7".parse().unwrap(),
+            subdomain: "fabien".to_string(),
+            method: "GET".to_string(),
+            path: "/api/list-repos?x=\"1\"".to_string(),
+            protocol: "HTTP/1.1".to_string(),
+            status: 200,
+            bytes: Some(2326),
+            duration_ms: 12,
+        }
+    }
+
+    #[test]
+    fn clf_time_is_utc() {
+        assert_eq!(clf_time(971_186_136), "[10/Oct/2000:13:55:36 +0000]");
+        assert_eq!(clf_time(0), "[01/Jan/1970:00:00:00 +0000]");
+        assert_eq!(clf_time(1_709_208_000), "[29/Feb/2024:12:00:00 +0000]");
+    }
+
+    #[test]
+    fn formats_vhost_common_and_json() {
+        assert_eq!(
+            entry().format(Format::Clf),
+            "fabien 203.0.113.7 - - [10/Oct/2000:13:55:36 +0000] \"GET /api/list-repos?x=%221%22 HTTP/1.1\" 200 2326"
+        );
+
+        let mut streamed = entry();
+        streamed.bytes = None;
+        assert!(streamed.format(Format::Clf).ends_with(" 200 -"));
+
+        let json: serde_json::Value = serde_json::from_str(&entry().format(Format::Json)).unwrap();
+        assert_eq!(json["subdomain"], "fabien");
+        assert_eq!(json["duration_ms"], 12);
+    }
+
+    #[test]
+    fn file_is_rotated() {
+        let dir = std::env::temp_dir().join(format!("nomadflow-relay-log-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("access.log");
+        let log = AccessLog::file(path.clone(), Format::Clf, 100, 2).unwrap();
+
+        for _ in 0..6 {
+            log.write(&entry());
+        }
+        assert!(dir.join("access.log.1").exists());
+        assert!(dir.join("access.log.2").exists());
+        assert!(!dir.join("access.log.3").exists());
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,286 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use nomadflow_core::fsutil::JsonStore;
+
+use crate::limits::Limits;
+
+/// A relay user identified by an API key, with its own quotas.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+    pub max_tunnels: usize,
+    pub max_registrations_per_hour: usize,
+    /// Seconds an idle tunnel of this account is kept, instead of the
+    /// relay's `RELAY_TUNNEL_TTL_SECS`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tunnel_ttl_secs: Option<u64>,
+    /// Subdomains only this account may register.
+    pub reserved_subdomains: Vec<String>,
+    /// Unix timestamp (seconds).
+    pub created_at: u64,
+    /// SHA-256 of the API key; the key itself is only shown once, when issued.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub key_hash: String,
+    /// SHA-256 of the client certificate that authenticates this account
+    /// instead of its key (lowercase hex). Empty = API key only.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub cert_fingerprint: String,
+}
+
+impl Account {
+    /// The account as returned by the admin API, without its key hash.
+    pub fn public(&self) -> Self {
+        Self {
+            key_hash: String::new(),
+            ..self.clone()
+        }
+    }
+
+    /// How long an idle tunnel of this account is kept.
+    pub fn tunnel_ttl(&self, limits: &Limits) -> Duration {
+        self.tunnel_ttl_secs
+            .map(Duration::from_secs)
+            .unwrap_or(limits.tunnel_ttl)
+    }
+}
+
+/// Parameters of a new API key.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewAccount {
+    pub name: String,
+    pub max_tunnels: Option<usize>,
+    pub max_registrations_per_hour: Option<usize>,
+    pub tunnel_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub reserved_subdomains: Vec<String>,
+    /// SHA-256 of a client certificate issued to the user, hex with or
+    /// without colons.
+    #[serde(default)]
+    pub cert_fingerprint: String,
+}
+
+/// A certificate fingerprint as lowercase hex without separators, the way
+/// Caddy reports it.
+pub fn normalize_fingerprint(fingerprint: &str) -> String {
+    fingerprint
+        .trim()
+        .chars()
+        .filter(|c| *c != ':')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn random_alphanumeric(len: usize) -> String {
+    rand::rng()
+        .sample_iter(rand::distr::Alphanumeric)
+        .take(len)
+        .map(|b| b as char)
+        .collect()
+}
+
+/// API keys issued through the admin API, persisted to a JSON file.
+pub struct AccountStore {
+    accounts: JsonStore<Vec<Account>>,
+}
+
+impl AccountStore {
+    /// Load the accounts from `path`, or start empty if it does not exist yet.
+    pub fn load(path: PathBuf) -> Self {
+        Self {
+            accounts: JsonStore::load(path),
+        }
+    }
+
+    /// A store that is never written to disk.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self {
+            accounts: JsonStore::in_memory(),
+        }
+    }
+
+    /// Issue a new API key, with the quotas of `limits` unless given. Returns
+    /// the key and its account, or an error if a requested subdomain is
+    /// already reserved by another account.
+    pub fn issue(&self, new: NewAccount, limits: &Limits) -> Result<(String, Account), String> {
+        let mut accounts = self.accounts.lock();
+        if let Some(taken) = new
+            .reserved_subdomains
+            .iter()
+            .find(|s| accounts.iter().any(|a| a.reserved_subdomains.contains(s)))
+        {
+            return Err(format!("subdomain {taken} is already reserved"));
+        }
+        let cert_fingerprint = normalize_fingerprint(&new.cert_fingerprint);
+        if !cert_fingerprint.is_empty()
+            && accounts.iter().any(|a| a.cert_fingerprint == cert_fingerprint)
+        {
+            return Err("certificate is already used by another account".to_string());
+        }
+
+        let key = format!("nfk_{}", random_alphanumeric(40));
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let account = Account {
+            id: random_alphanumeric(8).to_lowercase(),
+            name: new.name,
+            max_tunnels: new.max_tunnels.unwrap_or(limits.max_tunnels_per_ip),
+            max_registrations_per_hour: new
+                .max_registrations_per_hour
+                .unwrap_or(limits.max_registrations_per_hour),
+            tunnel_ttl_secs: new.tunnel_ttl_secs,
+            reserved_subdomains: new.reserved_subdomains,
+            created_at,
+            key_hash: hash_key(&key),
+            cert_fingerprint,
+        };
+        accounts.push(account.clone());
+        self.accounts.persist(&accounts);
+        Ok((key, account))
+    }
+
+    /// The account an API key belongs to.
+    pub fn find_by_key(&self, key: &str) -> Option<Account> {
+        let hash = hash_key(key);
+        self.accounts
+            .lock()
+            .iter()
+            .find(|a| bool::from(a.key_hash.as_bytes().ct_eq(hash.as_bytes())))
+            .cloned()
+    }
+
+    /// The account a client certificate belongs to.
+    pub fn find_by_cert(&self, fingerprint: &str) -> Option<Account> {
+        let fingerprint = normalize_fingerprint(fingerprint);
+        if fingerprint.is_empty() {
+            return None;
+        }
+        self.accounts
+            .lock()
+            .iter()
+            .find(|a| a.cert_fingerprint == fingerprint)
+            .cloned()
+    }
+
+    /// Id of the account that reserved `subdomain`, if any.
+    pub fn reserved_by(&self, subdomain: &str) -> Option<String> {
+        self.accounts
+            .lock()
+            .iter()
+            .find(|a| a.reserved_subdomains.iter().any(|s| s == subdomain))
+            .map(|a| a.id.clone())
+    }
+
+    pub fn list(&self) -> Vec<Account> {
+        self.accounts.lock().iter().map(Account::public).collect()
+    }
+
+    /// Revoke an API key. Its tunnels stay up until they go stale.
+    pub fn revoke(&self, id: &str) -> bool {
+        let mut accounts = self.accounts.lock();
+        let before = accounts.len();
+        accounts.retain(|a| a.id != id);
+        let revoked = accounts.len() != before;
+        if revoked {
+            self.accounts.persist(&accounts);
+        }
+        revoked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_account(name: &str, reserved: &[&str]) -> NewAccount {
+        NewAccount {
+            name: name.to_string(),
+            max_tunnels: None,
+            max_registrations_per_hour: Some(2),
+            tunnel_ttl_secs: None,
+            reserved_subdomains: reserved.iter().map(|s| s.to_string()).collect(),
+            cert_fingerprint: String::new(),
+        }
+    }
+
+    #[test]
+    fn issue_and_find_by_key() {
+        let store = AccountStore::in_memory();
+        let (key, account) = store
+            .issue(new_account("alice", &["alice"]), &Limits::default())
+            .unwrap();
+        assert_eq!(account.max_tunnels, Limits::default().max_tunnels_per_ip);
+        assert_eq!(account.max_registrations_per_hour, 2);
+
+        assert_eq!(store.find_by_key(&key).unwrap().id, account.id);
+        assert!(store.find_by_key("nfk_wrong").is_none());
+        assert_eq!(store.reserved_by("alice"), Some(account.id.clone()));
+        assert_eq!(store.reserved_by("bob"), None);
+
+        // Listing never exposes key hashes
+        assert!(store.list()[0].key_hash.is_empty());
+
+        assert!(store.revoke(&account.id));
+        assert!(store.find_by_key(&key).is_none());
+        assert!(!store.revoke(&account.id));
+    }
+
+    #[test]
+    fn find_by_cert_fingerprint() {
+        let store = AccountStore::in_memory();
+        let mut new = new_account("alice", &[]);
+        new.cert_fingerprint = "AB:CD:EF".to_string();
+        let (_, account) = store.issue(new, &Limits::default()).unwrap();
+        assert_eq!(account.cert_fingerprint, "abcdef");
+
+        assert_eq!(store.find_by_cert("abcdef").unwrap().id, account.id);
+        assert!(store.find_by_cert("abcdee").is_none());
+        assert!(store.find_by_cert("").is_none());
+
+        let mut other = new_account("mallory", &[]);
+        other.cert_fingerprint = "abcdef".to_string();
+        assert!(store.issue(other, &Limits::default()).is_err());
+    }
+
+    #[test]
+    fn reserved_subdomains_are_exclusive() {
+        let store = AccountStore::in_memory();
+        let limits = Limits::default();
+        store.issue(new_account("alice", &["alice"]), &limits).unwrap();
+        assert!(store.issue(new_account("mallory", &["alice"]), &limits).is_err());
+    }
+
+    #[test]
+    fn accounts_survive_reload() {
+        let path = std::env::temp_dir().join(format!(
+            "nomadflow-relay-accounts-{}.json",
+            random_alphanumeric(8)
+        ));
+        let (key, _) = AccountStore::load(path.clone())
+            .issue(new_account("alice", &[]), &Limits::default())
+            .unwrap();
+
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(!json.contains(&key));
+        assert!(AccountStore::load(path.clone()).find_by_key(&key).is_some());
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,98 @@
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use nomadflow_core::fsutil::JsonStore;
+
+use crate::stats::now_secs;
+
+/// An IP refused by the relay, set by the operator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ban {
+    pub ip: IpAddr,
+    #[serde(default)]
+    pub reason: String,
+    /// Unix timestamp (seconds).
+    pub created_at: u64,
+}
+
+/// IPs that may not register tunnels nor attach domains, persisted to a
+/// JSON file.
+pub struct BanList {
+    bans: JsonStore<Vec<Ban>>,
+}
+
+impl BanList {
+    /// Load the bans from `path`, or start empty if it does not exist yet.
+    pub fn load(path: PathBuf) -> Self {
+        Self {
+            bans: JsonStore::load(path),
+        }
+    }
+
+    /// A list that is never written to disk.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self {
+            bans: JsonStore::in_memory(),
+        }
+    }
+
+    /// Ban `ip`, or update the reason of an existing ban.
+    pub fn ban(&self, ip: IpAddr, reason: String) -> Ban {
+        let mut bans = self.bans.lock();
+        bans.retain(|b| b.ip != ip);
+        let ban = Ban {
+            ip,
+            reason,
+            created_at: now_secs(),
+        };
+        bans.push(ban.clone());
+        self.bans.persist(&bans);
+        ban
+    }
+
+    pub fn unban(&self, ip: IpAddr) -> bool {
+        let mut bans = self.bans.lock();
+        let before = bans.len();
+        bans.retain(|b| b.ip != ip);
+        let removed = bans.len() != before;
+        if removed {
+            self.bans.persist(&bans);
+        }
+        removed
+    }
+
+    pub fn is_banned(&self, ip: IpAddr) -> bool {
+        self.bans.lock().iter().any(|b| b.ip == ip)
+    }
+
+    pub fn list(&self) -> Vec<Ban> {
+        self.bans.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn ban_and_unban() {
+        let bans = BanList::in_memory();
+        let ip = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+        assert!(!bans.is_banned(ip));
+
+        bans.ban(ip, "spam".to_string());
+        bans.ban(ip, "phishing".to_string());
+        assert!(bans.is_banned(ip));
+        assert_eq!(bans.list().len(), 1);
+        assert_eq!(bans.list()[0].reason, "phishing");
+
+        assert!(bans.unban(ip));
+        assert!(!bans.is_banned(ip));
+        assert!(!bans.unban(ip));
+    }
+}
@@ -0,0 +1,128 @@
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::proto::rr::{RData, RecordType};
+use hickory_resolver::TokioAsyncResolver;
+
+/// Maximum number of custom domains attached to one tunnel.
+pub const MAX_DOMAINS_PER_TUNNEL: usize = 5;
+
+/// Lowercase hostname without port or trailing dot, as used for lookups.
+pub fn normalize_host(host: &str) -> String {
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// A fully qualified hostname: at least two labels of 1-63 alphanumeric
+/// characters or hyphens, no leading/trailing hyphens, at most 253 characters.
+pub fn is_valid_hostname(host: &str) -> bool {
+    host.len() <= 253
+        && host.split('.').count() >= 2
+        && host.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+}
+
+/// Subdomain of the tunnel serving `host`: a hostname under `tunnel_domain`
+/// (`abc123.tunnel.example.com` → `abc123`). Without a tunnel domain, any
+/// host's first label is used.
+pub fn tunnel_label(host: &str, tunnel_domain: &str) -> Option<String> {
+    if tunnel_domain.is_empty() {
+        return host
+            .split('.')
+            .next()
+            .filter(|l| !l.is_empty())
+            .map(str::to_string);
+    }
+    host.strip_suffix(tunnel_domain)?
+        .strip_suffix('.')
+        .filter(|l| !l.is_empty() && !l.contains('.'))
+        .map(str::to_string)
+}
+
+/// Checks the DNS records of custom domains.
+pub struct DomainVerifier {
+    resolver: TokioAsyncResolver,
+}
+
+impl DomainVerifier {
+    /// Use the system resolver, or public DNS if it cannot be read.
+    pub fn new() -> Self {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf().unwrap_or_else(|_| {
+            TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default())
+        });
+        Self { resolver }
+    }
+
+    /// Check that `hostname` is a CNAME to `target`.
+    pub async fn verify_cname(&self, hostname: &str, target: &str) -> Result<(), String> {
+        let lookup = self
+            .resolver
+            .lookup(format!("{hostname}."), RecordType::CNAME)
+            .await
+            .map_err(|e| format!("no CNAME record for {hostname}: {e}"))?;
+        let found: Vec<String> = lookup
+            .iter()
+            .filter_map(|rdata| match rdata {
+                RData::CNAME(name) => Some(normalize_host(&name.0.to_utf8())),
+                _ => None,
+            })
+            .collect();
+        if found.iter().any(|name| name == target) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{hostname} points to {} instead of {target}",
+                found.join(", ")
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_hosts() {
+        assert_eq!(normalize_host("Dev.MyCompany.com:443"), "dev.mycompany.com");
+        assert_eq!(normalize_host("dev.mycompany.com."), "dev.mycompany.com");
+        assert_eq!(
+            normalize_host("abc123.tunnel.example.com"),
+            "abc123.tunnel.example.com"
+        );
+    }
+
+    #[test]
+    fn hostname_format() {
+        assert!(is_valid_hostname("dev.mycompany.com"));
+        assert!(is_valid_hostname("a-b.c"));
+        assert!(!is_valid_hostname("localhost"));
+        assert!(!is_valid_hostname("-dev.mycompany.com"));
+        assert!(!is_valid_hostname("dev..mycompany.com"));
+        assert!(!is_valid_hostname("dev_1.mycompany.com"));
+    }
+
+    #[test]
+    fn tunnel_label_matches_full_hostname() {
+        let domain = "tunnel.example.com";
+        assert_eq!(
+            tunnel_label("abc123.tunnel.example.com", domain),
+            Some("abc123".to_string())
+        );
+        assert_eq!(tunnel_label("tunnel.example.com", domain), None);
+        assert_eq!(tunnel_label("a.b.tunnel.example.com", domain), None);
+        assert_eq!(tunnel_label("abc123.evil.com", domain), None);
+        assert_eq!(tunnel_label("abc123xtunnel.example.com", domain), None);
+
+        // Without a configured domain, the first label is used
+        assert_eq!(
+            tunnel_label("abc123.evil.com", ""),
+            Some("abc123".to_string())
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+/// Quotas of the clients using the shared secret, which are also the defaults
+/// of new API keys, and how long idle tunnels are kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    /// Active tunnels per IP address.
+    pub max_tunnels_per_ip: usize,
+    /// Tunnel registrations per IP address per hour.
+    pub max_registrations_per_hour: usize,
+    /// Tunnels unused for this long are dropped.
+    pub tunnel_ttl: Duration,
+    /// How often stale tunnels and rate limit entries are dropped.
+    pub cleanup_interval: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_tunnels_per_ip: 3,
+            max_registrations_per_hour: 10,
+            tunnel_ttl: Duration::from_secs(24 * 60 * 60),
+            cleanup_interval: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Parse `name` as a positive integer, or `default` if unset or empty.
+fn positive(
+    var: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    default: u64,
+) -> Result<u64, String> {
+    match var(name).as_deref().map(str::trim) {
+        None | Some("") => Ok(default),
+        Some(value) => match value.parse::<u64>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("{name} must be a positive integer, got {value:?}")),
+        },
+    }
+}
+
+impl Limits {
+    /// Configure from `RELAY_MAX_TUNNELS_PER_IP`, `RELAY_MAX_REGISTRATIONS_PER_HOUR`,
+    /// `RELAY_TUNNEL_TTL_SECS` and `RELAY_CLEANUP_INTERVAL_SECS`.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let default = Self::default();
+        let limits = Self {
+            max_tunnels_per_ip: positive(
+                &var,
+                "RELAY_MAX_TUNNELS_PER_IP",
+                default.max_tunnels_per_ip as u64,
+            )? as usize,
+            max_registrations_per_hour: positive(
+                &var,
+                "RELAY_MAX_REGISTRATIONS_PER_HOUR",
+                default.max_registrations_per_hour as u64,
+            )? as usize,
+            tunnel_ttl: Duration::from_secs(positive(
+                &var,
+                "RELAY_TUNNEL_TTL_SECS",
+                default.tunnel_ttl.as_secs(),
+            )?),
+            cleanup_interval: Duration::from_secs(positive(
+                &var,
+                "RELAY_CLEANUP_INTERVAL_SECS",
+                default.cleanup_interval.as_secs(),
+            )?),
+        };
+        if limits.cleanup_interval > limits.tunnel_ttl {
+            return Err(
+                "RELAY_CLEANUP_INTERVAL_SECS must not exceed RELAY_TUNNEL_TTL_SECS".to_string(),
+            );
+        }
+        Ok(limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from(vars: &[(&str, &str)]) -> Result<Limits, String> {
+        Limits::from_vars(|name| {
+            vars.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.to_string())
+        })
+    }
+
+    #[test]
+    fn unset_variables_keep_the_defaults() {
+        assert_eq!(from(&[]).unwrap(), Limits::default());
+        let limits = from(&[
+            ("RELAY_MAX_TUNNELS_PER_IP", "20"),
+            ("RELAY_TUNNEL_TTL_SECS", "3600"),
+        ])
+        .unwrap();
+        assert_eq!(limits.max_tunnels_per_ip, 20);
+        assert_eq!(limits.tunnel_ttl, Duration::from_secs(3600));
+        assert_eq!(limits.max_registrations_per_hour, 10);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(from(&[("RELAY_MAX_TUNNELS_PER_IP", "0")]).is_err());
+        assert!(from(&[("RELAY_MAX_REGISTRATIONS_PER_HOUR", "ten")]).is_err());
+        assert!(from(&[("RELAY_TUNNEL_TTL_SECS", "-1")]).is_err());
+        // Tunnels would outlive their TTL by more than the TTL itself
+        assert!(from(&[
+            ("RELAY_TUNNEL_TTL_SECS", "60"),
+            ("RELAY_CLEANUP_INTERVAL_SECS", "300"),
+        ])
+        .is_err());
+    }
+}
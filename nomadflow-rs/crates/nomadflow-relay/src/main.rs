@@ -1,3 +1,11 @@
+mod access_log;
+mod accounts;
+mod bans;
+mod domains;
+mod limits;
+mod stats;
+
+use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -7,26 +15,40 @@ use axum::{
     extract::{
         connect_info::ConnectInfo,
         ws::{WebSocket, WebSocketUpgrade},
-        FromRequest, Query, State,
+        FromRequest, Path, Query, State,
     },
-    http::{header, uri::Uri, Request, StatusCode},
+    http::{header, uri::Uri, HeaderMap, Request, StatusCode},
     response::{IntoResponse, Response},
-    routing::{get, post},
+    routing::{delete, get, post},
     Json, Router,
 };
 use dashmap::DashMap;
+use hmac::{Hmac, Mac};
 use hyper_util::{client::legacy::Client as HyperClient, rt::TokioExecutor};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use subtle::ConstantTimeEq;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tower_http::trace::TraceLayer;
 use tracing::{error, info, warn};
 
+use crate::access_log::{AccessEntry, AccessLog};
+use crate::accounts::{Account, AccountStore, NewAccount};
+use crate::bans::{Ban, BanList};
+use crate::domains::DomainVerifier;
+use crate::limits::Limits;
+use crate::stats::{now_secs, Stats, StatsSnapshot};
+
 /// Mapping entry: subdomain → bore port + last usage time
 struct TunnelEntry {
     bore_port: u16,
     last_used: Instant,
     client_ip: IpAddr,
+    /// Account of the API key used to register, if any.
+    account: Option<String>,
+    /// Key the server proved to hold at registration, used to sign the
+    /// shutdown notice.
+    tunnel_key: String,
 }
 
 struct RelayState {
@@ -34,14 +56,38 @@ struct RelayState {
     tunnels: DashMap<String, TunnelEntry>,
     /// IP → list of registration timestamps (for rate limiting)
     rate_limits: DashMap<IpAddr, Vec<Instant>>,
+    /// Account id → list of registration timestamps
+    account_rate_limits: DashMap<String, Vec<Instant>>,
     /// Shared secret for relay registration
     relay_secret: String,
+    /// Per-user API keys with their own quotas
+    accounts: AccountStore,
+    /// Quotas of the shared secret and defaults of new API keys
+    limits: Limits,
+    /// Bearer token for the admin API (empty = admin API disabled)
+    admin_secret: String,
+    /// Header in which the TLS front (Caddy) passes the fingerprint of a
+    /// verified client certificate (empty = client certificates ignored)
+    client_cert_header: String,
     /// Host where bore tunnel ports are accessible (default: 127.0.0.1, in Docker: host.docker.internal)
     bore_host: String,
     /// Minimum allowed bore port (must match bore --min-port)
     min_bore_port: u16,
     /// HTTP client for proxying to bore tunnels
     http_client: HyperClient<hyper_util::client::legacy::connect::HttpConnector, Body>,
+    /// Per-request access log of the proxied traffic (None = disabled)
+    access_log: Option<AccessLog>,
+    /// Base domain of the tunnels, e.g. `tunnel.nomadflowcode.dev`
+    /// (empty = match hosts on their first label only, no custom domains)
+    tunnel_domain: String,
+    /// Custom hostname → subdomain of the tunnel it fronts
+    domains: DashMap<String, String>,
+    /// Checks the CNAME records of custom domains
+    domain_verifier: DomainVerifier,
+    /// IPs the operator refused
+    bans: BanList,
+    /// Traffic and registration counts for the admin dashboard
+    stats: Stats,
 }
 
 // ── Registration API ──────────────────────────────────────────────────
@@ -49,8 +95,14 @@ struct RelayState {
 #[derive(Deserialize)]
 struct RegisterRequest {
     port: u16,
+    #[serde(default)]
     secret: String,
     subdomain: Option<String>,
+    /// Per-user API key, used instead of the shared secret
+    api_key: Option<String>,
+    /// Key the server behind the tunnel uses to answer the registration challenge
+    #[serde(default)]
+    tunnel_key: String,
 }
 
 #[derive(Serialize)]
@@ -58,11 +110,6 @@ struct RegisterResponse {
     subdomain: String,
 }
 
-/// Maximum number of active tunnels per IP address.
-const MAX_TUNNELS_PER_IP: usize = 3;
-/// Maximum number of tunnel registrations per IP per hour.
-const MAX_REGISTRATIONS_PER_HOUR: usize = 10;
-
 /// Extract client IP from X-Forwarded-For (set by Caddy) with fallback to ConnectInfo.
 fn extract_client_ip(headers: &axum::http::HeaderMap, connect_info: &ConnectInfo<SocketAddr>) -> IpAddr {
     headers
@@ -81,6 +128,11 @@ async fn register(
 ) -> Result<Json<RegisterResponse>, StatusCode> {
     let client_ip = extract_client_ip(&headers, &ConnectInfo(addr));
 
+    if state.bans.is_banned(client_ip) {
+        warn!(%client_ip, "Registration rejected: banned IP");
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // Reject ports below the bore minimum
     if let Err(msg) = validate_port(req.port, state.min_bore_port) {
         warn!(port = req.port, %client_ip, "Registration rejected: {msg}");
@@ -93,72 +145,77 @@ async fn register(
         return Err(StatusCode::CONFLICT);
     }
 
-    // Verify secret (constant-time comparison)
-    if !state.relay_secret.is_empty() {
-        let matches: bool = req
-            .secret
-            .as_bytes()
-            .ct_eq(state.relay_secret.as_bytes())
-            .into();
-        if !matches {
-            warn!(%client_ip, "Registration rejected: invalid secret");
-            return Err(StatusCode::UNAUTHORIZED);
-        }
-    }
-
-    // Rate limit: count active tunnels for this IP
-    let active_count = state
-        .tunnels
-        .iter()
-        .filter(|entry| entry.value().client_ip == client_ip)
-        .count();
-    if active_count >= MAX_TUNNELS_PER_IP {
-        warn!(%client_ip, active_count, "Registration rejected: too many active tunnels");
+    let cert = client_cert(&state, &headers);
+    let account = authenticate(&state, &req.secret, req.api_key.as_deref(), cert, client_ip)?;
+    let account_id = account.as_ref().map(|a| a.id.clone());
+
+    // Quotas: per account for API keys, per IP otherwise
+    let (active_count, max_tunnels) = match &account {
+        Some(account) => (
+            state
+                .tunnels
+                .iter()
+                .filter(|entry| entry.value().account.as_deref() == Some(account.id.as_str()))
+                .count(),
+            account.max_tunnels,
+        ),
+        None => (
+            state
+                .tunnels
+                .iter()
+                .filter(|entry| entry.value().client_ip == client_ip)
+                .count(),
+            state.limits.max_tunnels_per_ip,
+        ),
+    };
+    if active_count >= max_tunnels {
+        warn!(%client_ip, account = ?account_id, active_count, "Registration rejected: too many active tunnels");
         return Err(StatusCode::TOO_MANY_REQUESTS);
     }
 
-    // Rate limit: count recent registrations for this IP (last hour)
-    let one_hour_ago = Instant::now() - Duration::from_secs(3600);
-    let recent_count = {
-        let mut entry = state.rate_limits.entry(client_ip).or_default();
-        entry.retain(|ts| *ts > one_hour_ago);
-        entry.len()
+    // Rate limit: count recent registrations (last hour), then record this one
+    let allowed = match &account {
+        Some(account) => record_registration(
+            &mut state.account_rate_limits.entry(account.id.clone()).or_default(),
+            account.max_registrations_per_hour,
+            Instant::now(),
+        ),
+        None => record_registration(
+            &mut state.rate_limits.entry(client_ip).or_default(),
+            state.limits.max_registrations_per_hour,
+            Instant::now(),
+        ),
     };
-    if recent_count >= MAX_REGISTRATIONS_PER_HOUR {
-        warn!(%client_ip, recent_count, "Registration rejected: too many registrations per hour");
+    if let Err(recent_count) = allowed {
+        warn!(%client_ip, account = ?account_id, recent_count, "Registration rejected: too many registrations per hour");
         return Err(StatusCode::TOO_MANY_REQUESTS);
     }
 
-    // Record this registration
-    state
-        .rate_limits
-        .entry(client_ip)
-        .or_default()
-        .push(Instant::now());
-
     // Resolve subdomain: use preferred if provided, otherwise generate random
     let subdomain = if let Some(preferred) = req.subdomain {
-        // Validate format: alphanumeric + hyphens, 3-32 chars, no leading/trailing hyphens
-        if preferred.len() < 3
-            || preferred.len() > 32
-            || !preferred
-                .chars()
-                .all(|c| c.is_ascii_alphanumeric() || c == '-')
-            || preferred.starts_with('-')
-            || preferred.ends_with('-')
-        {
+        if !is_valid_subdomain(&preferred) {
             warn!(%client_ip, subdomain = %preferred, "Registration rejected: invalid subdomain format");
             return Err(StatusCode::BAD_REQUEST);
         }
 
-        // Check if already taken
-        if let Some(existing) = state.tunnels.get(&preferred) {
-            if existing.client_ip != client_ip {
-                warn!(%client_ip, subdomain = %preferred, "Registration rejected: subdomain taken by another IP");
+        match state.accounts.reserved_by(&preferred) {
+            // Reserved for this account: take it over from whoever holds it
+            Some(owner) if account_id.as_deref() == Some(owner.as_str()) => {}
+            Some(_) => {
+                warn!(%client_ip, subdomain = %preferred, "Registration rejected: subdomain reserved by another account");
                 return Err(StatusCode::CONFLICT);
             }
-            // Same IP → re-register (update bore_port)
-            drop(existing);
+            None => {
+                // Check if already taken
+                if let Some(existing) = state.tunnels.get(&preferred) {
+                    if !is_same_owner(&existing, account_id.as_deref(), client_ip) {
+                        warn!(%client_ip, subdomain = %preferred, "Registration rejected: subdomain taken by another owner");
+                        return Err(StatusCode::CONFLICT);
+                    }
+                    // Same owner → re-register (update bore_port)
+                    drop(existing);
+                }
+            }
         }
 
         preferred
@@ -166,20 +223,244 @@ async fn register(
         generate_subdomain()
     };
 
+    // Prove that the port leads to the registering server, not someone else's tunnel
+    if let Err(msg) = verify_tunnel(&state, req.port, &req.tunnel_key).await {
+        warn!(port = req.port, %client_ip, "Registration rejected: {msg}");
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     state.tunnels.insert(
         subdomain.clone(),
         TunnelEntry {
             bore_port: req.port,
             last_used: Instant::now(),
             client_ip,
+            account: account_id.clone(),
+            tunnel_key: req.tunnel_key,
         },
     );
 
-    info!(subdomain = %subdomain, port = req.port, %client_ip, "Tunnel registered");
+    state.stats.record_registration(now_secs());
+    info!(subdomain = %subdomain, port = req.port, %client_ip, account = ?account_id, "Tunnel registered");
 
     Ok(Json(RegisterResponse { subdomain }))
 }
 
+/// Fingerprint of the client certificate the TLS front verified, if the
+/// relay is set up to trust it.
+fn client_cert<'a>(state: &RelayState, headers: &'a HeaderMap) -> Option<&'a str> {
+    if state.client_cert_header.is_empty() {
+        return None;
+    }
+    headers
+        .get(state.client_cert_header.as_str())
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.trim().is_empty())
+}
+
+/// Identify the caller: the account of a client certificate or of a per-user
+/// API key, or `None` for the shared secret.
+fn authenticate(
+    state: &RelayState,
+    secret: &str,
+    api_key: Option<&str>,
+    cert: Option<&str>,
+    client_ip: IpAddr,
+) -> Result<Option<Account>, StatusCode> {
+    if let Some(fingerprint) = cert {
+        return match state.accounts.find_by_cert(fingerprint) {
+            Some(account) => Ok(Some(account)),
+            None => {
+                warn!(%client_ip, "Request rejected: unknown client certificate");
+                Err(StatusCode::UNAUTHORIZED)
+            }
+        };
+    }
+    match api_key.filter(|k| !k.is_empty()) {
+        Some(key) => match state.accounts.find_by_key(key) {
+            Some(account) => Ok(Some(account)),
+            None => {
+                warn!(%client_ip, "Request rejected: unknown API key");
+                Err(StatusCode::UNAUTHORIZED)
+            }
+        },
+        None => {
+            // Verify secret (constant-time comparison)
+            if !state.relay_secret.is_empty() {
+                let matches: bool = secret.as_bytes().ct_eq(state.relay_secret.as_bytes()).into();
+                if !matches {
+                    warn!(%client_ip, "Request rejected: invalid secret");
+                    return Err(StatusCode::UNAUTHORIZED);
+                }
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Subdomain format: alphanumeric + hyphens, 3-32 chars, no leading/trailing hyphens.
+fn is_valid_subdomain(subdomain: &str) -> bool {
+    (3..=32).contains(&subdomain.len())
+        && subdomain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !subdomain.starts_with('-')
+        && !subdomain.ends_with('-')
+}
+
+/// Whether a registered tunnel belongs to the caller: the same account for
+/// API keys, the same IP for the shared secret.
+fn is_same_owner(entry: &TunnelEntry, account: Option<&str>, client_ip: IpAddr) -> bool {
+    match (entry.account.as_deref(), account) {
+        (Some(owner), Some(account)) => owner == account,
+        (None, None) => entry.client_ip == client_ip,
+        _ => false,
+    }
+}
+
+/// Record a registration unless `max` were already made in the last hour.
+/// On refusal, returns the number of recent registrations.
+fn record_registration(timestamps: &mut Vec<Instant>, max: usize, now: Instant) -> Result<(), usize> {
+    timestamps.retain(|ts| now.duration_since(*ts) < Duration::from_secs(3600));
+    if timestamps.len() >= max {
+        return Err(timestamps.len());
+    }
+    timestamps.push(now);
+    Ok(())
+}
+
+/// Path of the server endpoint answering the registration challenge.
+const CHALLENGE_PATH: &str = "/_tunnel/challenge";
+
+/// Expected answer to a challenge: hex HMAC-SHA256 of `nonce` keyed with the tunnel key.
+fn challenge_response(tunnel_key: &str, nonce: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(tunnel_key.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(nonce.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Send a random nonce through the bore port and check that the server behind
+/// it answers with the HMAC of the tunnel key given at registration.
+async fn verify_tunnel(state: &RelayState, port: u16, tunnel_key: &str) -> Result<(), String> {
+    if tunnel_key.is_empty() {
+        return Err("missing tunnel key (client too old?)".to_string());
+    }
+    let nonce = generate_nonce();
+    let bore_host = &state.bore_host;
+    let uri: Uri = format!("http://{bore_host}:{port}{CHALLENGE_PATH}?nonce={nonce}")
+        .parse()
+        .map_err(|e| format!("invalid challenge URI: {e}"))?;
+    let request = Request::get(uri)
+        .body(Body::empty())
+        .map_err(|e| e.to_string())?;
+
+    let response = tokio::time::timeout(Duration::from_secs(5), state.http_client.request(request))
+        .await
+        .map_err(|_| "challenge timed out".to_string())?
+        .map_err(|e| format!("challenge failed: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("challenge answered {}", response.status()));
+    }
+    let body = axum::body::to_bytes(Body::new(response.into_body()), 1024)
+        .await
+        .map_err(|e| format!("challenge body: {e}"))?;
+
+    let expected = challenge_response(tunnel_key, &nonce);
+    if bool::from(body.as_ref().ct_eq(expected.as_bytes())) {
+        Ok(())
+    } else {
+        Err("challenge answer does not match the tunnel key".to_string())
+    }
+}
+
+/// Path of the server endpoint receiving the relay shutdown notice.
+const SHUTDOWN_PATH: &str = "/_tunnel/shutdown";
+
+/// Tell every registered server that the relay is going away, so that it
+/// re-registers with a fallback relay right away instead of waiting for its
+/// tunnel to fail. Returns the number of servers that acknowledged.
+async fn notify_tunnels(state: &RelayState) -> usize {
+    let timestamp = now_secs();
+    let targets: Vec<(String, u16, String)> = state
+        .tunnels
+        .iter()
+        .map(|e| (e.key().clone(), e.bore_port, e.tunnel_key.clone()))
+        .collect();
+
+    let notices = targets.into_iter().map(|(subdomain, port, tunnel_key)| async move {
+        let body = serde_json::json!({
+            "timestamp": timestamp,
+            "signature": challenge_response(&tunnel_key, &format!("shutdown:{timestamp}")),
+        });
+        let request = Request::post(format!("http://{}:{port}{SHUTDOWN_PATH}", state.bore_host))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()));
+        let Ok(request) = request else {
+            return false;
+        };
+        match tokio::time::timeout(Duration::from_secs(3), state.http_client.request(request)).await {
+            Ok(Ok(response)) if response.status().is_success() => true,
+            Ok(Ok(response)) => {
+                warn!(%subdomain, status = %response.status(), "Shutdown notice refused");
+                false
+            }
+            Ok(Err(e)) => {
+                warn!(%subdomain, "Shutdown notice failed: {e}");
+                false
+            }
+            Err(_) => {
+                warn!(%subdomain, "Shutdown notice timed out");
+                false
+            }
+        }
+    });
+    futures_util::future::join_all(notices)
+        .await
+        .into_iter()
+        .filter(|&ok| ok)
+        .count()
+}
+
+/// Resolve on Ctrl+C or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Cannot listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+fn generate_nonce() -> String {
+    use rand::Rng;
+    rand::rng()
+        .sample_iter(rand::distr::Alphanumeric)
+        .take(32)
+        .map(|b| b as char)
+        .collect()
+}
+
 /// Validate that a bore port is within the allowed range.
 fn validate_port(port: u16, min_bore_port: u16) -> Result<(), String> {
     if port < min_bore_port {
@@ -216,6 +497,294 @@ fn generate_subdomain() -> String {
     chars.into_iter().collect()
 }
 
+// ── Custom domains ────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+struct DomainRequest {
+    subdomain: String,
+    hostname: String,
+    #[serde(default)]
+    secret: String,
+    api_key: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DomainResponse {
+    hostname: String,
+    subdomain: String,
+}
+
+/// Attach a custom hostname to a tunnel. The hostname must be a CNAME to the
+/// tunnel's own hostname, and only the tunnel's owner may attach it.
+async fn attach_domain(
+    State(state): State<Arc<RelayState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(req): Json<DomainRequest>,
+) -> Result<Json<DomainResponse>, StatusCode> {
+    if state.tunnel_domain.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let client_ip = extract_client_ip(&headers, &ConnectInfo(addr));
+    if state.bans.is_banned(client_ip) {
+        warn!(%client_ip, "Custom domain rejected: banned IP");
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let hostname = domains::normalize_host(&req.hostname);
+    if !domains::is_valid_hostname(&hostname)
+        || domains::tunnel_label(&hostname, &state.tunnel_domain).is_some()
+        || hostname == state.tunnel_domain
+    {
+        warn!(%client_ip, %hostname, "Custom domain rejected: invalid hostname");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let cert = client_cert(&state, &headers);
+    let account = authenticate(&state, &req.secret, req.api_key.as_deref(), cert, client_ip)?;
+    let account_id = account.as_ref().map(|a| a.id.as_str());
+    match state.tunnels.get(&req.subdomain) {
+        Some(entry) if is_same_owner(&entry, account_id, client_ip) => {}
+        Some(_) => {
+            warn!(%client_ip, %hostname, subdomain = %req.subdomain, "Custom domain rejected: tunnel owned by someone else");
+            return Err(StatusCode::FORBIDDEN);
+        }
+        None => return Err(StatusCode::NOT_FOUND),
+    }
+
+    let attached = state
+        .domains
+        .iter()
+        .filter(|d| d.value() == &req.subdomain && d.key() != &hostname)
+        .count();
+    if attached >= domains::MAX_DOMAINS_PER_TUNNEL {
+        warn!(%client_ip, subdomain = %req.subdomain, "Custom domain rejected: too many domains");
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let target = format!("{}.{}", req.subdomain, state.tunnel_domain);
+    if let Err(msg) = state.domain_verifier.verify_cname(&hostname, &target).await {
+        warn!(%client_ip, %hostname, "Custom domain rejected: {msg}");
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    state.domains.insert(hostname.clone(), req.subdomain.clone());
+    info!(%hostname, subdomain = %req.subdomain, "Custom domain attached");
+    Ok(Json(DomainResponse {
+        hostname,
+        subdomain: req.subdomain,
+    }))
+}
+
+// ── Admin API (per-user API keys) ────────────────────────────────────
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateKeyResponse {
+    /// Only returned here; the relay keeps a hash.
+    api_key: String,
+    account: Account,
+}
+
+/// Check the admin bearer token. The admin API is hidden when no admin secret is set.
+fn check_admin(state: &RelayState, headers: &HeaderMap) -> Result<(), StatusCode> {
+    if state.admin_secret.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if bool::from(token.as_bytes().ct_eq(state.admin_secret.as_bytes())) {
+        Ok(())
+    } else {
+        warn!("Admin API rejected: invalid token");
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+async fn list_keys(
+    State(state): State<Arc<RelayState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Account>>, StatusCode> {
+    check_admin(&state, &headers)?;
+    Ok(Json(state.accounts.list()))
+}
+
+async fn create_key(
+    State(state): State<Arc<RelayState>>,
+    headers: HeaderMap,
+    Json(new): Json<NewAccount>,
+) -> Result<Json<CreateKeyResponse>, StatusCode> {
+    check_admin(&state, &headers)?;
+    if new.name.trim().is_empty()
+        || new.tunnel_ttl_secs == Some(0)
+        || !new.reserved_subdomains.iter().all(|s| is_valid_subdomain(s))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let (api_key, account) = state.accounts.issue(new, &state.limits).map_err(|e| {
+        warn!("API key rejected: {e}");
+        StatusCode::CONFLICT
+    })?;
+    info!(id = %account.id, name = %account.name, "API key issued");
+    Ok(Json(CreateKeyResponse {
+        api_key,
+        account: account.public(),
+    }))
+}
+
+async fn revoke_key(
+    State(state): State<Arc<RelayState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> StatusCode {
+    if let Err(status) = check_admin(&state, &headers) {
+        return status;
+    }
+    if state.accounts.revoke(&id) {
+        info!(%id, "API key revoked");
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+// ── Admin API (tunnels, bans, dashboard) ─────────────────────────────
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TunnelInfo {
+    subdomain: String,
+    port: u16,
+    client_ip: IpAddr,
+    account: Option<String>,
+    /// Seconds since the tunnel last proxied a request.
+    idle_secs: u64,
+    /// Custom domains attached to the tunnel.
+    domains: Vec<String>,
+}
+
+async fn list_tunnels(
+    State(state): State<Arc<RelayState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<TunnelInfo>>, StatusCode> {
+    check_admin(&state, &headers)?;
+    let mut tunnels: Vec<TunnelInfo> = state
+        .tunnels
+        .iter()
+        .map(|entry| TunnelInfo {
+            subdomain: entry.key().clone(),
+            port: entry.bore_port,
+            client_ip: entry.client_ip,
+            account: entry.account.clone(),
+            idle_secs: entry.last_used.elapsed().as_secs(),
+            domains: state
+                .domains
+                .iter()
+                .filter(|d| d.value() == entry.key())
+                .map(|d| d.key().clone())
+                .collect(),
+        })
+        .collect();
+    tunnels.sort_by(|a, b| a.subdomain.cmp(&b.subdomain));
+    Ok(Json(tunnels))
+}
+
+/// Drop the tunnels matching `close`, with their custom domains.
+fn close_tunnels(state: &RelayState, close: impl Fn(&str, &TunnelEntry) -> bool) -> usize {
+    let before = state.tunnels.len();
+    state.tunnels.retain(|subdomain, entry| !close(subdomain, entry));
+    state
+        .domains
+        .retain(|_, subdomain| state.tunnels.contains_key(subdomain));
+    before - state.tunnels.len()
+}
+
+async fn close_tunnel(
+    State(state): State<Arc<RelayState>>,
+    headers: HeaderMap,
+    Path(subdomain): Path<String>,
+) -> StatusCode {
+    if let Err(status) = check_admin(&state, &headers) {
+        return status;
+    }
+    if close_tunnels(&state, |s, _| s == subdomain) > 0 {
+        info!(%subdomain, "Tunnel closed by admin");
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn get_stats(
+    State(state): State<Arc<RelayState>>,
+    headers: HeaderMap,
+) -> Result<Json<StatsSnapshot>, StatusCode> {
+    check_admin(&state, &headers)?;
+    Ok(Json(state.stats.snapshot(now_secs())))
+}
+
+async fn list_bans(
+    State(state): State<Arc<RelayState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Ban>>, StatusCode> {
+    check_admin(&state, &headers)?;
+    Ok(Json(state.bans.list()))
+}
+
+#[derive(Deserialize)]
+struct BanRequest {
+    ip: IpAddr,
+    #[serde(default)]
+    reason: String,
+}
+
+/// Ban an IP and close the tunnels it registered.
+async fn create_ban(
+    State(state): State<Arc<RelayState>>,
+    headers: HeaderMap,
+    Json(req): Json<BanRequest>,
+) -> Result<Json<Ban>, StatusCode> {
+    check_admin(&state, &headers)?;
+    let ban = state.bans.ban(req.ip, req.reason);
+    let closed = close_tunnels(&state, |_, entry| entry.client_ip == req.ip);
+    info!(ip = %req.ip, closed, "IP banned");
+    Ok(Json(ban))
+}
+
+async fn delete_ban(
+    State(state): State<Arc<RelayState>>,
+    headers: HeaderMap,
+    Path(ip): Path<IpAddr>,
+) -> StatusCode {
+    if let Err(status) = check_admin(&state, &headers) {
+        return status;
+    }
+    if state.bans.unban(ip) {
+        info!(%ip, "IP unbanned");
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// The admin dashboard. The page holds no data: it asks for the admin
+/// secret and calls the admin API with it.
+async fn dashboard(State(state): State<Arc<RelayState>>) -> Response {
+    if state.admin_secret.is_empty() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    (
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        include_str!("dashboard.html"),
+    )
+        .into_response()
+}
+
 // ── Check endpoint (for Caddy on_demand TLS) ─────────────────────────
 
 #[derive(Deserialize)]
@@ -227,18 +796,10 @@ async fn check(
     State(state): State<Arc<RelayState>>,
     Query(query): Query<CheckQuery>,
 ) -> StatusCode {
-    // domain looks like "abc123.tunnel.nomadflowcode.dev"
-    let subdomain = query
-        .domain
-        .split('.')
-        .next()
-        .unwrap_or_default()
-        .to_string();
-
-    if state.tunnels.contains_key(&subdomain) {
-        StatusCode::OK
-    } else {
-        StatusCode::NOT_FOUND
+    // domain looks like "abc123.tunnel.nomadflowcode.dev" or a custom domain
+    match tunnel_subdomain(&state, &query.domain) {
+        Some(subdomain) if state.tunnels.contains_key(&subdomain) => StatusCode::OK,
+        _ => StatusCode::NOT_FOUND,
     }
 }
 
@@ -250,15 +811,30 @@ async fn health() -> &'static str {
 
 // ── Reverse proxy (subdomain → bore port) ─────────────────────────────
 
-/// Resolve subdomain from Host header → bore port. Updates `last_used` on each access.
-fn resolve_tunnel(state: &RelayState, req: &Request<Body>) -> Result<(String, u16), StatusCode> {
-    let host = req
-        .headers()
+/// Subdomain of the tunnel serving `host`: an attached custom domain, or a
+/// hostname under the tunnel domain.
+fn tunnel_subdomain(state: &RelayState, host: &str) -> Option<String> {
+    let host = domains::normalize_host(host);
+    if let Some(subdomain) = state.domains.get(&host) {
+        return Some(subdomain.clone());
+    }
+    domains::tunnel_label(&host, &state.tunnel_domain)
+}
+
+fn request_host(req: &Request<Body>) -> &str {
+    req.headers()
         .get(header::HOST)
         .and_then(|v| v.to_str().ok())
         .unwrap_or_default()
-        .to_string();
-    let subdomain = host.split('.').next().unwrap_or_default().to_string();
+}
+
+/// Resolve subdomain from Host header → bore port. Updates `last_used` on each access.
+fn resolve_tunnel(state: &RelayState, req: &Request<Body>) -> Result<(String, u16), StatusCode> {
+    let host = request_host(req);
+    let subdomain = tunnel_subdomain(state, host).ok_or_else(|| {
+        warn!(%host, "Unknown tunnel host");
+        StatusCode::NOT_FOUND
+    })?;
 
     let mut entry = state.tunnels.get_mut(&subdomain).ok_or_else(|| {
         warn!(subdomain = %subdomain, "Unknown tunnel subdomain");
@@ -282,9 +858,41 @@ fn is_ws_upgrade(req: &Request<Body>) -> bool {
 
 async fn proxy_handler(
     State(state): State<Arc<RelayState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     req: Request<Body>,
 ) -> Result<Response, StatusCode> {
-    let (subdomain, bore_port) = resolve_tunnel(&state, &req)?;
+    let start = Instant::now();
+    let entry = state.access_log.as_ref().map(|_| {
+        AccessEntry::new(
+            extract_client_ip(req.headers(), &ConnectInfo(addr)),
+            &tunnel_subdomain(&state, request_host(&req)).unwrap_or_default(),
+            req.method().as_str(),
+            req.uri().path_and_query().map(|pq| pq.as_str()).unwrap_or("/"),
+            &format!("{:?}", req.version()),
+        )
+    });
+
+    let result = proxy(&state, req).await;
+    let (status, bytes) = match &result {
+        Ok(resp) => (
+            resp.status(),
+            resp.headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok()),
+        ),
+        Err(status) => (*status, None),
+    };
+    state.stats.record_request(now_secs(), bytes);
+    if let (Some(access_log), Some(mut entry)) = (&state.access_log, entry) {
+        entry.finish(status.as_u16(), bytes, start.elapsed());
+        access_log.write(&entry);
+    }
+    result
+}
+
+async fn proxy(state: &Arc<RelayState>, req: Request<Body>) -> Result<Response, StatusCode> {
+    let (subdomain, bore_port) = resolve_tunnel(state, &req)?;
 
     // WebSocket upgrade: proxy bidirectionally
     if is_ws_upgrade(&req) {
@@ -305,7 +913,7 @@ async fn proxy_handler(
             .collect();
 
         let protocols_for_closure = protocols.clone();
-        let ws = WebSocketUpgrade::from_request(req, &state)
+        let ws = WebSocketUpgrade::from_request(req, state)
             .await
             .map_err(|_| StatusCode::BAD_REQUEST)?;
 
@@ -422,18 +1030,17 @@ async fn handle_ws_proxy(
 // ── Cleanup task ──────────────────────────────────────────────────────
 
 async fn cleanup_stale_tunnels(state: Arc<RelayState>) {
-    let ttl = Duration::from_secs(24 * 60 * 60); // 24 hours
     let rate_limit_window = Duration::from_secs(3600); // 1 hour
     loop {
-        tokio::time::sleep(Duration::from_secs(300)).await;
+        tokio::time::sleep(state.limits.cleanup_interval).await;
 
-        // Cleanup stale tunnels based on last usage (not creation time)
-        let before = state.tunnels.len();
-        state.tunnels.retain(|_, entry| entry.last_used.elapsed() < ttl);
-        let removed = before - state.tunnels.len();
+        let removed = remove_stale_tunnels(&state);
         if removed > 0 {
             info!(removed, "Cleaned up stale tunnel entries");
         }
+        state
+            .domains
+            .retain(|_, subdomain| state.tunnels.contains_key(subdomain));
 
         // Cleanup stale rate limit entries
         let now = Instant::now();
@@ -441,14 +1048,46 @@ async fn cleanup_stale_tunnels(state: Arc<RelayState>) {
             timestamps.retain(|ts| now.duration_since(*ts) < rate_limit_window);
             !timestamps.is_empty()
         });
+        state.account_rate_limits.retain(|_, timestamps| {
+            timestamps.retain(|ts| now.duration_since(*ts) < rate_limit_window);
+            !timestamps.is_empty()
+        });
     }
 }
 
+/// Drop the tunnels unused for longer than their account's TTL, or the
+/// relay's for the shared secret. Returns how many were dropped.
+fn remove_stale_tunnels(state: &RelayState) -> usize {
+    let ttls: HashMap<String, Duration> = state
+        .accounts
+        .list()
+        .into_iter()
+        .map(|a| (a.id.clone(), a.tunnel_ttl(&state.limits)))
+        .collect();
+    // Based on last usage, not creation time
+    let before = state.tunnels.len();
+    state.tunnels.retain(|_, entry| {
+        let ttl = entry
+            .account
+            .as_ref()
+            .and_then(|id| ttls.get(id))
+            .copied()
+            .unwrap_or(state.limits.tunnel_ttl);
+        entry.last_used.elapsed() < ttl
+    });
+    before - state.tunnels.len()
+}
+
 // ── Main ──────────────────────────────────────────────────────────────
 
 #[tokio::main]
 async fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
+    if let Ok(dir) = std::env::var("CRASH_REPORTS_DIR") {
+        nomadflow_crash::Reporter::new("nomadflow-relay", env!("CARGO_PKG_VERSION"), dir)
+            .with_upload(&std::env::var("CRASH_REPORTS_URL").unwrap_or_default())
+            .install();
+    }
 
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -458,6 +1097,11 @@ async fn main() -> color_eyre::Result<()> {
         .init();
 
     let relay_secret = std::env::var("RELAY_SECRET").unwrap_or_default();
+    let admin_secret = std::env::var("RELAY_ADMIN_SECRET").unwrap_or_default();
+    let client_cert_header = std::env::var("RELAY_CLIENT_CERT_HEADER").unwrap_or_default();
+    let accounts_file = std::env::var("RELAY_ACCOUNTS_FILE")
+        .unwrap_or_else(|_| "accounts.json".to_string());
+    let bans_file = std::env::var("RELAY_BANS_FILE").unwrap_or_else(|_| "bans.json".to_string());
     let bore_host =
         std::env::var("BORE_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
     let port: u16 = std::env::var("RELAY_PORT")
@@ -471,13 +1115,33 @@ async fn main() -> color_eyre::Result<()> {
 
     info!(%bore_host, min_bore_port, "Bore tunnel host");
 
+    let limits = Limits::from_env().map_err(|e| color_eyre::eyre::eyre!(e))?;
+    info!(?limits, "Relay limits");
+
+    let access_log = AccessLog::from_env()?;
+    let tunnel_domain = domains::normalize_host(&std::env::var("TUNNEL_DOMAIN").unwrap_or_default());
+    if tunnel_domain.is_empty() {
+        warn!("TUNNEL_DOMAIN is not set: hosts are matched on their first label and custom domains are disabled");
+    }
+
     let state = Arc::new(RelayState {
         tunnels: DashMap::new(),
         rate_limits: DashMap::new(),
+        account_rate_limits: DashMap::new(),
         relay_secret,
+        accounts: AccountStore::load(accounts_file.into()),
+        limits,
+        admin_secret,
+        client_cert_header,
         bore_host,
         min_bore_port,
         http_client: HyperClient::builder(TokioExecutor::new()).build_http(),
+        access_log,
+        tunnel_domain,
+        domains: DashMap::new(),
+        domain_verifier: DomainVerifier::new(),
+        bans: BanList::load(bans_file.into()),
+        stats: Stats::default(),
     });
 
     // Spawn cleanup task
@@ -487,11 +1151,21 @@ async fn main() -> color_eyre::Result<()> {
     let api = Router::new()
         .route("/_api/register", post(register))
         .route("/_api/check", get(check))
-        .route("/_api/health", get(health));
+        .route("/_api/health", get(health))
+        .route("/_api/domains", post(attach_domain))
+        .route("/_api/admin/keys", get(list_keys).post(create_key))
+        .route("/_api/admin/keys/{id}", delete(revoke_key))
+        .route("/_api/admin/tunnels", get(list_tunnels))
+        .route("/_api/admin/tunnels/{subdomain}", delete(close_tunnel))
+        .route("/_api/admin/stats", get(get_stats))
+        .route("/_api/admin/bans", get(list_bans).post(create_ban))
+        .route("/_api/admin/bans/{ip}", delete(delete_ban))
+        .route("/_admin", get(dashboard));
 
     // Catch-all proxy for subdomain traffic
     let proxy = Router::new().fallback(proxy_handler);
 
+    let notify_state = state.clone();
     let app = api
         .merge(proxy)
         .layer(TraceLayer::new_for_http())
@@ -501,11 +1175,21 @@ async fn main() -> color_eyre::Result<()> {
     info!(%addr, "NomadFlow Relay listening");
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(
+    let server = axum::serve(
         listener,
         app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await?;
+    );
+
+    // Not a graceful shutdown: open WebSocket bridges would hold it forever,
+    // and the servers are told to move elsewhere anyway.
+    tokio::select! {
+        result = server => result?,
+        _ = shutdown_signal() => {
+            let total = notify_state.tunnels.len();
+            let notified = notify_tunnels(&notify_state).await;
+            info!(notified, total, "Relay shutting down, tunnels notified");
+        }
+    }
 
     Ok(())
 }
@@ -541,6 +1225,8 @@ mod tests {
                 bore_port: 12345,
                 last_used: Instant::now(),
                 client_ip: ip_a,
+                account: None,
+                tunnel_key: String::new(),
             },
         );
 
@@ -559,6 +1245,8 @@ mod tests {
                 bore_port: 12345,
                 last_used: Instant::now(),
                 client_ip: ip_a,
+                account: None,
+                tunnel_key: String::new(),
             },
         );
 
@@ -566,6 +1254,158 @@ mod tests {
         assert!(!is_port_taken_by_other(&tunnels, 12345, ip_a));
     }
 
+    #[test]
+    fn subdomain_format() {
+        assert!(is_valid_subdomain("fabien"));
+        assert!(is_valid_subdomain("my-box-2"));
+        assert!(!is_valid_subdomain("ab"));
+        assert!(!is_valid_subdomain("-abc"));
+        assert!(!is_valid_subdomain("abc-"));
+        assert!(!is_valid_subdomain("a.b.c"));
+        assert!(!is_valid_subdomain(&"a".repeat(33)));
+    }
+
+    #[test]
+    fn owner_is_account_or_ip() {
+        let ip_a: IpAddr = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+        let ip_b: IpAddr = IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8));
+        let entry = |account: Option<&str>| TunnelEntry {
+            bore_port: 12345,
+            last_used: Instant::now(),
+            client_ip: ip_a,
+            account: account.map(str::to_string),
+            tunnel_key: String::new(),
+        };
+
+        assert!(is_same_owner(&entry(None), None, ip_a));
+        assert!(!is_same_owner(&entry(None), None, ip_b));
+        // An account keeps its tunnels when its IP changes
+        assert!(is_same_owner(&entry(Some("alice")), Some("alice"), ip_b));
+        assert!(!is_same_owner(&entry(Some("alice")), Some("bob"), ip_a));
+        assert!(!is_same_owner(&entry(Some("alice")), None, ip_a));
+        assert!(!is_same_owner(&entry(None), Some("alice"), ip_a));
+    }
+
+    #[test]
+    fn registrations_limited_per_hour() {
+        let now = Instant::now();
+        let mut timestamps = Vec::new();
+        assert!(record_registration(&mut timestamps, 2, now).is_ok());
+        assert!(record_registration(&mut timestamps, 2, now).is_ok());
+        assert_eq!(record_registration(&mut timestamps, 2, now), Err(2));
+        assert!(record_registration(&mut timestamps, 2, now + Duration::from_secs(3600)).is_ok());
+    }
+
+    #[test]
+    fn challenge_response_is_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            challenge_response("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    fn test_state(bore_host: &str) -> RelayState {
+        RelayState {
+            tunnels: DashMap::new(),
+            rate_limits: DashMap::new(),
+            account_rate_limits: DashMap::new(),
+            relay_secret: String::new(),
+            accounts: AccountStore::in_memory(),
+            limits: Limits::default(),
+            admin_secret: String::new(),
+            client_cert_header: "x-client-cert-fingerprint".to_string(),
+            bore_host: bore_host.to_string(),
+            min_bore_port: 10000,
+            http_client: HyperClient::builder(TokioExecutor::new()).build_http(),
+            access_log: None,
+            tunnel_domain: "tunnel.example.com".to_string(),
+            domains: DashMap::new(),
+            domain_verifier: DomainVerifier::new(),
+            bans: BanList::in_memory(),
+            stats: Stats::default(),
+        }
+    }
+
+    /// Serve the challenge endpoint like a NomadFlow server with `tunnel_key`.
+    async fn spawn_challenge_server(tunnel_key: &'static str) -> u16 {
+        #[derive(Deserialize)]
+        struct Nonce {
+            nonce: String,
+        }
+        let app = Router::new().route(
+            CHALLENGE_PATH,
+            get(move |Query(q): Query<Nonce>| async move { challenge_response(tunnel_key, &q.nonce) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        port
+    }
+
+    #[tokio::test]
+    async fn verify_tunnel_requires_matching_key() {
+        let state = test_state("127.0.0.1");
+        let port = spawn_challenge_server("server-key").await;
+
+        assert!(verify_tunnel(&state, port, "server-key").await.is_ok());
+        // Someone pointing a subdomain at another client's port does not know its key
+        assert!(verify_tunnel(&state, port, "attacker-key").await.is_err());
+        assert!(verify_tunnel(&state, port, "").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn verify_tunnel_fails_without_server() {
+        let state = test_state("127.0.0.1");
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        assert!(verify_tunnel(&state, port, "server-key").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn shutdown_notice_is_signed_with_the_tunnel_key() {
+        #[derive(Deserialize)]
+        struct Notice {
+            timestamp: u64,
+            signature: String,
+        }
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let app = Router::new().route(
+            SHUTDOWN_PATH,
+            post(move |Json(notice): Json<Notice>| async move {
+                let expected =
+                    challenge_response("server-key", &format!("shutdown:{}", notice.timestamp));
+                let _ = tx.send(notice.timestamp);
+                if notice.signature == expected {
+                    StatusCode::NO_CONTENT
+                } else {
+                    StatusCode::FORBIDDEN
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let state = test_state("127.0.0.1");
+        let entry = |tunnel_key: &str| TunnelEntry {
+            bore_port: port,
+            last_used: Instant::now(),
+            client_ip: IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)),
+            account: None,
+            tunnel_key: tunnel_key.to_string(),
+        };
+        state.tunnels.insert("good".to_string(), entry("server-key"));
+        state.tunnels.insert("stale".to_string(), entry("old-key"));
+
+        // Both notices reach the server, only the one signed with its key is accepted
+        assert_eq!(notify_tunnels(&state).await, 1);
+        let timestamp = rx.recv().await.unwrap();
+        assert!(timestamp.abs_diff(now_secs()) < 5);
+        assert!(rx.recv().await.is_some());
+    }
+
     #[test]
     fn port_allowed_when_not_registered() {
         let tunnels: DashMap<String, TunnelEntry> = DashMap::new();
@@ -574,4 +1414,192 @@ mod tests {
         // No tunnels registered → port is free
         assert!(!is_port_taken_by_other(&tunnels, 12345, ip_a));
     }
+
+    fn insert_tunnel(state: &RelayState, subdomain: &str, client_ip: IpAddr) {
+        state.tunnels.insert(
+            subdomain.to_string(),
+            TunnelEntry {
+                bore_port: 12345,
+                last_used: Instant::now(),
+                client_ip,
+                account: None,
+                tunnel_key: String::new(),
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_domains_match_full_hostnames() {
+        let state = Arc::new(test_state("127.0.0.1"));
+        insert_tunnel(&state, "fabien", IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)));
+        state
+            .domains
+            .insert("dev.mycompany.com".to_string(), "fabien".to_string());
+
+        let check_domain = |domain: &str| {
+            check(
+                State(state.clone()),
+                Query(CheckQuery {
+                    domain: domain.to_string(),
+                }),
+            )
+        };
+        assert_eq!(check_domain("fabien.tunnel.example.com").await, StatusCode::OK);
+        assert_eq!(check_domain("Dev.MyCompany.com").await, StatusCode::OK);
+        // Only hosts under the tunnel domain match on their first label
+        assert_eq!(check_domain("fabien.evil.com").await, StatusCode::NOT_FOUND);
+        assert_eq!(check_domain("other.tunnel.example.com").await, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn custom_domain_requires_tunnel_owner() {
+        let state = Arc::new(test_state("127.0.0.1"));
+        let owner = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+        insert_tunnel(&state, "fabien", owner);
+
+        let attach = |hostname: &str, ip: IpAddr| {
+            attach_domain(
+                State(state.clone()),
+                ConnectInfo(SocketAddr::new(ip, 5000)),
+                HeaderMap::new(),
+                Json(DomainRequest {
+                    subdomain: "fabien".to_string(),
+                    hostname: hostname.to_string(),
+                    secret: String::new(),
+                    api_key: None,
+                }),
+            )
+        };
+        let other = IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8));
+        assert_eq!(
+            attach("dev.mycompany.com", other).await.err(),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(attach("localhost", owner).await.err(), Some(StatusCode::BAD_REQUEST));
+        assert_eq!(
+            attach("other.tunnel.example.com", owner).await.err(),
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert!(state.domains.is_empty());
+    }
+
+    #[test]
+    fn client_cert_authenticates_its_account() {
+        let mut state = test_state("127.0.0.1");
+        state.relay_secret = "shared".to_string();
+        let (_, account) = state
+            .accounts
+            .issue(
+                NewAccount {
+                    name: "alice".to_string(),
+                    max_tunnels: None,
+                    max_registrations_per_hour: None,
+                    tunnel_ttl_secs: None,
+                    reserved_subdomains: Vec::new(),
+                    cert_fingerprint: "AB:CD".to_string(),
+                },
+                &Limits::default(),
+            )
+            .unwrap();
+        let ip = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-client-cert-fingerprint", "abcd".parse().unwrap());
+        let cert = client_cert(&state, &headers);
+        let found = authenticate(&state, "", None, cert, ip).unwrap();
+        assert_eq!(found.map(|a| a.id), Some(account.id));
+
+        headers.insert("x-client-cert-fingerprint", "ffff".parse().unwrap());
+        let cert = client_cert(&state, &headers);
+        assert_eq!(
+            authenticate(&state, "shared", None, cert, ip).err(),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+
+        // The header is ignored unless the relay is set up to trust it
+        state.client_cert_header.clear();
+        assert!(client_cert(&state, &headers).is_none());
+    }
+
+    #[tokio::test]
+    async fn ban_closes_tunnels_and_blocks_registration() {
+        let mut state = test_state("127.0.0.1");
+        state.admin_secret = "admin".to_string();
+        let state = Arc::new(state);
+        let banned = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+        let other = IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8));
+        insert_tunnel(&state, "spam", banned);
+        insert_tunnel(&state, "fabien", other);
+        state.domains.insert("spam.example.com".to_string(), "spam".to_string());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer admin".parse().unwrap());
+        let Json(ban) = create_ban(
+            State(state.clone()),
+            headers.clone(),
+            Json(BanRequest {
+                ip: banned,
+                reason: "spam".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!((ban.ip, ban.reason.as_str()), (banned, "spam"));
+        assert!(!state.tunnels.contains_key("spam"));
+        assert!(state.tunnels.contains_key("fabien"));
+        assert!(state.domains.is_empty());
+
+        let registered = register(
+            State(state.clone()),
+            ConnectInfo(SocketAddr::new(banned, 5000)),
+            HeaderMap::new(),
+            Json(RegisterRequest {
+                port: 12345,
+                secret: String::new(),
+                subdomain: None,
+                api_key: None,
+                tunnel_key: String::new(),
+            }),
+        )
+        .await;
+        assert_eq!(registered.err(), Some(StatusCode::FORBIDDEN));
+
+        assert_eq!(
+            delete_ban(State(state.clone()), headers, Path(banned)).await,
+            StatusCode::NO_CONTENT
+        );
+        assert!(!state.bans.is_banned(banned));
+    }
+
+    #[test]
+    fn stale_tunnels_expire_after_their_account_ttl() {
+        let mut state = test_state("127.0.0.1");
+        state.limits.tunnel_ttl = Duration::from_secs(60);
+        let (_, account) = state
+            .accounts
+            .issue(
+                NewAccount {
+                    name: "alice".to_string(),
+                    max_tunnels: None,
+                    max_registrations_per_hour: None,
+                    tunnel_ttl_secs: Some(3600),
+                    reserved_subdomains: Vec::new(),
+                    cert_fingerprint: String::new(),
+                },
+                &state.limits,
+            )
+            .unwrap();
+        let idle = |account: Option<&str>| TunnelEntry {
+            bore_port: 12345,
+            last_used: Instant::now() - Duration::from_secs(120),
+            client_ip: IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)),
+            account: account.map(str::to_string),
+            tunnel_key: String::new(),
+        };
+        state.tunnels.insert("shared".to_string(), idle(None));
+        state.tunnels.insert("alice".to_string(), idle(Some(&account.id)));
+
+        assert_eq!(remove_stale_tunnels(&state), 1);
+        assert!(state.tunnels.contains_key("alice"));
+    }
 }
@@ -0,0 +1,143 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+/// Minutes of proxied traffic kept.
+const MINUTES: usize = 60;
+/// Hours of registrations kept.
+const HOURS: usize = 24;
+
+/// Counts of one time slot, tagged with the slot it belongs to so that
+/// stale slots of the ring read as empty.
+#[derive(Debug, Clone, Copy, Default)]
+struct Slot {
+    index: u64,
+    requests: u64,
+    bytes: u64,
+}
+
+/// Proxied traffic per minute and registrations per hour, in memory only.
+pub struct Stats {
+    minutes: Mutex<[Slot; MINUTES]>,
+    hours: Mutex<[Slot; HOURS]>,
+}
+
+/// Counts for the admin dashboard, oldest first, ending with the current slot.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsSnapshot {
+    pub requests_per_minute: Vec<u64>,
+    /// Response bytes per minute, for responses with a known length.
+    pub bytes_per_minute: Vec<u64>,
+    pub registrations_per_hour: Vec<u64>,
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The slot of `ring` for `index`, emptied if it last held an older one.
+fn slot<const N: usize>(ring: &mut [Slot; N], index: u64) -> &mut Slot {
+    let slot = &mut ring[(index % N as u64) as usize];
+    if slot.index != index {
+        *slot = Slot {
+            index,
+            ..Slot::default()
+        };
+    }
+    slot
+}
+
+/// The last `N` slots up to `current`, oldest first.
+fn series<const N: usize>(
+    ring: &[Slot; N],
+    current: u64,
+    value: impl Fn(&Slot) -> u64,
+) -> Vec<u64> {
+    (0..N as u64)
+        .rev()
+        .map(|ago| {
+            let index = current.saturating_sub(ago);
+            let slot = &ring[(index % N as u64) as usize];
+            if slot.index == index {
+                value(slot)
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            minutes: Mutex::new([Slot::default(); MINUTES]),
+            hours: Mutex::new([Slot::default(); HOURS]),
+        }
+    }
+}
+
+impl Stats {
+    pub fn record_request(&self, now: u64, bytes: Option<u64>) {
+        let mut minutes = self.minutes.lock().unwrap();
+        let slot = slot(&mut minutes, now / 60);
+        slot.requests += 1;
+        slot.bytes += bytes.unwrap_or(0);
+    }
+
+    pub fn record_registration(&self, now: u64) {
+        let mut hours = self.hours.lock().unwrap();
+        slot(&mut hours, now / 3600).requests += 1;
+    }
+
+    pub fn snapshot(&self, now: u64) -> StatsSnapshot {
+        let minutes = self.minutes.lock().unwrap();
+        let hours = self.hours.lock().unwrap();
+        StatsSnapshot {
+            requests_per_minute: series(&minutes, now / 60, |s| s.requests),
+            bytes_per_minute: series(&minutes, now / 60, |s| s.bytes),
+            registrations_per_hour: series(&hours, now / 3600, |s| s.requests),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_land_in_their_minute() {
+        let stats = Stats::default();
+        let now = 1_000_000 * 60;
+        stats.record_request(now - 120, Some(100));
+        stats.record_request(now, Some(10));
+        stats.record_request(now + 5, None);
+
+        let snapshot = stats.snapshot(now + 5);
+        assert_eq!(snapshot.requests_per_minute.len(), MINUTES);
+        assert_eq!(snapshot.requests_per_minute[MINUTES - 1], 2);
+        assert_eq!(snapshot.requests_per_minute[MINUTES - 3], 1);
+        assert_eq!(snapshot.bytes_per_minute[MINUTES - 1], 10);
+    }
+
+    #[test]
+    fn old_slots_read_as_empty() {
+        let stats = Stats::default();
+        let now = 1_000_000 * 3600;
+        stats.record_registration(now);
+        stats.record_request(now, Some(1));
+
+        // A full ring later, the same slots are stale
+        let later = now + (HOURS as u64) * 3600;
+        let snapshot = stats.snapshot(later);
+        assert!(snapshot.registrations_per_hour.iter().all(|&n| n == 0));
+        assert!(snapshot.requests_per_minute.iter().all(|&n| n == 0));
+
+        stats.record_registration(later);
+        assert_eq!(stats.snapshot(later).registrations_per_hour[HOURS - 1], 1);
+    }
+}
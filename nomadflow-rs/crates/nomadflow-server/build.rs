@@ -0,0 +1,20 @@
+//! Embeds the xterm.js files of the native terminal page when they are
+//! vendored in `assets/xterm` by `make vendor-xterm`.
+
+use std::path::Path;
+
+const XTERM_FILES: &[&str] = &["xterm.min.js", "xterm.min.css", "addon-fit.min.js"];
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(vendored_xterm)");
+    println!("cargo:rerun-if-changed=assets/xterm");
+    let dir = Path::new("assets/xterm");
+    if XTERM_FILES.iter().all(|file| dir.join(file).is_file()) {
+        println!("cargo:rustc-cfg=vendored_xterm");
+    } else {
+        println!(
+            "cargo:warning=xterm.js is not vendored in assets/xterm, run `make vendor-xterm`: \
+             the terminal page of the native backend will not load"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use nomadflow_core::models::{ActivityAction, ActivityEntry};
+
+/// Number of entries kept in memory; older ones are dropped.
+const MAX_ENTRIES: usize = 100;
+
+/// How to revert a recorded operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoAction {
+    /// Recreate a deleted branch at its former tip and attach a worktree again.
+    RestoreBranch { branch: String, sha: String },
+    /// Attach a worktree again to a branch that was kept when pruning.
+    ReattachBranch { branch: String },
+    /// Switch back to the feature that was selected before.
+    SwitchBack {
+        repo_path: String,
+        feature_name: String,
+    },
+}
+
+struct Record {
+    entry: ActivityEntry,
+    undo: Option<UndoAction>,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    records: VecDeque<Record>,
+}
+
+/// In-memory log of the operations performed through the API.
+#[derive(Default)]
+pub struct ActivityLog {
+    inner: Mutex<Inner>,
+}
+
+impl ActivityLog {
+    /// Append an entry and return its id.
+    pub fn record(
+        &self,
+        action: ActivityAction,
+        repo_path: &str,
+        feature_name: &str,
+        undo: Option<UndoAction>,
+    ) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let id = inner.next_id;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        inner.records.push_back(Record {
+            entry: ActivityEntry {
+                id,
+                timestamp,
+                action,
+                repo_path: repo_path.to_string(),
+                feature_name: feature_name.to_string(),
+                undoable: undo.is_some(),
+            },
+            undo,
+        });
+        while inner.records.len() > MAX_ENTRIES {
+            inner.records.pop_front();
+        }
+        id
+    }
+
+    /// Most recent entries first.
+    pub fn recent(&self, limit: usize) -> Vec<ActivityEntry> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .records
+            .iter()
+            .rev()
+            .take(limit)
+            .map(|r| r.entry.clone())
+            .collect()
+    }
+
+    /// Repo path and feature name of the last switch, if any.
+    pub fn last_switch(&self) -> Option<(String, String)> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .records
+            .iter()
+            .rev()
+            .find(|r| r.entry.action == ActivityAction::SwitchFeature)
+            .map(|r| (r.entry.repo_path.clone(), r.entry.feature_name.clone()))
+    }
+
+    /// Take the undo action of an entry so it cannot be applied twice.
+    pub fn take_undo(&self, id: u64) -> Option<(ActivityEntry, UndoAction)> {
+        let mut inner = self.inner.lock().unwrap();
+        let record = inner.records.iter_mut().find(|r| r.entry.id == id)?;
+        let undo = record.undo.take()?;
+        record.entry.undoable = false;
+        Some((record.entry.clone(), undo))
+    }
+
+    /// Put back an undo action taken by [`take_undo`](Self::take_undo) when reverting failed.
+    pub fn restore_undo(&self, id: u64, undo: UndoAction) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(record) = inner.records.iter_mut().find(|r| r.entry.id == id) {
+            record.undo = Some(undo);
+            record.entry.undoable = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_is_newest_first_and_bounded() {
+        let log = ActivityLog::default();
+        for i in 0..(MAX_ENTRIES + 5) {
+            log.record(ActivityAction::CreateFeature, "/r", &format!("f{i}"), None);
+        }
+        let entries = log.recent(usize::MAX);
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].feature_name, format!("f{}", MAX_ENTRIES + 4));
+        assert_eq!(log.recent(2).len(), 2);
+    }
+
+    #[test]
+    fn test_take_undo_only_once() {
+        let log = ActivityLog::default();
+        let undo = UndoAction::ReattachBranch {
+            branch: "feature/x".to_string(),
+        };
+        let id = log.record(ActivityAction::PruneFeature, "/r", "x", Some(undo.clone()));
+        assert!(log.recent(1)[0].undoable);
+
+        let (entry, taken) = log.take_undo(id).unwrap();
+        assert_eq!(entry.feature_name, "x");
+        assert_eq!(taken, undo);
+        assert!(!log.recent(1)[0].undoable);
+        assert!(log.take_undo(id).is_none());
+
+        log.restore_undo(id, taken);
+        assert!(log.take_undo(id).is_some());
+    }
+
+    #[test]
+    fn test_last_switch() {
+        let log = ActivityLog::default();
+        assert!(log.last_switch().is_none());
+        log.record(ActivityAction::SwitchFeature, "/r", "a", None);
+        log.record(ActivityAction::CreateFeature, "/r", "b", None);
+        assert_eq!(log.last_switch(), Some(("/r".to_string(), "a".to_string())));
+    }
+}
@@ -2,25 +2,269 @@ use std::sync::Arc;
 
 use axum::{
     extract::State,
-    http::{header, Request, StatusCode},
+    http::{header, HeaderMap, HeaderValue, Method, Request},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use base64::Engine;
 use subtle::ConstantTimeEq;
 
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{ActivityAction, ApiTokenInfo, TokenScope};
+
+use crate::bans::client_ip;
+use crate::downloads;
+use crate::oidc::{looks_like_jwt, SsoUser};
+use crate::shares::now_secs;
 use crate::state::AppState;
 
-/// Auth middleware: verifies Bearer token or Basic Auth if a secret is configured.
-pub async fn auth_middleware(
+/// Header carrying the device token returned by `/api/verify-otp`.
+pub const DEVICE_HEADER: &str = "x-nomadflow-device";
+/// Cookie set when the device token arrives as a `device` query parameter,
+/// so the terminal page's own requests carry it too.
+const DEVICE_COOKIE: &str = "nf_device";
+/// The only authenticated route reachable before passing the TOTP check.
+const VERIFY_OTP_PATH: &str = "/api/verify-otp";
+
+/// Who authenticated a request.
+#[derive(Debug, Clone)]
+pub enum Caller {
+    /// Holder of the server secret, allowed everything.
+    Admin,
+    /// Holder of a named API token, limited to its scopes.
+    Token(ApiTokenInfo),
+    /// Signed in through the `[oidc]` provider, limited to the scopes it
+    /// granted unless it granted `admin`.
+    User(SsoUser),
+}
+
+impl Caller {
+    /// Whether the caller may send `method` to `path`.
+    pub fn may(&self, method: &Method, path: &str) -> bool {
+        match self {
+            Caller::Admin => true,
+            Caller::Token(token) => scopes_allow(&token.scopes, method, path),
+            Caller::User(user) => user.admin || scopes_allow(&user.scopes, method, path),
+        }
+    }
+
+    /// Who the caller is, for errors.
+    fn name(&self) -> &str {
+        match self {
+            Caller::Admin => "admin",
+            Caller::Token(token) => &token.name,
+            Caller::User(user) => &user.name,
+        }
+    }
+}
+
+fn scopes_allow(scopes: &[TokenScope], method: &Method, path: &str) -> bool {
+    let required = required_scopes(method, path);
+    // Every scope grants reading
+    (required == [TokenScope::ReadOnly] && !scopes.is_empty())
+        || required.iter().any(|scope| scopes.contains(scope))
+}
+
+/// Identify the holder of `credential`: the server secret or an API token.
+pub fn caller(state: &AppState, credential: &str) -> Option<Caller> {
+    let secret = &state.settings.auth.secret;
+    if !secret.is_empty() && bool::from(credential.as_bytes().ct_eq(secret.as_bytes())) {
+        return Some(Caller::Admin);
+    }
+    state.tokens.verify(credential).map(Caller::Token)
+}
+
+/// [`caller`], also accepting the tokens of the `[oidc]` provider.
+pub async fn authenticate(state: &AppState, credential: &str) -> Option<Caller> {
+    if let Some(caller) = caller(state, credential) {
+        return Some(caller);
+    }
+    let oidc = state.oidc.as_ref().filter(|_| looks_like_jwt(credential))?;
+    oidc.verify(credential, now_secs()).await.map(Caller::User)
+}
+
+/// Scopes of which an API token needs one to send `method` to `path`.
+/// Empty for routes only the server secret may use, such as token
+/// management, and for unknown routes.
+pub fn required_scopes(method: &Method, path: &str) -> &'static [TokenScope] {
+    route_scopes(method, path).unwrap_or(&[])
+}
+
+/// [`required_scopes`] of the routes it knows, `None` for the others. Every
+/// route of the API has an entry, checked by the tests.
+fn route_scopes(method: &Method, path: &str) -> Option<&'static [TokenScope]> {
+    use TokenScope::*;
+    Some(match path {
+        "/api/clone-repo" | "/api/delete-repo" | "/api/duplicate-repo" | "/api/gc" => &[Repos],
+        "/api/create-feature"
+        | "/api/delete-feature"
+        | "/api/attach-branch"
+        | "/api/bulk-features"
+        | "/api/pull-feature"
+        | "/api/commit"
+        | "/api/push"
+        | "/api/create-pr"
+        | "/api/feature-notes"
+        | "/api/undo"
+        | "/api/reconcile" => &[Features],
+        "/api/switch-feature" | "/api/quick-attach" | "/api/resolve-conflicts" => {
+            &[Features, Terminal]
+        }
+        "/api/share"
+        | "/api/revoke-share"
+        | "/api/run-task"
+        | "/api/webrtc/offer"
+        | "/api/feature-process/interrupt"
+        | "/api/feature-process/kill"
+        | "/api/feature-output" => &[Terminal],
+        "/terminal" => &[Terminal],
+        p if p.starts_with("/terminal/") => &[Terminal],
+        "/api/list-devices" | "/api/forget-device" => &[],
+        p if p.starts_with("/api/tokens") || p.starts_with("/api/bans") => &[],
+        VERIFY_OTP_PATH
+        | "/api/validate-branch"
+        | "/api/suggest-branch-name"
+        | "/api/protected-branches"
+        | "/api/search"
+        | "/api/workspace-descriptor"
+        | "/api/download-url"
+        | "/api/feature-process" => &[ReadOnly],
+        p if *method == Method::GET || p.starts_with("/api/list-") => &[ReadOnly],
+        _ => return None,
+    })
+}
+
+/// Device token sent by the client, from the header, the cookie or the query.
+fn device_token(headers: &HeaderMap, query: Option<&str>) -> Option<(String, bool)> {
+    if let Some(token) = headers.get(DEVICE_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some((token.to_string(), false));
+    }
+    let from_cookie = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == DEVICE_COOKIE)
+        .map(|(_, value)| value.to_string());
+    if let Some(token) = from_cookie {
+        return Some((token, false));
+    }
+    query?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(name, _)| *name == "device")
+        .and_then(|(_, value)| urlencoding::decode(value).ok())
+        .map(|token| (token.into_owned(), true))
+}
+
+/// Whether the request passes the TOTP second factor (always true when disabled).
+/// The second value asks the caller to persist the token as a cookie.
+pub fn second_factor(
+    state: &AppState,
+    headers: &HeaderMap,
+    query: Option<&str>,
+) -> (bool, Option<String>) {
+    if state.settings.auth.totp_secret.is_empty() {
+        return (true, None);
+    }
+    let Some((token, from_query)) = device_token(headers, query) else {
+        return (false, None);
+    };
+    let local = state
+        .local_device_token
+        .as_ref()
+        .is_some_and(|local| bool::from(local.as_bytes().ct_eq(token.as_bytes())));
+    let trusted = local || state.devices.is_trusted(&token, now_secs());
+    (trusted, (trusted && from_query).then_some(token))
+}
+
+/// Id of the trusted device making the request, `local` for local clients.
+pub fn device_id(state: &AppState, headers: &HeaderMap, query: Option<&str>) -> Option<String> {
+    let (token, _) = device_token(headers, query)?;
+    let local = state
+        .local_device_token
+        .as_ref()
+        .is_some_and(|local| bool::from(local.as_bytes().ct_eq(token.as_bytes())));
+    if local {
+        return Some("local".to_string());
+    }
+    state.devices.find(&token, now_secs()).map(|d| d.id)
+}
+
+/// Response extension marking a failed authentication attempt, counted by
+/// [`ban_middleware`] towards banning the client.
+#[derive(Debug, Clone, Copy)]
+pub struct AuthFailure;
+
+/// Refuse banned clients and ban those that keep failing to authenticate.
+pub async fn ban_middleware(
     State(state): State<Arc<AppState>>,
     request: Request<axum::body::Body>,
     next: Next,
 ) -> Response {
-    let secret = &state.settings.auth.secret;
+    let Some(ip) = client_ip(&request) else {
+        return next.run(request).await;
+    };
+    if state.bans.is_banned(ip, now_secs()) {
+        return ApiError::new(
+            ErrorCode::Banned,
+            "Too many failed attempts, try again later",
+        )
+        .into_response();
+    }
+
+    let response = next.run(request).await;
+    if response.extensions().get::<AuthFailure>().is_some() {
+        if let Some(ban) = state.bans.record_failure(ip, now_secs()) {
+            tracing::warn!(ip = %ban.ip, failures = ban.failures, "IP banned after repeated auth failures");
+            state
+                .activity
+                .record(ActivityAction::BanIp, "", &ban.ip, None);
+        }
+    }
+    response
+}
 
+fn otp_required() -> Response {
+    (
+        [("x-nomadflow-otp", "required")],
+        ApiError::new(ErrorCode::OtpRequired, "TOTP verification required"),
+    )
+        .into_response()
+}
+
+fn forbidden(caller: &Caller) -> Response {
+    let detail = match caller {
+        Caller::User(user) => format!("'{}' is not allowed this request", user.name),
+        _ => format!("The token '{}' does not allow this request", caller.name()),
+    };
+    ApiError::new(ErrorCode::Forbidden, detail).into_response()
+}
+
+/// Auth middleware: verifies Bearer token or Basic Auth if a secret is
+/// configured. The credential is the secret, an API token or a token of the
+/// `[oidc]` provider whose scopes cover the route. Downloads may instead
+/// carry a signed URL's query.
+pub async fn auth_middleware(
+    State(state): State<Arc<AppState>>,
+    mut request: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
     // Skip auth if no secret configured
-    if secret.is_empty() {
+    if state.settings.auth.secret.is_empty() {
+        return next.run(request).await;
+    }
+
+    // Signed by /api/download-url, which checked the caller and the TOTP
+    if request.method() == Method::GET
+        && downloads::verify(
+            &state.settings.auth.secret,
+            request.uri().path(),
+            request.uri().query(),
+            now_secs(),
+        )
+    {
         return next.run(request).await;
     }
 
@@ -30,34 +274,240 @@ pub async fn auth_middleware(
         .get("Authorization")
         .and_then(|v| v.to_str().ok());
 
-    let authenticated = match auth_header {
-        Some(h) if h.starts_with("Bearer ") => {
-            h.as_bytes()[7..].ct_eq(secret.as_bytes()).into()
-        }
-        Some(h) if h.starts_with("Basic ") => {
-            // Decode Basic Auth and check password matches secret
-            base64::engine::general_purpose::STANDARD
-                .decode(&h[6..])
-                .ok()
-                .and_then(|bytes| String::from_utf8(bytes).ok())
-                .and_then(|decoded| {
-                    decoded
-                        .split_once(':')
-                        .map(|(_, pw)| pw.as_bytes().ct_eq(secret.as_bytes()).into())
-                })
-                .unwrap_or(false)
-        }
-        _ => false,
+    let credential = match auth_header {
+        Some(h) if h.starts_with("Bearer ") => Some(h[7..].to_string()),
+        // Basic Auth: the password is the credential
+        Some(h) if h.starts_with("Basic ") => base64::engine::general_purpose::STANDARD
+            .decode(&h[6..])
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|decoded| decoded.split_once(':').map(|(_, pw)| pw.to_string())),
+        _ => None,
     };
+    let has_header = auth_header.is_some();
 
-    if authenticated {
-        next.run(request).await
+    let caller = match credential {
+        Some(credential) => authenticate(&state, &credential).await,
+        None => None,
+    };
+    if let Some(caller) = caller {
+        if !caller.may(request.method(), request.uri().path()) {
+            return forbidden(&caller);
+        }
+        // The provider enforces its own second factor
+        let sso = matches!(caller, Caller::User(_));
+        request.extensions_mut().insert(caller);
+        if sso || request.uri().path() == VERIFY_OTP_PATH {
+            return next.run(request).await;
+        }
+        let (passed, cookie) = second_factor(&state, request.headers(), request.uri().query());
+        if !passed {
+            return otp_required();
+        }
+        let mut response = next.run(request).await;
+        if let Some(token) = cookie {
+            let cookie = format!("{DEVICE_COOKIE}={token}; Path=/; HttpOnly; SameSite=Strict");
+            if let Ok(value) = HeaderValue::from_str(&cookie) {
+                response.headers_mut().append(header::SET_COOKIE, value);
+            }
+        }
+        response
     } else {
         // Include WWW-Authenticate so WebView sends Basic Auth credentials
-        (
-            StatusCode::UNAUTHORIZED,
+        let mut response = (
             [(header::WWW_AUTHENTICATE, "Basic realm=\"NomadFlow\"")],
+            ApiError::new(ErrorCode::Unauthorized, "Authentication required"),
         )
-            .into_response()
+            .into_response();
+        // The first request of a WebView carries no credentials; only wrong ones count
+        if has_header {
+            response.extensions_mut().insert(AuthFailure);
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_token_sources() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            device_token(&headers, Some("token=x&device=abc")),
+            Some(("abc".to_string(), true))
+        );
+
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("a=1; nf_device=fromcookie"),
+        );
+        assert_eq!(
+            device_token(&headers, None),
+            Some(("fromcookie".to_string(), false))
+        );
+
+        headers.insert(DEVICE_HEADER, HeaderValue::from_static("fromheader"));
+        assert_eq!(
+            device_token(&headers, Some("device=q")),
+            Some(("fromheader".to_string(), false))
+        );
+
+        assert_eq!(device_token(&HeaderMap::new(), Some("token=x")), None);
+    }
+
+    #[test]
+    fn test_token_scopes() {
+        let token = |scopes: &[TokenScope]| {
+            Caller::Token(ApiTokenInfo {
+                id: "abcd1234".to_string(),
+                name: "alice".to_string(),
+                scopes: scopes.to_vec(),
+                created_at: 0,
+            })
+        };
+        let terminal = token(&[TokenScope::Terminal]);
+        assert!(terminal.may(&Method::GET, "/terminal/ws"));
+        assert!(terminal.may(&Method::POST, "/api/switch-feature"));
+        assert!(terminal.may(&Method::POST, "/api/list-features"));
+        assert!(terminal.may(&Method::POST, "/api/feature-process/kill"));
+        assert!(terminal.may(&Method::POST, "/api/feature-output"));
+        assert!(!terminal.may(&Method::POST, "/api/clone-repo"));
+        assert!(!terminal.may(&Method::POST, "/api/delete-feature"));
+
+        let read_only = token(&[TokenScope::ReadOnly]);
+        assert!(read_only.may(&Method::GET, "/api/repos"));
+        assert!(!read_only.may(&Method::GET, "/terminal"));
+        assert!(!read_only.may(&Method::POST, "/api/switch-feature"));
+        assert!(read_only.may(&Method::POST, "/api/feature-process"));
+        assert!(!read_only.may(&Method::POST, "/api/feature-process/interrupt"));
+        assert!(!read_only.may(&Method::POST, "/api/feature-output"));
+
+        let repos = token(&[TokenScope::Repos]);
+        assert!(repos.may(&Method::POST, "/api/delete-repo"));
+        assert!(repos.may(&Method::POST, "/api/download-url"));
+        // Only the secret manages tokens and bans, whatever the scopes
+        let all = token(&[
+            TokenScope::ReadOnly,
+            TokenScope::Features,
+            TokenScope::Repos,
+            TokenScope::Terminal,
+        ]);
+        assert!(!all.may(&Method::GET, "/api/tokens"));
+        assert!(!all.may(&Method::DELETE, "/api/bans"));
+        assert!(!all.may(&Method::POST, "/api/some-new-endpoint"));
+        assert!(Caller::Admin.may(&Method::GET, "/api/tokens"));
+
+        let features = token(&[TokenScope::Features]);
+        for path in [
+            "/api/pull-feature",
+            "/api/commit",
+            "/api/push",
+            "/api/create-pr",
+        ] {
+            assert!(features.may(&Method::POST, path));
+            assert!(!read_only.may(&Method::POST, path));
+        }
+        assert!(features.may(&Method::POST, "/api/resolve-conflicts"));
+        assert!(terminal.may(&Method::POST, "/api/resolve-conflicts"));
+        assert!(read_only.may(&Method::POST, "/api/suggest-branch-name"));
+        assert!(read_only.may(&Method::POST, "/api/protected-branches"));
+        assert!(repos.may(&Method::POST, "/api/duplicate-repo"));
+        assert!(repos.may(&Method::POST, "/api/gc"));
+        assert!(!features.may(&Method::POST, "/api/gc"));
+    }
+
+    /// `(method, path)` of the routes registered in `src/routes`, read from
+    /// the literal path of each `.route(` call and its method routers.
+    fn registered_routes() -> Vec<(Method, String)> {
+        let methods = [
+            ("get", Method::GET),
+            ("post", Method::POST),
+            ("put", Method::PUT),
+            ("patch", Method::PATCH),
+            ("delete", Method::DELETE),
+        ];
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/routes");
+        let mut routes = Vec::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            for call in source.split(".route(").skip(1) {
+                // Paths given by a constant are the tunnel's and the tmux hooks'
+                let Some(rest) = call.trim_start().strip_prefix('"') else {
+                    continue;
+                };
+                let (path, rest) = rest.split_once('"').unwrap();
+                let mut depth = 1;
+                let end = rest
+                    .find(|c| {
+                        match c {
+                            '(' => depth += 1,
+                            ')' => depth -= 1,
+                            _ => {}
+                        }
+                        depth == 0
+                    })
+                    .unwrap();
+                let handlers = &rest[..end];
+                for (name, method) in &methods {
+                    let called = handlers.match_indices(&format!("{name}(")).any(|(i, _)| {
+                        !handlers[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_')
+                    });
+                    if called {
+                        routes.push((method.clone(), path.to_string()));
+                    }
+                }
+            }
+        }
+        routes
+    }
+
+    #[test]
+    fn test_every_route_has_scopes() {
+        // Outside the API auth, with credentials of their own
+        const PUBLIC: &[&str] = &[
+            "/health",
+            "/share/{token}",
+            "/share/{token}/ws",
+            "/share/{token}/{*path}",
+        ];
+        let routes = registered_routes();
+        assert!(routes.contains(&(Method::POST, "/api/commit".to_string())));
+        assert!(routes.contains(&(Method::DELETE, "/api/bans".to_string())));
+        for (method, path) in routes {
+            if PUBLIC.contains(&path.as_str()) {
+                continue;
+            }
+            // Path parameters stand for any value
+            let concrete = path.replace("{*path}", "x").replace(['{', '}'], "");
+            assert!(
+                route_scopes(&method, &concrete).is_some(),
+                "{method} {path} has no entry in route_scopes"
+            );
+        }
+    }
+
+    #[test]
+    fn test_sso_user_scopes() {
+        let user = |scopes: &[TokenScope], admin: bool| {
+            Caller::User(SsoUser {
+                subject: "u-42".to_string(),
+                name: "alice".to_string(),
+                scopes: scopes.to_vec(),
+                admin,
+            })
+        };
+        let features = user(&[TokenScope::Features], false);
+        assert!(features.may(&Method::GET, "/api/repos"));
+        assert!(features.may(&Method::POST, "/api/create-feature"));
+        assert!(!features.may(&Method::GET, "/terminal"));
+        assert!(!features.may(&Method::GET, "/api/tokens"));
+        // Signed in without any scope of ours
+        assert!(!user(&[], false).may(&Method::GET, "/api/repos"));
+
+        let admin = user(&[], true);
+        assert!(admin.may(&Method::GET, "/api/tokens"));
+        assert!(admin.may(&Method::GET, "/terminal/ws"));
     }
 }
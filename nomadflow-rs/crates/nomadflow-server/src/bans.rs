@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use axum::{extract::ConnectInfo, http::Request};
+
+use nomadflow_core::fsutil::JsonStore;
+use nomadflow_core::models::BanInfo;
+
+/// Failures older than this no longer count towards a ban.
+const FAILURE_WINDOW_SECS: u64 = 10 * 60;
+
+/// Address of the client behind a request.
+///
+/// Tunnel traffic reaches the server through the local bore client, so for
+/// loopback peers the first `X-Forwarded-For` entry (set by the relay's Caddy)
+/// is used instead. The header is ignored from any other peer, since LAN
+/// clients could forge it.
+pub fn client_ip<B>(request: &Request<B>) -> Option<IpAddr> {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()?
+        .0
+        .ip();
+    if !peer.is_loopback() {
+        return Some(peer);
+    }
+    let forwarded = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .and_then(|s| s.trim().parse::<IpAddr>().ok());
+    Some(forwarded.unwrap_or(peer))
+}
+
+/// IPs banned after repeated authentication failures, persisted to `bans.json`
+/// so that restarting the server does not reset them.
+pub struct BanList {
+    max_failures: u32,
+    ban_secs: u64,
+    /// Timestamps of recent failures, per IP.
+    failures: Mutex<HashMap<IpAddr, Vec<u64>>>,
+    bans: JsonStore<Vec<BanInfo>>,
+}
+
+impl BanList {
+    /// Load the bans from `path`, or start empty if it is missing or unreadable.
+    pub fn load(path: PathBuf, max_failures: u32, ban_minutes: u32) -> Self {
+        Self {
+            max_failures,
+            ban_secs: u64::from(ban_minutes) * 60,
+            failures: Mutex::default(),
+            bans: JsonStore::load(path),
+        }
+    }
+
+    /// A list that is never written to disk.
+    pub fn in_memory(max_failures: u32, ban_minutes: u32) -> Self {
+        Self {
+            max_failures,
+            ban_secs: u64::from(ban_minutes) * 60,
+            failures: Mutex::default(),
+            bans: JsonStore::in_memory(),
+        }
+    }
+
+    pub fn is_banned(&self, ip: IpAddr, now: u64) -> bool {
+        let ip = ip.to_string();
+        self.bans
+            .lock()
+            .iter()
+            .any(|b| b.ip == ip && b.expires_at > now)
+    }
+
+    /// Count a failed authentication and return the ban it triggers, if any.
+    /// Loopback addresses are never banned so the local TUI cannot lock itself out.
+    pub fn record_failure(&self, ip: IpAddr, now: u64) -> Option<BanInfo> {
+        if self.max_failures == 0 || ip.is_loopback() {
+            return None;
+        }
+        let mut failures = self.failures.lock().unwrap();
+        let recent = failures.entry(ip).or_default();
+        recent.retain(|&t| t + FAILURE_WINDOW_SECS > now);
+        recent.push(now);
+        let count = recent.len() as u32;
+        if count < self.max_failures {
+            return None;
+        }
+        failures.remove(&ip);
+        drop(failures);
+
+        let ban = BanInfo {
+            ip: ip.to_string(),
+            failures: count,
+            banned_at: now,
+            expires_at: now + self.ban_secs,
+        };
+        let mut bans = self.bans.lock();
+        bans.retain(|b| b.expires_at > now && b.ip != ban.ip);
+        bans.push(ban.clone());
+        self.bans.persist(&bans);
+        Some(ban)
+    }
+
+    /// Active bans, newest first.
+    pub fn list(&self, now: u64) -> Vec<BanInfo> {
+        let mut bans: Vec<BanInfo> = self
+            .bans
+            .lock()
+            .iter()
+            .filter(|b| b.expires_at > now)
+            .cloned()
+            .collect();
+        bans.sort_by_key(|b| std::cmp::Reverse(b.banned_at));
+        bans
+    }
+
+    /// Lift the ban on `ip`, or every ban when `ip` is `None`.
+    /// Returns the IPs that were still banned.
+    pub fn unban(&self, ip: Option<&str>, now: u64) -> Vec<String> {
+        let mut bans = self.bans.lock();
+        let before = bans.len();
+        let mut lifted = Vec::new();
+        bans.retain(|b| {
+            let matches = ip.is_none_or(|ip| b.ip == ip);
+            if matches && b.expires_at > now {
+                lifted.push(b.ip.clone());
+            }
+            !matches
+        });
+        if bans.len() != before {
+            self.bans.persist(&bans);
+        }
+        lifted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ban_after_max_failures_in_window() {
+        let bans = BanList::in_memory(3, 60);
+        let attacker = ip("203.0.113.7");
+
+        assert!(bans.record_failure(attacker, 0).is_none());
+        // Outside the window, the first failure no longer counts
+        assert!(bans.record_failure(attacker, FAILURE_WINDOW_SECS).is_none());
+        assert!(bans
+            .record_failure(attacker, FAILURE_WINDOW_SECS + 1)
+            .is_none());
+        let ban = bans
+            .record_failure(attacker, FAILURE_WINDOW_SECS + 2)
+            .unwrap();
+        assert_eq!(ban.failures, 3);
+        assert_eq!(ban.expires_at, FAILURE_WINDOW_SECS + 2 + 3600);
+
+        assert!(bans.is_banned(attacker, ban.banned_at));
+        assert!(!bans.is_banned(ip("203.0.113.8"), ban.banned_at));
+        assert!(!bans.is_banned(attacker, ban.expires_at));
+    }
+
+    #[test]
+    fn test_loopback_and_disabled_never_ban() {
+        let bans = BanList::in_memory(1, 60);
+        assert!(bans.record_failure(ip("127.0.0.1"), 0).is_none());
+        assert!(bans.record_failure(ip("::1"), 0).is_none());
+
+        let disabled = BanList::in_memory(0, 60);
+        assert!(disabled.record_failure(ip("203.0.113.7"), 0).is_none());
+    }
+
+    #[test]
+    fn test_unban() {
+        let bans = BanList::in_memory(1, 60);
+        bans.record_failure(ip("203.0.113.1"), 10);
+        bans.record_failure(ip("203.0.113.2"), 20);
+        let listed: Vec<String> = bans.list(30).into_iter().map(|b| b.ip).collect();
+        assert_eq!(listed, vec!["203.0.113.2", "203.0.113.1"]);
+
+        assert_eq!(bans.unban(Some("203.0.113.1"), 30), vec!["203.0.113.1"]);
+        assert!(bans.unban(Some("203.0.113.1"), 30).is_empty());
+        assert_eq!(bans.unban(None, 30), vec!["203.0.113.2"]);
+        assert!(bans.list(30).is_empty());
+    }
+
+    #[test]
+    fn test_bans_survive_reload() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("bans.json");
+        BanList::load(path.clone(), 1, 60).record_failure(ip("203.0.113.7"), 100);
+
+        let reloaded = BanList::load(path, 1, 60);
+        assert!(reloaded.is_banned(ip("203.0.113.7"), 200));
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let request = |peer: &str, forwarded: Option<&str>| {
+            let mut builder = Request::get("/");
+            if let Some(forwarded) = forwarded {
+                builder = builder.header("x-forwarded-for", forwarded);
+            }
+            let mut request = builder.body(()).unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+            request
+        };
+
+        assert_eq!(client_ip(&Request::get("/").body(()).unwrap()), None);
+        assert_eq!(
+            client_ip(&request("127.0.0.1:5000", Some("198.51.100.4, 10.0.0.1"))),
+            Some(ip("198.51.100.4"))
+        );
+        assert_eq!(
+            client_ip(&request("127.0.0.1:5000", None)),
+            Some(ip("127.0.0.1"))
+        );
+        // A LAN client cannot pick its own address
+        assert_eq!(
+            client_ip(&request("192.168.1.20:5000", Some("198.51.100.4"))),
+            Some(ip("192.168.1.20"))
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+
+/// Largest request or response body inspected for deprecated fields.
+const MAX_INSPECTED_BODY: usize = 1024 * 1024;
+
+/// An endpoint, or a request field of one, that will be removed.
+/// Add an entry whenever the CHANGELOG announces a deprecation.
+#[derive(Debug)]
+pub struct Deprecation {
+    pub method: Method,
+    pub path: &'static str,
+    /// JSON field of the request body. `None` deprecates the whole endpoint.
+    pub field: Option<&'static str>,
+    /// When the deprecation was announced, in seconds since the epoch.
+    pub since: u64,
+    /// When it goes away, as an HTTP date.
+    pub sunset: &'static str,
+    /// What clients should do instead.
+    pub message: &'static str,
+}
+
+/// Every deprecation currently announced.
+pub const DEPRECATIONS: &[Deprecation] = &[
+    Deprecation {
+        method: Method::POST,
+        path: "/api/create-feature",
+        field: Some("featureName"),
+        since: 1_792_108_800, // 2026-10-16
+        sunset: "Thu, 01 Jul 2027 00:00:00 GMT",
+        message: "`featureName` is deprecated, send `branchName` instead",
+    },
+    Deprecation {
+        method: Method::POST,
+        path: "/api/validate-branch",
+        field: Some("featureName"),
+        since: 1_792_108_800, // 2026-10-16
+        sunset: "Thu, 01 Jul 2027 00:00:00 GMT",
+        message: "`featureName` is deprecated, send `branchName` instead",
+    },
+];
+
+/// Deprecations a request to `method` `path` may run into.
+fn candidates<'r>(
+    table: &'static [Deprecation],
+    method: &'r Method,
+    path: &'r str,
+) -> impl Iterator<Item = &'static Deprecation> + 'r {
+    table
+        .iter()
+        .filter(move |d| d.method == *method && d.path == path)
+}
+
+/// Deprecations `body` actually uses, among `candidates`.
+fn used<'a>(candidates: &[&'a Deprecation], body: &[u8]) -> Vec<&'a Deprecation> {
+    let fields = serde_json::from_slice::<Value>(body).ok();
+    candidates
+        .iter()
+        .copied()
+        .filter(|d| match d.field {
+            None => true,
+            Some(field) => fields
+                .as_ref()
+                .and_then(Value::as_object)
+                .is_some_and(|object| object.contains_key(field)),
+        })
+        .collect()
+}
+
+/// Flag deprecated endpoints and request fields with `Deprecation` and
+/// `Sunset` headers, and a `warnings` array in JSON object responses.
+pub async fn deprecation_middleware(request: Request, next: Next) -> Response {
+    let candidates: Vec<_> =
+        candidates(DEPRECATIONS, request.method(), request.uri().path()).collect();
+    if candidates.is_empty() {
+        return next.run(request).await;
+    }
+
+    if candidates.iter().all(|d| d.field.is_none()) {
+        return annotate(next.run(request).await, &candidates).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_INSPECTED_BODY).await else {
+        return Response::builder()
+            .status(413)
+            .body(Body::from("Request body too large"))
+            .unwrap();
+    };
+    let used = used(&candidates, &bytes);
+    let request = Request::from_parts(parts, Body::from(bytes));
+    annotate(next.run(request).await, &used).await
+}
+
+async fn annotate(response: Response, used: &[&Deprecation]) -> Response {
+    // The headers hold a single date, the table lists the most urgent first
+    let Some(first) = used.first() else {
+        return response;
+    };
+    let (mut parts, body) = response.into_parts();
+    let headers = &mut parts.headers;
+    if let Ok(value) = HeaderValue::from_str(&format!("@{}", first.since)) {
+        headers.insert("Deprecation", value);
+    }
+    headers.insert("Sunset", HeaderValue::from_static(first.sunset));
+
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !is_json {
+        return Response::from_parts(parts, body);
+    }
+    let Ok(bytes) = to_bytes(body, MAX_INSPECTED_BODY).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(Value::Object(mut object)) => {
+            let warnings = used.iter().map(|d| Value::from(d.message)).collect();
+            object.insert("warnings".to_string(), Value::Array(warnings));
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Body::from(Value::Object(object).to_string())
+        }
+        _ => Body::from(bytes),
+    };
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_fields_present_in_the_body_are_reported() {
+        let matched: Vec<_> =
+            candidates(DEPRECATIONS, &Method::POST, "/api/create-feature").collect();
+        assert_eq!(matched.len(), 1);
+
+        let legacy = br#"{"repoPath": "/r", "featureName": "x"}"#;
+        assert_eq!(used(&matched, legacy).len(), 1);
+        let current = br#"{"repoPath": "/r", "branchName": "x"}"#;
+        assert!(used(&matched, current).is_empty());
+        assert!(used(&matched, b"not json").is_empty());
+
+        assert_eq!(
+            candidates(DEPRECATIONS, &Method::GET, "/api/create-feature").count(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_legacy_field_is_flagged_in_headers_and_body() {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let router = axum::Router::new()
+            .route(
+                "/api/validate-branch",
+                axum::routing::post(|| async { axum::Json(serde_json::json!({ "valid": true })) }),
+            )
+            .layer(axum::middleware::from_fn(deprecation_middleware));
+        let send = |body: &'static str| {
+            let request = axum::http::Request::post("/api/validate-branch")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            router.clone().oneshot(request)
+        };
+
+        let response = send(r#"{"featureName": "x"}"#).await.unwrap();
+        assert_eq!(response.headers()["Deprecation"], "@1792108800");
+        assert_eq!(
+            response.headers()["Sunset"],
+            "Thu, 01 Jul 2027 00:00:00 GMT"
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["valid"], true);
+        assert_eq!(json["warnings"].as_array().unwrap().len(), 1);
+
+        let response = send(r#"{"branchName": "x"}"#).await.unwrap();
+        assert!(response.headers().get("Deprecation").is_none());
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert!(json.get("warnings").is_none());
+    }
+}
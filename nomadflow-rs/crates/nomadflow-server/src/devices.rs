@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use nomadflow_core::fsutil::JsonStore;
+use nomadflow_core::models::DeviceInfo;
+
+/// Seconds in a day, for device lifetimes.
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredDevice {
+    #[serde(flatten)]
+    info: DeviceInfo,
+    /// SHA-256 of the device token; the token itself is never stored.
+    token_hash: String,
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+pub(crate) fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Devices that passed the TOTP check, persisted to `devices.json`.
+pub struct DeviceStore {
+    devices: JsonStore<Vec<StoredDevice>>,
+}
+
+impl DeviceStore {
+    /// Load the store from `path`, or start empty if it is missing or unreadable.
+    pub fn load(path: PathBuf) -> Self {
+        Self {
+            devices: JsonStore::load(path),
+        }
+    }
+
+    /// A store that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            devices: JsonStore::in_memory(),
+        }
+    }
+
+    /// Trust a new device for `days` and return its token.
+    pub fn remember(&self, name: &str, days: u64, now: u64) -> (String, DeviceInfo) {
+        let token = random_token();
+        let info = DeviceInfo {
+            id: token[..8].to_string(),
+            name: name.to_string(),
+            created_at: now,
+            expires_at: now + days * DAY_SECS,
+        };
+
+        let mut devices = self.devices.lock();
+        devices.retain(|d| d.info.expires_at > now);
+        devices.push(StoredDevice {
+            info: info.clone(),
+            token_hash: hash_token(&token),
+        });
+        self.devices.persist(&devices);
+        (token, info)
+    }
+
+    /// Whether `token` belongs to a device that is still trusted.
+    pub fn is_trusted(&self, token: &str, now: u64) -> bool {
+        self.find(token, now).is_some()
+    }
+
+    /// The trusted device `token` belongs to.
+    pub fn find(&self, token: &str, now: u64) -> Option<DeviceInfo> {
+        let hash = hash_token(token);
+        self.devices
+            .lock()
+            .iter()
+            .find(|d| {
+                d.info.expires_at > now
+                    && bool::from(d.token_hash.as_bytes().ct_eq(hash.as_bytes()))
+            })
+            .map(|d| d.info.clone())
+    }
+
+    /// Trusted devices, newest first.
+    pub fn list(&self, now: u64) -> Vec<DeviceInfo> {
+        let devices = self.devices.lock();
+        let mut infos: Vec<DeviceInfo> = devices
+            .iter()
+            .filter(|d| d.info.expires_at > now)
+            .map(|d| d.info.clone())
+            .collect();
+        infos.sort_by_key(|d| std::cmp::Reverse(d.created_at));
+        infos
+    }
+
+    /// Stop trusting a device.
+    pub fn forget(&self, id: &str) -> bool {
+        let mut devices = self.devices.lock();
+        let before = devices.len();
+        devices.retain(|d| d.info.id != id);
+        let forgotten = devices.len() != before;
+        if forgotten {
+            self.devices.persist(&devices);
+        }
+        forgotten
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remember_and_forget() {
+        let store = DeviceStore::in_memory();
+        let (token, info) = store.remember("phone", 30, 1000);
+        assert!(store.is_trusted(&token, 1000));
+        assert!(!store.is_trusted("other", 1000));
+        assert!(!store.is_trusted(&token, info.expires_at));
+
+        assert_eq!(store.list(1000).len(), 1);
+        assert!(store.forget(&info.id));
+        assert!(!store.is_trusted(&token, 1000));
+        assert!(!store.forget(&info.id));
+    }
+
+    #[test]
+    fn test_persisted_without_token() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("devices.json");
+        let (token, _) = DeviceStore::load(path.clone()).remember("phone", 1, 1000);
+
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(!json.contains(&token));
+        assert!(DeviceStore::load(path).is_trusted(&token, 1000));
+    }
+}
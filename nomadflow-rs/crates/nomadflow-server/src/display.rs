@@ -1,3 +1,7 @@
+use std::io::IsTerminal;
+use std::path::Path;
+
+use nomadflow_core::i18n::{t, tf};
 use qrcode::QrCode;
 
 /// Render a QR code as a compact Unicode string using half-block characters.
@@ -46,23 +50,125 @@ fn render_qr_unicode(code: &QrCode) -> String {
     output
 }
 
+/// How much of the connection info fits in the terminal.
+#[derive(Debug, PartialEq, Eq)]
+enum QrLayout {
+    /// QR code inside the framed box.
+    Boxed,
+    /// QR code without the frame, for panes narrower than the box.
+    Bare,
+    /// No QR code at all: just the URL (hyperlinked to the deep link when possible).
+    UrlOnly,
+}
+
+/// Pick the richest layout that fits in `term_width` columns.
+/// An unknown width (not a terminal) keeps the full box.
+fn choose_layout(term_width: Option<usize>, boxed_width: usize, qr_width: usize) -> QrLayout {
+    match term_width {
+        None => QrLayout::Boxed,
+        Some(w) if w >= boxed_width => QrLayout::Boxed,
+        Some(w) if w >= qr_width => QrLayout::Bare,
+        Some(_) => QrLayout::UrlOnly,
+    }
+}
+
+/// Width of the terminal the connection info is printed to, if any.
+fn terminal_width() -> Option<usize> {
+    if !std::io::stderr().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size()
+        .ok()
+        .map(|(w, _)| w as usize)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+}
+
+/// Wrap `text` in an OSC 8 hyperlink pointing at `target`.
+fn hyperlink(target: &str, text: &str) -> String {
+    format!("\x1b]8;;{target}\x1b\\{text}\x1b]8;;\x1b\\")
+}
+
+/// Write the QR code as a black-on-white PNG (8 px per module, 4-module quiet zone).
+fn write_qr_png(code: &QrCode, path: &Path) -> std::io::Result<()> {
+    const SCALE: usize = 8;
+    const QUIET: usize = 4;
+
+    let colors = code.to_colors();
+    let width = code.width();
+    let side = (width + 2 * QUIET) * SCALE;
+    let mut pixels = vec![255u8; side * side];
+    for (i, color) in colors.iter().enumerate() {
+        if *color != qrcode::Color::Dark {
+            continue;
+        }
+        let (mx, my) = (i % width + QUIET, i / width + QUIET);
+        for y in my * SCALE..(my + 1) * SCALE {
+            pixels[y * side + mx * SCALE..y * side + (mx + 1) * SCALE].fill(0);
+        }
+    }
+
+    let file = std::fs::File::create(path)?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), side as u32, side as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+    writer
+        .write_image_data(&pixels)
+        .map_err(std::io::Error::other)?;
+    Ok(())
+}
+
+/// Deep link of the pairing QR code. `key` lets the app check the signatures
+/// of the server, wherever the URL leads later.
+fn pairing_link(connect_url: &str, secret: &str, server_key: &str) -> String {
+    let mut link = format!(
+        "nomadflowcode://add-server?url={}",
+        urlencoding::encode(connect_url)
+    );
+    if !secret.is_empty() {
+        link.push_str(&format!("&secret={}", urlencoding::encode(secret)));
+    }
+    link.push_str(&format!("&key={}", urlencoding::encode(server_key)));
+    link
+}
+
 /// Display connection info with QR code in the terminal.
-pub fn print_connection_info(connect_url: &str, secret: &str, public: bool) {
-    let encoded_url = urlencoding::encode(connect_url);
-    let deep_link = if secret.is_empty() {
-        format!("nomadflowcode://add-server?url={encoded_url}")
-    } else {
-        let encoded_secret = urlencoding::encode(secret);
-        format!("nomadflowcode://add-server?url={encoded_url}&secret={encoded_secret}")
-    };
+/// The QR code is shrunk or dropped when the terminal is too narrow for it,
+/// and also written to `qr_png` when a path is given.
+pub fn print_connection_info(
+    connect_url: &str,
+    secret: &str,
+    server_key: &str,
+    public: bool,
+    qr_png: Option<&Path>,
+) {
+    let deep_link = pairing_link(connect_url, secret, server_key);
 
-    let qr_block = match QrCode::new(&deep_link) {
-        Ok(code) => render_qr_unicode(&code),
-        Err(_) => "  [QR code generation failed]\n".to_string(),
+    let code = QrCode::new(&deep_link).ok();
+    let qr_block = match &code {
+        Some(code) => render_qr_unicode(code),
+        None => format!("  {}\n", t("connect.qr_failed")),
     };
 
+    let png_line = match (qr_png, &code) {
+        (Some(path), Some(code)) => Some(match write_qr_png(code, path) {
+            Ok(()) => format!("  {}", tf("connect.qr_image", &[("path", &path.display())])),
+            Err(e) => format!(
+                "  {}",
+                tf(
+                    "connect.qr_image_failed",
+                    &[("path", &path.display()), ("err", &e)]
+                )
+            ),
+        }),
+        _ => None,
+    };
     let qr_lines: Vec<&str> = qr_block.lines().collect();
-    let qr_width = qr_lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let qr_width = qr_lines
+        .iter()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or(0);
 
     // Compute box width: at least as wide as QR + some padding, or URL line
     let url_line = format!("  URL      : {connect_url}");
@@ -76,8 +182,8 @@ pub fn print_connection_info(connect_url: &str, secret: &str, public: bool) {
         qr_width + 2,
         url_line.chars().count() + 2,
         secret_line.chars().count() + 2,
-        "  NomadFlow Server Ready  ".len(),
-        "  Scan this QR code from the app  ".len(),
+        t("connect.ready").chars().count() + 4,
+        t("connect.scan").chars().count() + 4,
     ]
     .into_iter()
     .max()
@@ -85,6 +191,22 @@ pub fn print_connection_info(connect_url: &str, secret: &str, public: bool) {
 
     let box_width = content_width + 2; // +2 for ║ borders
 
+    match choose_layout(terminal_width(), box_width + 4, qr_width) {
+        QrLayout::Boxed => {}
+        layout => {
+            print_compact(
+                layout,
+                &qr_lines,
+                &deep_link,
+                &url_line,
+                &secret_line,
+                png_line.as_deref(),
+                public,
+            );
+            return;
+        }
+    }
+
     let top = format!("  ╔{}╗", "═".repeat(box_width));
     let bottom = format!("  ╚{}╝", "═".repeat(box_width));
     let sep = format!("  ╠{}╣", "═".repeat(box_width));
@@ -111,7 +233,7 @@ pub fn print_connection_info(connect_url: &str, secret: &str, public: bool) {
 
     eprintln!();
     eprintln!("{top}");
-    eprintln!("{}", center("NomadFlow Server Ready"));
+    eprintln!("{}", center(t("connect.ready")));
     eprintln!("{sep}");
     eprintln!("{empty}");
 
@@ -120,8 +242,8 @@ pub fn print_connection_info(connect_url: &str, secret: &str, public: bool) {
     }
 
     eprintln!("{empty}");
-    eprintln!("{}", center("Scan this QR code from the app"));
-    eprintln!("{}", center("or enter manually:"));
+    eprintln!("{}", center(t("connect.scan")));
+    eprintln!("{}", center(t("connect.manual")));
     eprintln!("{empty}");
     eprintln!("{}", left_align(&url_line));
     if !secret.is_empty() {
@@ -129,11 +251,111 @@ pub fn print_connection_info(connect_url: &str, secret: &str, public: bool) {
     }
     eprintln!("{empty}");
     eprintln!("{bottom}");
+    if let Some(line) = &png_line {
+        eprintln!("{line}");
+    }
+    print_public_notice(public);
+}
+
+/// Frameless output for narrow terminals.
+fn print_compact(
+    layout: QrLayout,
+    qr_lines: &[&str],
+    deep_link: &str,
+    url_line: &str,
+    secret_line: &str,
+    png_line: Option<&str>,
+    public: bool,
+) {
+    eprintln!();
+    eprintln!("  {}", t("connect.ready"));
+    if layout == QrLayout::Bare {
+        for line in qr_lines {
+            eprintln!("{line}");
+        }
+        eprintln!("  {}", t("connect.scan"));
+    } else {
+        // Too narrow for any QR: let terminals that support it open the deep link directly
+        eprintln!("  {}", hyperlink(deep_link, t("connect.open_app")));
+    }
+    eprintln!("{url_line}");
+    if !secret_line.is_empty() {
+        eprintln!("{secret_line}");
+    }
+    if let Some(line) = png_line {
+        eprintln!("{line}");
+    }
+    print_public_notice(public);
+}
+
+fn print_public_notice(public: bool) {
     if public {
         eprintln!();
-        eprintln!("  Public tunnel provided by fab_uleuh — free during beta.");
-        eprintln!("  This may become a paid option in the future.");
-        eprintln!("  You can always self-host via VPN or your own relay.");
+        eprintln!("  {}", t("connect.tunnel_beta"));
+        eprintln!("  {}", t("connect.tunnel_paid"));
+        eprintln!("  {}", t("connect.tunnel_selfhost"));
+    }
+    eprintln!();
+}
+
+/// Show a TOTP provisioning URI as a QR code for authenticator apps,
+/// with the secret for manual entry.
+pub fn print_totp_enrollment(uri: &str, secret: &str) {
+    eprintln!();
+    eprintln!("  {}", t("totp.scan"));
+    match QrCode::new(uri) {
+        Ok(code) => {
+            let qr = render_qr_unicode(&code);
+            let qr_width = qr.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+            if choose_layout(terminal_width(), qr_width, qr_width) == QrLayout::UrlOnly {
+                eprintln!("  {}", hyperlink(uri, uri));
+            } else {
+                eprint!("{qr}");
+            }
+        }
+        Err(_) => eprintln!("  {}", t("connect.qr_failed")),
     }
+    eprintln!("  {}", tf("totp.manual", &[("secret", &secret)]));
     eprintln!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_layout() {
+        assert_eq!(choose_layout(None, 80, 50), QrLayout::Boxed);
+        assert_eq!(choose_layout(Some(120), 80, 50), QrLayout::Boxed);
+        assert_eq!(choose_layout(Some(60), 80, 50), QrLayout::Bare);
+        assert_eq!(choose_layout(Some(40), 80, 50), QrLayout::UrlOnly);
+    }
+
+    #[test]
+    fn test_pairing_link() {
+        assert_eq!(
+            pairing_link("http://10.0.0.2:8080", "", "k3y"),
+            "nomadflowcode://add-server?url=http%3A%2F%2F10.0.0.2%3A8080&key=k3y"
+        );
+        assert!(pairing_link("http://x", "a b", "k3y").contains("&secret=a%20b&key=k3y"));
+    }
+
+    #[test]
+    fn test_hyperlink() {
+        assert_eq!(
+            hyperlink("nomadflowcode://x", "open"),
+            "\x1b]8;;nomadflowcode://x\x1b\\open\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_write_qr_png() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("qr.png");
+        let code = QrCode::new("nomadflowcode://add-server?url=x").unwrap();
+        write_qr_png(&code, &path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+    }
+}
@@ -0,0 +1,143 @@
+//! Short-lived signed URLs for large downloads (feature archives, task logs),
+//! which mobile clients hand to the OS download manager without giving it
+//! the bearer token.
+//!
+//! A URL is signed with an HMAC-SHA256 of its path and expiry keyed with
+//! the server secret; it reaches only that path, until it expires.
+
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Lifetime of a signed URL when the client does not ask for one.
+pub const DEFAULT_TTL_SECS: u64 = 15 * 60;
+/// Longest lifetime a client may ask for.
+pub const MAX_TTL_SECS: u64 = 24 * 60 * 60;
+
+const B64: base64::engine::GeneralPurpose = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+/// Whether `path` is a download that may be signed.
+pub fn is_download_path(path: &str) -> bool {
+    let segments: Vec<&str> = path.split('/').collect();
+    match segments.as_slice() {
+        ["", "api", "task-runs", id, "log"] => id.parse::<u64>().is_ok(),
+        ["", "api", "repos", name, "features", feature, "archive"] => {
+            !name.is_empty() && !feature.is_empty()
+        }
+        _ => false,
+    }
+}
+
+fn mac(secret: &str, path: &str, expires: u64) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(format!("nomadflow-download:v1\n{path}\n{expires}").as_bytes());
+    mac
+}
+
+/// `path` with the query reaching it until `expires` (unix seconds).
+pub fn sign(secret: &str, path: &str, expires: u64) -> String {
+    let sig = B64.encode(mac(secret, path, expires).finalize().into_bytes());
+    format!("{path}?expires={expires}&sig={sig}")
+}
+
+/// Whether `query` signs `path` and has not expired at `now`.
+pub fn verify(secret: &str, path: &str, query: Option<&str>, now: u64) -> bool {
+    let (mut expires, mut sig) = (None, None);
+    for (name, value) in query
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|p| p.split_once('='))
+    {
+        match name {
+            "expires" => expires = value.parse::<u64>().ok(),
+            "sig" => sig = B64.decode(value).ok(),
+            _ => {}
+        }
+    }
+    let (Some(expires), Some(sig)) = (expires, sig) else {
+        return false;
+    };
+    expires >= now
+        && is_download_path(path)
+        && mac(secret, path, expires).verify_slice(&sig).is_ok()
+}
+
+/// `body` served as a file named `filename`, for browsers and download
+/// managers to save rather than show.
+pub fn attachment(content_type: &'static str, filename: &str, body: impl IntoResponse) -> Response {
+    // Feature names hold slashes, and quotes would end the header's value
+    let filename: String = filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_download_path() {
+        assert!(is_download_path("/api/task-runs/12/log"));
+        assert!(is_download_path("/api/repos/app/features/login/archive"));
+        assert!(!is_download_path("/api/task-runs/x/log"));
+        assert!(!is_download_path("/api/task-runs/12"));
+        assert!(!is_download_path("/api/repos/app/features/login/diff"));
+        assert!(!is_download_path("/api/repos/app/features//archive"));
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let path = "/api/task-runs/3/log";
+        let url = sign("secret", path, 1_000);
+        let (signed, query) = url.split_once('?').unwrap();
+        assert_eq!(signed, path);
+
+        assert!(verify("secret", path, Some(query), 1_000));
+        assert!(verify("secret", path, Some(&format!("a=b&{query}")), 999));
+        // Expired, other secret, other path
+        assert!(!verify("secret", path, Some(query), 1_001));
+        assert!(!verify("other", path, Some(query), 1_000));
+        assert!(!verify(
+            "secret",
+            "/api/task-runs/4/log",
+            Some(query),
+            1_000
+        ));
+        // Moved expiry
+        let moved = query.replace("expires=1000", "expires=9000");
+        assert!(!verify("secret", path, Some(&moved), 1_000));
+        assert!(!verify("secret", path, None, 0));
+    }
+
+    #[test]
+    fn test_attachment_filename() {
+        let response = attachment("text/plain", "app-feature/\"x\".log", "");
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"app-feature--x-.log\""
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::hash::{Hash, Hasher};
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use nomadflow_core::api_error::ApiError;
+
+/// Serialize `value` as JSON with an `ETag` header, answering `304 Not Modified`
+/// when the client's `If-None-Match` already matches the payload.
+pub fn json_with_etag<T: Serialize>(headers: &HeaderMap, value: &T) -> Response {
+    let body = match serde_json::to_vec(value) {
+        Ok(body) => body,
+        Err(e) => return ApiError::internal(e).into_response(),
+    };
+    let etag = compute_etag(&body);
+
+    if if_none_match(headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    (
+        [
+            (header::ETAG, etag),
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Cheap non-cryptographic hash of the payload, quoted as an entity tag.
+fn compute_etag(body: &[u8]) -> HeaderValue {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    body.hash(&mut hasher);
+    HeaderValue::from_str(&format!("\"{:016x}\"", hasher.finish()))
+        .expect("hex etag is a valid header value")
+}
+
+fn if_none_match(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Some(value) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return false;
+    };
+    let etag = etag.to_str().unwrap_or_default();
+    value.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_is_stable_and_content_sensitive() {
+        let a = compute_etag(b"{\"repos\":[]}");
+        assert_eq!(a, compute_etag(b"{\"repos\":[]}"));
+        assert_ne!(a, compute_etag(b"{\"repos\":[1]}"));
+    }
+
+    #[test]
+    fn test_not_modified_when_etag_matches() {
+        let value = serde_json::json!({ "features": [] });
+        let first = json_with_etag(&HeaderMap::new(), &value);
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers().get(header::ETAG).unwrap().clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag.clone());
+        assert_eq!(
+            json_with_etag(&headers, &value).status(),
+            StatusCode::NOT_MODIFIED
+        );
+
+        // Weak comparison and lists of tags also match
+        let weak = format!("\"other\", W/{}", etag.to_str().unwrap());
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&weak).unwrap());
+        assert_eq!(
+            json_with_etag(&headers, &value).status(),
+            StatusCode::NOT_MODIFIED
+        );
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"stale\""));
+        assert_eq!(json_with_etag(&headers, &value).status(), StatusCode::OK);
+    }
+}
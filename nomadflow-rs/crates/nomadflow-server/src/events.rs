@@ -0,0 +1,80 @@
+//! Changes of server state, broadcast to the clients of `/api/events` so
+//! they can refresh without polling.
+
+use std::sync::Mutex;
+
+use tokio::sync::broadcast;
+
+use nomadflow_core::models::ServerEvent;
+
+/// Events a slow client may fall behind by before it misses some.
+const CAPACITY: usize = 256;
+
+pub struct EventBus {
+    /// `None` once closed for shutdown.
+    sender: Mutex<Option<broadcast::Sender<ServerEvent>>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: Mutex::new(Some(broadcast::channel(CAPACITY).0)),
+        }
+    }
+}
+
+impl EventBus {
+    /// Send `event` to the current subscribers, if any.
+    pub fn publish(&self, event: ServerEvent) {
+        if let Some(sender) = &*self.sender.lock().unwrap() {
+            let _ = sender.send(event);
+        }
+    }
+
+    /// Events published from now on. Ends once the bus is closed.
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerEvent> {
+        match &*self.sender.lock().unwrap() {
+            Some(sender) => sender.subscribe(),
+            None => broadcast::channel(1).1,
+        }
+    }
+
+    /// End every stream, so that the server can stop without waiting on them.
+    pub fn close(&self) {
+        self.sender.lock().unwrap().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use broadcast::error::RecvError;
+
+    fn switched(name: &str) -> ServerEvent {
+        ServerEvent::FeatureSwitched {
+            repo_path: "/repos/app".to_string(),
+            feature_name: name.to_string(),
+            tmux_window: format!("app:{name}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_get_later_events_until_closed() {
+        let bus = EventBus::default();
+        bus.publish(switched("before"));
+        let mut receiver = bus.subscribe();
+        bus.publish(switched("after"));
+
+        match receiver.recv().await.unwrap() {
+            ServerEvent::FeatureSwitched { feature_name, .. } => assert_eq!(feature_name, "after"),
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        bus.close();
+        assert!(matches!(receiver.recv().await, Err(RecvError::Closed)));
+        assert!(matches!(
+            bus.subscribe().recv().await,
+            Err(RecvError::Closed)
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+//! Long-lived Ed25519 identity of the server. The public key is shown in the
+//! pairing QR code and signs `/health` and the responses issuing credentials,
+//! so that clients can tell they reach the machine they paired with even
+//! after the tunnel URL changes.
+
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use ed25519_dalek::{Signer, SigningKey};
+use rand::Rng;
+use serde::Serialize;
+
+use nomadflow_core::api_error::ApiError;
+
+/// Request header carrying the client's nonce, covered by the signature.
+pub const NONCE_HEADER: &str = "x-nomadflow-nonce";
+/// Response header carrying the signature.
+pub const SIGNATURE_HEADER: &str = "x-nomadflow-signature";
+/// Longest nonce signed; longer or non-alphanumeric ones are ignored.
+const MAX_NONCE_LEN: usize = 64;
+
+const B64: base64::engine::GeneralPurpose = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+pub struct ServerIdentity {
+    key: SigningKey,
+}
+
+impl ServerIdentity {
+    /// Read the key at `path`, creating it (readable only by the owner) on
+    /// first use.
+    pub fn load_or_create(path: &Path) -> std::io::Result<Self> {
+        if let Ok(encoded) = std::fs::read_to_string(path) {
+            if let Some(seed) = B64
+                .decode(encoded.trim())
+                .ok()
+                .and_then(|b| <[u8; 32]>::try_from(b).ok())
+            {
+                return Ok(Self::from_seed(seed));
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let identity = Self::generate();
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(B64.encode(identity.key.to_bytes()).as_bytes())?;
+        Ok(identity)
+    }
+
+    /// A key kept in memory only, for when it cannot be saved.
+    pub fn generate() -> Self {
+        Self::from_seed(rand::rng().random())
+    }
+
+    fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    /// Public key, URL-safe base64 without padding.
+    pub fn public_key(&self) -> String {
+        B64.encode(self.key.verifying_key().to_bytes())
+    }
+
+    /// Signature of `body` answering a request with `nonce`.
+    pub fn sign(&self, nonce: &str, body: &[u8]) -> String {
+        B64.encode(self.key.sign(&signed_message(nonce, body)).to_bytes())
+    }
+
+    /// `value` as JSON, signed for the nonce of the request `headers`.
+    pub fn signed_json<T: Serialize>(&self, headers: &HeaderMap, value: &T) -> Response {
+        let body = match serde_json::to_vec(value) {
+            Ok(body) => body,
+            Err(e) => return ApiError::internal(e).into_response(),
+        };
+        let signature = self.sign(request_nonce(headers), &body);
+        let mut response = body.into_response();
+        let response_headers = response.headers_mut();
+        response_headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        if let Ok(signature) = HeaderValue::from_str(&signature) {
+            response_headers.insert(SIGNATURE_HEADER, signature);
+        }
+        response
+    }
+}
+
+/// Nonce sent by the client, empty when missing or malformed.
+fn request_nonce(headers: &HeaderMap) -> &str {
+    headers
+        .get(NONCE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|n| n.len() <= MAX_NONCE_LEN)
+        .filter(|n| n.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'))
+        .unwrap_or("")
+}
+
+/// What a signature covers: a fixed prefix, the nonce and the exact body.
+pub fn signed_message(nonce: &str, body: &[u8]) -> Vec<u8> {
+    let mut message = format!("nomadflow-sig:v1\n{nonce}\n").into_bytes();
+    message.extend_from_slice(body);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+    fn verify(public_key: &str, nonce: &str, body: &[u8], signature: &str) -> bool {
+        let key = <[u8; 32]>::try_from(B64.decode(public_key).unwrap()).unwrap();
+        let signature = <[u8; 64]>::try_from(B64.decode(signature).unwrap()).unwrap();
+        VerifyingKey::from_bytes(&key)
+            .unwrap()
+            .verify(&signed_message(nonce, body), &Signature::from_bytes(&signature))
+            .is_ok()
+    }
+
+    #[test]
+    fn test_key_persists() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("server-identity.key");
+        let first = ServerIdentity::load_or_create(&path).unwrap();
+        let again = ServerIdentity::load_or_create(&path).unwrap();
+        assert_eq!(first.public_key(), again.public_key());
+        assert_ne!(first.public_key(), ServerIdentity::generate().public_key());
+    }
+
+    #[test]
+    fn test_signature_covers_nonce_and_body() {
+        let identity = ServerIdentity::generate();
+        let key = identity.public_key();
+        let signature = identity.sign("abc", b"{\"status\":\"ok\"}");
+        assert!(verify(&key, "abc", b"{\"status\":\"ok\"}", &signature));
+        assert!(!verify(&key, "abd", b"{\"status\":\"ok\"}", &signature));
+        assert!(!verify(&key, "abc", b"{\"status\":\"ko\"}", &signature));
+    }
+
+    #[test]
+    fn test_malformed_nonce_is_ignored() {
+        let mut headers = HeaderMap::new();
+        headers.insert(NONCE_HEADER, HeaderValue::from_static("n0nce_-1"));
+        assert_eq!(request_nonce(&headers), "n0nce_-1");
+        headers.insert(NONCE_HEADER, HeaderValue::from_static("a b"));
+        assert_eq!(request_nonce(&headers), "");
+    }
+}
@@ -0,0 +1,183 @@
+//! Long operations started by a request, such as clones, which clients follow
+//! with `GET /api/jobs/{id}` instead of waiting on the request.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use nomadflow_core::models::{CloneRepoResponse, Job, JobKind, JobProgress, JobStatus};
+
+use crate::shares::now_secs;
+
+/// Number of jobs kept; older ones are dropped.
+const MAX_JOBS: usize = 50;
+/// Output kept per job. Past it, the beginning is dropped.
+const MAX_OUTPUT: usize = 64 * 1024;
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    jobs: VecDeque<Job>,
+}
+
+/// Jobs of this process, most recent last.
+#[derive(Default)]
+pub struct JobRegistry {
+    inner: Mutex<Inner>,
+}
+
+impl JobRegistry {
+    /// Start a job on `target`. `None` when one is already running on it.
+    pub fn start(&self, kind: JobKind, target: &str) -> Option<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner
+            .jobs
+            .iter()
+            .any(|j| j.target == target && j.status == JobStatus::Running)
+        {
+            return None;
+        }
+        inner.next_id += 1;
+        let id = inner.next_id;
+        inner.jobs.push_back(Job {
+            id,
+            kind,
+            target: target.to_string(),
+            status: JobStatus::Running,
+            progress: None,
+            started_at: now_secs(),
+            finished_at: None,
+            repo: None,
+            error: None,
+            output: String::new(),
+        });
+        // Running jobs stay listed
+        while inner.jobs.len() > MAX_JOBS {
+            let Some(pos) = inner
+                .jobs
+                .iter()
+                .position(|j| j.status != JobStatus::Running)
+            else {
+                break;
+            };
+            inner.jobs.remove(pos);
+        }
+        Some(id)
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut Job)) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(job) = inner.jobs.iter_mut().find(|j| j.id == id) {
+            f(job);
+        }
+    }
+
+    pub fn progress(&self, id: u64, progress: JobProgress) {
+        self.update(id, |job| job.progress = Some(progress));
+    }
+
+    /// Add a line to the output of a job.
+    pub fn append_output(&self, id: u64, line: &str) {
+        self.update(id, |job| {
+            job.output.push_str(line);
+            job.output.push('\n');
+            if job.output.len() > MAX_OUTPUT {
+                let mut cut = job.output.len() - MAX_OUTPUT;
+                while !job.output.is_char_boundary(cut) {
+                    cut += 1;
+                }
+                job.output.drain(..cut);
+            }
+        });
+    }
+
+    /// Record the end of a job other than a clone, with why it failed if it did.
+    pub fn finish(&self, id: u64, error: Option<String>) {
+        self.update(id, |job| {
+            job.finished_at = Some(now_secs());
+            job.status = if error.is_some() {
+                JobStatus::Failed
+            } else {
+                JobStatus::Succeeded
+            };
+            job.error = error;
+        });
+    }
+
+    /// Record the end of a clone.
+    pub fn finish_clone(&self, id: u64, result: Result<CloneRepoResponse, String>) {
+        self.update(id, |job| {
+            job.finished_at = Some(now_secs());
+            match result {
+                Ok(repo) => {
+                    job.status = JobStatus::Succeeded;
+                    job.repo = Some(repo);
+                }
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e);
+                }
+            }
+        });
+    }
+
+    /// All jobs, most recent first.
+    pub fn list(&self) -> Vec<Job> {
+        let inner = self.inner.lock().unwrap();
+        inner.jobs.iter().rev().cloned().collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        let inner = self.inner.lock().unwrap();
+        inner.jobs.iter().find(|j| j.id == id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_running_job_per_target() {
+        let jobs = JobRegistry::default();
+        let id = jobs.start(JobKind::CloneRepo, "/repos/a").unwrap();
+        assert!(jobs.start(JobKind::CloneRepo, "/repos/a").is_none());
+        assert!(jobs.start(JobKind::CloneRepo, "/repos/b").is_some());
+
+        jobs.finish_clone(id, Err("git clone failed".to_string()));
+        let job = jobs.get(id).unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(job.finished_at.is_some());
+        assert!(jobs.start(JobKind::CloneRepo, "/repos/a").is_some());
+    }
+
+    #[test]
+    fn test_output_keeps_the_end() {
+        let jobs = JobRegistry::default();
+        let id = jobs.start(JobKind::Commit, "/worktrees/a/x").unwrap();
+        jobs.append_output(id, "lint");
+        let long = "x".repeat(MAX_OUTPUT);
+        jobs.append_output(id, &long);
+        jobs.append_output(id, "failed");
+        jobs.finish(id, Some("The pre-commit hook failed".to_string()));
+
+        let job = jobs.get(id).unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.output.len(), MAX_OUTPUT);
+        assert!(job.output.ends_with("x\nfailed\n"));
+        assert!(!job.output.contains("lint"));
+    }
+
+    #[test]
+    fn test_finished_jobs_are_dropped_first() {
+        let jobs = JobRegistry::default();
+        let running = jobs.start(JobKind::CloneRepo, "/repos/slow").unwrap();
+        for i in 0..MAX_JOBS {
+            let id = jobs
+                .start(JobKind::CloneRepo, &format!("/repos/{i}"))
+                .unwrap();
+            jobs.finish_clone(id, Err(String::new()));
+        }
+        assert_eq!(jobs.list().len(), MAX_JOBS);
+        assert!(jobs.get(running).is_some());
+    }
+}
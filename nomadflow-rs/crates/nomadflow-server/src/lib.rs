@@ -1,23 +1,57 @@
+pub mod activity;
 pub mod auth;
+pub mod bans;
+pub mod deprecations;
+pub mod devices;
 pub mod display;
+pub mod downloads;
+pub mod etag;
+pub mod events;
+pub mod identity;
+pub mod jobs;
+pub mod metrics;
+pub mod nettest;
+pub mod notes;
+pub mod oidc;
+pub mod protection;
+pub mod pty;
+pub mod reconcile;
 pub mod routes;
+pub mod rtc;
+pub mod sessions;
+pub mod shares;
 pub mod state;
+pub mod statusline;
+pub mod suspend;
+pub mod tasks;
+pub mod tls;
+pub mod tmux_hooks;
+pub mod tokens;
+pub mod totp;
 pub mod tunnel;
+pub mod windows;
 
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use axum::{middleware, Router};
 use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use tracing::info;
 
-use nomadflow_core::config::Settings;
+use nomadflow_core::config::{Settings, TerminalBackend};
+use nomadflow_core::models::{ServerEvent, TerminalStatus};
 use nomadflow_core::services::tmux::TmuxService;
 use nomadflow_core::services::ttyd::TtydService;
 
-use crate::auth::auth_middleware;
+use crate::auth::{auth_middleware, ban_middleware};
+use crate::identity::ServerIdentity;
 use crate::state::AppState;
 
 /// Initialize tracing/logging for the server.
@@ -36,22 +70,56 @@ pub fn init_tracing() {
 pub fn build_router(state: Arc<AppState>) -> Router {
     let cors = CorsLayer::permissive();
 
-    // Health endpoint has no auth
-    let public = Router::new().merge(routes::health::router());
+    // Health endpoint has no auth; guest share pages and tmux hooks carry their own token
+    let public = Router::new()
+        .merge(routes::health::router())
+        .merge(routes::shares::guest_router())
+        .merge(routes::tmux_events::hook_router());
 
     // API endpoints require auth
     let api = Router::new()
         .merge(routes::repos::router())
         .merge(routes::features::router())
+        .merge(routes::files::router())
+        .merge(routes::activity::router())
+        .merge(routes::tmux_events::router())
+        .merge(routes::bans::router())
+        .merge(routes::devices::router())
+        .merge(routes::downloads::router())
+        .merge(routes::sessions::router())
+        .merge(routes::windows::router())
+        .merge(routes::tokens::router())
+        .merge(routes::shares::router())
+        .merge(routes::tunnel::router())
+        .merge(routes::reconcile::router())
+        .merge(routes::gc::router())
+        .merge(routes::tasks::router())
+        .merge(routes::jobs::router())
+        .merge(routes::events::router())
+        .merge(routes::search::router())
+        .merge(routes::workspace::router())
+        .merge(routes::metrics::router())
+        .merge(routes::webrtc::router())
         .merge(routes::terminal::http_proxy_router())
         .route_layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
 
+    // Compress JSON and proxied ttyd assets, but never the WebSocket upgrade
+    let mut http = public
+        .merge(api)
+        .layer(middleware::from_fn(deprecations::deprecation_middleware));
+    if state.settings.api.compression {
+        http = http.layer(CompressionLayer::new());
+    }
+
     // WebSocket proxy to ttyd (auth via query param, handled in handler)
-    let ws = Router::new().merge(routes::terminal::ws_router());
+    let ws = Router::new()
+        .merge(routes::terminal::ws_router())
+        .merge(routes::shares::guest_ws_router());
 
-    public
-        .merge(api)
-        .merge(ws)
+    // Banned clients are refused everywhere, including guest pages
+    http.merge(ws)
+        .layer(middleware::from_fn_with_state(state.clone(), metrics::track_requests))
+        .layer(middleware::from_fn_with_state(state.clone(), ban_middleware))
         .layer(cors)
         .layer(TraceLayer::new_for_http())
         .with_state(state)
@@ -80,15 +148,88 @@ pub fn spawn_signal_handler(shutdown: CancellationToken) {
     });
 }
 
+/// How long a window's ttyd is kept after its last client left, so that
+/// reconnecting is quick.
+const WINDOW_TTYD_IDLE: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Close expired share links and idle window ttyds every minute until shutdown.
+fn spawn_share_sweeper(state: Arc<AppState>, shutdown: CancellationToken) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+            for share in state.shares.take_expired(shares::now_secs()) {
+                info!(id = %share.info.id, "Share link expired");
+                share.close().await;
+            }
+            let stopped = state.window_ttyds.stop_idle(WINDOW_TTYD_IDLE).await;
+            if stopped > 0 {
+                info!(count = stopped, "Stopped idle window ttyds");
+            }
+        }
+    });
+}
+
+/// Log the supervised ttyd going down and coming back.
+fn spawn_ttyd_status_logger(mut status: watch::Receiver<TerminalStatus>) {
+    tokio::spawn(async move {
+        while status.changed().await.is_ok() {
+            match *status.borrow_and_update() {
+                TerminalStatus::Ok => info!("ttyd is running again"),
+                TerminalStatus::Restarting => tracing::warn!("ttyd stopped, restarting it"),
+                TerminalStatus::Unavailable => {
+                    tracing::warn!("ttyd cannot be started (terminal proxy will not work)")
+                }
+            }
+        }
+    });
+}
+
+/// Print the connection info again whenever the tunnel comes back under
+/// another public URL, e.g. after failing over to another relay, since the
+/// QR code printed at startup no longer works.
+fn spawn_connection_info_reprinter(
+    state: Arc<AppState>,
+    mut public_url: String,
+    qr_png: Option<PathBuf>,
+) {
+    let mut events = state.events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(ServerEvent::TunnelConnected { public_url: url }) if url != public_url => {
+                    display::print_connection_info(
+                        &url,
+                        &state.settings.auth.secret,
+                        &state.identity.public_key(),
+                        true,
+                        qr_png.as_deref(),
+                    );
+                    public_url = url;
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
 /// Build the connect URL from a host override or local IP detection.
-/// - IP address → `http://{ip}:{port}`
-/// - Domain name → `https://{domain}` (sans port, on suppose reverse proxy + TLS)
-/// - None → détecte l'IP locale → `http://{ip}:{port}`
-fn build_connect_url(host_override: &Option<String>, port: u16) -> String {
+/// - IP address → `http://{ip}:{port}` (`https` when `tls`)
+/// - Domain name → `https://{domain}` (sans port, on suppose reverse proxy + TLS),
+///   or `https://{domain}:{port}` when the server itself serves TLS
+/// - None → détecte l'IP locale → `http://{ip}:{port}` (`https` when `tls`)
+fn build_connect_url(host_override: &Option<String>, port: u16, tls: bool) -> String {
+    let scheme = if tls { "https" } else { "http" };
     match host_override {
         Some(h) => {
             if h.parse::<std::net::IpAddr>().is_ok() {
-                format!("http://{}:{}", h, port)
+                format!("{}://{}:{}", scheme, h, port)
+            } else if tls {
+                format!("https://{}:{}", h, port)
             } else {
                 format!("https://{}", h)
             }
@@ -97,7 +238,7 @@ fn build_connect_url(host_override: &Option<String>, port: u16) -> String {
             let local_ip = local_ip_address::local_ip()
                 .map(|ip| ip.to_string())
                 .unwrap_or_else(|_| "127.0.0.1".to_string());
-            format!("http://{}:{}", local_ip, port)
+            format!("{}://{}:{}", scheme, local_ip, port)
         }
     }
 }
@@ -106,15 +247,18 @@ fn build_connect_url(host_override: &Option<String>, port: u16) -> String {
 /// The server shuts down gracefully when `shutdown` is cancelled.
 /// When `public` is true, a bore tunnel is started and the server is exposed via the relay.
 /// When `quiet` is true, connection info (QR code) is not printed (used when running alongside TUI).
+/// When `qr_png` is set, the QR code is also written to that file as a PNG image.
 pub async fn serve(
     mut settings: Settings,
     shutdown: CancellationToken,
     public: bool,
     quiet: bool,
     host_override: Option<String>,
+    qr_png: Option<PathBuf>,
 ) -> color_eyre::Result<()> {
-    // 0. Auto-generate a secret if --public and none configured
-    if public && settings.auth.secret.is_empty() {
+    // 0. Auto-generate a secret if --public or [oidc] and none configured,
+    // so that the API is not left open
+    if (public || settings.oidc.enabled()) && settings.auth.secret.is_empty() {
         use rand::Rng;
         let secret: String = rand::rng()
             .sample_iter(rand::distr::Alphanumeric)
@@ -124,6 +268,11 @@ pub async fn serve(
         tracing::warn!("No auth secret configured — generated a temporary one for this session");
         settings.auth.secret = secret;
     }
+    if public && settings.tls_identity().is_some() {
+        tracing::warn!("[tls] is ignored with --public: the relay already serves HTTPS");
+        settings.tls = Default::default();
+    }
+    let tls = tls::rustls_config(&settings, host_override.as_deref())?;
 
     // 1. Ensure tmux session exists (ttyd needs it)
     let tmux = TmuxService::new(&settings.tmux.session);
@@ -133,53 +282,124 @@ pub async fn serve(
         info!(session = %settings.tmux.session, "Tmux session ready");
     }
 
-    // 2. Start ttyd subprocess
-    let mut ttyd = TtydService::new(&settings);
-    match ttyd.start().await {
-        Ok(()) => info!(port = settings.ttyd.port, "ttyd started"),
-        Err(e) => tracing::warn!("Failed to start ttyd: {e} (terminal proxy will not work)"),
-    }
+    // 2. Start ttyd subprocess, unless the server attaches tmux itself
+    let ttyd = if settings.ttyd.backend == TerminalBackend::Ttyd {
+        let mut service = TtydService::new(&settings);
+        match service.start().await {
+            Ok(()) => info!(port = settings.ttyd.port, "ttyd started"),
+            Err(e) => tracing::warn!("Failed to start ttyd: {e} (will keep retrying)"),
+        }
+        Some(service.supervise().await)
+    } else {
+        None
+    };
 
     // 3. Build state and router
-    let state = Arc::new(AppState::new(settings.clone()));
+    let mut state = AppState::new(settings.clone());
+    if let Some(ttyd) = &ttyd {
+        state.terminal_status = ttyd.status();
+        spawn_ttyd_status_logger(ttyd.status());
+    }
+    let identity_path = settings.base_dir().join("server-identity.key");
+    match ServerIdentity::load_or_create(&identity_path) {
+        Ok(identity) => state.identity = identity,
+        Err(e) => tracing::warn!("Failed to load the server identity key, using a temporary one: {e}"),
+    }
+    let state = Arc::new(state);
+    let observed = Arc::downgrade(&state);
+    nomadflow_core::shell::set_observer(move |command, elapsed| {
+        if let Some(state) = observed.upgrade() {
+            state.metrics.record_command(command, elapsed);
+        }
+    });
     let addr = format!("{}:{}", settings.api.host, settings.api.port);
     let router = build_router(state.clone());
+    if settings.tmux.restore_windows {
+        windows::restore(&state).await;
+    }
+    if settings.tmux.hooks {
+        tmux_hooks::install(&state).await;
+    }
 
     let listener = TcpListener::bind(&addr).await?;
     info!(%addr, "NomadFlow server listening");
 
     // 4. Start tunnel if --public
     let connect_url = if public {
-        match tunnel::start_tunnel(
-            settings.api.port,
-            &settings.tunnel,
-            shutdown.clone(),
-            &state.http_client,
-        )
-        .await
-        {
+        match tunnel::start_tunnel(state.clone(), settings.api.port, shutdown.clone()).await {
             Ok(info) => info.public_url,
             Err(e) => {
                 tracing::warn!("Tunnel failed: {e}");
-                build_connect_url(&host_override, settings.api.port)
+                build_connect_url(&host_override, settings.api.port, false)
             }
         }
     } else {
-        build_connect_url(&host_override, settings.api.port)
+        build_connect_url(&host_override, settings.api.port, tls.is_some())
     };
 
     // 5. Display connection info with QR code (only in foreground serve mode)
     if !quiet {
-        display::print_connection_info(&connect_url, &settings.auth.secret, public);
+        display::print_connection_info(
+            &connect_url,
+            &settings.auth.secret,
+            &state.identity.public_key(),
+            public,
+            qr_png.as_deref(),
+        );
+        if public {
+            spawn_connection_info_reprinter(state.clone(), connect_url.clone(), qr_png.clone());
+        }
+    }
+
+    spawn_share_sweeper(state.clone(), shutdown.clone());
+    if settings.tmux.close_orphan_windows {
+        windows::spawn_window_gc(state.clone(), shutdown.clone());
+    }
+    if !settings.limits.is_empty() {
+        windows::spawn_usage_monitor(state.clone(), shutdown.clone());
+    }
+    if settings.suspend.idle_minutes > 0 {
+        suspend::spawn_idle_suspender(state.clone(), shutdown.clone());
     }
+    statusline::spawn_statusline_writer(state.clone(), shutdown.clone());
 
-    axum::serve(listener, router)
-        .with_graceful_shutdown(shutdown.cancelled_owned())
-        .await?;
+    let app = router.into_make_service_with_connect_info::<SocketAddr>();
+    let stopping = {
+        // Event streams never end on their own
+        let state = state.clone();
+        async move {
+            shutdown.cancelled().await;
+            state.events.close();
+        }
+    };
+    match tls {
+        Some(config) => {
+            let handle = axum_server::Handle::new();
+            let stopper = handle.clone();
+            tokio::spawn(async move {
+                stopping.await;
+                stopper.graceful_shutdown(Some(std::time::Duration::from_secs(10)));
+            });
+            axum_server::from_tcp_rustls(listener.into_std()?, config)
+                .handle(handle)
+                .serve(app)
+                .await?;
+        }
+        None => axum::serve(listener, app).with_graceful_shutdown(stopping).await?,
+    }
 
-    // Cleanup: stop ttyd after graceful shutdown
-    info!("Stopping ttyd…");
-    ttyd.stop().await;
+    // Cleanup: stop ttyd and guest share ttyds after graceful shutdown
+    if let Some(mut ttyd) = ttyd {
+        info!("Stopping ttyd…");
+        ttyd.stop().await;
+    }
+    if settings.tmux.hooks {
+        tmux_hooks::uninstall(&state).await;
+    }
+    for share in state.shares.take_all() {
+        share.close().await;
+    }
+    state.window_ttyds.stop_all().await;
     info!("Server stopped");
 
     Ok(())
@@ -189,23 +409,431 @@ pub async fn serve(
 mod tests {
     use super::*;
 
+    async fn health_encoding(compression: bool) -> Option<String> {
+        use tower::ServiceExt;
+
+        let mut settings = Settings::default();
+        settings.api.compression = compression;
+        // Pad the body above the compression size threshold
+        settings.tmux.session = "x".repeat(64);
+        let router = build_router(Arc::new(AppState::new(settings)));
+        let request = axum::http::Request::get("/health")
+            .header("accept-encoding", "gzip")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        response
+            .headers()
+            .get("content-encoding")
+            .map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_health_is_signed_for_the_nonce() {
+        use base64::Engine;
+        use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let state = Arc::new(AppState::new(Settings::default()));
+        let router = build_router(state.clone());
+        let request = axum::http::Request::get("/health")
+            .header(identity::NONCE_HEADER, "n0nce")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let signature = response.headers()[identity::SIGNATURE_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let health: nomadflow_core::models::HealthResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health.server_key, Some(state.identity.public_key()));
+
+        let b64 = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let key: [u8; 32] = b64.decode(state.identity.public_key()).unwrap().try_into().unwrap();
+        let signature: [u8; 64] = b64.decode(signature).unwrap().try_into().unwrap();
+        let message = identity::signed_message("n0nce", &body);
+        assert!(VerifyingKey::from_bytes(&key)
+            .unwrap()
+            .verify(&message, &Signature::from_bytes(&signature))
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_compression_is_config_gated() {
+        assert_eq!(health_encoding(true).await.as_deref(), Some("gzip"));
+        assert_eq!(health_encoding(false).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_share_routes_auth() {
+        use tower::ServiceExt;
+
+        let mut settings = Settings::default();
+        settings.auth.secret = "s3cret".to_string();
+        let router = build_router(Arc::new(AppState::new(settings)));
+
+        // Guest pages bypass the API secret but need a valid share token
+        let request = axum::http::Request::get("/share/unknown-token")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        // Managing shares requires the secret
+        let request = axum::http::Request::post("/api/list-shares")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from("{}"))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_totp_second_factor() {
+        use tower::ServiceExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        settings.auth.secret = "s3cret".to_string();
+        settings.auth.totp_secret = totp::generate_secret();
+        let state = Arc::new(AppState::new(settings));
+        let router = build_router(state.clone());
+
+        let list_shares = |device: Option<&str>| {
+            let mut request = axum::http::Request::post("/api/list-shares")
+                .header("authorization", "Bearer s3cret")
+                .header("content-type", "application/json");
+            if let Some(device) = device {
+                request = request.header(auth::DEVICE_HEADER, device);
+            }
+            request.body(axum::body::Body::from("{}")).unwrap()
+        };
+
+        let response = router.clone().oneshot(list_shares(None)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["x-nomadflow-otp"], "required");
+
+        let local = state.local_device_token.clone().unwrap();
+        let response = router.clone().oneshot(list_shares(Some(&local))).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let (remembered, _) = state.devices.remember("phone", 1, shares::now_secs());
+        let response = router.clone().oneshot(list_shares(Some(&remembered))).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let response = router.oneshot(list_shares(Some("bogus"))).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_failed_auth_bans_client() {
+        use axum::extract::ConnectInfo;
+        use tower::ServiceExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        settings.auth.secret = "s3cret".to_string();
+        settings.auth.ban_max_failures = 2;
+        let state = Arc::new(AppState::new(settings));
+        let router = build_router(state.clone());
+
+        let request = |method: &str, uri: &str, bearer: &str, peer: &str| {
+            let mut request = axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {bearer}"))
+                .body(axum::body::Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+            request
+        };
+        let attacker = "203.0.113.7:4000";
+
+        for _ in 0..2 {
+            let response = router
+                .clone()
+                .oneshot(request("GET", "/api/bans", "wrong", attacker))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+        }
+
+        // Even the right secret is refused once banned
+        let response = router
+            .clone()
+            .oneshot(request("GET", "/health", "s3cret", attacker))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+        assert_eq!(
+            state.activity.recent(1)[0].action,
+            nomadflow_core::models::ActivityAction::BanIp
+        );
+
+        // The ban is persisted and can be lifted from the local machine
+        assert!(tmp.path().join("bans.json").exists());
+        let response = router
+            .clone()
+            .oneshot(request("DELETE", "/api/bans/203.0.113.7", "s3cret", "127.0.0.1:5000"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let response = router
+            .oneshot(request("GET", "/health", "s3cret", attacker))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_challenge_is_public() {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let mut settings = Settings::default();
+        settings.auth.secret = "s3cret".to_string();
+        let state = Arc::new(AppState::new(settings));
+        let router = build_router(state.clone());
+
+        let request = axum::http::Request::get("/_tunnel/challenge?nonce=abc")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, tunnel::challenge_response(&state.tunnel_key, "abc"));
+    }
+
+    #[tokio::test]
+    async fn test_webrtc_offer_disabled_by_default() {
+        use tower::ServiceExt;
+
+        let router = build_router(Arc::new(AppState::new(Settings::default())));
+        let request = axum::http::Request::post("/api/webrtc/offer")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(r#"{"sdp":"v=0"}"#))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_requests_by_route() {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let mut settings = Settings::default();
+        settings.auth.secret = "s3cret".to_string();
+        let router = build_router(Arc::new(AppState::new(settings)));
+        let get = |uri: &str, auth: bool| {
+            let mut request = axum::http::Request::get(uri);
+            if auth {
+                request = request.header("authorization", "Bearer s3cret");
+            }
+            request.body(axum::body::Body::empty()).unwrap()
+        };
+
+        let response = router.clone().oneshot(get("/metrics", false)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+        router.clone().oneshot(get("/health", false)).await.unwrap();
+        router.clone().oneshot(get("/no-such-page", false)).await.unwrap();
+
+        let response = router.oneshot(get("/metrics", true)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains(
+            "nomadflow_http_requests_total{method=\"GET\",route=\"/health\",status=\"200\"} 1"
+        ));
+        assert!(text.contains("route=\"unmatched\",status=\"404\"} 1"));
+        assert!(text.contains("nomadflow_tmux_windows "));
+    }
+
+    #[tokio::test]
+    async fn test_api_tokens_are_limited_to_their_scopes() {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.auth.secret = "s3cret".to_string();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        let router = build_router(Arc::new(AppState::new(settings)));
+        let request = |method: &str, uri: &str, credential: &str, body: &str| {
+            axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {credential}"))
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/tokens",
+                "s3cret",
+                r#"{"name":"alice","scopes":["terminal"]}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let token = created["token"].as_str().unwrap().to_string();
+        let id = created["id"].as_str().unwrap().to_string();
+
+        let status = |method: &str, uri: &str, credential: &str| {
+            router
+                .clone()
+                .oneshot(request(method, uri, credential, "{}"))
+        };
+        // Reading is allowed, cloning and managing tokens are not
+        let response = status("GET", "/api/tunnel", &token).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let response = status("POST", "/api/clone-repo", &token).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+        let response = status("GET", "/api/tokens", &token).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+
+        let revoke = format!("/api/tokens/{id}");
+        let response = status("DELETE", &revoke, "s3cret").await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let response = status("GET", "/api/tunnel", &token).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_relay_shutdown_notice_must_be_signed() {
+        use tower::ServiceExt;
+
+        let mut settings = Settings::default();
+        settings.auth.secret = "s3cret".to_string();
+        let state = Arc::new(AppState::new(settings));
+        let router = build_router(state.clone());
+        let notice = |signature: &str, timestamp: u64| {
+            axum::http::Request::post(tunnel::SHUTDOWN_PATH)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(
+                    serde_json::json!({ "timestamp": timestamp, "signature": signature })
+                        .to_string(),
+                ))
+                .unwrap()
+        };
+        let now = shares::now_secs();
+        let signed = |timestamp: u64| {
+            tunnel::challenge_response(&state.tunnel_key, &format!("shutdown:{timestamp}"))
+        };
+
+        let waiting = state.relay_shutdown.notified();
+        tokio::pin!(waiting);
+        waiting.as_mut().enable();
+
+        let response = router.clone().oneshot(notice("forged", now)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+        // An old notice cannot be replayed
+        let old = now - 3600;
+        let response = router.clone().oneshot(notice(&signed(old), old)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+
+        let response = router.oneshot(notice(&signed(now), now)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NO_CONTENT);
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiting)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tmux_hook_needs_its_key() {
+        use nomadflow_core::shell::{command_exists, run};
+        use tower::ServiceExt;
+
+        let mut settings = Settings::default();
+        settings.auth.secret = "s3cret".to_string();
+        settings.tmux.session = format!("nf-test-hook-{}", std::process::id());
+        let state = Arc::new(AppState::new(settings));
+        let router = build_router(state.clone());
+
+        let hook = |key: &str, session: &str, window: &str| {
+            let uri = format!(
+                "/api/tmux-hook?event=bell&session={}&window={window}&key={key}",
+                session.replace('$', "%24")
+            );
+            axum::http::Request::post(uri)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(hook("wrong", "$1", "@1"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+        assert!(state.tmux_events.recent().is_empty());
+
+        // Ids that are not windows of the session are dropped
+        let key = state.hook_key.clone();
+        let response = router
+            .clone()
+            .oneshot(hook(&key, "$999999", "@999999"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NO_CONTENT);
+        assert!(state.tmux_events.recent().is_empty());
+
+        if !command_exists("tmux").await {
+            eprintln!("Skipping tmux hook test: tmux not available");
+            return;
+        }
+        state.tmux.ensure_session().await.unwrap();
+        state.tmux.create_window("app:it's", None).await.unwrap();
+        let ids = run(
+            &format!(
+                "tmux display-message -p -t \"{}:app:it's\" \"#{{session_id}} #{{window_id}}\"",
+                state.tmux.session_name()
+            ),
+            None,
+        )
+        .await;
+        let (session_id, window_id) = ids.stdout.trim().split_once(' ').unwrap();
+        let response = router
+            .oneshot(hook(&key, session_id, window_id))
+            .await
+            .unwrap();
+        state.tmux.kill_session().await;
+        assert_eq!(response.status(), axum::http::StatusCode::NO_CONTENT);
+        let events = state.tmux_events.recent();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, nomadflow_core::models::TmuxEventKind::Bell);
+        assert_eq!(events[0].window, "app:it's");
+    }
+
     #[test]
     fn test_build_connect_url_with_ipv4() {
         let host = Some("192.168.1.42".to_string());
-        assert_eq!(build_connect_url(&host, 8080), "http://192.168.1.42:8080");
+        assert_eq!(build_connect_url(&host, 8080, false), "http://192.168.1.42:8080");
     }
 
     #[test]
     fn test_build_connect_url_with_ipv6() {
         let host = Some("::1".to_string());
-        assert_eq!(build_connect_url(&host, 3000), "http://::1:3000");
+        assert_eq!(build_connect_url(&host, 3000, false), "http://::1:3000");
     }
 
     #[test]
     fn test_build_connect_url_with_domain() {
         let host = Some("myserver.example.com".to_string());
         assert_eq!(
-            build_connect_url(&host, 8080),
+            build_connect_url(&host, 8080, false),
             "https://myserver.example.com"
         );
     }
@@ -214,14 +842,14 @@ mod tests {
     fn test_build_connect_url_with_subdomain() {
         let host = Some("dev.internal.company.io".to_string());
         assert_eq!(
-            build_connect_url(&host, 9090),
+            build_connect_url(&host, 9090, false),
             "https://dev.internal.company.io"
         );
     }
 
     #[test]
     fn test_build_connect_url_none_falls_back_to_local_ip() {
-        let url = build_connect_url(&None, 8080);
+        let url = build_connect_url(&None, 8080, false);
         assert!(url.starts_with("http://"));
         assert!(url.ends_with(":8080"));
     }
@@ -229,14 +857,220 @@ mod tests {
     #[test]
     fn test_build_connect_url_domain_ignores_port() {
         let host = Some("example.com".to_string());
-        let url = build_connect_url(&host, 9999);
+        let url = build_connect_url(&host, 9999, false);
         assert!(!url.contains("9999"));
         assert_eq!(url, "https://example.com");
     }
 
+    #[test]
+    fn test_build_connect_url_with_tls() {
+        let host = Some("192.168.1.42".to_string());
+        assert_eq!(build_connect_url(&host, 8080, true), "https://192.168.1.42:8080");
+        // No reverse proxy in front: the port is part of the URL
+        let host = Some("devbox.lan".to_string());
+        assert_eq!(build_connect_url(&host, 8080, true), "https://devbox.lan:8080");
+        assert!(build_connect_url(&None, 8080, true).starts_with("https://"));
+    }
+
     #[test]
     fn test_build_connect_url_localhost_ip() {
         let host = Some("127.0.0.1".to_string());
-        assert_eq!(build_connect_url(&host, 4000), "http://127.0.0.1:4000");
+        assert_eq!(build_connect_url(&host, 4000, false), "http://127.0.0.1:4000");
+    }
+
+    #[tokio::test]
+    async fn test_search_stays_in_repos_dir() {
+        use tower::ServiceExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        let router = build_router(Arc::new(AppState::new(settings)));
+
+        let search = |body: &str| {
+            axum::http::Request::post("/api/search")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(search(r#"{"query":"root","repoPaths":["/etc"]}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        let response = router.oneshot(search(r#"{"query":"  "}"#)).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_quick_attach_needs_token_and_known_feature() {
+        use tower::ServiceExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        settings.auth.secret = "s3cret".to_string();
+        let repo = settings.repos_dir().join("app");
+        std::fs::create_dir_all(&repo).unwrap();
+        nomadflow_core::shell::run("git init -q", Some(&repo.to_string_lossy())).await;
+        let router = build_router(Arc::new(AppState::new(settings)));
+
+        let quick_attach = |query: &str, token: Option<&str>| {
+            let mut request = axum::http::Request::get(format!("/api/quick-attach?{query}"));
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {token}"));
+            }
+            request.body(axum::body::Body::empty()).unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(quick_attach("repo=app&feature=x", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+
+        let response = router
+            .clone()
+            .oneshot(quick_attach("repo=nope&feature=x", Some("s3cret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        // An unknown feature is not created
+        let response = router
+            .oneshot(quick_attach("repo=app&feature=x", Some("s3cret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+        assert!(!tmp.path().join("worktrees/app/x").exists());
+    }
+
+    #[tokio::test]
+    async fn test_get_routes_address_repos_by_name() {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        for name in ["app", "api"] {
+            let repo = settings.repos_dir().join(name);
+            std::fs::create_dir_all(&repo).unwrap();
+            nomadflow_core::shell::run("git init -q", Some(&repo.to_string_lossy())).await;
+        }
+        let router = build_router(Arc::new(AppState::new(settings)));
+
+        let get = |uri: &str| {
+            axum::http::Request::get(uri)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(get("/api/repos?filter=pp&limit=5"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let repos: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(repos["total"], 1);
+        assert_eq!(repos["repos"][0]["name"], "app");
+
+        let response = router
+            .clone()
+            .oneshot(get("/api/repos/app/features"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let response = router
+            .oneshot(get("/api/repos/nope/branches"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_clone_repo_runs_as_a_job() {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        let origin = tmp.path().join("origin");
+        std::fs::create_dir_all(&origin).unwrap();
+        let origin = origin.to_string_lossy().to_string();
+        nomadflow_core::shell::run("git init -q", Some(&origin)).await;
+        nomadflow_core::shell::run("git commit -q --allow-empty -m init", Some(&origin)).await;
+        let router = build_router(Arc::new(AppState::new(settings)));
+
+        let clone = axum::http::Request::post("/api/clone-repo")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(
+                serde_json::json!({ "url": origin, "name": "copy" }).to_string(),
+            ))
+            .unwrap();
+        let response = router.clone().oneshot(clone).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::ACCEPTED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let started: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let uri = format!("/api/jobs/{}", started["jobId"]);
+
+        let job = loop {
+            let request = axum::http::Request::get(&uri)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let job: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            if job["status"] != "running" {
+                break job;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        };
+        assert_eq!(job["status"], "succeeded");
+        assert_eq!(job["repo"]["name"], "copy");
+
+        let request = axum::http::Request::get("/api/jobs/999")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_events_are_streamed() {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        let state = Arc::new(AppState::new(settings));
+        let router = build_router(state.clone());
+
+        let request = axum::http::Request::get("/api/events")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+        state.events.publish(ServerEvent::RepoCloned {
+            name: "app".to_string(),
+            path: "/repos/app".to_string(),
+        });
+        state.events.close();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("event: repoCloned\n"), "{body}");
+        assert!(body.contains(r#""path":"/repos/app""#), "{body}");
     }
 }
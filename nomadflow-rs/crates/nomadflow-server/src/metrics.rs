@@ -0,0 +1,274 @@
+//! Counters exposed in the Prometheus text format at `/metrics`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::state::AppState;
+
+/// Upper bounds of the latency buckets, in seconds.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Label of requests that matched no route, so that scanners probing random
+/// paths do not grow the label set.
+const UNMATCHED: &str = "unmatched";
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations at or below each of [`BUCKETS`].
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        for (count, bound) in self.buckets.iter().zip(BUCKETS) {
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {count}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+/// (method, route, status)
+type RequestKey = (String, String, u16);
+
+/// Request, WebSocket and git command counters, in memory only.
+#[derive(Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<RequestKey, u64>>,
+    latencies: Mutex<BTreeMap<String, Histogram>>,
+    git_commands: Mutex<BTreeMap<String, Histogram>>,
+    ws_sessions: AtomicI64,
+}
+
+/// Counts a WebSocket bridge as active until dropped.
+pub struct WsSession<'a>(&'a AtomicI64);
+
+impl Drop for WsSession<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// State sampled when `/metrics` is scraped rather than counted.
+pub struct Gauges {
+    pub ttyd_up: bool,
+    pub tmux_windows: usize,
+    /// Bytes sent to and received from terminal clients, from the session
+    /// registry.
+    pub terminal_bytes: (u64, u64),
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+/// The git subcommand of a shell command line, e.g. `status` for
+/// `git --no-optional-locks status --porcelain`.
+fn git_subcommand(command: &str) -> Option<&str> {
+    let mut words = command.split_whitespace();
+    if words.next()? != "git" {
+        return None;
+    }
+    words.find(|w| !w.starts_with('-'))
+}
+
+impl Metrics {
+    pub fn record_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        *self
+            .requests
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), route.to_string(), status))
+            .or_default() += 1;
+        self.latencies
+            .lock()
+            .unwrap()
+            .entry(route.to_string())
+            .or_default()
+            .observe(elapsed);
+    }
+
+    /// Record a command run by the core services; only git commands are kept.
+    pub fn record_command(&self, command: &str, elapsed: Duration) {
+        if let Some(subcommand) = git_subcommand(command) {
+            self.git_commands
+                .lock()
+                .unwrap()
+                .entry(subcommand.to_string())
+                .or_default()
+                .observe(elapsed);
+        }
+    }
+
+    pub fn ws_session(&self) -> WsSession<'_> {
+        self.ws_sessions.fetch_add(1, Ordering::Relaxed);
+        WsSession(&self.ws_sessions)
+    }
+
+    /// Everything in the Prometheus text exposition format.
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP nomadflow_http_requests_total HTTP requests by method, route and status.\n");
+        out.push_str("# TYPE nomadflow_http_requests_total counter\n");
+        for ((method, route, status), count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "nomadflow_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{status}\"}} {count}",
+                escape(method),
+                escape(route),
+            );
+        }
+
+        out.push_str("# HELP nomadflow_http_request_duration_seconds Time to respond, by route.\n");
+        out.push_str("# TYPE nomadflow_http_request_duration_seconds histogram\n");
+        for (route, histogram) in self.latencies.lock().unwrap().iter() {
+            histogram.render(
+                &mut out,
+                "nomadflow_http_request_duration_seconds",
+                &format!("route=\"{}\"", escape(route)),
+            );
+        }
+
+        out.push_str("# HELP nomadflow_git_command_duration_seconds Git commands run by the server, by subcommand.\n");
+        out.push_str("# TYPE nomadflow_git_command_duration_seconds histogram\n");
+        for (command, histogram) in self.git_commands.lock().unwrap().iter() {
+            histogram.render(
+                &mut out,
+                "nomadflow_git_command_duration_seconds",
+                &format!("command=\"{}\"", escape(command)),
+            );
+        }
+
+        out.push_str("# HELP nomadflow_ws_sessions Terminal WebSockets bridged to ttyd.\n");
+        out.push_str("# TYPE nomadflow_ws_sessions gauge\n");
+        let _ = writeln!(
+            out,
+            "nomadflow_ws_sessions {}",
+            self.ws_sessions.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP nomadflow_terminal_bytes_total Bytes carried by terminal sessions.\n");
+        out.push_str("# TYPE nomadflow_terminal_bytes_total counter\n");
+        let (sent, received) = gauges.terminal_bytes;
+        let _ = writeln!(out, "nomadflow_terminal_bytes_total{{direction=\"sent\"}} {sent}");
+        let _ = writeln!(
+            out,
+            "nomadflow_terminal_bytes_total{{direction=\"received\"}} {received}"
+        );
+
+        out.push_str("# HELP nomadflow_ttyd_up Whether ttyd accepts connections.\n");
+        out.push_str("# TYPE nomadflow_ttyd_up gauge\n");
+        let _ = writeln!(out, "nomadflow_ttyd_up {}", u8::from(gauges.ttyd_up));
+
+        out.push_str("# HELP nomadflow_tmux_windows Windows in the tmux session.\n");
+        out.push_str("# TYPE nomadflow_tmux_windows gauge\n");
+        let _ = writeln!(out, "nomadflow_tmux_windows {}", gauges.tmux_windows);
+
+        out
+    }
+}
+
+/// Count every request and how long it took, by the route it matched.
+pub async fn track_requests(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED, |path| path.as_str())
+        .to_string();
+    let started = Instant::now();
+    let response = next.run(request).await;
+    state.metrics.record_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_subcommand() {
+        assert_eq!(git_subcommand("git --no-optional-locks status --porcelain"), Some("status"));
+        assert_eq!(git_subcommand("git worktree list --porcelain"), Some("worktree"));
+        assert_eq!(git_subcommand("tmux list-windows"), None);
+        assert_eq!(git_subcommand("git"), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        metrics.record_request("GET", "/api/list-repos", 200, Duration::from_millis(20));
+        metrics.record_request("GET", "/api/list-repos", 200, Duration::from_secs(3));
+        metrics.record_command("git fetch --all", Duration::from_millis(300));
+        metrics.record_command("tmux list-windows", Duration::from_millis(1));
+        let session = metrics.ws_session();
+
+        let text = metrics.render(&Gauges {
+            ttyd_up: true,
+            tmux_windows: 4,
+            terminal_bytes: (2048, 16),
+        });
+        assert!(text.contains(
+            "nomadflow_http_requests_total{method=\"GET\",route=\"/api/list-repos\",status=\"200\"} 2"
+        ));
+        assert!(text.contains(
+            "nomadflow_http_request_duration_seconds_bucket{route=\"/api/list-repos\",le=\"0.025\"} 1"
+        ));
+        assert!(text.contains(
+            "nomadflow_http_request_duration_seconds_bucket{route=\"/api/list-repos\",le=\"+Inf\"} 2"
+        ));
+        assert!(text.contains("nomadflow_git_command_duration_seconds_count{command=\"fetch\"} 1"));
+        assert!(!text.contains("command=\"list-windows\""));
+        assert!(text.contains("nomadflow_ws_sessions 1\n"));
+        assert!(text.contains("nomadflow_ttyd_up 1\n"));
+        assert!(text.contains("nomadflow_tmux_windows 4\n"));
+        assert!(text.contains("nomadflow_terminal_bytes_total{direction=\"sent\"} 2048\n"));
+
+        drop(session);
+        assert!(metrics
+            .render(&Gauges {
+                ttyd_up: false,
+                tmux_windows: 0,
+                terminal_bytes: (0, 0),
+            })
+            .contains("nomadflow_ws_sessions 0\n"));
+    }
+}
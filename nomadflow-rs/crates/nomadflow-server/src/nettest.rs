@@ -0,0 +1,294 @@
+//! Connectivity self-test behind `nomadflow nettest`: checks each hop
+//! between a client and this machine and times it.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+use nomadflow_core::config::{Settings, TerminalBackend};
+use nomadflow_core::models::{TunnelState, TunnelStatus};
+
+use crate::auth::DEVICE_HEADER;
+
+/// Time allowed for each check.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    LocalApi,
+    Ttyd,
+    RelayApi,
+    BoreControl,
+    PublicUrl,
+}
+
+impl Check {
+    /// i18n key of the check's label.
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::LocalApi => "nettest.local_api",
+            Self::Ttyd => "nettest.ttyd",
+            Self::RelayApi => "nettest.relay_api",
+            Self::BoreControl => "nettest.bore",
+            Self::PublicUrl => "nettest.public_url",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed(String),
+    /// Not applicable; holds the i18n key of the reason.
+    Skipped(&'static str),
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub check: Check,
+    /// What was contacted (URL or host:port).
+    pub target: String,
+    pub outcome: Outcome,
+    pub latency: Option<Duration>,
+}
+
+impl CheckResult {
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome, Outcome::Failed(_))
+    }
+}
+
+/// Run `attempt` with the check timeout and time it.
+async fn timed<F>(check: Check, target: String, attempt: F) -> CheckResult
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let start = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, attempt)
+        .await
+        .unwrap_or_else(|_| Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())));
+    let latency = start.elapsed();
+    CheckResult {
+        check,
+        target,
+        outcome: match result {
+            Ok(()) => Outcome::Passed,
+            Err(e) => Outcome::Failed(e),
+        },
+        latency: Some(latency),
+    }
+}
+
+fn skipped(check: Check, target: String, reason: &'static str) -> CheckResult {
+    CheckResult {
+        check,
+        target,
+        outcome: Outcome::Skipped(reason),
+        latency: None,
+    }
+}
+
+async fn get_ok(client: &reqwest::Client, url: &str) -> Result<(), String> {
+    let resp = client.get(url).send().await.map_err(|e| e.to_string())?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", resp.status()))
+    }
+}
+
+/// Open the ttyd WebSocket the way the terminal proxy does.
+async fn ttyd_handshake(port: u16, auth_secret: &str) -> Result<(), String> {
+    let mut request = format!("ws://127.0.0.1:{port}/ws")
+        .into_client_request()
+        .map_err(|e| e.to_string())?;
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", "tty".parse().unwrap());
+    if !auth_secret.is_empty() {
+        let creds =
+            base64::engine::general_purpose::STANDARD.encode(format!("nomadflow:{auth_secret}"));
+        request.headers_mut().insert(
+            "Authorization",
+            format!("Basic {creds}")
+                .parse()
+                .map_err(|_| "invalid secret")?,
+        );
+    }
+    tokio_tungstenite::connect_async(request)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Tunnel status of the local server, if it is running and reachable.
+async fn local_tunnel_status(
+    client: &reqwest::Client,
+    settings: &Settings,
+    base_url: &str,
+) -> Option<TunnelStatus> {
+    let mut request = client
+        .get(format!("{base_url}/api/tunnel"))
+        .bearer_auth(&settings.auth.secret);
+    if !settings.auth.totp_secret.is_empty() {
+        if let Ok(token) = settings.local_device_token() {
+            request = request.header(DEVICE_HEADER, token);
+        }
+    }
+    let resp = request.timeout(CHECK_TIMEOUT).send().await.ok()?;
+    resp.error_for_status().ok()?.json().await.ok()
+}
+
+/// Run every check in order. A check that depends on a failed one is skipped.
+pub async fn run(settings: &Settings) -> Vec<CheckResult> {
+    let client = reqwest::Client::new();
+    let tunnel = &settings.tunnel;
+    let base_url = format!("http://127.0.0.1:{}", settings.api.port);
+    let mut results = Vec::new();
+
+    let health_url = format!("{base_url}/health");
+    results.push(
+        timed(
+            Check::LocalApi,
+            health_url.clone(),
+            get_ok(&client, &health_url),
+        )
+        .await,
+    );
+    let local_up = !results[0].failed();
+
+    let ttyd_port = settings.ttyd.port;
+    let ttyd_url = format!("ws://127.0.0.1:{ttyd_port}/ws");
+    results.push(match settings.ttyd.backend {
+        TerminalBackend::Native => skipped(Check::Ttyd, ttyd_url, "nettest.skip_native_terminal"),
+        TerminalBackend::Ttyd => {
+            timed(
+                Check::Ttyd,
+                ttyd_url,
+                ttyd_handshake(ttyd_port, &settings.auth.secret),
+            )
+            .await
+        }
+    });
+
+    let relay_url = format!("https://{}/_api/health", tunnel.relay_host);
+    results.push(
+        timed(
+            Check::RelayApi,
+            relay_url.clone(),
+            get_ok(&client, &relay_url),
+        )
+        .await,
+    );
+
+    let bore_addr = format!("{}:{}", tunnel.relay_host, tunnel.relay_port);
+    results.push(
+        timed(Check::BoreControl, bore_addr.clone(), async {
+            tokio::net::TcpStream::connect(&bore_addr)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+        .await,
+    );
+
+    let status = if local_up {
+        local_tunnel_status(&client, settings, &base_url).await
+    } else {
+        None
+    };
+    results.push(match status {
+        None => skipped(Check::PublicUrl, String::new(), "nettest.skip_no_server"),
+        Some(TunnelStatus {
+            state: TunnelState::Connected | TunnelState::Reconnecting,
+            public_url: Some(public_url),
+            ..
+        }) => {
+            let url = format!("{public_url}/health");
+            timed(Check::PublicUrl, url.clone(), get_ok(&client, &url)).await
+        }
+        Some(_) => skipped(Check::PublicUrl, String::new(), "nettest.skip_not_public"),
+    });
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timed_reports_failures() {
+        let ok = timed(Check::LocalApi, "x".to_string(), async { Ok(()) }).await;
+        assert_eq!(ok.outcome, Outcome::Passed);
+        assert!(ok.latency.is_some());
+
+        let failed = timed(Check::LocalApi, "x".to_string(), async {
+            Err("refused".to_string())
+        })
+        .await;
+        assert!(failed.failed());
+    }
+
+    #[tokio::test]
+    async fn test_run_against_nothing_listening() {
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = closed.local_addr().unwrap().port();
+        drop(closed);
+
+        let mut settings = Settings::default();
+        settings.api.port = port;
+        settings.ttyd.backend = TerminalBackend::Ttyd;
+        settings.ttyd.port = port;
+        settings.tunnel.relay_host = "127.0.0.1".to_string();
+        settings.tunnel.relay_port = port;
+
+        let results = run(&settings).await;
+        let checks: Vec<Check> = results.iter().map(|r| r.check).collect();
+        assert_eq!(
+            checks,
+            [
+                Check::LocalApi,
+                Check::Ttyd,
+                Check::RelayApi,
+                Check::BoreControl,
+                Check::PublicUrl
+            ]
+        );
+        assert!(results[..4].iter().all(CheckResult::failed));
+        assert_eq!(
+            results[4].outcome,
+            Outcome::Skipped("nettest.skip_no_server")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_api_and_tunnel_status() {
+        use std::sync::Arc;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut settings = Settings::default();
+        settings.api.port = port;
+        settings.auth.secret = "s3cret".to_string();
+        let router = crate::build_router(Arc::new(crate::state::AppState::new(settings.clone())));
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+
+        let client = reqwest::Client::new();
+        let base_url = format!("http://127.0.0.1:{port}");
+        assert!(get_ok(&client, &format!("{base_url}/health")).await.is_ok());
+        let status = local_tunnel_status(&client, &settings, &base_url)
+            .await
+            .unwrap();
+        assert_eq!(status.state, TunnelState::Disabled);
+    }
+}
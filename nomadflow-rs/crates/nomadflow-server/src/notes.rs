@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use nomadflow_core::fsutil::JsonStore;
+use nomadflow_core::models::FeatureNote;
+
+/// Longest note accepted, in bytes.
+pub const MAX_NOTE_LEN: usize = 16 * 1024;
+
+/// Notes by repo path, then by feature name.
+type Notes = BTreeMap<String, BTreeMap<String, FeatureNote>>;
+
+/// Freeform notes about features, persisted to `feature-notes.json`.
+pub struct NoteStore {
+    notes: JsonStore<Notes>,
+}
+
+impl NoteStore {
+    /// Load the store from `path`, or start empty if it is missing or unreadable.
+    pub fn load(path: PathBuf) -> Self {
+        Self {
+            notes: JsonStore::load(path),
+        }
+    }
+
+    /// A store that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            notes: JsonStore::in_memory(),
+        }
+    }
+
+    /// The note of a feature, if it has one.
+    pub fn get(&self, repo_path: &str, feature_name: &str) -> Option<FeatureNote> {
+        self.notes
+            .lock()
+            .get(repo_path)
+            .and_then(|features| features.get(feature_name))
+            .cloned()
+    }
+
+    /// Replace the note of a feature. A blank text removes it.
+    pub fn set(
+        &self,
+        repo_path: &str,
+        feature_name: &str,
+        text: &str,
+        now: u64,
+    ) -> Option<FeatureNote> {
+        let mut notes = self.notes.lock();
+        let note = if text.trim().is_empty() {
+            Self::take(&mut notes, repo_path, feature_name);
+            None
+        } else {
+            let note = FeatureNote {
+                text: text.to_string(),
+                updated_at: now,
+            };
+            notes
+                .entry(repo_path.to_string())
+                .or_default()
+                .insert(feature_name.to_string(), note.clone());
+            Some(note)
+        };
+        self.notes.persist(&notes);
+        note
+    }
+
+    /// Forget the note of a deleted feature.
+    pub fn remove(&self, repo_path: &str, feature_name: &str) {
+        let mut notes = self.notes.lock();
+        if Self::take(&mut notes, repo_path, feature_name).is_some() {
+            self.notes.persist(&notes);
+        }
+    }
+
+    fn take(notes: &mut Notes, repo_path: &str, feature_name: &str) -> Option<FeatureNote> {
+        let features = notes.get_mut(repo_path)?;
+        let note = features.remove(feature_name);
+        if features.is_empty() {
+            notes.remove(repo_path);
+        }
+        note
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_get_clear() {
+        let store = NoteStore::in_memory();
+        assert_eq!(store.get("/r", "login"), None);
+
+        let note = store.set("/r", "login", "Fix the OAuth redirect", 1000);
+        assert_eq!(store.get("/r", "login"), note);
+        assert_eq!(store.get("/other", "login"), None);
+
+        assert_eq!(store.set("/r", "login", "  \n", 2000), None);
+        assert_eq!(store.get("/r", "login"), None);
+        assert!(store.notes.lock().is_empty());
+    }
+
+    #[test]
+    fn test_persisted() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("feature-notes.json");
+        NoteStore::load(path.clone()).set("/r", "login", "Waiting on the API", 1000);
+        NoteStore::load(path.clone()).set("/r", "search", "Done, needs review", 1000);
+
+        let store = NoteStore::load(path.clone());
+        assert_eq!(store.get("/r", "login").unwrap().text, "Waiting on the API");
+        store.remove("/r", "login");
+        assert_eq!(NoteStore::load(path).get("/r", "login"), None);
+    }
+}
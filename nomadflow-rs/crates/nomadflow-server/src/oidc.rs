@@ -0,0 +1,460 @@
+//! Single sign-on: bearer JWTs issued by the OpenID Connect provider of
+//! `[oidc]`, for teams fronting NomadFlow with their SSO.
+//!
+//! A token is accepted when the provider signed it, it is meant for
+//! `oidc.audience` and has not expired. Its scopes, prefixed with
+//! `oidc.scope_prefix`, are the [`TokenScope`]s of API tokens, and `admin`
+//! grants what the secret does.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::Deserialize;
+
+use nomadflow_core::config::OidcConfig;
+use nomadflow_core::models::TokenScope;
+
+/// How long the provider's keys are used before being fetched again.
+const KEYS_TTL: Duration = Duration::from_secs(60 * 60);
+/// Least time between two fetches, so that tokens naming unknown keys do
+/// not make the server hammer the provider.
+const KEYS_RETRY: Duration = Duration::from_secs(60);
+/// Clock difference allowed with the provider.
+const LEEWAY_SECS: u64 = 60;
+
+const B64: base64::engine::GeneralPurpose = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+/// Someone signed in through the provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsoUser {
+    /// `sub` of the token.
+    pub subject: String,
+    /// Username or email, for logs and errors.
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    /// Granted the `admin` scope.
+    pub admin: bool,
+}
+
+/// A signing key of the provider, as published in its JWKS.
+#[derive(Debug, Clone, Deserialize)]
+struct Jwk {
+    kty: String,
+    #[serde(default)]
+    kid: Option<String>,
+    #[serde(default)]
+    alg: Option<String>,
+    #[serde(default)]
+    n: Option<String>,
+    #[serde(default)]
+    e: Option<String>,
+    #[serde(default)]
+    crv: Option<String>,
+    #[serde(default)]
+    x: Option<String>,
+    #[serde(default)]
+    y: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn values(&self) -> Vec<&str> {
+        match self {
+            Self::One(value) => value.split_whitespace().collect(),
+            Self::Many(values) => values.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    iss: String,
+    sub: String,
+    aud: OneOrMany,
+    exp: u64,
+    #[serde(default)]
+    nbf: Option<u64>,
+    /// Space-separated, as in OAuth access tokens.
+    #[serde(default)]
+    scope: Option<String>,
+    /// Azure AD and Okta name them this way, as a list or a string.
+    #[serde(default)]
+    scp: Option<OneOrMany>,
+    #[serde(default)]
+    preferred_username: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+}
+
+#[derive(Default)]
+struct KeyCache {
+    keys: Vec<Jwk>,
+    fetched_at: Option<Instant>,
+    /// Last fetch, whether it worked or not.
+    tried_at: Option<Instant>,
+}
+
+/// Checks the tokens of the provider, fetching its keys as needed.
+pub struct OidcVerifier {
+    config: OidcConfig,
+    client: reqwest::Client,
+    cache: Mutex<KeyCache>,
+}
+
+/// Whether `credential` has the shape of a JWT rather than of a secret or
+/// API token.
+pub fn looks_like_jwt(credential: &str) -> bool {
+    credential.split('.').count() == 3 && credential.starts_with("eyJ")
+}
+
+impl OidcVerifier {
+    /// A verifier for `config`, `None` when single sign-on is off.
+    pub fn new(config: &OidcConfig, client: reqwest::Client) -> Option<Self> {
+        config.enabled().then(|| Self {
+            config: config.clone(),
+            client,
+            cache: Mutex::new(KeyCache::default()),
+        })
+    }
+
+    /// Who `token` was issued to, if the provider issued it for us and it
+    /// is still valid.
+    pub async fn verify(&self, token: &str, now: u64) -> Option<SsoUser> {
+        let (header, claims, signed, signature) = decode(token)?;
+        if !valid_claims(&claims, &self.config, now) {
+            return None;
+        }
+        let mut keys = self.keys(false).await;
+        if find_key(&keys, &header).is_none() {
+            // The provider may have rotated its keys
+            keys = self.keys(true).await;
+        }
+        let key = find_key(&keys, &header)?;
+        if !verify_signature(key, &header.alg, signed.as_bytes(), &signature) {
+            return None;
+        }
+        Some(sso_user(&claims, &self.config.scope_prefix))
+    }
+
+    /// The provider's keys, fetched again when stale or to `refresh` them,
+    /// unless they were fetched within [`KEYS_RETRY`].
+    async fn keys(&self, refresh: bool) -> Vec<Jwk> {
+        {
+            let mut cache = self.cache.lock().unwrap();
+            let stale = refresh || cache.fetched_at.is_none_or(|at| at.elapsed() >= KEYS_TTL);
+            let tried = cache.tried_at.is_some_and(|at| at.elapsed() < KEYS_RETRY);
+            if !stale || tried {
+                return cache.keys.clone();
+            }
+            cache.tried_at = Some(Instant::now());
+        }
+        match self.fetch_keys().await {
+            Ok(keys) => {
+                let mut cache = self.cache.lock().unwrap();
+                cache.keys = keys.clone();
+                cache.fetched_at = Some(Instant::now());
+                keys
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to fetch the signing keys of {}: {e}",
+                    self.config.issuer
+                );
+                // Keep the keys known until the provider answers again
+                self.cache.lock().unwrap().keys.clone()
+            }
+        }
+    }
+
+    async fn fetch_keys(&self) -> Result<Vec<Jwk>, reqwest::Error> {
+        let jwks_url = if self.config.jwks_url.is_empty() {
+            let url = format!(
+                "{}/.well-known/openid-configuration",
+                self.config.issuer.trim_end_matches('/')
+            );
+            let discovery: Discovery = self.get(&url).await?.json().await?;
+            discovery.jwks_uri
+        } else {
+            self.config.jwks_url.clone()
+        };
+        let jwks: Jwks = self.get(&jwks_url).await?.json().await?;
+        Ok(jwks.keys)
+    }
+
+    async fn get(&self, url: &str) -> Result<reqwest::Response, reqwest::Error> {
+        self.client
+            .get(url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await?
+            .error_for_status()
+    }
+}
+
+/// The header, claims, signed part and signature of a JWT.
+fn decode(token: &str) -> Option<(Header, Claims, &str, Vec<u8>)> {
+    let (signed, signature) = token.rsplit_once('.')?;
+    let (header, claims) = signed.split_once('.')?;
+    let header: Header = serde_json::from_slice(&B64.decode(header).ok()?).ok()?;
+    let claims: Claims = serde_json::from_slice(&B64.decode(claims).ok()?).ok()?;
+    Some((header, claims, signed, B64.decode(signature).ok()?))
+}
+
+fn valid_claims(claims: &Claims, config: &OidcConfig, now: u64) -> bool {
+    claims.iss.trim_end_matches('/') == config.issuer.trim_end_matches('/')
+        && claims.aud.values().contains(&config.audience.as_str())
+        && claims.exp + LEEWAY_SECS > now
+        && claims.nbf.is_none_or(|nbf| nbf <= now + LEEWAY_SECS)
+}
+
+/// The key of `keys` that signed a token with `header`: the one it names,
+/// or the only one of the right type.
+fn find_key<'a>(keys: &'a [Jwk], header: &Header) -> Option<&'a Jwk> {
+    let kty = match header.alg.as_str() {
+        "RS256" | "RS384" | "RS512" | "PS256" => "RSA",
+        "ES256" | "ES384" => "EC",
+        "EdDSA" => "OKP",
+        _ => return None,
+    };
+    let mut candidates = keys.iter().filter(|k| {
+        k.kty == kty
+            && k.alg.as_ref().is_none_or(|alg| *alg == header.alg)
+            && (header.kid.is_none() || k.kid == header.kid)
+    });
+    let key = candidates.next()?;
+    candidates.next().is_none().then_some(key)
+}
+
+fn verify_signature(key: &Jwk, alg: &str, message: &[u8], signature: &[u8]) -> bool {
+    let part = |value: &Option<String>| value.as_deref().and_then(|v| B64.decode(v).ok());
+    match alg {
+        "RS256" | "RS384" | "RS512" | "PS256" => {
+            let (Some(n), Some(e)) = (part(&key.n), part(&key.e)) else {
+                return false;
+            };
+            let params = match alg {
+                "RS256" => &signature::RSA_PKCS1_2048_8192_SHA256,
+                "RS384" => &signature::RSA_PKCS1_2048_8192_SHA384,
+                "RS512" => &signature::RSA_PKCS1_2048_8192_SHA512,
+                _ => &signature::RSA_PSS_2048_8192_SHA256,
+            };
+            RsaPublicKeyComponents { n, e }
+                .verify(params, message, signature)
+                .is_ok()
+        }
+        "ES256" | "ES384" => {
+            let (Some(x), Some(y)) = (part(&key.x), part(&key.y)) else {
+                return false;
+            };
+            let algorithm = match (alg, key.crv.as_deref()) {
+                ("ES256", Some("P-256")) => &signature::ECDSA_P256_SHA256_FIXED,
+                ("ES384", Some("P-384")) => &signature::ECDSA_P384_SHA384_FIXED,
+                _ => return false,
+            };
+            // Uncompressed point
+            let point = [&[4u8][..], &x, &y].concat();
+            UnparsedPublicKey::new(algorithm, point)
+                .verify(message, signature)
+                .is_ok()
+        }
+        "EdDSA" if key.crv.as_deref() == Some("Ed25519") => part(&key.x).is_some_and(|x| {
+            UnparsedPublicKey::new(&signature::ED25519, x)
+                .verify(message, signature)
+                .is_ok()
+        }),
+        _ => false,
+    }
+}
+
+fn sso_user(claims: &Claims, prefix: &str) -> SsoUser {
+    let mut granted: Vec<&str> = claims
+        .scope
+        .as_deref()
+        .map(|s| s.split_whitespace().collect())
+        .unwrap_or_default();
+    if let Some(scp) = &claims.scp {
+        granted.extend(scp.values());
+    }
+    let granted: Vec<&str> = granted
+        .into_iter()
+        .filter_map(|scope| scope.strip_prefix(prefix))
+        .collect();
+    let mut scopes: Vec<TokenScope> = Vec::new();
+    for name in &granted {
+        let scope = serde_json::from_value(serde_json::Value::from(*name)).ok();
+        if let Some(scope) = scope.filter(|s| !scopes.contains(s)) {
+            scopes.push(scope);
+        }
+    }
+    SsoUser {
+        subject: claims.sub.clone(),
+        name: claims
+            .preferred_username
+            .clone()
+            .or_else(|| claims.email.clone())
+            .unwrap_or_else(|| claims.sub.clone()),
+        scopes,
+        admin: granted.contains(&"admin"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+    use serde_json::json;
+
+    fn config() -> OidcConfig {
+        OidcConfig {
+            issuer: "https://sso.example.com/realms/dev/".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A P-256 key of the provider and its JWK.
+    fn provider_key() -> (EcdsaKeyPair, Jwk) {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+        let point = pair.public_key().as_ref();
+        let jwk = Jwk {
+            kty: "EC".to_string(),
+            kid: Some("k1".to_string()),
+            alg: None,
+            n: None,
+            e: None,
+            crv: Some("P-256".to_string()),
+            x: Some(B64.encode(&point[1..33])),
+            y: Some(B64.encode(&point[33..])),
+        };
+        (pair, jwk)
+    }
+
+    fn sign(pair: &EcdsaKeyPair, header: serde_json::Value, claims: serde_json::Value) -> String {
+        let signed = format!(
+            "{}.{}",
+            B64.encode(header.to_string()),
+            B64.encode(claims.to_string())
+        );
+        let signature = pair.sign(&SystemRandom::new(), signed.as_bytes()).unwrap();
+        format!("{signed}.{}", B64.encode(signature.as_ref()))
+    }
+
+    fn verifier(keys: Vec<Jwk>) -> OidcVerifier {
+        let verifier = OidcVerifier::new(&config(), reqwest::Client::new()).unwrap();
+        // Fetched and tried just now: the tests never reach the network
+        *verifier.cache.lock().unwrap() = KeyCache {
+            keys,
+            fetched_at: Some(Instant::now()),
+            tried_at: Some(Instant::now()),
+        };
+        verifier
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let (pair, jwk) = provider_key();
+        let verifier = verifier(vec![jwk]);
+        let header = json!({ "alg": "ES256", "kid": "k1", "typ": "JWT" });
+        let claims = |aud: serde_json::Value, exp: u64| {
+            json!({
+                "iss": "https://sso.example.com/realms/dev",
+                "sub": "u-42",
+                "aud": aud,
+                "exp": exp,
+                "scope": "openid nomadflow:features nomadflow:readOnly other:terminal",
+                "preferred_username": "alice",
+            })
+        };
+
+        let token = sign(
+            &pair,
+            header.clone(),
+            claims(json!(["web", "nomadflow"]), 2_000),
+        );
+        assert!(looks_like_jwt(&token));
+        assert_eq!(
+            verifier.verify(&token, 1_000).await,
+            Some(SsoUser {
+                subject: "u-42".to_string(),
+                name: "alice".to_string(),
+                scopes: vec![TokenScope::Features, TokenScope::ReadOnly],
+                admin: false,
+            })
+        );
+        // Expired, for another audience, or tampered with
+        assert!(verifier.verify(&token, 3_000).await.is_none());
+        let other = sign(&pair, header.clone(), claims(json!("web"), 2_000));
+        assert!(verifier.verify(&other, 1_000).await.is_none());
+        let (signed, signature) = token.rsplit_once('.').unwrap();
+        let forged_claims = B64.encode(claims(json!("nomadflow"), 9_000).to_string());
+        let forged = format!(
+            "{}.{forged_claims}.{signature}",
+            signed.split_once('.').unwrap().0
+        );
+        assert!(verifier.verify(&forged, 1_000).await.is_none());
+        // Unsigned
+        let none = format!(
+            "{}.{}.",
+            B64.encode(json!({ "alg": "none" }).to_string()),
+            B64.encode(claims(json!("nomadflow"), 2_000).to_string())
+        );
+        assert!(verifier.verify(&none, 1_000).await.is_none());
+    }
+
+    #[test]
+    fn test_sso_user_scopes() {
+        let claims: Claims = serde_json::from_value(json!({
+            "iss": "i", "sub": "u-1", "aud": "nomadflow", "exp": 1,
+            "scp": ["nomadflow:terminal", "nomadflow:admin", "nomadflow:unknown"],
+            "email": "bob@example.com",
+        }))
+        .unwrap();
+        let user = sso_user(&claims, "nomadflow:");
+        assert_eq!(user.name, "bob@example.com");
+        assert_eq!(user.scopes, [TokenScope::Terminal]);
+        assert!(user.admin);
+    }
+
+    #[test]
+    fn test_find_key() {
+        let (_, jwk) = provider_key();
+        let header = |alg: &str, kid: Option<&str>| Header {
+            alg: alg.to_string(),
+            kid: kid.map(str::to_string),
+        };
+        let keys = vec![jwk];
+        assert!(find_key(&keys, &header("ES256", Some("k1"))).is_some());
+        assert!(find_key(&keys, &header("ES256", None)).is_some());
+        assert!(find_key(&keys, &header("ES256", Some("k2"))).is_none());
+        assert!(find_key(&keys, &header("RS256", Some("k1"))).is_none());
+        assert!(find_key(&keys, &header("HS256", None)).is_none());
+    }
+}
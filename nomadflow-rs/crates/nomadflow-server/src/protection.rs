@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use nomadflow_core::config::ForgeConfig;
+use nomadflow_core::forge::ForgeRepo;
+
+/// How long the protected branches of a forge repo are trusted.
+const TTL: Duration = Duration::from_secs(600);
+
+/// Protected branches of the forge repos behind remotes, asked for when a
+/// token is configured and kept for [`TTL`].
+#[derive(Default)]
+pub struct ProtectedBranches {
+    by_remote: Mutex<HashMap<String, (Instant, Vec<String>)>>,
+}
+
+impl ProtectedBranches {
+    /// Protected branches of the repo `remote_url` points at. Empty when it
+    /// is on no known forge, there is no token for it, or it cannot be
+    /// reached: protection must not get in the way of working offline.
+    pub async fn get(&self, forge: &ForgeConfig, remote_url: &str) -> Vec<String> {
+        if let Some((at, branches)) = self.by_remote.lock().unwrap().get(remote_url) {
+            if at.elapsed() < TTL {
+                return branches.clone();
+            }
+        }
+        let Some(repo) = ForgeRepo::from_remote_url(remote_url) else {
+            return Vec::new();
+        };
+        let Some(token) = forge.token(repo.kind) else {
+            return Vec::new();
+        };
+        let branches = match repo.protected_branches(token).await {
+            Ok(branches) => branches,
+            Err(e) => {
+                tracing::warn!("Protected branches unknown: {e}");
+                Vec::new()
+            }
+        };
+        self.by_remote
+            .lock()
+            .unwrap()
+            .insert(remote_url.to_string(), (Instant::now(), branches.clone()));
+        branches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cached_then_offline() {
+        let protected = ProtectedBranches::default();
+        let url = "git@github.com:fab/nomadflow.git";
+        protected
+            .by_remote
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), (Instant::now(), vec!["main".to_string()]));
+        assert_eq!(protected.get(&ForgeConfig::default(), url).await, ["main"]);
+
+        // No token for the forge: nothing is asked, nothing is protected
+        let other = "git@github.com:fab/other.git";
+        assert!(protected.get(&ForgeConfig::default(), other).await.is_empty());
+        let unknown = "git@git.example.com:fab/nomadflow.git";
+        assert!(protected.get(&ForgeConfig::default(), unknown).await.is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+//! Built-in terminal: `tmux attach` in a PTY, speaking the ttyd protocol so
+//! that clients cannot tell it from a proxied ttyd.
+
+use std::io::{Read, Write};
+use std::thread;
+
+use futures_util::Stream;
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_util::sync::PollSender;
+use tracing::{debug, info};
+
+use nomadflow_core::error::{NomadError, Result};
+
+/// Messages queued towards the PTY before the client is slowed down.
+const INPUT_QUEUE: usize = 64;
+/// Output chunks queued towards the client before the PTY is no longer read.
+const OUTPUT_QUEUE: usize = 64;
+/// Bytes read from the PTY at once.
+const READ_CHUNK: usize = 8192;
+
+/// Size of the PTY until the client reports its own.
+const DEFAULT_SIZE: PtySize = PtySize {
+    rows: 24,
+    cols: 80,
+    pixel_width: 0,
+    pixel_height: 0,
+};
+
+/// Size a client reports, in its handshake or when resizing.
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+struct TerminalSize {
+    columns: u16,
+    rows: u16,
+}
+
+impl TerminalSize {
+    fn pty_size(&self) -> Option<PtySize> {
+        (self.columns > 0 && self.rows > 0).then_some(PtySize {
+            rows: self.rows,
+            cols: self.columns,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+    }
+}
+
+/// A message of the ttyd protocol sent by the client.
+#[derive(Debug, PartialEq, Eq)]
+enum ClientMessage<'a> {
+    /// Keystrokes for the terminal.
+    Input(&'a [u8]),
+    /// The terminal was resized.
+    Resize(TerminalSize),
+    /// The JSON handshake opening the session. The token in it was already
+    /// checked on the upgrade.
+    Handshake(TerminalSize),
+    /// Flow control and anything newer, which the throttled bridge makes
+    /// needless.
+    Ignored,
+}
+
+impl<'a> ClientMessage<'a> {
+    fn parse(data: &'a [u8]) -> Self {
+        let Some((&command, payload)) = data.split_first() else {
+            return Self::Ignored;
+        };
+        let size = |json: &[u8]| serde_json::from_slice::<TerminalSize>(json).ok();
+        match command {
+            b'0' => Self::Input(payload),
+            b'1' => size(payload).map_or(Self::Ignored, Self::Resize),
+            b'{' => size(data).map_or(Self::Ignored, Self::Handshake),
+            _ => Self::Ignored,
+        }
+    }
+}
+
+/// Attach to `session` in a new PTY. Returns the halves to exchange ttyd
+/// messages through: the session ends when either is dropped or tmux exits.
+pub fn attach(
+    session: &str,
+) -> Result<(
+    PollSender<Message>,
+    impl Stream<Item = std::result::Result<Message, tungstenite::Error>>,
+)> {
+    let pair = native_pty_system()
+        .openpty(DEFAULT_SIZE)
+        .map_err(|e| failed("Failed to open a PTY", e))?;
+    let mut cmd = CommandBuilder::new("tmux");
+    cmd.args(["attach-session", "-t", session]);
+    cmd.env("TERM", "xterm-256color");
+    let child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| failed("Failed to attach tmux in a PTY", e))?;
+    // The child holds its own copy; ours would keep the PTY open after it exits
+    drop(pair.slave);
+
+    let reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| failed("Failed to read the PTY", e))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| failed("Failed to write the PTY", e))?;
+
+    let (output_tx, output_rx) = mpsc::channel(OUTPUT_QUEUE);
+    let (input_tx, input_rx) = mpsc::channel(INPUT_QUEUE);
+
+    let reading = output_tx.clone();
+    thread::spawn(move || read_output(reader, reading));
+    let title = format!("1{session}");
+    thread::spawn(move || write_input(input_rx, pair.master, writer, child, output_tx, title));
+
+    info!(%session, "Native terminal attached");
+    let output = futures_util::stream::unfold(output_rx, |mut rx| async move {
+        rx.recv().await.map(|msg| (Ok(msg), rx))
+    });
+    Ok((PollSender::new(input_tx), output))
+}
+
+fn failed(what: &str, e: impl std::fmt::Display) -> NomadError {
+    NomadError::CommandFailed(format!("{what}: {e}"))
+}
+
+/// Forward PTY output as ttyd output messages until tmux exits or the client
+/// is gone.
+fn read_output(mut reader: Box<dyn Read + Send>, output: mpsc::Sender<Message>) {
+    let mut buf = vec![0u8; READ_CHUNK + 1];
+    buf[0] = b'0';
+    loop {
+        let n = match reader.read(&mut buf[1..]) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        let msg = Message::Binary(buf[..=n].to_vec().into());
+        if output.blocking_send(msg).is_err() {
+            break;
+        }
+    }
+    debug!("Native terminal output ended");
+}
+
+/// Apply client messages to the PTY until the client is gone, then detach.
+fn write_input(
+    mut input: mpsc::Receiver<Message>,
+    master: Box<dyn MasterPty + Send>,
+    mut writer: Box<dyn Write + Send>,
+    mut child: Box<dyn Child + Send + Sync>,
+    output: mpsc::Sender<Message>,
+    title: String,
+) {
+    let resize = |size: &TerminalSize| {
+        if let Some(size) = size.pty_size() {
+            let _ = master.resize(size);
+        }
+    };
+    while let Some(msg) = input.blocking_recv() {
+        let data = match &msg {
+            Message::Text(text) => text.as_bytes(),
+            Message::Binary(data) => &data[..],
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        match ClientMessage::parse(data) {
+            ClientMessage::Input(keys) => {
+                if writer
+                    .write_all(keys)
+                    .and_then(|()| writer.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            ClientMessage::Resize(size) => resize(&size),
+            ClientMessage::Handshake(size) => {
+                resize(&size);
+                // What ttyd answers a handshake with
+                let _ = output.blocking_send(Message::Binary(title.clone().into_bytes().into()));
+                let _ = output.blocking_send(Message::Binary(b"2{}".to_vec().into()));
+            }
+            ClientMessage::Ignored => {}
+        }
+    }
+    // Killing the client only detaches it, the session lives on
+    let _ = child.kill();
+    let _ = child.wait();
+    info!("Native terminal detached");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_client_messages() {
+        assert_eq!(
+            ClientMessage::parse(b"0ls\r"),
+            ClientMessage::Input(b"ls\r")
+        );
+        assert_eq!(
+            ClientMessage::parse(br#"1{"columns":120,"rows":40}"#),
+            ClientMessage::Resize(TerminalSize {
+                columns: 120,
+                rows: 40
+            })
+        );
+        assert_eq!(
+            ClientMessage::parse(br#"{"AuthToken":"","columns":80,"rows":24}"#),
+            ClientMessage::Handshake(TerminalSize {
+                columns: 80,
+                rows: 24
+            })
+        );
+        assert_eq!(ClientMessage::parse(b"2"), ClientMessage::Ignored);
+        assert_eq!(ClientMessage::parse(b"1not json"), ClientMessage::Ignored);
+        assert_eq!(ClientMessage::parse(b""), ClientMessage::Ignored);
+    }
+
+    #[test]
+    fn test_empty_size_is_not_applied() {
+        assert!(TerminalSize {
+            columns: 0,
+            rows: 24
+        }
+        .pty_size()
+        .is_none());
+        assert_eq!(
+            TerminalSize {
+                columns: 100,
+                rows: 30
+            }
+            .pty_size()
+            .map(|s| (s.cols, s.rows)),
+            Some((100, 30))
+        );
+    }
+}
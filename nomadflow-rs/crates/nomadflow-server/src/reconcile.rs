@@ -0,0 +1,176 @@
+//! Consistency check between git worktrees, the worktrees directory and the
+//! tmux session, behind `POST /api/reconcile`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use nomadflow_core::gc::{canonical, unregistered_dirs};
+use nomadflow_core::models::{ReconcileIssue, ReconcileIssueKind};
+
+use crate::state::AppState;
+use crate::windows::orphan_windows;
+
+/// Report the inconsistencies of every repo and, with `fix`, repair them:
+/// orphaned directories are re-registered with `git worktree repair`,
+/// prunable worktrees pruned, missing windows opened and orphaned windows
+/// closed when idle. Features whose upstream is gone are only reported.
+pub async fn reconcile(state: &AppState, fix: bool) -> Vec<ReconcileIssue> {
+    let mut issues = Vec::new();
+    let Ok(repos) = state.git.list_repos().await else {
+        return issues;
+    };
+    let worktrees_dir = state.settings.worktrees_dir();
+    let windows = state.tmux.list_windows().await;
+    let window_names: HashSet<&str> = windows.iter().map(|w| w.name.as_str()).collect();
+    let mut live_features: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut repo_paths: HashMap<String, String> = HashMap::new();
+
+    for repo in repos {
+        let Ok(registered) = state.git.registered_worktrees(&repo.path).await else {
+            continue;
+        };
+        let mut issue = |kind, target: &Path, fixed| {
+            issues.push(ReconcileIssue {
+                kind,
+                repo_path: repo.path.clone(),
+                target: target.to_string_lossy().to_string(),
+                fixed,
+            })
+        };
+
+        let prunable: Vec<&str> = registered
+            .iter()
+            .filter(|(_, prunable)| *prunable)
+            .map(|(path, _)| path.as_str())
+            .collect();
+        let pruned = fix && !prunable.is_empty() && state.git.prune_worktrees(&repo.path).await;
+        for path in &prunable {
+            issue(ReconcileIssueKind::PrunableWorktree, Path::new(path), pruned);
+        }
+
+        let known: HashSet<PathBuf> = registered
+            .iter()
+            .map(|(path, _)| canonical(Path::new(path)))
+            .collect();
+        let orphaned = unregistered_dirs(&worktrees_dir.join(&repo.name), &known);
+        for dir in &orphaned {
+            let repaired = fix
+                && state
+                    .git
+                    .repair_worktree(&repo.path, &dir.to_string_lossy())
+                    .await;
+            issue(ReconcileIssueKind::OrphanedDir, dir, repaired);
+        }
+
+        // Features backed by a worktree git knows about and that still exists
+        let Ok(features) = state.git.list_features(&repo.path).await else {
+            continue;
+        };
+        let features: Vec<_> = features
+            .into_iter()
+            .filter(|f| {
+                let path = canonical(Path::new(&f.worktree_path));
+                known.contains(&path) && !prunable.iter().any(|p| canonical(Path::new(p)) == path)
+            })
+            .collect();
+
+        for feature in features.iter().filter(|f| !f.is_main) {
+            let name = state.window_names.window_name(&repo.path, &feature.name);
+            if window_names.contains(name.as_str()) {
+                continue;
+            }
+            let opened = fix
+                && state
+                    .tmux
+                    .ensure_window(&name, Some(&feature.worktree_path))
+                    .await
+                    .is_ok();
+            issue(ReconcileIssueKind::MissingWindow, Path::new(&name), opened);
+        }
+
+        // The track of an upstream is only unknown once it is gone
+        for feature in features.iter().filter(|f| !f.is_main) {
+            if feature.upstream.is_some() && feature.ahead.is_none() {
+                issue(
+                    ReconcileIssueKind::GoneUpstream,
+                    Path::new(&feature.worktree_path),
+                    false,
+                );
+            }
+        }
+
+        live_features.insert(
+            repo.name.clone(),
+            features.into_iter().map(|f| f.name).collect(),
+        );
+        repo_paths.insert(repo.name.clone(), repo.path.clone());
+    }
+
+    for name in orphan_windows(&windows, &live_features) {
+        let repo_name = name.split_once(':').map(|(repo, _)| repo).unwrap_or_default();
+        let closed =
+            fix && state.tmux.is_shell_idle(name).await && state.tmux.kill_window(name).await;
+        issues.push(ReconcileIssue {
+            kind: ReconcileIssueKind::OrphanedWindow,
+            repo_path: repo_paths.get(repo_name).cloned().unwrap_or_default(),
+            target: name.to_string(),
+            fixed: closed,
+        });
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reconcile_reports_and_prunes() {
+        use nomadflow_core::shell::run;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = nomadflow_core::config::Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        settings.tmux.session = format!("nomadflow-reconcile-test-{}", std::process::id());
+        settings.ensure_directories().unwrap();
+        let repo = settings.repos_dir().join("app");
+        std::fs::create_dir_all(&repo).unwrap();
+        let repo = repo.to_string_lossy().to_string();
+        run("git init", Some(&repo)).await;
+        run("git commit --allow-empty -m init", Some(&repo)).await;
+
+        let worktrees = settings.worktrees_dir().join("app");
+        let gone = worktrees.join("gone");
+        run(
+            &format!("git worktree add -b feature/gone \"{}\"", gone.display()),
+            Some(&repo),
+        )
+        .await;
+        std::fs::remove_dir_all(&gone).unwrap();
+        std::fs::create_dir_all(worktrees.join("stray")).unwrap();
+
+        let state = AppState::new(settings);
+        let kinds = |issues: &[ReconcileIssue]| {
+            let mut kinds: Vec<_> = issues.iter().map(|i| (i.kind, i.fixed)).collect();
+            kinds.sort_by_key(|(kind, _)| *kind as u8);
+            kinds
+        };
+        assert_eq!(
+            kinds(&reconcile(&state, false).await),
+            [
+                (ReconcileIssueKind::OrphanedDir, false),
+                (ReconcileIssueKind::PrunableWorktree, false)
+            ]
+        );
+
+        let fixed = reconcile(&state, true).await;
+        assert!(fixed
+            .iter()
+            .any(|i| i.kind == ReconcileIssueKind::PrunableWorktree && i.fixed));
+        assert_eq!(
+            kinds(&reconcile(&state, false).await),
+            [(ReconcileIssueKind::OrphanedDir, false)]
+        );
+    }
+}
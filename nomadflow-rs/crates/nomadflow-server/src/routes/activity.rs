@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use axum::{extract::State, routing::post, Json, Router};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{
+    ActivityAction, ListActivityRequest, ListActivityResponse, UndoRequest, UndoResponse,
+};
+
+use crate::activity::UndoAction;
+use crate::state::AppState;
+
+const DEFAULT_LIMIT: usize = 20;
+
+async fn list_activity(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ListActivityRequest>,
+) -> Json<ListActivityResponse> {
+    let mut page = request.page;
+    page.limit = Some(page.limit.unwrap_or(DEFAULT_LIMIT));
+    let (entries, total) = page.apply(state.activity.recent(usize::MAX), |e| &e.feature_name);
+    Json(ListActivityResponse { entries, total })
+}
+
+/// Revert a previously recorded operation.
+async fn undo(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<UndoRequest>,
+) -> Result<Json<UndoResponse>, ApiError> {
+    let Some((entry, action)) = state.activity.take_undo(request.id) else {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            "Nothing to undo for this entry",
+        ));
+    };
+
+    let outcome = match &action {
+        UndoAction::RestoreBranch { branch, sha } => {
+            match state
+                .git
+                .restore_branch(&entry.repo_path, branch, sha)
+                .await
+            {
+                Ok((worktree_path, _)) => {
+                    open_window(&state, &entry.repo_path, &worktree_path).await;
+                    Ok(format!("Restored {branch}"))
+                }
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        UndoAction::ReattachBranch { branch } => {
+            match state.git.attach_branch(&entry.repo_path, branch).await {
+                Ok((worktree_path, _)) => {
+                    open_window(&state, &entry.repo_path, &worktree_path).await;
+                    Ok(format!("Re-attached {branch}"))
+                }
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        UndoAction::SwitchBack {
+            repo_path,
+            feature_name,
+        } => switch_back(&state, repo_path, feature_name)
+            .await
+            .inspect(|_| {
+                // Keep the log's notion of the current feature accurate for the next undo
+                state
+                    .activity
+                    .record(ActivityAction::SwitchFeature, repo_path, feature_name, None);
+            }),
+    };
+
+    match outcome {
+        Ok(detail) => {
+            state.activity.record(
+                ActivityAction::Undo,
+                &entry.repo_path,
+                &entry.feature_name,
+                None,
+            );
+            Ok(Json(UndoResponse {
+                undone: true,
+                detail,
+            }))
+        }
+        Err(detail) => {
+            state.activity.restore_undo(entry.id, action);
+            Err(ApiError::internal(detail))
+        }
+    }
+}
+
+/// Recreate the tmux window of a restored worktree.
+async fn open_window(state: &AppState, repo_path: &str, worktree_path: &str) {
+    let wt_name = std::path::Path::new(worktree_path)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    if state.tmux.ensure_session().await.is_ok() {
+        let win_name = state.window_names.window_name(repo_path, &wt_name);
+        if let Err(e) = state
+            .tmux
+            .ensure_window(&win_name, Some(worktree_path))
+            .await
+        {
+            tracing::warn!("Failed to recreate window '{win_name}': {e}");
+        }
+    }
+}
+
+async fn switch_back(
+    state: &AppState,
+    repo_path: &str,
+    feature_name: &str,
+) -> Result<String, String> {
+    let features = state
+        .git
+        .list_features(repo_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let feature = features
+        .iter()
+        .find(|f| f.name == feature_name)
+        .ok_or_else(|| format!("Feature '{feature_name}' no longer exists"))?;
+
+    state
+        .tmux
+        .ensure_session()
+        .await
+        .map_err(|e| e.to_string())?;
+    let win_name = state.window_names.window_name(repo_path, feature_name);
+    let (switched, _) = state
+        .tmux
+        .switch_to_window(&win_name, Some(&feature.worktree_path))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !switched {
+        return Err(format!("Failed to switch to window '{win_name}'"));
+    }
+
+    Ok(format!("Switched back to {feature_name}"))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/list-activity", post(list_activity))
+        .route("/api/undo", post(undo))
+}
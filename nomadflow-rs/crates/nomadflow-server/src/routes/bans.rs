@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get},
+    Json, Router,
+};
+use tracing::info;
+
+use nomadflow_core::models::{ActivityAction, DeleteBansResponse, ListBansResponse};
+
+use crate::shares::now_secs;
+use crate::state::AppState;
+
+async fn list_bans(State(state): State<Arc<AppState>>) -> Json<ListBansResponse> {
+    let bans = state.bans.list(now_secs());
+    let total = bans.len();
+    Json(ListBansResponse { bans, total })
+}
+
+/// Lift every active ban.
+async fn clear_bans(State(state): State<Arc<AppState>>) -> Json<DeleteBansResponse> {
+    Json(DeleteBansResponse {
+        removed: unban(&state, None),
+    })
+}
+
+/// Lift the ban on one IP.
+async fn delete_ban(
+    State(state): State<Arc<AppState>>,
+    Path(ip): Path<String>,
+) -> Json<DeleteBansResponse> {
+    Json(DeleteBansResponse {
+        removed: unban(&state, Some(&ip)),
+    })
+}
+
+fn unban(state: &AppState, ip: Option<&str>) -> usize {
+    let lifted = state.bans.unban(ip, now_secs());
+    for ip in &lifted {
+        info!(%ip, "Ban lifted");
+        state.activity.record(ActivityAction::UnbanIp, "", ip, None);
+    }
+    lifted.len()
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/bans", get(list_bans).delete(clear_bans))
+        .route("/api/bans/{ip}", delete(delete_ban))
+}
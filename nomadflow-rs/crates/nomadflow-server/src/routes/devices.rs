@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use tracing::{info, warn};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{
+    ForgetDeviceRequest, ForgetDeviceResponse, ListDevicesRequest, ListDevicesResponse,
+    VerifyOtpRequest, VerifyOtpResponse,
+};
+
+use crate::auth::AuthFailure;
+use crate::shares::now_secs;
+use crate::state::AppState;
+use crate::totp::OtpError;
+
+/// Trust period of a device verified without "remember this device".
+const SESSION_DAYS: u64 = 1;
+
+/// Exchange a TOTP code for a device token.
+async fn verify_otp(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<VerifyOtpRequest>,
+) -> Result<Response, Response> {
+    let secret = &state.settings.auth.totp_secret;
+    if secret.is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "TOTP is not enabled on this server",
+        )
+        .into_response());
+    }
+
+    let now = now_secs();
+    let checked = state
+        .otp_guard
+        .lock()
+        .unwrap()
+        .check(secret, &request.code, now);
+    if let Err(e) = checked {
+        warn!("TOTP verification failed: {e:?}");
+        let error = match e {
+            OtpError::Invalid => ApiError::new(ErrorCode::InvalidOtp, "Invalid code"),
+            OtpError::Reused => ApiError::new(
+                ErrorCode::InvalidOtp,
+                "Code already used, wait for the next one",
+            ),
+            OtpError::Locked(secs) => ApiError::new(
+                ErrorCode::TooManyRequests,
+                format!("Too many attempts, retry in {secs}s"),
+            )
+            .with("retryAfterSecs", secs),
+        };
+        let mut response = error.into_response();
+        if e == OtpError::Invalid {
+            response.extensions_mut().insert(AuthFailure);
+        }
+        return Err(response);
+    }
+
+    let days = if request.remember {
+        u64::from(state.settings.auth.totp_remember_days)
+    } else {
+        SESSION_DAYS
+    };
+    let name = request
+        .device_name
+        .unwrap_or_else(|| "unnamed device".to_string());
+    let (device_token, device) = state.devices.remember(&name, days, now);
+    info!(id = %device.id, name = %device.name, "Device verified with TOTP");
+
+    let response = VerifyOtpResponse {
+        device_token,
+        device,
+    };
+    Ok(state.identity.signed_json(&headers, &response))
+}
+
+async fn list_devices(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ListDevicesRequest>,
+) -> Json<ListDevicesResponse> {
+    let (devices, total) = request
+        .page
+        .apply(state.devices.list(now_secs()), |d| &d.name);
+    Json(ListDevicesResponse { devices, total })
+}
+
+/// Stop trusting a device; it has to enter a TOTP code again.
+async fn forget_device(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ForgetDeviceRequest>,
+) -> Json<ForgetDeviceResponse> {
+    Json(ForgetDeviceResponse {
+        forgotten: state.devices.forget(&request.id),
+    })
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/verify-otp", post(verify_otp))
+        .route("/api/list-devices", post(list_devices))
+        .route("/api/forget-device", post(forget_device))
+}
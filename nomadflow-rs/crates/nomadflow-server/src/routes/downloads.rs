@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::Method, routing::post, Extension, Json, Router};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{DownloadUrlRequest, DownloadUrlResponse};
+
+use crate::auth::Caller;
+use crate::downloads::{is_download_path, sign, DEFAULT_TTL_SECS, MAX_TTL_SECS};
+use crate::shares::now_secs;
+use crate::state::AppState;
+
+/// Sign a URL reaching a download without credentials, for a while.
+async fn create_download_url(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<Caller>>,
+    Json(request): Json<DownloadUrlRequest>,
+) -> Result<Json<DownloadUrlResponse>, ApiError> {
+    let ttl = request.ttl_secs.unwrap_or(DEFAULT_TTL_SECS);
+    if ttl == 0 || ttl > MAX_TTL_SECS {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            format!("ttlSecs must be between 1 and {MAX_TTL_SECS}"),
+        ));
+    }
+    if !is_download_path(&request.path) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            format!(
+                "'{}' cannot be downloaded through a signed URL",
+                request.path
+            ),
+        ));
+    }
+    // The URL must not reach more than the caller's own credential
+    if let Some(Extension(caller)) = caller {
+        if !caller.may(&Method::GET, &request.path) {
+            return Err(ApiError::new(
+                ErrorCode::Forbidden,
+                format!("Not allowed to download '{}'", request.path),
+            ));
+        }
+    }
+
+    let expires_at = now_secs() + ttl;
+    let url = sign(&state.settings.auth.secret, &request.path, expires_at);
+    Ok(Json(DownloadUrlResponse { url, expires_at }))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/download-url", post(create_download_url))
+}
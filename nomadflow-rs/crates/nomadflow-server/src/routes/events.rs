@@ -0,0 +1,45 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use futures_util::Stream;
+use tokio::sync::broadcast::error::RecvError;
+
+use nomadflow_core::models::ServerEvent;
+
+use crate::state::AppState;
+
+/// The event as SSE, named after its `type`.
+fn sse_event(event: &ServerEvent) -> Event {
+    let data = serde_json::to_value(event).unwrap_or_default();
+    let name = data["type"].as_str().unwrap_or("message").to_string();
+    Event::default().event(name).data(data.to_string())
+}
+
+/// Stream of state changes. A client too slow to keep up gets a `lagged`
+/// event with the number of events it missed, and should reload its data.
+async fn events(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.events.subscribe();
+    let stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let event = match receiver.recv().await {
+            Ok(event) => sse_event(&event),
+            Err(RecvError::Lagged(missed)) => {
+                Event::default().event("lagged").data(missed.to_string())
+            }
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(event), receiver))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/events", get(events))
+}
@@ -1,39 +1,133 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use axum::{
-    extract::State,
-    http::StatusCode,
-    routing::post,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::Response,
+    routing::{get, post},
     Json, Router,
 };
-use serde_json::{json, Value};
-
 use nomadflow_core::models::{
-    AttachBranchRequest, AttachBranchResponse, CreateFeatureRequest, CreateFeatureResponse,
-    DeleteFeatureRequest, DeleteFeatureResponse, ListBranchesRequest, ListBranchesResponse,
-    ListFeaturesRequest, ListFeaturesResponse, SwitchFeatureRequest, SwitchFeatureResponse,
+    ActivityAction, AttachBranchRequest, AttachBranchResponse, BulkFeatureAction, BulkFeatureResult,
+    BulkFeaturesRequest, BulkFeaturesResponse, CommitRequest, CommitResponse, CreateFeatureRequest,
+    CreateFeatureResponse, CreatePrRequest, CreatePrResponse, DeleteFeatureRequest,
+    DeleteFeatureResponse, DiffParams, DiffSummary, Feature, FeatureDiff, FeatureNotesRequest,
+    FeatureNotesResponse, ForgeKind, JobKind, ListBranchesRequest, ListBranchesResponse,
+    ListFeaturesRequest, ListFeaturesResponse, PageParams, ProtectedBranchesRequest,
+    ProtectedBranchesResponse, PullFeatureRequest, PullFeatureResponse, PushRequest, PushResponse,
+    QuickAttachParams, QuickAttachResponse, ResolveConflictsRequest, ResolveConflictsResponse,
+    ServerEvent, SuggestBranchNameRequest, SuggestBranchNameResponse, SwitchFeatureRequest,
+    SwitchFeatureResponse, ValidateBranchRequest, ValidateBranchResponse,
 };
-use nomadflow_core::services::tmux::window_name;
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::branch::{check_branch_name, name_from_description, suggest_branch_names};
+use nomadflow_core::commits::{load_commit_rules, CommitParts};
+use nomadflow_core::error::NomadError;
+use nomadflow_core::forge::{is_protected, ForgeRepo, PullRequest};
+use nomadflow_core::hooks::{hook_command, HookEvent};
+use nomadflow_core::services::git::{resolve_conflicts_command, CommitOutcome, PushOutcome};
+use nomadflow_core::shell::{run_command, shell_quote};
+use nomadflow_core::worktree_files::{copy_matching, load_copy_patterns};
 
+use crate::activity::UndoAction;
+use crate::downloads::attachment;
+use crate::etag::json_with_etag;
+use crate::notes::MAX_NOTE_LEN;
+use crate::routes::repos::find_repo;
+use crate::shares::now_secs;
 use crate::state::AppState;
+use crate::suspend;
 
 async fn list_features(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<ListFeaturesRequest>,
-) -> Result<Json<ListFeaturesResponse>, (StatusCode, Json<Value>)> {
-    match state.git.list_features(&request.repo_path).await {
-        Ok(features) => Ok(Json(ListFeaturesResponse { features })),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "detail": e.to_string() })),
-        )),
+) -> Result<Response, ApiError> {
+    features_page(&state, &headers, &request.repo_path, request.page).await
+}
+
+/// `GET /api/repos/{name}/features`: [`list_features`] addressed by repo name.
+async fn get_features(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Query(page): Query<PageParams>,
+) -> Result<Response, ApiError> {
+    let repo = find_repo(&state, &name).await?;
+    features_page(&state, &headers, &repo.path, page).await
+}
+
+async fn features_page(
+    state: &AppState,
+    headers: &HeaderMap,
+    repo_path: &str,
+    page: PageParams,
+) -> Result<Response, ApiError> {
+    let mut features = state.git.list_features(repo_path).await?;
+
+    // Flag features whose window produced output since it was last viewed
+    let active_windows: HashSet<String> = state
+        .tmux
+        .list_windows()
+        .await
+        .into_iter()
+        .filter(|w| w.activity)
+        .map(|w| w.name)
+        .collect();
+    for f in &mut features {
+        let name = state.window_names.window_name(repo_path, &f.name);
+        f.has_unread = active_windows.contains(&name);
+        f.suspended = state.suspender.is_suspended(&name);
     }
+
+    let (features, total) = page.apply(features, |f| &f.name);
+    Ok(json_with_etag(
+        headers,
+        &ListFeaturesResponse { features, total },
+    ))
 }
 
+/// How long the `pre_delete` hook may run before the deletion is given up.
+const PRE_DELETE_HOOK_TIMEOUT_SECS: f64 = 300.0;
+
 async fn create_feature(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<CreateFeatureRequest>,
-) -> Result<Json<CreateFeatureResponse>, (StatusCode, Json<Value>)> {
+    Json(mut request): Json<CreateFeatureRequest>,
+) -> Result<Json<CreateFeatureResponse>, ApiError> {
+    let description = request
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty());
+    if request.branch_name.is_empty() {
+        if let Some(description) = description {
+            let taken = state.git.branch_names(&request.repo_path).await;
+            let name = name_from_description(description, None, &taken).ok_or_else(|| {
+                ApiError::new(
+                    ErrorCode::InvalidRequest,
+                    "No branch name can be made from the description",
+                )
+            })?;
+            request.branch_name = name;
+        }
+    }
+    if let Err(problem) = check_branch_name(&request.branch_name) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidBranchName,
+            format!("Invalid branch name '{}': {problem}", request.branch_name),
+        )
+        .with("suggestions", suggest_branch_names(&request.branch_name)));
+    }
+
+    check_unprotected(
+        &state,
+        &request.repo_path,
+        &request.branch_name,
+        request.allow_protected,
+    )
+    .await?;
+
     let base_branch = if request.base_branch == "main" {
         None
     } else {
@@ -44,12 +138,7 @@ async fn create_feature(
         .git
         .create_feature(&request.repo_path, &request.branch_name, base_branch)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(json!({ "detail": e.to_string() })),
-            )
-        })?;
+        .map_err(worktree_error)?;
 
     // Derive the worktree name for tmux window naming
     let wt_name = std::path::Path::new(&worktree_path)
@@ -59,24 +148,32 @@ async fn create_feature(
         .to_string();
 
     // Ensure tmux session and window
-    state.tmux.ensure_session().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "detail": e.to_string() })),
-        )
-    })?;
+    state
+        .tmux
+        .ensure_session()
+        .await
+        .map_err(|e| ApiError::new(ErrorCode::TmuxUnavailable, e.to_string()))?;
 
-    let win_name = window_name(&request.repo_path, &wt_name);
+    let win_name = state.window_names.window_name(&request.repo_path, &wt_name);
     state
         .tmux
         .ensure_window(&win_name, Some(&worktree_path))
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "detail": e.to_string() })),
-            )
-        })?;
+        .await?;
+
+    set_up_worktree(&state, &request.repo_path, &win_name, &worktree_path).await;
+
+    if let Some(description) = description {
+        state
+            .notes
+            .set(&request.repo_path, &wt_name, description, now_secs());
+    }
+    state
+        .activity
+        .record(ActivityAction::CreateFeature, &request.repo_path, &wt_name, None);
+    state.events.publish(ServerEvent::FeatureCreated {
+        repo_path: request.repo_path.clone(),
+        feature_name: wt_name,
+    });
 
     Ok(Json(CreateFeatureResponse {
         worktree_path,
@@ -85,45 +182,72 @@ async fn create_feature(
     }))
 }
 
+/// Make a branch name out of a sentence describing a feature.
+async fn suggest_branch_name(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SuggestBranchNameRequest>,
+) -> Json<SuggestBranchNameResponse> {
+    let taken = state.git.branch_names(&request.repo_path).await;
+    Json(SuggestBranchNameResponse {
+        branch_name: name_from_description(
+            &request.description,
+            request.prefix.as_deref(),
+            &taken,
+        ),
+    })
+}
+
+/// Check a branch name before creating a feature, with names to offer instead.
+async fn validate_branch(
+    Json(request): Json<ValidateBranchRequest>,
+) -> Json<ValidateBranchResponse> {
+    let problem = check_branch_name(&request.branch_name).err();
+    Json(ValidateBranchResponse {
+        valid: problem.is_none(),
+        problem: problem.map(|p| p.to_string()),
+        suggestions: suggest_branch_names(&request.branch_name),
+    })
+}
+
 async fn delete_feature(
     State(state): State<Arc<AppState>>,
     Json(request): Json<DeleteFeatureRequest>,
-) -> Result<Json<DeleteFeatureResponse>, (StatusCode, Json<Value>)> {
+) -> Result<Json<DeleteFeatureResponse>, ApiError> {
     // Prevent deletion of main branch
-    let features = state
-        .git
-        .list_features(&request.repo_path)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "detail": e.to_string() })),
-            )
-        })?;
+    let features = state.git.list_features(&request.repo_path).await?;
 
-    if let Some(f) = features.iter().find(|f| f.name == request.feature_name) {
-        if f.is_main {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(json!({ "detail": "Cannot delete the main repository branch" })),
-            ));
-        }
+    let feature = features.iter().find(|f| f.name == request.feature_name);
+    if feature.is_some_and(|f| f.is_main) {
+        return Err(ApiError::new(
+            ErrorCode::MainWorktree,
+            "Cannot delete the main repository branch",
+        ));
+    }
+    let undo = restore_undo(&state, &request.repo_path, feature).await;
+    if let Some(feature) = feature {
+        run_pre_delete_hook(&state, &feature.worktree_path).await?;
     }
 
     // Kill tmux window if it exists
-    let win_name = window_name(&request.repo_path, &request.feature_name);
+    let win_name = state.window_names.window_name(&request.repo_path, &request.feature_name);
     state.tmux.kill_window(&win_name).await;
 
     let deleted = state
         .git
         .delete_feature(&request.repo_path, &request.feature_name)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "detail": e.to_string() })),
-            )
-        })?;
+        .await?;
+
+    state.notes.remove(&request.repo_path, &request.feature_name);
+    state.activity.record(
+        ActivityAction::DeleteFeature,
+        &request.repo_path,
+        &request.feature_name,
+        undo,
+    );
+    state.events.publish(ServerEvent::FeatureDeleted {
+        repo_path: request.repo_path,
+        feature_name: request.feature_name,
+    });
 
     Ok(Json(DeleteFeatureResponse { deleted }))
 }
@@ -131,108 +255,273 @@ async fn delete_feature(
 async fn switch_feature(
     State(state): State<Arc<AppState>>,
     Json(request): Json<SwitchFeatureRequest>,
-) -> Result<Json<SwitchFeatureResponse>, (StatusCode, Json<Value>)> {
-    let features = state
-        .git
-        .list_features(&request.repo_path)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "detail": e.to_string() })),
-            )
-        })?;
+) -> Result<Json<SwitchFeatureResponse>, ApiError> {
+    let features = state.git.list_features(&request.repo_path).await?;
 
     let feature = features.iter().find(|f| f.name == request.feature_name);
 
-    let worktree_path = if let Some(f) = feature {
-        f.worktree_path.clone()
+    let (worktree_path, created) = if let Some(f) = feature {
+        (f.worktree_path.clone(), false)
     } else {
         // Feature doesn't exist, create it
         let (wt, _branch) = state
             .git
             .create_feature(&request.repo_path, &request.feature_name, None)
             .await
-            .map_err(|e| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({ "detail": e.to_string() })),
-                )
-            })?;
-        wt
+            .map_err(worktree_error)?;
+        (wt, true)
     };
 
-    // Ensure tmux session
-    state.tmux.ensure_session().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "detail": e.to_string() })),
+    let response =
+        switch_to(&state, &request.repo_path, &request.feature_name, &worktree_path).await?;
+    if created {
+        set_up_worktree(
+            &state,
+            &request.repo_path,
+            &response.tmux_window,
+            &worktree_path,
         )
-    })?;
+        .await;
+    } else if !response.has_running_process {
+        // A busy window is left alone when switching back to a feature
+        let window = &response.tmux_window;
+        run_hook_in_window(&state, window, &worktree_path, HookEvent::PostSwitch).await;
+    }
+    Ok(Json(response))
+}
+
+/// Get a new worktree ready to work in: copy the gitignored files the repo
+/// lists from its main worktree, then start its `post_create` hook.
+async fn set_up_worktree(state: &AppState, repo_path: &str, win_name: &str, worktree_path: &str) {
+    let worktree = std::path::Path::new(worktree_path);
+    let copied = load_copy_patterns(worktree)
+        .and_then(|patterns| copy_matching(std::path::Path::new(repo_path), worktree, &patterns));
+    match copied {
+        Ok(copied) if !copied.is_empty() => {
+            tracing::info!("Copied {} into {worktree_path}", copied.join(", "));
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to copy files into {worktree_path}: {e}"),
+    }
+    run_hook_in_window(state, win_name, worktree_path, HookEvent::PostCreate).await;
+}
+
+/// Type the `event` hook of the feature checked out in `worktree_path` into
+/// its window, where its output can be followed. Hooks that cannot be read
+/// are skipped with a warning rather than failing the request.
+async fn run_hook_in_window(
+    state: &AppState,
+    win_name: &str,
+    worktree_path: &str,
+    event: HookEvent,
+) {
+    let worktree = std::path::Path::new(worktree_path);
+    match hook_command(&state.settings.hooks, worktree, event) {
+        Ok(Some(command)) => {
+            if !state.tmux.type_command(win_name, &command).await {
+                tracing::warn!("Failed to send the {event:?} hook to window '{win_name}'");
+            }
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Skipping the {event:?} hook: {e}"),
+    }
+}
+
+/// Run the `pre_delete` hook in the worktree of a feature about to be
+/// deleted, and wait for it: its window goes away with the feature. A failing
+/// hook keeps the feature.
+async fn run_pre_delete_hook(state: &AppState, worktree_path: &str) -> Result<(), ApiError> {
+    let command = hook_command(
+        &state.settings.hooks,
+        std::path::Path::new(worktree_path),
+        HookEvent::PreDelete,
+    )?;
+    let Some(command) = command else {
+        return Ok(());
+    };
+    let result = run_command(&command, Some(worktree_path), PRE_DELETE_HOOK_TIMEOUT_SECS).await;
+    if result.success() {
+        return Ok(());
+    }
+    let output = if result.stderr.trim().is_empty() {
+        result.stdout
+    } else {
+        result.stderr
+    };
+    Err(ApiError::new(
+        ErrorCode::HookFailed,
+        format!("The pre_delete hook failed: {}", output.trim()),
+    )
+    .with_status(409))
+}
+
+/// Select the window of a feature whose worktree exists, creating the window
+/// if needed, and record the switch.
+async fn switch_to(
+    state: &AppState,
+    repo_path: &str,
+    feature_name: &str,
+    worktree_path: &str,
+) -> Result<SwitchFeatureResponse, ApiError> {
+    // Ensure tmux session
+    state
+        .tmux
+        .ensure_session()
+        .await
+        .map_err(|e| ApiError::new(ErrorCode::TmuxUnavailable, e.to_string()))?;
 
     // Switch to window
-    let win_name = window_name(&request.repo_path, &request.feature_name);
+    let win_name = state.window_names.window_name(repo_path, feature_name);
+    suspend::resume(state, &win_name).await;
     let (switched, has_running_process) = state
         .tmux
-        .switch_to_window(&win_name, Some(&worktree_path))
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "detail": e.to_string() })),
-            )
-        })?;
+        .switch_to_window(&win_name, Some(worktree_path))
+        .await?;
 
     if !switched {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "detail": format!("Failed to switch to window '{win_name}'") })),
+        return Err(ApiError::new(
+            ErrorCode::TmuxUnavailable,
+            format!("Failed to switch to window '{win_name}'"),
         ));
     }
 
-    Ok(Json(SwitchFeatureResponse {
+    let undo = state
+        .activity
+        .last_switch()
+        .filter(|(repo, name)| repo != repo_path || name != feature_name)
+        .map(|(repo_path, feature_name)| UndoAction::SwitchBack {
+            repo_path,
+            feature_name,
+        });
+    state.activity.record(ActivityAction::SwitchFeature, repo_path, feature_name, undo);
+    state.events.publish(ServerEvent::FeatureSwitched {
+        repo_path: repo_path.to_string(),
+        feature_name: feature_name.to_string(),
+        tmux_window: win_name.clone(),
+    });
+
+    Ok(SwitchFeatureResponse {
         switched: true,
-        worktree_path,
+        worktree_path: worktree_path.to_string(),
         tmux_window: win_name,
         has_running_process,
+    })
+}
+
+/// Switch to an existing feature for a desktop launcher, and return how to
+/// open it. The repo is given by name or path.
+async fn quick_attach(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<QuickAttachParams>,
+) -> Result<Json<QuickAttachResponse>, ApiError> {
+    let repos = state.git.list_repos().await?;
+    let repo = repos
+        .into_iter()
+        .find(|r| r.name == params.repo || r.path == params.repo)
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::RepoNotFound,
+                format!("Repository '{}' not found", params.repo),
+            )
+        })?;
+    let features = state.git.list_features(&repo.path).await?;
+    // Unlike switch-feature, a GET never creates the feature
+    let feature = features
+        .into_iter()
+        .find(|f| f.name == params.feature)
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::FeatureNotFound,
+                format!("Feature '{}' not found", params.feature),
+            )
+        })?;
+
+    let switched = switch_to(&state, &repo.path, &feature.name, &feature.worktree_path).await?;
+
+    let public_url = state.tunnel.lock().unwrap().public_url.clone();
+    let server_url = public_url.or_else(|| {
+        let host = headers.get(header::HOST)?.to_str().ok()?;
+        Some(format!("http://{host}"))
+    });
+    let mut deep_link = format!(
+        "nomadflowcode://terminal?repoPath={}&featureName={}",
+        urlencoding::encode(&repo.path),
+        urlencoding::encode(&feature.name)
+    );
+    if let Some(url) = server_url {
+        deep_link.push_str(&format!("&url={}", urlencoding::encode(&url)));
+    }
+
+    let mut attach_command = state.tmux.attach_command(&switched.tmux_window);
+    let ssh_host = &state.settings.editor.ssh_host;
+    if !ssh_host.is_empty() {
+        attach_command = format!(
+            "ssh -t {} {}",
+            shell_quote(ssh_host),
+            shell_quote(&attach_command)
+        );
+    }
+
+    Ok(Json(QuickAttachResponse {
+        repo_path: repo.path,
+        feature_name: feature.name,
+        worktree_path: switched.worktree_path,
+        tmux_window: switched.tmux_window,
+        has_running_process: switched.has_running_process,
+        deep_link,
+        attach_command,
     }))
 }
 
 async fn list_branches(
     State(state): State<Arc<AppState>>,
     Json(request): Json<ListBranchesRequest>,
-) -> Result<Json<ListBranchesResponse>, (StatusCode, Json<Value>)> {
-    let (branches, default_branch) = state
-        .git
-        .list_branches(&request.repo_path)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "detail": e.to_string() })),
-            )
-        })?;
+) -> Result<Json<ListBranchesResponse>, ApiError> {
+    branches_page(&state, &request.repo_path, request.page).await
+}
+
+/// `GET /api/repos/{name}/branches`: [`list_branches`] addressed by repo name.
+async fn get_branches(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<ListBranchesResponse>, ApiError> {
+    let repo = find_repo(&state, &name).await?;
+    branches_page(&state, &repo.path, page).await
+}
+
+async fn branches_page(
+    state: &AppState,
+    repo_path: &str,
+    page: PageParams,
+) -> Result<Json<ListBranchesResponse>, ApiError> {
+    let (branches, default_branch) = state.git.list_branches(repo_path).await?;
 
+    let (branches, total) = page.apply(branches, |b| &b.name);
     Ok(Json(ListBranchesResponse {
         branches,
         default_branch,
+        total,
     }))
 }
 
 async fn attach_branch(
     State(state): State<Arc<AppState>>,
     Json(request): Json<AttachBranchRequest>,
-) -> Result<Json<AttachBranchResponse>, (StatusCode, Json<Value>)> {
+) -> Result<Json<AttachBranchResponse>, ApiError> {
+    check_unprotected(
+        &state,
+        &request.repo_path,
+        &request.branch_name,
+        request.allow_protected,
+    )
+    .await?;
     let (worktree_path, branch) = state
         .git
         .attach_branch(&request.repo_path, &request.branch_name)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(json!({ "detail": e.to_string() })),
-            )
-        })?;
+        .map_err(worktree_error)?;
 
     let wt_name = std::path::Path::new(&worktree_path)
         .file_name()
@@ -241,24 +530,27 @@ async fn attach_branch(
         .to_string();
 
     // Ensure tmux session and window
-    state.tmux.ensure_session().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "detail": e.to_string() })),
-        )
-    })?;
+    state
+        .tmux
+        .ensure_session()
+        .await
+        .map_err(|e| ApiError::new(ErrorCode::TmuxUnavailable, e.to_string()))?;
 
-    let win_name = window_name(&request.repo_path, &wt_name);
+    let win_name = state.window_names.window_name(&request.repo_path, &wt_name);
     state
         .tmux
         .ensure_window(&win_name, Some(&worktree_path))
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "detail": e.to_string() })),
-            )
-        })?;
+        .await?;
+
+    set_up_worktree(&state, &request.repo_path, &win_name, &worktree_path).await;
+
+    state
+        .activity
+        .record(ActivityAction::AttachBranch, &request.repo_path, &wt_name, None);
+    state.events.publish(ServerEvent::FeatureCreated {
+        repo_path: request.repo_path.clone(),
+        feature_name: wt_name,
+    });
 
     Ok(Json(AttachBranchResponse {
         worktree_path,
@@ -267,12 +559,549 @@ async fn attach_branch(
     }))
 }
 
+/// `409` when git refuses a feature's branch because it exists or is checked
+/// out already, `400` for its other failures to add the worktree.
+fn worktree_error(e: NomadError) -> ApiError {
+    let message = e.to_string();
+    let code = if message.contains("already exists")
+        || message.contains("already checked out")
+        || message.contains("is already used by worktree")
+    {
+        ErrorCode::BranchExists
+    } else {
+        ErrorCode::WorktreeFailed
+    };
+    ApiError::new(code, message)
+}
+
+/// Branches of a repo its forge protects, when a token for the forge is
+/// configured.
+async fn protected_branches(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ProtectedBranchesRequest>,
+) -> Json<ProtectedBranchesResponse> {
+    let branches = match state.git.remote_url(&request.repo_path, "origin").await {
+        Some(url) => state.protected_branches.get(&state.settings.forge, &url).await,
+        None => Vec::new(),
+    };
+    Json(ProtectedBranchesResponse { branches })
+}
+
+/// `409` when the forge of the repo protects `branch`, unless `allow`:
+/// work on such branches goes through feature branches.
+async fn check_unprotected(
+    state: &AppState,
+    repo_path: &str,
+    branch: &str,
+    allow: bool,
+) -> Result<(), ApiError> {
+    if allow {
+        return Ok(());
+    }
+    let Some(url) = state.git.remote_url(repo_path, "origin").await else {
+        return Ok(());
+    };
+    let protected = state.protected_branches.get(&state.settings.forge, &url).await;
+    if !is_protected(&protected, branch) {
+        return Ok(());
+    }
+    Err(ApiError::new(
+        ErrorCode::BranchProtected,
+        format!(
+            "'{branch}' is protected on the forge: work on a feature branch, \
+             or set allowProtected"
+        ),
+    )
+    .with("protected", true))
+}
+
+/// Apply one action to several features at once.
+/// Each feature is processed independently; failures are reported per feature.
+async fn bulk_features(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BulkFeaturesRequest>,
+) -> Result<Json<BulkFeaturesResponse>, ApiError> {
+    let features = state.git.list_features(&request.repo_path).await?;
+
+    let mut results = Vec::with_capacity(request.feature_names.len());
+    for name in &request.feature_names {
+        let Some(feature) = features.iter().find(|f| &f.name == name) else {
+            results.push(BulkFeatureResult {
+                feature_name: name.clone(),
+                ok: false,
+                detail: Some("Feature not found".to_string()),
+            });
+            continue;
+        };
+
+        let outcome = match request.action {
+            BulkFeatureAction::Delete | BulkFeatureAction::Prune if feature.is_main => {
+                Err("Cannot remove the main repository branch".to_string())
+            }
+            BulkFeatureAction::Delete => {
+                let undo = restore_undo(&state, &request.repo_path, Some(feature)).await;
+                state
+                    .tmux
+                    .kill_window(&state.window_names.window_name(&request.repo_path, name))
+                    .await;
+                state
+                    .git
+                    .delete_feature(&request.repo_path, name)
+                    .await
+                    .map(|_| {
+                        state.activity.record(
+                            ActivityAction::DeleteFeature,
+                            &request.repo_path,
+                            name,
+                            undo,
+                        );
+                        state.events.publish(ServerEvent::FeatureDeleted {
+                            repo_path: request.repo_path.clone(),
+                            feature_name: name.clone(),
+                        });
+                        None
+                    })
+                    .map_err(|e| e.to_string())
+            }
+            BulkFeatureAction::Prune => {
+                state
+                    .tmux
+                    .kill_window(&state.window_names.window_name(&request.repo_path, name))
+                    .await;
+                state
+                    .git
+                    .remove_worktree(&request.repo_path, name)
+                    .await
+                    .map(|_| {
+                        state.activity.record(
+                            ActivityAction::PruneFeature,
+                            &request.repo_path,
+                            name,
+                            Some(UndoAction::ReattachBranch {
+                                branch: feature.branch.clone(),
+                            }),
+                        );
+                        state.events.publish(ServerEvent::FeatureDeleted {
+                            repo_path: request.repo_path.clone(),
+                            feature_name: name.clone(),
+                        });
+                        None
+                    })
+                    .map_err(|e| e.to_string())
+            }
+            BulkFeatureAction::Sync => state
+                .git
+                .sync_feature(&feature.worktree_path)
+                .await
+                .map(Some)
+                .map_err(|e| e.to_string()),
+        };
+
+        results.push(match outcome {
+            Ok(detail) => BulkFeatureResult {
+                feature_name: name.clone(),
+                ok: true,
+                detail,
+            },
+            Err(detail) => BulkFeatureResult {
+                feature_name: name.clone(),
+                ok: false,
+                detail: Some(detail),
+            },
+        });
+    }
+
+    Ok(Json(BulkFeaturesResponse { results }))
+}
+
+/// The feature `feature_name` of the repo at `repo_path`, or `404`.
+pub(crate) async fn find_feature(
+    state: &AppState,
+    repo_path: &str,
+    feature_name: &str,
+) -> Result<Feature, ApiError> {
+    let features = state.git.list_features(repo_path).await?;
+    features
+        .into_iter()
+        .find(|f| f.name == feature_name)
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::FeatureNotFound,
+                format!("Feature '{feature_name}' not found"),
+            )
+        })
+}
+
+/// Fetch a feature's upstream and bring its commits into the worktree.
+async fn pull_feature(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<PullFeatureRequest>,
+) -> Result<Json<PullFeatureResponse>, ApiError> {
+    let feature = find_feature(&state, &request.repo_path, &request.feature_name).await?;
+    let pulled = state
+        .git
+        .pull_feature(&feature.worktree_path, request.strategy)
+        .await
+        .map_err(|e| match e {
+            NomadError::NotFound(_) => ApiError::new(
+                ErrorCode::NoUpstream,
+                format!("'{}' has no upstream branch", feature.branch),
+            ),
+            NomadError::InUse(msg) if msg.contains("would be overwritten") => {
+                ApiError::new(ErrorCode::WorktreeDirty, msg)
+            }
+            NomadError::InUse(msg) => ApiError::new(ErrorCode::BranchDiverged, msg),
+            e => ApiError::from(e),
+        })?;
+    Ok(Json(pulled))
+}
+
+/// The worktree of feature `feature` of repo `name`, and the branch to
+/// compare it with.
+async fn diff_target(
+    state: &AppState,
+    name: &str,
+    feature: &str,
+    params: DiffParams,
+) -> Result<(String, String), ApiError> {
+    let repo = find_repo(state, name).await?;
+    let feature = find_feature(state, &repo.path, feature).await?;
+    let base = match params.base.filter(|b| !b.is_empty()) {
+        Some(base) => base,
+        None => state.git.get_default_branch(&repo.path).await,
+    };
+    Ok((feature.worktree_path, base))
+}
+
+fn diff_error(e: NomadError) -> ApiError {
+    match e {
+        NomadError::NotFound(msg) => ApiError::new(ErrorCode::InvalidRequest, msg),
+        e => ApiError::from(e),
+    }
+}
+
+/// `GET /api/repos/{name}/features/{feature}/diff`: what a feature changes,
+/// for review before committing.
+async fn get_feature_diff(
+    State(state): State<Arc<AppState>>,
+    Path((name, feature)): Path<(String, String)>,
+    Query(params): Query<DiffParams>,
+) -> Result<Json<FeatureDiff>, ApiError> {
+    let (worktree_path, base) = diff_target(&state, &name, &feature, params).await?;
+    let diff = state
+        .git
+        .feature_diff(&worktree_path, &base)
+        .await
+        .map_err(diff_error)?;
+    Ok(Json(diff))
+}
+
+/// `GET /api/repos/{name}/features/{feature}/diff/summary`: lines changed per
+/// file by [`get_feature_diff`].
+async fn get_diff_summary(
+    State(state): State<Arc<AppState>>,
+    Path((name, feature)): Path<(String, String)>,
+    Query(params): Query<DiffParams>,
+) -> Result<Json<DiffSummary>, ApiError> {
+    let (worktree_path, base) = diff_target(&state, &name, &feature, params).await?;
+    let summary = state
+        .git
+        .diff_summary(&worktree_path, &base)
+        .await
+        .map_err(diff_error)?;
+    Ok(Json(summary))
+}
+
+/// `GET /api/repos/{name}/features/{feature}/archive`: the files of the
+/// feature's last commit, as a gzipped tarball.
+async fn get_feature_archive(
+    State(state): State<Arc<AppState>>,
+    Path((name, feature)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let repo = find_repo(&state, &name).await?;
+    let feature = find_feature(&state, &repo.path, &feature).await?;
+    let prefix = format!("{}-{}", repo.name, feature.name);
+    let archive = state.git.archive(&feature.worktree_path, &prefix).await?;
+    Ok(attachment(
+        "application/gzip",
+        &format!("{prefix}.tar.gz"),
+        archive,
+    ))
+}
+
+/// Read the note of a feature, or replace it when the request carries one.
+async fn feature_notes(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<FeatureNotesRequest>,
+) -> Result<Json<FeatureNotesResponse>, ApiError> {
+    let Some(text) = request.note else {
+        let note = state.notes.get(&request.repo_path, &request.feature_name);
+        return Ok(Json(FeatureNotesResponse { note }));
+    };
+    if text.len() > MAX_NOTE_LEN {
+        return Err(ApiError::new(
+            ErrorCode::TooLarge,
+            format!("Notes are limited to {MAX_NOTE_LEN} bytes"),
+        ));
+    }
+    find_feature(&state, &request.repo_path, &request.feature_name).await?;
+    let note = state
+        .notes
+        .set(&request.repo_path, &request.feature_name, &text, now_secs());
+    Ok(Json(FeatureNotesResponse { note }))
+}
+
+/// Commit the changes of a feature's worktree, with a message following the
+/// rules of the repo's `.nomadflow.toml`.
+async fn commit_feature(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CommitRequest>,
+) -> Result<Json<CommitResponse>, ApiError> {
+    if request.message.trim().is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "Commit message is empty",
+        ));
+    }
+    let feature = find_feature(&state, &request.repo_path, &request.feature_name).await?;
+    let rules = load_commit_rules(std::path::Path::new(&feature.worktree_path))?;
+    let message = rules.compose(&CommitParts {
+        message: &request.message,
+        commit_type: request.commit_type.as_deref(),
+        scope: request.scope.as_deref(),
+        breaking: request.breaking,
+        branch: &feature.branch,
+    });
+    let violations = rules.check(&message, request.commit_type.is_some());
+    if let Some(first) = violations.first() {
+        return Err(ApiError::new(
+            ErrorCode::CommitRulesBroken,
+            format!(
+                "The commit message breaks the repo's rules: {}",
+                first.message
+            ),
+        )
+        .with("violations", &violations));
+    }
+    if request.skip_hooks && !state.settings.hooks.allow_skip_git_hooks {
+        return Err(ApiError::new(
+            ErrorCode::Forbidden,
+            "This server does not allow skipping git hooks",
+        ));
+    }
+
+    // The hooks' output can be followed on the job while they run
+    let Some(job_id) = state.jobs.start(JobKind::Commit, &feature.worktree_path) else {
+        return Err(ApiError::new(
+            ErrorCode::JobRunning,
+            format!("A commit is already running in '{}'", feature.name),
+        ));
+    };
+    let outcome = state
+        .git
+        .commit_feature(
+            &feature.worktree_path,
+            &message,
+            request.paths.as_deref(),
+            request.skip_hooks,
+            |line| state.jobs.append_output(job_id, line),
+        )
+        .await;
+    state.jobs.finish(
+        job_id,
+        match &outcome {
+            Ok(CommitOutcome::HookFailed(failure)) => {
+                Some(format!("The {} hook failed", failure.hook))
+            }
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        },
+    );
+
+    match outcome {
+        Ok(CommitOutcome::Committed(committed)) => Ok(Json(committed)),
+        Ok(CommitOutcome::NothingToCommit) => Err(ApiError::new(
+            ErrorCode::NothingToCommit,
+            "Nothing to commit",
+        )),
+        Ok(CommitOutcome::HookFailed(failure)) => Err(ApiError::new(
+            ErrorCode::HookFailed,
+            format!("The {} hook refused the commit", failure.hook),
+        )
+        .with("hookFailure", failure)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Push a feature's branch, setting its upstream on the first push.
+async fn push_feature(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<PushRequest>,
+) -> Result<Json<PushResponse>, ApiError> {
+    let feature = find_feature(&state, &request.repo_path, &request.feature_name).await?;
+    let outcome = state
+        .git
+        .push_feature(&feature.worktree_path, &feature.branch)
+        .await?;
+    match outcome {
+        PushOutcome::Pushed(pushed) => Ok(Json(pushed)),
+        PushOutcome::Rejected { reason, detail } => {
+            Err(ApiError::new(ErrorCode::PushRejected, detail).with("reason", reason))
+        }
+    }
+}
+
+/// Switch to a feature's window and start resolving its conflicts there:
+/// rebase onto the upstream unless a rebase or merge already stopped, then
+/// open the configured mergetool, or list the conflicts.
+async fn resolve_conflicts(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ResolveConflictsRequest>,
+) -> Result<Json<ResolveConflictsResponse>, ApiError> {
+    let feature = find_feature(&state, &request.repo_path, &request.feature_name).await?;
+    if !feature.needs_resolution && feature.upstream.is_none() {
+        return Err(ApiError::new(
+            ErrorCode::NoUpstream,
+            format!("Branch '{}' has no upstream", feature.branch),
+        ));
+    }
+    let switched = switch_to(
+        &state,
+        &request.repo_path,
+        &request.feature_name,
+        &feature.worktree_path,
+    )
+    .await?;
+    let window = switched.tmux_window;
+    // Typing into a running program would feed it the command instead
+    if !state.tmux.is_shell_idle(&window).await {
+        return Err(ApiError::new(
+            ErrorCode::WindowBusy,
+            format!("Window '{window}' is busy"),
+        ));
+    }
+    let command =
+        resolve_conflicts_command(&state.settings.editor.mergetool, feature.needs_resolution);
+    if !state.tmux.type_command(&window, &command).await {
+        return Err(ApiError::new(
+            ErrorCode::TmuxUnavailable,
+            format!("Failed to send the command to window '{window}'"),
+        ));
+    }
+    Ok(Json(ResolveConflictsResponse {
+        tmux_window: window,
+        command,
+    }))
+}
+
+/// Open a pull request for a feature's pushed branch on the forge hosting
+/// its remote.
+async fn create_pr(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CreatePrRequest>,
+) -> Result<Json<CreatePrResponse>, ApiError> {
+    let not_configured = |detail: String| ApiError::new(ErrorCode::ForgeNotConfigured, detail);
+    let feature = find_feature(&state, &request.repo_path, &request.feature_name).await?;
+    let url = state
+        .git
+        .push_remote_url(&feature.worktree_path, &feature.branch)
+        .await
+        .ok_or_else(|| not_configured(format!("Branch '{}' has no remote", feature.branch)))?;
+    let repo = ForgeRepo::from_remote_url(&url)
+        .ok_or_else(|| not_configured(format!("'{url}' is not hosted on GitHub or GitLab")))?;
+    let token = state.settings.forge.token(repo.kind).ok_or_else(|| {
+        let key = match repo.kind {
+            ForgeKind::GitHub => "forge.github_token",
+            ForgeKind::GitLab => "forge.gitlab_token",
+        };
+        not_configured(format!("No token for {}: set {key}", repo.host))
+    })?;
+
+    let base = match request.base.filter(|b| !b.trim().is_empty()) {
+        Some(base) => base,
+        None => state.git.get_default_branch(&request.repo_path).await,
+    };
+    if base == feature.branch {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            format!("'{base}' cannot be merged into itself"),
+        ));
+    }
+    let title = match request.title.filter(|t| !t.trim().is_empty()) {
+        Some(title) => title,
+        None => state
+            .git
+            .last_commit_subject(&feature.worktree_path)
+            .await
+            .unwrap_or_else(|| feature.branch.clone()),
+    };
+    let body = request
+        .body
+        .or_else(|| {
+            state
+                .notes
+                .get(&request.repo_path, &request.feature_name)
+                .map(|note| note.text)
+        })
+        .unwrap_or_default();
+
+    let pr = PullRequest {
+        head: &feature.branch,
+        base: &base,
+        title: &title,
+        body: &body,
+        draft: request.draft,
+    };
+    let url = repo.create_pull_request(token, &pr).await.map_err(|e| {
+        let code = match e {
+            NomadError::AlreadyExists(_) => ErrorCode::AlreadyExists,
+            NomadError::Config(_) => ErrorCode::ForgeNotConfigured,
+            _ => ErrorCode::ForgeFailed,
+        };
+        ApiError::new(code, e.to_string())
+    })?;
+    Ok(Json(CreatePrResponse {
+        url,
+        forge: repo.kind,
+        head: feature.branch,
+        base,
+        draft: request.draft,
+    }))
+}
+
+/// Remember where a feature's branch points so a delete can be undone.
+async fn restore_undo(
+    state: &AppState,
+    repo_path: &str,
+    feature: Option<&Feature>,
+) -> Option<UndoAction> {
+    let branch = feature?.branch.clone();
+    let sha = state.git.branch_tip(repo_path, &branch).await?;
+    Some(UndoAction::RestoreBranch { branch, sha })
+}
+
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/api/list-features", post(list_features))
+        .route("/api/repos/{name}/features", get(get_features))
+        .route("/api/repos/{name}/features/{feature}/diff", get(get_feature_diff))
+        .route("/api/repos/{name}/features/{feature}/diff/summary", get(get_diff_summary))
+        .route("/api/repos/{name}/features/{feature}/archive", get(get_feature_archive))
         .route("/api/create-feature", post(create_feature))
+        .route("/api/validate-branch", post(validate_branch))
+        .route("/api/suggest-branch-name", post(suggest_branch_name))
         .route("/api/delete-feature", post(delete_feature))
         .route("/api/switch-feature", post(switch_feature))
+        .route("/api/quick-attach", get(quick_attach))
         .route("/api/list-branches", post(list_branches))
+        .route("/api/repos/{name}/branches", get(get_branches))
         .route("/api/attach-branch", post(attach_branch))
+        .route("/api/protected-branches", post(protected_branches))
+        .route("/api/bulk-features", post(bulk_features))
+        .route("/api/pull-feature", post(pull_feature))
+        .route("/api/feature-notes", post(feature_notes))
+        .route("/api/commit", post(commit_feature))
+        .route("/api/push", post(push_feature))
+        .route("/api/create-pr", post(create_pr))
+        .route("/api/resolve-conflicts", post(resolve_conflicts))
 }
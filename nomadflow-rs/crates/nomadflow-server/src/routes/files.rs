@@ -0,0 +1,197 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{
+    FileContentResponse, FileEntry, FileKind, FilesParams, ListFilesResponse,
+};
+
+use crate::routes::features::find_feature;
+use crate::state::AppState;
+
+/// Largest file `/api/file` returns.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Bytes looked at for a NUL to tell binary files.
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// `relative` inside `root`, symlinks resolved, and its path relative to
+/// `root`. Anything leading out of `root`, or into its git metadata, is
+/// refused.
+fn resolve(root: &Path, relative: &str) -> Result<(PathBuf, String), ApiError> {
+    let relative = Path::new(relative.trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|c| c == Component::Normal(".git".as_ref()))
+    {
+        return Err(ApiError::new(
+            ErrorCode::Forbidden,
+            "Git metadata cannot be browsed",
+        ));
+    }
+    let root = root.canonicalize().map_err(ApiError::internal)?;
+    let path = root
+        .join(relative)
+        .canonicalize()
+        .map_err(|_| ApiError::new(ErrorCode::NotFound, "No such file or directory"))?;
+    let Ok(inside) = path.strip_prefix(&root) else {
+        return Err(ApiError::new(
+            ErrorCode::Forbidden,
+            "Path is outside the worktree",
+        ));
+    };
+    let inside = inside.to_string_lossy().to_string();
+    Ok((path, inside))
+}
+
+/// The worktree of the feature in `params`.
+async fn worktree(state: &AppState, params: &FilesParams) -> Result<PathBuf, ApiError> {
+    let feature = find_feature(state, &params.repo_path, &params.feature).await?;
+    Ok(PathBuf::from(feature.worktree_path))
+}
+
+/// `GET /api/files`: the entries of a directory of a feature's worktree.
+async fn list_files(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FilesParams>,
+) -> Result<Json<ListFilesResponse>, ApiError> {
+    let root = worktree(&state, &params).await?;
+    let (dir, path) = resolve(&root, &params.path)?;
+    let mut read = tokio::fs::read_dir(&dir)
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotADirectory => {
+                ApiError::new(ErrorCode::InvalidRequest, "Not a directory")
+            }
+            _ => ApiError::internal(e),
+        })?;
+
+    let mut entries = Vec::new();
+    while let Ok(Some(entry)) = read.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" {
+            continue;
+        }
+        let Ok(metadata) = tokio::fs::symlink_metadata(entry.path()).await else {
+            continue;
+        };
+        let kind = if metadata.is_symlink() {
+            FileKind::Symlink
+        } else if metadata.is_dir() {
+            FileKind::Dir
+        } else {
+            FileKind::File
+        };
+        entries.push(FileEntry {
+            path: Path::new(&path).join(&name).to_string_lossy().to_string(),
+            name,
+            kind,
+            size: if kind == FileKind::Dir {
+                0
+            } else {
+                metadata.len()
+            },
+        });
+    }
+    entries.sort_by(|a, b| {
+        (a.kind != FileKind::Dir, &a.name).cmp(&(b.kind != FileKind::Dir, &b.name))
+    });
+    Ok(Json(ListFilesResponse { path, entries }))
+}
+
+/// `GET /api/file`: the content of a text file of a feature's worktree.
+async fn read_file(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FilesParams>,
+) -> Result<Json<FileContentResponse>, ApiError> {
+    let root = worktree(&state, &params).await?;
+    let (file, path) = resolve(&root, &params.path)?;
+    let metadata = tokio::fs::metadata(&file)
+        .await
+        .map_err(ApiError::internal)?;
+    if metadata.is_dir() {
+        return Err(ApiError::new(ErrorCode::InvalidRequest, "Is a directory"));
+    }
+    if metadata.len() > MAX_FILE_BYTES {
+        return Err(ApiError::new(
+            ErrorCode::TooLarge,
+            format!("Files over {MAX_FILE_BYTES} bytes cannot be shown"),
+        ));
+    }
+    let bytes = tokio::fs::read(&file).await.map_err(ApiError::internal)?;
+    let content = text_content(bytes);
+    Ok(Json(FileContentResponse {
+        path,
+        size: metadata.len(),
+        binary: content.is_none(),
+        content,
+    }))
+}
+
+/// `bytes` as text, `None` for binary content.
+fn text_content(bytes: Vec<u8>) -> Option<String> {
+    let sniffed = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+    if sniffed.contains(&0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/files", get(list_files))
+        .route("/api/file", get(read_file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_stays_in_the_worktree() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path().join("wt");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(tmp.path().join("secret"), "").unwrap();
+        std::os::unix::fs::symlink(tmp.path().join("secret"), root.join("escape")).unwrap();
+
+        assert_eq!(resolve(&root, "src/main.rs").unwrap().1, "src/main.rs");
+        assert_eq!(
+            resolve(&root, "/src/../src/main.rs").unwrap().1,
+            "src/main.rs"
+        );
+        assert_eq!(resolve(&root, "").unwrap().1, "");
+        assert_eq!(
+            resolve(&root, "../secret").unwrap_err().code,
+            ErrorCode::Forbidden
+        );
+        assert_eq!(
+            resolve(&root, "escape").unwrap_err().code,
+            ErrorCode::Forbidden
+        );
+        assert_eq!(
+            resolve(&root, ".git/config").unwrap_err().code,
+            ErrorCode::Forbidden
+        );
+        assert_eq!(
+            resolve(&root, "missing").unwrap_err().code,
+            ErrorCode::NotFound
+        );
+    }
+
+    #[test]
+    fn test_text_content() {
+        assert_eq!(
+            text_content(b"hello\n".to_vec()).as_deref(),
+            Some("hello\n")
+        );
+        assert_eq!(text_content(b"\x89PNG\r\n\x1a\n\0\0".to_vec()), None);
+        assert_eq!(text_content(vec![0xff, 0xfe, b'a']), None);
+    }
+}
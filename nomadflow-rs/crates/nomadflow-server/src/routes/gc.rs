@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use axum::{extract::State, routing::post, Json, Router};
+use tracing::info;
+
+use nomadflow_core::gc::{collect, format_bytes};
+use nomadflow_core::models::{GcRequest, GcResponse};
+
+use crate::state::AppState;
+
+/// Remove orphaned worktree directories and expired files, or with `dryRun`
+/// only list them.
+async fn gc(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<GcRequest>,
+) -> Json<GcResponse> {
+    let report = collect(&state.settings, &state.git, request.dry_run).await;
+    if !request.dry_run {
+        state.task_runs.forget_removed();
+        info!(
+            entries = report.entries.len(),
+            "Cleanup reclaimed {}",
+            format_bytes(report.reclaimed_bytes)
+        );
+    }
+    Json(report)
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/gc", post(gc))
+}
@@ -1,19 +1,61 @@
 use std::sync::Arc;
 
-use axum::{extract::State, routing::get, Json, Router};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
 
 use nomadflow_core::models::HealthResponse;
 
+use crate::shares::now_secs;
 use crate::state::AppState;
+use crate::tunnel::{challenge_response, ShutdownNotice, CHALLENGE_PATH, SHUTDOWN_PATH};
 
-async fn health(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
-    Json(HealthResponse {
+/// Signed with the server identity, for the nonce the client sends.
+async fn health(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let response = HealthResponse {
         status: "ok".to_string(),
         tmux_session: state.settings.tmux.session.clone(),
         api_port: state.settings.api.port,
-    })
+        server_key: Some(state.identity.public_key()),
+        terminal: Some(*state.terminal_status.borrow()),
+        auth_required: Some(!state.settings.auth.secret.is_empty()),
+    };
+    state.identity.signed_json(&headers, &response)
+}
+
+#[derive(Deserialize)]
+struct ChallengeQuery {
+    nonce: String,
+}
+
+/// Prove to the relay that this server is the one behind the tunnel being registered.
+async fn tunnel_challenge(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ChallengeQuery>,
+) -> String {
+    challenge_response(&state.tunnel_key, &query.nonce)
+}
+
+/// Move the tunnel to another relay before this one goes away.
+async fn relay_shutdown(
+    State(state): State<Arc<AppState>>,
+    Json(notice): Json<ShutdownNotice>,
+) -> StatusCode {
+    if !notice.is_valid(&state.tunnel_key, now_secs()) {
+        return StatusCode::FORBIDDEN;
+    }
+    state.relay_shutdown.notify_waiters();
+    StatusCode::NO_CONTENT
 }
 
 pub fn router() -> Router<Arc<AppState>> {
-    Router::new().route("/health", get(health))
+    Router::new()
+        .route("/health", get(health))
+        .route(CHALLENGE_PATH, get(tunnel_challenge))
+        .route(SHUTDOWN_PATH, post(relay_shutdown))
 }
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path as UrlPath, State},
+    routing::get,
+    Json, Router,
+};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{Job, ListJobsResponse};
+
+use crate::state::AppState;
+
+async fn list_jobs(State(state): State<Arc<AppState>>) -> Json<ListJobsResponse> {
+    Json(ListJobsResponse {
+        jobs: state.jobs.list(),
+    })
+}
+
+async fn get_job(
+    State(state): State<Arc<AppState>>,
+    UrlPath(id): UrlPath<u64>,
+) -> Result<Json<Job>, ApiError> {
+    state
+        .jobs
+        .get(id)
+        .map(Json)
+        .ok_or_else(|| ApiError::new(ErrorCode::NotFound, format!("Job {id} not found")))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/jobs", get(list_jobs))
+        .route("/api/jobs/{id}", get(get_job))
+}
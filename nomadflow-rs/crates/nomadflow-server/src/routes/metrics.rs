@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    http::header,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+
+use crate::metrics::Gauges;
+use crate::state::AppState;
+
+/// Prometheus text exposition format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+async fn ttyd_up(port: u16) -> bool {
+    matches!(
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            tokio::net::TcpStream::connect(("127.0.0.1", port)),
+        )
+        .await,
+        Ok(Ok(_))
+    )
+}
+
+async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let (ttyd_up, windows) = tokio::join!(
+        ttyd_up(state.settings.ttyd.port),
+        state.tmux.list_windows()
+    );
+    let gauges = Gauges {
+        ttyd_up,
+        tmux_windows: windows.len(),
+        terminal_bytes: state.sessions.totals(),
+    };
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], state.metrics.render(&gauges))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/metrics", get(metrics))
+}
@@ -1,4 +1,24 @@
+pub mod activity;
+pub mod bans;
+pub mod devices;
+pub mod downloads;
+pub mod events;
 pub mod features;
+pub mod files;
+pub mod gc;
 pub mod health;
+pub mod jobs;
+pub mod metrics;
+pub mod reconcile;
 pub mod repos;
+pub mod search;
+pub mod sessions;
+pub mod shares;
+pub mod tasks;
 pub mod terminal;
+pub mod tmux_events;
+pub mod tokens;
+pub mod tunnel;
+pub mod webrtc;
+pub mod windows;
+pub mod workspace;
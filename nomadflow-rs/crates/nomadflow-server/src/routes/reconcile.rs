@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use axum::{extract::State, routing::post, Json, Router};
+
+use nomadflow_core::models::{ReconcileRequest, ReconcileResponse};
+
+use crate::reconcile::reconcile;
+use crate::state::AppState;
+
+async fn reconcile_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ReconcileRequest>,
+) -> Json<ReconcileResponse> {
+    let issues = reconcile(&state, request.fix).await;
+    Json(ReconcileResponse { issues })
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/reconcile", post(reconcile_handler))
+}
@@ -1,53 +1,197 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::State,
-    http::StatusCode,
-    routing::post,
+    body::Bytes,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    routing::{get, post},
     Json, Router,
 };
-use serde_json::{json, Value};
-
+use nomadflow_core::api_error::{ApiError, ErrorCode};
 use nomadflow_core::error::NomadError;
-use nomadflow_core::models::{CloneRepoRequest, CloneRepoResponse, ListReposResponse};
+use nomadflow_core::models::{
+    CloneRepoRequest, CloneRepoResponse, CloneRepoStarted, DeleteRepoRequest, DeleteRepoResponse,
+    DuplicateRepoRequest, JobKind, ListReposRequest, ListReposResponse, PageParams, Repository,
+    ServerEvent,
+};
 
+use crate::etag::json_with_etag;
 use crate::state::AppState;
 
 async fn list_repos(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListReposResponse>, (StatusCode, Json<Value>)> {
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    // The body is optional: older clients post nothing at all
+    let request: ListReposRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        ListReposRequest::default()
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| ApiError::new(ErrorCode::InvalidRequest, e.to_string()))?
+    };
+    repos_page(&state, &headers, request.page).await
+}
+
+/// `GET /api/repos`: [`list_repos`] with the page in the query string.
+async fn get_repos(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(page): Query<PageParams>,
+) -> Result<Response, ApiError> {
+    repos_page(&state, &headers, page).await
+}
+
+async fn repos_page(
+    state: &AppState,
+    headers: &HeaderMap,
+    page: PageParams,
+) -> Result<Response, ApiError> {
     match state.git.list_repos().await {
-        Ok(repos) => Ok(Json(ListReposResponse { repos })),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "detail": e.to_string() })),
-        )),
+        Ok(repos) => {
+            let (repos, total) = page.apply(repos, |r| &r.name);
+            Ok(json_with_etag(headers, &ListReposResponse { repos, total }))
+        }
+        Err(e) => Err(e.into()),
     }
 }
 
+/// The repo called `name`, for routes addressing repos by name.
+pub(crate) async fn find_repo(state: &AppState, name: &str) -> Result<Repository, ApiError> {
+    let repos = state.git.list_repos().await?;
+    repos.into_iter().find(|r| r.name == name).ok_or_else(|| {
+        ApiError::new(
+            ErrorCode::RepoNotFound,
+            format!("Repository '{name}' not found"),
+        )
+    })
+}
+
+/// Start cloning in the background; the clone is followed as a job.
 async fn clone_repo(
     State(state): State<Arc<AppState>>,
     Json(request): Json<CloneRepoRequest>,
-) -> Result<Json<CloneRepoResponse>, (StatusCode, Json<Value>)> {
-    match state
+) -> Result<(StatusCode, Json<CloneRepoStarted>), ApiError> {
+    let (name, dest) = state
         .git
-        .clone_repo(&request.url, request.token.as_deref(), request.name.as_deref())
+        .prepare_clone(&request.url, request.name.as_deref())
+        .await?;
+    let path = dest.to_string_lossy().to_string();
+    let Some(job_id) = state.jobs.start(JobKind::CloneRepo, &path) else {
+        return Err(ApiError::new(
+            ErrorCode::JobRunning,
+            format!("Repository '{name}' is already being cloned"),
+        ));
+    };
+
+    let response = CloneRepoStarted {
+        job_id,
+        name: name.clone(),
+        path: path.clone(),
+    };
+    tokio::spawn(async move {
+        let result = state
+            .git
+            .clone_into(&request.url, request.token.as_deref(), &dest, |progress| {
+                state.jobs.progress(job_id, progress)
+            })
+            .await;
+        match &result {
+            Ok(_) => state.events.publish(ServerEvent::RepoCloned {
+                name: name.clone(),
+                path: path.clone(),
+            }),
+            Err(e) => tracing::warn!(job_id, repo = %name, "Clone failed: {e}"),
+        }
+        let result = result
+            .map(|branch| CloneRepoResponse { name, path, branch })
+            .map_err(|e| e.to_string());
+        state.jobs.finish_clone(job_id, result);
+    });
+    Ok((StatusCode::ACCEPTED, Json(response)))
+}
+
+/// Copy a repo under a new name, to experiment without touching the original.
+async fn duplicate_repo(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<DuplicateRepoRequest>,
+) -> Result<Json<CloneRepoResponse>, ApiError> {
+    let repos = state.git.list_repos().await?;
+    if !repos.iter().any(|r| r.path == request.repo_path) {
+        return Err(ApiError::new(
+            ErrorCode::RepoNotFound,
+            format!("Repository '{}' not found", request.repo_path),
+        ));
+    }
+    let (name, dest) = state
+        .git
+        .prepare_clone(&request.repo_path, Some(&request.name))
+        .await?;
+
+    let branch = state
+        .git
+        .duplicate_repo(
+            &request.repo_path,
+            &dest,
+            request.mode,
+            request.remote_url.as_deref(),
+        )
+        .await?;
+    let path = dest.to_string_lossy().to_string();
+    tracing::info!(from = %request.repo_path, repo = %name, "Repository duplicated");
+    state.events.publish(ServerEvent::RepoCloned {
+        name: name.clone(),
+        path: path.clone(),
+    });
+    Ok(Json(CloneRepoResponse { name, path, branch }))
+}
+
+/// Delete a repo of the repos directory, or unlink a symlinked one. With
+/// `force`, its worktrees go too; without it, a repo with worktrees is a 409.
+async fn delete_repo(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<DeleteRepoRequest>,
+) -> Result<Json<DeleteRepoResponse>, ApiError> {
+    let repos = state.git.list_repos().await?;
+    let Some(repo) = repos.into_iter().find(|r| r.path == request.repo_path) else {
+        return Err(ApiError::new(
+            ErrorCode::RepoNotFound,
+            format!("Repository '{}' not found", request.repo_path),
+        ));
+    };
+    let features = state.git.list_features(&repo.path).await?;
+
+    let deleted = state
+        .git
+        .delete_repo(&repo.path, request.force)
         .await
+        .map_err(|e| match e {
+            NomadError::InUse(msg) => ApiError::new(ErrorCode::RepoHasWorktrees, msg),
+            e => ApiError::from(e),
+        })?;
+
+    // The main window and those of the removed worktrees
+    for feature in features
+        .iter()
+        .filter(|f| f.is_main || deleted.removed_features.contains(&f.name))
     {
-        Ok((name, path, branch)) => Ok(Json(CloneRepoResponse { name, path, branch })),
-        Err(NomadError::AlreadyExists(msg)) => Err((
-            StatusCode::CONFLICT,
-            Json(json!({ "detail": msg })),
-        )),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "detail": e.to_string() })),
-        )),
+        let win_name = state.window_names.window_name(&repo.path, &feature.name);
+        state.tmux.kill_window(&win_name).await;
     }
+    tracing::info!(repo = %repo.name, force = request.force, "Repository deleted");
+    state.events.publish(ServerEvent::RepoDeleted {
+        name: repo.name,
+        path: repo.path,
+    });
+    Ok(Json(deleted))
 }
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/api/list-repos", post(list_repos))
+        .route("/api/repos", get(get_repos))
         .route("/api/clone-repo", post(clone_repo))
+        .route("/api/duplicate-repo", post(duplicate_repo))
+        .route("/api/delete-repo", post(delete_repo))
 }
@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::{extract::State, routing::post, Json, Router};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{SearchRequest, SearchResponse};
+use nomadflow_core::search::{search, SearchRoot};
+
+use crate::state::AppState;
+
+/// Matches returned when the request sets no limit.
+const DEFAULT_LIMIT: usize = 100;
+/// Highest limit a request can set.
+const MAX_LIMIT: usize = 1000;
+
+/// Search the worktrees of every feature of the requested repos. Only repos
+/// of the repos directory can be searched, whatever paths the client sends.
+async fn search_features(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SearchRequest>,
+) -> Result<Json<SearchResponse>, ApiError> {
+    let query = request.query.trim();
+    if query.is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "The search query is empty",
+        ));
+    }
+
+    let repos = state.git.list_repos().await?;
+    if let Some(unknown) = request
+        .repo_paths
+        .iter()
+        .find(|p| !repos.iter().any(|r| &r.path == *p))
+    {
+        return Err(ApiError::new(
+            ErrorCode::RepoNotFound,
+            format!("Repository '{unknown}' not found"),
+        ));
+    }
+
+    let mut roots = Vec::new();
+    for repo in repos
+        .iter()
+        .filter(|r| request.repo_paths.is_empty() || request.repo_paths.contains(&r.path))
+    {
+        let features = state.git.list_features(&repo.path).await?;
+        roots.extend(features.into_iter().map(|f| SearchRoot {
+            repo_path: repo.path.clone(),
+            feature_name: f.name,
+            worktree: PathBuf::from(f.worktree_path),
+        }));
+    }
+
+    let limit = request.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let (matches, truncated) = search(&roots, query, request.case_sensitive, limit).await;
+    Ok(Json(SearchResponse { matches, truncated }))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/search", post(search_features))
+}
@@ -0,0 +1,16 @@
+use std::sync::Arc;
+
+use axum::{extract::State, routing::post, Json, Router};
+
+use nomadflow_core::models::ListSessionsResponse;
+
+use crate::state::AppState;
+
+/// Open terminal sessions and the bytes each device used.
+async fn list_sessions(State(state): State<Arc<AppState>>) -> Json<ListSessionsResponse> {
+    Json(state.sessions.list())
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/list-sessions", post(list_sessions))
+}
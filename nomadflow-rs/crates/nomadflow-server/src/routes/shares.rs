@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{ws::WebSocketUpgrade, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use rand::Rng;
+use tracing::{info, warn};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{
+    CreateShareRequest, CreateShareResponse, ListSharesRequest, ListSharesResponse,
+    RevokeShareRequest, RevokeShareResponse, SessionTransport, ShareInfo,
+};
+use nomadflow_core::services::tmux::TmuxService;
+use nomadflow_core::services::ttyd::{free_port, TerminalTarget, TtydService};
+use nomadflow_core::shell::command_exists;
+
+use crate::routes::terminal::{handle_ws, proxy_ttyd_request};
+use crate::shares::{now_secs, Share};
+use crate::state::AppState;
+
+/// Lifetime of a share link when the request does not specify one.
+const DEFAULT_TTL_SECS: u64 = 60 * 60;
+/// Longest lifetime a share link can be given.
+const MAX_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+fn random_string(len: usize) -> String {
+    rand::rng()
+        .sample_iter(rand::distr::Alphanumeric)
+        .take(len)
+        .map(|b| b as char)
+        .collect()
+}
+
+/// Create a time-limited link giving access to one feature's terminal.
+async fn create_share(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<CreateShareRequest>,
+) -> Result<Response, ApiError> {
+    let ttl = request.ttl_secs.unwrap_or(DEFAULT_TTL_SECS);
+    if ttl == 0 || ttl > MAX_TTL_SECS {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            format!("ttlSecs must be between 1 and {MAX_TTL_SECS}"),
+        ));
+    }
+
+    let win_name = state.window_names.window_name(&request.repo_path, &request.feature_name);
+    if !state.tmux.window_exists(&win_name).await {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("No terminal open for '{}'", request.feature_name),
+        ));
+    }
+    // Each share gets a ttyd of its own, whatever the terminal backend
+    if !command_exists("ttyd").await {
+        return Err(ApiError::new(
+            ErrorCode::TtydUnavailable,
+            "Share links need ttyd, which is not installed. \
+             Install with: brew install ttyd (macOS) or apt install ttyd (Linux)",
+        ));
+    }
+
+    let id = random_string(8).to_lowercase();
+    let tmux_session = format!("{}-share-{id}", state.settings.tmux.session);
+    state
+        .tmux
+        .create_linked_session(&tmux_session, &win_name)
+        .await?;
+
+    let port = match free_port() {
+        Ok(port) => port,
+        Err(e) => {
+            TmuxService::new(&tmux_session).kill_session().await;
+            return Err(ApiError::internal(e));
+        }
+    };
+    let credential = random_string(32);
+    let mut ttyd = TtydService::for_session(port, &tmux_session, &credential, !request.read_only);
+    if let Err(e) = ttyd.start().await {
+        TmuxService::new(&tmux_session).kill_session().await;
+        return Err(e.into());
+    }
+
+    let created_at = now_secs();
+    let info = ShareInfo {
+        id,
+        repo_path: request.repo_path,
+        feature_name: request.feature_name,
+        read_only: request.read_only,
+        created_at,
+        expires_at: created_at + ttl,
+    };
+    let token = random_string(32);
+    info!(id = %info.id, window = %win_name, read_only = info.read_only, "Share link created");
+
+    state.shares.insert(Share {
+        info: info.clone(),
+        token: token.clone(),
+        port,
+        credential,
+        tmux_session,
+        ttyd,
+    });
+
+    let response = CreateShareResponse {
+        share: info,
+        path: format!("/share/{token}"),
+        token,
+    };
+    Ok(state.identity.signed_json(&headers, &response))
+}
+
+async fn list_shares(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ListSharesRequest>,
+) -> Json<ListSharesResponse> {
+    let (shares, total) = request
+        .page
+        .apply(state.shares.list(now_secs()), |s| &s.feature_name);
+    Json(ListSharesResponse { shares, total })
+}
+
+/// Revoke a share link and disconnect its guests.
+async fn revoke_share(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RevokeShareRequest>,
+) -> Json<RevokeShareResponse> {
+    let revoked = match state.shares.remove(&request.id) {
+        Some(share) => {
+            share.close().await;
+            info!(id = %request.id, "Share link revoked");
+            true
+        }
+        None => false,
+    };
+    Json(RevokeShareResponse { revoked })
+}
+
+/// Guest terminal page.
+async fn guest_page(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Response, StatusCode> {
+    guest_proxy(&state, &token, "/").await
+}
+
+/// Guest terminal assets and the ttyd `/token` endpoint.
+async fn guest_asset(
+    State(state): State<Arc<AppState>>,
+    Path((token, path)): Path<(String, String)>,
+) -> Result<Response, StatusCode> {
+    guest_proxy(&state, &token, &format!("/{path}")).await
+}
+
+async fn guest_proxy(state: &AppState, token: &str, path: &str) -> Result<Response, StatusCode> {
+    let target = state
+        .shares
+        .resolve(token, now_secs())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    proxy_ttyd_request(state, target.port, &target.credential, path).await
+}
+
+/// Guest WebSocket, bridged to the share's own ttyd.
+async fn guest_ws(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let Some(target) = state.shares.resolve(&token, now_secs()) else {
+        warn!("Share WebSocket rejected: unknown or expired link");
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    ws.protocols(["tty"]).on_upgrade(move |socket| {
+        handle_ws(
+            state,
+            socket,
+            TerminalTarget::Ttyd {
+                port: target.port,
+                secret: target.credential,
+            },
+            SessionTransport::Share,
+            None,
+        )
+    })
+}
+
+/// Authenticated endpoints managing share links.
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/share", post(create_share))
+        .route("/api/list-shares", post(list_shares))
+        .route("/api/revoke-share", post(revoke_share))
+}
+
+/// Guest pages, authorized by the token in the path.
+pub fn guest_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/share/{token}", get(guest_page))
+        .route("/share/{token}/{*path}", get(guest_asset))
+}
+
+/// Guest WebSocket, kept out of the compression layer like `/terminal/ws`.
+pub fn guest_ws_router() -> Router<Arc<AppState>> {
+    Router::new().route("/share/{token}/ws", get(guest_ws))
+}
@@ -0,0 +1,190 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path as UrlPath, Query, State},
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{
+    ActivityAction, Feature, ListTaskRunsResponse, ListTasksRequest, ListTasksResponse,
+    PageParams, RunTaskRequest, RunTaskResponse, TaskInfo, TaskRun,
+};
+use nomadflow_core::tasks::load_tasks;
+
+use crate::downloads::attachment;
+use crate::state::AppState;
+use crate::suspend;
+use crate::tasks::spawn_run;
+
+/// Tell the session's clients that `run` failed, and log it to the activity.
+async fn notify_failure(state: &AppState, run: &TaskRun) {
+    let status = run
+        .exit_code
+        .map_or_else(|| "was killed".to_string(), |c| format!("exited with {c}"));
+    tracing::warn!(id = run.id, task = %run.task, feature = %run.feature_name, "Task {status}");
+    state
+        .tmux
+        .notify(&format!(
+            "nomadflow: task '{}' of {} {status}",
+            run.task, run.feature_name
+        ))
+        .await;
+    state.activity.record(
+        ActivityAction::TaskFailed,
+        &run.repo_path,
+        &run.feature_name,
+        None,
+    );
+}
+
+/// The feature and the tasks defined in its worktree.
+async fn feature_tasks(
+    state: &AppState,
+    repo_path: &str,
+    feature_name: &str,
+) -> Result<(Feature, Vec<TaskInfo>), ApiError> {
+    let features = state.git.list_features(repo_path).await?;
+    let feature = features
+        .into_iter()
+        .find(|f| f.name == feature_name)
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::FeatureNotFound,
+                format!("Feature '{feature_name}' not found"),
+            )
+        })?;
+    let tasks = load_tasks(Path::new(&feature.worktree_path))?;
+    Ok((feature, tasks))
+}
+
+async fn list_tasks(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ListTasksRequest>,
+) -> Result<Json<ListTasksResponse>, ApiError> {
+    let (_, tasks) = feature_tasks(&state, &request.repo_path, &request.feature_name).await?;
+    Ok(Json(ListTasksResponse { tasks }))
+}
+
+/// Run a task in the feature's window, or as a background job.
+async fn run_task(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RunTaskRequest>,
+) -> Result<Json<RunTaskResponse>, ApiError> {
+    let (feature, tasks) = feature_tasks(&state, &request.repo_path, &request.feature_name).await?;
+    let task = tasks
+        .into_iter()
+        .find(|t| t.name == request.task)
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::NotFound,
+                format!("Task '{}' is not defined", request.task),
+            )
+        })?;
+
+    let response = if request.background.unwrap_or(task.background) {
+        let notify_state = state.clone();
+        let run_id = spawn_run(
+            state.task_runs.clone(),
+            &task,
+            &request.repo_path,
+            &request.feature_name,
+            &feature.worktree_path,
+            move |run| {
+                if run.failed() {
+                    tokio::spawn(async move { notify_failure(&notify_state, &run).await });
+                }
+            },
+        )
+        .map_err(ApiError::internal)?;
+        RunTaskResponse {
+            window: None,
+            run_id: Some(run_id),
+        }
+    } else {
+        state
+            .tmux
+            .ensure_session()
+            .await
+            .map_err(|e| ApiError::new(ErrorCode::TmuxUnavailable, e.to_string()))?;
+        let win_name = state.window_names.window_name(&request.repo_path, &request.feature_name);
+        suspend::resume(&state, &win_name).await;
+        state
+            .tmux
+            .ensure_window(&win_name, Some(&feature.worktree_path))
+            .await?;
+        // Typing into a running program would feed it the command instead
+        if !state.tmux.is_shell_idle(&win_name).await {
+            return Err(ApiError::new(
+                ErrorCode::WindowBusy,
+                format!("Window '{win_name}' is busy; run the task in the background"),
+            ));
+        }
+        if !state.tmux.type_command(&win_name, &task.command).await {
+            return Err(ApiError::new(
+                ErrorCode::TmuxUnavailable,
+                format!("Failed to send the task to window '{win_name}'"),
+            ));
+        }
+        RunTaskResponse {
+            window: Some(win_name),
+            run_id: None,
+        }
+    };
+
+    state.activity.record(
+        ActivityAction::RunTask,
+        &request.repo_path,
+        &request.feature_name,
+        None,
+    );
+    Ok(Json(response))
+}
+
+/// Background runs, most recent first, filtered by task name.
+async fn list_task_runs(
+    State(state): State<Arc<AppState>>,
+    Query(page): Query<PageParams>,
+) -> Json<ListTaskRunsResponse> {
+    let (runs, total) = page.apply(state.task_runs.list(), |r| &r.task);
+    Json(ListTaskRunsResponse { runs, total })
+}
+
+async fn get_task_run(
+    State(state): State<Arc<AppState>>,
+    UrlPath(id): UrlPath<u64>,
+) -> Result<Json<TaskRun>, ApiError> {
+    state
+        .task_runs
+        .get(id)
+        .map(Json)
+        .ok_or_else(|| ApiError::new(ErrorCode::NotFound, format!("Task run {id} not found")))
+}
+
+/// `GET /api/task-runs/{id}/log`: the output of a run, as a file to save.
+async fn get_task_run_log(
+    State(state): State<Arc<AppState>>,
+    UrlPath(id): UrlPath<u64>,
+) -> Result<Response, ApiError> {
+    let run = state
+        .task_runs
+        .get(id)
+        .ok_or_else(|| ApiError::new(ErrorCode::NotFound, format!("Task run {id} not found")))?;
+    Ok(attachment(
+        "text/plain; charset=utf-8",
+        &format!("task-{id}-{}.log", run.task),
+        run.output,
+    ))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/list-tasks", post(list_tasks))
+        .route("/api/run-task", post(run_task))
+        .route("/api/task-runs", get(list_task_runs))
+        .route("/api/task-runs/{id}", get(get_task_run))
+        .route("/api/task-runs/{id}/log", get(get_task_run_log))
+}
@@ -1,67 +1,162 @@
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
-    body::Body,
+    body::{Body, Bytes},
     extract::{
         ws::WebSocket,
-        Path, Query, State, WebSocketUpgrade,
+        Path, Query, RawQuery, State, WebSocketUpgrade,
     },
-    http::{header, StatusCode},
-    response::{IntoResponse, Response},
+    http::{header, HeaderMap, Method, StatusCode},
+    response::{Html, IntoResponse, Response},
     routing::get,
     Router,
 };
 use base64::Engine;
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use nomadflow_ws::Throttle;
 use serde::Deserialize;
-use subtle::ConstantTimeEq;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
-use tokio_tungstenite::connect_async;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 use tracing::{error, warn};
 
-use crate::state::AppState;
+use nomadflow_core::config::TerminalBackend;
+use nomadflow_core::models::SessionTransport;
+use nomadflow_core::services::ttyd::TerminalTarget;
+
+use crate::auth::{authenticate, device_id, second_factor, AuthFailure, Caller};
+use crate::pty;
+use crate::shares::now_secs;
+use crate::state::{AppState, CachedAsset};
+
+/// Terminal page of the native backend, speaking the ttyd protocol.
+const TERMINAL_PAGE: &str = include_str!("terminal.html");
+
+/// xterm.js files loaded by [`TERMINAL_PAGE`] from `/terminal/`, vendored in
+/// `assets/xterm` (see `build.rs`).
+#[cfg(vendored_xterm)]
+const XTERM_ASSETS: &[(&str, &str, &[u8])] = &[
+    (
+        "xterm/xterm.min.js",
+        "text/javascript",
+        include_bytes!("../../assets/xterm/xterm.min.js"),
+    ),
+    (
+        "xterm/xterm.min.css",
+        "text/css",
+        include_bytes!("../../assets/xterm/xterm.min.css"),
+    ),
+    (
+        "xterm/addon-fit.min.js",
+        "text/javascript",
+        include_bytes!("../../assets/xterm/addon-fit.min.js"),
+    ),
+];
+#[cfg(not(vendored_xterm))]
+const XTERM_ASSETS: &[(&str, &str, &[u8])] = &[];
+
+/// Browser cache lifetime for static ttyd assets.
+const ASSET_CACHE_CONTROL: &str = "public, max-age=86400";
 
 #[derive(Deserialize)]
 struct WsQuery {
     token: Option<String>,
+    /// tmux window to attach to instead of the whole session.
+    window: Option<String>,
 }
 
-/// WebSocket proxy: mobile connects here, we forward to ttyd with Basic Auth.
+/// WebSocket proxy: mobile connects here, we forward to ttyd with Basic Auth,
+/// or attach tmux ourselves with the native backend.
+/// With `window`, the client gets a terminal of its own showing only that
+/// window, so that several terminals can follow different features.
 /// The mobile loads the ttyd HTML page directly (with basicAuthCredential),
 /// but WKWebView does not send Basic Auth on WebSocket upgrades,
 /// so the WS connection must go through this proxy.
 async fn ws_proxy(
     State(state): State<Arc<AppState>>,
     Query(query): Query<WsQuery>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
     ws: WebSocketUpgrade,
 ) -> Response {
-    let secret = &state.settings.auth.secret;
-    if !secret.is_empty() {
+    if !state.settings.auth.secret.is_empty() {
         let token = query.token.unwrap_or_default();
-        let matches: bool = token.as_bytes().ct_eq(secret.as_bytes()).into();
-        if !matches {
-            warn!("WebSocket auth failed: invalid token");
+        let sso = match authenticate(&state, &token).await {
+            Some(caller) if caller.may(&Method::GET, "/terminal/ws") => {
+                matches!(caller, Caller::User(_))
+            }
+            Some(_) => {
+                warn!("WebSocket refused: token without the terminal scope");
+                return Response::builder()
+                    .status(403)
+                    .body("Token does not allow terminal access".into())
+                    .unwrap();
+            }
+            None => {
+                warn!("WebSocket auth failed: invalid token");
+                let mut response = Response::builder()
+                    .status(403)
+                    .body(Body::from("Authentication required"))
+                    .unwrap();
+                if !token.is_empty() {
+                    response.extensions_mut().insert(AuthFailure);
+                }
+                return response;
+            }
+        };
+        if !sso && !second_factor(&state, &headers, raw_query.as_deref()).0 {
+            warn!("WebSocket auth failed: TOTP verification required");
             return Response::builder()
                 .status(403)
-                .body("Authentication required".into())
+                .body("TOTP verification required".into())
                 .unwrap();
         }
     }
 
-    let ttyd_port = state.settings.ttyd.port;
-    let auth_secret = state.settings.auth.secret.clone();
+    if let Some(window) = &query.window {
+        if !state.tmux.window_exists(window).await {
+            return Response::builder()
+                .status(404)
+                .body(format!("No window named '{window}'").into())
+                .unwrap();
+        }
+    }
+
+    let device = device_id(&state, &headers, raw_query.as_deref());
 
-    ws.protocols(["tty"])
-        .on_upgrade(move |socket| handle_ws(socket, ttyd_port, auth_secret))
+    ws.protocols(["tty"]).on_upgrade(move |socket| async move {
+        let transport = SessionTransport::WebSocket;
+        let Some(window) = query.window else {
+            let target = TerminalTarget::for_session(&state.settings);
+            return handle_ws(state, socket, target, transport, device).await;
+        };
+        let target = match state.window_ttyds.acquire(&window).await {
+            Ok(target) => target,
+            Err(e) => {
+                error!(%window, "Failed to start a terminal for the window: {e}");
+                return;
+            }
+        };
+        handle_ws(state.clone(), socket, target, transport, device).await;
+        state.window_ttyds.release(&window).await;
+    })
 }
 
-async fn handle_ws(client_ws: WebSocket, ttyd_port: u16, auth_secret: String) {
+/// WebSocket to ttyd, as the terminal proxies open it.
+pub(crate) type TtydSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Open the ttyd WebSocket on `ttyd_port`, authenticated with `auth_secret`.
+pub(crate) async fn connect_ttyd(ttyd_port: u16, auth_secret: &str) -> Option<TtydSocket> {
     let ws_url = format!("ws://127.0.0.1:{ttyd_port}/ws");
 
     let mut request = match ws_url.into_client_request() {
         Ok(r) => r,
         Err(e) => {
             error!("Failed to build ttyd request: {e}");
-            return;
+            return None;
         }
     };
 
@@ -77,45 +172,128 @@ async fn handle_ws(client_ws: WebSocket, ttyd_port: u16, auth_secret: String) {
             .insert("Authorization", format!("Basic {creds}").parse().unwrap());
     }
 
-    let ttyd_ws = match connect_async(request).await {
-        Ok((ws, _)) => ws,
+    match connect_async(request).await {
+        Ok((ws, _)) => Some(ws),
         Err(e) => {
             error!("Failed to connect to ttyd: {e}");
-            return;
+            None
         }
+    }
+}
+
+/// Sending half of a terminal, whichever backend serves it.
+pub(crate) type TerminalSink = Pin<Box<dyn Sink<Message, Error = ()> + Send>>;
+/// Receiving half of a terminal, whichever backend serves it.
+pub(crate) type TerminalStream =
+    Pin<Box<dyn Stream<Item = Result<Message, tungstenite::Error>> + Send>>;
+
+/// Open the terminal of `target`: its ttyd WebSocket, or tmux in a PTY.
+pub(crate) async fn connect_terminal(
+    target: &TerminalTarget,
+) -> Option<(TerminalSink, TerminalStream)> {
+    match target {
+        TerminalTarget::Ttyd { port, secret } => {
+            let (tx, rx) = connect_ttyd(*port, secret).await?.split();
+            Some((Box::pin(tx.sink_map_err(|_| ())), Box::pin(rx)))
+        }
+        TerminalTarget::Tmux(session) => match pty::attach(session) {
+            Ok((tx, rx)) => Some((Box::pin(tx.sink_map_err(|_| ())), Box::pin(rx))),
+            Err(e) => {
+                error!("Failed to start the native terminal: {e}");
+                None
+            }
+        },
+    }
+}
+
+pub(crate) async fn handle_ws(
+    state: Arc<AppState>,
+    client_ws: WebSocket,
+    target: TerminalTarget,
+    transport: SessionTransport,
+    device_id: Option<String>,
+) {
+    let Some((upstream_tx, upstream_rx)) = connect_terminal(&target).await else {
+        return;
     };
 
-    nomadflow_ws::bridge(client_ws, ttyd_ws).await;
+    let _ws_session = state.metrics.ws_session();
+    let session = state.sessions.open(transport, device_id, now_secs());
+    let ttyd = &state.settings.ttyd;
+    let throttle = Throttle {
+        interval: Duration::from_millis(ttyd.output_interval_ms),
+        backlog_bytes: ttyd.output_backlog_kb * 1024,
+    };
+    let traffic = session.traffic();
+    nomadflow_ws::bridge_counted(client_ws, upstream_tx, upstream_rx, traffic, Some(throttle))
+        .await;
 }
 
-/// Proxy GET /terminal → ttyd HTML page
-async fn terminal_html_proxy(
-    State(state): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, StatusCode> {
-    proxy_ttyd_request(&state, "/").await
+/// Proxy GET /terminal → ttyd HTML page, or our own with the native backend
+async fn terminal_html_proxy(State(state): State<Arc<AppState>>) -> Result<Response, StatusCode> {
+    if state.settings.ttyd.backend == TerminalBackend::Native {
+        return Ok(Html(TERMINAL_PAGE).into_response());
+    }
+    let secret = &state.settings.auth.secret;
+    proxy_ttyd_request(&state, state.settings.ttyd.port, secret, "/").await
 }
 
-/// Proxy GET /terminal/*path → ttyd assets (JS, CSS, etc.)
+/// Proxy GET /terminal/*path → ttyd assets (JS, CSS, etc.), or the
+/// embedded xterm.js files with the native backend
 async fn terminal_asset_proxy(
     State(state): State<Arc<AppState>>,
     Path(path): Path<String>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    proxy_ttyd_request(&state, &format!("/{path}")).await
+    if state.settings.ttyd.backend == TerminalBackend::Native {
+        return native_asset(&path).ok_or(StatusCode::NOT_FOUND);
+    }
+    let secret = &state.settings.auth.secret;
+    proxy_ttyd_request(&state, state.settings.ttyd.port, secret, &format!("/{path}")).await
+}
+
+fn native_asset(path: &str) -> Option<Response> {
+    let (_, content_type, body) = XTERM_ASSETS.iter().find(|(name, ..)| *name == path)?;
+    Some(asset_response(
+        StatusCode::OK,
+        content_type,
+        Bytes::from_static(body),
+        true,
+    ))
+}
+
+/// Whether a ttyd path is a static asset that never changes while ttyd runs.
+/// The HTML page and the `/token` endpoint are always fetched fresh.
+fn is_static_asset(path: &str) -> bool {
+    let ext = path.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+    matches!(
+        ext,
+        "js" | "css" | "wasm" | "map" | "woff" | "woff2" | "ttf" | "png" | "svg" | "ico"
+    )
 }
 
-/// Proxy an HTTP request to the local ttyd instance.
-async fn proxy_ttyd_request(
+/// Proxy an HTTP request to the local ttyd instance listening on `ttyd_port`.
+/// Static assets are served from memory after the first fetch.
+pub(crate) async fn proxy_ttyd_request(
     state: &AppState,
+    ttyd_port: u16,
+    secret: &str,
     path: &str,
-) -> Result<impl IntoResponse, StatusCode> {
-    let ttyd_port = state.settings.ttyd.port;
+) -> Result<Response, StatusCode> {
+    let cacheable = is_static_asset(path);
+    if cacheable {
+        let cached = state.ttyd_assets.lock().unwrap().get(path).cloned();
+        if let Some(asset) = cached {
+            return Ok(asset_response(StatusCode::OK, &asset.content_type, asset.body, true));
+        }
+    }
+
     let url = format!("http://127.0.0.1:{ttyd_port}{path}");
 
     let mut req = state.http_client.get(&url);
 
     // Add Basic Auth if secret is configured
-    if !state.settings.auth.secret.is_empty() {
-        req = req.basic_auth("nomadflow", Some(&state.settings.auth.secret));
+    if !secret.is_empty() {
+        req = req.basic_auth("nomadflow", Some(secret));
     }
 
     let resp = req.send().await.map_err(|e| {
@@ -136,11 +314,28 @@ async fn proxy_ttyd_request(
         StatusCode::BAD_GATEWAY
     })?;
 
-    Ok(Response::builder()
+    let cache = cacheable && status.is_success();
+    if cache {
+        state.ttyd_assets.lock().unwrap().insert(
+            path.to_string(),
+            CachedAsset {
+                content_type: content_type.clone(),
+                body: bytes.clone(),
+            },
+        );
+    }
+
+    Ok(asset_response(status, &content_type, bytes, cache))
+}
+
+fn asset_response(status: StatusCode, content_type: &str, body: Bytes, cache: bool) -> Response {
+    let cache_control = if cache { ASSET_CACHE_CONTROL } else { "no-cache" };
+    Response::builder()
         .status(status)
         .header(header::CONTENT_TYPE, content_type)
-        .body(Body::from(bytes))
-        .unwrap())
+        .header(header::CACHE_CONTROL, cache_control)
+        .body(Body::from(body))
+        .unwrap()
 }
 
 pub fn ws_router() -> Router<Arc<AppState>> {
@@ -152,3 +347,29 @@ pub fn http_proxy_router() -> Router<Arc<AppState>> {
         .route("/terminal", get(terminal_html_proxy))
         .route("/terminal/{*path}", get(terminal_asset_proxy))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_static_asset() {
+        assert!(is_static_asset("/js/app.js"));
+        assert!(is_static_asset("/favicon.ico"));
+        assert!(is_static_asset("/wasm/term.wasm"));
+        assert!(!is_static_asset("/"));
+        assert!(!is_static_asset("/token"));
+        assert!(!is_static_asset("/index.html"));
+    }
+
+    #[test]
+    fn test_native_page_assets_are_local() {
+        assert!(!TERMINAL_PAGE.contains("https://"));
+        for (name, ..) in XTERM_ASSETS {
+            assert!(TERMINAL_PAGE.contains(&format!("\"/terminal/{name}\"")));
+            assert_eq!(native_asset(name).unwrap().status(), StatusCode::OK);
+        }
+        assert!(native_asset("xterm/missing.js").is_none());
+        assert!(native_asset("../Cargo.toml").is_none());
+    }
+}
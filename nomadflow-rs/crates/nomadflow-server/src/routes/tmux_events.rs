@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing::post,
+    Json, Router,
+};
+use serde::Deserialize;
+use subtle::ConstantTimeEq;
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{
+    ListTmuxEventsRequest, ListTmuxEventsResponse, ServerEvent, TmuxEventKind,
+};
+
+use crate::state::AppState;
+use crate::suspend;
+use crate::tmux_hooks::{parse_kind, HOOK_PATH};
+
+const DEFAULT_LIMIT: usize = 20;
+
+#[derive(Deserialize)]
+struct HookQuery {
+    event: String,
+    /// tmux ids, `$1` and `@3`.
+    session: String,
+    #[serde(default)]
+    window: String,
+    key: String,
+}
+
+/// Callback of the tmux hooks, authenticated by the key they were installed with.
+async fn tmux_hook(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HookQuery>,
+) -> Result<StatusCode, ApiError> {
+    if !bool::from(query.key.as_bytes().ct_eq(state.hook_key.as_bytes())) {
+        return Err(ApiError::new(ErrorCode::Forbidden, "Invalid hook key"));
+    }
+    let Some(kind) = parse_kind(&query.event) else {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            format!("Unknown tmux event '{}'", query.event),
+        ));
+    };
+    // Hooks are global to the tmux server: the ids of other sessions do not
+    // resolve. A silent shell prompt is no news, a silent program may wait
+    // for input
+    let Some(window) = state
+        .tmux
+        .window_name_by_id(&query.session, &query.window)
+        .await
+    else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+    if !(kind == TmuxEventKind::PaneIdle && state.tmux.is_shell_idle(&window).await) {
+        let event = state.tmux_events.record(kind, &window);
+        state.events.publish(ServerEvent::WindowChanged(event));
+        if kind == TmuxEventKind::ClientAttached {
+            suspend::resume(&state, &window).await;
+        }
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_tmux_events(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ListTmuxEventsRequest>,
+) -> Json<ListTmuxEventsResponse> {
+    let mut page = request.page;
+    page.limit = Some(page.limit.unwrap_or(DEFAULT_LIMIT));
+    let (events, total) = page.apply(state.tmux_events.recent(), |e| &e.window);
+    Json(ListTmuxEventsResponse { events, total })
+}
+
+/// The hook callback, outside the API auth: tmux has no secret to send.
+pub fn hook_router() -> Router<Arc<AppState>> {
+    Router::new().route(HOOK_PATH, post(tmux_hook))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/list-tmux-events", post(list_tmux_events))
+}
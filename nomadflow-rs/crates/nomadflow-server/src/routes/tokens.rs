@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get},
+    Json, Router,
+};
+use tracing::info;
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{
+    CreateTokenRequest, CreateTokenResponse, ListTokensResponse, RevokeTokenResponse,
+};
+
+use crate::shares::now_secs;
+use crate::state::AppState;
+
+async fn list_tokens(State(state): State<Arc<AppState>>) -> Json<ListTokensResponse> {
+    Json(ListTokensResponse {
+        tokens: state.tokens.list(),
+    })
+}
+
+/// Issue a named token; it is returned this once only.
+async fn create_token(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CreateTokenRequest>,
+) -> Result<Json<CreateTokenResponse>, ApiError> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "Token name is required",
+        ));
+    }
+    if request.scopes.is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "At least one scope is required",
+        ));
+    }
+    let (token, info) = state.tokens.create(name, &request.scopes, now_secs());
+    info!(id = %info.id, name = %info.name, scopes = ?info.scopes, "API token created");
+    Ok(Json(CreateTokenResponse { token, info }))
+}
+
+async fn revoke_token(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Json<RevokeTokenResponse> {
+    let revoked = state.tokens.revoke(&id);
+    if revoked {
+        info!(%id, "API token revoked");
+    }
+    Json(RevokeTokenResponse { revoked })
+}
+
+/// Managed with the server secret only, see [`crate::auth::required_scopes`].
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/tokens", get(list_tokens).post(create_token))
+        .route("/api/tokens/{id}", delete(revoke_token))
+}
@@ -0,0 +1,15 @@
+use std::sync::Arc;
+
+use axum::{extract::State, routing::get, Json, Router};
+
+use nomadflow_core::models::TunnelStatus;
+
+use crate::state::AppState;
+
+async fn tunnel_status(State(state): State<Arc<AppState>>) -> Json<TunnelStatus> {
+    Json(state.tunnel.lock().unwrap().clone())
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/tunnel", get(tunnel_status))
+}
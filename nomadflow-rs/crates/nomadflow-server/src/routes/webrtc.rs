@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{RawQuery, State},
+    http::HeaderMap,
+    routing::post,
+    Json, Router,
+};
+use tracing::warn;
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{WebRtcAnswerResponse, WebRtcOfferRequest};
+
+use crate::auth::device_id;
+use crate::state::AppState;
+
+/// Negotiate a terminal session over a WebRTC data channel.
+async fn offer(
+    State(state): State<Arc<AppState>>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+    Json(request): Json<WebRtcOfferRequest>,
+) -> Result<Json<WebRtcAnswerResponse>, ApiError> {
+    if !state.settings.webrtc.enabled {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            "WebRTC transport is disabled",
+        ));
+    }
+    let device = device_id(&state, &headers, query.as_deref());
+    match crate::rtc::answer(state.clone(), request.sdp, device).await {
+        Ok(sdp) => Ok(Json(WebRtcAnswerResponse { sdp })),
+        Err(e) => {
+            warn!("WebRTC negotiation failed: {e}");
+            Err(ApiError::new(
+                ErrorCode::InvalidRequest,
+                format!("WebRTC negotiation failed: {e}"),
+            ))
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/webrtc/offer", post(offer))
+}
@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::limits::foreground_group;
+use nomadflow_core::models::{
+    ActivityAction, FeatureOutputRequest, FeatureOutputResponse, FeatureProcessRequest,
+    FeatureProcessResponse, WindowState, WindowStatusResponse,
+};
+use nomadflow_core::services::tmux::is_idle_command;
+use nomadflow_core::shell::run;
+
+use crate::state::AppState;
+use crate::suspend;
+
+/// Time given to a program to exit before its state is reported again.
+const STOP_GRACE: Duration = Duration::from_millis(500);
+/// Lines of output returned when the client does not ask for a number.
+const DEFAULT_OUTPUT_LINES: u32 = 200;
+/// Most lines of output a client may ask for.
+const MAX_OUTPUT_LINES: u32 = 10_000;
+
+/// What runs in each window of the session and when it last did something,
+/// so that clients can offer to go back where the work is.
+async fn window_status(State(state): State<Arc<AppState>>) -> Json<WindowStatusResponse> {
+    let activity: HashMap<String, u64> = state
+        .tmux
+        .window_activity()
+        .await
+        .into_iter()
+        .map(|(name, at, _)| (name, at))
+        .collect();
+
+    let mut windows = Vec::new();
+    for window in state.tmux.list_windows().await {
+        let command = state.tmux.get_pane_command(&window.name).await;
+        let (repo_path, feature_name) = match window.name.split_once(':') {
+            Some((prefix, feature)) => match state.window_names.repo_path(prefix) {
+                Some(repo) => (Some(repo), Some(feature.to_string())),
+                None => (None, None),
+            },
+            None => (None, None),
+        };
+        windows.push(WindowState {
+            idle: is_idle_command(command.as_deref()),
+            last_activity: activity.get(&window.name).copied().unwrap_or(0),
+            has_unread: window.activity,
+            window: window.name,
+            repo_path,
+            feature_name,
+            command,
+        });
+    }
+    sort_busiest_first(&mut windows);
+    Json(WindowStatusResponse { windows })
+}
+
+/// Busy windows first, then the most recently active.
+fn sort_busiest_first(windows: &mut [WindowState]) {
+    windows.sort_by_key(|w| (w.idle, std::cmp::Reverse(w.last_activity)));
+}
+
+/// The window of a feature, which must be open.
+async fn feature_window(
+    state: &AppState,
+    repo_path: &str,
+    feature_name: &str,
+) -> Result<String, ApiError> {
+    let win_name = state.window_names.window_name(repo_path, feature_name);
+    if !state.tmux.window_exists(&win_name).await {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("No terminal open for '{feature_name}'"),
+        ));
+    }
+    Ok(win_name)
+}
+
+async fn process_state(state: &AppState, window: &str) -> FeatureProcessResponse {
+    let pgid = match state.tmux.pane_pid(window).await {
+        Some(pid) => foreground_group(pid).await,
+        None => None,
+    };
+    FeatureProcessResponse {
+        window: window.to_string(),
+        running: pgid.is_some(),
+        command: state.tmux.get_pane_command(window).await,
+        pgid,
+        suspended: state.suspender.is_suspended(window),
+    }
+}
+
+/// What runs in the foreground of a feature's window.
+async fn feature_process(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<FeatureProcessRequest>,
+) -> Result<Json<FeatureProcessResponse>, ApiError> {
+    let win_name = feature_window(&state, &request.repo_path, &request.feature_name).await?;
+    Ok(Json(process_state(&state, &win_name).await))
+}
+
+/// Press Ctrl-C in a feature's window, to stop the program running there
+/// the way it expects. Does nothing when the shell waits for input.
+async fn interrupt_process(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<FeatureProcessRequest>,
+) -> Result<Json<FeatureProcessResponse>, ApiError> {
+    let win_name = feature_window(&state, &request.repo_path, &request.feature_name).await?;
+    let current = process_state(&state, &win_name).await;
+    if !current.running {
+        return Ok(Json(current));
+    }
+    // A stopped program would only see the interrupt once continued
+    suspend::resume(&state, &win_name).await;
+    if !state.tmux.send_keys(&win_name, "C-c", false).await {
+        return Err(ApiError::new(
+            ErrorCode::TmuxUnavailable,
+            format!("Failed to send Ctrl-C to window '{win_name}'"),
+        ));
+    }
+    state.activity.record(
+        ActivityAction::StopProcess,
+        &request.repo_path,
+        &request.feature_name,
+        None,
+    );
+    tokio::time::sleep(STOP_GRACE).await;
+    Ok(Json(process_state(&state, &win_name).await))
+}
+
+/// Kill the process group running in the foreground of a feature's window,
+/// for programs that ignore Ctrl-C. The window's shell is kept.
+async fn kill_process(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<FeatureProcessRequest>,
+) -> Result<Json<FeatureProcessResponse>, ApiError> {
+    let win_name = feature_window(&state, &request.repo_path, &request.feature_name).await?;
+    let current = process_state(&state, &win_name).await;
+    let Some(pgid) = current.pgid else {
+        return Ok(Json(current));
+    };
+    // Forget the suspension, the group is going away
+    suspend::resume(&state, &win_name).await;
+    let result = run(&format!("kill -s KILL -- -{pgid}"), None).await;
+    if !result.success() {
+        return Err(ApiError::new(
+            ErrorCode::CommandFailed,
+            format!(
+                "Failed to kill process group {pgid}: {}",
+                result.stderr.trim()
+            ),
+        ));
+    }
+    state.activity.record(
+        ActivityAction::StopProcess,
+        &request.repo_path,
+        &request.feature_name,
+        None,
+    );
+    tokio::time::sleep(STOP_GRACE).await;
+    Ok(Json(process_state(&state, &win_name).await))
+}
+
+/// The last lines of a feature's window, so that clients can show what a
+/// build or test run printed without opening the terminal.
+async fn feature_output(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<FeatureOutputRequest>,
+) -> Result<Json<FeatureOutputResponse>, ApiError> {
+    let lines = request.lines.unwrap_or(DEFAULT_OUTPUT_LINES);
+    if !(1..=MAX_OUTPUT_LINES).contains(&lines) {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            format!("lines must be between 1 and {MAX_OUTPUT_LINES}"),
+        ));
+    }
+    let win_name = feature_window(&state, &request.repo_path, &request.feature_name).await?;
+    let Some(output) = state.tmux.capture_pane(&win_name, lines).await else {
+        return Err(ApiError::new(
+            ErrorCode::TmuxUnavailable,
+            format!("Failed to capture window '{win_name}'"),
+        ));
+    };
+    Ok(Json(FeatureOutputResponse {
+        window: win_name,
+        output,
+    }))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/window-status", get(window_status))
+        .route("/api/feature-process", post(feature_process))
+        .route("/api/feature-process/interrupt", post(interrupt_process))
+        .route("/api/feature-process/kill", post(kill_process))
+        .route("/api/feature-output", post(feature_output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_busy_windows_come_first() {
+        let window = |name: &str, idle, last_activity| WindowState {
+            window: name.to_string(),
+            repo_path: None,
+            feature_name: None,
+            command: None,
+            idle,
+            last_activity,
+            has_unread: false,
+        };
+        let mut windows = vec![
+            window("a:recent", true, 300),
+            window("a:tests", false, 100),
+            window("a:old", true, 50),
+            window("a:build", false, 200),
+        ];
+        sort_busiest_first(&mut windows);
+        let names: Vec<&str> = windows.iter().map(|w| w.window.as_str()).collect();
+        assert_eq!(names, ["a:build", "a:tests", "a:recent", "a:old"]);
+    }
+
+    fn tmux_available() -> bool {
+        std::process::Command::new("which")
+            .arg("tmux")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Wait for the foreground of `window` to be `running` or not.
+    async fn wait_running(state: &AppState, window: &str, running: bool) -> bool {
+        for _ in 0..100 {
+            if process_state(state, window).await.running == running {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_stop_process() {
+        if !tmux_available() {
+            eprintln!("Skipping tmux test: tmux not available");
+            return;
+        }
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = nomadflow_core::config::Settings {
+            paths: nomadflow_core::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        settings.tmux.session = format!("nf-test-windows-{}", std::process::id());
+        let state = Arc::new(AppState::new(settings));
+        let request = |feature: &str| {
+            Json(FeatureProcessRequest {
+                repo_path: "/srv/app".to_string(),
+                feature_name: feature.to_string(),
+            })
+        };
+
+        let err = kill_process(State(state.clone()), request("missing"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+        let err = interrupt_process(State(state.clone()), request("missing"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+
+        state.tmux.ensure_session().await.unwrap();
+        let window = state.window_names.window_name("/srv/app", "work");
+        state.tmux.create_window(&window, None).await.unwrap();
+
+        // Nothing to stop while the shell waits for input
+        assert!(wait_running(&state, &window, false).await);
+        let idle = kill_process(State(state.clone()), request("work"))
+            .await
+            .unwrap()
+            .0;
+        assert!(!idle.running);
+        assert_eq!(idle.pgid, None);
+        let idle = interrupt_process(State(state.clone()), request("work"))
+            .await
+            .unwrap()
+            .0;
+        assert!(!idle.running);
+
+        state.tmux.type_command(&window, "sleep 600").await;
+        assert!(wait_running(&state, &window, true).await);
+        interrupt_process(State(state.clone()), request("work"))
+            .await
+            .unwrap();
+        assert!(wait_running(&state, &window, false).await);
+
+        // Killed when it ignores Ctrl-C, keeping the shell
+        state
+            .tmux
+            .type_command(&window, "sh -c 'trap \"\" INT; sleep 600'")
+            .await;
+        assert!(wait_running(&state, &window, true).await);
+        let running = interrupt_process(State(state.clone()), request("work"))
+            .await
+            .unwrap()
+            .0;
+        assert!(running.running);
+        kill_process(State(state.clone()), request("work"))
+            .await
+            .unwrap();
+        assert!(wait_running(&state, &window, false).await);
+        assert!(state.tmux.window_exists(&window).await);
+
+        run(
+            &format!("tmux kill-session -t \"{}\"", state.settings.tmux.session),
+            None,
+        )
+        .await;
+    }
+}
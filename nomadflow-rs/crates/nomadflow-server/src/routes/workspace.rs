@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use axum::{extract::State, routing::post, Json, Router};
+
+use nomadflow_core::api_error::{ApiError, ErrorCode};
+use nomadflow_core::models::{WorkspaceDescriptor, WorkspaceDescriptorRequest};
+use nomadflow_core::workspace::describe;
+
+use crate::state::AppState;
+
+/// How to open a feature's worktree in an editor, in one call.
+async fn workspace_descriptor(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<WorkspaceDescriptorRequest>,
+) -> Result<Json<WorkspaceDescriptor>, ApiError> {
+    let features = state.git.list_features(&request.repo_path).await?;
+    let feature = features
+        .into_iter()
+        .find(|f| f.name == request.feature_name)
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::FeatureNotFound,
+                format!("Feature '{}' not found", request.feature_name),
+            )
+        })?;
+
+    describe(
+        &request.repo_path,
+        &feature,
+        &state.settings.editor.ssh_host,
+    )
+    .map(Json)
+    .map_err(ApiError::from)
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/workspace-descriptor", post(workspace_descriptor))
+}
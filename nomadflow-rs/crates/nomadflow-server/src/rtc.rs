@@ -0,0 +1,193 @@
+//! Experimental terminal transport over a WebRTC data channel.
+//!
+//! The client posts an SDP offer to `/api/webrtc/offer` (through the relay
+//! tunnel when the server is public, which makes it the signaling path) and
+//! opens a `tty` data channel. Its messages are the ttyd WebSocket messages,
+//! bridged to the terminal like the WebSocket proxy does. A direct UDP path
+//! avoids the two TCP hops of the relay, and ICE keeps the session across
+//! network changes when it can.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite;
+use tracing::{info, warn};
+use webrtc::api::APIBuilder;
+use webrtc::data_channel::data_channel_message::DataChannelMessage;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+
+use nomadflow_core::models::SessionTransport;
+use nomadflow_core::services::ttyd::TerminalTarget;
+
+use crate::routes::terminal::connect_terminal;
+use crate::shares::now_secs;
+use crate::state::AppState;
+
+/// Label of the data channel carrying the terminal.
+const CHANNEL_LABEL: &str = "tty";
+/// Time the client has to open the data channel after the answer.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Answer the client's `offer` and bridge the data channel it opens to ttyd.
+/// Returns the SDP answer, with the server's ICE candidates.
+pub async fn answer(
+    state: Arc<AppState>,
+    offer: String,
+    device_id: Option<String>,
+) -> Result<String, webrtc::Error> {
+    let config = RTCConfiguration {
+        ice_servers: vec![RTCIceServer {
+            urls: state.settings.webrtc.stun_servers.clone(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let api = APIBuilder::new().build();
+    let pc = Arc::new(api.new_peer_connection(config).await?);
+    let opened = Arc::new(AtomicBool::new(false));
+
+    let weak = Arc::downgrade(&pc);
+    let channel_state = state.clone();
+    let channel_opened = opened.clone();
+    pc.on_data_channel(Box::new(move |dc: Arc<RTCDataChannel>| {
+        let state = channel_state.clone();
+        let weak = weak.clone();
+        let opened = channel_opened.clone();
+        let device_id = device_id.clone();
+        Box::pin(async move {
+            if dc.label() != CHANNEL_LABEL {
+                warn!(label = dc.label(), "Ignoring unexpected WebRTC data channel");
+                return;
+            }
+            let Some(pc) = weak.upgrade() else {
+                return;
+            };
+            opened.store(true, Ordering::Relaxed);
+
+            // Registered before returning, so that no early message is lost
+            let (tx, rx) = mpsc::unbounded_channel();
+            let on_message = tx.clone();
+            dc.on_message(Box::new(move |msg: DataChannelMessage| {
+                let _ = on_message.send(Some(msg));
+                Box::pin(async {})
+            }));
+            dc.on_close(Box::new(move || {
+                let _ = tx.send(None);
+                Box::pin(async {})
+            }));
+            tokio::spawn(bridge(state, pc, dc, rx, device_id));
+        })
+    }));
+
+    let weak = Arc::downgrade(&pc);
+    pc.on_peer_connection_state_change(Box::new(move |s: RTCPeerConnectionState| {
+        let weak: Weak<RTCPeerConnection> = weak.clone();
+        Box::pin(async move {
+            // Disconnected may recover, e.g. when the phone changes network
+            if s == RTCPeerConnectionState::Failed {
+                warn!("WebRTC connection failed");
+                if let Some(pc) = weak.upgrade() {
+                    let _ = pc.close().await;
+                }
+            }
+        })
+    }));
+
+    pc.set_remote_description(RTCSessionDescription::offer(offer)?)
+        .await?;
+    let answer = pc.create_answer(None).await?;
+    // Candidates are sent in the answer, not trickled
+    let mut gathered = pc.gathering_complete_promise().await;
+    pc.set_local_description(answer).await?;
+    let _ = gathered.recv().await;
+    let sdp = pc
+        .local_description()
+        .await
+        .map(|description| description.sdp)
+        .unwrap_or_default();
+
+    let abandoned = pc.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(CONNECT_TIMEOUT).await;
+        if !opened.load(Ordering::Relaxed) {
+            info!("WebRTC data channel never opened, closing the connection");
+            let _ = abandoned.close().await;
+        }
+    });
+    Ok(sdp)
+}
+
+/// Relay messages between the data channel and the terminal until either side
+/// ends.
+async fn bridge(
+    state: Arc<AppState>,
+    pc: Arc<RTCPeerConnection>,
+    dc: Arc<RTCDataChannel>,
+    mut from_client: mpsc::UnboundedReceiver<Option<DataChannelMessage>>,
+    device_id: Option<String>,
+) {
+    let target = TerminalTarget::for_session(&state.settings);
+    let Some((mut ttyd_tx, mut ttyd_rx)) = connect_terminal(&target).await else {
+        let _ = pc.close().await;
+        return;
+    };
+    let _ws_session = state.metrics.ws_session();
+    let session = state
+        .sessions
+        .open(SessionTransport::WebRtc, device_id, now_secs());
+    let traffic = session.traffic();
+    let count = |counter: &AtomicU64, bytes: usize| {
+        counter.fetch_add(bytes as u64, Ordering::Relaxed);
+    };
+    info!("WebRTC terminal session started");
+
+    let client_to_ttyd = async {
+        while let Some(Some(msg)) = from_client.recv().await {
+            count(&traffic.received, msg.data.len());
+            let msg = if msg.is_string {
+                tungstenite::Message::Text(String::from_utf8_lossy(&msg.data).into_owned().into())
+            } else {
+                tungstenite::Message::Binary(msg.data)
+            };
+            if ttyd_tx.send(msg).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    let ttyd_to_client = async {
+        while let Some(Ok(msg)) = ttyd_rx.next().await {
+            let sent = match msg {
+                tungstenite::Message::Text(text) => {
+                    count(&traffic.sent, text.len());
+                    dc.send_text(text.to_string()).await
+                }
+                tungstenite::Message::Binary(data) => {
+                    count(&traffic.sent, data.len());
+                    dc.send(&data).await
+                }
+                tungstenite::Message::Close(_) => break,
+                _ => continue,
+            };
+            if sent.is_err() {
+                break;
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = client_to_ttyd => {},
+        _ = ttyd_to_client => {},
+    }
+
+    info!("WebRTC terminal session ended");
+    let _ = pc.close().await;
+}
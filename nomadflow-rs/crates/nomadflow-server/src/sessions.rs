@@ -0,0 +1,178 @@
+//! Terminal sessions bridged to ttyd, and the bytes they carry, so that users
+//! on metered connections can see what the terminal costs them.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+use nomadflow_core::models::{
+    DeviceTrafficInfo, ListSessionsResponse, SessionTransport, TerminalSessionInfo,
+};
+use nomadflow_ws::Traffic;
+
+struct Session {
+    transport: SessionTransport,
+    device_id: Option<String>,
+    started_at: u64,
+    traffic: Arc<Traffic>,
+}
+
+/// Sessions and bytes of a device, once its sessions ended.
+#[derive(Default, Clone, Copy)]
+struct Totals {
+    sessions: u64,
+    sent: u64,
+    received: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    open: BTreeMap<u64, Session>,
+    ended: BTreeMap<Option<String>, Totals>,
+}
+
+/// Open terminal sessions and the traffic of each device, in memory only.
+#[derive(Default)]
+pub struct SessionRegistry {
+    inner: Mutex<Inner>,
+}
+
+/// Keeps a session listed until dropped, then adds its bytes to its device.
+pub struct SessionGuard<'a> {
+    registry: &'a SessionRegistry,
+    id: u64,
+    traffic: Arc<Traffic>,
+}
+
+impl SessionGuard<'_> {
+    /// Counters to pass to the bridge.
+    pub fn traffic(&self) -> &Traffic {
+        &self.traffic
+    }
+}
+
+impl Drop for SessionGuard<'_> {
+    fn drop(&mut self) {
+        let mut inner = self.registry.inner.lock().unwrap();
+        if let Some(session) = inner.open.remove(&self.id) {
+            let totals = inner.ended.entry(session.device_id).or_default();
+            totals.sessions += 1;
+            totals.sent += session.traffic.sent.load(Ordering::Relaxed);
+            totals.received += session.traffic.received.load(Ordering::Relaxed);
+        }
+    }
+}
+
+impl SessionRegistry {
+    pub fn open(
+        &self,
+        transport: SessionTransport,
+        device_id: Option<String>,
+        now: u64,
+    ) -> SessionGuard<'_> {
+        let traffic = Arc::new(Traffic::default());
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let id = inner.next_id;
+        inner.open.insert(
+            id,
+            Session {
+                transport,
+                device_id,
+                started_at: now,
+                traffic: traffic.clone(),
+            },
+        );
+        SessionGuard {
+            registry: self,
+            id,
+            traffic,
+        }
+    }
+
+    /// Open sessions, and the traffic of every device with open or ended ones.
+    pub fn list(&self) -> ListSessionsResponse {
+        let inner = self.inner.lock().unwrap();
+        let mut devices = inner.ended.clone();
+        let sessions = inner
+            .open
+            .iter()
+            .map(|(&id, session)| {
+                let info = TerminalSessionInfo {
+                    id,
+                    transport: session.transport,
+                    device_id: session.device_id.clone(),
+                    started_at: session.started_at,
+                    bytes_sent: session.traffic.sent.load(Ordering::Relaxed),
+                    bytes_received: session.traffic.received.load(Ordering::Relaxed),
+                };
+                let totals = devices.entry(info.device_id.clone()).or_default();
+                totals.sessions += 1;
+                totals.sent += info.bytes_sent;
+                totals.received += info.bytes_received;
+                info
+            })
+            .collect();
+        ListSessionsResponse {
+            sessions,
+            devices: devices
+                .into_iter()
+                .map(|(device_id, totals)| DeviceTrafficInfo {
+                    device_id,
+                    sessions: totals.sessions,
+                    bytes_sent: totals.sent,
+                    bytes_received: totals.received,
+                })
+                .collect(),
+        }
+    }
+
+    /// Bytes sent and received by all sessions since the server started.
+    pub fn totals(&self) -> (u64, u64) {
+        self.list()
+            .devices
+            .iter()
+            .fold((0, 0), |(sent, received), d| {
+                (sent + d.bytes_sent, received + d.bytes_received)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traffic_outlives_sessions() {
+        let registry = SessionRegistry::default();
+        let phone = registry.open(SessionTransport::WebSocket, Some("abcd1234".into()), 100);
+        phone.traffic().sent.fetch_add(1000, Ordering::Relaxed);
+        phone.traffic().received.fetch_add(10, Ordering::Relaxed);
+        {
+            let guest = registry.open(SessionTransport::Share, None, 200);
+            guest.traffic().sent.fetch_add(50, Ordering::Relaxed);
+
+            let listed = registry.list();
+            assert_eq!(listed.sessions.len(), 2);
+            assert_eq!(listed.sessions[0].device_id.as_deref(), Some("abcd1234"));
+            assert_eq!(listed.sessions[0].bytes_sent, 1000);
+            assert_eq!(listed.sessions[1].transport, SessionTransport::Share);
+        }
+        drop(phone);
+        let again = registry.open(SessionTransport::WebRtc, Some("abcd1234".into()), 300);
+        again.traffic().sent.fetch_add(1, Ordering::Relaxed);
+
+        let listed = registry.list();
+        assert_eq!(listed.sessions.len(), 1);
+        let phone = listed
+            .devices
+            .iter()
+            .find(|d| d.device_id.as_deref() == Some("abcd1234"))
+            .unwrap();
+        assert_eq!(phone.sessions, 2);
+        assert_eq!(phone.bytes_sent, 1001);
+        assert_eq!(phone.bytes_received, 10);
+        assert_eq!(registry.totals(), (1051, 10));
+    }
+}
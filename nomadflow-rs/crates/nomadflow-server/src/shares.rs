@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use subtle::ConstantTimeEq;
+
+use nomadflow_core::models::ShareInfo;
+use nomadflow_core::services::tmux::TmuxService;
+use nomadflow_core::services::ttyd::TtydService;
+
+/// A live guest share: a dedicated ttyd attached to a tmux session that
+/// only contains the shared window.
+pub struct Share {
+    pub info: ShareInfo,
+    pub token: String,
+    /// Local port of the share's ttyd.
+    pub port: u16,
+    /// Basic Auth password of the share's ttyd.
+    pub credential: String,
+    pub tmux_session: String,
+    pub ttyd: TtydService,
+}
+
+impl Share {
+    /// Stop the ttyd (dropping connected guests) and remove the linked session.
+    pub async fn close(mut self) {
+        self.ttyd.stop().await;
+        TmuxService::new(&self.tmux_session).kill_session().await;
+    }
+}
+
+/// Where to proxy a guest request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareTarget {
+    pub port: u16,
+    pub credential: String,
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Active share links, keyed by id.
+#[derive(Default)]
+pub struct ShareRegistry {
+    shares: Mutex<HashMap<String, Share>>,
+}
+
+impl ShareRegistry {
+    pub fn insert(&self, share: Share) {
+        self.shares
+            .lock()
+            .unwrap()
+            .insert(share.info.id.clone(), share);
+    }
+
+    /// Resolve a guest token to its ttyd, if the link exists and has not expired.
+    pub fn resolve(&self, token: &str, now: u64) -> Option<ShareTarget> {
+        let shares = self.shares.lock().unwrap();
+        shares
+            .values()
+            .find(|s| bool::from(s.token.as_bytes().ct_eq(token.as_bytes())))
+            .filter(|s| s.info.expires_at > now)
+            .map(|s| ShareTarget {
+                port: s.port,
+                credential: s.credential.clone(),
+            })
+    }
+
+    /// Unexpired shares, newest first.
+    pub fn list(&self, now: u64) -> Vec<ShareInfo> {
+        let shares = self.shares.lock().unwrap();
+        let mut infos: Vec<ShareInfo> = shares
+            .values()
+            .filter(|s| s.info.expires_at > now)
+            .map(|s| s.info.clone())
+            .collect();
+        infos.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+        infos
+    }
+
+    /// Remove a share so it can be closed.
+    pub fn remove(&self, id: &str) -> Option<Share> {
+        self.shares.lock().unwrap().remove(id)
+    }
+
+    /// Remove every share whose link has expired.
+    pub fn take_expired(&self, now: u64) -> Vec<Share> {
+        let mut shares = self.shares.lock().unwrap();
+        let expired: Vec<String> = shares
+            .values()
+            .filter(|s| s.info.expires_at <= now)
+            .map(|s| s.info.id.clone())
+            .collect();
+        expired.iter().filter_map(|id| shares.remove(id)).collect()
+    }
+
+    /// Remove every share, e.g. on shutdown.
+    pub fn take_all(&self) -> Vec<Share> {
+        self.shares
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, s)| s)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share(id: &str, token: &str, created_at: u64, expires_at: u64) -> Share {
+        Share {
+            info: ShareInfo {
+                id: id.to_string(),
+                repo_path: "/r".to_string(),
+                feature_name: "demo".to_string(),
+                read_only: true,
+                created_at,
+                expires_at,
+            },
+            token: token.to_string(),
+            port: 9000,
+            credential: "cred".to_string(),
+            tmux_session: format!("nf-share-{id}"),
+            ttyd: TtydService::for_session(9000, "nf", "cred", false),
+        }
+    }
+
+    #[test]
+    fn test_resolve_checks_token_and_expiry() {
+        let registry = ShareRegistry::default();
+        registry.insert(share("a", "tok-a", 100, 200));
+
+        assert_eq!(registry.resolve("tok-a", 150).unwrap().port, 9000);
+        assert!(registry.resolve("tok-b", 150).is_none());
+        assert!(registry.resolve("tok-a", 200).is_none());
+    }
+
+    #[test]
+    fn test_list_and_take_expired() {
+        let registry = ShareRegistry::default();
+        registry.insert(share("old", "t1", 100, 150));
+        registry.insert(share("new", "t2", 120, 500));
+        registry.insert(share("mid", "t3", 110, 500));
+
+        let ids: Vec<String> = registry.list(160).into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec!["new", "mid"]);
+
+        let expired = registry.take_expired(160);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].info.id, "old");
+        assert!(registry.remove("new").is_some());
+        assert_eq!(registry.take_all().len(), 1);
+    }
+}
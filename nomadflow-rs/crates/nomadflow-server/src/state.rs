@@ -1,23 +1,166 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::body::Bytes;
+use tokio::sync::{watch, Notify};
 use nomadflow_core::config::Settings;
+use nomadflow_core::models::{TerminalStatus, TunnelStatus};
 use nomadflow_core::services::git::GitService;
 use nomadflow_core::services::tmux::TmuxService;
+use nomadflow_core::services::ttyd::WindowTtyds;
+use nomadflow_core::window_names::WindowNames;
+
+use crate::activity::ActivityLog;
+use crate::bans::BanList;
+use crate::devices::DeviceStore;
+use crate::events::EventBus;
+use crate::identity::ServerIdentity;
+use crate::jobs::JobRegistry;
+use crate::metrics::Metrics;
+use crate::notes::NoteStore;
+use crate::oidc::OidcVerifier;
+use crate::protection::ProtectedBranches;
+use crate::sessions::SessionRegistry;
+use crate::shares::ShareRegistry;
+use crate::suspend::SuspendTracker;
+use crate::tasks::TaskRuns;
+use crate::tmux_hooks::TmuxEventLog;
+use crate::tokens::TokenStore;
+use crate::totp::OtpGuard;
 
 pub struct AppState {
     pub settings: Settings,
     pub git: GitService,
     pub tmux: TmuxService,
+    /// Names of the feature windows, unique across repos.
+    pub window_names: WindowNames,
     pub http_client: reqwest::Client,
+    pub activity: ActivityLog,
+    /// Guest share links and their dedicated ttyd processes.
+    pub shares: ShareRegistry,
+    /// Terminals serving single windows to `/terminal/ws?window=`.
+    pub window_ttyds: WindowTtyds,
+    /// Health of the terminal backend, reported by `/health`. Always ok
+    /// unless [`serve`](crate::serve) supervises a ttyd.
+    pub terminal_status: watch::Receiver<TerminalStatus>,
+    /// Devices that passed the TOTP check.
+    pub devices: DeviceStore,
+    /// Named API tokens given out instead of the secret.
+    pub tokens: TokenStore,
+    /// Checks single sign-on tokens when `[oidc]` is set.
+    pub oidc: Option<OidcVerifier>,
+    /// Notes left on features.
+    pub notes: NoteStore,
+    /// Branches the repos' forges protect.
+    pub protected_branches: ProtectedBranches,
+    pub otp_guard: Mutex<OtpGuard>,
+    /// Device token of local clients, set when TOTP is enabled.
+    pub local_device_token: Option<String>,
+    /// IPs refused after repeated authentication failures.
+    pub bans: BanList,
+    /// Random key proving to the relay that a tunnel leads to this server.
+    pub tunnel_key: String,
+    /// Key signing `/health` and credential-issuing responses. Temporary
+    /// unless [`serve`](crate::serve) loads the saved one.
+    pub identity: ServerIdentity,
+    /// Random key the tmux hooks send back to `/api/tmux-hook`.
+    pub hook_key: String,
+    /// Events reported by the tmux hooks.
+    pub tmux_events: TmuxEventLog,
+    /// Windows whose job was suspended for being idle.
+    pub suspender: SuspendTracker,
+    /// Tasks run as background jobs.
+    pub task_runs: Arc<TaskRuns>,
+    /// Clones and other operations followed through `/api/jobs`.
+    pub jobs: JobRegistry,
+    /// State changes streamed by `/api/events`.
+    pub events: EventBus,
+    /// Public tunnel registration, reported by `/api/tunnel`.
+    pub tunnel: Mutex<TunnelStatus>,
+    /// Woken when the relay announces that it is shutting down.
+    pub relay_shutdown: Notify,
+    /// Request, WebSocket and git counters served at `/metrics`.
+    pub metrics: Metrics,
+    /// Open terminal sessions and the bytes they carried.
+    pub sessions: SessionRegistry,
+    /// Static ttyd assets (JS/CSS/wasm…) keyed by path, fetched once per process.
+    pub ttyd_assets: Mutex<HashMap<String, CachedAsset>>,
+}
+
+/// A ttyd asset kept in memory by the terminal proxy.
+#[derive(Clone)]
+pub struct CachedAsset {
+    pub content_type: String,
+    pub body: Bytes,
+}
+
+fn random_key() -> String {
+    use rand::Rng;
+    rand::rng()
+        .sample_iter(rand::distr::Alphanumeric)
+        .take(32)
+        .map(|b| b as char)
+        .collect()
 }
 
 impl AppState {
     pub fn new(settings: Settings) -> Self {
         let git = GitService::new(&settings);
-        let tmux = TmuxService::new(&settings.tmux.session);
+        let tmux = TmuxService::new(&settings.tmux.session)
+            .with_limits(settings.limits.clone())
+            .with_silence_alert(settings.tmux.idle_alert_seconds);
+        let devices = DeviceStore::load(settings.base_dir().join("devices.json"));
+        let tokens = TokenStore::load(settings.base_dir().join("tokens.json"));
+        let notes = NoteStore::load(settings.base_dir().join("feature-notes.json"));
+        let bans = BanList::load(
+            settings.base_dir().join("bans.json"),
+            settings.auth.ban_max_failures,
+            settings.auth.ban_minutes,
+        );
+        let task_runs = Arc::new(TaskRuns::load(settings.base_dir().join("task-runs")));
+        let local_device_token = if settings.auth.totp_secret.is_empty() {
+            None
+        } else {
+            settings
+                .local_device_token()
+                .inspect_err(|e| tracing::warn!("Failed to create local device token: {e}"))
+                .ok()
+        };
+        let window_names = WindowNames::load(&settings);
+        let window_ttyds = WindowTtyds::new(&settings);
+        let http_client = reqwest::Client::new();
+        let oidc = OidcVerifier::new(&settings.oidc, http_client.clone());
         Self {
             settings,
             git,
             tmux,
-            http_client: reqwest::Client::new(),
+            window_names,
+            http_client,
+            activity: ActivityLog::default(),
+            shares: ShareRegistry::default(),
+            window_ttyds,
+            terminal_status: watch::channel(TerminalStatus::Ok).1,
+            devices,
+            tokens,
+            oidc,
+            notes,
+            protected_branches: ProtectedBranches::default(),
+            otp_guard: Mutex::new(OtpGuard::default()),
+            local_device_token,
+            bans,
+            tunnel_key: random_key(),
+            identity: ServerIdentity::generate(),
+            hook_key: random_key(),
+            tmux_events: TmuxEventLog::default(),
+            suspender: SuspendTracker::default(),
+            task_runs,
+            jobs: JobRegistry::default(),
+            events: EventBus::default(),
+            tunnel: Mutex::new(TunnelStatus::default()),
+            relay_shutdown: Notify::new(),
+            metrics: Metrics::default(),
+            sessions: SessionRegistry::default(),
+            ttyd_assets: Mutex::new(HashMap::new()),
         }
     }
 }
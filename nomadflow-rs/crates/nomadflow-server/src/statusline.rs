@@ -0,0 +1,79 @@
+//! Keeps the status snapshot read by `nomadflow statusline` up to date.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use nomadflow_core::fsutil;
+use nomadflow_core::models::{FeatureSnapshot, StatusSnapshot};
+use nomadflow_core::statusline::SNAPSHOT_FILE;
+
+use crate::shares::now_secs;
+use crate::state::AppState;
+
+/// How often the snapshot is refreshed. Listing the features costs a
+/// `git status` per worktree, so this stays well under [`nomadflow_core::statusline::STALE_SECS`]
+/// without running git constantly.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Current features, busy windows and tunnel state.
+pub async fn snapshot(state: &AppState) -> StatusSnapshot {
+    let mut features = Vec::new();
+    for repo in state.git.list_repos().await.unwrap_or_default() {
+        for feature in state
+            .git
+            .list_features(&repo.path)
+            .await
+            .unwrap_or_default()
+        {
+            features.push(FeatureSnapshot {
+                dirty: feature.dirty,
+                repo: repo.name.clone(),
+                name: feature.name,
+                worktree_path: feature.worktree_path,
+            });
+        }
+    }
+
+    let mut busy_windows = 0;
+    for window in state.tmux.list_windows().await {
+        if !state.tmux.is_shell_idle(&window.name).await {
+            busy_windows += 1;
+        }
+    }
+
+    StatusSnapshot {
+        updated_at: now_secs(),
+        busy_windows,
+        tunnel: state.tunnel.lock().unwrap().state,
+        features,
+    }
+}
+
+/// Write the snapshot every few seconds until shutdown, then remove it so the
+/// status line shows the server is off.
+pub fn spawn_statusline_writer(state: Arc<AppState>, shutdown: CancellationToken) {
+    let path = state.settings.base_dir().join(SNAPSHOT_FILE);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+            let snapshot = snapshot(&state).await;
+            match serde_json::to_string(&snapshot) {
+                Ok(json) => {
+                    if let Err(e) = fsutil::write_atomic(&path, json) {
+                        warn!("Failed to save {}: {e}", path.display());
+                    }
+                }
+                Err(e) => warn!("Failed to serialize status snapshot: {e}"),
+            }
+        }
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_file(fsutil::backup_path(&path)).ok();
+    });
+}
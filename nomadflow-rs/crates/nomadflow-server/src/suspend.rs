@@ -0,0 +1,166 @@
+//! Suspends the foreground job of windows left idle (dev servers, watchers)
+//! and resumes it as soon as the window is looked at again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use nomadflow_core::config::SuspendConfig;
+use nomadflow_core::limits::foreground_group;
+use nomadflow_core::shell::run;
+
+use crate::shares::now_secs;
+use crate::state::AppState;
+
+/// How often windows are checked for idleness and for being viewed again.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct Inner {
+    /// Process group of the suspended job, per window.
+    suspended: HashMap<String, u32>,
+    /// When a client was last seen viewing each window.
+    viewed_at: HashMap<String, u64>,
+}
+
+/// Windows whose job is suspended.
+#[derive(Default)]
+pub struct SuspendTracker {
+    inner: Mutex<Inner>,
+}
+
+impl SuspendTracker {
+    pub fn is_suspended(&self, window: &str) -> bool {
+        self.inner.lock().unwrap().suspended.contains_key(window)
+    }
+
+    fn viewed(&self, window: &str, now: u64) {
+        self.inner
+            .lock()
+            .unwrap()
+            .viewed_at
+            .insert(window.to_string(), now);
+    }
+
+    /// Start of the idle period of a window whose last output was at `activity`.
+    fn idle_since(&self, window: &str, activity: u64) -> u64 {
+        let inner = self.inner.lock().unwrap();
+        inner.viewed_at.get(window).map_or(activity, |&v| v.max(activity))
+    }
+}
+
+/// `command` with `{pgid}` replaced, or the signal sent to the group.
+fn job_command(command: &str, signal: &str, pgid: u32) -> String {
+    if command.is_empty() {
+        format!("kill -s {signal} -- -{pgid}")
+    } else {
+        command.replace("{pgid}", &pgid.to_string())
+    }
+}
+
+async fn suspend(state: &AppState, config: &SuspendConfig, window: &str) {
+    let Some(pid) = state.tmux.pane_pid(window).await else {
+        return;
+    };
+    let Some(pgid) = foreground_group(pid).await else {
+        return;
+    };
+    let result = run(&job_command(&config.command, "STOP", pgid), None).await;
+    if result.success() {
+        info!(%window, pgid, "Suspended idle window");
+        state
+            .suspender
+            .inner
+            .lock()
+            .unwrap()
+            .suspended
+            .insert(window.to_string(), pgid);
+    } else {
+        warn!(%window, "Failed to suspend idle window: {}", result.stderr.trim());
+    }
+}
+
+/// Resume the job of `window` if it was suspended.
+pub async fn resume(state: &AppState, window: &str) {
+    let Some(pgid) = state.suspender.inner.lock().unwrap().suspended.remove(window) else {
+        return;
+    };
+    state.suspender.viewed(window, now_secs());
+    let command = job_command(&state.settings.suspend.resume_command, "CONT", pgid);
+    let result = run(&command, None).await;
+    if result.success() {
+        info!(%window, pgid, "Resumed window");
+    } else {
+        warn!(%window, "Failed to resume window: {}", result.stderr.trim());
+    }
+}
+
+async fn check(state: &AppState, config: &SuspendConfig) {
+    let now = now_secs();
+    let threshold = u64::from(config.idle_minutes) * 60;
+    for (window, activity, viewed) in state.tmux.window_activity().await {
+        if viewed {
+            state.suspender.viewed(&window, now);
+            resume(state, &window).await;
+        } else if !state.suspender.is_suspended(&window)
+            && now.saturating_sub(state.suspender.idle_since(&window, activity)) >= threshold
+        {
+            suspend(state, config, &window).await;
+        }
+    }
+}
+
+/// Suspend idle windows until shutdown, then resume every suspended job so
+/// that none is left stopped behind the server.
+pub fn spawn_idle_suspender(state: Arc<AppState>, shutdown: CancellationToken) {
+    tokio::spawn(async move {
+        let config = state.settings.suspend.clone();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+            check(&state, &config).await;
+        }
+        let windows: Vec<String> = state
+            .suspender
+            .inner
+            .lock()
+            .unwrap()
+            .suspended
+            .keys()
+            .cloned()
+            .collect();
+        for window in windows {
+            resume(&state, &window).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_command() {
+        assert_eq!(job_command("", "STOP", 42), "kill -s STOP -- -42");
+        assert_eq!(
+            job_command("docker compose -p app pause # {pgid}", "STOP", 42),
+            "docker compose -p app pause # 42"
+        );
+    }
+
+    #[test]
+    fn test_idle_since_counts_views() {
+        let tracker = SuspendTracker::default();
+        assert_eq!(tracker.idle_since("app:x", 100), 100);
+        tracker.viewed("app:x", 500);
+        assert_eq!(tracker.idle_since("app:x", 100), 500);
+        assert_eq!(tracker.idle_since("app:x", 900), 900);
+        assert!(!tracker.is_suspended("app:x"));
+    }
+}
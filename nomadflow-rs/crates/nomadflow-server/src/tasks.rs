@@ -0,0 +1,339 @@
+//! Background runs of repo tasks. Each run, its output included, is kept as
+//! `<id>.json` under the runs directory so results outlive the server.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use nomadflow_core::fsutil;
+use nomadflow_core::models::{TaskInfo, TaskRun};
+
+use crate::shares::now_secs;
+
+/// Number of runs kept; older ones are dropped.
+const MAX_RUNS: usize = 50;
+/// Output kept per run. Past it, the beginning is dropped: failures show at the end.
+const MAX_OUTPUT: usize = 256 * 1024;
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    runs: VecDeque<TaskRun>,
+}
+
+/// Background task runs, most recent last.
+pub struct TaskRuns {
+    dir: Option<PathBuf>,
+    inner: Mutex<Inner>,
+}
+
+/// The runs saved in `dir`, oldest first.
+fn read_runs(dir: &Path) -> Vec<TaskRun> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut runs: Vec<TaskRun> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+        .filter_map(|s| serde_json::from_str(&s).ok())
+        .collect();
+    runs.sort_by_key(|r| r.id);
+    runs
+}
+
+impl TaskRuns {
+    /// Load the runs saved in `dir`. Runs cut short by a stop of the server
+    /// are marked finished, without an exit code.
+    pub fn load(dir: PathBuf) -> Self {
+        let mut runs = read_runs(&dir);
+        let excess = runs.len().saturating_sub(MAX_RUNS);
+        for run in runs.drain(..excess) {
+            let _ = std::fs::remove_file(dir.join(format!("{}.json", run.id)));
+        }
+        let store = Self {
+            dir: Some(dir),
+            inner: Mutex::new(Inner::default()),
+        };
+        for run in &mut runs {
+            if run.finished_at.is_none() {
+                run.finished_at = Some(now_secs());
+                store.persist(run);
+            }
+        }
+        let mut inner = store.inner.lock().unwrap();
+        inner.next_id = runs.last().map_or(0, |r| r.id);
+        inner.runs = runs.into();
+        drop(inner);
+        store
+    }
+
+    /// Runs that are never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            dir: None,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    fn persist(&self, run: &TaskRun) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            warn!("Failed to create {}: {e}", dir.display());
+            return;
+        }
+        fsutil::save_json(&dir.join(format!("{}.json", run.id)), run);
+    }
+
+    fn start(&self, task: &TaskInfo, repo_path: &str, feature_name: &str) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let id = inner.next_id;
+        let run = TaskRun {
+            id,
+            task: task.name.clone(),
+            repo_path: repo_path.to_string(),
+            feature_name: feature_name.to_string(),
+            command: task.command.clone(),
+            started_at: now_secs(),
+            finished_at: None,
+            exit_code: None,
+            output: String::new(),
+        };
+        self.persist(&run);
+        inner.runs.push_back(run);
+        while inner.runs.len() > MAX_RUNS {
+            if let (Some(old), Some(dir)) = (inner.runs.pop_front(), &self.dir) {
+                let _ = std::fs::remove_file(dir.join(format!("{}.json", old.id)));
+            }
+        }
+        id
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut TaskRun)) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(run) = inner.runs.iter_mut().find(|r| r.id == id) {
+            f(run);
+        }
+    }
+
+    /// Record the end of a run and save it with its output.
+    fn finish(&self, id: u64, exit_code: Option<i32>) -> Option<TaskRun> {
+        let mut inner = self.inner.lock().unwrap();
+        let run = inner.runs.iter_mut().find(|r| r.id == id)?;
+        run.finished_at = Some(now_secs());
+        run.exit_code = exit_code;
+        self.persist(run);
+        Some(run.clone())
+    }
+
+    /// All runs, most recent first.
+    pub fn list(&self) -> Vec<TaskRun> {
+        let inner = self.inner.lock().unwrap();
+        inner.runs.iter().rev().cloned().collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<TaskRun> {
+        let inner = self.inner.lock().unwrap();
+        inner.runs.iter().find(|r| r.id == id).cloned()
+    }
+
+    /// Drop the finished runs whose file was removed, by `nomadflow gc`.
+    pub fn forget_removed(&self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .runs
+            .retain(|r| r.finished_at.is_none() || dir.join(format!("{}.json", r.id)).exists());
+    }
+}
+
+/// Append `chunk` to `output`, dropping its beginning past [`MAX_OUTPUT`].
+fn append_capped(output: &mut String, chunk: &str) {
+    output.push_str(chunk);
+    if output.len() > MAX_OUTPUT {
+        let mut cut = output.len() - MAX_OUTPUT;
+        while !output.is_char_boundary(cut) {
+            cut += 1;
+        }
+        output.drain(..cut);
+    }
+}
+
+/// The text decoded from `bytes`, leaving an incomplete trailing character.
+fn take_text(bytes: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => bytes.len(),
+    };
+    let text = String::from_utf8_lossy(&bytes[..valid]).into_owned();
+    bytes.drain(..valid);
+    text
+}
+
+/// Run `task` from `worktree` in the background and return the run's id.
+/// `on_finish` is called with the run once it has ended.
+pub fn spawn_run(
+    runs: Arc<TaskRuns>,
+    task: &TaskInfo,
+    repo_path: &str,
+    feature_name: &str,
+    worktree: &str,
+    on_finish: impl FnOnce(TaskRun) + Send + 'static,
+) -> std::io::Result<u64> {
+    // Interleave stderr with stdout, as a terminal would show them
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(format!("exec 2>&1\n{}", task.command))
+        .current_dir(worktree)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let id = runs.start(task, repo_path, feature_name);
+    info!(id, task = %task.name, feature = %feature_name, "Task started");
+
+    let stdout = child.stdout.take();
+    tokio::spawn(async move {
+        if let Some(mut stdout) = stdout {
+            let mut buf = vec![0u8; 8192];
+            // Bytes of a character split across reads
+            let mut pending = Vec::new();
+            loop {
+                match stdout.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        pending.extend_from_slice(&buf[..n]);
+                        let chunk = take_text(&mut pending);
+                        runs.update(id, |run| append_capped(&mut run.output, &chunk));
+                    }
+                }
+            }
+            if !pending.is_empty() {
+                let rest = String::from_utf8_lossy(&pending).into_owned();
+                runs.update(id, |run| append_capped(&mut run.output, &rest));
+            }
+        }
+        let status = child.wait().await.ok();
+        let exit_code = status.and_then(|s| s.code());
+        info!(id, ?exit_code, "Task finished");
+        if let Some(run) = runs.finish(id, exit_code) {
+            on_finish(run);
+        }
+    });
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_capped_keeps_the_end() {
+        let mut output = "a".repeat(MAX_OUTPUT - 2);
+        append_capped(&mut output, "éxyz");
+        assert!(output.len() <= MAX_OUTPUT);
+        assert!(output.ends_with("éxyz"));
+    }
+
+    #[test]
+    fn test_take_text_keeps_split_characters() {
+        let mut bytes = "aé".as_bytes()[..2].to_vec();
+        assert_eq!(take_text(&mut bytes), "a");
+        bytes.push("é".as_bytes()[1]);
+        assert_eq!(take_text(&mut bytes), "é");
+        assert!(bytes.is_empty());
+    }
+
+    fn task(command: &str) -> TaskInfo {
+        TaskInfo {
+            name: "check".to_string(),
+            command: command.to_string(),
+            description: String::new(),
+            background: true,
+        }
+    }
+
+    async fn wait_for(runs: &TaskRuns, id: u64) -> TaskRun {
+        loop {
+            let run = runs.get(id).unwrap();
+            if run.finished_at.is_some() {
+                break run;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_captures_output_and_exit_code() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let runs = Arc::new(TaskRuns::in_memory());
+        let cwd = tmp.path().to_string_lossy().to_string();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let id = spawn_run(
+            runs.clone(),
+            &task("pwd; echo oops >&2; exit 3"),
+            "/r",
+            "f",
+            &cwd,
+            move |run| {
+                let _ = tx.send(run.exit_code);
+            },
+        )
+        .unwrap();
+
+        let run = wait_for(&runs, id).await;
+        assert_eq!(rx.await.unwrap(), Some(3));
+        assert_eq!(run.exit_code, Some(3));
+        assert!(run.output.contains("oops"));
+        assert!(run
+            .output
+            .contains(tmp.path().file_name().unwrap().to_str().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_runs_are_saved_and_reloaded() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("task-runs");
+        let runs = Arc::new(TaskRuns::load(dir.clone()));
+        let cwd = tmp.path().to_string_lossy().to_string();
+        let id = spawn_run(runs.clone(), &task("echo done"), "/r", "f", &cwd, |_| {}).unwrap();
+        wait_for(&runs, id).await;
+        // A run left unfinished by a stop of the server
+        let interrupted = runs.start(&task("sleep 60"), "/r", "f");
+
+        let reloaded = TaskRuns::load(dir);
+        let done = reloaded.get(id).unwrap();
+        assert_eq!(done.exit_code, Some(0));
+        assert_eq!(done.output, "done\n");
+        let interrupted = reloaded.get(interrupted).unwrap();
+        assert!(interrupted.finished_at.is_some());
+        assert_eq!(interrupted.exit_code, None);
+        assert_eq!(reloaded.list()[0].id, interrupted.id);
+        let next = reloaded.start(&task("true"), "/r", "f");
+        assert_eq!(next, interrupted.id + 1);
+    }
+
+    #[test]
+    fn test_old_runs_are_removed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let runs = TaskRuns::load(dir.clone());
+        for _ in 0..MAX_RUNS + 2 {
+            runs.start(&task("true"), "/r", "f");
+        }
+        assert_eq!(runs.list().len(), MAX_RUNS);
+        assert!(!dir.join("1.json").exists());
+        assert!(!dir.join("2.json").exists());
+        assert!(dir.join("3.json").exists());
+    }
+}
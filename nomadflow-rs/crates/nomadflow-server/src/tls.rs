@@ -0,0 +1,117 @@
+//! HTTPS on the API port (`[tls]`), for LAN use without a reverse proxy.
+//! Without a certificate of one's own, a self-signed one is generated once
+//! under `<base_dir>/tls`; clients pin it on first use.
+
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::Arc;
+
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tracing::info;
+
+use nomadflow_core::config::Settings;
+
+/// Names the self-signed certificate is valid for: loopback, the LAN address
+/// and the host given with `--host`.
+fn subject_names(host_override: Option<&str>) -> Vec<String> {
+    let mut names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    let extra = local_ip_address::local_ip()
+        .ok()
+        .map(|ip| ip.to_string())
+        .into_iter()
+        .chain(host_override.map(str::to_string));
+    for name in extra {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Write a self-signed certificate for `names`, its key readable only by the
+/// owner.
+fn generate_self_signed(cert_path: &Path, key_path: &Path, names: Vec<String>) -> io::Result<()> {
+    let certified = rcgen::generate_simple_self_signed(names).map_err(io::Error::other)?;
+    if let Some(dir) = key_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(key_path)?
+        .write_all(certified.key_pair.serialize_pem().as_bytes())?;
+    std::fs::write(cert_path, certified.cert.pem())?;
+    info!(path = %cert_path.display(), "Generated a self-signed TLS certificate");
+    Ok(())
+}
+
+fn invalid(path: &Path, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {e}", path.display()),
+    )
+}
+
+/// The TLS configuration of the API, or `None` when `[tls]` is off.
+pub fn rustls_config(
+    settings: &Settings,
+    host_override: Option<&str>,
+) -> io::Result<Option<RustlsConfig>> {
+    let Some((cert_path, key_path)) = settings.tls_identity() else {
+        return Ok(None);
+    };
+    if settings.tls.cert.is_empty() && !(cert_path.exists() && key_path.exists()) {
+        generate_self_signed(&cert_path, &key_path, subject_names(host_override))?;
+    }
+
+    let certs = CertificateDer::pem_file_iter(&cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| invalid(&cert_path, e))?;
+    let key = PrivateKeyDer::from_pem_file(&key_path).map_err(|e| invalid(&key_path, e))?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(io::Error::other)?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| invalid(&cert_path, e))?;
+    // The terminal WebSockets need HTTP/1.1 upgrades
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Some(RustlsConfig::from_config(Arc::new(config))))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    #[test]
+    fn test_self_signed_certificate_is_reused() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        assert!(rustls_config(&settings, None).unwrap().is_none());
+
+        settings.tls.self_signed = true;
+        assert!(rustls_config(&settings, Some("devbox.lan")).unwrap().is_some());
+        let (cert_path, key_path) = settings.tls_identity().unwrap();
+        let cert = std::fs::read(&cert_path).unwrap();
+        let mode = std::fs::metadata(&key_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // Clients pinned it: the next start must not replace it
+        assert!(rustls_config(&settings, None).unwrap().is_some());
+        assert_eq!(std::fs::read(&cert_path).unwrap(), cert);
+    }
+}
@@ -0,0 +1,177 @@
+//! tmux hooks reporting pane deaths, bells, silences and client attaches back to the
+//! server through `/api/tmux-hook`, kept in an in-memory [`TmuxEventLog`].
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use tracing::warn;
+
+use nomadflow_core::models::{TmuxEvent, TmuxEventKind};
+
+use crate::shares::now_secs;
+use crate::state::AppState;
+
+/// Path the hooks call back.
+pub const HOOK_PATH: &str = "/api/tmux-hook";
+
+/// Number of events kept in memory; older ones are dropped.
+const MAX_EVENTS: usize = 100;
+
+/// The hooks installed, their event name in the callback and whether they
+/// are window hooks (`set-hook -w`). `pane-died` only fires for panes kept
+/// with `remain-on-exit`.
+const HOOKS: &[(&str, &str, bool)] = &[
+    ("pane-died", "paneDied", true),
+    ("alert-bell", "bell", false),
+    ("client-attached", "clientAttached", false),
+    ("alert-silence", "paneIdle", false),
+];
+
+pub fn parse_kind(event: &str) -> Option<TmuxEventKind> {
+    match event {
+        "paneDied" => Some(TmuxEventKind::PaneDied),
+        "bell" => Some(TmuxEventKind::Bell),
+        "clientAttached" => Some(TmuxEventKind::ClientAttached),
+        "paneIdle" => Some(TmuxEventKind::PaneIdle),
+        _ => None,
+    }
+}
+
+/// tmux command run by a hook: a background `curl` to the local API. The
+/// formats are expanded by tmux when the hook fires, into a shell command:
+/// only ids are sent, as window names may hold quotes, and the `$` of the
+/// session id is escaped by `q:`. With `tls`, the certificate is not
+/// checked, as it is often self-signed.
+fn hook_command(event: &str, host: &str, port: u16, tls: bool, key: &str) -> String {
+    let host = match host {
+        "0.0.0.0" | "::" | "" => "127.0.0.1",
+        host => host,
+    };
+    let (scheme, insecure) = if tls { ("https", "-k ") } else { ("http", "") };
+    format!(
+        "run-shell -b 'curl -sf {insecure}-m 2 -o /dev/null -X POST -G \
+         --data-urlencode \"event={event}\" \
+         --data-urlencode \"session=#{{q:session_id}}\" \
+         --data-urlencode \"window=#{{window_id}}\" \
+         --data-urlencode \"key={key}\" \
+         \"{scheme}://{host}:{port}{HOOK_PATH}\"'"
+    )
+}
+
+/// Remove the hooks left by this or an earlier server.
+pub async fn uninstall(state: &AppState) {
+    // Highest index first so the remaining indexes stay valid
+    let mut hooks = state.tmux.global_hooks().await;
+    hooks.reverse();
+    for (entry, command, window) in hooks {
+        if command.contains(HOOK_PATH) && !state.tmux.remove_global_hook(&entry, window).await {
+            warn!(hook = %entry, "Failed to remove tmux hook");
+        }
+    }
+}
+
+/// Install the hooks, replacing stale ones from a previous run whose key is
+/// no longer valid. tmux hooks are global: events of other sessions are
+/// dropped by the callback.
+pub async fn install(state: &AppState) {
+    uninstall(state).await;
+    for (hook, event, window) in HOOKS {
+        let command = hook_command(
+            event,
+            &state.settings.api.host,
+            state.settings.api.port,
+            state.settings.tls_identity().is_some(),
+            &state.hook_key,
+        );
+        if !state.tmux.add_global_hook(hook, &command, *window).await {
+            warn!(%hook, "Failed to install tmux hook");
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    events: VecDeque<TmuxEvent>,
+}
+
+/// In-memory log of the events reported by the tmux hooks.
+#[derive(Default)]
+pub struct TmuxEventLog {
+    inner: Mutex<Inner>,
+}
+
+impl TmuxEventLog {
+    /// Append an event and return it.
+    pub fn record(&self, kind: TmuxEventKind, window: &str) -> TmuxEvent {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let event = TmuxEvent {
+            id: inner.next_id,
+            timestamp: now_secs(),
+            kind,
+            window: window.to_string(),
+        };
+        inner.events.push_back(event.clone());
+        while inner.events.len() > MAX_EVENTS {
+            inner.events.pop_front();
+        }
+        event
+    }
+
+    /// Most recent events first.
+    pub fn recent(&self) -> Vec<TmuxEvent> {
+        let inner = self.inner.lock().unwrap();
+        inner.events.iter().rev().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_command() {
+        let command = hook_command("bell", "0.0.0.0", 8080, false, "k3y");
+        assert!(command.starts_with("run-shell -b 'curl "));
+        assert!(command.ends_with("\"http://127.0.0.1:8080/api/tmux-hook\"'"));
+        assert!(command.contains("\"session=#{q:session_id}\""));
+        assert!(command.contains("\"window=#{window_id}\""));
+        assert!(command.contains("\"key=k3y\""));
+        // One single-quoted argument for run-shell
+        assert_eq!(command.matches('\'').count(), 2);
+
+        let command = hook_command("bell", "192.168.1.5", 9000, false, "k3y");
+        assert!(command.contains("http://192.168.1.5:9000/"));
+
+        let command = hook_command("bell", "0.0.0.0", 8080, true, "k3y");
+        assert!(command.contains("curl -sf -k "));
+        assert!(command.contains("\"https://127.0.0.1:8080/api/tmux-hook\""));
+
+        // What tmux runs for a window whose name would break out of the quotes
+        let name = "x'\"; touch /tmp/pwned; '";
+        let expanded = command
+            .replace("#{session_name}", name)
+            .replace("#{window_name}", name)
+            .replace("#{q:session_id}", "\\$1")
+            .replace("#{window_id}", "@2");
+        assert!(!expanded.contains("pwned"));
+        assert!(expanded.contains("\"session=\\$1\" --data-urlencode \"window=@2\""));
+
+        for (_, event, _) in HOOKS {
+            assert!(parse_kind(event).is_some());
+        }
+    }
+
+    #[test]
+    fn test_event_log_is_newest_first_and_bounded() {
+        let log = TmuxEventLog::default();
+        for i in 0..(MAX_EVENTS + 3) {
+            log.record(TmuxEventKind::Bell, &format!("app:f{i}"));
+        }
+        let events = log.recent();
+        assert_eq!(events.len(), MAX_EVENTS);
+        assert_eq!(events[0].window, format!("app:f{}", MAX_EVENTS + 2));
+        assert_eq!(events[0].id, MAX_EVENTS as u64 + 3);
+    }
+}
@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+
+use nomadflow_core::fsutil::JsonStore;
+use nomadflow_core::models::{ApiTokenInfo, TokenScope};
+
+use crate::devices::{hash_token, random_token};
+
+/// Prefix of API tokens, telling them apart from the server secret in logs
+/// and secret scanners.
+const TOKEN_PREFIX: &str = "nft_";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredToken {
+    #[serde(flatten)]
+    info: ApiTokenInfo,
+    /// SHA-256 of the token; the token itself is never stored.
+    token_hash: String,
+}
+
+/// Named API tokens with scopes, persisted to `tokens.json`.
+pub struct TokenStore {
+    tokens: JsonStore<Vec<StoredToken>>,
+}
+
+impl TokenStore {
+    /// Load the store from `path`, or start empty if it is missing or unreadable.
+    pub fn load(path: PathBuf) -> Self {
+        Self {
+            tokens: JsonStore::load(path),
+        }
+    }
+
+    /// A store that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            tokens: JsonStore::in_memory(),
+        }
+    }
+
+    /// Issue a token and return it; only its hash is kept.
+    pub fn create(&self, name: &str, scopes: &[TokenScope], now: u64) -> (String, ApiTokenInfo) {
+        let random = random_token();
+        let mut unique = Vec::new();
+        for scope in scopes {
+            if !unique.contains(scope) {
+                unique.push(*scope);
+            }
+        }
+        let info = ApiTokenInfo {
+            id: random[..8].to_string(),
+            name: name.to_string(),
+            scopes: unique,
+            created_at: now,
+        };
+        let token = format!("{TOKEN_PREFIX}{random}");
+
+        let mut tokens = self.tokens.lock();
+        tokens.push(StoredToken {
+            info: info.clone(),
+            token_hash: hash_token(&token),
+        });
+        self.tokens.persist(&tokens);
+        (token, info)
+    }
+
+    /// The token `token` belongs to, if it was issued and not revoked.
+    pub fn verify(&self, token: &str) -> Option<ApiTokenInfo> {
+        if !token.starts_with(TOKEN_PREFIX) {
+            return None;
+        }
+        let hash = hash_token(token);
+        self.tokens
+            .lock()
+            .iter()
+            .find(|t| bool::from(t.token_hash.as_bytes().ct_eq(hash.as_bytes())))
+            .map(|t| t.info.clone())
+    }
+
+    /// Issued tokens, newest first.
+    pub fn list(&self) -> Vec<ApiTokenInfo> {
+        let mut infos: Vec<ApiTokenInfo> =
+            self.tokens.lock().iter().map(|t| t.info.clone()).collect();
+        infos.sort_by_key(|t| std::cmp::Reverse(t.created_at));
+        infos
+    }
+
+    /// Revoke a token; requests carrying it are refused from now on.
+    pub fn revoke(&self, id: &str) -> bool {
+        let mut tokens = self.tokens.lock();
+        let before = tokens.len();
+        tokens.retain(|t| t.info.id != id);
+        let revoked = tokens.len() != before;
+        if revoked {
+            self.tokens.persist(&tokens);
+        }
+        revoked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_verify_revoke() {
+        let store = TokenStore::in_memory();
+        let (token, info) = store.create("alice", &[TokenScope::Terminal], 1000);
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(store.verify(&token), Some(info.clone()));
+        assert_eq!(store.verify("nft_other"), None);
+        // The bare hex part is not a token
+        assert_eq!(store.verify(&token[TOKEN_PREFIX.len()..]), None);
+
+        assert_eq!(store.list(), vec![info.clone()]);
+        assert!(store.revoke(&info.id));
+        assert_eq!(store.verify(&token), None);
+        assert!(!store.revoke(&info.id));
+    }
+
+    #[test]
+    fn test_persisted_without_token() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("tokens.json");
+        let (token, info) =
+            TokenStore::load(path.clone()).create("ci", &[TokenScope::ReadOnly], 1000);
+
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(!json.contains(&token));
+        assert_eq!(TokenStore::load(path).verify(&token), Some(info));
+    }
+}
@@ -1,10 +1,19 @@
-use std::time::Duration;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
-use nomadflow_core::config::TunnelConfig;
+use nomadflow_core::config::{Settings, TunnelConfig};
+use nomadflow_core::fsutil;
+use nomadflow_core::models::{ServerEvent, TunnelState, TunnelStatus};
+
+use crate::state::AppState;
 
 type Result<T> = color_eyre::Result<T>;
 
@@ -18,6 +27,10 @@ struct RegisterRequest {
     secret: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     subdomain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<String>,
+    /// Lets the relay check that `port` really leads to this server.
+    tunnel_key: String,
 }
 
 #[derive(Deserialize)]
@@ -25,26 +38,177 @@ struct RegisterResponse {
     subdomain: String,
 }
 
-/// Start a bore tunnel and register with the relay server.
-///
-/// 1. Connect bore client → obtain remote port
-/// 2. POST to relay registration API → receive subdomain
-/// 3. Build public URL
-/// 4. Spawn bore.listen() in background
-pub async fn start_tunnel(
-    local_port: u16,
-    config: &TunnelConfig,
-    shutdown: CancellationToken,
+#[derive(Serialize)]
+struct DomainRequest<'a> {
+    subdomain: &'a str,
+    hostname: &'a str,
+    secret: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+}
+
+/// Attach `config.custom_domain` to the registered tunnel.
+async fn attach_custom_domain(
     http_client: &reqwest::Client,
-) -> Result<TunnelInfo> {
-    info!(
-        relay_host = %config.relay_host,
-        relay_port = config.relay_port,
-        preferred_subdomain = %config.subdomain,
-        "Connecting to tunnel relay…"
-    );
+    config: &TunnelConfig,
+    subdomain: &str,
+) -> Result<()> {
+    let resp = http_client
+        .post(format!("https://{}/_api/domains", config.relay_host))
+        .timeout(Duration::from_secs(10))
+        .json(&DomainRequest {
+            subdomain,
+            hostname: &config.custom_domain,
+            secret: &config.relay_secret,
+            api_key: Some(config.api_key.as_str()).filter(|k| !k.is_empty()),
+        })
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(color_eyre::eyre::eyre!("{status}: {body}"));
+    }
+    Ok(())
+}
+
+/// Client for the relay API, presenting the configured client certificate.
+fn relay_client(settings: &Settings, default: &reqwest::Client) -> Result<reqwest::Client> {
+    let Some((cert_path, key_path)) = settings.relay_client_identity() else {
+        return Ok(default.clone());
+    };
+    let read = |path: &Path| {
+        std::fs::read(path)
+            .map_err(|e| color_eyre::eyre::eyre!("Cannot read {}: {e}", path.display()))
+    };
+    let mut pem = read(&cert_path)?;
+    if key_path != cert_path {
+        pem.push(b'\n');
+        pem.extend(read(&key_path)?);
+    }
+    let identity = reqwest::Identity::from_pem(&pem)
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid relay client certificate: {e}"))?;
+    Ok(reqwest::Client::builder()
+        .use_rustls_tls()
+        .identity(identity)
+        .build()?)
+}
+
+/// Path the relay calls through the tunnel to verify a registration.
+pub const CHALLENGE_PATH: &str = "/_tunnel/challenge";
+
+/// Answer to the relay's registration challenge: hex HMAC-SHA256 of `nonce`
+/// keyed with the tunnel key sent at registration.
+pub fn challenge_response(tunnel_key: &str, nonce: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(tunnel_key.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(nonce.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Path the relay calls through the tunnel to announce it is shutting down.
+pub const SHUTDOWN_PATH: &str = "/_tunnel/shutdown";
 
-    // 1. Connect bore client to the relay's bore server
+/// Age past which a shutdown notice is refused, so that one overheard on the
+/// bore connection cannot be replayed later.
+const SHUTDOWN_NOTICE_MAX_AGE: u64 = 60;
+
+/// Body of the relay's shutdown notice.
+#[derive(Debug, Deserialize)]
+pub struct ShutdownNotice {
+    /// Unix timestamp (seconds) at which the relay sent the notice.
+    pub timestamp: u64,
+    /// Hex HMAC-SHA256 of `shutdown:<timestamp>` keyed with the tunnel key.
+    pub signature: String,
+}
+
+impl ShutdownNotice {
+    /// Whether the relay this server registered with sent the notice, recently.
+    pub fn is_valid(&self, tunnel_key: &str, now: u64) -> bool {
+        let expected = challenge_response(tunnel_key, &format!("shutdown:{}", self.timestamp));
+        now.abs_diff(self.timestamp) <= SHUTDOWN_NOTICE_MAX_AGE
+            && bool::from(self.signature.as_bytes().ct_eq(expected.as_bytes()))
+    }
+}
+
+/// Last registration, persisted to `tunnel.json` in the base dir so that a
+/// restart or a resume from sleep asks the relay for the same subdomain and
+/// bore port instead of changing the public URL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelCache {
+    pub relay_host: String,
+    pub subdomain: String,
+    pub remote_port: u16,
+    pub public_url: String,
+    /// Unix timestamp (seconds).
+    pub registered_at: u64,
+}
+
+/// File name of the [`TunnelCache`] in the base dir.
+pub const CACHE_FILE: &str = "tunnel.json";
+
+impl TunnelCache {
+    pub fn load(path: &Path) -> Option<Self> {
+        fsutil::read_with_backup(path, |s| serde_json::from_str(s).ok()).map(|(cache, _)| cache)
+    }
+
+    pub fn save(&self, path: &Path) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(e) = fsutil::write_atomic(path, json) {
+                    warn!("Failed to save {}: {e}", path.display());
+                }
+            }
+            Err(e) => warn!("Failed to serialize tunnel cache: {e}"),
+        }
+    }
+}
+
+/// Wall-clock time the monotonic clock may lag behind before we assume the
+/// machine was suspended.
+const RESUME_THRESHOLD: Duration = Duration::from_secs(30);
+/// How often the clocks are compared.
+const RESUME_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Longest wait between two reconnection attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+/// Failed re-registrations with a relay before moving to the next one.
+const FAILOVER_ATTEMPTS: u32 = 3;
+
+/// The monotonic clock stops while the machine sleeps, the wall clock does not.
+fn is_resume(wall_elapsed: Duration, monotonic_elapsed: Duration) -> bool {
+    wall_elapsed > monotonic_elapsed + RESUME_THRESHOLD
+}
+
+/// Resolve once the machine resumes from sleep.
+async fn wait_for_resume() {
+    loop {
+        let wall = SystemTime::now();
+        let monotonic = Instant::now();
+        tokio::time::sleep(RESUME_CHECK_INTERVAL).await;
+        if is_resume(wall.elapsed().unwrap_or_default(), monotonic.elapsed()) {
+            return;
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Open the bore connection on `port` (0 = any).
+async fn connect_bore(
+    local_port: u16,
+    config: &TunnelConfig,
+    port: u16,
+) -> Result<bore_cli::client::Client> {
     let secret = if config.relay_secret.is_empty() {
         None
     } else {
@@ -54,18 +218,63 @@ pub async fn start_tunnel(
     // bore Client::new(local_host, local_port, to, remote_port, secret)
     // - `to` = server hostname (bore uses CONTROL_PORT=7835 by default)
     // - `remote_port` = 0 means "assign a random port"
-    let client = tokio::time::timeout(
+    tokio::time::timeout(
         Duration::from_secs(15),
-        bore_cli::client::Client::new("localhost", local_port, &config.relay_host, 0, secret),
+        bore_cli::client::Client::new("localhost", local_port, &config.relay_host, port, secret),
     )
     .await
     .map_err(|_| color_eyre::eyre::eyre!("Bore connection timed out after 15s"))?
-    .map_err(|e| color_eyre::eyre::eyre!("Bore connection failed: {e}"))?;
+    .map_err(|e| color_eyre::eyre::eyre!("Bore connection failed: {e}"))
+}
+
+/// Connect to the relay and register, asking for the subdomain and port of
+/// `cached` when it comes from the same relay.
+///
+/// 1. Connect bore client → obtain remote port
+/// 2. POST to relay registration API → receive subdomain (the relay first
+///    checks through the tunnel that the server knows `tunnel_key`)
+/// 3. Build public URL, attaching the custom domain if one is configured
+async fn register(
+    local_port: u16,
+    config: &TunnelConfig,
+    http_client: &reqwest::Client,
+    tunnel_key: &str,
+    cached: Option<&TunnelCache>,
+) -> Result<(bore_cli::client::Client, TunnelCache)> {
+    let cached = cached.filter(|c| c.relay_host == config.relay_host);
+    let preferred_subdomain = if config.subdomain.is_empty() {
+        cached.map(|c| c.subdomain.clone())
+    } else {
+        Some(config.subdomain.clone())
+    };
+    info!(
+        relay_host = %config.relay_host,
+        relay_port = config.relay_port,
+        preferred_subdomain = ?preferred_subdomain,
+        "Connecting to tunnel relay…"
+    );
+
+    // 1. Connect bore client to the relay's bore server, on the cached port if still free
+    let client = match cached {
+        Some(cached) => match connect_bore(local_port, config, cached.remote_port).await {
+            Ok(client) => client,
+            Err(e) => {
+                info!(port = cached.remote_port, "Cached bore port unavailable ({e}), using a new one");
+                connect_bore(local_port, config, 0).await?
+            }
+        },
+        None => connect_bore(local_port, config, 0).await?,
+    };
 
     let remote_port = client.remote_port();
     info!(remote_port, "Bore tunnel established");
 
     // 2. Register with the relay API to get a subdomain
+    let api_key = if config.api_key.is_empty() {
+        None
+    } else {
+        Some(config.api_key.clone())
+    };
     let relay_url = format!("https://{}/_api/register", config.relay_host);
 
     let resp = http_client
@@ -74,18 +283,16 @@ pub async fn start_tunnel(
         .json(&RegisterRequest {
             port: remote_port,
             secret: config.relay_secret.clone(),
-            subdomain: if config.subdomain.is_empty() {
-                None
-            } else {
-                Some(config.subdomain.clone())
-            },
+            subdomain: preferred_subdomain.clone(),
+            api_key: api_key.clone(),
+            tunnel_key: tunnel_key.to_string(),
         })
         .send()
         .await?;
 
-    let resp = if resp.status() == reqwest::StatusCode::CONFLICT && !config.subdomain.is_empty() {
+    let resp = if resp.status() == reqwest::StatusCode::CONFLICT && preferred_subdomain.is_some() {
         warn!(
-            subdomain = %config.subdomain,
+            subdomain = ?preferred_subdomain,
             "Subdomain is taken by another user, retrying with random…"
         );
         // Retry without subdomain — let the relay assign a random one
@@ -96,6 +303,8 @@ pub async fn start_tunnel(
                 port: remote_port,
                 secret: config.relay_secret.clone(),
                 subdomain: None,
+                api_key,
+                tunnel_key: tunnel_key.to_string(),
             })
             .send()
             .await?
@@ -119,23 +328,314 @@ pub async fn start_tunnel(
         .relay_host
         .strip_prefix("relay.")
         .unwrap_or(&config.relay_host);
-    let public_url = format!("https://{subdomain}.tunnel.{base_domain}");
+    let mut public_url = format!("https://{subdomain}.tunnel.{base_domain}");
+
+    if !config.custom_domain.is_empty() {
+        match attach_custom_domain(http_client, config, &subdomain).await {
+            Ok(()) => public_url = format!("https://{}", config.custom_domain),
+            Err(e) => warn!(
+                custom_domain = %config.custom_domain,
+                target = %format!("{subdomain}.tunnel.{base_domain}"),
+                "Custom domain not attached, check its CNAME record: {e}"
+            ),
+        }
+    }
 
     info!(%public_url, "Tunnel registered");
 
-    // 3. Spawn bore client listener in background
-    tokio::spawn(async move {
+    Ok((
+        client,
+        TunnelCache {
+            relay_host: config.relay_host.clone(),
+            subdomain,
+            remote_port,
+            public_url,
+            registered_at: now_secs(),
+        },
+    ))
+}
+
+/// Record a new registration in the status and the cache.
+fn registered(state: &AppState, registration: &TunnelCache, previous_url: Option<&str>) {
+    registration.save(&state.settings.base_dir().join(CACHE_FILE));
+    let mut status = state.tunnel.lock().unwrap();
+    let continuous = previous_url == Some(registration.public_url.as_str());
+    if let Some(previous) = previous_url.filter(|_| !continuous) {
+        warn!(%previous, public_url = %registration.public_url, "Public URL changed");
+    }
+    *status = TunnelStatus {
+        state: TunnelState::Connected,
+        public_url: Some(registration.public_url.clone()),
+        subdomain: Some(registration.subdomain.clone()),
+        relay_host: Some(registration.relay_host.clone()),
+        remote_port: Some(registration.remote_port),
+        registered_at: Some(registration.registered_at),
+        registrations: status.registrations + 1,
+        continuous,
+        previous_url: previous_url.filter(|_| !continuous).map(str::to_string),
+        last_error: None,
+    };
+    drop(status);
+    state.events.publish(ServerEvent::TunnelConnected {
+        public_url: registration.public_url.clone(),
+    });
+}
+
+/// The tunnel settings, pointed at the `index`th of the relay hosts.
+fn relay_config(settings: &Settings, hosts: &[String], index: usize) -> TunnelConfig {
+    TunnelConfig {
+        relay_host: hosts[index % hosts.len()].clone(),
+        ..settings.tunnel.clone()
+    }
+}
+
+/// Start a bore tunnel and register with the relay server, or the first
+/// fallback relay that accepts, then keep it registered in the background:
+/// when the bore connection drops or the machine resumes from sleep,
+/// re-register right away with the same subdomain and port.
+pub async fn start_tunnel(
+    state: Arc<AppState>,
+    local_port: u16,
+    shutdown: CancellationToken,
+) -> Result<TunnelInfo> {
+    let hosts = state.settings.tunnel.relays();
+    let cached = TunnelCache::load(&state.settings.base_dir().join(CACHE_FILE));
+    let started = async {
+        let http_client = relay_client(&state.settings, &state.http_client)?;
+        let mut last_error = None;
+        for index in 0..hosts.len() {
+            let config = relay_config(&state.settings, &hosts, index);
+            match register(
+                local_port,
+                &config,
+                &http_client,
+                &state.tunnel_key,
+                cached.as_ref(),
+            )
+            .await
+            {
+                Ok(registered) => return Ok((registered, http_client, index)),
+                Err(e) => {
+                    warn!(relay_host = %config.relay_host, "Tunnel registration failed: {e}");
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| color_eyre::eyre::eyre!("No relay host configured")))
+    }
+    .await;
+    let ((client, registration), http_client, relay) = match started {
+        Ok(registered) => registered,
+        Err(e) => {
+            let mut status = state.tunnel.lock().unwrap();
+            status.state = TunnelState::Failed;
+            status.last_error = Some(e.to_string());
+            return Err(e);
+        }
+    };
+    registered(
+        &state,
+        &registration,
+        cached.as_ref().map(|c| c.public_url.as_str()),
+    );
+
+    let public_url = registration.public_url.clone();
+    tokio::spawn(keep_registered(
+        state,
+        http_client,
+        local_port,
+        shutdown,
+        client,
+        registration,
+        relay,
+    ));
+    Ok(TunnelInfo { public_url })
+}
+
+/// Run the bore client, re-registering whenever it stops or the machine
+/// resumes, until shutdown. A relay announcing its shutdown is left for the
+/// next relay host, as is one that fails [`FAILOVER_ATTEMPTS`] times in a row.
+async fn keep_registered(
+    state: Arc<AppState>,
+    http_client: reqwest::Client,
+    local_port: u16,
+    shutdown: CancellationToken,
+    mut client: bore_cli::client::Client,
+    mut current: TunnelCache,
+    mut relay: usize,
+) {
+    let hosts = state.settings.tunnel.relays();
+    loop {
         tokio::select! {
             result = client.listen() => {
-                if let Err(e) = result {
-                    error!("Bore tunnel closed: {e}");
+                match result {
+                    Ok(()) => warn!("Bore tunnel closed"),
+                    Err(e) => error!("Bore tunnel closed: {e}"),
                 }
             }
+            _ = wait_for_resume() => {
+                info!("Resumed from sleep, re-registering the tunnel");
+            }
+            _ = state.relay_shutdown.notified() => {
+                relay += 1;
+                info!(
+                    relay_host = %current.relay_host,
+                    next = %hosts[relay % hosts.len()],
+                    "Relay is shutting down, moving the tunnel"
+                );
+            }
             _ = shutdown.cancelled() => {
                 info!("Shutting down bore tunnel");
+                return;
             }
         }
-    });
 
-    Ok(TunnelInfo { public_url })
+        state.tunnel.lock().unwrap().state = TunnelState::Reconnecting;
+        let mut delay = Duration::from_secs(1);
+        let mut failures = 0;
+        loop {
+            let config = relay_config(&state.settings, &hosts, relay);
+            match register(
+                local_port,
+                &config,
+                &http_client,
+                &state.tunnel_key,
+                Some(&current),
+            )
+            .await
+            {
+                Ok((new_client, registration)) => {
+                    registered(&state, &registration, Some(&current.public_url));
+                    client = new_client;
+                    current = registration;
+                    break;
+                }
+                Err(e) => {
+                    warn!("Tunnel re-registration failed, retrying in {delay:?}: {e}");
+                    state.tunnel.lock().unwrap().last_error = Some(e.to_string());
+                    failures += 1;
+                    if failures == FAILOVER_ATTEMPTS && hosts.len() > 1 {
+                        relay += 1;
+                        failures = 0;
+                        delay = Duration::from_secs(1);
+                        warn!(
+                            relay_host = %config.relay_host,
+                            next = %hosts[relay % hosts.len()],
+                            "Relay keeps failing, moving the tunnel"
+                        );
+                    }
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = shutdown.cancelled() => return,
+                    }
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge_response_is_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            challenge_response("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_is_resume() {
+        let secs = Duration::from_secs;
+        assert!(!is_resume(secs(5), secs(5)));
+        // Small clock adjustments are not a resume
+        assert!(!is_resume(secs(20), secs(5)));
+        assert!(is_resume(secs(3600), secs(5)));
+    }
+
+    #[test]
+    fn test_relay_client_needs_readable_certificate() {
+        let default = reqwest::Client::new();
+        let mut settings = Settings::default();
+        assert!(relay_client(&settings, &default).is_ok());
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        settings.tunnel.client_cert = tmp.path().join("missing.pem").to_string_lossy().to_string();
+        let err = relay_client(&settings, &default).unwrap_err();
+        assert!(err.to_string().contains("missing.pem"));
+
+        std::fs::write(tmp.path().join("garbage.pem"), "not a certificate").unwrap();
+        settings.tunnel.client_cert = tmp.path().join("garbage.pem").to_string_lossy().to_string();
+        assert!(relay_client(&settings, &default).is_err());
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join(CACHE_FILE);
+        assert!(TunnelCache::load(&path).is_none());
+
+        let cache = TunnelCache {
+            relay_host: "relay.nomadflowcode.dev".to_string(),
+            subdomain: "abc123".to_string(),
+            remote_port: 12345,
+            public_url: "https://abc123.tunnel.nomadflowcode.dev".to_string(),
+            registered_at: 1000,
+        };
+        cache.save(&path);
+        assert_eq!(TunnelCache::load(&path), Some(cache));
+    }
+
+    #[test]
+    fn test_registered_reports_continuity() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut settings = nomadflow_core::config::Settings::default();
+        settings.paths.base_dir = tmp.path().to_string_lossy().to_string();
+        let state = AppState::new(settings);
+        let registration = |subdomain: &str| TunnelCache {
+            relay_host: "relay.nomadflowcode.dev".to_string(),
+            subdomain: subdomain.to_string(),
+            remote_port: 12345,
+            public_url: format!("https://{subdomain}.tunnel.nomadflowcode.dev"),
+            registered_at: 1000,
+        };
+
+        registered(&state, &registration("abc123"), None);
+        let status = state.tunnel.lock().unwrap().clone();
+        assert_eq!(status.state, TunnelState::Connected);
+        assert_eq!(status.relay_host.as_deref(), Some("relay.nomadflowcode.dev"));
+        assert_eq!(status.registrations, 1);
+        assert!(!status.continuous);
+        assert!(tmp.path().join(CACHE_FILE).exists());
+
+        let previous = "https://abc123.tunnel.nomadflowcode.dev";
+        registered(&state, &registration("abc123"), Some(previous));
+        let status = state.tunnel.lock().unwrap().clone();
+        assert_eq!(status.registrations, 2);
+        assert!(status.continuous);
+        assert_eq!(status.previous_url, None);
+
+        registered(&state, &registration("xyz789"), Some(previous));
+        let status = state.tunnel.lock().unwrap().clone();
+        assert!(!status.continuous);
+        assert_eq!(status.previous_url.as_deref(), Some(previous));
+    }
+
+    #[test]
+    fn test_relay_config_cycles_through_hosts() {
+        let mut settings = nomadflow_core::config::Settings::default();
+        settings.tunnel.subdomain = "fabien".to_string();
+        settings.tunnel.relay_hosts = vec!["a.example.com".into(), "b.example.com".into()];
+        let hosts = settings.tunnel.relays();
+
+        let config = relay_config(&settings, &hosts, 0);
+        assert_eq!(config.relay_host, "a.example.com");
+        assert_eq!(config.subdomain, "fabien");
+        assert_eq!(relay_config(&settings, &hosts, 1).relay_host, "b.example.com");
+        assert_eq!(relay_config(&settings, &hosts, 2).relay_host, "a.example.com");
+    }
 }
@@ -0,0 +1,173 @@
+//! Keeps the tmux session in line with the feature worktrees: windows of
+//! deleted features are closed, windows of existing ones reopened on start.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use nomadflow_core::limits::pane_usage;
+use nomadflow_core::models::Feature;
+use nomadflow_core::services::tmux::TmuxWindow;
+
+use crate::state::AppState;
+
+/// How often the session is checked for orphaned windows.
+const GC_INTERVAL: Duration = Duration::from_secs(300);
+/// How often the usage of limited windows is refreshed.
+const USAGE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Features of every repo, keyed by window prefix.
+/// Repos whose worktrees could not be listed are left out, so their windows
+/// are never mistaken for orphans.
+async fn features_by_repo(state: &AppState) -> Option<HashMap<String, (String, Vec<Feature>)>> {
+    let repos = state
+        .git
+        .list_repos()
+        .await
+        .inspect_err(|e| warn!("Failed to list repos: {e}"))
+        .ok()?;
+    let mut by_repo = HashMap::new();
+    for repo in repos {
+        match state.git.list_features(&repo.path).await {
+            // A repo always has its main worktree; none means git failed
+            Ok(features) if !features.is_empty() => {
+                by_repo.insert(state.window_names.prefix(&repo.path), (repo.path, features));
+            }
+            _ => {}
+        }
+    }
+    Some(by_repo)
+}
+
+/// Windows named `repo:feature` for a known repo whose feature no longer exists.
+pub fn orphan_windows<'a>(
+    windows: &'a [TmuxWindow],
+    features: &HashMap<String, HashSet<String>>,
+) -> Vec<&'a str> {
+    windows
+        .iter()
+        .filter(|w| match w.name.split_once(':') {
+            Some((repo, feature)) => features
+                .get(repo)
+                .is_some_and(|names| !names.contains(feature)),
+            None => false,
+        })
+        .map(|w| w.name.as_str())
+        .collect()
+}
+
+/// Close the orphaned windows that are not running anything. Returns their names.
+pub async fn close_orphans(state: &AppState) -> Vec<String> {
+    let Some(by_repo) = features_by_repo(state).await else {
+        return Vec::new();
+    };
+    let names: HashMap<String, HashSet<String>> = by_repo
+        .into_iter()
+        .map(|(repo, (_, features))| (repo, features.into_iter().map(|f| f.name).collect()))
+        .collect();
+
+    let windows = state.tmux.list_windows().await;
+    let mut closed = Vec::new();
+    for name in orphan_windows(&windows, &names) {
+        if !state.tmux.is_shell_idle(name).await {
+            continue;
+        }
+        if state.tmux.kill_window(name).await {
+            info!(window = %name, "Closed window of deleted feature");
+            closed.push(name.to_string());
+        }
+    }
+    closed
+}
+
+/// Open a window for every feature worktree that has none.
+pub async fn restore(state: &AppState) {
+    let Some(by_repo) = features_by_repo(state).await else {
+        return;
+    };
+    for (repo_path, features) in by_repo.values() {
+        for feature in features.iter().filter(|f| !f.is_main) {
+            let name = state.window_names.window_name(repo_path, &feature.name);
+            if let Err(e) = state
+                .tmux
+                .ensure_window(&name, Some(&feature.worktree_path))
+                .await
+            {
+                warn!(window = %name, "Failed to restore window: {e}");
+            }
+        }
+    }
+}
+
+/// Close orphaned windows every few minutes until shutdown.
+pub fn spawn_window_gc(state: Arc<AppState>, shutdown: CancellationToken) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(GC_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+            close_orphans(&state).await;
+        }
+    });
+}
+
+/// Refresh the usage shown in the status of windows with resource limits
+/// until shutdown.
+pub fn spawn_usage_monitor(state: Arc<AppState>, shutdown: CancellationToken) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(USAGE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+            for window in state.tmux.list_windows().await {
+                if state.tmux.limits().for_window(&window.name).is_empty() {
+                    continue;
+                }
+                let Some(pid) = state.tmux.pane_pid(&window.name).await else {
+                    continue;
+                };
+                if let Some(usage) = pane_usage(pid).await {
+                    state.tmux.set_usage(&window.name, &usage.to_string()).await;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(name: &str) -> TmuxWindow {
+        TmuxWindow {
+            index: 0,
+            name: name.to_string(),
+            activity: false,
+        }
+    }
+
+    #[test]
+    fn test_orphan_windows() {
+        let windows = vec![
+            window("shell"),
+            window("app:main"),
+            window("app:login-form"),
+            window("app:deleted"),
+            window("gone:feature"),
+        ];
+        let features = HashMap::from([(
+            "app".to_string(),
+            HashSet::from(["main".to_string(), "login-form".to_string()]),
+        )]);
+
+        // Windows of unknown repos are left alone: they may not be ours
+        assert_eq!(orphan_windows(&windows, &features), ["app:deleted"]);
+    }
+}
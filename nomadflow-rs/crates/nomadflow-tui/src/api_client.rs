@@ -1,7 +1,13 @@
 use nomadflow_core::models::{
-    Feature, ListFeaturesResponse, ListReposResponse, Repository,
+    ActivityEntry, BulkFeatureAction, BulkFeatureResult, BulkFeaturesResponse, Feature,
+    FeatureNote, FeatureNotesResponse, HealthResponse, ListFeaturesResponse,
+    ListActivityResponse, ListReposResponse, ListTaskRunsResponse, ListTasksResponse,
+    ListTmuxEventsResponse, ProtectedBranchesResponse, ReconcileIssue, ReconcileResponse, Repository, RunTaskResponse,
+    SearchResponse, TaskInfo, TunnelStatus, UndoResponse, WindowState, WindowStatusResponse,
 };
 
+use crate::notify::ServerEvents;
+use crate::pinning;
 use crate::state::ServerConfig;
 
 /// Derive the API base URL from a server config.
@@ -18,33 +24,71 @@ pub fn get_api_base_url(server: &ServerConfig) -> String {
     }
 }
 
-/// Check if a server is healthy.
-pub async fn check_health(server: &ServerConfig) -> bool {
+/// Add the server's bearer token and, when it has one, its TOTP device token.
+/// Fails when the token is in a keychain that could not be read.
+fn authorize(
+    mut req: reqwest::RequestBuilder,
+    server: &ServerConfig,
+) -> Result<reqwest::RequestBuilder, String> {
+    if let Some(ref e) = server.token_error {
+        return Err(e.clone());
+    }
+    if let Some(ref token) = server.auth_token {
+        req = req.header("Authorization", format!("Bearer {token}"));
+    }
+    if let Some(ref device) = server.device_token {
+        req = req.header("X-NomadFlow-Device", device);
+    }
+    Ok(req)
+}
+
+/// The `detail` of an API error body, or the body as is when it is not one.
+fn error_detail(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["detail"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// Check if a server is healthy. Returns the round-trip time, or `None` if
+/// the server did not answer.
+pub async fn check_health(server: &ServerConfig) -> Option<std::time::Duration> {
+    server_health(server).await.map(|(latency, _)| latency)
+}
+
+/// The round-trip time to a server and what it reports about itself, or
+/// `None` if it did not answer. The report is `None` when its body cannot
+/// be read.
+pub async fn server_health(
+    server: &ServerConfig,
+) -> Option<(std::time::Duration, Option<HealthResponse>)> {
     let base = get_api_base_url(server).replace("/api", "");
     let url = format!("{base}/health");
 
-    let client = reqwest::Client::new();
+    let client = pinning::client(server).ok()?;
     let mut req = client.get(&url).timeout(std::time::Duration::from_secs(3));
-    if let Some(ref token) = server.auth_token {
-        req = req.header("Authorization", format!("Bearer {token}"));
+    // Health answers without credentials too, just with less detail
+    if server.token_error.is_none() {
+        req = authorize(req, server).ok()?;
     }
 
-    req.send().await.map(|r| r.status().is_success()).unwrap_or(false)
+    let start = std::time::Instant::now();
+    let resp = req.send().await.ok().filter(|r| r.status().is_success())?;
+    let latency = start.elapsed();
+    Some((latency, resp.json().await.ok()))
 }
 
 /// List repos from the server.
 pub async fn list_repos(server: &ServerConfig) -> Result<Vec<Repository>, String> {
     let url = format!("{}/list-repos", get_api_base_url(server));
 
-    let client = reqwest::Client::new();
+    let client = pinning::client(server)?;
     let mut req = client
         .post(&url)
         .header("Content-Type", "application/json")
         .timeout(std::time::Duration::from_secs(10));
 
-    if let Some(ref token) = server.auth_token {
-        req = req.header("Authorization", format!("Bearer {token}"));
-    }
+    req = authorize(req, server)?;
 
     let resp = req.send().await.map_err(|e| e.to_string())?;
 
@@ -63,16 +107,14 @@ pub async fn list_features(
 ) -> Result<Vec<Feature>, String> {
     let url = format!("{}/list-features", get_api_base_url(server));
 
-    let client = reqwest::Client::new();
+    let client = pinning::client(server)?;
     let mut req = client
         .post(&url)
         .header("Content-Type", "application/json")
         .json(&serde_json::json!({ "repoPath": repo_path }))
         .timeout(std::time::Duration::from_secs(10));
 
-    if let Some(ref token) = server.auth_token {
-        req = req.header("Authorization", format!("Bearer {token}"));
-    }
+    req = authorize(req, server)?;
 
     let resp = req.send().await.map_err(|e| e.to_string())?;
 
@@ -84,38 +126,70 @@ pub async fn list_features(
     Ok(data.features)
 }
 
-/// Create a feature.
+/// Create a feature, with the sentence its name was made from as its note.
 pub async fn create_feature(
     server: &ServerConfig,
     repo_path: &str,
     feature_name: &str,
+    description: Option<&str>,
+    allow_protected: bool,
 ) -> Result<String, String> {
     let url = format!("{}/create-feature", get_api_base_url(server));
 
-    let client = reqwest::Client::new();
+    let client = pinning::client(server)?;
     let mut req = client
         .post(&url)
         .header("Content-Type", "application/json")
         .json(&serde_json::json!({
             "repoPath": repo_path,
-            "featureName": feature_name,
+            "branchName": feature_name,
+            "description": description,
+            "allowProtected": allow_protected,
         }))
         .timeout(std::time::Duration::from_secs(30));
 
-    if let Some(ref token) = server.auth_token {
-        req = req.header("Authorization", format!("Bearer {token}"));
-    }
+    req = authorize(req, server)?;
 
     let resp = req.send().await.map_err(|e| e.to_string())?;
 
     if !resp.status().is_success() {
         let body = resp.text().await.unwrap_or_default();
-        return Err(format!("Failed to create feature: {body}"));
+        return Err(format!("Failed to create feature: {}", error_detail(&body)));
     }
 
     Ok(feature_name.to_string())
 }
 
+/// Delete a feature: its worktree, tmux window and branch.
+pub async fn delete_feature(
+    server: &ServerConfig,
+    repo_path: &str,
+    feature_name: &str,
+) -> Result<(), String> {
+    let url = format!("{}/delete-feature", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let mut req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({
+            "repoPath": repo_path,
+            "featureName": feature_name,
+        }))
+        .timeout(std::time::Duration::from_secs(30));
+
+    req = authorize(req, server)?;
+
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Failed to delete feature: {}", error_detail(&body)));
+    }
+
+    Ok(())
+}
+
 /// Switch to a feature (prepares tmux window).
 pub async fn switch_feature(
     server: &ServerConfig,
@@ -124,7 +198,7 @@ pub async fn switch_feature(
 ) -> Result<String, String> {
     let url = format!("{}/switch-feature", get_api_base_url(server));
 
-    let client = reqwest::Client::new();
+    let client = pinning::client(server)?;
     let mut req = client
         .post(&url)
         .header("Content-Type", "application/json")
@@ -134,20 +208,346 @@ pub async fn switch_feature(
         }))
         .timeout(std::time::Duration::from_secs(10));
 
-    if let Some(ref token) = server.auth_token {
-        req = req.header("Authorization", format!("Bearer {token}"));
-    }
+    req = authorize(req, server)?;
 
     let resp = req.send().await.map_err(|e| e.to_string())?;
 
     if !resp.status().is_success() {
         let body = resp.text().await.unwrap_or_default();
-        return Err(format!("Failed to switch: {body}"));
+        return Err(format!("Failed to switch: {}", error_detail(&body)));
     }
 
     Ok(feature_name.to_string())
 }
 
+/// Apply one action to several features of a repo.
+pub async fn bulk_features(
+    server: &ServerConfig,
+    repo_path: &str,
+    feature_names: &[String],
+    action: BulkFeatureAction,
+) -> Result<Vec<BulkFeatureResult>, String> {
+    let url = format!("{}/bulk-features", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let mut req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({
+            "repoPath": repo_path,
+            "featureNames": feature_names,
+            "action": action,
+        }))
+        .timeout(std::time::Duration::from_secs(120));
+
+    req = authorize(req, server)?;
+
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Batch action failed: {}", error_detail(&body)));
+    }
+
+    let data: BulkFeaturesResponse = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(data.results)
+}
+
+/// Fetch the most recent entries of the server activity log.
+pub async fn list_activity(
+    server: &ServerConfig,
+    limit: usize,
+) -> Result<Vec<ActivityEntry>, String> {
+    let url = format!("{}/list-activity", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let mut req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({ "limit": limit }))
+        .timeout(std::time::Duration::from_secs(5));
+
+    req = authorize(req, server)?;
+
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Failed to load activity: {}", error_detail(&body)));
+    }
+
+    let data: ListActivityResponse = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(data.entries)
+}
+
+/// Revert an activity log entry. Returns the server's description of what was done.
+pub async fn undo(server: &ServerConfig, id: u64) -> Result<String, String> {
+    let url = format!("{}/undo", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let mut req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({ "id": id }))
+        .timeout(std::time::Duration::from_secs(60));
+
+    req = authorize(req, server)?;
+
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Undo failed: {}", error_detail(&body)));
+    }
+
+    let data: UndoResponse = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(data.detail)
+}
+
+/// Check git worktrees, the worktrees directory and tmux windows for
+/// inconsistencies, fixing them when `fix` is set.
+pub async fn reconcile(server: &ServerConfig, fix: bool) -> Result<Vec<ReconcileIssue>, String> {
+    let url = format!("{}/reconcile", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let mut req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({ "fix": fix }))
+        .timeout(std::time::Duration::from_secs(60));
+
+    req = authorize(req, server)?;
+
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Reconciliation failed: {}", error_detail(&body)));
+    }
+
+    let data: ReconcileResponse = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(data.issues)
+}
+
+/// Tasks defined in a feature's `.nomadflow.toml`.
+pub async fn list_tasks(
+    server: &ServerConfig,
+    repo_path: &str,
+    feature_name: &str,
+) -> Result<Vec<TaskInfo>, String> {
+    let url = format!("{}/list-tasks", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let mut req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({ "repoPath": repo_path, "featureName": feature_name }))
+        .timeout(std::time::Duration::from_secs(10));
+
+    req = authorize(req, server)?;
+
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Failed to load tasks: {}", error_detail(&body)));
+    }
+
+    let data: ListTasksResponse = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(data.tasks)
+}
+
+/// Run a task in the feature's window, or in the background when `background` is set.
+pub async fn run_task(
+    server: &ServerConfig,
+    repo_path: &str,
+    feature_name: &str,
+    task: &str,
+    background: Option<bool>,
+) -> Result<RunTaskResponse, String> {
+    let url = format!("{}/run-task", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let mut req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({
+            "repoPath": repo_path,
+            "featureName": feature_name,
+            "task": task,
+            "background": background,
+        }))
+        .timeout(std::time::Duration::from_secs(30));
+
+    req = authorize(req, server)?;
+
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Failed to run task: {}", error_detail(&body)));
+    }
+
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// Search the features of every repo of the server for `query`.
+pub async fn search(server: &ServerConfig, query: &str) -> Result<SearchResponse, String> {
+    let url = format!("{}/search", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let mut req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({ "query": query }))
+        .timeout(std::time::Duration::from_secs(60));
+
+    req = authorize(req, server)?;
+
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Search failed: {}", error_detail(&body)));
+    }
+
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// Windows of the server's session, busiest first.
+pub async fn window_status(server: &ServerConfig) -> Result<Vec<WindowState>, String> {
+    let url = format!("{}/window-status", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let req = client
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(5));
+
+    let resp = authorize(req, server)?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let data: WindowStatusResponse = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(data.windows)
+}
+
+/// The note left on a feature, if any.
+pub async fn feature_note(
+    server: &ServerConfig,
+    repo_path: &str,
+    feature_name: &str,
+) -> Result<Option<FeatureNote>, String> {
+    let url = format!("{}/feature-notes", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({
+            "repoPath": repo_path,
+            "featureName": feature_name,
+        }))
+        .timeout(std::time::Duration::from_secs(5));
+
+    let resp = authorize(req, server)?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Failed to load note: {}", error_detail(&body)));
+    }
+    let data: FeatureNotesResponse = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(data.note)
+}
+
+/// Branches of a repo its forge protects, empty when the server has no
+/// token for the forge.
+pub async fn protected_branches(
+    server: &ServerConfig,
+    repo_path: &str,
+) -> Result<Vec<String>, String> {
+    let url = format!("{}/protected-branches", get_api_base_url(server));
+
+    let client = pinning::client(server)?;
+    let req = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({ "repoPath": repo_path }))
+        .timeout(std::time::Duration::from_secs(10));
+
+    let resp = authorize(req, server)?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to load protected branches: {}",
+            error_detail(&body)
+        ));
+    }
+    let data: ProtectedBranchesResponse = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(data.branches)
+}
+
+/// Recent tmux events, background task runs and tunnel state of the server.
+pub async fn server_events(server: &ServerConfig) -> Result<ServerEvents, String> {
+    let base = get_api_base_url(server);
+    let client = pinning::client(server)?;
+
+    let req = client
+        .post(format!("{base}/list-tmux-events"))
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({ "limit": 50 }))
+        .timeout(std::time::Duration::from_secs(5));
+    let resp = authorize(req, server)?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to load tmux events: {}",
+            error_detail(&body)
+        ));
+    }
+    let tmux_events: ListTmuxEventsResponse = resp.json().await.map_err(|e| e.to_string())?;
+
+    let req = client
+        .get(format!("{base}/task-runs"))
+        .timeout(std::time::Duration::from_secs(5));
+    let resp = authorize(req, server)?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Failed to load task runs: {}", error_detail(&body)));
+    }
+    let task_runs: ListTaskRunsResponse = resp.json().await.map_err(|e| e.to_string())?;
+
+    // Older servers have no tunnel endpoint
+    let req = client
+        .get(format!("{base}/tunnel"))
+        .timeout(std::time::Duration::from_secs(5));
+    let tunnel = match authorize(req, server)?.send().await {
+        Ok(resp) if resp.status().is_success() => {
+            resp.json::<TunnelStatus>().await.ok().map(|s| s.state)
+        }
+        _ => None,
+    };
+
+    Ok(ServerEvents {
+        tmux_events: tmux_events.events,
+        task_runs: task_runs.runs,
+        tunnel,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -160,6 +560,9 @@ mod tests {
             api_url: Some("http://myserver:9000".to_string()),
             ttyd_url: None,
             auth_token: None,
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
         };
         assert_eq!(get_api_base_url(&server), "http://myserver:9000/api");
     }
@@ -172,10 +575,22 @@ mod tests {
             api_url: Some("http://myserver:9000/".to_string()),
             ttyd_url: None,
             auth_token: None,
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
         };
         assert_eq!(get_api_base_url(&server), "http://myserver:9000/api");
     }
 
+    #[test]
+    fn test_error_detail() {
+        assert_eq!(
+            error_detail(r#"{"code":"branch_exists","detail":"'login' already exists"}"#),
+            "'login' already exists"
+        );
+        assert_eq!(error_detail("Bad Gateway"), "Bad Gateway");
+    }
+
     #[test]
     fn test_api_base_url_fallback() {
         let server = ServerConfig {
@@ -184,7 +599,26 @@ mod tests {
             api_url: None,
             ttyd_url: None,
             auth_token: None,
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
         };
         assert_eq!(get_api_base_url(&server), "http://localhost:8080/api");
     }
+
+    #[test]
+    fn test_authorize_refuses_unreadable_token() {
+        let server = ServerConfig {
+            id: "nas".to_string(),
+            name: "nas".to_string(),
+            api_url: Some("http://nas:8080".to_string()),
+            ttyd_url: None,
+            auth_token: None,
+            device_token: None,
+            cert: Default::default(),
+            token_error: Some("keychain locked".to_string()),
+        };
+        let req = reqwest::Client::new().get("http://nas:8080/api/list-repos");
+        assert_eq!(authorize(req, &server).unwrap_err(), "keychain locked");
+    }
 }
@@ -1,19 +1,36 @@
 use std::io;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use color_eyre::Result;
 use crossterm::event::{KeyCode, KeyModifiers};
 use ratatui::prelude::*;
 
+use nomadflow_core::branch::{check_branch_name, name_from_description, suggest_branch_names};
 use nomadflow_core::config::Settings;
-use nomadflow_core::models::{Feature, Repository};
+use nomadflow_core::forge::is_protected;
+use nomadflow_core::i18n::{t, tf};
+use nomadflow_core::models::{
+    ActivityEntry, BulkFeatureAction, Feature, FeatureNote, HealthResponse, ReconcileIssue,
+    ReconcileIssueKind, Repository, SearchMatch, TaskInfo, WindowState,
+};
+use nomadflow_core::window_names::WindowNames;
 
 use crate::api_client;
 use crate::event::{poll_event, AppEvent};
+use crate::glyphs::{self, Status};
+use crate::local;
+use crate::notify;
+use crate::pinning;
 use crate::screens;
 use crate::state::{self, CliState, ServerConfig};
 use crate::tmux_local;
 use crate::widgets;
+use crate::widgets::tips::{Tip, MERGED_FEATURES_KEY};
+
+/// How often the servers on screen are health-checked.
+const HEALTH_INTERVAL: Duration = Duration::from_secs(15);
+/// How often the selected server is asked for events to notify.
+const EVENTS_INTERVAL: Duration = Duration::from_secs(5);
 
 /// What the TUI should do when it exits.
 pub enum AppResult {
@@ -31,6 +48,8 @@ pub enum Screen {
     RepoPicker,
     FeaturePicker,
     FeatureCreate,
+    TaskMenu,
+    Search,
     Attaching,
 }
 
@@ -40,6 +59,9 @@ pub struct App {
     pub screen: Screen,
     pub servers: Vec<ServerConfig>,
     pub cli_state: CliState,
+    /// Busiest or most recently active feature window of the last session's
+    /// server, offered above resuming when it is another feature.
+    pub jump_to: Option<WindowState>,
 
     // Selection state
     pub server: Option<ServerConfig>,
@@ -47,18 +69,64 @@ pub struct App {
     pub features: Vec<CliFeature>,
     pub repo: Option<Repository>,
     pub feature: Option<Feature>,
-
-    // Server health
-    pub health_map: std::collections::HashMap<String, bool>,
+    /// Tasks of the selected feature, in the task menu.
+    pub tasks: Vec<TaskInfo>,
+    /// Results of the search screen, and the query they were found for.
+    pub search_results: Vec<SearchMatch>,
+    pub searched: Option<String>,
+    pub search_truncated: bool,
+
+    // Server health: round-trip time of the last check, `None` if unreachable
+    pub health_map: std::collections::HashMap<String, Option<Duration>>,
     pub health_checking: bool,
+    last_health_check: Option<Instant>,
+    /// What each server reported about itself in its last health check.
+    pub health_reports: std::collections::HashMap<String, HealthResponse>,
+
+    // Desktop notifications of the selected server's events
+    notifications: notify::Watcher,
+    last_events_poll: Option<Instant>,
 
     // UI state
     pub selected_index: usize,
     pub loading: bool,
+    /// Ticks counted since start, for spinner frames.
+    pub tick: usize,
+    /// When the current wait started, while `loading`.
+    pub busy_since: Option<Instant>,
+    /// The API call being waited for, aborted when it is cancelled.
+    in_flight: Option<tokio::task::AbortHandle>,
+    /// Screen to return to when attaching is cancelled.
+    attach_return: Screen,
+    /// Window of the managed session the TUI runs in, which it then switches
+    /// away from instead of attaching.
+    pub tmux_window: Option<String>,
     pub error: Option<String>,
     pub input_text: String,
     pub input_cursor: usize,
     pub confirm_step: bool,
+    /// Sentence the name of the feature being created was made from.
+    pub description: Option<String>,
+    /// Branches the forge of the repo protects, warned about on creation.
+    pub protected: Vec<String>,
+    /// Informational message shown above lists (e.g. batch action results).
+    pub notice: Option<String>,
+    /// Most recent server activity, shown in the history bar.
+    pub last_action: Option<ActivityEntry>,
+    /// The localhost API is down and operations run in-process.
+    pub offline: bool,
+    /// Unfixed inconsistencies between git and tmux on the server.
+    pub issues: Vec<ReconcileIssue>,
+
+    // Feature multi-select state
+    pub marked: std::collections::BTreeSet<String>,
+    pub batch_action: Option<BulkFeatureAction>,
+
+    // Notes panel of the feature picker
+    pub notes_open: bool,
+    /// Feature whose note is shown, and the note: `None` while it loads.
+    pub note_for: Option<String>,
+    pub note: Option<Result<Option<FeatureNote>, String>>,
 
     // Server add state
     pub server_add_step: u8,
@@ -74,6 +142,8 @@ pub struct App {
     // Result
     pub should_quit: bool,
     pub attach_session: Option<String>,
+    /// The last session could not be saved; printed once the TUI is gone.
+    pub state_error: Option<String>,
 }
 
 /// Feature enriched with local tmux pane command.
@@ -85,7 +155,7 @@ pub struct CliFeature {
 
 impl App {
     /// Generate a random password (16 chars: a-z, 0-9, hyphens).
-    fn generate_password() -> String {
+    pub(crate) fn generate_password() -> String {
         use rand::Rng;
         const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789-";
         let mut rng = rand::rng();
@@ -95,7 +165,7 @@ impl App {
     }
 
     /// Generate a random subdomain (nf-XXXXXXXX, 8 lowercase alphanumeric chars).
-    fn generate_subdomain() -> String {
+    pub(crate) fn generate_subdomain() -> String {
         use rand::Rng;
         const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
         let mut rng = rand::rng();
@@ -142,24 +212,51 @@ impl App {
             String::new()
         };
 
+        let tmux_window = tmux_local::current_window(&settings.tmux.session);
+
         Self {
             settings,
             screen: initial_screen,
             servers,
             cli_state,
+            jump_to: None,
             server,
             repos: Vec::new(),
             features: Vec::new(),
             repo: None,
             feature: None,
+            tasks: Vec::new(),
+            search_results: Vec::new(),
+            searched: None,
+            search_truncated: false,
             health_map: std::collections::HashMap::new(),
             health_checking: false,
+            last_health_check: None,
+            health_reports: std::collections::HashMap::new(),
+            notifications: notify::Watcher::default(),
+            last_events_poll: None,
             selected_index: 0,
             loading: false,
+            tick: 0,
+            busy_since: None,
+            in_flight: None,
+            attach_return: Screen::FeaturePicker,
+            tmux_window,
             error: None,
             input_text: String::new(),
             input_cursor: 0,
             confirm_step: false,
+            description: None,
+            protected: Vec::new(),
+            notice: None,
+            last_action: None,
+            offline: false,
+            issues: Vec::new(),
+            marked: std::collections::BTreeSet::new(),
+            batch_action: None,
+            notes_open: false,
+            note_for: None,
+            note: None,
             server_add_step: 0,
             server_add_name: String::new(),
             server_add_url: String::new(),
@@ -169,6 +266,7 @@ impl App {
             setup_public: false,
             should_quit: false,
             attach_session: None,
+            state_error: None,
         }
     }
 
@@ -180,6 +278,10 @@ impl App {
         // Async event channel for API results
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<AppEvent>();
 
+        if self.screen == Screen::Resume {
+            self.trigger_window_status(tx.clone());
+        }
+
         // Auto-skip to repo picker if single server
         if self.screen == Screen::ServerPicker && self.servers.len() == 1 {
             self.server = Some(self.servers[0].clone());
@@ -197,10 +299,27 @@ impl App {
                 self.handle_async_event(event, tx.clone());
             }
 
+            if self
+                .last_health_check
+                .is_none_or(|at| at.elapsed() >= HEALTH_INTERVAL)
+            {
+                self.trigger_health_checks(tx.clone());
+            }
+            if self.settings.notifications.enabled
+                && self
+                    .last_events_poll
+                    .is_none_or(|at| at.elapsed() >= EVENTS_INTERVAL)
+            {
+                self.trigger_poll_events(tx.clone());
+            }
+
             // Poll terminal events (50ms tick)
-            if let Some(AppEvent::Key(key)) = poll_event(Duration::from_millis(50)) {
-                self.handle_key(key.code, key.modifiers, tx.clone());
+            match poll_event(Duration::from_millis(50)) {
+                Some(AppEvent::Key(key)) => self.handle_key(key.code, key.modifiers, tx.clone()),
+                Some(AppEvent::Tick) => self.on_tick(),
+                _ => {}
             }
+            self.check_attach_timeout(tx.clone());
 
             if self.should_quit {
                 return Ok(match self.attach_session.take() {
@@ -211,21 +330,83 @@ impl App {
         }
     }
 
+    /// Advance the spinner and time the current wait.
+    fn on_tick(&mut self) {
+        self.tick = self.tick.wrapping_add(1);
+        if !self.loading {
+            self.busy_since = None;
+        } else if self.busy_since.is_none() {
+            self.busy_since = Some(Instant::now());
+        }
+    }
+
+    /// Give up on a switch that takes longer than `tui.attach_timeout_seconds`
+    /// and go back to the features with the reason.
+    fn check_attach_timeout(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        let limit = self.settings.tui.attach_timeout_seconds;
+        if self.screen != Screen::Attaching
+            || !self.loading
+            || limit == 0
+            || self.elapsed() < Duration::from_secs(limit.into())
+        {
+            return;
+        }
+        self.cancel_in_flight();
+        self.busy_since = None;
+        self.screen = Screen::FeaturePicker;
+        self.notice = Some(glyphs::mark(
+            Status::Error,
+            &tf("attaching.timeout", &[("secs", &limit)]),
+        ));
+        // The server may still have created or switched to the feature
+        self.loading = true;
+        self.trigger_load_features(tx);
+    }
+
+    /// Time spent waiting for the current call.
+    pub fn elapsed(&self) -> Duration {
+        self.busy_since.map_or(Duration::ZERO, |at| at.elapsed())
+    }
+
+    /// Abort the API call being waited for, if any.
+    fn cancel_in_flight(&mut self) {
+        if let Some(call) = self.in_flight.take() {
+            call.abort();
+        }
+        self.loading = false;
+    }
+
     fn draw(&self, frame: &mut Frame) {
         let area = frame.area();
 
+        let history = self
+            .last_action
+            .as_ref()
+            .filter(|_| matches!(self.screen, Screen::RepoPicker | Screen::FeaturePicker));
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3), // Header
                 Constraint::Length(1), // Breadcrumb
                 Constraint::Min(1),   // Content
+                Constraint::Length(history.map_or(0, |_| 1)), // History bar
                 Constraint::Length(1), // Footer
             ])
             .split(area);
 
         // Header
-        widgets::header::render(frame, chunks[0]);
+        let latency = self
+            .server
+            .as_ref()
+            .and_then(|s| self.health_map.get(&s.id).copied());
+        widgets::header::render(
+            frame,
+            chunks[0],
+            latency,
+            self.offline,
+            self.tmux_window.as_deref(),
+        );
 
         // Breadcrumb
         widgets::breadcrumb::render(
@@ -245,18 +426,37 @@ impl App {
             Screen::RepoPicker => screens::repo_picker::render(frame, chunks[2], self),
             Screen::FeaturePicker => screens::feature_picker::render(frame, chunks[2], self),
             Screen::FeatureCreate => screens::feature_create::render(frame, chunks[2], self),
+            Screen::TaskMenu => screens::task_menu::render(frame, chunks[2], self),
+            Screen::Search => screens::search::render(frame, chunks[2], self),
             Screen::Attaching => screens::attaching::render(frame, chunks[2], self),
         }
 
+        // History bar
+        if let Some(entry) = history {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            widgets::history_bar::render(frame, chunks[3], entry, now);
+        }
+
         // Footer
         let footer_text = match self.screen {
+            Screen::Attaching if self.error.is_none() => t("footer.cancel"),
             Screen::Attaching => "",
-            Screen::Setup => "Escape: back",
-            _ => "Escape: back  q: quit",
+            Screen::Setup => t("footer.back"),
+            Screen::FeaturePicker if self.batch_action.is_some() => t("footer.batch_confirm"),
+            Screen::FeaturePicker if !self.marked.is_empty() => t("footer.marked"),
+            Screen::FeaturePicker => t("footer.features"),
+            Screen::TaskMenu => t("footer.tasks"),
+            Screen::RepoPicker if !self.tips().is_empty() => t("footer.repos_tips"),
+            Screen::RepoPicker => t("footer.repos"),
+            Screen::Search => t("footer.search"),
+            _ => t("footer.back_quit"),
         };
         let footer = ratatui::widgets::Paragraph::new(footer_text)
             .style(Style::default().fg(Color::DarkGray));
-        frame.render_widget(footer, chunks[3]);
+        frame.render_widget(footer, chunks[4]);
     }
 
     fn handle_key(
@@ -270,6 +470,7 @@ impl App {
             && self.screen != Screen::FeatureCreate
             && self.screen != Screen::ServerAdd
             && self.screen != Screen::Setup
+            && self.screen != Screen::Search
         {
             self.should_quit = true;
             return;
@@ -293,12 +494,17 @@ impl App {
             Screen::RepoPicker => self.handle_repo_picker_key(code, tx),
             Screen::FeaturePicker => self.handle_feature_picker_key(code, tx),
             Screen::FeatureCreate => self.handle_feature_create_key(code, tx),
-            Screen::Attaching => {} // No keys during attaching
+            Screen::TaskMenu => self.handle_task_menu_key(code, tx),
+            Screen::Search => self.handle_search_key(code, tx),
+            Screen::Attaching => {} // Only Escape, which cancels
         }
     }
 
     fn go_back(&mut self) {
         self.error = None;
+        if self.loading {
+            self.cancel_in_flight();
+        }
         match self.screen {
             Screen::RepoPicker => {
                 self.screen = Screen::ServerPicker;
@@ -307,9 +513,18 @@ impl App {
                 self.selected_index = 0;
             }
             Screen::FeaturePicker => {
+                // Escape first cancels a pending batch, then clears marks
+                if self.batch_action.take().is_some() {
+                    return;
+                }
+                if !self.marked.is_empty() {
+                    self.marked.clear();
+                    return;
+                }
                 self.screen = Screen::RepoPicker;
                 self.repo = None;
                 self.features.clear();
+                self.notice = None;
                 self.selected_index = 0;
             }
             Screen::ServerAdd => {
@@ -321,6 +536,21 @@ impl App {
                 self.server_add_url.clear();
                 self.selected_index = 0;
             }
+            Screen::TaskMenu => {
+                self.screen = Screen::FeaturePicker;
+                self.tasks.clear();
+                self.loading = false;
+                self.selected_index = 0;
+            }
+            Screen::Search => {
+                self.screen = Screen::RepoPicker;
+                self.input_text.clear();
+                self.input_cursor = 0;
+                self.search_results.clear();
+                self.searched = None;
+                self.loading = false;
+                self.selected_index = 0;
+            }
             Screen::FeatureCreate => {
                 self.screen = Screen::FeaturePicker;
                 self.input_text.clear();
@@ -335,6 +565,9 @@ impl App {
                 self.screen = Screen::ServerPicker;
                 self.selected_index = 0;
             }
+            Screen::Attaching => {
+                self.screen = self.attach_return.clone();
+            }
             _ => {}
         }
     }
@@ -345,24 +578,25 @@ impl App {
         tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
     ) {
         match code {
-            KeyCode::Up | KeyCode::Char('k') => {
-                if self.selected_index > 0 {
-                    self.selected_index -= 1;
-                }
+            KeyCode::Up | KeyCode::Char('k') if self.selected_index > 0 => {
+                self.selected_index -= 1;
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if self.selected_index < 1 {
-                    self.selected_index += 1;
-                }
+            KeyCode::Down | KeyCode::Char('j')
+                if self.selected_index < self.jump_to.is_some() as usize + 1 =>
+            {
+                self.selected_index += 1;
             }
             KeyCode::Enter => {
-                if self.selected_index == 0 {
-                    // Resume
-                    self.do_resume(tx);
-                } else {
-                    // Skip to server picker
-                    self.screen = Screen::ServerPicker;
-                    self.selected_index = 0;
+                // The jump, when offered, comes first
+                let choice = self.selected_index + self.jump_to.is_none() as usize;
+                match choice {
+                    0 => self.do_jump(tx),
+                    1 => self.do_resume(tx),
+                    _ => {
+                        // Skip to server picker
+                        self.screen = Screen::ServerPicker;
+                        self.selected_index = 0;
+                    }
                 }
             }
             _ => {}
@@ -377,15 +611,11 @@ impl App {
         // Servers + 1 for "Add server" option
         let count = self.servers.len() + 1;
         match code {
-            KeyCode::Up | KeyCode::Char('k') => {
-                if self.selected_index > 0 {
-                    self.selected_index -= 1;
-                }
+            KeyCode::Up | KeyCode::Char('k') if self.selected_index > 0 => {
+                self.selected_index -= 1;
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if self.selected_index + 1 < count {
-                    self.selected_index += 1;
-                }
+            KeyCode::Down | KeyCode::Char('j') if self.selected_index + 1 < count => {
+                self.selected_index += 1;
             }
             KeyCode::Enter => {
                 if self.selected_index == self.servers.len() {
@@ -428,9 +658,17 @@ impl App {
                         ttyd_url: Some(state::derive_ttyd_url(&self.server_add_url)),
                         api_url: Some(self.server_add_url.clone()),
                         auth_token: if token.is_empty() { None } else { Some(token) },
+                        device_token: None,
+                        cert: Default::default(),
+                        token_error: None,
                     };
-                    self.servers.push(new_server);
-                    state::save_servers(&self.settings, &self.servers);
+                    match state::add_server(&self.settings, new_server.clone()) {
+                        Ok(servers) => self.servers = servers,
+                        Err(e) => {
+                            self.servers.push(new_server);
+                            self.error = Some(tf("servers.save_failed", &[("err", &e)]));
+                        }
+                    }
 
                     // Reset and go back to picker
                     self.screen = Screen::ServerPicker;
@@ -459,21 +697,15 @@ impl App {
                 self.input_text.insert(self.input_cursor, c);
                 self.input_cursor += 1;
             }
-            KeyCode::Backspace => {
-                if self.input_cursor > 0 {
-                    self.input_cursor -= 1;
-                    self.input_text.remove(self.input_cursor);
-                }
+            KeyCode::Backspace if self.input_cursor > 0 => {
+                self.input_cursor -= 1;
+                self.input_text.remove(self.input_cursor);
             }
-            KeyCode::Left => {
-                if self.input_cursor > 0 {
-                    self.input_cursor -= 1;
-                }
+            KeyCode::Left if self.input_cursor > 0 => {
+                self.input_cursor -= 1;
             }
-            KeyCode::Right => {
-                if self.input_cursor < self.input_text.len() {
-                    self.input_cursor += 1;
-                }
+            KeyCode::Right if self.input_cursor < self.input_text.len() => {
+                self.input_cursor += 1;
             }
             KeyCode::Enter => {
                 let trimmed = self.input_text.trim().to_string();
@@ -515,15 +747,11 @@ impl App {
             0 => {
                 // Password choice: up/down + enter
                 match code {
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        if self.selected_index > 0 {
-                            self.selected_index -= 1;
-                        }
+                    KeyCode::Up | KeyCode::Char('k') if self.selected_index > 0 => {
+                        self.selected_index -= 1;
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if self.selected_index < 1 {
-                            self.selected_index += 1;
-                        }
+                    KeyCode::Down | KeyCode::Char('j') if self.selected_index < 1 => {
+                        self.selected_index += 1;
                     }
                     KeyCode::Enter => {
                         if self.selected_index == 0 {
@@ -548,21 +776,15 @@ impl App {
                         self.input_text.insert(self.input_cursor, c);
                         self.input_cursor += 1;
                     }
-                    KeyCode::Backspace => {
-                        if self.input_cursor > 0 {
-                            self.input_cursor -= 1;
-                            self.input_text.remove(self.input_cursor);
-                        }
+                    KeyCode::Backspace if self.input_cursor > 0 => {
+                        self.input_cursor -= 1;
+                        self.input_text.remove(self.input_cursor);
                     }
-                    KeyCode::Left => {
-                        if self.input_cursor > 0 {
-                            self.input_cursor -= 1;
-                        }
+                    KeyCode::Left if self.input_cursor > 0 => {
+                        self.input_cursor -= 1;
                     }
-                    KeyCode::Right => {
-                        if self.input_cursor < self.input_text.len() {
-                            self.input_cursor += 1;
-                        }
+                    KeyCode::Right if self.input_cursor < self.input_text.len() => {
+                        self.input_cursor += 1;
                     }
                     KeyCode::Enter => {
                         let trimmed = self.input_text.trim().to_string();
@@ -685,7 +907,7 @@ impl App {
         self.settings.auth.secret = self.setup_secret.clone();
         self.settings.tunnel.subdomain = self.setup_subdomain.clone();
         if let Err(e) = self.settings.save() {
-            self.error = Some(format!("Failed to save config: {e}"));
+            self.error = Some(tf("setup.save_failed", &[("err", &e)]));
             return;
         }
         // Transition to normal flow
@@ -701,24 +923,32 @@ impl App {
     ) {
         let count = self.repos.len();
         match code {
-            KeyCode::Up | KeyCode::Char('k') => {
-                if self.selected_index > 0 {
-                    self.selected_index -= 1;
-                }
+            KeyCode::Up | KeyCode::Char('k') if self.selected_index > 0 => {
+                self.selected_index -= 1;
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if self.selected_index + 1 < count {
-                    self.selected_index += 1;
-                }
+            KeyCode::Down | KeyCode::Char('j') if self.selected_index + 1 < count => {
+                self.selected_index += 1;
             }
-            KeyCode::Enter => {
-                if self.selected_index < count {
-                    self.repo = Some(self.repos[self.selected_index].clone());
-                    self.screen = Screen::FeaturePicker;
-                    self.selected_index = 0;
-                    self.loading = true;
-                    self.trigger_load_features(tx);
-                }
+            KeyCode::Char('u') => self.do_undo(tx),
+            KeyCode::Char('/') => {
+                self.screen = Screen::Search;
+                self.selected_index = 0;
+                self.input_text.clear();
+                self.input_cursor = 0;
+                self.search_results.clear();
+                self.searched = None;
+                self.error = None;
+            }
+            KeyCode::Char('f') if self.fixable_issues() > 0 => {
+                self.trigger_reconcile(true, tx);
+            }
+            KeyCode::Char('x') => self.dismiss_tips(),
+            KeyCode::Enter if self.selected_index < count => {
+                self.repo = Some(self.repos[self.selected_index].clone());
+                self.screen = Screen::FeaturePicker;
+                self.selected_index = 0;
+                self.loading = true;
+                self.trigger_load_features(tx);
             }
             _ => {}
         }
@@ -729,18 +959,58 @@ impl App {
         code: KeyCode,
         tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
     ) {
+        if self.batch_action.is_some() {
+            match code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                    self.do_batch(tx);
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') => {
+                    self.batch_action = None;
+                }
+                _ => {}
+            }
+            return;
+        }
+
         // Features + 1 for "Create" option
         let count = self.features.len() + 1;
         match code {
+            KeyCode::Char(' ') => self.toggle_mark(),
+            KeyCode::Char('u') => self.do_undo(tx.clone()),
+            KeyCode::Char('d') if !self.marked.is_empty() => {
+                self.batch_action = Some(BulkFeatureAction::Delete);
+            }
+            KeyCode::Char('s') if !self.marked.is_empty() => {
+                self.batch_action = Some(BulkFeatureAction::Sync);
+            }
+            KeyCode::Char('p') if !self.marked.is_empty() => {
+                self.batch_action = Some(BulkFeatureAction::Prune);
+            }
+            KeyCode::Char('n') => {
+                self.notes_open = !self.notes_open;
+                self.note_for = None;
+                self.refresh_note(tx);
+            }
+            KeyCode::Char('t') if self.selected_index < self.features.len() => {
+                self.feature = Some(self.features[self.selected_index].feature.clone());
+                self.screen = Screen::TaskMenu;
+                self.selected_index = 0;
+                self.tasks.clear();
+                self.loading = true;
+                self.error = None;
+                self.trigger_load_tasks(tx);
+            }
             KeyCode::Up | KeyCode::Char('k') => {
                 if self.selected_index > 0 {
                     self.selected_index -= 1;
                 }
+                self.refresh_note(tx);
             }
             KeyCode::Down | KeyCode::Char('j') => {
                 if self.selected_index + 1 < count {
                     self.selected_index += 1;
                 }
+                self.refresh_note(tx);
             }
             KeyCode::Enter => {
                 if self.selected_index == self.features.len() {
@@ -750,6 +1020,8 @@ impl App {
                     self.input_text.clear();
                     self.input_cursor = 0;
                     self.confirm_step = false;
+                    self.description = None;
+                    self.trigger_load_protected(tx);
                 } else if self.selected_index < self.features.len() {
                     let f = &self.features[self.selected_index];
                     self.feature = Some(f.feature.clone());
@@ -760,6 +1032,121 @@ impl App {
         }
     }
 
+    fn handle_task_menu_key(
+        &mut self,
+        code: KeyCode,
+        tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
+    ) {
+        if self.loading {
+            return;
+        }
+        match code {
+            KeyCode::Up | KeyCode::Char('k') if self.selected_index > 0 => {
+                self.selected_index -= 1;
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.selected_index + 1 < self.tasks.len() => {
+                self.selected_index += 1;
+            }
+            KeyCode::Enter => self.do_run_task(None, tx),
+            KeyCode::Char('b') => self.do_run_task(Some(true), tx),
+            _ => {}
+        }
+    }
+
+    /// Type the query, then Enter searches; once results are shown for the
+    /// typed query, Enter opens the feature of the highlighted match.
+    fn handle_search_key(
+        &mut self,
+        code: KeyCode,
+        tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
+    ) {
+        if self.loading {
+            return;
+        }
+        match code {
+            KeyCode::Char(c) => {
+                self.input_text.insert(self.input_cursor, c);
+                self.input_cursor += 1;
+            }
+            KeyCode::Backspace if self.input_cursor > 0 => {
+                self.input_cursor -= 1;
+                self.input_text.remove(self.input_cursor);
+            }
+            KeyCode::Left if self.input_cursor > 0 => {
+                self.input_cursor -= 1;
+            }
+            KeyCode::Right if self.input_cursor < self.input_text.len() => {
+                self.input_cursor += 1;
+            }
+            KeyCode::Up if self.selected_index > 0 => {
+                self.selected_index -= 1;
+            }
+            KeyCode::Down if self.selected_index + 1 < self.search_results.len() => {
+                self.selected_index += 1;
+            }
+            KeyCode::Enter => {
+                let query = self.input_text.trim().to_string();
+                if query.is_empty() {
+                    return;
+                }
+                if self.searched.as_deref() == Some(query.as_str()) {
+                    self.open_search_result(tx);
+                } else {
+                    self.do_search(query, tx);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Attach to the feature of the highlighted search result.
+    fn open_search_result(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        let Some(found) = self.search_results.get(self.selected_index).cloned() else {
+            return;
+        };
+        let repo = self
+            .repos
+            .iter()
+            .find(|r| r.path == found.repo_path)
+            .cloned()
+            .unwrap_or_else(|| Repository {
+                name: std::path::Path::new(&found.repo_path)
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                path: found.repo_path.clone(),
+                branch: String::new(),
+            });
+        self.repo = Some(repo);
+        self.feature = Some(Feature {
+            name: found.feature_name,
+            worktree_path: String::new(),
+            branch: String::new(),
+            is_active: false,
+            is_main: false,
+            has_unread: false,
+            suspended: false,
+            ..Default::default()
+        });
+        self.do_attach(tx);
+    }
+
+    /// Toggle the multi-select mark on the highlighted feature.
+    /// The main worktree can't be marked since batch actions would remove it.
+    fn toggle_mark(&mut self) {
+        let Some(cf) = self.features.get(self.selected_index) else {
+            return;
+        };
+        if cf.feature.is_main {
+            return;
+        }
+        let name = cf.feature.name.clone();
+        if !self.marked.remove(&name) {
+            self.marked.insert(name);
+        }
+    }
+
     fn handle_feature_create_key(
         &mut self,
         code: KeyCode,
@@ -773,6 +1160,11 @@ impl App {
                 }
                 KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                     self.confirm_step = false;
+                    // Back to the sentence, not the name made from it
+                    if let Some(description) = self.description.take() {
+                        self.input_cursor = description.len();
+                        self.input_text = description;
+                    }
                 }
                 _ => {}
             }
@@ -785,25 +1177,36 @@ impl App {
                 self.input_text.insert(self.input_cursor, c);
                 self.input_cursor += 1;
             }
-            KeyCode::Backspace => {
-                if self.input_cursor > 0 {
-                    self.input_cursor -= 1;
-                    self.input_text.remove(self.input_cursor);
-                }
+            KeyCode::Backspace if self.input_cursor > 0 => {
+                self.input_cursor -= 1;
+                self.input_text.remove(self.input_cursor);
             }
-            KeyCode::Left => {
-                if self.input_cursor > 0 {
-                    self.input_cursor -= 1;
-                }
+            KeyCode::Left if self.input_cursor > 0 => {
+                self.input_cursor -= 1;
             }
-            KeyCode::Right => {
-                if self.input_cursor < self.input_text.len() {
-                    self.input_cursor += 1;
+            KeyCode::Right if self.input_cursor < self.input_text.len() => {
+                self.input_cursor += 1;
+            }
+            KeyCode::Tab => {
+                let name = self
+                    .proposed_name()
+                    .or_else(|| suggest_branch_names(self.input_text.trim()).into_iter().next());
+                if let Some(name) = name {
+                    self.input_cursor = name.len();
+                    self.input_text = name;
                 }
             }
             KeyCode::Enter => {
+                if let Some(name) = self.proposed_name() {
+                    self.description = Some(self.input_text.trim().to_string());
+                    self.input_cursor = name.len();
+                    self.input_text = name;
+                    self.confirm_step = true;
+                    return;
+                }
                 let trimmed = self.input_text.trim().to_string();
-                if !trimmed.is_empty() {
+                // Invalid names are shown inline and can't be submitted
+                if check_branch_name(&trimmed).is_ok() {
                     self.input_text = trimmed;
                     self.confirm_step = true;
                 }
@@ -815,6 +1218,21 @@ impl App {
         }
     }
 
+    /// Branch name made from the input when a sentence is typed instead of
+    /// a name, not taken by a listed feature.
+    pub fn proposed_name(&self) -> Option<String> {
+        let input = self.input_text.trim();
+        if !input.contains(char::is_whitespace) {
+            return None;
+        }
+        let taken: Vec<String> = self
+            .features
+            .iter()
+            .map(|cf| cf.feature.branch.clone())
+            .collect();
+        name_from_description(input, None, &taken)
+    }
+
     fn handle_async_event(
         &mut self,
         event: AppEvent,
@@ -825,6 +1243,10 @@ impl App {
                 self.repos = repos;
                 self.loading = false;
                 self.error = None;
+                self.trigger_load_activity(tx.clone());
+                if !self.offline {
+                    self.trigger_reconcile(false, tx);
+                }
             }
             AppEvent::ReposLoaded(Err(e)) => {
                 self.loading = false;
@@ -832,11 +1254,12 @@ impl App {
             }
             AppEvent::FeaturesLoaded(Ok(features)) => {
                 let session = &self.settings.tmux.session;
+                let names = WindowNames::load(&self.settings);
                 self.features = features
                     .into_iter()
                     .map(|f| {
-                        let repo_name = self.repo.as_ref().map(|r| r.name.as_str()).unwrap_or("");
-                        let win_name = format!("{repo_name}:{}", f.name);
+                        let repo_path = self.repo.as_ref().map(|r| r.path.as_str()).unwrap_or("");
+                        let win_name = names.window_name(repo_path, &f.name);
                         let pane_cmd = tmux_local::get_pane_command(session, &win_name);
                         CliFeature {
                             feature: f,
@@ -846,6 +1269,8 @@ impl App {
                     .collect();
                 self.loading = false;
                 self.error = None;
+                self.refresh_note(tx.clone());
+                self.trigger_load_activity(tx);
             }
             AppEvent::FeaturesLoaded(Err(e)) => {
                 self.loading = false;
@@ -859,9 +1284,37 @@ impl App {
                     branch: format!("feature/{name}"),
                     is_active: false,
                     is_main: false,
+                    has_unread: false,
+                    suspended: false,
+                    ..Default::default()
                 });
                 self.do_attach(tx);
             }
+            AppEvent::WindowStatus(Ok(windows)) => {
+                let last = (
+                    self.cli_state.last_repo.as_deref(),
+                    self.cli_state.last_feature.as_deref(),
+                );
+                self.jump_to = windows.into_iter().find(|w| {
+                    w.feature_name.is_some()
+                        && (w.repo_path.as_deref(), w.feature_name.as_deref()) != last
+                });
+                // Keep the highlighted choice under the cursor
+                if self.jump_to.is_some() && self.selected_index > 0 {
+                    self.selected_index += 1;
+                }
+            }
+            // Older servers have no window status; resuming still works
+            AppEvent::WindowStatus(Err(_)) => {}
+            AppEvent::ProtectedLoaded(result) => {
+                // Older servers and unreachable forges protect nothing
+                self.protected = result.unwrap_or_default();
+            }
+            AppEvent::NoteLoaded(name, result)
+                if self.note_for.as_deref() == Some(name.as_str()) =>
+            {
+                self.note = Some(result);
+            }
             AppEvent::FeatureCreated(Err(e)) => {
                 self.loading = false;
                 self.error = Some(e);
@@ -878,8 +1331,11 @@ impl App {
                             .unwrap_or_default()
                             .as_millis() as u64,
                     ),
+                    ..self.cli_state.clone()
                 };
-                state::save_state(&self.settings, &new_state);
+                if let Err(e) = state::save_state(&self.settings, &new_state) {
+                    self.state_error = Some(tf("state.save_failed", &[("err", &e)]));
+                }
 
                 self.attach_session = Some(self.settings.tmux.session.clone());
                 self.should_quit = true;
@@ -889,37 +1345,395 @@ impl App {
                 self.error = Some(e);
                 self.screen = Screen::FeaturePicker;
             }
-            AppEvent::HealthResult(id, ok) => {
-                self.health_map.insert(id, ok);
+            AppEvent::HealthResult(id, latency, report) => {
+                // Back online once the selected server answers again
+                if latency.is_some() && self.server.as_ref().is_some_and(|s| s.id == id) {
+                    self.offline = false;
+                }
+                if let Some(seen) = pinning::take_seen(&id) {
+                    self.trust_certificate(&id, seen);
+                }
+                if let Some(report) = report {
+                    self.health_reports.insert(id.clone(), report);
+                }
+                self.health_map.insert(id, latency);
+            }
+            AppEvent::Offline => {
+                self.offline = true;
+            }
+            AppEvent::ServerEvents(id, Ok(events)) => {
+                // Ignore the answer of a server left since
+                if let Some(server) = self.server.as_ref().filter(|s| s.id == id) {
+                    let config = &self.settings.notifications;
+                    for notification in self.notifications.update(&server.name, &events, config) {
+                        notify::show(notification);
+                    }
+                }
+            }
+            AppEvent::Reconciled(Ok(issues)) => {
+                self.issues = issues.into_iter().filter(|i| !i.fixed).collect();
+            }
+            AppEvent::BatchDone(Ok(results)) => {
+                let ok = results.iter().filter(|r| r.ok).count();
+                let failures: Vec<String> = results
+                    .iter()
+                    .filter(|r| !r.ok)
+                    .map(|r| {
+                        format!("{}: {}", r.feature_name, r.detail.as_deref().unwrap_or("failed"))
+                    })
+                    .collect();
+                let mut notice = tf("batch.result", &[("ok", &ok), ("total", &results.len())]);
+                if !failures.is_empty() {
+                    notice.push_str(&format!(" — {}", failures.join("; ")));
+                }
+                self.notice = Some(notice);
+                self.marked.clear();
+                self.selected_index = 0;
+                self.trigger_load_features(tx);
+            }
+            AppEvent::BatchDone(Err(e)) => {
+                self.loading = false;
+                self.error = Some(e);
+            }
+            AppEvent::ActivityLoaded(Ok(entries)) => {
+                self.last_action = entries.into_iter().next();
+            }
+            AppEvent::TasksLoaded(Ok(tasks)) => {
+                self.tasks = tasks;
+                self.loading = false;
+            }
+            AppEvent::TasksLoaded(Err(e)) => {
+                self.loading = false;
+                self.error = Some(e);
+            }
+            AppEvent::TaskStarted(Ok(notice)) => {
+                self.notice = Some(notice);
+                self.screen = Screen::FeaturePicker;
+                self.tasks.clear();
+                self.loading = false;
+                self.selected_index = 0;
+                self.trigger_load_activity(tx);
+            }
+            AppEvent::TaskStarted(Err(e)) => {
+                self.loading = false;
+                self.error = Some(e);
+            }
+            AppEvent::SearchDone(Ok(response)) => {
+                self.searched = Some(self.input_text.trim().to_string());
+                self.search_results = response.matches;
+                self.search_truncated = response.truncated;
+                self.selected_index = 0;
+                self.loading = false;
+            }
+            AppEvent::SearchDone(Err(e)) => {
+                self.loading = false;
+                self.error = Some(e);
+            }
+            AppEvent::UndoDone(result) => {
+                self.notice = Some(result.unwrap_or_else(|e| e));
+                if self.screen == Screen::FeaturePicker {
+                    self.trigger_load_features(tx);
+                } else {
+                    self.trigger_load_activity(tx);
+                }
             }
             _ => {}
         }
     }
 
-    fn trigger_load_repos(&self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+    /// Pin the certificate a server presented on its first HTTPS connection,
+    /// and report one that changed when the server lets the change through.
+    fn trust_certificate(&mut self, id: &str, seen: pinning::Seen) {
+        let Some(server) = self.servers.iter().find(|s| s.id == id) else {
+            return;
+        };
+        if seen.changed {
+            self.error = Some(tf(
+                "pin.changed",
+                &[("server", &server.name), ("fingerprint", &seen.fingerprint)],
+            ));
+            return;
+        }
+        if server.cert.fingerprint.is_some() || pinning::is_relay(&self.settings, server) {
+            return;
+        }
+        let mut server = server.clone();
+        server.cert.fingerprint = Some(seen.fingerprint);
+        if let Some(selected) = self.server.as_mut().filter(|s| s.id == id) {
+            selected.cert = server.cert.clone();
+        }
+        match state::add_server(&self.settings, server) {
+            Ok(servers) => self.servers = servers,
+            Err(e) => self.error = Some(tf("servers.save_failed", &[("err", &e)])),
+        }
+    }
+
+    /// Measure the round trip to every listed server on the server picker, or
+    /// to the selected server elsewhere.
+    fn trigger_health_checks(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        self.last_health_check = Some(Instant::now());
+        let servers: Vec<ServerConfig> = match self.screen {
+            Screen::ServerPicker => self.servers.clone(),
+            Screen::Setup | Screen::Attaching => Vec::new(),
+            _ => self.server.iter().cloned().collect(),
+        };
+        for server in servers {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let health = api_client::server_health(&server).await;
+                let (latency, report) = match health {
+                    Some((latency, report)) => (Some(latency), report),
+                    None => (None, None),
+                };
+                tx.send(AppEvent::HealthResult(server.id, latency, report)).ok();
+            });
+        }
+    }
+
+    /// Ask the selected server what happened since the last poll.
+    fn trigger_poll_events(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        self.last_events_poll = Some(Instant::now());
+        if let Some(server) = self.server.clone() {
+            tokio::spawn(async move {
+                let result = api_client::server_events(&server).await;
+                tx.send(AppEvent::ServerEvents(server.id, result)).ok();
+            });
+        }
+    }
+
+    fn trigger_load_repos(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
         if let Some(ref server) = self.server {
             let server = server.clone();
-            tokio::spawn(async move {
-                let result = api_client::list_repos(&server).await;
+            let settings = self.settings.clone();
+            let call = tokio::spawn(async move {
+                let mut result = api_client::list_repos(&server).await;
+                if result.is_err() && local::should_fallback(&server).await {
+                    tx.send(AppEvent::Offline).ok();
+                    result = local::list_repos(&settings).await;
+                }
                 tx.send(AppEvent::ReposLoaded(result)).ok();
             });
+            self.in_flight = Some(call.abort_handle());
         }
     }
 
-    fn trigger_load_features(&self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+    fn trigger_load_features(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
         if let Some(ref server) = self.server {
             if let Some(ref repo) = self.repo {
                 let server = server.clone();
                 let repo_path = repo.path.clone();
-                tokio::spawn(async move {
-                    let result = api_client::list_features(&server, &repo_path).await;
+                let settings = self.settings.clone();
+                let call = tokio::spawn(async move {
+                    let mut result = api_client::list_features(&server, &repo_path).await;
+                    if result.is_err() && local::should_fallback(&server).await {
+                        tx.send(AppEvent::Offline).ok();
+                        result = local::list_features(&settings, &repo_path).await;
+                    }
                     tx.send(AppEvent::FeaturesLoaded(result)).ok();
                 });
+                self.in_flight = Some(call.abort_handle());
+            }
+        }
+    }
+
+    /// Load the note of the highlighted feature when the notes panel shows
+    /// another one.
+    fn refresh_note(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        let selected = self
+            .features
+            .get(self.selected_index)
+            .map(|cf| cf.feature.name.clone())
+            .filter(|_| self.notes_open);
+        if selected == self.note_for {
+            return;
+        }
+        self.note_for = selected.clone();
+        self.note = None;
+        if let (Some(name), Some(server), Some(repo)) =
+            (selected, self.server.clone(), self.repo.clone())
+        {
+            tokio::spawn(async move {
+                let result = api_client::feature_note(&server, &repo.path, &name).await;
+                tx.send(AppEvent::NoteLoaded(name, result)).ok();
+            });
+        }
+    }
+
+    fn trigger_load_protected(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        self.protected.clear();
+        if let (Some(server), Some(repo)) = (self.server.clone(), self.repo.clone()) {
+            tokio::spawn(async move {
+                let result = api_client::protected_branches(&server, &repo.path).await;
+                tx.send(AppEvent::ProtectedLoaded(result)).ok();
+            });
+        }
+    }
+
+    /// Whether the name being confirmed is a branch the forge protects.
+    pub fn creating_protected(&self) -> bool {
+        self.confirm_step && is_protected(&self.protected, &self.input_text)
+    }
+
+    /// Issues reconciliation can fix. Features whose upstream is gone are
+    /// only the subject of a tip.
+    pub fn fixable_issues(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.kind != ReconcileIssueKind::GoneUpstream)
+            .count()
+    }
+
+    /// Tips about the selected server that were not dismissed.
+    pub fn tips(&self) -> Vec<Tip> {
+        let Some(server) = self.server.as_ref() else {
+            return Vec::new();
+        };
+        let dismissed = &self.cli_state.dismissed_tips;
+        widgets::tips::tips(self.health_reports.get(&server.id), &self.issues)
+            .into_iter()
+            .filter(|tip| !dismissed.contains(&format!("{}:{}", server.id, tip.key())))
+            .collect()
+    }
+
+    /// Hide the tips shown for good, or until what they are about changes.
+    fn dismiss_tips(&mut self) {
+        let tips = self.tips();
+        let Some(id) = self.server.as_ref().map(|s| s.id.clone()) else {
+            return;
+        };
+        if tips.is_empty() {
+            return;
+        }
+        let dismissed = &mut self.cli_state.dismissed_tips;
+        // Only the latest count of merged features is worth remembering
+        if tips.iter().any(|tip| matches!(tip, Tip::MergedFeatures(_))) {
+            let merged = format!("{id}:{MERGED_FEATURES_KEY}");
+            dismissed.retain(|key| !key.starts_with(&merged));
+        }
+        dismissed.extend(tips.iter().map(|tip| format!("{id}:{}", tip.key())));
+        // Saved on its own: the last session may have changed since start
+        let mut saved = state::load_state(&self.settings);
+        saved.dismissed_tips = self.cli_state.dismissed_tips.clone();
+        if let Err(e) = state::save_state(&self.settings, &saved) {
+            self.state_error = Some(tf("tips.save_failed", &[("err", &e)]));
+        }
+    }
+
+    /// Look for inconsistencies on the server, fixing them when `fix` is set.
+    fn trigger_reconcile(&self, fix: bool, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        if let Some(server) = self.server.clone() {
+            tokio::spawn(async move {
+                let result = api_client::reconcile(&server, fix).await;
+                tx.send(AppEvent::Reconciled(result)).ok();
+            });
+        }
+    }
+
+    fn trigger_load_activity(&self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        if let Some(ref server) = self.server {
+            let server = server.clone();
+            tokio::spawn(async move {
+                let result = api_client::list_activity(&server, 1).await;
+                tx.send(AppEvent::ActivityLoaded(result)).ok();
+            });
+        }
+    }
+
+    /// Revert the operation shown in the history bar, if the server allows it.
+    fn do_undo(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        let Some(id) = self.last_action.as_ref().filter(|e| e.undoable).map(|e| e.id) else {
+            return;
+        };
+        if let Some(server) = self.server.clone() {
+            // Hide the undo hint while the request is in flight
+            if let Some(entry) = self.last_action.as_mut() {
+                entry.undoable = false;
             }
+            tokio::spawn(async move {
+                let result = api_client::undo(&server, id).await;
+                tx.send(AppEvent::UndoDone(result)).ok();
+            });
+        }
+    }
+
+    fn trigger_load_tasks(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        if let (Some(server), Some(repo), Some(feature)) =
+            (self.server.clone(), self.repo.clone(), self.feature.clone())
+        {
+            let call = tokio::spawn(async move {
+                let result = api_client::list_tasks(&server, &repo.path, &feature.name).await;
+                tx.send(AppEvent::TasksLoaded(result)).ok();
+            });
+            self.in_flight = Some(call.abort_handle());
+        }
+    }
+
+    /// Run the highlighted task, in the background when `background` says so
+    /// or else as the task defines.
+    fn do_run_task(
+        &mut self,
+        background: Option<bool>,
+        tx: tokio::sync::mpsc::UnboundedSender<AppEvent>,
+    ) {
+        let Some(task) = self.tasks.get(self.selected_index).cloned() else {
+            return;
+        };
+        if let (Some(server), Some(repo), Some(feature)) =
+            (self.server.clone(), self.repo.clone(), self.feature.clone())
+        {
+            self.loading = true;
+            let call = tokio::spawn(async move {
+                let result =
+                    api_client::run_task(&server, &repo.path, &feature.name, &task.name, background)
+                        .await
+                        .map(|started| match (started.window, started.run_id) {
+                            (Some(window), _) => tf(
+                                "tasks.started_window",
+                                &[("task", &task.name), ("window", &window)],
+                            ),
+                            (None, id) => tf(
+                                "tasks.started_job",
+                                &[("task", &task.name), ("id", &id.unwrap_or_default())],
+                            ),
+                        });
+                tx.send(AppEvent::TaskStarted(result)).ok();
+            });
+            self.in_flight = Some(call.abort_handle());
+        }
+    }
+
+    fn do_search(&mut self, query: String, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        if let Some(server) = self.server.clone() {
+            self.loading = true;
+            self.error = None;
+            let call = tokio::spawn(async move {
+                let result = api_client::search(&server, &query).await;
+                tx.send(AppEvent::SearchDone(result)).ok();
+            });
+            self.in_flight = Some(call.abort_handle());
+        }
+    }
+
+    fn do_batch(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        let Some(action) = self.batch_action.take() else {
+            return;
+        };
+        if let (Some(server), Some(repo)) = (self.server.clone(), self.repo.clone()) {
+            let names: Vec<String> = self.marked.iter().cloned().collect();
+            self.loading = true;
+            self.notice = None;
+            let call = tokio::spawn(async move {
+                let result = api_client::bulk_features(&server, &repo.path, &names, action).await;
+                tx.send(AppEvent::BatchDone(result)).ok();
+            });
+            self.in_flight = Some(call.abort_handle());
         }
     }
 
     fn do_attach(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        if self.screen != Screen::Attaching {
+            self.attach_return = self.screen.clone();
+        }
         self.screen = Screen::Attaching;
         self.loading = true;
         self.error = None;
@@ -927,11 +1741,17 @@ impl App {
         if let (Some(server), Some(repo), Some(feature)) =
             (self.server.clone(), self.repo.clone(), self.feature.clone())
         {
-            tokio::spawn(async move {
-                let result =
+            let settings = self.settings.clone();
+            let call = tokio::spawn(async move {
+                let mut result =
                     api_client::switch_feature(&server, &repo.path, &feature.name).await;
+                if result.is_err() && local::should_fallback(&server).await {
+                    tx.send(AppEvent::Offline).ok();
+                    result = local::switch_feature(&settings, &repo.path, &feature.name).await;
+                }
                 tx.send(AppEvent::SwitchDone(result)).ok();
             });
+            self.in_flight = Some(call.abort_handle());
         }
     }
 
@@ -959,22 +1779,72 @@ impl App {
                     branch: String::new(),
                     is_active: false,
                     is_main: false,
+                    has_unread: false,
+                    suspended: false,
+                    ..Default::default()
                 });
                 self.do_attach(tx);
             }
         }
     }
 
+    /// Windows of the last session's server, for [`App::jump_to`].
+    fn trigger_window_status(&self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        let server = self
+            .cli_state
+            .last_server
+            .as_ref()
+            .and_then(|id| self.servers.iter().find(|s| s.id == *id))
+            .cloned();
+        if let Some(server) = server {
+            tokio::spawn(async move {
+                let result = api_client::window_status(&server).await;
+                tx.send(AppEvent::WindowStatus(result)).ok();
+            });
+        }
+    }
+
+    /// Attach to the feature window offered in [`App::jump_to`].
+    fn do_jump(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        let Some(window) = self.jump_to.clone() else {
+            return;
+        };
+        if let (Some(repo_path), Some(feature_name)) = (window.repo_path, window.feature_name) {
+            self.cli_state.last_repo = Some(repo_path);
+            self.cli_state.last_feature = Some(feature_name);
+            self.do_resume(tx);
+        }
+    }
+
     fn do_create_feature(&mut self, tx: tokio::sync::mpsc::UnboundedSender<AppEvent>) {
+        if self.screen != Screen::Attaching {
+            self.attach_return = self.screen.clone();
+        }
         self.screen = Screen::Attaching;
         self.loading = true;
 
         if let (Some(server), Some(repo)) = (self.server.clone(), self.repo.clone()) {
             let name = self.input_text.clone();
-            tokio::spawn(async move {
-                let result = api_client::create_feature(&server, &repo.path, &name).await;
+            let description = self.description.clone();
+            // The warning was shown on the confirmation step
+            let allow_protected = self.creating_protected();
+            let settings = self.settings.clone();
+            let call = tokio::spawn(async move {
+                let mut result = api_client::create_feature(
+                    &server,
+                    &repo.path,
+                    &name,
+                    description.as_deref(),
+                    allow_protected,
+                )
+                .await;
+                if result.is_err() && local::should_fallback(&server).await {
+                    tx.send(AppEvent::Offline).ok();
+                    result = local::create_feature(&settings, &repo.path, &name).await;
+                }
                 tx.send(AppEvent::FeatureCreated(result)).ok();
             });
+            self.in_flight = Some(call.abort_handle());
         }
     }
 }
@@ -1028,8 +1898,9 @@ mod tests {
             last_repo: Some("/tmp/repo".to_string()),
             last_feature: Some("feat".to_string()),
             last_attached: None,
+            ..Default::default()
         };
-        state::save_state(&settings, &state);
+        state::save_state(&settings, &state).unwrap();
 
         let app = App::new(settings);
         assert_eq!(app.screen, Screen::Resume);
@@ -1059,6 +1930,206 @@ mod tests {
         assert_eq!(app.screen, Screen::RepoPicker);
     }
 
+    fn cli_feature(name: &str, is_main: bool) -> CliFeature {
+        CliFeature {
+            feature: Feature {
+                name: name.to_string(),
+                worktree_path: String::new(),
+                branch: name.to_string(),
+                is_active: false,
+                is_main,
+                has_unread: false,
+                suspended: false,
+                ..Default::default()
+            },
+            pane_command: None,
+        }
+    }
+
+    #[test]
+    fn test_toggle_mark_skips_main() {
+        let mut app = App::new(test_settings());
+        app.screen = Screen::FeaturePicker;
+        app.features = vec![cli_feature("main", true), cli_feature("feat", false)];
+
+        app.selected_index = 0;
+        app.toggle_mark();
+        assert!(app.marked.is_empty());
+
+        app.selected_index = 1;
+        app.toggle_mark();
+        assert!(app.marked.contains("feat"));
+        app.toggle_mark();
+        assert!(app.marked.is_empty());
+    }
+
+    #[test]
+    fn test_feature_create_names_a_described_feature() {
+        let mut app = App::new(test_settings());
+        app.screen = Screen::FeatureCreate;
+        let mut taken = cli_feature("dark-mode", false);
+        taken.feature.branch = "feature/dark-mode".to_string();
+        app.features = vec![taken];
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+
+        for c in "The dark mode".chars() {
+            app.handle_feature_create_key(KeyCode::Char(c), tx.clone());
+        }
+        assert_eq!(app.proposed_name().as_deref(), Some("feature/dark-mode-2"));
+        app.handle_feature_create_key(KeyCode::Enter, tx.clone());
+        assert!(app.confirm_step);
+        assert_eq!(app.input_text, "feature/dark-mode-2");
+        assert_eq!(app.description.as_deref(), Some("The dark mode"));
+
+        // Declining goes back to the sentence
+        app.handle_feature_create_key(KeyCode::Char('n'), tx);
+        assert_eq!(app.input_text, "The dark mode");
+        assert!(app.description.is_none());
+    }
+
+    #[test]
+    fn test_protected_branch_is_warned_about() {
+        let mut app = App::new(test_settings());
+        app.screen = Screen::FeatureCreate;
+        app.protected = vec!["main".to_string(), "release/*".to_string()];
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+
+        for c in "release/v2".chars() {
+            app.handle_feature_create_key(KeyCode::Char(c), tx.clone());
+        }
+        assert!(!app.creating_protected());
+        app.handle_feature_create_key(KeyCode::Enter, tx.clone());
+        assert!(app.creating_protected());
+
+        app.handle_feature_create_key(KeyCode::Char('n'), tx);
+        app.input_text = "feature/login".to_string();
+        app.confirm_step = true;
+        assert!(!app.creating_protected());
+    }
+
+    #[test]
+    fn test_jump_offered_for_another_busy_feature() {
+        let mut app = App::new(test_settings());
+        app.screen = Screen::Resume;
+        app.cli_state.last_repo = Some("/repos/app".to_string());
+        app.cli_state.last_feature = Some("login".to_string());
+        app.selected_index = 1;
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let window = |name: &str, feature: Option<&str>| WindowState {
+            window: name.to_string(),
+            repo_path: feature.map(|_| "/repos/app".to_string()),
+            feature_name: feature.map(str::to_string),
+            command: Some("cargo".to_string()),
+            idle: false,
+            last_activity: 100,
+            has_unread: false,
+        };
+
+        // The window of the last session is not worth a jump
+        let windows = vec![window("scratch", None), window("app:login", Some("login"))];
+        app.handle_async_event(AppEvent::WindowStatus(Ok(windows)), tx.clone());
+        assert!(app.jump_to.is_none());
+        assert_eq!(app.selected_index, 1);
+
+        app.handle_async_event(
+            AppEvent::WindowStatus(Ok(vec![window("app:tests", Some("tests"))])),
+            tx,
+        );
+        assert_eq!(app.jump_to.as_ref().unwrap().window, "app:tests");
+        // Still on "No, choose another session"
+        assert_eq!(app.selected_index, 2);
+    }
+
+    #[test]
+    fn test_dismissed_tips_stay_hidden() {
+        let (_tmp, settings) = tmp_settings_with_config();
+        let mut app = App::new(settings.clone());
+        app.server = Some(app.servers[0].clone());
+        app.screen = Screen::RepoPicker;
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let report: HealthResponse = serde_json::from_value(serde_json::json!({
+            "status": "ok", "tmuxSession": "nomadflow", "apiPort": 8080, "authRequired": false,
+        }))
+        .unwrap();
+        app.handle_async_event(
+            AppEvent::HealthResult("localhost".to_string(), None, Some(report)),
+            tx.clone(),
+        );
+        let gone = |target: &str| ReconcileIssue {
+            kind: ReconcileIssueKind::GoneUpstream,
+            repo_path: "/repos/app".to_string(),
+            target: target.to_string(),
+            fixed: false,
+        };
+        app.handle_async_event(
+            AppEvent::Reconciled(Ok(vec![gone("a"), gone("b")])),
+            tx.clone(),
+        );
+        assert_eq!(app.tips(), [Tip::NoSecret, Tip::MergedFeatures(2)]);
+        assert_eq!(app.fixable_issues(), 0);
+
+        app.handle_key(KeyCode::Char('x'), KeyModifiers::NONE, tx.clone());
+        assert!(app.tips().is_empty());
+        assert_eq!(
+            state::load_state(&settings).dismissed_tips,
+            ["localhost:no-secret", "localhost:merged-features-2"]
+        );
+
+        // One more merged feature is news
+        app.handle_async_event(
+            AppEvent::Reconciled(Ok(vec![gone("a"), gone("b"), gone("c")])),
+            tx.clone(),
+        );
+        assert_eq!(app.tips(), [Tip::MergedFeatures(3)]);
+        app.handle_key(KeyCode::Char('x'), KeyModifiers::NONE, tx);
+        assert_eq!(
+            app.cli_state.dismissed_tips,
+            ["localhost:no-secret", "localhost:merged-features-3"]
+        );
+    }
+
+    #[test]
+    fn test_notes_panel_follows_selection() {
+        let mut app = App::new(test_settings());
+        app.screen = Screen::FeaturePicker;
+        app.features = vec![cli_feature("main", true), cli_feature("feat", false)];
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+
+        app.handle_feature_picker_key(KeyCode::Char('n'), tx.clone());
+        assert_eq!(app.note_for.as_deref(), Some("main"));
+        app.handle_feature_picker_key(KeyCode::Down, tx.clone());
+        assert_eq!(app.note_for.as_deref(), Some("feat"));
+        assert!(app.note.is_none());
+
+        // A late answer for the feature left behind is dropped
+        app.handle_async_event(AppEvent::NoteLoaded("main".to_string(), Ok(None)), tx.clone());
+        assert!(app.note.is_none());
+        app.handle_async_event(AppEvent::NoteLoaded("feat".to_string(), Ok(None)), tx.clone());
+        assert!(matches!(app.note, Some(Ok(None))));
+
+        app.handle_feature_picker_key(KeyCode::Char('n'), tx);
+        assert!(app.note_for.is_none());
+    }
+
+    #[test]
+    fn test_go_back_clears_marks_before_leaving() {
+        let mut app = App::new(test_settings());
+        app.screen = Screen::FeaturePicker;
+        app.marked.insert("feat".to_string());
+        app.batch_action = Some(BulkFeatureAction::Delete);
+
+        app.go_back();
+        assert!(app.batch_action.is_none());
+        assert_eq!(app.screen, Screen::FeaturePicker);
+
+        app.go_back();
+        assert!(app.marked.is_empty());
+        assert_eq!(app.screen, Screen::FeaturePicker);
+
+        app.go_back();
+        assert_eq!(app.screen, Screen::RepoPicker);
+    }
+
     #[test]
     fn test_go_back_from_feature_create() {
         let mut app = App::new(test_settings());
@@ -1067,6 +2138,101 @@ mod tests {
         assert_eq!(app.screen, Screen::FeaturePicker);
     }
 
+    #[test]
+    fn test_search_enter_opens_result_once_searched() {
+        let mut app = App::new(test_settings());
+        // No server: the switch request is not sent
+        app.server = None;
+        app.screen = Screen::Search;
+        app.input_text = "login".to_string();
+        app.searched = Some("login".to_string());
+        app.search_results = vec![SearchMatch {
+            repo_path: "/repos/api".to_string(),
+            feature_name: "add-login".to_string(),
+            path: "src/auth.rs".to_string(),
+            line: 3,
+            text: "fn login()".to_string(),
+        }];
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE, tx);
+        assert_eq!(app.screen, Screen::Attaching);
+        assert_eq!(app.repo.as_ref().unwrap().name, "api");
+        assert_eq!(app.feature.as_ref().unwrap().name, "add-login");
+    }
+
+    #[test]
+    fn test_attach_timeout_returns_to_features() {
+        let mut app = App::new(test_settings());
+        app.server = None;
+        app.screen = Screen::Search;
+        app.do_attach(tokio::sync::mpsc::unbounded_channel().0);
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        app.busy_since = Some(Instant::now());
+        app.check_attach_timeout(tx.clone());
+        assert_eq!(app.screen, Screen::Attaching);
+
+        app.busy_since = Some(Instant::now() - Duration::from_secs(31));
+        app.check_attach_timeout(tx);
+        assert_eq!(app.screen, Screen::FeaturePicker);
+        assert!(app.notice.as_ref().unwrap().contains("30"));
+    }
+
+    #[tokio::test]
+    async fn test_escape_cancels_attaching() {
+        let mut app = App::new(test_settings());
+        // No server: the switch request is not sent
+        app.server = None;
+        app.screen = Screen::FeaturePicker;
+        app.do_attach(tokio::sync::mpsc::unbounded_channel().0);
+        assert_eq!(app.screen, Screen::Attaching);
+        let call = tokio::spawn(std::future::pending::<()>());
+        app.in_flight = Some(call.abort_handle());
+
+        app.on_tick();
+        assert!(app.busy_since.is_some());
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        app.handle_key(KeyCode::Esc, KeyModifiers::NONE, tx);
+        assert_eq!(app.screen, Screen::FeaturePicker);
+        assert!(!app.loading);
+        assert!(call.await.unwrap_err().is_cancelled());
+        app.on_tick();
+        assert_eq!(app.busy_since, None);
+    }
+
+    #[test]
+    fn test_go_back_from_search() {
+        let mut app = App::new(test_settings());
+        app.screen = Screen::Search;
+        app.input_text = "q".to_string();
+        app.input_cursor = 1;
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        // q is typed into the query instead of quitting
+        app.handle_key(KeyCode::Char('q'), KeyModifiers::NONE, tx);
+        assert!(!app.should_quit);
+        assert_eq!(app.input_text, "qq");
+        app.go_back();
+        assert_eq!(app.screen, Screen::RepoPicker);
+        assert!(app.input_text.is_empty());
+    }
+
+    #[test]
+    fn test_feature_create_refuses_invalid_names() {
+        let mut app = App::new(test_settings());
+        app.screen = Screen::FeatureCreate;
+        app.input_text = "Add~login".to_string();
+        app.input_cursor = app.input_text.len();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE, tx.clone());
+        assert!(!app.confirm_step);
+
+        // Tab takes the first suggestion
+        app.handle_key(KeyCode::Tab, KeyModifiers::NONE, tx.clone());
+        assert_eq!(app.input_text, "add-login");
+        assert_eq!(app.input_cursor, 9);
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE, tx);
+        assert!(app.confirm_step);
+    }
+
     #[test]
     fn test_go_back_from_server_add() {
         let mut app = App::new(test_settings());
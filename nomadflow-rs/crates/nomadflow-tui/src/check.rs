@@ -0,0 +1,285 @@
+//! API smoke test behind `nomadflow check <server>`: exercises what the app
+//! relies on against a configured server, e.g. after an upgrade.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+
+use crate::api_client::{self, get_api_base_url};
+use crate::pinning;
+use crate::remote;
+use crate::state::ServerConfig;
+
+/// Time allowed for each check.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Branch prefix of the scratch features, so that a leftover one is easy to
+/// recognize.
+const SCRATCH_PREFIX: &str = "feature/nomadflow-check-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    Health,
+    Auth,
+    ListRepos,
+    Feature,
+    Terminal,
+}
+
+impl Check {
+    /// i18n key of the check's label.
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Self::Health => "check.health",
+            Self::Auth => "check.auth",
+            Self::ListRepos => "check.list_repos",
+            Self::Feature => "check.feature",
+            Self::Terminal => "check.terminal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed(String),
+    /// Not applicable; holds the i18n key of the reason.
+    Skipped(&'static str),
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub check: Check,
+    /// What was exercised (URL, repo or branch).
+    pub target: String,
+    pub outcome: Outcome,
+    pub latency: Option<Duration>,
+}
+
+impl CheckResult {
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome, Outcome::Failed(_))
+    }
+}
+
+/// Run `attempt` with the check timeout and time it.
+async fn timed<F>(check: Check, target: String, attempt: F) -> CheckResult
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let start = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, attempt)
+        .await
+        .unwrap_or_else(|_| Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())));
+    CheckResult {
+        check,
+        target,
+        outcome: match result {
+            Ok(()) => Outcome::Passed,
+            Err(e) => Outcome::Failed(e),
+        },
+        latency: Some(start.elapsed()),
+    }
+}
+
+fn skipped(check: Check, target: String, reason: &'static str) -> CheckResult {
+    CheckResult {
+        check,
+        target,
+        outcome: Outcome::Skipped(reason),
+        latency: None,
+    }
+}
+
+/// A request without credentials must be refused.
+async fn refuses_anonymous(server: &ServerConfig, url: &str) -> Result<(), String> {
+    let resp = pinning::client(server)?
+        .post(url)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    match resp.status().as_u16() {
+        401 | 403 => Ok(()),
+        status => Err(format!("expected HTTP 401 without a token, got {status}")),
+    }
+}
+
+/// Name of a scratch feature branch that no one else uses.
+fn scratch_branch() -> String {
+    let suffix: u32 = rand::rng().random();
+    format!("{SCRATCH_PREFIX}{suffix:08x}")
+}
+
+/// Create a scratch feature, find it in the list and delete it again.
+async fn feature_round_trip(
+    server: &ServerConfig,
+    repo_path: &str,
+    branch: &str,
+) -> Result<(), String> {
+    api_client::create_feature(server, repo_path, branch, None, false).await?;
+    let features = api_client::list_features(server, repo_path).await;
+    // Delete even when listing failed, not to leave the scratch feature behind
+    let name = features
+        .as_ref()
+        .ok()
+        .and_then(|features| features.iter().find(|f| f.branch == branch))
+        .map(|f| f.name.clone());
+    let fallback = branch.rsplit('/').next().unwrap_or(branch);
+    api_client::delete_feature(server, repo_path, name.as_deref().unwrap_or(fallback)).await?;
+    match (features, name) {
+        (Err(e), _) => Err(e),
+        (Ok(_), None) => Err(format!("{branch} was created but not listed")),
+        (Ok(_), Some(_)) => Ok(()),
+    }
+}
+
+/// Open the terminal WebSocket and wait for ttyd's first frame.
+async fn terminal_handshake(server: &ServerConfig) -> Result<(), String> {
+    let mut request = remote::ws_url(server)?
+        .into_client_request()
+        .map_err(|e| e.to_string())?;
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", "tty".parse().unwrap());
+    let connector = pinning::tls_config(server)?
+        .map(|tls| tokio_tungstenite::Connector::Rustls(std::sync::Arc::new(tls)));
+    let (mut ws, _) =
+        tokio_tungstenite::connect_async_tls_with_config(request, None, false, connector)
+            .await
+            .map_err(|e| e.to_string())?;
+    let handshake = serde_json::json!({ "AuthToken": "", "columns": 80, "rows": 24 });
+    ws.send(Message::Text(handshake.to_string().into()))
+        .await
+        .map_err(|e| e.to_string())?;
+    let result = loop {
+        match ws.next().await {
+            Some(Ok(Message::Binary(_) | Message::Text(_))) => break Ok(()),
+            Some(Ok(Message::Close(_))) | None => {
+                break Err("closed before the first frame".to_string())
+            }
+            Some(Err(e)) => break Err(e.to_string()),
+            Some(Ok(_)) => continue,
+        }
+    };
+    ws.close(None).await.ok();
+    result
+}
+
+/// Run every check in order. `scratch_repo` (name or path of a repo of the
+/// server) is where a feature is created and deleted; without it, that check
+/// is skipped so that nothing is written.
+pub async fn run(server: &ServerConfig, scratch_repo: Option<&str>) -> Vec<CheckResult> {
+    let api = get_api_base_url(server);
+    let mut results = Vec::new();
+
+    let health_url = format!("{}/health", api.trim_end_matches("/api"));
+    results.push(
+        timed(Check::Health, health_url, async {
+            api_client::check_health(server)
+                .await
+                .map(|_| ())
+                .ok_or_else(|| "no healthy answer".to_string())
+        })
+        .await,
+    );
+
+    let list_url = format!("{api}/list-repos");
+    results.push(if let Some(ref e) = server.token_error {
+        timed(Check::Auth, list_url.clone(), async { Err(e.clone()) }).await
+    } else if server.auth_token.is_none() {
+        skipped(Check::Auth, list_url.clone(), "check.skip_no_token")
+    } else {
+        timed(
+            Check::Auth,
+            list_url.clone(),
+            refuses_anonymous(server, &list_url),
+        )
+        .await
+    });
+
+    let mut repos = Vec::new();
+    results.push(
+        timed(Check::ListRepos, list_url, async {
+            repos = api_client::list_repos(server).await?;
+            Ok(())
+        })
+        .await,
+    );
+
+    let repos_failed = results[2].failed();
+    results.push(match scratch_repo {
+        None => skipped(Check::Feature, String::new(), "check.skip_no_repo"),
+        Some(wanted) => match repos.iter().find(|r| r.name == wanted || r.path == wanted) {
+            None if repos_failed => {
+                skipped(Check::Feature, wanted.to_string(), "check.skip_no_repos")
+            }
+            None => CheckResult {
+                check: Check::Feature,
+                target: wanted.to_string(),
+                outcome: Outcome::Failed("no such repo on the server".to_string()),
+                latency: None,
+            },
+            Some(repo) => {
+                let branch = scratch_branch();
+                timed(
+                    Check::Feature,
+                    format!("{}: {branch}", repo.name),
+                    feature_round_trip(server, &repo.path, &branch),
+                )
+                .await
+            }
+        },
+    });
+
+    let ws_target = format!("{}/terminal/ws", api.trim_end_matches("/api"));
+    results.push(timed(Check::Terminal, ws_target, terminal_handshake(server)).await);
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_branch_is_recognizable() {
+        let branch = scratch_branch();
+        assert!(branch.starts_with(SCRATCH_PREFIX));
+        assert_eq!(branch.len(), SCRATCH_PREFIX.len() + 8);
+        assert_ne!(branch, scratch_branch());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_fails_without_writing() {
+        let server = ServerConfig {
+            id: "test".to_string(),
+            name: "test".to_string(),
+            api_url: Some("http://127.0.0.1:9".to_string()),
+            ttyd_url: None,
+            auth_token: Some("secret".to_string()),
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
+        };
+        let results = run(&server, Some("my-app")).await;
+        let checks: Vec<_> = results.iter().map(|r| r.check).collect();
+        assert_eq!(
+            checks,
+            [
+                Check::Health,
+                Check::Auth,
+                Check::ListRepos,
+                Check::Feature,
+                Check::Terminal
+            ]
+        );
+        assert!(results[0].failed());
+        assert!(results[2].failed());
+        // No repo list, so no scratch feature either
+        assert_eq!(results[3].outcome, Outcome::Skipped("check.skip_no_repos"));
+    }
+}
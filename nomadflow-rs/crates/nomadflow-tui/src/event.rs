@@ -2,14 +2,19 @@ use std::time::Duration;
 
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent};
 
-use nomadflow_core::models::{Feature, Repository};
+use nomadflow_core::models::{
+    ActivityEntry, BulkFeatureResult, Feature, FeatureNote, HealthResponse, ReconcileIssue,
+    Repository, SearchResponse, TaskInfo, WindowState,
+};
+
+use crate::notify::ServerEvents;
 
 /// Application events combining terminal events and async results.
 #[derive(Debug)]
 pub enum AppEvent {
     /// Terminal key event.
     Key(KeyEvent),
-    /// Terminal tick (for animations/spinners).
+    /// No terminal event during the poll timeout (drives spinners).
     Tick,
     /// Repos loaded from API.
     ReposLoaded(Result<Vec<Repository>, String>),
@@ -19,11 +24,36 @@ pub enum AppEvent {
     FeatureCreated(Result<String, String>),
     /// Switch feature completed.
     SwitchDone(Result<String, String>),
-    /// Health check result for a server.
-    HealthResult(String, bool),
+    /// Health check result for a server: its round-trip time, or `None` if
+    /// unreachable, and what it reported.
+    HealthResult(String, Option<Duration>, Option<HealthResponse>),
+    /// Batch action on marked features completed.
+    BatchDone(Result<Vec<BulkFeatureResult>, String>),
+    /// Latest server activity loaded (for the history bar).
+    ActivityLoaded(Result<Vec<ActivityEntry>, String>),
+    /// Undo of the last action completed.
+    UndoDone(Result<String, String>),
+    /// The localhost API did not answer; the request was handled in-process.
+    Offline,
+    /// Inconsistencies reported by the server, after fixing them if asked.
+    Reconciled(Result<Vec<ReconcileIssue>, String>),
+    /// Windows of the last session's server, busiest first.
+    WindowStatus(Result<Vec<WindowState>, String>),
+    /// Note of a feature (by name) loaded for the notes panel.
+    NoteLoaded(String, Result<Option<FeatureNote>, String>),
+    /// Branches the forge of the selected repo protects.
+    ProtectedLoaded(Result<Vec<String>, String>),
+    /// Tasks of the selected feature loaded.
+    TasksLoaded(Result<Vec<TaskInfo>, String>),
+    /// A task was started; the message says where.
+    TaskStarted(Result<String, String>),
+    /// Search across repos completed.
+    SearchDone(Result<SearchResponse, String>),
+    /// Events of a server (by id), polled for desktop notifications.
+    ServerEvents(String, Result<ServerEvents, String>),
 }
 
-/// Poll for crossterm events with a timeout.
+/// Poll for crossterm events with a timeout, `Tick` when none came.
 pub fn poll_event(timeout: Duration) -> Option<AppEvent> {
     if event::poll(timeout).unwrap_or(false) {
         if let Ok(evt) = event::read() {
@@ -32,6 +62,7 @@ pub fn poll_event(timeout: Duration) -> Option<AppEvent> {
                 _ => None,
             };
         }
+        return None;
     }
-    None
+    Some(AppEvent::Tick)
 }
@@ -0,0 +1,133 @@
+//! Glyphs prefixed to states, so that they can be told apart without their
+//! color. Each state has its own shape, and `tui.ascii` swaps them for plain
+//! ASCII on terminals whose font lacks the symbols.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+const SPINNER: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const ASCII_SPINNER: &[&str] = &["|", "/", "-", "\\"];
+
+/// A state shown next to a feature or server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// A program is running in the feature's window.
+    Busy,
+    /// The feature's window is back at its shell prompt.
+    Idle,
+    /// The repo's own checkout.
+    Main,
+    /// The window printed something since it was last looked at.
+    Unread,
+    /// The feature's worktree has uncommitted changes.
+    Dirty,
+    Warning,
+    Error,
+}
+
+impl Status {
+    fn unicode(self) -> &'static str {
+        match self {
+            Status::Busy => "●",
+            Status::Idle => "○",
+            Status::Main => "⌂",
+            Status::Unread => "✦",
+            Status::Dirty => "✎",
+            Status::Warning => "⚠",
+            Status::Error => "✗",
+        }
+    }
+
+    fn ascii(self) -> &'static str {
+        match self {
+            Status::Busy => "*",
+            Status::Idle => "-",
+            Status::Main => "#",
+            Status::Unread => "+",
+            Status::Dirty => "~",
+            Status::Warning => "!",
+            Status::Error => "x",
+        }
+    }
+}
+
+/// Use ASCII glyphs from now on.
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+/// The glyph of `status`.
+pub fn glyph(status: Status) -> &'static str {
+    if ASCII.load(Ordering::Relaxed) {
+        status.ascii()
+    } else {
+        status.unicode()
+    }
+}
+
+/// Frame of the spinner shown at `tick`.
+pub fn spinner(tick: usize) -> &'static str {
+    let frames = if ASCII.load(Ordering::Relaxed) {
+        ASCII_SPINNER
+    } else {
+        SPINNER
+    };
+    frames[tick % frames.len()]
+}
+
+/// `text` prefixed with the glyph of `status`.
+pub fn mark(status: Status, text: &str) -> String {
+    format!("{} {text}", glyph(status))
+}
+
+/// Commits ahead of and behind the upstream, e.g. `↑2 ↓1`, leaving out the
+/// zero counts. Empty when in sync.
+pub fn ahead_behind(ahead: u32, behind: u32) -> String {
+    let (up, down) = if ASCII.load(Ordering::Relaxed) {
+        ("^", "v")
+    } else {
+        ("↑", "↓")
+    };
+    let mut counts = Vec::new();
+    if ahead > 0 {
+        counts.push(format!("{up}{ahead}"));
+    }
+    if behind > 0 {
+        counts.push(format!("{down}{behind}"));
+    }
+    counts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Status; 7] = [
+        Status::Busy,
+        Status::Idle,
+        Status::Main,
+        Status::Unread,
+        Status::Dirty,
+        Status::Warning,
+        Status::Error,
+    ];
+
+    #[test]
+    fn test_glyphs_are_distinct() {
+        for set in [Status::unicode, Status::ascii] {
+            let mut glyphs: Vec<&str> = ALL.iter().map(|s| set(*s)).collect();
+            glyphs.sort();
+            glyphs.dedup();
+            assert_eq!(glyphs.len(), ALL.len());
+        }
+        assert!(ALL.iter().all(|s| s.ascii().is_ascii()));
+        assert_eq!(
+            mark(Status::Idle, "idle"),
+            format!("{} idle", glyph(Status::Idle))
+        );
+        assert_eq!(ahead_behind(0, 0), "");
+        assert_eq!(ahead_behind(2, 0).chars().count(), 2);
+        assert_eq!(ahead_behind(2, 1).chars().count(), 5);
+    }
+}
@@ -1,15 +1,27 @@
 pub mod api_client;
 pub mod app;
+pub mod check;
 pub mod event;
+pub mod glyphs;
+pub mod local;
+pub mod notify;
+pub mod pinning;
+pub mod plain;
+pub mod quick;
+pub mod remote;
 pub mod screens;
 pub mod state;
+pub mod status;
 pub mod tmux_local;
 pub mod widgets;
 
 use std::io;
+use std::ops::{Deref, DerefMut};
+use std::sync::Once;
 
 use color_eyre::Result;
 use crossterm::{
+    cursor::Show,
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -17,39 +29,86 @@ use crossterm::{
 use ratatui::prelude::*;
 
 use nomadflow_core::config::Settings;
+use nomadflow_core::i18n::t;
 
 use crate::app::{App, AppResult};
 
+/// The TUI's terminal. Dropping it restores the user's terminal, so that
+/// every way out restores it: a return, an error bubbling up, a panic.
+struct Tui(Terminal<CrosstermBackend<io::Stdout>>);
+
+impl Deref for Tui {
+    type Target = Terminal<CrosstermBackend<io::Stdout>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Tui {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
 /// Initialize the terminal.
-fn init_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
+fn init_terminal() -> Result<Tui> {
+    install_panic_hook();
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // From here on, a failure must still leave the alternate screen
     let backend = CrosstermBackend::new(stdout);
-    let terminal = Terminal::new(backend)?;
-    Ok(terminal)
+    match Terminal::new(backend) {
+        Ok(terminal) => Ok(Tui(terminal)),
+        Err(e) => {
+            restore_terminal();
+            Err(e.into())
+        }
+    }
+}
+
+/// Restore the terminal to its original state. Best effort: this also runs
+/// while panicking.
+fn restore_terminal() {
+    disable_raw_mode().ok();
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show).ok();
 }
 
-/// Restore the terminal to its original state.
-fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture,
-    )?;
-    terminal.show_cursor()?;
-    Ok(())
+/// Restore the terminal before a panic message is printed, so that it shows
+/// in the user's shell instead of on the alternate screen. Unwinding drops
+/// [`Tui`] too late for that. The previous hook (e.g. the crash reporter)
+/// still runs afterwards.
+fn install_panic_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            previous(info);
+        }));
+    });
 }
 
 /// Run the TUI wizard. Returns the tmux session name to attach to (if any).
 pub async fn run_tui(settings: Settings) -> Result<Option<String>> {
+    if plain::is_plain() {
+        return plain::run(settings).await;
+    }
     let mut terminal = init_terminal()?;
     let mut app = App::new(settings);
 
     let result = app.run(&mut terminal).await;
-
-    restore_terminal(&mut terminal)?;
+    drop(terminal);
+    if let Some(e) = &app.state_error {
+        eprintln!("{}", glyphs::mark(glyphs::Status::Warning, e));
+    }
 
     match result {
         Ok(AppResult::Attach(session)) => Ok(Some(session)),
@@ -58,43 +117,17 @@ pub async fn run_tui(settings: Settings) -> Result<Option<String>> {
     }
 }
 
-/// Run status mode: print tmux status and exit.
-pub fn run_status(settings: &Settings) {
-    let session = &settings.tmux.session;
-
-    if !tmux_local::session_exists(session) {
-        println!("Session: {session}");
-        println!("No active session");
-        return;
-    }
-
-    let windows = tmux_local::list_windows(session);
-    println!("Session: {session}");
-    println!("{} window(s)", windows.len());
-    println!();
-
-    for w in &windows {
-        let cmd = tmux_local::get_pane_command(session, &w.name);
-        let idle = tmux_local::is_shell_idle_str(cmd.as_deref());
-        let status = match &cmd {
-            Some(_) if idle => "idle".to_string(),
-            Some(c) => format!("● {c}"),
-            None => String::new(),
-        };
-        let marker = if w.active { ">" } else { " " };
-        println!("{marker} {}: {}  {status}", w.index, w.name);
-    }
-}
-
 /// Run only the first-run setup wizard (no server needed).
 /// Returns the updated Settings on success, or None if cancelled.
 pub fn run_setup(settings: Settings) -> Result<Option<Settings>> {
+    if plain::is_plain() {
+        return plain::run_setup(settings);
+    }
     let mut terminal = init_terminal()?;
     let mut app = app::App::new(settings);
 
     let completed = app.run_setup_loop(&mut terminal)?;
-
-    restore_terminal(&mut terminal)?;
+    drop(terminal);
 
     if completed {
         Ok(Some(app.settings))
@@ -117,6 +150,9 @@ pub struct PickItem {
 
 /// Show a ratatui list picker. Returns the selected index, or None if cancelled.
 pub fn pick_from_list(title: &str, items: &[PickItem]) -> Result<Option<usize>> {
+    if plain::is_plain() {
+        return plain::pick_from_list(title, items);
+    }
     if items.is_empty() {
         return Ok(None);
     }
@@ -176,7 +212,7 @@ pub fn pick_from_list(title: &str, items: &[PickItem]) -> Result<Option<usize>>
                 f.render_widget(Paragraph::new(line), *row_area);
             }
 
-            let footer = Paragraph::new("Up/Down: navigate  Enter: select  Esc: cancel")
+            let footer = Paragraph::new(t("footer.picker"))
                 .style(Style::default().fg(Color::DarkGray));
             f.render_widget(footer, chunks[2]);
         })?;
@@ -184,22 +220,16 @@ pub fn pick_from_list(title: &str, items: &[PickItem]) -> Result<Option<usize>>
         if ct_event::poll(Duration::from_millis(50))? {
             if let ct_event::Event::Key(key) = ct_event::read()? {
                 match key.code {
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        if selected > 0 {
-                            selected -= 1;
-                        }
+                    KeyCode::Up | KeyCode::Char('k') if selected > 0 => {
+                        selected -= 1;
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if selected + 1 < items.len() {
-                            selected += 1;
-                        }
+                    KeyCode::Down | KeyCode::Char('j') if selected + 1 < items.len() => {
+                        selected += 1;
                     }
                     KeyCode::Enter => {
-                        restore_terminal(&mut terminal)?;
                         return Ok(Some(selected));
                     }
                     KeyCode::Esc | KeyCode::Char('q') => {
-                        restore_terminal(&mut terminal)?;
                         return Ok(None);
                     }
                     _ => {}
@@ -211,6 +241,9 @@ pub fn pick_from_list(title: &str, items: &[PickItem]) -> Result<Option<usize>>
 
 /// Show a ratatui y/n confirmation. Returns true if confirmed.
 pub fn confirm(message: &str) -> Result<bool> {
+    if plain::is_plain() {
+        return plain::confirm(message);
+    }
     let mut terminal = init_terminal()?;
 
     loop {
@@ -233,11 +266,9 @@ pub fn confirm(message: &str) -> Result<bool> {
             if let ct_event::Event::Key(key) = ct_event::read()? {
                 match key.code {
                     KeyCode::Char('y') | KeyCode::Char('Y') => {
-                        restore_terminal(&mut terminal)?;
                         return Ok(true);
                     }
                     KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                        restore_terminal(&mut terminal)?;
                         return Ok(false);
                     }
                     _ => {}
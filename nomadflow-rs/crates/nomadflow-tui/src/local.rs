@@ -0,0 +1,154 @@
+//! Offline mode: the core workflow run in-process with `GitService` and
+//! `TmuxService`, for the localhost server when its API does not answer.
+
+use nomadflow_core::config::Settings;
+use nomadflow_core::models::{Feature, Repository};
+use nomadflow_core::services::git::GitService;
+use nomadflow_core::services::tmux::TmuxService;
+use nomadflow_core::window_names::WindowNames;
+
+use crate::api_client;
+use crate::state::ServerConfig;
+
+/// Whether requests to `server` should be handled in-process: it is this
+/// machine and its API is not answering.
+pub async fn should_fallback(server: &ServerConfig) -> bool {
+    server.id == "localhost" && api_client::check_health(server).await.is_none()
+}
+
+/// List repos directly from the repos directory.
+pub async fn list_repos(settings: &Settings) -> Result<Vec<Repository>, String> {
+    GitService::new(settings)
+        .list_repos()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List features, flagging those whose window produced output since last viewed.
+pub async fn list_features(settings: &Settings, repo_path: &str) -> Result<Vec<Feature>, String> {
+    let mut features = GitService::new(settings)
+        .list_features(repo_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let windows = TmuxService::new(&settings.tmux.session).list_windows().await;
+    let names = WindowNames::load(settings);
+    for f in &mut features {
+        let name = names.window_name(repo_path, &f.name);
+        f.has_unread = windows.iter().any(|w| w.activity && w.name == name);
+    }
+    Ok(features)
+}
+
+/// Create a feature worktree and its tmux window.
+pub async fn create_feature(
+    settings: &Settings,
+    repo_path: &str,
+    feature_name: &str,
+) -> Result<String, String> {
+    let (worktree_path, _branch) = GitService::new(settings)
+        .create_feature(repo_path, feature_name, None)
+        .await
+        .map_err(|e| format!("Failed to create feature: {e}"))?;
+
+    let wt_name = std::path::Path::new(&worktree_path)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let tmux = TmuxService::new(&settings.tmux.session)
+        .with_limits(settings.limits.clone())
+        .with_silence_alert(settings.tmux.idle_alert_seconds);
+    tmux.ensure_session().await.map_err(|e| e.to_string())?;
+    let win_name = WindowNames::load(settings).window_name(repo_path, &wt_name);
+    tmux.ensure_window(&win_name, Some(&worktree_path))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(feature_name.to_string())
+}
+
+/// Switch the tmux session to a feature's window, creating the feature if needed.
+pub async fn switch_feature(
+    settings: &Settings,
+    repo_path: &str,
+    feature_name: &str,
+) -> Result<String, String> {
+    let git = GitService::new(settings);
+    let features = git
+        .list_features(repo_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let worktree_path = match features.into_iter().find(|f| f.name == feature_name) {
+        Some(f) => f.worktree_path,
+        None => {
+            git.create_feature(repo_path, feature_name, None)
+                .await
+                .map_err(|e| format!("Failed to switch: {e}"))?
+                .0
+        }
+    };
+
+    let tmux = TmuxService::new(&settings.tmux.session)
+        .with_limits(settings.limits.clone())
+        .with_silence_alert(settings.tmux.idle_alert_seconds);
+    tmux.ensure_session().await.map_err(|e| e.to_string())?;
+
+    let win_name = WindowNames::load(settings).window_name(repo_path, feature_name);
+    let (switched, _) = tmux
+        .switch_to_window(&win_name, Some(&worktree_path))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !switched {
+        return Err(format!("Failed to switch to window '{win_name}'"));
+    }
+
+    Ok(feature_name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(id: &str, port: u16) -> ServerConfig {
+        ServerConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            api_url: Some(format!("http://127.0.0.1:{port}")),
+            ttyd_url: None,
+            auth_token: None,
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_only_for_unreachable_localhost() {
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = closed.local_addr().unwrap().port();
+        drop(closed);
+
+        assert!(should_fallback(&server("localhost", port)).await);
+        assert!(!should_fallback(&server("remote", port)).await);
+    }
+
+    #[tokio::test]
+    async fn test_list_repos_without_server() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let settings = Settings {
+            paths: nomadflow_core::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        settings.ensure_directories().unwrap();
+        std::fs::create_dir_all(settings.repos_dir().join("demo/.git")).unwrap();
+
+        let repos = list_repos(&settings).await.unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].name, "demo");
+    }
+}
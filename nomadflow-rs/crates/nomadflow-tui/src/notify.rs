@@ -0,0 +1,260 @@
+//! Desktop notifications for what happens on the server while the TUI runs:
+//! programs going quiet, jobs ending and tunnel drops. The server's events
+//! are polled and compared with what the previous poll returned.
+
+use std::collections::HashSet;
+
+use nomadflow_core::config::NotificationsConfig;
+use nomadflow_core::i18n::{t, tf};
+use nomadflow_core::models::{TaskRun, TmuxEvent, TmuxEventKind, TunnelState};
+
+/// What the server reported at one poll.
+#[derive(Debug, Default)]
+pub struct ServerEvents {
+    /// Most recent first.
+    pub tmux_events: Vec<TmuxEvent>,
+    /// Background task runs, most recent first.
+    pub task_runs: Vec<TaskRun>,
+    /// `None` when the server does not report it.
+    pub tunnel: Option<TunnelState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+}
+
+/// What was already seen of one server, so that each event is notified
+/// once. The first poll only records the baseline.
+#[derive(Debug, Default)]
+pub struct Watcher {
+    server: Option<String>,
+    last_tmux_event: u64,
+    finished_runs: HashSet<u64>,
+    tunnel: Option<TunnelState>,
+}
+
+fn run_notification(run: &TaskRun) -> Notification {
+    let key = if run.failed() {
+        "notify.task_failed"
+    } else {
+        "notify.task_ok"
+    };
+    let status = match run.exit_code {
+        Some(code) => tf("notify.exited", &[("code", &code)]),
+        None => t("notify.killed").to_string(),
+    };
+    Notification {
+        summary: tf(key, &[("task", &run.task)]),
+        body: tf(
+            "notify.task_body",
+            &[("feature", &run.feature_name), ("status", &status)],
+        ),
+    }
+}
+
+impl Watcher {
+    /// Notifications for what changed on `server` since the last poll.
+    pub fn update(
+        &mut self,
+        server: &str,
+        events: &ServerEvents,
+        config: &NotificationsConfig,
+    ) -> Vec<Notification> {
+        if self.server.as_deref() != Some(server) {
+            *self = Self {
+                server: Some(server.to_string()),
+                ..Self::default()
+            };
+            self.record(events);
+            return Vec::new();
+        }
+
+        let mut notes = Vec::new();
+        let newest = events.tmux_events.first().map_or(0, |e| e.id);
+        // Event ids start over when the server restarts
+        if newest >= self.last_tmux_event {
+            for event in events
+                .tmux_events
+                .iter()
+                .rev()
+                .filter(|e| e.id > self.last_tmux_event)
+            {
+                match event.kind {
+                    TmuxEventKind::PaneIdle if config.pane_idle => notes.push(Notification {
+                        summary: tf("notify.pane_idle", &[("window", &event.window)]),
+                        body: t("notify.pane_idle_body").to_string(),
+                    }),
+                    TmuxEventKind::PaneDied if config.job_finished => notes.push(Notification {
+                        summary: tf("notify.pane_died", &[("window", &event.window)]),
+                        body: String::new(),
+                    }),
+                    _ => {}
+                }
+            }
+        }
+
+        if config.job_finished {
+            notes.extend(
+                events
+                    .task_runs
+                    .iter()
+                    .rev()
+                    .filter(|r| r.finished_at.is_some() && !self.finished_runs.contains(&r.id))
+                    .map(run_notification),
+            );
+        }
+
+        if config.tunnel_drop {
+            let was_up = self.tunnel == Some(TunnelState::Connected);
+            match events.tunnel {
+                Some(TunnelState::Reconnecting | TunnelState::Failed) if was_up => {
+                    notes.push(Notification {
+                        summary: t("notify.tunnel_drop").to_string(),
+                        body: tf("notify.tunnel_drop_body", &[("server", &server)]),
+                    })
+                }
+                Some(TunnelState::Connected)
+                    if matches!(
+                        self.tunnel,
+                        Some(TunnelState::Reconnecting | TunnelState::Failed)
+                    ) =>
+                {
+                    notes.push(Notification {
+                        summary: t("notify.tunnel_back").to_string(),
+                        body: server.to_string(),
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        self.record(events);
+        notes
+    }
+
+    fn record(&mut self, events: &ServerEvents) {
+        self.last_tmux_event = events.tmux_events.first().map_or(0, |e| e.id);
+        self.finished_runs = events
+            .task_runs
+            .iter()
+            .filter(|r| r.finished_at.is_some())
+            .map(|r| r.id)
+            .collect();
+        if events.tunnel.is_some() {
+            self.tunnel = events.tunnel;
+        }
+    }
+}
+
+/// Show `notification` on the desktop. Failures (no notification daemon,
+/// headless session) are ignored.
+pub fn show(notification: Notification) {
+    // The D-Bus call blocks: keep it off the event loop
+    tokio::task::spawn_blocking(move || {
+        notify_rust::Notification::new()
+            .appname("NomadFlow")
+            .summary(&notification.summary)
+            .body(&notification.body)
+            .show()
+            .ok();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: u64, kind: TmuxEventKind) -> TmuxEvent {
+        TmuxEvent {
+            id,
+            timestamp: 0,
+            kind,
+            window: "app:login".to_string(),
+        }
+    }
+
+    fn run(id: u64, exit_code: Option<i32>) -> TaskRun {
+        TaskRun {
+            id,
+            task: "test".to_string(),
+            repo_path: "/r/app".to_string(),
+            feature_name: "login".to_string(),
+            command: "cargo test".to_string(),
+            started_at: 0,
+            finished_at: exit_code.map(|_| 1),
+            exit_code,
+            output: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_first_poll_is_the_baseline() {
+        let mut watcher = Watcher::default();
+        let config = NotificationsConfig::default();
+        let events = ServerEvents {
+            tmux_events: vec![event(3, TmuxEventKind::PaneIdle)],
+            task_runs: vec![run(1, Some(1))],
+            tunnel: Some(TunnelState::Connected),
+        };
+        assert!(watcher.update("home", &events, &config).is_empty());
+        assert!(watcher.update("home", &events, &config).is_empty());
+    }
+
+    #[test]
+    fn test_new_events_are_notified_once() {
+        let mut watcher = Watcher::default();
+        let config = NotificationsConfig::default();
+        let mut events = ServerEvents {
+            tmux_events: vec![event(3, TmuxEventKind::Bell)],
+            task_runs: vec![run(2, None)],
+            tunnel: Some(TunnelState::Connected),
+        };
+        watcher.update("home", &events, &config);
+
+        events
+            .tmux_events
+            .insert(0, event(4, TmuxEventKind::PaneIdle));
+        events.task_runs = vec![run(2, Some(1))];
+        events.tunnel = Some(TunnelState::Reconnecting);
+        let notes = watcher.update("home", &events, &config);
+        assert_eq!(notes.len(), 3);
+        assert_eq!(
+            notes[0].summary,
+            tf("notify.pane_idle", &[("window", &"app:login")])
+        );
+        assert_eq!(
+            notes[1].summary,
+            tf("notify.task_failed", &[("task", &"test")])
+        );
+        assert_eq!(notes[2].summary, t("notify.tunnel_drop"));
+        assert!(watcher.update("home", &events, &config).is_empty());
+
+        events.tunnel = Some(TunnelState::Connected);
+        let notes = watcher.update("home", &events, &config);
+        assert_eq!(notes[0].summary, t("notify.tunnel_back"));
+    }
+
+    #[test]
+    fn test_disabled_kinds_and_server_change() {
+        let mut watcher = Watcher::default();
+        let config = NotificationsConfig {
+            pane_idle: false,
+            ..Default::default()
+        };
+        watcher.update("home", &ServerEvents::default(), &config);
+        let events = ServerEvents {
+            tmux_events: vec![event(1, TmuxEventKind::PaneIdle)],
+            ..Default::default()
+        };
+        assert!(watcher.update("home", &events, &config).is_empty());
+
+        // Another server starts from its own baseline
+        let events = ServerEvents {
+            task_runs: vec![run(1, Some(0))],
+            ..Default::default()
+        };
+        assert!(watcher.update("work", &events, &config).is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+//! Trust on first use for the TLS certificates of servers reached directly:
+//! the fingerprint of the certificate seen on the first HTTPS connection is
+//! saved with the server, and another certificate is then refused or only
+//! reported, as the server's `cert.onChange` says. Servers behind the relay
+//! are left to the usual CA checks, as its certificates are renewed often.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+
+use nomadflow_core::config::Settings;
+
+use crate::api_client::get_api_base_url;
+use crate::state::{OnCertChange, ServerConfig};
+
+/// Certificate a server presented on its last connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seen {
+    pub fingerprint: String,
+    /// It is not the pinned one, and the server lets the change through.
+    pub changed: bool,
+}
+
+static SEEN: LazyLock<Mutex<HashMap<String, Seen>>> = LazyLock::new(Default::default);
+
+/// SHA-256 fingerprint of a DER certificate, as `openssl x509 -fingerprint
+/// -sha256` prints it.
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Checks the certificate against the pinned fingerprint, then against the
+/// usual CA roots.
+#[derive(Debug)]
+struct PinVerifier {
+    server_id: String,
+    pinned: Option<String>,
+    on_change: OnCertChange,
+    roots: Arc<WebPkiServerVerifier>,
+}
+
+impl PinVerifier {
+    fn record(&self, fingerprint: String, changed: bool) {
+        if let Ok(mut seen) = SEEN.lock() {
+            seen.insert(
+                self.server_id.clone(),
+                Seen {
+                    fingerprint,
+                    changed,
+                },
+            );
+        }
+    }
+}
+
+impl ServerCertVerifier for PinVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let fingerprint = fingerprint(end_entity);
+        let changed = match &self.pinned {
+            // The certificate the user trusted, even a self-signed one
+            Some(pinned) if *pinned == fingerprint => {
+                self.record(fingerprint, false);
+                return Ok(ServerCertVerified::assertion());
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if changed && self.on_change == OnCertChange::Block {
+            return Err(rustls::Error::General(format!(
+                "the certificate of the server changed (now {fingerprint}), refusing to connect"
+            )));
+        }
+        self.roots.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        self.record(fingerprint, changed);
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.roots.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.roots.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.roots.supported_verify_schemes()
+    }
+}
+
+/// TLS settings checking the certificate of `server`. `None` when it is not
+/// reached over HTTPS or does not pin its certificate.
+pub fn tls_config(server: &ServerConfig) -> Result<Option<rustls::ClientConfig>, String> {
+    let is_https = url::Url::parse(&get_api_base_url(server)).is_ok_and(|u| u.scheme() == "https");
+    if !is_https || server.cert.on_change == OnCertChange::Off {
+        return Ok(None);
+    }
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let roots = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| e.to_string())?;
+    let verifier = PinVerifier {
+        server_id: server.id.clone(),
+        pinned: server.cert.fingerprint.clone(),
+        on_change: server.cert.on_change,
+        roots,
+    };
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    Ok(Some(config))
+}
+
+/// HTTP client for `server`, checking its pinned certificate.
+pub fn client(server: &ServerConfig) -> Result<reqwest::Client, String> {
+    match tls_config(server)? {
+        Some(tls) => reqwest::Client::builder()
+            .use_preconfigured_tls(tls)
+            .build()
+            .map_err(|e| e.to_string()),
+        None => Ok(reqwest::Client::new()),
+    }
+}
+
+/// Certificate presented by the server `server_id` since the last call.
+pub fn take_seen(server_id: &str) -> Option<Seen> {
+    SEEN.lock().ok()?.remove(server_id)
+}
+
+/// Whether `server` is reached through one of the relays of `settings`.
+pub fn is_relay(settings: &Settings, server: &ServerConfig) -> bool {
+    let Some(host) = url::Url::parse(&get_api_base_url(server))
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+    else {
+        return false;
+    };
+    settings.tunnel.relays().iter().any(|relay_host| {
+        let domain = relay_host.strip_prefix("relay.").unwrap_or(relay_host);
+        host == domain || host.ends_with(&format!(".{domain}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::CertTrust;
+
+    fn server(api_url: &str) -> ServerConfig {
+        ServerConfig {
+            id: "vps".to_string(),
+            name: "vps".to_string(),
+            api_url: Some(api_url.to_string()),
+            ttyd_url: None,
+            auth_token: None,
+            device_token: None,
+            cert: CertTrust::default(),
+            token_error: None,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_format() {
+        let fp = fingerprint(b"certificate");
+        assert_eq!(fp.len(), 32 * 3 - 1);
+        assert!(fp.split(':').all(|b| b.len() == 2 && b == b.to_uppercase()));
+    }
+
+    #[test]
+    fn test_pinning_only_over_https() {
+        assert!(tls_config(&server("http://vps:8080")).unwrap().is_none());
+        assert!(tls_config(&server("https://vps.example.com"))
+            .unwrap()
+            .is_some());
+
+        let mut off = server("https://vps.example.com");
+        off.cert.on_change = OnCertChange::Off;
+        assert!(tls_config(&off).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_is_relay() {
+        let settings = Settings::default();
+        let domain = settings
+            .tunnel
+            .relay_host
+            .trim_start_matches("relay.")
+            .to_string();
+        assert!(is_relay(
+            &settings,
+            &server(&format!("https://me.{domain}"))
+        ));
+        assert!(!is_relay(&settings, &server("https://vps.example.com")));
+        assert!(!is_relay(
+            &settings,
+            &server(&format!("https://not{domain}"))
+        ));
+
+        let mut settings = Settings::default();
+        settings.tunnel.relay_hosts = vec![
+            "relay.example.com".to_string(),
+            "relay.backup.example.com".to_string(),
+        ];
+        assert!(is_relay(&settings, &server("https://me.tunnel.backup.example.com")));
+        assert!(!is_relay(&settings, &server(&format!("https://me.{domain}"))));
+    }
+}
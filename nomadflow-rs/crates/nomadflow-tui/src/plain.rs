@@ -0,0 +1,540 @@
+//! Line-based replacement for the ratatui screens.
+//!
+//! Used with `--plain` (or when the terminal cannot draw a full-screen UI):
+//! choices are printed as a numbered list and read back from stdin, so the
+//! flows work with screen readers, dumb terminals and CI logs.
+
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::{eyre::eyre, Result};
+
+use nomadflow_core::branch::{check_branch_name, suggest_branch_names};
+use nomadflow_core::config::Settings;
+use nomadflow_core::i18n::{t, tf};
+
+use crate::api_client;
+use crate::app::App;
+use crate::glyphs::{self, Status};
+use crate::local;
+use crate::screens::feature_picker;
+use crate::state::{self, CliState, ServerConfig};
+use crate::PickItem;
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable plain mode for the rest of the process.
+pub fn set_plain(enabled: bool) {
+    PLAIN.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// Whether the terminal is unable to host the full-screen UI
+/// (`TERM=dumb`, or stdin/stdout not attached to a terminal).
+pub fn should_auto_enable() -> bool {
+    use std::io::IsTerminal;
+    std::env::var("TERM").is_ok_and(|term| term == "dumb")
+        || !io::stdin().is_terminal()
+        || !io::stdout().is_terminal()
+}
+
+/// Print `items` as a numbered list and read the chosen number.
+/// An empty line, `q` or end of input cancels.
+pub fn pick_with(
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+    title: &str,
+    items: &[PickItem],
+) -> io::Result<Option<usize>> {
+    if items.is_empty() {
+        return Ok(None);
+    }
+
+    writeln!(out, "{title}")?;
+    for (i, item) in items.iter().enumerate() {
+        if item.detail.is_empty() {
+            writeln!(out, "  {}. {}", i + 1, item.label)?;
+        } else {
+            writeln!(out, "  {}. {} — {}", i + 1, item.label, item.detail)?;
+        }
+    }
+
+    loop {
+        let Some(line) = prompt_with(input, out, t("plain.choice"))? else {
+            return Ok(None);
+        };
+        if line.is_empty() || line.eq_ignore_ascii_case("q") {
+            return Ok(None);
+        }
+        match line.parse::<usize>() {
+            Ok(n) if (1..=items.len()).contains(&n) => return Ok(Some(n - 1)),
+            _ => writeln!(out, "{}", tf("plain.invalid", &[("max", &items.len())]))?,
+        }
+    }
+}
+
+/// Ask a yes/no question. Only `yes`, the localized yes, or a prefix of them accepts.
+pub fn confirm_with(
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+    message: &str,
+) -> io::Result<bool> {
+    let prompt = format!("{message} {}", t("plain.yes_no"));
+    let answer = prompt_with(input, out, &prompt)?.unwrap_or_default();
+    let answer = answer.to_lowercase();
+    Ok(!answer.is_empty() && ("yes".starts_with(&answer) || t("common.yes").starts_with(&answer)))
+}
+
+/// Print `label` and read one trimmed line; `None` at end of input.
+pub fn prompt_with(
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+    label: &str,
+) -> io::Result<Option<String>> {
+    write!(out, "{label}")?;
+    out.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        writeln!(out)?;
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+pub fn pick_from_list(title: &str, items: &[PickItem]) -> Result<Option<usize>> {
+    Ok(pick_with(
+        &mut io::stdin().lock(),
+        &mut io::stderr(),
+        title,
+        items,
+    )?)
+}
+
+pub fn confirm(message: &str) -> Result<bool> {
+    Ok(confirm_with(
+        &mut io::stdin().lock(),
+        &mut io::stderr(),
+        message,
+    )?)
+}
+
+fn prompt(label: &str) -> Result<Option<String>> {
+    Ok(prompt_with(
+        &mut io::stdin().lock(),
+        &mut io::stderr(),
+        label,
+    )?)
+}
+
+/// Setup questions carry the TUI's "(y/n)" key hint; [`confirm`] adds its own.
+fn question(key: &'static str) -> &'static str {
+    t(key).trim_end_matches("(y/n)").trim_end()
+}
+
+/// Answers given up front to `nomadflow init`; missing ones are prompted for.
+#[derive(Debug, Clone, Default)]
+pub struct InitOptions {
+    pub secret: Option<String>,
+    pub public: Option<bool>,
+    pub subdomain: Option<String>,
+    /// Never prompt: generate a secret, keep public mode off unless asked for,
+    /// and save without confirmation.
+    pub yes: bool,
+}
+
+/// Same rules as the relay: 3-32 alphanumerics or hyphens, no leading/trailing hyphen.
+pub fn is_valid_subdomain(subdomain: &str) -> bool {
+    (3..=32).contains(&subdomain.len())
+        && subdomain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !subdomain.starts_with('-')
+        && !subdomain.ends_with('-')
+}
+
+/// Plain version of the first-run setup wizard.
+pub fn run_setup(settings: Settings) -> Result<Option<Settings>> {
+    run_init(settings, InitOptions::default())
+}
+
+/// Setup wizard driven by `opts`, prompting on stdin for whatever is missing.
+/// Returns the saved settings, or None if cancelled.
+pub fn run_init(mut settings: Settings, opts: InitOptions) -> Result<Option<Settings>> {
+    if let Some(subdomain) = opts.subdomain.as_deref() {
+        if !is_valid_subdomain(subdomain) {
+            return Err(eyre!(tf(
+                "init.invalid_subdomain",
+                &[("subdomain", &subdomain)]
+            )));
+        }
+    }
+    if !opts.yes {
+        eprintln!("{}", t("setup.welcome"));
+    }
+
+    let secret = match opts.secret {
+        Some(secret) if !secret.is_empty() => secret,
+        Some(_) => return Err(eyre!(t("init.empty_secret"))),
+        None if opts.yes => App::generate_password(),
+        None => {
+            let options = [
+                PickItem {
+                    label: t("setup.generate").to_string(),
+                    detail: String::new(),
+                },
+                PickItem {
+                    label: t("setup.custom").to_string(),
+                    detail: String::new(),
+                },
+            ];
+            match pick_from_list(t("setup.password_prompt"), &options)? {
+                Some(0) => App::generate_password(),
+                Some(_) => loop {
+                    match prompt(t("setup.password_label"))? {
+                        Some(p) if !p.is_empty() => break p,
+                        Some(_) => continue,
+                        None => return Ok(None),
+                    }
+                },
+                None => return Ok(None),
+            }
+        }
+    };
+
+    // A fixed subdomain only makes sense in public mode, so it implies it
+    let public = match opts.public {
+        Some(public) => public || opts.subdomain.is_some(),
+        None if opts.subdomain.is_some() || opts.yes => opts.subdomain.is_some(),
+        None => {
+            eprintln!("{}", t("setup.public_hint"));
+            confirm(question("setup.public_title"))?
+        }
+    };
+
+    let subdomain = match opts.subdomain {
+        Some(subdomain) => subdomain,
+        None if !public => String::new(),
+        None => {
+            let subdomain = App::generate_subdomain();
+            if opts.yes {
+                subdomain
+            } else {
+                eprintln!("{}{subdomain}", t("setup.subdomain_label"));
+                if confirm(question("setup.subdomain_title"))? {
+                    subdomain
+                } else {
+                    String::new()
+                }
+            }
+        }
+    };
+
+    eprintln!("{}", t("setup.summary"));
+    eprintln!("{}{secret}", t("setup.summary_password"));
+    let public_label = if public {
+        t("common.yes")
+    } else {
+        t("common.no")
+    };
+    eprintln!("{}{public_label}", t("setup.summary_public"));
+    if public {
+        let shown = if subdomain.is_empty() {
+            t("setup.random")
+        } else {
+            subdomain.as_str()
+        };
+        eprintln!("{}{shown}", t("setup.summary_subdomain"));
+    }
+    eprintln!(
+        "{}{}",
+        t("setup.summary_config"),
+        settings.config_file().display()
+    );
+    if !opts.yes && !confirm(question("setup.save"))? {
+        return Ok(None);
+    }
+
+    settings.auth.secret = secret;
+    settings.tunnel.subdomain = subdomain;
+    if let Err(e) = settings.save() {
+        return Err(eyre!(tf("setup.save_failed", &[("err", &e)])));
+    }
+    Ok(Some(settings))
+}
+
+/// Plain version of the server → repo → feature wizard.
+/// Returns the tmux session name to attach to (if any).
+pub async fn run(settings: Settings) -> Result<Option<String>> {
+    let servers = state::load_servers(&settings);
+    let cli_state = state::load_state(&settings);
+
+    let server = if servers.len() == 1 {
+        servers[0].clone()
+    } else {
+        let items: Vec<PickItem> = servers
+            .iter()
+            .map(|s| PickItem {
+                label: s.name.clone(),
+                detail: s
+                    .api_url
+                    .clone()
+                    .unwrap_or_else(|| t("servers.no_url").to_string()),
+            })
+            .collect();
+        match pick_from_list(t("servers.title"), &items)? {
+            Some(idx) => servers[idx].clone(),
+            None => return Ok(None),
+        }
+    };
+
+    wait_for_server(&server).await;
+
+    eprintln!("{}", t("repos.loading"));
+    // Without a reachable localhost API, work on the local repos directly
+    let mut offline = false;
+    let repos = match api_client::list_repos(&server).await {
+        Err(_) if local::should_fallback(&server).await => {
+            offline = true;
+            eprintln!("{}", t("offline.notice"));
+            local::list_repos(&settings).await
+        }
+        result => result,
+    };
+    let repos = match repos {
+        Ok(repos) => repos,
+        Err(e) => {
+            eprintln!("{}", tf("common.error", &[("err", &e)]));
+            return Ok(None);
+        }
+    };
+    if repos.is_empty() {
+        eprintln!("{}", t("repos.empty"));
+        return Ok(None);
+    }
+    let items: Vec<PickItem> = repos
+        .iter()
+        .map(|r| {
+            let mut detail = r.branch.clone();
+            if cli_state.last_repo.as_deref() == Some(r.path.as_str()) {
+                detail.push_str(t("repos.last_used"));
+            }
+            PickItem {
+                label: r.name.clone(),
+                detail,
+            }
+        })
+        .collect();
+    let repo = match pick_from_list(&tf("repos.title", &[("server", &server.name)]), &items)? {
+        Some(idx) => repos[idx].clone(),
+        None => return Ok(None),
+    };
+
+    eprintln!("{}", t("features.loading"));
+    let features = if offline {
+        local::list_features(&settings, &repo.path).await
+    } else {
+        api_client::list_features(&server, &repo.path).await
+    };
+    let features = match features {
+        Ok(features) => features,
+        Err(e) => {
+            eprintln!("{}", tf("common.error", &[("err", &e)]));
+            return Ok(None);
+        }
+    };
+    let mut items: Vec<PickItem> = features
+        .iter()
+        .map(|f| {
+            let mut detail = if f.is_main {
+                t("features.source").to_string()
+            } else {
+                f.branch.clone()
+            };
+            detail.push_str(&feature_picker::git_state(f));
+            if f.has_unread {
+                let unread = glyphs::mark(Status::Unread, t("features.new_output"));
+                detail.push_str(&format!("  {unread}"));
+            }
+            PickItem {
+                label: f.name.clone(),
+                detail,
+            }
+        })
+        .collect();
+    items.push(PickItem {
+        label: t("features.create").to_string(),
+        detail: String::new(),
+    });
+    let title = tf("features.title", &[("repo", &repo.name)]);
+    let feature_name = match pick_from_list(&title, &items)? {
+        Some(idx) if idx < features.len() => features[idx].name.clone(),
+        Some(_) => {
+            eprintln!("{}", tf("create.title", &[("repo", &repo.name)]));
+            let name = loop {
+                let name = match prompt(t("create.label"))? {
+                    Some(name) if !name.is_empty() => name,
+                    _ => return Ok(None),
+                };
+                match check_branch_name(&name) {
+                    Ok(()) => break name,
+                    Err(problem) => {
+                        eprintln!("{}", tf("common.error", &[("err", &problem)]));
+                        let suggestions = suggest_branch_names(&name);
+                        if !suggestions.is_empty() {
+                            let names = suggestions.join(", ");
+                            eprintln!("{}", tf("create.try", &[("names", &names)]));
+                        }
+                    }
+                }
+            };
+            let created = if offline {
+                local::create_feature(&settings, &repo.path, &name).await
+            } else {
+                api_client::create_feature(&server, &repo.path, &name, None, false).await
+            };
+            if let Err(e) = created {
+                eprintln!("{}", tf("common.error", &[("err", &e)]));
+                return Ok(None);
+            }
+            name
+        }
+        None => return Ok(None),
+    };
+
+    eprintln!("{}", t("attaching.preparing"));
+    let switched = if offline {
+        local::switch_feature(&settings, &repo.path, &feature_name).await
+    } else {
+        api_client::switch_feature(&server, &repo.path, &feature_name).await
+    };
+    if let Err(e) = switched {
+        eprintln!("{}", tf("common.error", &[("err", &e)]));
+        return Ok(None);
+    }
+
+    let new_state = CliState {
+        last_server: Some(server.id.clone()),
+        last_repo: Some(repo.path.clone()),
+        last_feature: Some(feature_name),
+        last_attached: Some(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        ),
+        ..state::load_state(&settings)
+    };
+    if let Err(e) = state::save_state(&settings, &new_state) {
+        let warning = tf("state.save_failed", &[("err", &e)]);
+        eprintln!("{}", glyphs::mark(Status::Warning, &warning));
+    }
+
+    Ok(Some(settings.tmux.session.clone()))
+}
+
+/// Give the embedded server a few seconds to come up before the first request.
+async fn wait_for_server(server: &ServerConfig) {
+    for _ in 0..20 {
+        if api_client::check_health(server).await.is_some() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<PickItem> {
+        ["main", "login"]
+            .iter()
+            .map(|name| PickItem {
+                label: name.to_string(),
+                detail: String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pick_numbered_choice() {
+        let mut out = Vec::new();
+        let mut input = io::Cursor::new("0\nabc\n2\n");
+        let choice = pick_with(&mut input, &mut out, "Pick:", &items()).unwrap();
+        assert_eq!(choice, Some(1));
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("  1. main\n  2. login\n"));
+        // Two invalid answers before the valid one
+        assert_eq!(out.matches("1 and 2").count(), 2);
+    }
+
+    #[test]
+    fn test_pick_cancel() {
+        for input in ["\n", "q\n", ""] {
+            let mut input = io::Cursor::new(input);
+            let choice = pick_with(&mut input, &mut Vec::new(), "Pick:", &items()).unwrap();
+            assert_eq!(choice, None);
+        }
+    }
+
+    #[test]
+    fn test_is_valid_subdomain() {
+        assert!(is_valid_subdomain("nf-abc123"));
+        assert!(!is_valid_subdomain("ab"));
+        assert!(!is_valid_subdomain("-abc"));
+        assert!(!is_valid_subdomain("abc-"));
+        assert!(!is_valid_subdomain("a.bc"));
+        assert!(!is_valid_subdomain(&"a".repeat(33)));
+    }
+
+    #[test]
+    fn test_run_init_non_interactive() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let settings = Settings {
+            paths: nomadflow_core::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        let opts = InitOptions {
+            secret: Some("s3cret".to_string()),
+            subdomain: Some("my-box".to_string()),
+            yes: true,
+            ..Default::default()
+        };
+        let saved = run_init(settings.clone(), opts).unwrap().unwrap();
+        assert_eq!(saved.auth.secret, "s3cret");
+        assert_eq!(saved.tunnel.subdomain, "my-box");
+
+        let loaded = Settings::load(Some(&settings.config_file())).unwrap();
+        assert_eq!(loaded.auth.secret, "s3cret");
+
+        // --yes alone generates a secret and leaves public mode off
+        let saved = run_init(
+            settings,
+            InitOptions {
+                yes: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(saved.auth.secret.len(), 16);
+        assert!(saved.tunnel.subdomain.is_empty());
+    }
+
+    #[test]
+    fn test_confirm() {
+        let answer = |s: &str| confirm_with(&mut io::Cursor::new(s), &mut Vec::new(), "Ok?");
+        assert!(answer("y\n").unwrap());
+        assert!(answer("YES\n").unwrap());
+        assert!(!answer("n\n").unwrap());
+        assert!(!answer("\n").unwrap());
+        assert!(!answer("").unwrap());
+    }
+}
@@ -0,0 +1,427 @@
+//! Commands that go straight to a feature without the wizard's pickers:
+//! `nomadflow new` from inside a repo, `nomadflow open <query>` and
+//! `nomadflow cd <query>` with its `shell-init` wrapper.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use color_eyre::{eyre::eyre, Result};
+
+use nomadflow_core::config::Settings;
+use nomadflow_core::i18n::{t, tf};
+use nomadflow_core::models::{Feature, Repository};
+use nomadflow_core::shell::run;
+
+use crate::api_client;
+use crate::glyphs::{self, Status};
+use crate::local;
+use crate::state::{self, CliState, ServerConfig};
+use crate::{confirm, pick_from_list, PickItem};
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Main working tree of the git repository containing `dir`, also when `dir`
+/// is inside one of its linked worktrees.
+async fn main_worktree(dir: &Path) -> Option<PathBuf> {
+    let result = run(
+        "git rev-parse --path-format=absolute --git-common-dir",
+        Some(&dir.to_string_lossy()),
+    )
+    .await;
+    if !result.success() {
+        return None;
+    }
+    let common_dir = PathBuf::from(result.stdout.trim());
+    match common_dir.file_name() {
+        Some(name) if name == ".git" => common_dir.parent().map(Path::to_path_buf),
+        // Bare repository
+        _ => Some(common_dir),
+    }
+}
+
+/// The repo of `repos` that `dir` belongs to.
+pub async fn repo_for_dir<'a>(repos: &'a [Repository], dir: &Path) -> Option<&'a Repository> {
+    let root = canonical(&main_worktree(dir).await?);
+    repos.iter().find(|r| canonical(Path::new(&r.path)) == root)
+}
+
+/// The localhost API, or the same calls made in-process when it is down.
+struct Local<'a> {
+    settings: &'a Settings,
+    server: ServerConfig,
+    offline: bool,
+}
+
+impl<'a> Local<'a> {
+    async fn connect(settings: &'a Settings) -> Result<Self> {
+        let server = state::load_servers(settings)
+            .into_iter()
+            .find(|s| s.id == "localhost")
+            .ok_or_else(|| eyre!("No localhost server"))?;
+        let offline = local::should_fallback(&server).await;
+        if offline {
+            eprintln!("{}", t("offline.notice"));
+        }
+        Ok(Self {
+            settings,
+            server,
+            offline,
+        })
+    }
+
+    async fn list_repos(&self) -> Result<Vec<Repository>> {
+        let repos = if self.offline {
+            local::list_repos(self.settings).await
+        } else {
+            api_client::list_repos(&self.server).await
+        };
+        repos.map_err(|e| eyre!(e))
+    }
+
+    async fn list_features(&self, repo: &Repository) -> Result<Vec<Feature>> {
+        let features = if self.offline {
+            local::list_features(self.settings, &repo.path).await
+        } else {
+            api_client::list_features(&self.server, &repo.path).await
+        };
+        features.map_err(|e| eyre!(e))
+    }
+
+    /// Every feature of every repo.
+    async fn all_features(&self, repos: &[Repository]) -> Result<Vec<(Repository, Feature)>> {
+        let mut candidates = Vec::new();
+        for repo in repos {
+            for feature in self.list_features(repo).await? {
+                candidates.push((repo.clone(), feature));
+            }
+        }
+        Ok(candidates)
+    }
+
+    async fn create_feature(&self, repo: &Repository, name: &str) -> Result<()> {
+        eprintln!(
+            "{}",
+            tf("new.creating", &[("feature", &name), ("repo", &repo.name)])
+        );
+        let created = if self.offline {
+            local::create_feature(self.settings, &repo.path, name).await
+        } else {
+            api_client::create_feature(&self.server, &repo.path, name, None, false).await
+        };
+        created.map(|_| ()).map_err(|e| eyre!(e))
+    }
+
+    /// Select the feature's window and remember it as the last one attached,
+    /// like the wizard does. Returns the tmux session to attach to.
+    async fn switch_feature(&self, repo: &Repository, name: &str) -> Result<String> {
+        let switched = if self.offline {
+            local::switch_feature(self.settings, &repo.path, name).await
+        } else {
+            api_client::switch_feature(&self.server, &repo.path, name).await
+        };
+        switched.map_err(|e| eyre!(e))?;
+
+        let cli_state = CliState {
+            last_server: Some(self.server.id.clone()),
+            last_repo: Some(repo.path.clone()),
+            last_feature: Some(name.to_string()),
+            last_attached: Some(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64,
+            ),
+            ..state::load_state(self.settings)
+        };
+        if let Err(e) = state::save_state(self.settings, &cli_state) {
+            let warning = tf("state.save_failed", &[("err", &e)]);
+            eprintln!("{}", glyphs::mark(Status::Warning, &warning));
+        }
+        Ok(self.settings.tmux.session.clone())
+    }
+}
+
+/// Create feature `name` in the repo containing `dir` and select its window.
+/// Returns the tmux session to attach to.
+pub async fn new_feature(settings: &Settings, name: &str, dir: &Path) -> Result<String> {
+    let local = Local::connect(settings).await?;
+    let repos = local.list_repos().await?;
+    let repo = repo_for_dir(&repos, dir)
+        .await
+        .ok_or_else(|| eyre!(t("new.not_in_repo")))?;
+    local.create_feature(repo, name).await?;
+    local.switch_feature(repo, name).await
+}
+
+/// How well `query` matches `candidate`: its characters must appear in order
+/// (ignoring case). Consecutive characters and characters starting a word
+/// score higher; `None` when it does not match.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<u32> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.to_lowercase().chars() {
+        let found = pos + candidate[pos..].iter().position(|&c| c == q)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || matches!(candidate[found - 1], ':' | '-' | '_' | '/' | '.') {
+            score += 3;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+    // Among equal matches, prefer the shortest name
+    Some(score * 100 - candidate.len().min(99) as u32)
+}
+
+/// `repo:feature` candidates matching `query`, best first. An exact feature
+/// name (or `repo:feature`) match comes alone.
+fn rank<'a>(
+    query: &str,
+    candidates: &'a [(Repository, Feature)],
+) -> Vec<&'a (Repository, Feature)> {
+    let label = |(repo, feature): &(Repository, Feature)| format!("{}:{}", repo.name, feature.name);
+    let exact: Vec<_> = candidates
+        .iter()
+        .filter(|c| c.1.name.eq_ignore_ascii_case(query) || label(c).eq_ignore_ascii_case(query))
+        .collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+    let mut scored: Vec<_> = candidates
+        .iter()
+        .filter_map(|c| fuzzy_score(query, &label(c)).map(|score| (score, c)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, c)| c).collect()
+}
+
+/// Repo to create a missing feature in: the one named in `repo:feature`,
+/// else the one containing `dir`, else the only one, else picked.
+async fn repo_for_new<'a>(
+    repos: &'a [Repository],
+    query: &str,
+    dir: &Path,
+) -> Result<Option<&'a Repository>> {
+    if let Some((name, _)) = query.split_once(':') {
+        return repos
+            .iter()
+            .find(|r| r.name == name)
+            .map(Some)
+            .ok_or_else(|| eyre!(tf("remote.unknown_repo", &[("repo", &name)])));
+    }
+    if let Some(repo) = repo_for_dir(repos, dir).await {
+        return Ok(Some(repo));
+    }
+    if let [repo] = repos {
+        return Ok(Some(repo));
+    }
+    let items: Vec<PickItem> = repos
+        .iter()
+        .map(|r| PickItem {
+            label: r.name.clone(),
+            detail: r.branch.clone(),
+        })
+        .collect();
+    let title = tf("repos.title", &[("server", &"localhost")]);
+    Ok(pick_from_list(&title, &items)?.map(|idx| &repos[idx]))
+}
+
+/// Find the feature best matching `query` across all repos and select its
+/// window, offering to create it when nothing matches. Returns the tmux
+/// session to attach to, `None` when cancelled.
+pub async fn open_feature(settings: &Settings, query: &str, dir: &Path) -> Result<Option<String>> {
+    let local = Local::connect(settings).await?;
+    let repos = local.list_repos().await?;
+    let candidates = local.all_features(&repos).await?;
+
+    let matches = rank(query, &candidates);
+    let chosen = match matches.as_slice() {
+        [] => None,
+        [only] => Some(*only),
+        _ => {
+            let items: Vec<PickItem> = matches
+                .iter()
+                .map(|(repo, feature)| PickItem {
+                    label: format!("{}:{}", repo.name, feature.name),
+                    detail: feature.branch.clone(),
+                })
+                .collect();
+            match pick_from_list(&tf("open.title", &[("query", &query)]), &items)? {
+                Some(idx) => Some(matches[idx]),
+                None => return Ok(None),
+            }
+        }
+    };
+    if let Some((repo, feature)) = chosen {
+        return local.switch_feature(repo, &feature.name).await.map(Some);
+    }
+
+    let name = query.split_once(':').map_or(query, |(_, name)| name);
+    if !confirm(&tf("open.create", &[("feature", &name)]))? {
+        return Ok(None);
+    }
+    let Some(repo) = repo_for_new(&repos, query, dir).await? else {
+        return Ok(None);
+    };
+    local.create_feature(repo, name).await?;
+    local.switch_feature(repo, name).await.map(Some)
+}
+
+/// Worktree path of the feature best matching `query`, without prompting:
+/// the output of `nomadflow cd --path-only` is captured by the shell.
+pub async fn feature_path(settings: &Settings, query: &str) -> Result<String> {
+    let local = Local::connect(settings).await?;
+    let repos = local.list_repos().await?;
+    let candidates = local.all_features(&repos).await?;
+    rank(query, &candidates)
+        .first()
+        .map(|(_, feature)| feature.worktree_path.clone())
+        .ok_or_else(|| eyre!(tf("cd.no_match", &[("query", &query)])))
+}
+
+const POSIX_INIT: &str = r#"nomadflow() {
+    if [ "$1" = "cd" ]; then
+        shift
+        local dir
+        dir="$(command nomadflow cd --path-only "$@")" || return
+        builtin cd -- "$dir"
+    else
+        command nomadflow "$@"
+    fi
+}
+"#;
+
+const FISH_INIT: &str = r#"function nomadflow
+    if test (count $argv) -gt 0; and test "$argv[1]" = cd
+        set -l dir (command nomadflow cd --path-only $argv[2..-1]); or return
+        builtin cd -- $dir
+    else
+        command nomadflow $argv
+    end
+end
+"#;
+
+/// Shells supported by `nomadflow shell-init`.
+pub const SHELLS: [&str; 3] = ["bash", "zsh", "fish"];
+
+/// Shell function wrapping `nomadflow` so that `nomadflow cd <feature>`
+/// changes the caller's directory. `None` for an unsupported shell.
+pub fn shell_init(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" | "zsh" => Some(POSIX_INIT),
+        "fish" => Some(FISH_INIT),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(path: &Path) -> Repository {
+        Repository {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
+            branch: "main".to_string(),
+        }
+    }
+
+    fn feature(name: &str) -> Feature {
+        Feature {
+            name: name.to_string(),
+            worktree_path: format!("/worktrees/{name}"),
+            branch: format!("feature/{name}"),
+            is_active: false,
+            is_main: false,
+            has_unread: false,
+            suspended: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("lgn", "app:login-form").is_some());
+        assert!(fuzzy_score("nigol", "app:login-form").is_none());
+        // Consecutive and word-start matches win
+        assert!(fuzzy_score("form", "app:login-form") > fuzzy_score("form", "app:f-o-r-m"));
+        assert!(fuzzy_score("lf", "app:login-form") > fuzzy_score("lf", "app:self"));
+        assert_eq!(
+            fuzzy_score("LOGIN", "app:login"),
+            fuzzy_score("login", "app:LOGIN")
+        );
+    }
+
+    #[test]
+    fn test_rank() {
+        let app = repo(Path::new("/repos/app"));
+        let api = repo(Path::new("/repos/api"));
+        let candidates = vec![
+            (app.clone(), feature("login-form")),
+            (app.clone(), feature("logout")),
+            (api.clone(), feature("login")),
+        ];
+        let labels = |query| {
+            rank(query, &candidates)
+                .iter()
+                .map(|(r, f)| format!("{}:{}", r.name, f.name))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels("login"), ["api:login"]);
+        assert_eq!(labels("app:logout"), ["app:logout"]);
+        assert_eq!(labels("lofo"), ["app:login-form"]);
+        assert_eq!(labels("log")[0], "api:login");
+        assert!(labels("zzz").is_empty());
+    }
+
+    #[test]
+    fn test_shell_init_scripts() {
+        for shell in SHELLS {
+            let script = shell_init(shell).unwrap();
+            assert!(script.contains("command nomadflow cd --path-only"));
+        }
+        assert!(shell_init("tcsh").is_none());
+
+        if std::process::Command::new("bash").arg("--version").output().is_ok() {
+            let status = std::process::Command::new("bash")
+                .args(["-n", "-c", shell_init("bash").unwrap()])
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repo_for_dir_from_subdir_and_worktree() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let app = tmp.path().join("app");
+        std::fs::create_dir_all(app.join("src")).unwrap();
+        let app_str = app.to_string_lossy().to_string();
+        run("git init", Some(&app_str)).await;
+        run("git commit --allow-empty -m init", Some(&app_str)).await;
+        let worktree = tmp.path().join("wt");
+        run(
+            &format!("git worktree add -b feat \"{}\"", worktree.display()),
+            Some(&app_str),
+        )
+        .await;
+
+        // Linked repos are symlinks in the repos dir
+        let link = tmp.path().join("link");
+        std::os::unix::fs::symlink(&app, &link).unwrap();
+        let repos = vec![repo(&tmp.path().join("other")), repo(&link)];
+
+        for dir in [app.clone(), app.join("src"), worktree] {
+            let found = repo_for_dir(&repos, &dir).await.unwrap();
+            assert_eq!(found.name, "link");
+        }
+        assert!(repo_for_dir(&repos, tmp.path()).await.is_none());
+    }
+}
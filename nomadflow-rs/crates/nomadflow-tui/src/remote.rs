@@ -0,0 +1,297 @@
+//! `nomadflow attach --remote`: join a feature's terminal on another machine
+//! through its API and WebSocket terminal proxy, with the local terminal in
+//! raw mode.
+
+use std::io::Write;
+
+use color_eyre::{eyre::eyre, Result};
+use crossterm::terminal;
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::AsyncReadExt;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+
+use nomadflow_core::i18n::{t, tf};
+
+use crate::api_client::{self, get_api_base_url};
+use crate::pinning;
+use crate::state::ServerConfig;
+use crate::{pick_from_list, PickItem};
+
+/// Ctrl-] ends the session, as in telnet.
+pub const DETACH_KEY: u8 = 0x1d;
+
+// ttyd protocol: each frame starts with a command byte
+const INPUT: u8 = b'0';
+const RESIZE_TERMINAL: u8 = b'1';
+const OUTPUT: u8 = b'0';
+
+/// Find a configured server by id or name.
+pub fn find_server<'a>(servers: &'a [ServerConfig], name: &str) -> Option<&'a ServerConfig> {
+    servers
+        .iter()
+        .find(|s| s.id == name)
+        .or_else(|| servers.iter().find(|s| s.name.eq_ignore_ascii_case(name)))
+}
+
+/// URL of the server's terminal WebSocket proxy, with its credentials.
+pub fn ws_url(server: &ServerConfig) -> Result<String, String> {
+    if let Some(ref e) = server.token_error {
+        return Err(e.clone());
+    }
+    let base = get_api_base_url(server);
+    let base = base.trim_end_matches("/api");
+    let mut url = url::Url::parse(&format!("{base}/terminal/ws")).map_err(|e| e.to_string())?;
+    let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+    url.set_scheme(scheme)
+        .map_err(|_| format!("unsupported URL: {base}"))?;
+    {
+        let mut query = url.query_pairs_mut();
+        if let Some(ref token) = server.auth_token {
+            query.append_pair("token", token);
+        }
+        if let Some(ref device) = server.device_token {
+            query.append_pair("device", device);
+        }
+    }
+    if url.query() == Some("") {
+        url.set_query(None);
+    }
+    Ok(url.to_string())
+}
+
+/// First message of a ttyd session: credentials and initial size.
+fn handshake(columns: u16, rows: u16) -> String {
+    serde_json::json!({ "AuthToken": "", "columns": columns, "rows": rows }).to_string()
+}
+
+fn input_frame(data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(data.len() + 1);
+    frame.push(INPUT);
+    frame.extend_from_slice(data);
+    frame
+}
+
+fn resize_frame(columns: u16, rows: u16) -> Vec<u8> {
+    let mut frame = vec![RESIZE_TERMINAL];
+    frame.extend(
+        serde_json::json!({ "columns": columns, "rows": rows })
+            .to_string()
+            .into_bytes(),
+    );
+    frame
+}
+
+/// Terminal output carried by a ttyd frame, if it is an output frame.
+fn output(frame: &[u8]) -> Option<&[u8]> {
+    match frame.split_first() {
+        Some((&OUTPUT, data)) => Some(data),
+        _ => None,
+    }
+}
+
+/// Restores the local terminal when the session ends, even on error.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> Result<Self, String> {
+        terminal::enable_raw_mode().map_err(|e| e.to_string())?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        terminal::disable_raw_mode().ok();
+    }
+}
+
+/// Open the server's terminal and pass the local terminal through until the
+/// server closes it or the user presses [`DETACH_KEY`].
+pub async fn run_terminal(server: &ServerConfig) -> Result<(), String> {
+    let mut request = ws_url(server)?
+        .into_client_request()
+        .map_err(|e| e.to_string())?;
+    request
+        .headers_mut()
+        .insert("Sec-WebSocket-Protocol", "tty".parse().unwrap());
+    let connector = pinning::tls_config(server)?
+        .map(|tls| tokio_tungstenite::Connector::Rustls(std::sync::Arc::new(tls)));
+    let (ws, _) = tokio_tungstenite::connect_async_tls_with_config(request, None, false, connector)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (mut ws_tx, mut ws_rx) = ws.split();
+
+    let (columns, rows) = terminal::size().unwrap_or((80, 24));
+    ws_tx
+        .send(Message::Text(handshake(columns, rows).into()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let _raw = RawMode::enable()?;
+    let mut stdin = tokio::io::stdin();
+    let mut stdout = std::io::stdout();
+    let mut resized = signal(SignalKind::window_change()).map_err(|e| e.to_string())?;
+    let mut buf = [0u8; 4096];
+
+    loop {
+        tokio::select! {
+            read = stdin.read(&mut buf) => {
+                let n = match read {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                let data = &buf[..n];
+                let detach = data.iter().position(|&b| b == DETACH_KEY);
+                let data = detach.map_or(data, |i| &data[..i]);
+                if !data.is_empty()
+                    && ws_tx.send(Message::Binary(input_frame(data).into())).await.is_err()
+                {
+                    break;
+                }
+                if detach.is_some() {
+                    break;
+                }
+            }
+            msg = ws_rx.next() => {
+                let frame = match msg {
+                    Some(Ok(Message::Binary(data))) => data.to_vec(),
+                    Some(Ok(Message::Text(text))) => text.as_bytes().to_vec(),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                if let Some(data) = output(&frame) {
+                    stdout.write_all(data).ok();
+                    stdout.flush().ok();
+                }
+            }
+            _ = resized.recv() => {
+                if let Ok((columns, rows)) = terminal::size() {
+                    ws_tx.send(Message::Binary(resize_frame(columns, rows).into())).await.ok();
+                }
+            }
+        }
+    }
+
+    ws_tx.send(Message::Close(None)).await.ok();
+    Ok(())
+}
+
+/// Switch the server's tmux session to `target` (`repo:feature`, picked from
+/// the server's lists when omitted), then open its terminal.
+pub async fn attach(server: &ServerConfig, target: Option<&str>) -> Result<()> {
+    let repos = api_client::list_repos(server).await.map_err(|e| eyre!(e))?;
+    let (repo_name, feature_name) = match target {
+        Some(target) => {
+            let (repo, feature) = target
+                .split_once(':')
+                .ok_or_else(|| eyre!(tf("remote.bad_target", &[("target", &target)])))?;
+            (repo.to_string(), Some(feature.to_string()))
+        }
+        None => {
+            let items: Vec<PickItem> = repos
+                .iter()
+                .map(|r| PickItem {
+                    label: r.name.clone(),
+                    detail: r.branch.clone(),
+                })
+                .collect();
+            let title = tf("repos.title", &[("server", &server.name)]);
+            match pick_from_list(&title, &items)? {
+                Some(idx) => (repos[idx].name.clone(), None),
+                None => return Ok(()),
+            }
+        }
+    };
+    let repo = repos
+        .iter()
+        .find(|r| r.name == repo_name)
+        .ok_or_else(|| eyre!(tf("remote.unknown_repo", &[("repo", &repo_name)])))?;
+
+    let feature_name = match feature_name {
+        Some(name) => name,
+        None => {
+            let features = api_client::list_features(server, &repo.path)
+                .await
+                .map_err(|e| eyre!(e))?;
+            let items: Vec<PickItem> = features
+                .iter()
+                .map(|f| PickItem {
+                    label: f.name.clone(),
+                    detail: f.branch.clone(),
+                })
+                .collect();
+            let title = tf("features.title", &[("repo", &repo.name)]);
+            match pick_from_list(&title, &items)? {
+                Some(idx) => features[idx].name.clone(),
+                None => return Ok(()),
+            }
+        }
+    };
+
+    api_client::switch_feature(server, &repo.path, &feature_name)
+        .await
+        .map_err(|e| eyre!(e))?;
+
+    eprintln!("{}", tf("remote.connected", &[("server", &server.name)]));
+    run_terminal(server).await.map_err(|e| eyre!(e))?;
+    eprintln!("\r\n{}", t("remote.detached"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(api_url: &str) -> ServerConfig {
+        ServerConfig {
+            id: "desk".to_string(),
+            name: "Desk".to_string(),
+            api_url: Some(api_url.to_string()),
+            ttyd_url: None,
+            auth_token: Some("s3cret&x".to_string()),
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
+        }
+    }
+
+    #[test]
+    fn test_ws_url_carries_credentials() {
+        assert_eq!(
+            ws_url(&server("https://nf-abc.tunnel.example.com")).unwrap(),
+            "wss://nf-abc.tunnel.example.com/terminal/ws?token=s3cret%26x"
+        );
+
+        let mut local = server("http://192.168.1.5:8080/api");
+        local.auth_token = None;
+        local.device_token = Some("dev".to_string());
+        assert_eq!(
+            ws_url(&local).unwrap(),
+            "ws://192.168.1.5:8080/terminal/ws?device=dev"
+        );
+    }
+
+    #[test]
+    fn test_find_server_by_id_or_name() {
+        let servers = vec![server("http://a"), {
+            let mut s = server("http://b");
+            s.id = "laptop".to_string();
+            s.name = "Laptop".to_string();
+            s
+        }];
+        assert_eq!(find_server(&servers, "desk").unwrap().id, "desk");
+        assert_eq!(find_server(&servers, "laptop").unwrap().id, "laptop");
+        assert_eq!(find_server(&servers, "LAPTOP").unwrap().id, "laptop");
+        assert!(find_server(&servers, "nas").is_none());
+    }
+
+    #[test]
+    fn test_ttyd_frames() {
+        assert_eq!(input_frame(b"ls\r"), b"0ls\r");
+        assert_eq!(resize_frame(120, 40), br#"1{"columns":120,"rows":40}"#);
+        assert_eq!(output(b"0hello"), Some(&b"hello"[..]));
+        assert_eq!(output(b"1title"), None);
+        assert_eq!(output(b""), None);
+    }
+}
@@ -3,7 +3,10 @@ use ratatui::{
     widgets::Paragraph,
 };
 
+use nomadflow_core::i18n::{t, tf};
+
 use crate::app::App;
+use crate::widgets;
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     if let Some(ref err) = app.error {
@@ -11,15 +14,19 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(1), Constraint::Length(1)])
             .split(area);
-        let error = Paragraph::new(format!("Error: {err}"))
+        let error = Paragraph::new(tf("common.error", &[("err", err)]))
             .style(Style::default().fg(Color::Red));
         frame.render_widget(error, chunks[0]);
-        let hint = Paragraph::new("Press q to quit")
+        let hint = Paragraph::new(t("common.press_q"))
             .style(Style::default().fg(Color::DarkGray));
         frame.render_widget(hint, chunks[1]);
         return;
     }
 
-    let text = Paragraph::new("Preparing tmux session...");
+    let text = Paragraph::new(widgets::spinner::line(
+        t("attaching.preparing"),
+        app.tick,
+        app.elapsed(),
+    ));
     frame.render_widget(text, area);
 }
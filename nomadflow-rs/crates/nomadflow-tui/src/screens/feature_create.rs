@@ -3,7 +3,12 @@ use ratatui::{
     widgets::Paragraph,
 };
 
+use nomadflow_core::branch::{check_branch_name, suggest_branch_names};
+use nomadflow_core::i18n::{t, tf};
+
 use crate::app::App;
+use crate::glyphs::{self, Status};
+use crate::widgets::text;
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     let repo_name = app.repo.as_ref().map(|r| r.name.as_str()).unwrap_or("");
@@ -13,10 +18,10 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(1), Constraint::Length(1)])
             .split(area);
-        let error = Paragraph::new(format!("Error: {err}"))
+        let error = Paragraph::new(tf("common.error", &[("err", err)]))
             .style(Style::default().fg(Color::Red));
         frame.render_widget(error, chunks[0]);
-        let hint = Paragraph::new("Press Escape to go back")
+        let hint = Paragraph::new(t("common.press_esc"))
             .style(Style::default().fg(Color::DarkGray));
         frame.render_widget(hint, chunks[1]);
         return;
@@ -29,13 +34,23 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             .constraints([Constraint::Length(2), Constraint::Length(1)])
             .split(area);
 
-        let confirm = Paragraph::new(Line::from(vec![
-            Span::raw("Create feature "),
-            Span::styled(&app.input_text, Style::default().fg(Color::Cyan).bold()),
-            Span::raw(" in "),
-            Span::styled(repo_name, Style::default().bold()),
-            Span::raw("? (y/n)"),
-        ]));
+        if app.creating_protected() {
+            let warning = tf("create.protected", &[("name", &app.input_text)]);
+            let warning = Paragraph::new(glyphs::mark(Status::Warning, &warning))
+                .style(Style::default().fg(Color::Yellow));
+            frame.render_widget(warning, chunks[1]);
+        }
+
+        let confirm = Paragraph::new(text::styled(
+            "create.confirm",
+            vec![
+                (
+                    "name",
+                    Span::styled(&app.input_text, Style::default().fg(Color::Cyan).bold()),
+                ),
+                ("repo", Span::styled(repo_name, Style::default().bold())),
+            ],
+        ));
         frame.render_widget(confirm, chunks[0]);
         return;
     }
@@ -43,22 +58,28 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     // Input step
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(2), Constraint::Length(1)])
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
         .split(area);
 
-    let title = Paragraph::new(format!("Create a new feature ({repo_name}):"))
+    let title = Paragraph::new(tf("create.title", &[("repo", &repo_name)]))
         .style(Style::default().bold());
     frame.render_widget(title, chunks[0]);
 
     // Render input field with cursor
+    let label = t("create.label");
     let input_display = if app.input_text.is_empty() {
         Line::from(vec![
-            Span::raw("Feature name: "),
+            Span::raw(label),
             Span::styled("my-feature", Style::default().fg(Color::DarkGray)),
         ])
     } else {
         Line::from(vec![
-            Span::raw("Feature name: "),
+            Span::raw(label),
             Span::raw(&app.input_text),
         ])
     };
@@ -67,7 +88,29 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(input, chunks[1]);
 
     // Show cursor position
-    let cursor_x = chunks[1].x + 14 + app.input_cursor as u16; // "Feature name: " is 14 chars
+    let cursor_x = chunks[1].x + label.chars().count() as u16 + app.input_cursor as u16;
     let cursor_y = chunks[1].y;
     frame.set_cursor_position(Position::new(cursor_x, cursor_y));
+
+    // Inline validation, as the name is typed
+    let name = app.input_text.trim();
+    if name.is_empty() {
+        return;
+    }
+    // A sentence describing the feature rather than its name
+    if let Some(proposed) = app.proposed_name() {
+        let hint = Paragraph::new(tf("create.from_description", &[("name", &proposed)]))
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(hint, chunks[2]);
+        return;
+    }
+    if let Err(problem) = check_branch_name(name) {
+        let problem = Paragraph::new(problem.to_string()).style(Style::default().fg(Color::Red));
+        frame.render_widget(problem, chunks[2]);
+    }
+    if let Some(suggestion) = suggest_branch_names(name).first() {
+        let hint = Paragraph::new(tf("create.suggest", &[("name", suggestion)]))
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(hint, chunks[3]);
+    }
 }
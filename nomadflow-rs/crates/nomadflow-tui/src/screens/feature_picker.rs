@@ -1,14 +1,41 @@
 use ratatui::{
     prelude::*,
-    widgets::{List, ListItem, Paragraph},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
 };
 
+use nomadflow_core::i18n::{t, tf};
+use nomadflow_core::models::{BulkFeatureAction, Feature};
+
 use crate::app::App;
+use crate::glyphs::{self, Status};
 use crate::tmux_local;
+use crate::widgets;
+
+/// Unresolved conflicts, uncommitted work and commits ahead of or behind
+/// the upstream, each preceded by two spaces. Empty for a clean feature in
+/// sync.
+pub fn git_state(feature: &Feature) -> String {
+    let mut state = String::new();
+    if feature.needs_resolution {
+        state.push_str(&format!(
+            "  {}",
+            glyphs::mark(Status::Warning, t("features.needs_resolution"))
+        ));
+    }
+    if feature.dirty {
+        state.push_str(&format!("  {}", glyphs::mark(Status::Dirty, t("features.dirty"))));
+    }
+    let counts = glyphs::ahead_behind(feature.ahead.unwrap_or(0), feature.behind.unwrap_or(0));
+    if !counts.is_empty() {
+        state.push_str(&format!("  {counts}"));
+    }
+    state
+}
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     if app.loading {
-        let text = Paragraph::new("Loading features...");
+        let line = widgets::spinner::line(t("features.loading"), app.tick, app.elapsed());
+        let text = Paragraph::new(line);
         frame.render_widget(text, area);
         return;
     }
@@ -18,24 +45,40 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(1), Constraint::Length(1)])
             .split(area);
-        let error = Paragraph::new(format!("Error: {err}"))
+        let error = Paragraph::new(tf("common.error", &[("err", err)]))
             .style(Style::default().fg(Color::Red));
         frame.render_widget(error, chunks[0]);
-        let hint = Paragraph::new("Press Escape to go back")
+        let hint = Paragraph::new(t("common.press_esc"))
             .style(Style::default().fg(Color::DarkGray));
         frame.render_widget(hint, chunks[1]);
         return;
     }
 
+    if let Some(action) = app.batch_action {
+        render_batch_confirm(frame, area, app, action);
+        return;
+    }
+
     let repo_name = app.repo.as_ref().map(|r| r.name.as_str()).unwrap_or("");
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(2), Constraint::Min(1)])
+        .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Min(1)])
         .split(area);
 
-    let title = Paragraph::new(format!("Select a feature ({repo_name}):"))
-        .style(Style::default().bold());
-    frame.render_widget(title, chunks[0]);
+    let title = if app.marked.is_empty() {
+        tf("features.title", &[("repo", &repo_name)])
+    } else {
+        tf(
+            "features.title_marked",
+            &[("repo", &repo_name), ("count", &app.marked.len())],
+        )
+    };
+    frame.render_widget(Paragraph::new(title).style(Style::default().bold()), chunks[0]);
+
+    if let Some(ref notice) = app.notice {
+        let notice = Paragraph::new(notice.as_str()).style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(notice, chunks[1]);
+    }
 
     let mut items: Vec<ListItem> = app
         .features
@@ -45,17 +88,46 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             let f = &cf.feature;
             let is_idle = tmux_local::is_shell_idle_str(cf.pane_command.as_deref());
             let process_info = match &cf.pane_command {
-                Some(_) if is_idle => "  idle".to_string(),
-                Some(cmd) => format!("  ● {cmd} running"),
+                Some(_) if is_idle => {
+                    format!("  {}", glyphs::mark(Status::Idle, t("features.idle")))
+                }
+                Some(cmd) => format!(
+                    "  {}",
+                    glyphs::mark(Status::Busy, &tf("features.running", &[("cmd", cmd)]))
+                ),
                 None => String::new(),
             };
-            let prefix = if f.is_main { "⌂ " } else { "" };
-            let suffix = if f.is_main { "  [source]" } else { "" };
-            let label = format!("{prefix}{}  {}{process_info}{suffix}", f.name, f.branch);
+            let mark = if app.marked.is_empty() {
+                ""
+            } else if app.marked.contains(&f.name) {
+                "[x] "
+            } else {
+                "[ ] "
+            };
+            let main = format!("{} ", glyphs::glyph(Status::Main));
+            let prefix = if f.is_main { main.as_str() } else { mark };
+            let suffix = if f.is_main {
+                format!("  {}", t("features.source"))
+            } else {
+                String::new()
+            };
+            let unread = if f.has_unread {
+                format!("  {}", glyphs::mark(Status::Unread, t("features.new_output")))
+            } else {
+                String::new()
+            };
+            let label = format!(
+                "{prefix}{}  {}{}{process_info}{unread}{suffix}",
+                f.name,
+                f.branch,
+                git_state(f)
+            );
 
             let item = ListItem::new(label);
             if i == app.selected_index {
                 item.style(Style::default().fg(Color::Cyan).bold())
+            } else if f.has_unread {
+                item.style(Style::default().fg(Color::Yellow))
             } else {
                 item
             }
@@ -64,7 +136,7 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
 
     // Add "Create" option
     let create_idx = app.features.len();
-    let create_item = ListItem::new("+ Create a feature");
+    let create_item = ListItem::new(t("features.create"));
     let create_item = if app.selected_index == create_idx {
         create_item.style(Style::default().fg(Color::Cyan).bold())
     } else {
@@ -73,5 +145,92 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     items.push(create_item);
 
     let list = List::new(items);
-    frame.render_widget(list, chunks[1]);
+    if app.notes_open {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(NOTES_HEIGHT)])
+            .split(chunks[2]);
+        frame.render_widget(list, split[0]);
+        render_notes(frame, split[1], app);
+    } else {
+        frame.render_widget(list, chunks[2]);
+    }
+}
+
+/// Rows of the notes panel, borders included.
+const NOTES_HEIGHT: u16 = 8;
+
+/// The note of the highlighted feature, below the list.
+fn render_notes(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .title(t("features.notes"))
+        .border_style(Style::default().fg(Color::DarkGray));
+    let dim = Style::default().fg(Color::DarkGray);
+    let text = match &app.note {
+        _ if app.note_for.is_none() => Paragraph::new(""),
+        None => Paragraph::new(t("features.note_loading")).style(dim),
+        Some(Ok(Some(note))) => Paragraph::new(note.text.as_str()),
+        Some(Ok(None)) => Paragraph::new(t("features.no_note")).style(dim),
+        Some(Err(err)) => Paragraph::new(tf("common.error", &[("err", err)]))
+            .style(Style::default().fg(Color::Red)),
+    };
+    frame.render_widget(text.block(block).wrap(Wrap { trim: false }), area);
+}
+
+/// Combined confirmation for a batch action, spelling out what happens to
+/// worktrees and branches of every marked feature.
+fn render_batch_confirm(frame: &mut Frame, area: Rect, app: &App, action: BulkFeatureAction) {
+    let marked: Vec<_> = app
+        .features
+        .iter()
+        .filter(|cf| app.marked.contains(&cf.feature.name))
+        .collect();
+    let count = marked.len();
+    let branches: Vec<&str> = marked.iter().map(|cf| cf.feature.branch.as_str()).collect();
+    let busy: Vec<&str> = marked
+        .iter()
+        .filter(|cf| {
+            cf.pane_command.is_some() && !tmux_local::is_shell_idle_str(cf.pane_command.as_deref())
+        })
+        .map(|cf| cf.feature.name.as_str())
+        .collect();
+
+    let (title, impact) = match action {
+        BulkFeatureAction::Delete => (
+            tf("batch.delete_title", &[("count", &count)]),
+            tf("batch.delete_impact", &[("count", &count)]),
+        ),
+        BulkFeatureAction::Prune => (
+            tf("batch.prune_title", &[("count", &count)]),
+            tf("batch.prune_impact", &[("count", &count)]),
+        ),
+        BulkFeatureAction::Sync => (
+            tf("batch.sync_title", &[("count", &count)]),
+            t("batch.sync_impact").to_string(),
+        ),
+    };
+
+    let mut lines = vec![
+        Line::from(Span::styled(title, Style::default().bold())),
+        Line::from(""),
+        Line::from(impact),
+    ];
+    for branch in &branches {
+        lines.push(Line::from(Span::styled(
+            format!("  {branch}"),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    if !busy.is_empty() && action != BulkFeatureAction::Sync {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            glyphs::mark(Status::Busy, &tf("batch.busy", &[("names", &busy.join(", "))])),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(t("batch.continue")));
+
+    frame.render_widget(Paragraph::new(lines), area);
 }
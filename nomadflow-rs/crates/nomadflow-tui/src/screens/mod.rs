@@ -3,6 +3,8 @@ pub mod feature_create;
 pub mod feature_picker;
 pub mod repo_picker;
 pub mod resume;
+pub mod search;
 pub mod server_add;
 pub mod server_picker;
 pub mod setup;
+pub mod task_menu;
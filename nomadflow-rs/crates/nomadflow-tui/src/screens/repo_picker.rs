@@ -3,11 +3,16 @@ use ratatui::{
     widgets::{List, ListItem, Paragraph},
 };
 
+use nomadflow_core::i18n::{t, tf};
+
 use crate::app::App;
+use crate::glyphs::{self, Status};
+use crate::widgets;
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     if app.loading {
-        let text = Paragraph::new("Loading repos...");
+        let line = widgets::spinner::line(t("repos.loading"), app.tick, app.elapsed());
+        let text = Paragraph::new(line);
         frame.render_widget(text, area);
         return;
     }
@@ -17,10 +22,10 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(1), Constraint::Length(1)])
             .split(area);
-        let error = Paragraph::new(format!("Error: {err}"))
+        let error = Paragraph::new(tf("common.error", &[("err", err)]))
             .style(Style::default().fg(Color::Red));
         frame.render_widget(error, chunks[0]);
-        let hint = Paragraph::new("Press Escape to go back")
+        let hint = Paragraph::new(t("common.press_esc"))
             .style(Style::default().fg(Color::DarkGray));
         frame.render_widget(hint, chunks[1]);
         return;
@@ -31,24 +36,46 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(1), Constraint::Length(1)])
             .split(area);
-        let text = Paragraph::new("No repositories found.");
+        let text = Paragraph::new(t("repos.empty"));
         frame.render_widget(text, chunks[0]);
-        let hint = Paragraph::new("Clone a repo via the mobile app or server first.")
+        let hint = Paragraph::new(t("repos.empty_hint"))
             .style(Style::default().fg(Color::DarkGray));
         frame.render_widget(hint, chunks[1]);
         return;
     }
 
     let server_name = app.server.as_ref().map(|s| s.name.as_str()).unwrap_or("");
+    let fixable = app.fixable_issues();
+    let banner_height = if fixable == 0 { 0 } else { 2 };
+    let tips = app.tips();
+    let tips_height = if tips.is_empty() {
+        0
+    } else {
+        tips.len() as u16 + 1
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(2), Constraint::Min(1)])
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Length(tips_height),
+            Constraint::Length(banner_height),
+            Constraint::Min(1),
+        ])
         .split(area);
 
-    let title = Paragraph::new(format!("Select a repo ({server_name}):"))
+    let title = Paragraph::new(tf("repos.title", &[("server", &server_name)]))
         .style(Style::default().bold());
     frame.render_widget(title, chunks[0]);
 
+    widgets::tips::render(frame, chunks[1], &tips);
+
+    if fixable > 0 {
+        let banner = tf("reconcile.banner", &[("count", &fixable)]);
+        let banner = Paragraph::new(glyphs::mark(Status::Warning, &banner))
+            .style(Style::default().fg(Color::Yellow));
+        frame.render_widget(banner, chunks[2]);
+    }
+
     let last_repo = app.cli_state.last_repo.as_deref().and_then(|r| {
         std::path::Path::new(r)
             .file_name()
@@ -61,7 +88,7 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .enumerate()
         .map(|(i, r)| {
             let is_last = last_repo.as_deref() == Some(&r.name);
-            let suffix = if is_last { "  (last used)" } else { "" };
+            let suffix = if is_last { t("repos.last_used") } else { "" };
             let label = format!("{}  {}{suffix}", r.name, r.branch);
 
             let item = ListItem::new(label);
@@ -74,5 +101,5 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .collect();
 
     let list = List::new(items);
-    frame.render_widget(list, chunks[1]);
+    frame.render_widget(list, chunks[3]);
 }
@@ -3,7 +3,11 @@ use ratatui::{
     widgets::{List, ListItem, Paragraph},
 };
 
+use nomadflow_core::i18n::{t, tf};
+use nomadflow_core::models::WindowState;
+
 use crate::app::App;
+use crate::widgets::text;
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     let label = [
@@ -26,23 +30,24 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .constraints([Constraint::Length(2), Constraint::Length(1), Constraint::Min(1)])
         .split(area);
 
-    let title = Paragraph::new("Resume previous session?")
+    let title = Paragraph::new(t("resume.title"))
         .style(Style::default().bold());
     frame.render_widget(title, chunks[0]);
 
-    let info = Paragraph::new(Line::from(vec![
-        Span::raw("Last session: "),
-        Span::styled(&label, Style::default().fg(Color::Yellow)),
-        Span::raw(" on "),
-        Span::raw(server_name),
-    ]))
+    let info = Paragraph::new(text::styled(
+        "resume.last",
+        vec![
+            ("label", Span::styled(&label, Style::default().fg(Color::Yellow))),
+            ("server", Span::raw(server_name)),
+        ],
+    ))
     .style(Style::default().fg(Color::DarkGray));
     frame.render_widget(info, chunks[1]);
 
-    let options = [
-        "Yes, attach tmux session",
-        "No, choose another session",
-    ];
+    let mut options = vec![t("resume.yes").to_string(), t("resume.no").to_string()];
+    if let Some(window) = &app.jump_to {
+        options.insert(0, jump_label(window));
+    }
 
     let items: Vec<ListItem> = options
         .iter()
@@ -62,3 +67,22 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     let list = List::new(items);
     frame.render_widget(list, chunks[2]);
 }
+
+/// `Jump to where you left off: repo:feature (tests running)`.
+fn jump_label(window: &WindowState) -> String {
+    let repo = window
+        .repo_path
+        .as_deref()
+        .and_then(|r| std::path::Path::new(r).file_name())
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let feature = window.feature_name.as_deref().unwrap_or(&window.window);
+    let state = match &window.command {
+        Some(cmd) if !window.idle => tf("features.running", &[("cmd", cmd)]),
+        _ => t("features.idle").to_string(),
+    };
+    tf(
+        "resume.jump",
+        &[("label", &format!("{repo}:{feature}")), ("state", &state)],
+    )
+}
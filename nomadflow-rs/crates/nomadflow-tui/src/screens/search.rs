@@ -0,0 +1,102 @@
+use ratatui::{
+    prelude::*,
+    widgets::{List, ListItem, Paragraph},
+};
+
+use nomadflow_core::i18n::{t, tf};
+
+use crate::app::App;
+use crate::widgets;
+
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let server_name = app.server.as_ref().map(|s| s.name.as_str()).unwrap_or("");
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Length(2),
+            Constraint::Min(1),
+        ])
+        .split(area);
+
+    let title = Paragraph::new(tf("search.title", &[("server", &server_name)]))
+        .style(Style::default().bold());
+    frame.render_widget(title, chunks[0]);
+
+    let label = t("search.label");
+    let input = if app.input_text.is_empty() {
+        Line::from(vec![
+            Span::raw(label),
+            Span::styled(
+                t("search.placeholder"),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])
+    } else {
+        Line::from(vec![Span::raw(label), Span::raw(&app.input_text)])
+    };
+    frame.render_widget(Paragraph::new(input), chunks[1]);
+    if !app.loading {
+        let cursor_x = chunks[1].x + label.chars().count() as u16 + app.input_cursor as u16;
+        frame.set_cursor_position(Position::new(cursor_x, chunks[1].y));
+    }
+
+    if app.loading {
+        let searching = widgets::spinner::line(t("search.searching"), app.tick, app.elapsed());
+        frame.render_widget(Paragraph::new(searching), chunks[2]);
+        return;
+    }
+
+    if let Some(ref err) = app.error {
+        let error = Paragraph::new(tf("common.error", &[("err", err)]))
+            .style(Style::default().fg(Color::Red));
+        frame.render_widget(error, chunks[2]);
+        return;
+    }
+
+    if app.searched.is_none() {
+        return;
+    }
+    if app.search_results.is_empty() {
+        frame.render_widget(Paragraph::new(t("search.empty")), chunks[2]);
+        return;
+    }
+
+    let mut items: Vec<ListItem> = app
+        .search_results
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let repo = std::path::Path::new(&m.repo_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let line = Line::from(vec![
+                Span::styled(
+                    format!("{repo}/{}", m.feature_name),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::styled(
+                    format!("  {}:{}  ", m.path, m.line),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(m.text.trim()),
+            ]);
+
+            let item = ListItem::new(line);
+            if i == app.selected_index {
+                item.style(Style::default().fg(Color::Cyan).bold())
+            } else {
+                item
+            }
+        })
+        .collect();
+    if app.search_truncated {
+        items.push(
+            ListItem::new(tf("search.truncated", &[("n", &app.search_results.len())]))
+                .style(Style::default().fg(Color::DarkGray)),
+        );
+    }
+
+    frame.render_widget(List::new(items), chunks[2]);
+}
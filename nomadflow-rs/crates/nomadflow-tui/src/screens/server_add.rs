@@ -3,7 +3,10 @@ use ratatui::{
     widgets::Paragraph,
 };
 
+use nomadflow_core::i18n::{t, tf};
+
 use crate::app::App;
+use crate::widgets::text;
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     if let Some(ref err) = app.error {
@@ -11,10 +14,10 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(1), Constraint::Length(1)])
             .split(area);
-        let error = Paragraph::new(format!("Error: {err}"))
+        let error = Paragraph::new(tf("common.error", &[("err", err)]))
             .style(Style::default().fg(Color::Red));
         frame.render_widget(error, chunks[0]);
-        let hint = Paragraph::new("Press Escape to go back")
+        let hint = Paragraph::new(t("common.press_esc"))
             .style(Style::default().fg(Color::DarkGray));
         frame.render_widget(hint, chunks[1]);
         return;
@@ -32,21 +35,24 @@ fn render_confirm(frame: &mut Frame, area: Rect, app: &App) {
         .constraints([Constraint::Length(2), Constraint::Length(1)])
         .split(area);
 
-    let confirm = Paragraph::new(Line::from(vec![
-        Span::raw("Add server "),
-        Span::styled(&app.server_add_name, Style::default().fg(Color::Cyan).bold()),
-        Span::raw(" at "),
-        Span::styled(&app.server_add_url, Style::default().bold()),
-        Span::raw("? (y/n)"),
-    ]));
+    let confirm = Paragraph::new(text::styled(
+        "server_add.confirm",
+        vec![
+            (
+                "name",
+                Span::styled(&app.server_add_name, Style::default().fg(Color::Cyan).bold()),
+            ),
+            ("url", Span::styled(&app.server_add_url, Style::default().bold())),
+        ],
+    ));
     frame.render_widget(confirm, chunks[0]);
 }
 
 fn render_input(frame: &mut Frame, area: Rect, app: &App) {
     let (title, label, placeholder) = match app.server_add_step {
-        0 => ("Add a new server:", "Server name: ", "my-server"),
-        1 => ("Add a new server:", "API URL: ", "http://host:8080"),
-        2 => ("Add a new server:", "Auth token: ", "(optional, Enter to skip)"),
+        0 => (t("server_add.title"), t("server_add.name"), "my-server"),
+        1 => (t("server_add.title"), t("server_add.url"), "http://host:8080"),
+        2 => (t("server_add.title"), t("server_add.token"), t("server_add.token_hint")),
         _ => unreachable!(),
     };
 
@@ -73,7 +79,7 @@ fn render_input(frame: &mut Frame, area: Rect, app: &App) {
     let input = Paragraph::new(input_display);
     frame.render_widget(input, chunks[1]);
 
-    let cursor_x = chunks[1].x + label.len() as u16 + app.input_cursor as u16;
+    let cursor_x = chunks[1].x + label.chars().count() as u16 + app.input_cursor as u16;
     let cursor_y = chunks[1].y;
     frame.set_cursor_position(Position::new(cursor_x, cursor_y));
 }
@@ -3,11 +3,14 @@ use ratatui::{
     widgets::{List, ListItem, Paragraph},
 };
 
+use nomadflow_core::i18n::t;
+
 use crate::app::App;
+use crate::widgets::latency;
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     if app.health_checking {
-        let spinner = Paragraph::new("Checking servers...");
+        let spinner = Paragraph::new(t("servers.checking"));
         frame.render_widget(spinner, area);
         return;
     }
@@ -17,7 +20,7 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .constraints([Constraint::Length(2), Constraint::Min(1)])
         .split(area);
 
-    let title = Paragraph::new("Select a server:")
+    let title = Paragraph::new(t("servers.title"))
         .style(Style::default().bold());
     frame.render_widget(title, chunks[0]);
 
@@ -26,18 +29,24 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .iter()
         .enumerate()
         .map(|(i, s)| {
-            let health = app
-                .health_map
-                .get(&s.id)
-                .map(|ok| if *ok { " ✓" } else { " ✗" })
-                .unwrap_or("");
-            let label = format!(
-                "{} ({}){health}",
+            let mut spans = vec![Span::raw(format!(
+                "{} ({})",
                 s.name,
-                s.api_url.as_deref().unwrap_or("no url")
-            );
+                s.api_url.as_deref().unwrap_or(t("servers.no_url"))
+            ))];
+            if let Some(&rtt) = app.health_map.get(&s.id) {
+                spans.push(Span::raw("  "));
+                spans.push(latency::span(rtt));
+                if rtt.is_some_and(latency::is_unusable) {
+                    spans.push(Span::raw("  "));
+                    spans.push(Span::styled(
+                        t("latency.warning"),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+            }
 
-            let item = ListItem::new(label);
+            let item = ListItem::new(Line::from(spans));
             if i == app.selected_index {
                 item.style(Style::default().fg(Color::Cyan).bold())
             } else {
@@ -47,7 +56,7 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .collect();
 
     // "+ Add server" option
-    let add_item = ListItem::new("+ Add server");
+    let add_item = ListItem::new(t("servers.add"));
     let add_idx = app.servers.len();
     items.push(if add_idx == app.selected_index {
         add_item.style(Style::default().fg(Color::Green).bold())
@@ -3,6 +3,8 @@ use ratatui::{
     widgets::Paragraph,
 };
 
+use nomadflow_core::i18n::t;
+
 use crate::app::App;
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
@@ -30,18 +32,15 @@ fn render_password_choice(frame: &mut Frame, area: Rect, app: &App) {
         ])
         .split(area);
 
-    let title = Paragraph::new("Welcome to NomadFlow!")
+    let title = Paragraph::new(t("setup.welcome"))
         .style(Style::default().fg(Color::Cyan).bold());
     frame.render_widget(title, chunks[0]);
 
-    let label = Paragraph::new("Set a password to secure your server:")
+    let label = Paragraph::new(t("setup.password_prompt"))
         .style(Style::default().bold());
     frame.render_widget(label, chunks[1]);
 
-    let options = [
-        "Generate a password (recommended)",
-        "Enter my own password",
-    ];
+    let options = [t("setup.generate"), t("setup.custom")];
 
     for (i, opt) in options.iter().enumerate() {
         let style = if app.selected_index == i {
@@ -65,10 +64,10 @@ fn render_password_input(frame: &mut Frame, area: Rect, app: &App) {
         ])
         .split(area);
 
-    let title = Paragraph::new("Enter your password:").style(Style::default().bold());
+    let title = Paragraph::new(t("setup.enter_password")).style(Style::default().bold());
     frame.render_widget(title, chunks[0]);
 
-    let label = "Password: ";
+    let label = t("setup.password_label");
     let input_display = if app.input_text.is_empty() {
         Line::from(vec![
             Span::raw(label),
@@ -84,7 +83,7 @@ fn render_password_input(frame: &mut Frame, area: Rect, app: &App) {
     let input = Paragraph::new(input_display);
     frame.render_widget(input, chunks[1]);
 
-    let cursor_x = chunks[1].x + label.len() as u16 + app.input_cursor as u16;
+    let cursor_x = chunks[1].x + label.chars().count() as u16 + app.input_cursor as u16;
     let cursor_y = chunks[1].y;
     frame.set_cursor_position(Position::new(cursor_x, cursor_y));
 }
@@ -100,11 +99,11 @@ fn render_public_choice(frame: &mut Frame, area: Rect, _app: &App) {
         ])
         .split(area);
 
-    let title = Paragraph::new("Will you use public tunnel mode? (y/n)")
+    let title = Paragraph::new(t("setup.public_title"))
         .style(Style::default().bold());
     frame.render_widget(title, chunks[0]);
 
-    let hint = Paragraph::new("This exposes your server over the internet via a tunnel URL.")
+    let hint = Paragraph::new(t("setup.public_hint"))
         .style(Style::default().fg(Color::DarkGray));
     frame.render_widget(hint, chunks[2]);
 }
@@ -129,12 +128,12 @@ fn render_subdomain_input(frame: &mut Frame, area: Rect, app: &App) {
         ])
         .split(area);
 
-    let title = Paragraph::new("Use a fixed subdomain for a stable public URL? (y/n)")
+    let title = Paragraph::new(t("setup.subdomain_title"))
         .style(Style::default().bold());
     frame.render_widget(title, chunks[0]);
 
     let sub_line = Paragraph::new(Line::from(vec![
-        Span::raw("  Your subdomain: "),
+        Span::raw(t("setup.subdomain_label")),
         Span::styled(&app.setup_subdomain, Style::default().fg(Color::Cyan).bold()),
     ]));
     frame.render_widget(sub_line, chunks[1]);
@@ -147,7 +146,7 @@ fn render_subdomain_input(frame: &mut Frame, area: Rect, app: &App) {
         Paragraph::new(url_preview).style(Style::default().fg(Color::DarkGray));
     frame.render_widget(url_line, chunks[2]);
 
-    let hint = Paragraph::new("y: use this fixed subdomain  n: random URL each time")
+    let hint = Paragraph::new(t("setup.subdomain_hint"))
         .style(Style::default().fg(Color::DarkGray));
     frame.render_widget(hint, chunks[4]);
 }
@@ -166,31 +165,31 @@ fn render_confirm(frame: &mut Frame, area: Rect, app: &App) {
         ])
         .split(area);
 
-    let title = Paragraph::new("Configuration summary:")
+    let title = Paragraph::new(t("setup.summary"))
         .style(Style::default().fg(Color::Cyan).bold());
     frame.render_widget(title, chunks[0]);
 
     let password_line = Paragraph::new(Line::from(vec![
-        Span::raw("  Password: "),
+        Span::raw(t("setup.summary_password")),
         Span::styled(&app.setup_secret, Style::default().fg(Color::Yellow)),
     ]));
     frame.render_widget(password_line, chunks[1]);
 
-    let public_str = if app.setup_public { "yes" } else { "no" };
+    let public_str = if app.setup_public { t("common.yes") } else { t("common.no") };
     let public_line = Paragraph::new(Line::from(vec![
-        Span::raw("  Public mode: "),
+        Span::raw(t("setup.summary_public")),
         Span::styled(public_str, Style::default().bold()),
     ]));
     frame.render_widget(public_line, chunks[2]);
 
     if app.setup_public {
         let sub = if app.setup_subdomain.is_empty() {
-            "(random)".to_string()
+            t("setup.random").to_string()
         } else {
             app.setup_subdomain.clone()
         };
         let sub_line = Paragraph::new(Line::from(vec![
-            Span::raw("  Subdomain: "),
+            Span::raw(t("setup.summary_subdomain")),
             Span::styled(sub, Style::default().bold()),
         ]));
         frame.render_widget(sub_line, chunks[3]);
@@ -198,7 +197,7 @@ fn render_confirm(frame: &mut Frame, area: Rect, app: &App) {
 
     let config_path = app.settings.config_file();
     let path_line = Paragraph::new(Line::from(vec![
-        Span::raw("  Config: "),
+        Span::raw(t("setup.summary_config")),
         Span::styled(
             config_path.display().to_string(),
             Style::default().fg(Color::DarkGray),
@@ -206,6 +205,6 @@ fn render_confirm(frame: &mut Frame, area: Rect, app: &App) {
     ]));
     frame.render_widget(path_line, chunks[4]);
 
-    let confirm = Paragraph::new("Save and continue? (y/n)").style(Style::default().bold());
+    let confirm = Paragraph::new(t("setup.save")).style(Style::default().bold());
     frame.render_widget(confirm, chunks[5]);
 }
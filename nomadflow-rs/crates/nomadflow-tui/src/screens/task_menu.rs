@@ -0,0 +1,82 @@
+use ratatui::{
+    prelude::*,
+    widgets::{List, ListItem, Paragraph},
+};
+
+use nomadflow_core::i18n::{t, tf};
+
+use crate::app::App;
+use crate::widgets;
+
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    if app.loading {
+        let line = widgets::spinner::line(t("tasks.loading"), app.tick, app.elapsed());
+        let text = Paragraph::new(line);
+        frame.render_widget(text, area);
+        return;
+    }
+
+    if let Some(ref err) = app.error {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1)])
+            .split(area);
+        let error = Paragraph::new(tf("common.error", &[("err", err)]))
+            .style(Style::default().fg(Color::Red));
+        frame.render_widget(error, chunks[0]);
+        let hint = Paragraph::new(t("common.press_esc"))
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(hint, chunks[1]);
+        return;
+    }
+
+    if app.tasks.is_empty() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1)])
+            .split(area);
+        frame.render_widget(Paragraph::new(t("tasks.empty")), chunks[0]);
+        let hint = Paragraph::new(t("tasks.empty_hint"))
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(hint, chunks[1]);
+        return;
+    }
+
+    let feature_name = app.feature.as_ref().map(|f| f.name.as_str()).unwrap_or("");
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(1)])
+        .split(area);
+
+    let title = Paragraph::new(tf("tasks.title", &[("feature", &feature_name)]))
+        .style(Style::default().bold());
+    frame.render_widget(title, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .tasks
+        .iter()
+        .enumerate()
+        .map(|(i, task)| {
+            let about = if task.description.is_empty() {
+                &task.command
+            } else {
+                &task.description
+            };
+            let mode = if task.background {
+                format!("  {}", t("tasks.background"))
+            } else {
+                String::new()
+            };
+            let label = format!("{}  {about}{mode}", task.name);
+
+            let item = ListItem::new(label);
+            if i == app.selected_index {
+                item.style(Style::default().fg(Color::Cyan).bold())
+            } else {
+                item
+            }
+        })
+        .collect();
+
+    frame.render_widget(List::new(items), chunks[1]);
+}
@@ -3,6 +3,10 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 
 use nomadflow_core::config::Settings;
+use nomadflow_core::error::{NomadError, Result};
+use nomadflow_core::fsutil;
+use nomadflow_core::i18n::tf;
+use nomadflow_core::secrets::{self, TokenStore, KEYCHAIN_REF};
 
 /// Persisted CLI state.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -12,6 +16,9 @@ pub struct CliState {
     pub last_repo: Option<String>,
     pub last_feature: Option<String>,
     pub last_attached: Option<u64>,
+    /// Tips dismissed in the repo picker, as `server id:tip key`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dismissed_tips: Vec<String>,
 }
 
 /// Server configuration for the TUI (loaded from cli-servers.json).
@@ -23,6 +30,46 @@ pub struct ServerConfig {
     pub api_url: Option<String>,
     pub ttyd_url: Option<String>,
     pub auth_token: Option<String>,
+    /// Sent as `X-NomadFlow-Device` to servers requiring a TOTP second factor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_token: Option<String>,
+    /// TLS certificate trusted for this server.
+    #[serde(default, skip_serializing_if = "CertTrust::is_unset")]
+    pub cert: CertTrust,
+    /// Why the auth token could not be read from the OS keychain. Requests
+    /// to the server fail with it instead of going out without the token.
+    #[serde(skip)]
+    pub token_error: Option<String>,
+}
+
+/// Certificate pinned on the first HTTPS connection to a server.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertTrust {
+    /// SHA-256 fingerprint of the certificate seen first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub on_change: OnCertChange,
+}
+
+impl CertTrust {
+    fn is_unset(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What to do when a server presents another certificate than the pinned one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnCertChange {
+    /// Refuse to connect.
+    #[default]
+    Block,
+    /// Connect if the certificate is otherwise valid, and tell the user.
+    Warn,
+    /// Neither pin nor check the fingerprint.
+    Off,
 }
 
 /// Derive ttyd URL from API URL (same host, port 7681).
@@ -41,66 +88,236 @@ fn state_path(settings: &Settings) -> PathBuf {
 }
 
 pub fn load_state(settings: &Settings) -> CliState {
-    let path = state_path(settings);
-    if path.exists() {
-        std::fs::read_to_string(&path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
-    } else {
-        CliState::default()
-    }
+    fsutil::read_with_backup(&state_path(settings), |s| serde_json::from_str(s).ok())
+        .map(|(state, _)| state)
+        .unwrap_or_default()
 }
 
-pub fn save_state(settings: &Settings, state: &CliState) {
-    let base = settings.base_dir();
-    std::fs::create_dir_all(&base).ok();
+/// Save `state`, waiting for another TUI or CLI writing it to be done.
+pub fn save_state(settings: &Settings, state: &CliState) -> Result<()> {
+    std::fs::create_dir_all(settings.base_dir())?;
     let path = state_path(settings);
-    if let Ok(json) = serde_json::to_string_pretty(state) {
-        std::fs::write(path, json).ok();
-    }
+    let _lock = fsutil::lock(&path)?;
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| NomadError::Other(e.to_string()))?;
+    fsutil::write_atomic(&path, json)?;
+    Ok(())
+}
+
+fn servers_path(settings: &Settings) -> PathBuf {
+    settings.base_dir().join("cli-servers.json")
+}
+
+/// The servers saved in cli-servers.json, credentials decrypted and fetched
+/// from the keychain, and whether some auth tokens are still in the file.
+fn load_saved_servers(settings: &Settings) -> Option<(Vec<ServerConfig>, bool)> {
+    let saved = fsutil::read_with_backup(&servers_path(settings), |s| {
+        serde_json::from_str::<Vec<ServerConfig>>(s).ok()
+    });
+    saved.map(|(mut servers, _)| {
+        let tokens_in_file = servers
+            .iter()
+            .filter_map(|s| s.auth_token.as_deref())
+            .any(|token| !token.is_empty() && token != KEYCHAIN_REF);
+        open_credentials(settings, &mut servers);
+        fetch_tokens(&mut servers, secrets::load_server_token);
+        (servers, tokens_in_file)
+    })
 }
 
 /// Load server configs: always include localhost, then merge cli-servers.json.
 pub fn load_servers(settings: &Settings) -> Vec<ServerConfig> {
     let api_url = format!("http://localhost:{}", settings.api.port);
+    // ttyd itself stays plain HTTP on loopback
+    let ttyd_url = derive_ttyd_url(&api_url);
+    let api_url = if settings.tls_identity().is_some() {
+        api_url.replacen("http://", "https://", 1)
+    } else {
+        api_url
+    };
     let localhost = ServerConfig {
         id: "localhost".to_string(),
         name: "localhost".to_string(),
-        ttyd_url: Some(derive_ttyd_url(&api_url)),
+        ttyd_url: Some(ttyd_url),
         api_url: Some(api_url),
         auth_token: if settings.auth.secret.is_empty() {
             None
         } else {
             Some(settings.auth.secret.clone())
         },
+        // The local server accepts this token instead of a TOTP code
+        device_token: if settings.auth.totp_secret.is_empty() {
+            None
+        } else {
+            settings.local_device_token().ok()
+        },
+        cert: CertTrust::default(),
+        token_error: None,
     };
 
-    let servers_path = settings.base_dir().join("cli-servers.json");
-    if servers_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(&servers_path) {
-            if let Ok(mut servers) = serde_json::from_str::<Vec<ServerConfig>>(&content) {
-                let has_localhost = servers.iter().any(|s| s.id == "localhost");
-                if !has_localhost {
-                    servers.insert(0, localhost);
+    if let Some((mut servers, tokens_in_file)) = load_saved_servers(settings) {
+        if tokens_in_file {
+            migrate_tokens(settings);
+        }
+        let has_localhost = servers.iter().any(|s| s.id == "localhost");
+        if !has_localhost {
+            servers.insert(0, localhost);
+        }
+        return servers;
+    }
+
+    vec![localhost]
+}
+
+fn credentials(server: &mut ServerConfig) -> impl Iterator<Item = &mut String> {
+    [&mut server.auth_token, &mut server.device_token]
+        .into_iter()
+        .flatten()
+}
+
+/// Decrypt stored server credentials. Values that cannot be decrypted are
+/// kept as is, so the server rejects them instead of the list being lost.
+fn open_credentials(settings: &Settings, servers: &mut [ServerConfig]) {
+    let Ok(Some(key)) = settings.secret_key(false) else {
+        return;
+    };
+    for value in servers.iter_mut().flat_map(credentials) {
+        if let Ok(plain) = key.open(value) {
+            *value = plain;
+        }
+    }
+}
+
+/// Encrypt server credentials when the config uses encrypted secrets.
+fn seal_credentials(settings: &Settings, servers: &mut [ServerConfig]) -> Result<()> {
+    let Some(key) = settings.secret_key(true)? else {
+        return Ok(());
+    };
+    for value in servers.iter_mut().flat_map(credentials) {
+        if value != KEYCHAIN_REF {
+            *value = key.seal(value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace each [`KEYCHAIN_REF`] with the token `load` finds for the server.
+/// When it fails, the server is left without a token and with a
+/// `token_error`: the placeholder must never be sent as a token, as failed
+/// attempts get the client banned.
+fn fetch_tokens(servers: &mut [ServerConfig], load: impl Fn(&str) -> Result<String>) {
+    for server in servers {
+        if server.auth_token.as_deref() == Some(KEYCHAIN_REF) {
+            match load(&server.id) {
+                Ok(token) => server.auth_token = Some(token),
+                Err(e) => {
+                    server.auth_token = None;
+                    server.token_error = Some(tf(
+                        "servers.keychain_failed",
+                        &[("server", &server.name), ("err", &e)],
+                    ));
                 }
-                return servers;
             }
         }
     }
+}
 
-    vec![localhost]
+/// Move the auth tokens to the OS keychain with `store`, leaving
+/// [`KEYCHAIN_REF`] in their place. Without a keychain (headless machines)
+/// they stay where they are. Returns whether any token moved.
+fn stash_tokens(
+    settings: &Settings,
+    servers: &mut [ServerConfig],
+    store: impl Fn(&str, &str) -> Result<()>,
+) -> bool {
+    if settings.secrets.server_tokens != TokenStore::Keychain {
+        return false;
+    }
+    let mut moved = false;
+    for server in servers {
+        let Some(token) = &mut server.auth_token else {
+            continue;
+        };
+        // Undecryptable tokens would be stored encrypted
+        if token.is_empty() || token == KEYCHAIN_REF || secrets::is_encrypted(token) {
+            continue;
+        }
+        if store(&server.id, token).is_err() {
+            break;
+        }
+        *token = KEYCHAIN_REF.to_string();
+        moved = true;
+    }
+    moved
+}
+
+/// Move the auth tokens saved in cli-servers.json by earlier versions to the
+/// keychain. Retried on the next load when it fails.
+fn migrate_tokens(settings: &Settings) {
+    if settings.secrets.server_tokens != TokenStore::Keychain {
+        return;
+    }
+    let Ok(_lock) = fsutil::lock(&servers_path(settings)) else {
+        return;
+    };
+    let Some((mut servers, true)) = load_saved_servers(settings) else {
+        return;
+    };
+    if stash_tokens(settings, &mut servers, secrets::store_server_token) {
+        write_stashed(settings, servers).ok();
+    }
 }
 
-/// Save servers to cli-servers.json (filtering out localhost which is auto-generated).
-pub fn save_servers(settings: &Settings, servers: &[ServerConfig]) {
-    let base = settings.base_dir();
-    std::fs::create_dir_all(&base).ok();
-    let servers_path = base.join("cli-servers.json");
-    let to_save: Vec<&ServerConfig> = servers.iter().filter(|s| s.id != "localhost").collect();
-    if let Ok(json) = serde_json::to_string_pretty(&to_save) {
-        std::fs::write(servers_path, json).ok();
+/// Write cli-servers.json (filtering out localhost which is auto-generated).
+/// The caller holds its lock.
+fn write_servers(settings: &Settings, servers: &[ServerConfig]) -> Result<()> {
+    let mut to_save: Vec<ServerConfig> = servers
+        .iter()
+        .filter(|s| s.id != "localhost")
+        .cloned()
+        .collect();
+    // Tokens the keychain did not give back are still in it
+    for server in &mut to_save {
+        if server.token_error.is_some() && server.auth_token.is_none() {
+            server.auth_token = Some(KEYCHAIN_REF.to_string());
+        }
     }
+    stash_tokens(settings, &mut to_save, secrets::store_server_token);
+    write_stashed(settings, to_save)
+}
+
+/// Write cli-servers.json once the tokens went to the keychain.
+fn write_stashed(settings: &Settings, mut to_save: Vec<ServerConfig>) -> Result<()> {
+    // Never fall back to writing the credentials in plain text
+    seal_credentials(settings, &mut to_save)?;
+    let json = serde_json::to_string_pretty(&to_save)
+        .map_err(|e| NomadError::Other(e.to_string()))?;
+    fsutil::write_atomic(&servers_path(settings), json)?;
+    Ok(())
+}
+
+/// Replace the whole server list, e.g. to rewrite it with another key.
+pub fn save_servers(settings: &Settings, servers: &[ServerConfig]) -> Result<()> {
+    std::fs::create_dir_all(settings.base_dir())?;
+    let _lock = fsutil::lock(&servers_path(settings))?;
+    write_servers(settings, servers)
+}
+
+/// Add `server` (or replace the one with its id) to the list as saved now,
+/// so that servers added by another TUI or CLI since this one loaded the
+/// list are kept. Returns the merged list, as [`load_servers`] would.
+pub fn add_server(settings: &Settings, server: ServerConfig) -> Result<Vec<ServerConfig>> {
+    std::fs::create_dir_all(settings.base_dir())?;
+    {
+        let _lock = fsutil::lock(&servers_path(settings))?;
+        let (mut servers, _) = load_saved_servers(settings).unwrap_or_default();
+        match servers.iter_mut().find(|s| s.id == server.id) {
+            Some(existing) => *existing = server,
+            None => servers.push(server),
+        }
+        write_servers(settings, &servers)?;
+    }
+    Ok(load_servers(settings))
 }
 
 #[cfg(test)]
@@ -124,14 +341,16 @@ mod tests {
             last_repo: Some("/tmp/repo".to_string()),
             last_feature: Some("feat".to_string()),
             last_attached: Some(12345),
+            dismissed_tips: vec!["localhost:no-secret".to_string()],
         };
 
-        save_state(&settings, &state);
+        save_state(&settings, &state).unwrap();
         let loaded = load_state(&settings);
 
         assert_eq!(loaded.last_server.as_deref(), Some("localhost"));
         assert_eq!(loaded.last_repo.as_deref(), Some("/tmp/repo"));
         assert_eq!(loaded.last_feature.as_deref(), Some("feat"));
+        assert_eq!(loaded.dismissed_tips, ["localhost:no-secret"]);
     }
 
     #[test]
@@ -165,4 +384,179 @@ mod tests {
         let loaded = load_state(&settings);
         assert!(loaded.last_server.is_none()); // Fallback to default
     }
+
+    #[test]
+    fn test_truncated_servers_recovered_from_backup() {
+        let tmp = TempDir::new().unwrap();
+        let settings = Settings {
+            paths: nomadflow_core::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        let server = |id: &str| ServerConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            api_url: Some(format!("https://{id}.example.com")),
+            ttyd_url: None,
+            auth_token: None,
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
+        };
+        save_servers(&settings, &[server("vps")]).unwrap();
+        save_servers(&settings, &[server("vps"), server("nas")]).unwrap();
+
+        // Power cut while an older version was writing the file
+        std::fs::write(tmp.path().join("cli-servers.json"), "[{\"id\": \"v").unwrap();
+
+        let ids: Vec<String> = load_servers(&settings).into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, ["localhost", "vps"]);
+    }
+
+    #[test]
+    fn test_add_server_keeps_servers_added_elsewhere() {
+        let tmp = TempDir::new().unwrap();
+        let settings = Settings {
+            paths: nomadflow_core::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        let server = |id: &str, name: &str| ServerConfig {
+            id: id.to_string(),
+            name: name.to_string(),
+            api_url: Some(format!("https://{id}.example.com")),
+            ttyd_url: None,
+            auth_token: None,
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
+        };
+        // Both TUIs loaded the list before either added a server
+        add_server(&settings, server("vps", "vps")).unwrap();
+        let servers = add_server(&settings, server("nas", "nas")).unwrap();
+        let ids: Vec<&str> = servers.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["localhost", "vps", "nas"]);
+
+        let servers = add_server(&settings, server("vps", "renamed")).unwrap();
+        assert_eq!(servers.len(), 3);
+        assert_eq!(servers[1].name, "renamed");
+    }
+
+    #[test]
+    fn test_server_credentials_encrypted() {
+        use nomadflow_core::secrets::{KeySource, PASSPHRASE_ENV};
+
+        let tmp = TempDir::new().unwrap();
+        let mut settings = Settings {
+            paths: nomadflow_core::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        settings.set_key_source(KeySource::Passphrase).unwrap();
+        settings.secrets.server_tokens = TokenStore::File;
+        std::env::set_var(PASSPHRASE_ENV, "correct horse");
+
+        let server = ServerConfig {
+            id: "vps".to_string(),
+            name: "vps".to_string(),
+            api_url: Some("https://vps.example.com".to_string()),
+            ttyd_url: None,
+            auth_token: Some("s3cret".to_string()),
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
+        };
+        // Saved by a TUI whose keychain was available
+        let nas = ServerConfig {
+            id: "nas".to_string(),
+            auth_token: Some(KEYCHAIN_REF.to_string()),
+            ..server.clone()
+        };
+        save_servers(&settings, &[server, nas]).unwrap();
+
+        let raw = std::fs::read_to_string(tmp.path().join("cli-servers.json")).unwrap();
+        assert!(!raw.contains("s3cret"));
+        assert!(raw.contains("\"authToken\": \"keychain:\""));
+        let servers = load_servers(&settings);
+        let vps = servers.iter().find(|s| s.id == "vps").unwrap();
+        assert_eq!(vps.auth_token.as_deref(), Some("s3cret"));
+        std::env::remove_var(PASSPHRASE_ENV);
+    }
+
+    fn keychain_server(id: &str, token: Option<&str>) -> ServerConfig {
+        ServerConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            api_url: Some(format!("https://{id}.example.com")),
+            ttyd_url: None,
+            auth_token: token.map(str::to_string),
+            device_token: None,
+            cert: Default::default(),
+            token_error: None,
+        }
+    }
+
+    #[test]
+    fn test_tokens_round_trip_through_keychain() {
+        use std::cell::RefCell;
+        use std::collections::HashMap;
+
+        let mut settings = Settings::default();
+        settings.secrets.server_tokens = TokenStore::Keychain;
+        let keychain = RefCell::new(HashMap::new());
+        let mut servers = vec![
+            keychain_server("vps", Some("s3cret")),
+            keychain_server("open", None),
+        ];
+
+        let moved = stash_tokens(&settings, &mut servers, |id, token| {
+            keychain
+                .borrow_mut()
+                .insert(id.to_string(), token.to_string());
+            Ok(())
+        });
+        assert!(moved);
+        assert_eq!(servers[0].auth_token.as_deref(), Some(KEYCHAIN_REF));
+        assert!(servers[1].auth_token.is_none());
+        assert_eq!(keychain.borrow().len(), 1);
+
+        fetch_tokens(&mut servers, |id| {
+            keychain
+                .borrow()
+                .get(id)
+                .cloned()
+                .ok_or_else(|| NomadError::Other("no entry".to_string()))
+        });
+        assert_eq!(servers[0].auth_token.as_deref(), Some("s3cret"));
+        assert!(servers[0].token_error.is_none());
+    }
+
+    #[test]
+    fn test_unreadable_keychain_never_sends_placeholder() {
+        let tmp = TempDir::new().unwrap();
+        let mut settings = Settings {
+            paths: nomadflow_core::config::PathsConfig {
+                base_dir: tmp.path().to_string_lossy().to_string(),
+            },
+            ..Default::default()
+        };
+        settings.secrets.server_tokens = TokenStore::File;
+        let mut servers = vec![keychain_server("nas", Some(KEYCHAIN_REF))];
+
+        fetch_tokens(&mut servers, |_| {
+            Err(NomadError::Other("keychain locked".to_string()))
+        });
+        assert!(servers[0].auth_token.is_none());
+        let error = servers[0].token_error.as_deref().unwrap();
+        assert!(error.contains("keychain locked"));
+
+        // Saving the list keeps the reference to the token still in the keychain
+        save_servers(&settings, &servers).unwrap();
+        let raw = std::fs::read_to_string(tmp.path().join("cli-servers.json")).unwrap();
+        assert!(raw.contains("\"authToken\": \"keychain:\""));
+        assert!(!raw.contains("keychain locked"));
+    }
 }
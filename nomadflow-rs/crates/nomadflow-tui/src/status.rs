@@ -0,0 +1,236 @@
+//! `nomadflow --status`: the daemon, its API and the tmux sessions of this
+//! machine, printed for people or as JSON, with an exit code for monitoring
+//! scripts.
+
+use serde::Serialize;
+
+use nomadflow_core::config::Settings;
+use nomadflow_core::i18n::{t, tf};
+
+use crate::glyphs::{self, Status};
+use crate::{api_client, state, tmux_local};
+
+/// Exit code when everything runs.
+pub const EXIT_RUNNING: i32 = 0;
+/// Exit code when the daemon is not running.
+pub const EXIT_STOPPED: i32 = 3;
+/// Exit code when the daemon runs but does not work.
+pub const EXIT_DEGRADED: i32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Health {
+    Running,
+    Stopped,
+    /// The daemon runs, but its API does not answer or it has no session.
+    Degraded,
+}
+
+impl Health {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Health::Running => EXIT_RUNNING,
+            Health::Stopped => EXIT_STOPPED,
+            Health::Degraded => EXIT_DEGRADED,
+        }
+    }
+}
+
+/// The daemon, as its PID file tells.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DaemonStatus {
+    pub pid: Option<u32>,
+    pub running: bool,
+    /// The PID file was left by a daemon that is gone.
+    pub stale_pid_file: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowStatus {
+    pub index: u32,
+    pub name: String,
+    pub active: bool,
+    pub has_unread: bool,
+    /// Program in the window's pane.
+    pub command: Option<String>,
+    /// Back at the shell prompt.
+    pub idle: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatus {
+    pub name: String,
+    pub windows: Vec<WindowStatus>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusReport {
+    pub status: Health,
+    pub daemon: DaemonStatus,
+    /// Round trip to the local API, `None` when it did not answer.
+    pub api_latency_ms: Option<u64>,
+    pub sessions: Vec<SessionStatus>,
+}
+
+fn health(daemon: &DaemonStatus, api_up: bool, has_session: bool) -> Health {
+    if !daemon.running {
+        Health::Stopped
+    } else if !api_up || !has_session {
+        Health::Degraded
+    } else {
+        Health::Running
+    }
+}
+
+fn session_status(name: String) -> SessionStatus {
+    let windows = tmux_local::list_windows(&name)
+        .into_iter()
+        .map(|w| {
+            let command = tmux_local::get_pane_command(&name, &w.name);
+            WindowStatus {
+                idle: tmux_local::is_shell_idle_str(command.as_deref()),
+                index: w.index,
+                name: w.name,
+                active: w.active,
+                has_unread: w.activity,
+                command,
+            }
+        })
+        .collect();
+    SessionStatus { name, windows }
+}
+
+/// Status of this machine, with the tmux sessions narrowed down to `only`.
+pub async fn collect(
+    settings: &Settings,
+    daemon: DaemonStatus,
+    only: Option<&str>,
+) -> StatusReport {
+    let sessions: Vec<SessionStatus> = tmux_local::managed_sessions(&settings.tmux.session, only)
+        .into_iter()
+        .map(session_status)
+        .collect();
+
+    // Without a daemon there is no API to wait for
+    let localhost = state::load_servers(settings)
+        .into_iter()
+        .find(|s| s.id == "localhost");
+    let latency = match localhost {
+        Some(server) if daemon.running => api_client::check_health(&server).await,
+        _ => None,
+    };
+
+    StatusReport {
+        status: health(&daemon, latency.is_some(), !sessions.is_empty()),
+        daemon,
+        api_latency_ms: latency.map(|l| l.as_millis() as u64),
+        sessions,
+    }
+}
+
+impl StatusReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Print the daemon state to stderr and the sessions to stdout.
+    pub fn print(&self, settings: &Settings, only: Option<&str>) {
+        match self.daemon.pid {
+            Some(pid) if self.daemon.running => {
+                eprintln!("{}", tf("cli.status_running", &[("pid", &pid)]));
+                if self.api_latency_ms.is_none() {
+                    eprintln!(
+                        "{}",
+                        glyphs::mark(Status::Warning, t("cli.status_api_down"))
+                    );
+                }
+            }
+            _ if self.daemon.stale_pid_file => eprintln!("{}", t("cli.status_stale")),
+            _ => eprintln!("{}", t("cli.status_stopped")),
+        }
+
+        if self.sessions.is_empty() {
+            let session = only.unwrap_or(&settings.tmux.session);
+            println!("{}", tf("status.session", &[("session", &session)]));
+            println!("{}", t("status.no_session"));
+            return;
+        }
+        for (i, session) in self.sessions.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            print_session(session);
+        }
+    }
+}
+
+fn print_session(session: &SessionStatus) {
+    println!("{}", tf("status.session", &[("session", &session.name)]));
+    println!(
+        "{}",
+        tf("status.windows", &[("count", &session.windows.len())])
+    );
+    println!();
+
+    for w in &session.windows {
+        let status = match &w.command {
+            Some(_) if w.idle => glyphs::mark(Status::Idle, t("features.idle")),
+            Some(c) => glyphs::mark(Status::Busy, c),
+            None => String::new(),
+        };
+        let marker = if w.active { ">" } else { " " };
+        let unread = if w.has_unread {
+            format!(
+                "  {}",
+                glyphs::mark(Status::Unread, t("features.new_output"))
+            )
+        } else {
+            String::new()
+        };
+        println!("{marker} {}: {}  {status}{unread}", w.index, w.name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health() {
+        let running = DaemonStatus {
+            pid: Some(42),
+            running: true,
+            stale_pid_file: false,
+        };
+        assert_eq!(health(&running, true, true), Health::Running);
+        assert_eq!(health(&running, false, true), Health::Degraded);
+        assert_eq!(health(&running, true, false).exit_code(), EXIT_DEGRADED);
+        let stale = DaemonStatus {
+            stale_pid_file: true,
+            ..Default::default()
+        };
+        assert_eq!(health(&stale, false, true).exit_code(), EXIT_STOPPED);
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let report = StatusReport {
+            status: Health::Degraded,
+            daemon: DaemonStatus::default(),
+            api_latency_ms: None,
+            sessions: vec![SessionStatus {
+                name: "nomadflow".to_string(),
+                windows: Vec::new(),
+            }],
+        };
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["status"], "degraded");
+        assert_eq!(json["daemon"]["stalePidFile"], false);
+        assert!(json["apiLatencyMs"].is_null());
+        assert_eq!(json["sessions"][0]["name"], "nomadflow");
+    }
+}
@@ -6,6 +6,8 @@ pub struct LocalTmuxWindow {
     pub index: u32,
     pub name: String,
     pub active: bool,
+    /// Output was produced since the window was last viewed.
+    pub activity: bool,
 }
 
 fn exec(cmd: &str) -> Option<String> {
@@ -30,24 +32,53 @@ pub fn session_exists(session: &str) -> bool {
     exec(&format!("tmux has-session -t \"{session}\" 2>/dev/null")).is_some()
 }
 
+/// Names of the running tmux sessions.
+pub fn list_sessions() -> Vec<String> {
+    exec("tmux list-sessions -F \"#{session_name}\" 2>/dev/null")
+        .map(|o| o.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Whether `name` is a session NomadFlow runs next to the configured `base`
+/// one, which it is itself, or `<base>-…` (shared windows).
+pub fn is_managed_session(name: &str, base: &str) -> bool {
+    name == base
+        || name
+            .strip_prefix(base)
+            .is_some_and(|rest| rest.starts_with('-'))
+}
+
+/// The running sessions of NomadFlow, `base` first, or only `only` when given.
+pub fn managed_sessions(base: &str, only: Option<&str>) -> Vec<String> {
+    let mut sessions: Vec<String> = list_sessions()
+        .into_iter()
+        .filter(|s| is_managed_session(s, base))
+        .filter(|s| only.is_none_or(|only| s == only))
+        .collect();
+    sessions.sort_by_key(|s| (s != base, s.clone()));
+    sessions
+}
+
 pub fn list_windows(session: &str) -> Vec<LocalTmuxWindow> {
     let output = match exec(&format!(
-        "tmux list-windows -t \"{session}\" -F \"#{{window_index}}:#{{window_name}}:#{{window_active}}\""
+        "tmux list-windows -t \"{session}\" -F \"#{{window_index}}:#{{window_active}}:#{{window_activity_flag}}:#{{window_name}}\""
     )) {
         Some(o) => o,
         None => return Vec::new(),
     };
 
+    // Name goes last since window names contain ':' (repo:feature)
     output
         .lines()
         .filter(|l| !l.is_empty())
         .filter_map(|line| {
-            let parts: Vec<&str> = line.splitn(3, ':').collect();
-            if parts.len() >= 3 {
+            let parts: Vec<&str> = line.splitn(4, ':').collect();
+            if parts.len() >= 4 {
                 Some(LocalTmuxWindow {
                     index: parts[0].parse().unwrap_or(0),
-                    name: parts[1].to_string(),
-                    active: parts[2] == "1",
+                    active: parts[1] == "1",
+                    activity: parts[2] == "1",
+                    name: parts[3].to_string(),
                 })
             } else {
                 None
@@ -79,6 +110,36 @@ pub fn is_shell_idle_str(command: Option<&str>) -> bool {
     }
 }
 
+/// Whether this process runs in a tmux client, where `attach-session` would
+/// nest a second client and fail.
+pub fn inside_tmux() -> bool {
+    std::env::var_os("TMUX").is_some_and(|v| !v.is_empty())
+}
+
+/// The window this process runs in, as `session:window`, when it runs in
+/// `session`.
+pub fn current_window(session: &str) -> Option<String> {
+    if !inside_tmux() {
+        return None;
+    }
+    let current = exec("tmux display-message -p '#{session_name}:#{window_name}'")?;
+    current
+        .strip_prefix(session)
+        .is_some_and(|rest| rest.starts_with(':'))
+        .then_some(current)
+}
+
+/// tmux arguments showing `target`: the current client switches to it from
+/// inside tmux, a new client attaches to it otherwise.
+pub fn attach_args(target: &str, inside: bool) -> [&str; 3] {
+    let command = if inside {
+        "switch-client"
+    } else {
+        "attach-session"
+    };
+    [command, "-t", target]
+}
+
 pub fn attach_session(session: &str) {
     attach_session_target(session, None);
 }
@@ -89,9 +150,32 @@ pub fn attach_session_target(session: &str, window: Option<&str>) {
         None => session.to_string(),
     };
     let _ = Command::new("tmux")
-        .args(["attach-session", "-t", &target])
+        .args(attach_args(&target, inside_tmux()))
         .stdin(std::process::Stdio::inherit())
         .stdout(std::process::Stdio::inherit())
         .stderr(std::process::Stdio::inherit())
         .status();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_managed_session() {
+        assert!(is_managed_session("nomadflow", "nomadflow"));
+        assert!(is_managed_session("nomadflow-share-ab12", "nomadflow"));
+        assert!(!is_managed_session("nomadflowx", "nomadflow"));
+        assert!(!is_managed_session("work", "nomadflow"));
+    }
+
+    #[test]
+    fn test_attach_args() {
+        assert_eq!(
+            attach_args("nomadflow:api:login", false),
+            ["attach-session", "-t", "nomadflow:api:login"]
+        );
+        // A nested attach fails: the current client switches instead
+        assert_eq!(attach_args("nomadflow", true)[0], "switch-client");
+    }
+}
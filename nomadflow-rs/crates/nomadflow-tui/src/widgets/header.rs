@@ -1,20 +1,61 @@
+use std::time::Duration;
+
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
 };
 
-pub fn render(frame: &mut Frame, area: Rect) {
+use nomadflow_core::i18n::{t, tf};
+
+use super::latency;
+use crate::glyphs::{self, Status};
+
+/// `latency` is the last health check of the selected server: `None` when no
+/// server is selected yet, `Some(None)` when it did not answer. `offline`
+/// replaces it once operations fall back to running in-process. `window` is
+/// the managed tmux window the TUI runs in, if any.
+pub fn render(
+    frame: &mut Frame,
+    area: Rect,
+    latency: Option<Option<Duration>>,
+    offline: bool,
+    window: Option<&str>,
+) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan))
         .title_alignment(Alignment::Left);
 
-    let header = Paragraph::new(Line::from(vec![
+    let mut spans = vec![
         Span::styled("NomadFlow", Style::default().fg(Color::Cyan).bold()),
         Span::raw("  "),
         Span::styled("v0.1", Style::default().fg(Color::DarkGray)),
-    ]))
-    .block(block);
+    ];
+    if offline {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            glyphs::mark(Status::Warning, t("offline.badge")),
+            Style::default().fg(Color::Yellow),
+        ));
+    } else if let Some(rtt) = latency {
+        spans.push(Span::raw("  "));
+        spans.push(latency::span(rtt));
+        if rtt.is_some_and(latency::is_unusable) {
+            spans.push(Span::raw("  "));
+            spans.push(Span::styled(
+                t("latency.warning"),
+                Style::default().fg(Color::Red),
+            ));
+        }
+    }
+    if let Some(window) = window {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            tf("header.inside", &[("window", &window)]),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    let header = Paragraph::new(Line::from(spans)).block(block);
 
     frame.render_widget(header, area);
 }
@@ -0,0 +1,62 @@
+use ratatui::{prelude::*, widgets::Paragraph};
+
+use nomadflow_core::i18n::{t, tf};
+use nomadflow_core::models::{ActivityAction, ActivityEntry};
+
+/// One-line summary of the last server operation, with an undo hint when
+/// the server can still revert it.
+pub fn render(frame: &mut Frame, area: Rect, entry: &ActivityEntry, now: u64) {
+    let mut spans = vec![
+        Span::styled(t("history.last"), Style::default().fg(Color::DarkGray)),
+        Span::raw(describe(entry)),
+        Span::styled(
+            format!(" · {}", ago(now.saturating_sub(entry.timestamp))),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ];
+    if entry.undoable {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(t("history.undo"), Style::default().fg(Color::Yellow)));
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn describe(entry: &ActivityEntry) -> String {
+    let key = match entry.action {
+        ActivityAction::CreateFeature => "history.created",
+        ActivityAction::DeleteFeature => "history.deleted",
+        ActivityAction::PruneFeature => "history.pruned",
+        ActivityAction::SwitchFeature => "history.switched",
+        ActivityAction::AttachBranch => "history.attached",
+        ActivityAction::Undo => "history.undid",
+        ActivityAction::BanIp => "history.banned",
+        ActivityAction::UnbanIp => "history.unbanned",
+        ActivityAction::RunTask => "history.ran_task",
+        ActivityAction::TaskFailed => "history.task_failed",
+        ActivityAction::StopProcess => "history.stopped_process",
+    };
+    tf(key, &[("name", &entry.feature_name)])
+}
+
+fn ago(secs: u64) -> String {
+    match secs {
+        0..=59 => t("history.just_now").to_string(),
+        60..=3599 => tf("history.minutes", &[("n", &(secs / 60))]),
+        3600..=86_399 => tf("history.hours", &[("n", &(secs / 3600))]),
+        _ => tf("history.days", &[("n", &(secs / 86_400))]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ago() {
+        assert_eq!(ago(5), "just now");
+        assert_eq!(ago(125), "2m ago");
+        assert_eq!(ago(7200), "2h ago");
+        assert_eq!(ago(3 * 86_400), "3d ago");
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use ratatui::prelude::*;
+
+use nomadflow_core::i18n::{t, tf};
+
+use crate::glyphs::{self, Status};
+
+/// Round trips above this feel sluggish when typing in the terminal.
+const SLOW: Duration = Duration::from_millis(150);
+/// Round trips above this make the terminal unusable for interactive work.
+pub const UNUSABLE: Duration = Duration::from_millis(400);
+
+/// Whether the terminal will lag too much over this connection.
+pub fn is_unusable(latency: Duration) -> bool {
+    latency >= UNUSABLE
+}
+
+fn color(latency: Duration) -> Color {
+    if is_unusable(latency) {
+        Color::Red
+    } else if latency >= SLOW {
+        Color::Yellow
+    } else {
+        Color::Green
+    }
+}
+
+/// Colored round-trip time from the last health check, or a cross when the
+/// server did not answer.
+pub fn span(latency: Option<Duration>) -> Span<'static> {
+    match latency {
+        Some(latency) => Span::styled(
+            tf("latency.ms", &[("ms", &latency.as_millis())]),
+            Style::default().fg(color(latency)),
+        ),
+        None => Span::styled(
+            glyphs::mark(Status::Error, t("latency.unreachable")),
+            Style::default().fg(Color::Red),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_levels() {
+        assert_eq!(color(Duration::from_millis(40)), Color::Green);
+        assert_eq!(color(Duration::from_millis(200)), Color::Yellow);
+        assert_eq!(color(Duration::from_millis(400)), Color::Red);
+        assert!(!is_unusable(Duration::from_millis(399)));
+        assert!(is_unusable(Duration::from_millis(900)));
+    }
+
+    #[test]
+    fn test_span() {
+        assert_eq!(span(Some(Duration::from_millis(42))).content, "42 ms");
+        assert_eq!(span(None).style.fg, Some(Color::Red));
+    }
+}
@@ -1,2 +1,7 @@
 pub mod breadcrumb;
 pub mod header;
+pub mod history_bar;
+pub mod latency;
+pub mod spinner;
+pub mod text;
+pub mod tips;
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use ratatui::prelude::*;
+
+use nomadflow_core::i18n::tf;
+
+use crate::glyphs;
+
+/// `text` behind an animated spinner, followed by the time spent waiting
+/// once it exceeds a second so that slow calls don't look frozen.
+pub fn line(text: &str, tick: usize, elapsed: Duration) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled(glyphs::spinner(tick), Style::default().fg(Color::Cyan)),
+        Span::raw(format!(" {text}")),
+    ];
+    if elapsed >= Duration::from_secs(1) {
+        spans.push(Span::styled(
+            format!(
+                "  {}",
+                tf("spinner.elapsed", &[("secs", &elapsed.as_secs())])
+            ),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        let first = line("Loading", 0, Duration::ZERO);
+        assert_eq!(first.spans.len(), 2);
+        assert_ne!(
+            first.spans[0].content,
+            line("Loading", 1, Duration::ZERO).spans[0].content
+        );
+        let slow = line("Loading", 0, Duration::from_millis(3500));
+        assert_eq!(
+            slow.spans[2].content,
+            format!("  {}", tf("spinner.elapsed", &[("secs", &3)]))
+        );
+    }
+}
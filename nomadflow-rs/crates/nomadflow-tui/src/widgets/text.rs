@@ -0,0 +1,49 @@
+use ratatui::prelude::*;
+
+use nomadflow_core::i18n::t;
+
+/// Build a line from a translated message, substituting each `{name}`
+/// placeholder with a styled span so highlights survive word reordering.
+pub fn styled<'a>(key: &'static str, args: Vec<(&str, Span<'a>)>) -> Line<'a> {
+    let mut rest = t(key);
+    let mut spans = Vec::new();
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + len];
+        match args.iter().find(|(n, _)| *n == name) {
+            Some((_, span)) => {
+                if start > 0 {
+                    spans.push(Span::raw(&rest[..start]));
+                }
+                spans.push(span.clone());
+            }
+            None => spans.push(Span::raw(&rest[..start + len + 1])),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    if !rest.is_empty() {
+        spans.push(Span::raw(rest));
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styled_substitutes_spans() {
+        let line = styled(
+            "create.confirm",
+            vec![
+                ("name", Span::styled("login", Style::default().bold())),
+                ("repo", Span::raw("app")),
+            ],
+        );
+        let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "Create feature login in app? (y/n)");
+        assert_eq!(line.spans[1].style, Style::default().bold());
+    }
+}
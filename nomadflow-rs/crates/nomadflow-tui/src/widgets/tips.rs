@@ -0,0 +1,129 @@
+use ratatui::{prelude::*, widgets::Paragraph};
+
+use nomadflow_core::i18n::{t, tf};
+use nomadflow_core::models::{HealthResponse, ReconcileIssue, ReconcileIssueKind, TerminalStatus};
+
+use crate::glyphs::{self, Status};
+
+/// Start of the key of [`Tip::MergedFeatures`], followed by the count.
+pub const MERGED_FEATURES_KEY: &str = "merged-features-";
+
+/// Something about the selected server worth acting on, shown above the
+/// repos until dismissed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tip {
+    /// ttyd is not installed, so the terminal cannot be opened from a phone.
+    NoTerminal,
+    /// No `auth.secret`: anyone reaching the server can use it.
+    NoSecret,
+    /// Features whose pull request was merged, by count.
+    MergedFeatures(usize),
+}
+
+impl Tip {
+    /// What the tip is remembered as once dismissed. Merged features come
+    /// back when there are more or fewer of them.
+    pub fn key(self) -> String {
+        match self {
+            Self::NoTerminal => "no-terminal".to_string(),
+            Self::NoSecret => "no-secret".to_string(),
+            Self::MergedFeatures(count) => format!("{MERGED_FEATURES_KEY}{count}"),
+        }
+    }
+
+    fn text(self) -> String {
+        match self {
+            Self::NoTerminal => t("tips.no_terminal").to_string(),
+            Self::NoSecret => t("tips.no_secret").to_string(),
+            Self::MergedFeatures(count) => tf("tips.merged_features", &[("count", &count)]),
+        }
+    }
+
+    fn look(self) -> (Status, Color) {
+        match self {
+            Self::NoSecret => (Status::Error, Color::Red),
+            Self::NoTerminal | Self::MergedFeatures(_) => (Status::Warning, Color::Yellow),
+        }
+    }
+}
+
+/// The tips for a server that last reported `health` and `issues`, most
+/// pressing first.
+pub fn tips(health: Option<&HealthResponse>, issues: &[ReconcileIssue]) -> Vec<Tip> {
+    let mut tips = Vec::new();
+    if let Some(health) = health {
+        if health.auth_required == Some(false) {
+            tips.push(Tip::NoSecret);
+        }
+        if health.terminal == Some(TerminalStatus::Unavailable) {
+            tips.push(Tip::NoTerminal);
+        }
+    }
+    let merged = issues
+        .iter()
+        .filter(|i| i.kind == ReconcileIssueKind::GoneUpstream)
+        .count();
+    if merged > 0 {
+        tips.push(Tip::MergedFeatures(merged));
+    }
+    tips
+}
+
+/// One line per tip.
+pub fn render(frame: &mut Frame, area: Rect, tips: &[Tip]) {
+    let lines: Vec<Line> = tips
+        .iter()
+        .map(|tip| {
+            let (status, color) = tip.look();
+            Line::styled(
+                glyphs::mark(status, &tip.text()),
+                Style::default().fg(color),
+            )
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health(terminal: TerminalStatus, auth_required: Option<bool>) -> HealthResponse {
+        HealthResponse {
+            status: "ok".to_string(),
+            tmux_session: "nomadflow".to_string(),
+            api_port: 8080,
+            server_key: None,
+            terminal: Some(terminal),
+            auth_required,
+        }
+    }
+
+    fn issue(kind: ReconcileIssueKind) -> ReconcileIssue {
+        ReconcileIssue {
+            kind,
+            repo_path: "/repos/app".to_string(),
+            target: "/worktrees/app/login".to_string(),
+            fixed: false,
+        }
+    }
+
+    #[test]
+    fn test_tips() {
+        let issues = [
+            issue(ReconcileIssueKind::GoneUpstream),
+            issue(ReconcileIssueKind::MissingWindow),
+            issue(ReconcileIssueKind::GoneUpstream),
+        ];
+        assert_eq!(
+            tips(
+                Some(&health(TerminalStatus::Unavailable, Some(false))),
+                &issues
+            ),
+            [Tip::NoSecret, Tip::NoTerminal, Tip::MergedFeatures(2)]
+        );
+        // Restarting ttyd comes back by itself; older servers do not say
+        assert!(tips(Some(&health(TerminalStatus::Restarting, None)), &[]).is_empty());
+        assert!(tips(None, &issues[1..2]).is_empty());
+    }
+}
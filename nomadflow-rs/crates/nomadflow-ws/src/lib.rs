@@ -1,35 +1,72 @@
+mod throttle;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use axum::extract::ws::{Message, WebSocket};
-use futures_util::{SinkExt, StreamExt};
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
 use tokio::net::TcpStream;
 use tokio_tungstenite::{tungstenite, MaybeTlsStream, WebSocketStream};
 use tracing::info;
 
+pub use throttle::Throttle;
+
+/// Payload bytes relayed by a bridge, readable while it runs.
+#[derive(Debug, Default)]
+pub struct Traffic {
+    /// From upstream to the client.
+    pub sent: AtomicU64,
+    /// From the client to upstream.
+    pub received: AtomicU64,
+}
+
+impl Traffic {
+    fn add(counter: &AtomicU64, bytes: usize) {
+        counter.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
 /// Bridge bidirectional messages between an axum WebSocket and a tungstenite WebSocket.
 pub async fn bridge(
     client: WebSocket,
     upstream: WebSocketStream<MaybeTlsStream<TcpStream>>,
 ) {
+    let (upstream_tx, upstream_rx) = upstream.split();
+    bridge_counted(client, upstream_tx, upstream_rx, &Traffic::default(), None).await;
+}
+
+/// [`bridge`] with the upstream given as its two halves, so that it needs
+/// not be a WebSocket, only speak its messages. Adds the bytes of every
+/// message to `traffic` and paces ttyd output by `throttle`.
+pub async fn bridge_counted<S, R>(
+    client: WebSocket,
+    mut upstream_tx: S,
+    mut upstream_rx: R,
+    traffic: &Traffic,
+    throttle: Option<Throttle>,
+) where
+    S: Sink<tungstenite::Message> + Unpin,
+    R: Stream<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin,
+{
     let (mut client_tx, mut client_rx) = client.split();
-    let (mut upstream_tx, mut upstream_rx) = upstream.split();
 
     let client_to_upstream = async {
         while let Some(msg) = client_rx.next().await {
             match msg {
                 Ok(Message::Text(text)) => {
-                    if upstream_tx
+                    Traffic::add(&traffic.received, text.len());
+                    let sent = upstream_tx
                         .send(tungstenite::Message::Text(text.to_string().into()))
-                        .await
-                        .is_err()
-                    {
+                        .await;
+                    if sent.is_err() {
                         break;
                     }
                 }
                 Ok(Message::Binary(data)) => {
-                    if upstream_tx
+                    Traffic::add(&traffic.received, data.len());
+                    let sent = upstream_tx
                         .send(tungstenite::Message::Binary(data.to_vec().into()))
-                        .await
-                        .is_err()
-                    {
+                        .await;
+                    if sent.is_err() {
                         break;
                     }
                 }
@@ -40,23 +77,26 @@ pub async fn bridge(
     };
 
     let upstream_to_client = async {
+        if let Some(throttle) = throttle {
+            return crate::throttle::forward(&mut upstream_rx, &mut client_tx, traffic, throttle).await;
+        }
         while let Some(msg) = upstream_rx.next().await {
             match msg {
                 Ok(tungstenite::Message::Text(text)) => {
-                    if client_tx
+                    Traffic::add(&traffic.sent, text.len());
+                    let sent = client_tx
                         .send(Message::Text(text.to_string().into()))
-                        .await
-                        .is_err()
-                    {
+                        .await;
+                    if sent.is_err() {
                         break;
                     }
                 }
                 Ok(tungstenite::Message::Binary(data)) => {
-                    if client_tx
+                    Traffic::add(&traffic.sent, data.len());
+                    let sent = client_tx
                         .send(Message::Binary(data.to_vec().into()))
-                        .await
-                        .is_err()
-                    {
+                        .await;
+                    if sent.is_err() {
                         break;
                     }
                 }
@@ -0,0 +1,246 @@
+//! Output pacing for slow clients. ttyd output frames are merged while the
+//! client is busy and sent at most once per interval. When the output
+//! waiting for the client grows past a limit, what precedes the last
+//! full-screen clear is dropped, since the clear erases it anyway.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::extract::ws::Message;
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite;
+
+use crate::Traffic;
+
+/// Command byte of ttyd output frames.
+const OUTPUT: u8 = b'0';
+/// Erase the whole screen (`ED 2`).
+const CLEAR_SCREEN: &[u8] = b"\x1b[2J";
+
+/// How the bridge paces ttyd output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttle {
+    /// Output is sent at most once per interval. Zero sends it as soon as the
+    /// client took the previous batch.
+    pub interval: Duration,
+    /// Output waiting for the client beyond which redraws are dropped.
+    /// `0` never drops anything.
+    pub backlog_bytes: usize,
+}
+
+enum Frame {
+    /// Merged output, without the command byte.
+    Output(Vec<u8>),
+    /// Anything else ttyd sends, kept in order.
+    Other(Message),
+}
+
+#[derive(Default)]
+struct Pending {
+    frames: VecDeque<Frame>,
+    output_bytes: usize,
+}
+
+fn find_last(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+impl Pending {
+    fn push_output(&mut self, payload: &[u8], backlog_bytes: usize) {
+        self.output_bytes += payload.len();
+        match self.frames.back_mut() {
+            Some(Frame::Output(buf)) => buf.extend_from_slice(payload),
+            _ => self.frames.push_back(Frame::Output(payload.to_vec())),
+        }
+        if backlog_bytes > 0 && self.output_bytes > backlog_bytes {
+            self.drop_stale();
+        }
+    }
+
+    fn push_other(&mut self, message: Message) {
+        self.frames.push_back(Frame::Other(message));
+    }
+
+    /// Drop the output before the last full-screen clear, keeping other frames.
+    fn drop_stale(&mut self) {
+        let last_clear = self
+            .frames
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, frame)| match frame {
+                Frame::Output(buf) => find_last(buf, CLEAR_SCREEN).map(|at| (i, at)),
+                Frame::Other(_) => None,
+            });
+        let Some((index, at)) = last_clear else {
+            return;
+        };
+        let frames = std::mem::take(&mut self.frames);
+        for (i, frame) in frames.into_iter().enumerate() {
+            match frame {
+                Frame::Output(_) if i < index => {}
+                Frame::Output(mut buf) if i == index => {
+                    buf.drain(..at);
+                    self.frames.push_back(Frame::Output(buf));
+                }
+                frame => self.frames.push_back(frame),
+            }
+        }
+        self.output_bytes = self
+            .frames
+            .iter()
+            .map(|frame| match frame {
+                Frame::Output(buf) => buf.len(),
+                Frame::Other(_) => 0,
+            })
+            .sum();
+    }
+
+    fn take(&mut self) -> VecDeque<Frame> {
+        self.output_bytes = 0;
+        std::mem::take(&mut self.frames)
+    }
+}
+
+async fn send_all<C>(client: &mut C, frames: VecDeque<Frame>, traffic: &Traffic) -> bool
+where
+    C: Sink<Message> + Unpin,
+{
+    for frame in frames {
+        let message = match frame {
+            Frame::Output(payload) => {
+                let mut data = Vec::with_capacity(payload.len() + 1);
+                data.push(OUTPUT);
+                data.extend_from_slice(&payload);
+                Message::Binary(data.into())
+            }
+            Frame::Other(message) => message,
+        };
+        let len = match &message {
+            Message::Binary(data) => data.len(),
+            Message::Text(text) => text.len(),
+            _ => 0,
+        };
+        Traffic::add(&traffic.sent, len);
+        if client.send(message).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Forward ttyd messages to the client, paced by `throttle`, until either
+/// side closes.
+pub(crate) async fn forward<U, C>(
+    upstream: &mut U,
+    client: &mut C,
+    traffic: &Traffic,
+    throttle: Throttle,
+) where
+    U: Stream<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin,
+    C: Sink<Message> + Unpin,
+{
+    let pending = Mutex::new(Pending::default());
+    let ready = Notify::new();
+
+    // Keeps reading while a send waits for the client, which is how a
+    // backlog builds up
+    let read = async {
+        while let Some(Ok(msg)) = upstream.next().await {
+            match msg {
+                tungstenite::Message::Binary(data) if data.first() == Some(&OUTPUT) => pending
+                    .lock()
+                    .unwrap()
+                    .push_output(&data[1..], throttle.backlog_bytes),
+                tungstenite::Message::Binary(data) => pending
+                    .lock()
+                    .unwrap()
+                    .push_other(Message::Binary(data.to_vec().into())),
+                tungstenite::Message::Text(text) => pending
+                    .lock()
+                    .unwrap()
+                    .push_other(Message::Text(text.to_string().into())),
+                tungstenite::Message::Close(_) => break,
+                _ => continue,
+            }
+            ready.notify_one();
+        }
+    };
+
+    let write = async {
+        loop {
+            ready.notified().await;
+            if !throttle.interval.is_zero() {
+                tokio::time::sleep(throttle.interval).await;
+            }
+            let frames = pending.lock().unwrap().take();
+            if !send_all(client, frames, traffic).await {
+                return;
+            }
+        }
+    };
+
+    let upstream_closed = tokio::select! {
+        _ = read => true,
+        _ = write => false,
+    };
+    if upstream_closed {
+        let frames = pending.lock().unwrap().take();
+        send_all(client, frames, traffic).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outputs(pending: &Pending) -> Vec<&[u8]> {
+        pending
+            .frames
+            .iter()
+            .filter_map(|frame| match frame {
+                Frame::Output(buf) => Some(buf.as_slice()),
+                Frame::Other(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_output_frames_are_merged() {
+        let mut pending = Pending::default();
+        pending.push_output(b"make: ", 0);
+        pending.push_output(b"building\r\n", 0);
+        pending.push_other(Message::Binary(b"1title".to_vec().into()));
+        pending.push_output(b"done", 0);
+
+        assert_eq!(pending.frames.len(), 3);
+        assert_eq!(outputs(&pending), [&b"make: building\r\n"[..], b"done"]);
+        assert_eq!(pending.take().len(), 3);
+        assert_eq!(pending.output_bytes, 0);
+    }
+
+    #[test]
+    fn test_backlog_drops_output_before_the_last_clear() {
+        let mut pending = Pending::default();
+        pending.push_output(b"\x1b[2Jold screen", 32);
+        pending.push_other(Message::Binary(b"1title".to_vec().into()));
+        pending.push_output(b"more old output", 32);
+        // Under the limit, nothing is dropped
+        assert_eq!(pending.output_bytes, 29);
+
+        pending.push_output(b"\x1b[H\x1b[2Jnew", 32);
+        assert_eq!(outputs(&pending), [&b"\x1b[2Jnew"[..]]);
+        assert_eq!(pending.output_bytes, 7);
+        // The title change is kept
+        assert_eq!(pending.frames.len(), 2);
+    }
+
+    #[test]
+    fn test_backlog_without_clear_keeps_everything() {
+        let mut pending = Pending::default();
+        pending.push_output(&[b'x'; 40], 32);
+        assert_eq!(pending.output_bytes, 40);
+    }
+}
@@ -7,6 +7,10 @@ use color_eyre::{eyre::eyre, Result};
 use tokio_util::sync::CancellationToken;
 
 use nomadflow_core::config::Settings;
+use nomadflow_core::error::NomadError;
+use nomadflow_core::i18n::{self, t, tf, Locale};
+use nomadflow_core::migrations;
+use nomadflow_core::secrets::{KeySource, PASSPHRASE_ENV};
 
 #[derive(Parser)]
 #[command(name = "nomadflow", version, about = "NomadFlow - Git worktree + tmux workflow manager")]
@@ -14,9 +18,22 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// Show tmux status and exit
+    /// Show tmux status and exit (0 running, 3 not running, 4 degraded)
     #[arg(long)]
     status: bool,
+
+    /// Print `--status` as JSON
+    #[arg(long, requires = "status")]
+    json: bool,
+
+    /// Only show or attach to this tmux session (default: every NomadFlow session)
+    #[arg(long, global = true, value_name = "NAME")]
+    session: Option<String>,
+
+    /// Use numbered line prompts instead of the full-screen interface
+    /// (screen readers, dumb terminals, CI logs)
+    #[arg(long, global = true)]
+    plain: bool,
 }
 
 #[derive(Subcommand)]
@@ -29,6 +46,60 @@ enum Commands {
         /// Override the displayed address (IP or domain name) for QR code and URL
         #[arg(long)]
         host: Option<String>,
+        /// Also write the connection QR code to this PNG file
+        #[arg(long, value_name = "PATH")]
+        qr_png: Option<PathBuf>,
+    },
+    /// Write the initial config from flags or stdin prompts (no TUI needed)
+    Init {
+        /// Server password (generated when omitted with --yes)
+        #[arg(long)]
+        secret: Option<String>,
+        /// Enable public tunnel mode
+        #[arg(long)]
+        public: bool,
+        /// Fixed tunnel subdomain (implies --public)
+        #[arg(long)]
+        subdomain: Option<String>,
+        /// Never prompt: use defaults for everything not given as a flag
+        #[arg(long, short = 'y')]
+        yes: bool,
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Manage the TOTP second factor required by the server
+    Totp {
+        #[command(subcommand)]
+        action: TotpAction,
+    },
+    /// Encrypt the secrets stored in the config files
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+    /// Inspect the configuration merged from all its sources
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Test connectivity to the local server, ttyd, the relay and the public URL
+    Nettest,
+    /// Smoke-test the API of a configured server: health, auth, repos, features, terminal
+    Check {
+        /// Server id or name, as in `cli-servers.json`
+        server: String,
+        /// Repo (name or path) where a scratch feature is created and deleted;
+        /// without it, nothing is written on the server
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Remove orphaned worktree directories and expired crash reports, task
+    /// runs and temporary files
+    Gc {
+        /// Only list what would be removed
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Start the server as a background daemon
     Start,
@@ -47,10 +118,83 @@ enum Commands {
         /// Name of the linked repository to remove
         name: Option<String>,
     },
+    /// Create a feature in the repository of the current directory and attach to it
+    New {
+        /// Feature (branch) name
+        name: String,
+    },
+    /// Jump to the feature best matching a query across all repositories
+    Open {
+        /// Part of the feature name, or `repo:feature`
+        query: String,
+    },
+    /// Print the worktree path of the feature best matching a query
+    /// (changes directory with the `shell-init` wrapper)
+    Cd {
+        /// Part of the feature name, or `repo:feature`
+        query: String,
+        /// Print the path alone, for scripts and the shell wrapper
+        #[arg(long)]
+        path_only: bool,
+    },
+    /// Print a shell function that makes `nomadflow cd` change directory
+    ShellInit {
+        #[arg(value_parser = nomadflow_tui::quick::SHELLS)]
+        shell: String,
+    },
+    /// Print a one-line status (feature, dirty flag, busy windows, tunnel)
+    /// for tmux status-right or a starship custom module
+    Statusline {
+        /// Directory whose feature is shown (defaults to the current one)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
     /// Attach to an existing tmux window (no server needed)
     Attach {
         /// Window name (e.g. "omstudio:my-feature"). If omitted, shows a picker.
         window: Option<String>,
+        /// Join the terminal of a configured server through its API instead
+        #[arg(long, value_name = "SERVER")]
+        remote: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TotpAction {
+    /// Generate a secret, show it as a QR code and enable it once a code is confirmed
+    Enroll {
+        /// Replace an existing secret
+        #[arg(long)]
+        force: bool,
+    },
+    /// Disable TOTP and forget remembered devices
+    Disable,
+    /// Show whether TOTP is enabled
+    Status,
+}
+
+#[derive(Subcommand)]
+enum SecretsAction {
+    /// Encrypt secrets with a key kept in the OS keychain
+    Encrypt {
+        /// Derive the key from NOMADFLOW_PASSPHRASE instead (headless machines)
+        #[arg(long)]
+        passphrase: bool,
+    },
+    /// Store secrets in plain text again
+    Decrypt,
+    /// Show how secrets are stored
+    Status,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the settings merged from the system config, the user config and
+    /// the environment (secrets masked)
+    Show {
+        /// Tag each value with the file or variable it comes from
+        #[arg(long)]
+        origin: bool,
     },
 }
 
@@ -80,7 +224,7 @@ fn start_daemon(settings: &Settings) -> Result<()> {
         if let Ok(contents) = std::fs::read_to_string(&pid_path) {
             if let Ok(pid) = contents.trim().parse::<u32>() {
                 if is_process_running(pid) {
-                    eprintln!("NomadFlow daemon already running (PID {pid})");
+                    eprintln!("{}", tf("cli.daemon_already_running", &[("pid", &pid)]));
                     return Ok(());
                 }
                 // Stale PID file, remove it
@@ -103,8 +247,8 @@ fn start_daemon(settings: &Settings) -> Result<()> {
     let pid = child.id();
     std::fs::write(&pid_path, pid.to_string())?;
 
-    eprintln!("NomadFlow daemon started (PID {pid})");
-    eprintln!("Logs: {}", log_path.display());
+    eprintln!("{}", tf("cli.daemon_started", &[("pid", &pid)]));
+    eprintln!("{}", tf("cli.logs", &[("path", &log_path.display())]));
     Ok(())
 }
 
@@ -112,7 +256,7 @@ fn stop_daemon(settings: &Settings) -> Result<()> {
     let pid_path = pid_file(settings);
 
     if !pid_path.exists() {
-        eprintln!("No PID file found — daemon not running");
+        eprintln!("{}", t("cli.no_pid_file"));
         return Ok(());
     }
 
@@ -123,13 +267,13 @@ fn stop_daemon(settings: &Settings) -> Result<()> {
         .map_err(|_| color_eyre::eyre::eyre!("Invalid PID file"))?;
 
     if !is_process_running(pid) {
-        eprintln!("Process {pid} not running, removing stale PID file");
+        eprintln!("{}", tf("cli.stale_pid", &[("pid", &pid)]));
         std::fs::remove_file(&pid_path)?;
         return Ok(());
     }
 
     // Send SIGTERM
-    eprintln!("Stopping NomadFlow daemon (PID {pid})…");
+    eprintln!("{}", tf("cli.stopping", &[("pid", &pid)]));
     std::process::Command::new("kill")
         .args([&pid.to_string()])
         .status()?;
@@ -143,7 +287,7 @@ fn stop_daemon(settings: &Settings) -> Result<()> {
     }
 
     if is_process_running(pid) {
-        eprintln!("Process did not exit, sending SIGKILL…");
+        eprintln!("{}", t("cli.sigkill"));
         std::process::Command::new("kill")
             .args(["-9", &pid.to_string()])
             .status()?;
@@ -153,7 +297,7 @@ fn stop_daemon(settings: &Settings) -> Result<()> {
         std::fs::remove_file(&pid_path)?;
     }
 
-    eprintln!("NomadFlow daemon stopped");
+    eprintln!("{}", t("cli.stopped"));
     Ok(())
 }
 
@@ -190,7 +334,13 @@ fn link_repo(settings: &Settings, path: &Path, name: Option<&str>) -> Result<()>
     }
 
     std::os::unix::fs::symlink(&canonical, &link_path)?;
-    eprintln!("Linked {} -> {}", link_name, canonical.display());
+    eprintln!(
+        "{}",
+        tf(
+            "cli.linked",
+            &[("name", &link_name), ("path", &canonical.display())]
+        )
+    );
     Ok(())
 }
 
@@ -212,7 +362,7 @@ fn unlink_repo(settings: &Settings, name: Option<&str>) -> Result<()> {
     }
 
     if links.is_empty() {
-        eprintln!("No linked repositories found.");
+        eprintln!("{}", t("cli.no_links"));
         return Ok(());
     }
 
@@ -231,7 +381,7 @@ fn unlink_repo(settings: &Settings, name: Option<&str>) -> Result<()> {
             })
             .collect();
 
-        match nomadflow_tui::pick_from_list("Unlink a repository:", &items)? {
+        match nomadflow_tui::pick_from_list(t("cli.unlink_title"), &items)? {
             Some(idx) => links[idx].clone(),
             None => return Ok(()), // cancelled
         }
@@ -261,10 +411,9 @@ fn unlink_repo(settings: &Settings, name: Option<&str>) -> Result<()> {
                 .iter()
                 .map(|wt| wt.file_name().to_string_lossy().to_string())
                 .collect();
-            let msg = format!(
-                "Remove {} worktree(s)? ({})",
-                worktrees.len(),
-                wt_names.join(", ")
+            let msg = tf(
+                "cli.remove_worktrees",
+                &[("count", &worktrees.len()), ("names", &wt_names.join(", "))],
             );
 
             if nomadflow_tui::confirm(&msg)? {
@@ -283,10 +432,10 @@ fn unlink_repo(settings: &Settings, name: Option<&str>) -> Result<()> {
                         .status();
 
                     if status.map(|s| s.success()).unwrap_or(false) {
-                        eprintln!("Removed worktree {wt_name}");
+                        eprintln!("{}", tf("cli.removed_worktree", &[("name", &wt_name)]));
                     } else {
                         std::fs::remove_dir_all(&wt_path).ok();
-                        eprintln!("Removed worktree directory {wt_name}");
+                        eprintln!("{}", tf("cli.removed_worktree_dir", &[("name", &wt_name)]));
                     }
                 }
 
@@ -304,79 +453,338 @@ fn unlink_repo(settings: &Settings, name: Option<&str>) -> Result<()> {
     }
 
     std::fs::remove_file(&link_path)?;
-    eprintln!("Unlinked {}", chosen.0);
+    eprintln!("{}", tf("cli.unlinked", &[("name", &chosen.0)]));
+    Ok(())
+}
+
+fn attach_tmux(session: &str) -> Result<()> {
+    let inside = nomadflow_tui::tmux_local::inside_tmux();
+    std::process::Command::new("tmux")
+        .args(nomadflow_tui::tmux_local::attach_args(session, inside))
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()?;
     Ok(())
 }
 
-fn attach_local(settings: &Settings, window: Option<String>) -> Result<()> {
-    let session = &settings.tmux.session;
+fn attach_local(settings: &Settings, window: Option<String>, only: Option<&str>) -> Result<()> {
+    let sessions = nomadflow_tui::tmux_local::managed_sessions(&settings.tmux.session, only);
 
-    if !nomadflow_tui::tmux_local::session_exists(session) {
+    if sessions.is_empty() {
+        let session = only.unwrap_or(&settings.tmux.session);
         return Err(eyre!(
             "No tmux session '{session}' found. Start one with `nomadflow` first."
         ));
     }
 
+    // Windows of every session, the configured one first
+    let windows: Vec<(&String, nomadflow_tui::tmux_local::LocalTmuxWindow)> = sessions
+        .iter()
+        .flat_map(|s| {
+            nomadflow_tui::tmux_local::list_windows(s)
+                .into_iter()
+                .map(move |w| (s, w))
+        })
+        .collect();
+
     if let Some(w) = window {
+        let session = windows
+            .iter()
+            .find(|(_, win)| win.name == w)
+            .map_or(&sessions[0], |(s, _)| *s);
         nomadflow_tui::tmux_local::attach_session_target(session, Some(&w));
         return Ok(());
     }
 
-    let windows = nomadflow_tui::tmux_local::list_windows(session);
-
     if windows.is_empty() {
-        return Err(eyre!("Session '{session}' has no windows."));
+        return Err(eyre!("Session '{}' has no windows.", sessions[0]));
     }
 
     if windows.len() == 1 {
-        nomadflow_tui::tmux_local::attach_session_target(session, Some(&windows[0].name));
+        let (session, w) = &windows[0];
+        nomadflow_tui::tmux_local::attach_session_target(session, Some(&w.name));
         return Ok(());
     }
 
     // Multiple windows → show picker
     let items: Vec<nomadflow_tui::PickItem> = windows
         .iter()
-        .map(|w| {
+        .map(|(session, w)| {
             let cmd = nomadflow_tui::tmux_local::get_pane_command(session, &w.name);
             let idle = nomadflow_tui::tmux_local::is_shell_idle_str(cmd.as_deref());
-            let detail = match &cmd {
+            let mut detail = match &cmd {
                 Some(c) if !idle => c.clone(),
-                _ => "idle".to_string(),
+                _ => t("features.idle").to_string(),
             };
-            nomadflow_tui::PickItem {
-                label: w.name.clone(),
-                detail,
+            if w.activity {
+                detail.push_str(&format!("  {}", t("features.new_output")));
             }
+            let label = if sessions.len() > 1 {
+                format!("{session}:{}", w.name)
+            } else {
+                w.name.clone()
+            };
+            nomadflow_tui::PickItem { label, detail }
         })
         .collect();
 
-    match nomadflow_tui::pick_from_list("Attach to window:", &items)? {
-        Some(idx) => {
-            nomadflow_tui::tmux_local::attach_session_target(session, Some(&windows[idx].name));
+    if let Some(idx) = nomadflow_tui::pick_from_list(t("cli.attach_title"), &items)? {
+        let (session, w) = &windows[idx];
+        nomadflow_tui::tmux_local::attach_session_target(session, Some(&w.name));
+    }
+
+    Ok(())
+}
+
+fn init_config(
+    settings: Settings,
+    opts: nomadflow_tui::plain::InitOptions,
+    force: bool,
+) -> Result<()> {
+    let path = settings.config_file();
+    if path.exists() && !force {
+        if opts.yes {
+            return Err(eyre!(tf("init.exists_abort", &[("path", &path.display())])));
+        }
+        if !nomadflow_tui::plain::confirm(&tf("init.exists", &[("path", &path.display())]))? {
+            return Ok(());
         }
-        None => {} // cancelled
     }
 
+    if let Some(settings) = nomadflow_tui::plain::run_init(settings, opts)? {
+        settings.ensure_directories()?;
+        eprintln!("{}", tf("init.saved", &[("path", &path.display())]));
+    }
     Ok(())
 }
 
-fn show_daemon_status(settings: &Settings) {
-    let pid_path = pid_file(settings);
+fn devices_file(settings: &Settings) -> PathBuf {
+    settings.base_dir().join("devices.json")
+}
 
-    if pid_path.exists() {
-        if let Ok(contents) = std::fs::read_to_string(&pid_path) {
-            if let Ok(pid) = contents.trim().parse::<u32>() {
-                if is_process_running(pid) {
-                    eprintln!("NomadFlow daemon: running (PID {pid})");
-                } else {
-                    eprintln!("NomadFlow daemon: not running (stale PID file)");
-                }
-                return;
-            }
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn totp_enroll(mut settings: Settings, force: bool) -> Result<()> {
+    use nomadflow_server::totp;
+
+    if settings.auth.secret.is_empty() {
+        return Err(eyre!(t("totp.needs_secret")));
+    }
+    if !settings.auth.totp_secret.is_empty() && !force {
+        return Err(eyre!(t("totp.already_enabled")));
+    }
+
+    let secret = totp::generate_secret();
+    let host = std::process::Command::new("hostname")
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "nomadflow".to_string());
+    nomadflow_server::display::print_totp_enrollment(
+        &totp::provisioning_uri(&secret, &host),
+        &secret,
+    );
+
+    let mut stdin = std::io::stdin().lock();
+    for _ in 0..3 {
+        let Some(code) = nomadflow_tui::plain::prompt_with(
+            &mut stdin,
+            &mut std::io::stderr(),
+            t("totp.code_prompt"),
+        )?
+        else {
+            return Ok(());
+        };
+        if totp::verify(&secret, &code, unix_now()).is_some() {
+            settings.auth.totp_secret = secret;
+            settings.save()?;
+            eprintln!("{}", t("totp.enabled"));
+            eprintln!("{}", t("totp.restart"));
+            return Ok(());
         }
+        eprintln!("{}", t("totp.bad_code"));
     }
+    Err(eyre!(t("totp.too_many")))
+}
 
-    eprintln!("NomadFlow daemon: not running");
+fn totp_disable(mut settings: Settings) -> Result<()> {
+    settings.auth.totp_secret.clear();
+    settings.save()?;
+    let devices = devices_file(&settings);
+    if devices.exists() {
+        std::fs::remove_file(devices)?;
+    }
+    eprintln!("{}", t("totp.disabled"));
+    eprintln!("{}", t("totp.restart"));
+    Ok(())
+}
+
+fn totp_status(settings: &Settings) {
+    if settings.auth.totp_secret.is_empty() {
+        eprintln!("{}", t("totp.status_disabled"));
+        return;
+    }
+    let store = nomadflow_server::devices::DeviceStore::load(devices_file(settings));
+    let count = store.list(unix_now()).len();
+    eprintln!("{}", tf("totp.status_enabled", &[("count", &count)]));
+}
+
+/// Switch the key source and rewrite every file holding secrets with it.
+fn secrets_set_source(mut settings: Settings, source: KeySource) -> Result<()> {
+    let servers = nomadflow_tui::state::load_servers(&settings);
+    settings.set_key_source(source)?;
+    settings.save()?;
+    nomadflow_tui::state::save_servers(&settings, &servers)?;
+    let message = match source {
+        KeySource::None => t("secrets.decrypted").to_string(),
+        KeySource::Keychain => t("secrets.encrypted_keychain").to_string(),
+        KeySource::Passphrase => tf("secrets.encrypted_passphrase", &[("env", &PASSPHRASE_ENV)]),
+    };
+    eprintln!("{message}");
+    Ok(())
+}
+
+fn secrets_status(settings: &Settings) {
+    let message = match settings.secrets.key_source {
+        KeySource::None => t("secrets.status_none").to_string(),
+        KeySource::Keychain => t("secrets.status_keychain").to_string(),
+        KeySource::Passphrase => tf("secrets.status_passphrase", &[("env", &PASSPHRASE_ENV)]),
+    };
+    eprintln!("{message}");
+}
+
+/// Run the connectivity checks and print one line per check.
+/// Returns whether they all passed or were skipped.
+async fn nettest(settings: &Settings) -> bool {
+    use nomadflow_server::nettest::Outcome;
+
+    eprintln!("{}", t("nettest.running"));
+    let results = nomadflow_server::nettest::run(settings).await;
+    let width = results
+        .iter()
+        .map(|r| t(r.check.label_key()).chars().count())
+        .max()
+        .unwrap_or(0);
+    for result in &results {
+        let label = t(result.check.label_key());
+        let latency = result
+            .latency
+            .map(|d| format!("{:>6} ms", d.as_millis()))
+            .unwrap_or_else(|| " ".repeat(9));
+        let (mark, detail) = match &result.outcome {
+            Outcome::Passed => ("✓", result.target.clone()),
+            Outcome::Failed(e) => ("✗", format!("{}: {e}", result.target)),
+            Outcome::Skipped(reason) => ("-", t(reason).to_string()),
+        };
+        eprintln!("  {mark} {label:<width$}  {latency}  {detail}");
+    }
+
+    let failed = results.iter().filter(|r| r.failed()).count();
+    if failed == 0 {
+        eprintln!("{}", t("nettest.all_passed"));
+    } else {
+        eprintln!("{}", tf("nettest.some_failed", &[("count", &failed)]));
+    }
+    failed == 0
+}
+
+/// Print what `nomadflow gc` removed, or would remove with `dry_run`.
+/// Returns whether everything could be removed.
+async fn gc(settings: &Settings, dry_run: bool) -> bool {
+    use nomadflow_core::gc::{collect, format_bytes};
+    use nomadflow_core::services::git::GitService;
+
+    let report = collect(settings, &GitService::new(settings), dry_run).await;
+    if report.entries.is_empty() {
+        eprintln!("{}", t("cli.gc_nothing"));
+        return true;
+    }
+    let width = report
+        .entries
+        .iter()
+        .map(|e| t(e.kind.label_key()).chars().count())
+        .max()
+        .unwrap_or(0);
+    for entry in &report.entries {
+        let label = t(entry.kind.label_key());
+        let size = format_bytes(entry.bytes);
+        match &entry.error {
+            None => eprintln!("  {label:<width$}  {size:>9}  {}", entry.path),
+            Some(e) => eprintln!("✗ {label:<width$}  {size:>9}  {}: {e}", entry.path),
+        }
+    }
+    let key = if dry_run {
+        "cli.gc_would_reclaim"
+    } else {
+        "cli.gc_reclaimed"
+    };
+    let size = format_bytes(report.reclaimed_bytes);
+    eprintln!("{}", tf(key, &[("size", &size)]));
+    report.entries.iter().all(|e| e.error.is_none())
+}
+
+/// Print the pass/fail matrix of `nomadflow check`. Returns whether every
+/// check passed.
+async fn check_server(server: &nomadflow_tui::state::ServerConfig, repo: Option<&str>) -> bool {
+    use nomadflow_tui::check::Outcome;
+
+    eprintln!("{}", tf("check.running", &[("server", &server.name)]));
+    let results = nomadflow_tui::check::run(server, repo).await;
+    let width = results
+        .iter()
+        .map(|r| t(r.check.label_key()).chars().count())
+        .max()
+        .unwrap_or(0);
+    for result in &results {
+        let label = t(result.check.label_key());
+        let latency = result
+            .latency
+            .map(|d| format!("{:>6} ms", d.as_millis()))
+            .unwrap_or_else(|| " ".repeat(9));
+        let (mark, detail) = match &result.outcome {
+            Outcome::Passed => ("✓", result.target.clone()),
+            Outcome::Failed(e) => ("✗", format!("{}: {e}", result.target)),
+            Outcome::Skipped(reason) => ("-", t(reason).to_string()),
+        };
+        eprintln!("  {mark} {label:<width$}  {latency}  {detail}");
+    }
+
+    let failed = results.iter().filter(|r| r.failed()).count();
+    if failed == 0 {
+        eprintln!("{}", t("nettest.all_passed"));
+    } else {
+        eprintln!("{}", tf("nettest.some_failed", &[("count", &failed)]));
+    }
+    failed == 0
+}
+
+/// The daemon as its PID file tells.
+fn daemon_status(settings: &Settings) -> nomadflow_tui::status::DaemonStatus {
+    let pid = std::fs::read_to_string(pid_file(settings))
+        .ok()
+        .and_then(|contents| contents.trim().parse::<u32>().ok());
+    let running = pid.is_some_and(is_process_running);
+    nomadflow_tui::status::DaemonStatus {
+        pid,
+        running,
+        stale_pid_file: pid.is_some() && !running,
+    }
+}
+
+fn load_settings() -> Result<Settings> {
+    match Settings::load(None) {
+        Ok(settings) => Ok(settings),
+        // Falling back to defaults would later overwrite the encrypted secrets
+        Err(e @ NomadError::Secrets(_)) => Err(e.into()),
+        Err(_) => Ok(Settings::default()),
+    }
 }
 
 #[tokio::main]
@@ -384,11 +792,74 @@ async fn main() -> Result<()> {
     color_eyre::install()?;
 
     let cli = Cli::parse();
-    let settings = Settings::load(None).unwrap_or_default();
+    // Run by shell prompts and `cd` wrappers: no migration, crash reporter or
+    // directories, and no secrets unless the local API needs them
+    match &cli.command {
+        Some(Commands::Statusline { dir }) => {
+            let dir = match dir {
+                Some(dir) => dir.clone(),
+                None => std::env::current_dir()?,
+            };
+            let settings = Settings::load_plain(None).unwrap_or_default();
+            let snapshot = nomadflow_core::statusline::load(&settings.base_dir());
+            println!(
+                "{}",
+                nomadflow_core::statusline::render(snapshot.as_ref(), &dir, unix_now())
+            );
+            return Ok(());
+        }
+        Some(Commands::ShellInit { shell }) => {
+            let script = nomadflow_tui::quick::shell_init(shell)
+                .ok_or_else(|| eyre!("Unsupported shell: {shell}"))?;
+            print!("{script}");
+            return Ok(());
+        }
+        Some(Commands::Cd { query, path_only }) => {
+            let settings = load_settings()?;
+            i18n::set_locale(Locale::detect(&settings.ui.locale));
+            let path = nomadflow_tui::quick::feature_path(&settings, query).await?;
+            println!("{path}");
+            if !path_only {
+                eprintln!("{}", t("cd.hint"));
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+    let mut settings = load_settings()?;
+    i18n::set_locale(Locale::detect(&settings.ui.locale));
+    nomadflow_tui::glyphs::set_ascii(settings.tui.ascii);
+    if settings.crash.reports {
+        nomadflow_crash::Reporter::new(
+            "nomadflow",
+            env!("CARGO_PKG_VERSION"),
+            settings.base_dir().join("crashes"),
+        )
+        .with_upload(&settings.crash.upload_url)
+        .install();
+    }
+    if let Some(migrated) = migrations::run(&settings.base_dir())? {
+        if let Some(backup) = migrated.backup {
+            eprintln!(
+                "{}",
+                tf(
+                    "migrate.done",
+                    &[("version", &migrated.to), ("backup", &backup.display())]
+                )
+            );
+        }
+        // The config file itself may have changed
+        settings = load_settings()?;
+    }
     settings.ensure_directories()?;
+    nomadflow_tui::plain::set_plain(cli.plain || nomadflow_tui::plain::should_auto_enable());
 
     match cli.command {
-        Some(Commands::Serve { public, host }) => {
+        Some(Commands::Serve {
+            public,
+            host,
+            qr_png,
+        }) => {
             let settings = if !settings.config_file().exists() {
                 match nomadflow_tui::run_setup(settings)? {
                     Some(s) => s,
@@ -400,7 +871,62 @@ async fn main() -> Result<()> {
             nomadflow_server::init_tracing();
             let shutdown = CancellationToken::new();
             nomadflow_server::spawn_signal_handler(shutdown.clone());
-            nomadflow_server::serve(settings, shutdown, public, false, host).await?;
+            nomadflow_server::serve(settings, shutdown, public, false, host, qr_png).await?;
+        }
+        Some(Commands::Init {
+            secret,
+            public,
+            subdomain,
+            yes,
+            force,
+        }) => {
+            let opts = nomadflow_tui::plain::InitOptions {
+                secret,
+                public: public.then_some(true),
+                subdomain,
+                yes,
+            };
+            init_config(settings, opts, force)?;
+        }
+        Some(Commands::Totp { action }) => match action {
+            TotpAction::Enroll { force } => totp_enroll(settings, force)?,
+            TotpAction::Disable => totp_disable(settings)?,
+            TotpAction::Status => totp_status(&settings),
+        },
+        Some(Commands::Secrets { action }) => match action {
+            SecretsAction::Encrypt { passphrase } => {
+                let source = if passphrase {
+                    KeySource::Passphrase
+                } else {
+                    KeySource::Keychain
+                };
+                secrets_set_source(settings, source)?;
+            }
+            SecretsAction::Decrypt => secrets_set_source(settings, KeySource::None)?,
+            SecretsAction::Status => secrets_status(&settings),
+        },
+        Some(Commands::Config {
+            action: ConfigAction::Show { origin },
+        }) => {
+            print!("{}", Settings::load_layered(None)?.render(origin)?);
+        }
+        Some(Commands::Nettest) => {
+            if !nettest(&settings).await {
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Check { server, repo }) => {
+            let servers = nomadflow_tui::state::load_servers(&settings);
+            let config = nomadflow_tui::remote::find_server(&servers, &server)
+                .ok_or_else(|| eyre!(tf("remote.unknown_server", &[("server", &server)])))?;
+            if !check_server(config, repo.as_deref()).await {
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Gc { dry_run }) => {
+            if !gc(&settings, dry_run).await {
+                std::process::exit(1);
+            }
         }
         Some(Commands::Start) => {
             start_daemon(&settings)?;
@@ -414,12 +940,45 @@ async fn main() -> Result<()> {
         Some(Commands::Unlink { name }) => {
             unlink_repo(&settings, name.as_deref())?;
         }
-        Some(Commands::Attach { window }) => {
-            attach_local(&settings, window)?;
+        Some(Commands::New { name }) => {
+            let dir = std::env::current_dir()?;
+            let session = nomadflow_tui::quick::new_feature(&settings, &name, &dir).await?;
+            attach_tmux(&session)?;
+        }
+        Some(Commands::Open { query }) => {
+            let dir = std::env::current_dir()?;
+            if let Some(session) = nomadflow_tui::quick::open_feature(&settings, &query, &dir).await? {
+                attach_tmux(&session)?;
+            }
+        }
+        Some(Commands::Statusline { .. } | Commands::ShellInit { .. } | Commands::Cd { .. }) => {
+            unreachable!("handled before loading the settings")
+        }
+        Some(Commands::Attach {
+            window,
+            remote: Some(remote),
+        }) => {
+            let servers = nomadflow_tui::state::load_servers(&settings);
+            let server = nomadflow_tui::remote::find_server(&servers, &remote)
+                .ok_or_else(|| eyre!(tf("remote.unknown_server", &[("server", &remote)])))?;
+            nomadflow_tui::remote::attach(server, window.as_deref()).await?;
+        }
+        Some(Commands::Attach {
+            window,
+            remote: None,
+        }) => {
+            attach_local(&settings, window, cli.session.as_deref())?;
         }
         None if cli.status => {
-            show_daemon_status(&settings);
-            nomadflow_tui::run_status(&settings);
+            let daemon = daemon_status(&settings);
+            let only = cli.session.as_deref();
+            let report = nomadflow_tui::status::collect(&settings, daemon, only).await;
+            if cli.json {
+                println!("{}", report.to_json());
+            } else {
+                report.print(&settings, only);
+            }
+            std::process::exit(report.status.exit_code());
         }
         None => {
             // Default: spawn server in background + TUI wizard
@@ -427,7 +986,7 @@ async fn main() -> Result<()> {
             let shutdown = CancellationToken::new();
             let shutdown_clone = shutdown.clone();
             let server_handle = tokio::spawn(async move {
-                nomadflow_server::serve(server_settings, shutdown_clone, false, true, None)
+                nomadflow_server::serve(server_settings, shutdown_clone, false, true, None, None)
                     .await
                     .ok();
             });
@@ -444,12 +1003,7 @@ async fn main() -> Result<()> {
 
             // Attach to tmux if TUI returned a session
             if let Some(session) = attach_session {
-                std::process::Command::new("tmux")
-                    .args(["attach-session", "-t", &session])
-                    .stdin(Stdio::inherit())
-                    .stdout(Stdio::inherit())
-                    .stderr(Stdio::inherit())
-                    .status()?;
+                attach_tmux(&session)?;
             }
         }
     }
@@ -381,7 +381,23 @@ export default function FeaturesScreen() {
               <View className="mt-1 flex-row items-center gap-1">
                 <Icon as={GitBranchIcon} className="text-muted-foreground" size={12} />
                 <CardDescription className="text-xs">{item.branch}</CardDescription>
+                {(item.ahead ?? 0) > 0 && (
+                  <Text className="text-xs text-muted-foreground">↑{item.ahead}</Text>
+                )}
+                {(item.behind ?? 0) > 0 && (
+                  <Text className="text-xs text-muted-foreground">↓{item.behind}</Text>
+                )}
+                {item.dirty && (
+                  <View className="rounded-full bg-warning/15 px-2 py-0.5">
+                    <Text className="text-[10px] font-semibold text-warning">Non commité</Text>
+                  </View>
+                )}
               </View>
+              {item.lastCommitSubject ? (
+                <CardDescription className="mt-1 text-xs" numberOfLines={1}>
+                  {item.lastCommitSubject}
+                </CardDescription>
+              ) : null}
               <View className="mt-1 flex-row items-center gap-1">
                 <Icon as={FolderIcon} className="text-muted-foreground" size={12} />
                 <CardDescription className="text-xs" numberOfLines={1}>
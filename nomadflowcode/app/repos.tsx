@@ -5,7 +5,7 @@ import { Label } from '@/components/ui/label';
 import { Icon } from '@/components/ui/icon';
 import { Text } from '@/components/ui/text';
 import { useStorage } from '@/lib/context/storage-context';
-import { executeServerCommand } from '@/lib/server-commands';
+import { executeServerCommand, waitForJob } from '@/lib/server-commands';
 import type { Repository } from '@shared';
 import { Stack, useLocalSearchParams, useRouter } from 'expo-router';
 import {
@@ -134,10 +134,15 @@ export default function ReposScreen() {
       if (cloneToken.trim()) params.token = cloneToken.trim();
       if (cloneName.trim()) params.name = cloneName.trim();
 
-      const result = await executeServerCommand(server, {
+      const started = await executeServerCommand(server, {
         action: 'clone-repo',
         params,
       });
+      if (!started.success || !started.data) {
+        throw new Error(started.error || 'Failed to clone repository');
+      }
+
+      const result = await waitForJob(server, started.data.jobId);
 
       if (result.success && result.data) {
         setShowCloneModal(false);
@@ -78,7 +78,8 @@ const TMUX_SHORTCUTS = [
 /**
  * Build the JavaScript to inject before the ttyd page loads.
  * Intercepts WebSocket to:
- * 1. Rewrite URL to the API server's WS proxy (WKWebView can't send Basic Auth on WS upgrades)
+ * 1. Rewrite URL to the API server's WS proxy (WKWebView can't send Basic Auth on WS upgrades),
+ *    or use a WebRTC data channel when the server has the experimental transport enabled
  * 2. Track connection state via postMessage to React Native
  * 3. Expose sendInput() for keyboard shortcuts
  */
@@ -96,30 +97,178 @@ const buildInjectedJS = (apiUrl: string, authToken?: string): string => {
   var _authToken = '${escapedToken}';
   var _apiUrl = '${escapedApiUrl}';
 
-  window.WebSocket = function(url, protocols) {
+  function notify(message) {
+    try { window.ReactNativeWebView.postMessage(JSON.stringify(message)); } catch(e) {}
+  }
+
+  function openWebSocket(protocols) {
     // Rewrite WebSocket URL to go through the API server's WS proxy
     // because WKWebView does not send Basic Auth on WebSocket upgrades
     var wsScheme = _apiUrl.indexOf('https') === 0 ? 'wss' : 'ws';
     var apiHost = _apiUrl.replace(/^https?:\\/\\//, '');
-    url = wsScheme + '://' + apiHost + '/terminal/ws';
+    var url = wsScheme + '://' + apiHost + '/terminal/ws';
     if (_authToken) {
       url = url + '?token=' + encodeURIComponent(_authToken);
     }
+    return protocols ? new OriginalWebSocket(url, protocols) : new OriginalWebSocket(url);
+  }
 
-    var ws = protocols ? new OriginalWebSocket(url, protocols) : new OriginalWebSocket(url);
-    window._ttydSocket = ws;
+  // Experimental: carry the ttyd protocol over a WebRTC data channel when
+  // the server enables it (peer-to-peer, skipping the relay when possible).
+  // Rejects when the server has it disabled or negotiation stalls, in which
+  // case the caller falls back to the WebSocket proxy.
+  var _webrtcDisabled = !window.RTCPeerConnection;
+
+  function waitForCandidates(pc) {
+    return new Promise(function(resolve) {
+      if (pc.iceGatheringState === 'complete') return resolve();
+      pc.addEventListener('icegatheringstatechange', function() {
+        if (pc.iceGatheringState === 'complete') resolve();
+      });
+      setTimeout(resolve, 2000);
+    });
+  }
+
+  function openDataChannel() {
+    return new Promise(function(resolve, reject) {
+      var pc = new RTCPeerConnection({ iceServers: [{ urls: 'stun:stun.l.google.com:19302' }] });
+      var channel = pc.createDataChannel('tty');
+      channel.binaryType = 'arraybuffer';
+      var fail = function(error) {
+        clearTimeout(timer);
+        pc.close();
+        reject(error);
+      };
+      var timer = setTimeout(function() { fail(new Error('timed out')); }, 5000);
+      channel.onopen = function() {
+        clearTimeout(timer);
+        resolve({ pc: pc, channel: channel });
+      };
+      pc.createOffer()
+        .then(function(offer) { return pc.setLocalDescription(offer); })
+        .then(function() { return waitForCandidates(pc); })
+        .then(function() {
+          var headers = { 'Content-Type': 'application/json' };
+          if (_authToken) headers.Authorization = 'Bearer ' + _authToken;
+          return fetch(_apiUrl + '/api/webrtc/offer', {
+            method: 'POST',
+            credentials: 'same-origin',
+            headers: headers,
+            body: JSON.stringify({ sdp: pc.localDescription.sdp }),
+          });
+        })
+        .then(function(response) {
+          if (response.status === 404) _webrtcDisabled = true;
+          if (!response.ok) throw new Error('HTTP ' + response.status);
+          return response.json();
+        })
+        .then(function(answer) {
+          return pc.setRemoteDescription({ type: 'answer', sdp: answer.sdp });
+        })
+        .catch(fail);
+    });
+  }
+
+  // Stands in for the WebSocket ttyd opens, over whichever transport connects.
+  function TerminalSocket(protocols) {
+    var self = this;
+    self.readyState = 0;
+    self.binaryType = 'arraybuffer';
+    self.protocol = 'tty';
+    self.transport = null;
+    self._listeners = {};
+    self._inner = null;
+    self._closed = false;
+
+    var fallback = function() {
+      var ws = openWebSocket(protocols);
+      ws.binaryType = self.binaryType;
+      self._attach(ws, 'websocket');
+    };
+    if (_webrtcDisabled) return fallback();
+    openDataChannel().then(function(rtc) {
+      self._attach(rtc.channel, 'webrtc', function() { rtc.pc.close(); });
+    }, function(error) {
+      console.log('[NomadFlow] WebRTC unavailable (' + error.message + '), using WebSocket');
+      fallback();
+    });
+  }
+
+  TerminalSocket.prototype.addEventListener = function(type, listener) {
+    (this._listeners[type] = this._listeners[type] || []).push(listener);
+  };
+
+  TerminalSocket.prototype.removeEventListener = function(type, listener) {
+    this._listeners[type] = (this._listeners[type] || []).filter(function(l) { return l !== listener; });
+  };
+
+  TerminalSocket.prototype._emit = function(type, event) {
+    var self = this;
+    if (self['on' + type]) self['on' + type](event);
+    (self._listeners[type] || []).slice().forEach(function(listener) { listener.call(self, event); });
+  };
+
+  TerminalSocket.prototype._attach = function(inner, transport, cleanup) {
+    var self = this;
+    self._inner = inner;
+    self.transport = transport;
+    if (self._closed) {
+      inner.close();
+      if (cleanup) cleanup();
+      return;
+    }
+    var open = function() {
+      self.readyState = 1;
+      self._emit('open', { type: 'open', target: self });
+    };
+    inner.onmessage = function(event) {
+      self._emit('message', { type: 'message', data: event.data, target: self });
+    };
+    inner.onerror = function() {
+      self._emit('error', { type: 'error', target: self });
+    };
+    inner.onclose = function(event) {
+      self.readyState = 3;
+      if (cleanup) cleanup();
+      self._emit('close', {
+        type: 'close',
+        code: event.code || 1000,
+        reason: event.reason || '',
+        wasClean: !!event.wasClean,
+        target: self,
+      });
+    };
+    // A data channel is already open once negotiated
+    if (inner.readyState === 'open') open();
+    else inner.onopen = open;
+  };
+
+  TerminalSocket.prototype.send = function(data) {
+    if (this.readyState === 1) this._inner.send(data);
+  };
+
+  TerminalSocket.prototype.close = function() {
+    this._closed = true;
+    if (this.readyState === 3) return;
+    this.readyState = 2;
+    if (this._inner) this._inner.close();
+  };
+
+  window.WebSocket = function(url, protocols) {
+    var socket = new TerminalSocket(protocols);
+    window._ttydSocket = socket;
 
-    ws.addEventListener('open', function() {
-      try { window.ReactNativeWebView.postMessage(JSON.stringify({ type: 'connected' })); } catch(e) {}
+    socket.addEventListener('open', function() {
+      notify({ type: 'connected', transport: socket.transport });
     });
-    ws.addEventListener('close', function() {
-      try { window.ReactNativeWebView.postMessage(JSON.stringify({ type: 'disconnected' })); } catch(e) {}
+    socket.addEventListener('close', function() {
+      notify({ type: 'disconnected' });
     });
-    ws.addEventListener('error', function() {
-      try { window.ReactNativeWebView.postMessage(JSON.stringify({ type: 'error', error: 'WebSocket connection failed' })); } catch(e) {}
+    socket.addEventListener('error', function() {
+      notify({ type: 'error', error: 'WebSocket connection failed' });
     });
 
-    return ws;
+    return socket;
   };
   window.WebSocket.prototype = OriginalWebSocket.prototype;
   window.WebSocket.CONNECTING = OriginalWebSocket.CONNECTING;
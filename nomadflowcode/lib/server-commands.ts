@@ -22,7 +22,7 @@ export async function executeServerCommand(
   server: Server,
   command: ServerCommand
 ): Promise<ApiResponse<any>> {
-  const timeout = command.action === 'clone-repo' ? 60000 : 10000;
+  const timeout = 10000;
 
   const baseUrl = getApiBaseUrl(server);
 
@@ -63,6 +63,47 @@ export async function executeServerCommand(
   }
 }
 
+/**
+ * Poll a server job (e.g. a clone) until it finishes.
+ * Resolves with the job's result, or an error if it failed.
+ */
+export async function waitForJob(
+  server: Server,
+  jobId: number,
+  onProgress?: (phase: string, percent: number) => void
+): Promise<ApiResponse<any>> {
+  const baseUrl = getApiBaseUrl(server);
+  const headers = server.authToken ? { Authorization: `Bearer ${server.authToken}` } : undefined;
+
+  for (;;) {
+    let job;
+    try {
+      const response = await fetch(`${baseUrl}/jobs/${jobId}`, { headers });
+      if (!response.ok) {
+        const errorData = await response.json().catch(() => ({}));
+        return {
+          success: false,
+          error: errorData.detail || `HTTP ${response.status}: ${response.statusText}`,
+        };
+      }
+      job = await response.json();
+    } catch (error) {
+      return { success: false, error: error instanceof Error ? error.message : 'Unknown error' };
+    }
+
+    if (job.status === 'succeeded') {
+      return { success: true, data: job.repo };
+    }
+    if (job.status === 'failed') {
+      return { success: false, error: job.error || 'Job failed' };
+    }
+    if (job.progress && onProgress) {
+      onProgress(job.progress.phase, job.progress.percent);
+    }
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+  }
+}
+
 /**
  * Switch to a feature worktree before opening the terminal.
  * This ensures the tmux window is selected and cd'd into the correct directory.
@@ -20,6 +20,10 @@ export interface Feature {
   isActive: boolean;
   isMain?: boolean;
   createdAt?: number;
+  lastCommitSubject?: string | null;
+  dirty?: boolean;
+  ahead?: number | null;
+  behind?: number | null;
   tmuxWindow?: string;
 }
 